@@ -0,0 +1,24 @@
+//! Parse arbitrary bytes as every document format, as the CLI does before
+//! validating a file.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use validate_json_schema::{canonicalize, DocumentFormat};
+
+fuzz_target!(|data: &[u8]| {
+    // Files that are not UTF-8 are rejected when they are read
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    for format in [
+        DocumentFormat::Yaml,
+        DocumentFormat::Json,
+        DocumentFormat::Json5,
+    ] {
+        if let Ok(document) = format.parse(content) {
+            let _ = canonicalize(&document);
+        }
+    }
+    let _ = DocumentFormat::from_content(content);
+});
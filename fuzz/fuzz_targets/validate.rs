@@ -0,0 +1,65 @@
+//! Validate arbitrary content against a schema that exercises most keywords,
+//! along with the checks the CLI runs on the same file.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::sync::LazyLock;
+use validate_json_schema::{
+    check_hygiene, check_style, find_yaml_ambiguities, fix_style, suggest_fixes, DocumentFormat,
+    StyleRules, Validator,
+};
+
+static SCHEMA: LazyLock<serde_json::Value> = LazyLock::new(|| {
+    serde_json::json!({
+        "type": "object",
+        "required": ["name"],
+        "properties": {
+            "name": {"type": "string", "minLength": 1, "pattern": "^[a-z][a-z0-9-]*$"},
+            "version": {"type": "string"},
+            "replicas": {"type": "integer", "minimum": 1, "maximum": 100},
+            "ratio": {"type": "number", "exclusiveMaximum": 1},
+            "enabled": {"type": "boolean"},
+            "mode": {"enum": ["fast", "safe", null]},
+            "tags": {"type": "array", "items": {"type": "string"}, "uniqueItems": true},
+            "owner": {"type": "string", "format": "email"},
+            "children": {"type": "array", "items": {"$ref": "#"}},
+            "labels": {
+                "type": "object",
+                "additionalProperties": {"type": "string"},
+                "propertyNames": {"maxLength": 63}
+            }
+        },
+        "dependencies": {"replicas": ["mode"]},
+        "if": {"properties": {"mode": {"const": "safe"}}},
+        "then": {"required": ["owner"]}
+    })
+});
+
+static VALIDATOR: LazyLock<Validator> =
+    LazyLock::new(|| Validator::from_value(&SCHEMA).expect("the schema is valid"));
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    let validator = &*VALIDATOR;
+    for format in [
+        DocumentFormat::Yaml,
+        DocumentFormat::Json,
+        DocumentFormat::Json5,
+    ] {
+        let _ = validator.validate_as(content, format);
+        let _ = check_hygiene(content, format);
+    }
+    let _ = find_yaml_ambiguities(validator, content);
+    let _ = suggest_fixes(validator, &SCHEMA, content);
+
+    let rules = StyleRules {
+        sorted_keys: true,
+        indent: Some(2),
+        max_flow_items: Some(3),
+    };
+    let _ = check_style(content, &rules);
+    let _ = fix_style(content, &rules);
+});
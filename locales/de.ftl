@@ -0,0 +1,53 @@
+# Meldungen von validate-json-schema auf Deutsch.
+
+## Ausgabe der Kommandozeile
+
+valid = Gültig
+validation-successful = Validierung erfolgreich!
+validation-failed = Validierung fehlgeschlagen: { $detail }
+error = Fehler: { $detail }
+skipped-empty = Übersprungen (leeres Dokument)
+hint = Hinweis: { $path }: { $detail }
+warning = Warnung: { $path }: { $detail }
+timed-out = Zeitüberschreitung nach { $seconds } s
+internal-error = Interner Fehler: { $detail }
+vanished = Übersprungen: Die Datei ist während des Laufs verschwunden: { $detail }
+changed = Geändert: Die Datei wurde während der Validierung verändert
+deadline-exceeded = Frist von { $deadline } überschritten; { $count } Datei(en) übersprungen:
+
+## Fehler gegen das Schema
+
+failure = { $path }: { $message }
+
+## Fehler
+
+error-file-read = Datei konnte nicht gelesen werden: { $detail }
+error-yaml-parse = YAML konnte nicht gelesen werden: { $detail }
+error-yaml-directive = Ungültige YAML-Direktive: { $detail }
+error-yaml-alias = YAML-Anker und -Aliase sind nicht erlaubt: { $detail }
+error-yaml-tag = Nicht unterstütztes YAML-Tag: { $detail }
+error-duplicate-key = Doppelter Schlüssel: { $detail }
+error-json-parse = JSON konnte nicht gelesen werden: { $detail }
+error-csv-parse = CSV konnte nicht gelesen werden: { $detail }
+error-xml-parse = XML konnte nicht gelesen werden: { $detail }
+error-cbor-parse = CBOR konnte nicht gelesen werden: { $detail }
+error-bson-parse = BSON konnte nicht gelesen werden: { $detail }
+error-ini-parse = INI konnte nicht gelesen werden: { $detail }
+error-properties-parse = Properties-Datei konnte nicht gelesen werden: { $detail }
+error-json-syntax = JSON konnte nicht gelesen werden: { $detail } (Hinweis: { $hint })
+error-schema-compilation = Ungültiges Schema: { $detail }
+error-validation-failed = Validierung fehlgeschlagen: { $detail }
+error-http-request = HTTP-Anfrage fehlgeschlagen: { $detail }
+error-http-status = HTTP-Fehlerstatus: { $detail }
+error-http-config = Ungültige HTTP-Konfiguration: { $detail }
+error-invalid-url = Ungültige URL: { $detail }
+error-cache-directory = Fehler im Cache-Verzeichnis: { $detail }
+error-cache = Fehler im Schema-Cache: { $detail }
+error-schema-load = Schema konnte nicht geladen werden: { $detail }
+error-unsupported-migration = Nicht unterstützte Schema-Migration: { $detail }
+error-limit-exceeded = Dokumentgrenze überschritten: { $detail }
+error-invalid-pointer = Ungültiger Pointer: { $detail }
+error-config = Ungültige Konfiguration: { $detail }
+error-invalid-report = Ungültiger Bericht: { $detail }
+error-invalid-compiled = Ungültiges kompiliertes Schema: { $detail }
+error-empty-document = Leeres Dokument: Die Datei enthält nur Kommentare
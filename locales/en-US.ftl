@@ -0,0 +1,54 @@
+# Messages of validate-json-schema in English, the fallback for every other
+# locale. Placeables are filled in as plain text.
+
+## Command-line output
+
+valid = Valid
+validation-successful = Validation successful!
+validation-failed = Validation failed: { $detail }
+error = Error: { $detail }
+skipped-empty = Skipped (empty document)
+hint = Hint: { $path }: { $detail }
+warning = Warning: { $path }: { $detail }
+timed-out = Timed out after { $seconds }s
+internal-error = Internal error: { $detail }
+vanished = Skipped: the file vanished during the run: { $detail }
+changed = Changed: the file was modified while it was validated
+deadline-exceeded = Deadline of { $deadline } exceeded; skipped { $count } file(s):
+
+## Schema failures; `failure-<keyword>` overrides this for one keyword
+
+failure = { $path }: { $message }
+
+## Errors
+
+error-file-read = Failed to read file: { $detail }
+error-yaml-parse = Failed to parse YAML: { $detail }
+error-yaml-directive = Invalid YAML directive: { $detail }
+error-yaml-alias = YAML anchors and aliases are not allowed: { $detail }
+error-yaml-tag = Unsupported YAML tag: { $detail }
+error-duplicate-key = Duplicate key: { $detail }
+error-json-parse = Failed to parse JSON: { $detail }
+error-csv-parse = Failed to parse CSV: { $detail }
+error-xml-parse = Failed to parse XML: { $detail }
+error-cbor-parse = Failed to parse CBOR: { $detail }
+error-bson-parse = Failed to parse BSON: { $detail }
+error-ini-parse = Failed to parse INI: { $detail }
+error-properties-parse = Failed to parse properties: { $detail }
+error-json-syntax = Failed to parse JSON: { $detail } (hint: { $hint })
+error-schema-compilation = Invalid schema: { $detail }
+error-validation-failed = Validation failed: { $detail }
+error-http-request = HTTP request failed: { $detail }
+error-http-status = HTTP error status: { $detail }
+error-http-config = Invalid HTTP configuration: { $detail }
+error-invalid-url = Invalid URL: { $detail }
+error-cache-directory = Cache directory error: { $detail }
+error-cache = Schema cache error: { $detail }
+error-schema-load = Failed to load schema: { $detail }
+error-unsupported-migration = Unsupported schema migration: { $detail }
+error-limit-exceeded = Document limit exceeded: { $detail }
+error-invalid-pointer = Invalid pointer: { $detail }
+error-config = Invalid configuration: { $detail }
+error-invalid-report = Invalid report: { $detail }
+error-invalid-compiled = Invalid compiled schema: { $detail }
+error-empty-document = Empty document: the file has no content besides comments
@@ -0,0 +1,53 @@
+# Mensajes de validate-json-schema en español.
+
+## Salida de la línea de comandos
+
+valid = Válido
+validation-successful = ¡Validación correcta!
+validation-failed = La validación falló: { $detail }
+error = Error: { $detail }
+skipped-empty = Omitido (documento vacío)
+hint = Sugerencia: { $path }: { $detail }
+warning = Advertencia: { $path }: { $detail }
+timed-out = Tiempo agotado tras { $seconds } s
+internal-error = Error interno: { $detail }
+vanished = Omitido: el archivo desapareció durante la ejecución: { $detail }
+changed = Modificado: el archivo cambió mientras se validaba
+deadline-exceeded = Se superó el plazo de { $deadline }; se omitieron { $count } archivo(s):
+
+## Fallos frente al esquema
+
+failure = { $path }: { $message }
+
+## Errores
+
+error-file-read = No se pudo leer el archivo: { $detail }
+error-yaml-parse = No se pudo analizar el YAML: { $detail }
+error-yaml-directive = Directiva YAML no válida: { $detail }
+error-yaml-alias = No se permiten anclas ni alias de YAML: { $detail }
+error-yaml-tag = Etiqueta YAML no admitida: { $detail }
+error-duplicate-key = Clave duplicada: { $detail }
+error-json-parse = No se pudo analizar el JSON: { $detail }
+error-csv-parse = No se pudo analizar el CSV: { $detail }
+error-xml-parse = No se pudo analizar el XML: { $detail }
+error-cbor-parse = No se pudo analizar el CBOR: { $detail }
+error-bson-parse = No se pudo analizar el BSON: { $detail }
+error-ini-parse = No se pudo analizar el INI: { $detail }
+error-properties-parse = No se pudo analizar el archivo de propiedades: { $detail }
+error-json-syntax = No se pudo analizar el JSON: { $detail } (sugerencia: { $hint })
+error-schema-compilation = Esquema no válido: { $detail }
+error-validation-failed = La validación falló: { $detail }
+error-http-request = La petición HTTP falló: { $detail }
+error-http-status = Estado de error HTTP: { $detail }
+error-http-config = Configuración HTTP no válida: { $detail }
+error-invalid-url = URL no válida: { $detail }
+error-cache-directory = Error del directorio de caché: { $detail }
+error-cache = Error de la caché de esquemas: { $detail }
+error-schema-load = No se pudo cargar el esquema: { $detail }
+error-unsupported-migration = Migración de esquema no admitida: { $detail }
+error-limit-exceeded = Se superó un límite del documento: { $detail }
+error-invalid-pointer = Puntero no válido: { $detail }
+error-config = Configuración no válida: { $detail }
+error-invalid-report = Informe no válido: { $detail }
+error-invalid-compiled = Esquema compilado no válido: { $detail }
+error-empty-document = Documento vacío: el archivo solo contiene comentarios
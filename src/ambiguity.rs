@@ -0,0 +1,374 @@
+//! Diagnostics for YAML scalars that are typed differently than intended.
+//!
+//! An unquoted YAML scalar is typed by its spelling: `version: 1.20` is the
+//! number 1.2, and under YAML 1.1, which many tools still implement,
+//! `country: NO` is the boolean `false` (the "Norway problem"). When the schema
+//! expects a string there, the fix is always to quote the value, but the
+//! validation error alone ("1.2 is not of type string") rarely makes that
+//! obvious. [`find_yaml_ambiguities`] reports such scalars together with the
+//! quoted spelling.
+
+use crate::walk::escape_token;
+use crate::Validator;
+use serde_json::Value;
+use std::fmt;
+
+/// An unquoted YAML scalar that is not read as the string the schema expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlAmbiguity {
+    /// JSON Pointer to the value in the document.
+    pub instance_path: String,
+    /// 1-based line of the scalar, if it could be located in the source.
+    pub line: Option<usize>,
+    /// The scalar as written in the document.
+    pub scalar: String,
+    /// Whether only YAML 1.1 parsers misread the scalar; this validator reads
+    /// it as a string, so the document passes here but not in those tools.
+    pub yaml11_only: bool,
+    /// What the scalar is read as, e.g. `the number 1.2`.
+    pub read_as: String,
+}
+
+impl YamlAmbiguity {
+    /// The scalar quoted, as it should be written to stay a string.
+    pub fn quoted(&self) -> String {
+        Value::String(self.scalar.clone()).to_string()
+    }
+}
+
+impl fmt::Display for YamlAmbiguity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        let path = if self.instance_path.is_empty() {
+            "root"
+        } else {
+            &self.instance_path
+        };
+        let parsers = if self.yaml11_only {
+            " by YAML 1.1 parsers"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "{}: unquoted {} is read as {}{}, but the schema expects a string; \
+             quote it as {}",
+            path,
+            self.scalar,
+            self.read_as,
+            parsers,
+            self.quoted()
+        )
+    }
+}
+
+/// Find unquoted scalars in YAML `content` that are read as booleans or
+/// numbers where `validator`'s schema expects a string.
+///
+/// Two cases are reported: scalars such as `1.20` or `true` that fail a
+/// `type: string` check here, and scalars such as `NO`, `on` or `22:22` that
+/// pass here but are booleans or numbers to YAML 1.1 parsers. Returns nothing
+/// if `content` is not valid YAML.
+pub fn find_yaml_ambiguities(validator: &Validator, content: &str) -> Vec<YamlAmbiguity> {
+    let document: Value = match crate::yaml::from_str(content) {
+        Ok(document) => document,
+        Err(_) => return Vec::new(),
+    };
+    let mut ambiguities = Vec::new();
+
+    for failure in validator.iter_errors(&document) {
+        let value = match failure.instance(&document) {
+            Some(value @ (Value::Bool(_) | Value::Number(_))) => value,
+            _ => continue,
+        };
+        if failure.keyword != "type" {
+            continue;
+        }
+        let source = locate_scalar(content, &failure.instance_path, value);
+        if source.as_ref().is_some_and(|source| source.quoted) {
+            continue;
+        }
+        let scalar = source
+            .as_ref()
+            .map_or_else(|| value.to_string(), |source| source.raw.clone());
+        let quoted = Value::String(scalar.clone());
+        if has_type_failure(validator, &document, &failure.instance_path, quoted) {
+            continue;
+        }
+        ambiguities.push(YamlAmbiguity {
+            instance_path: failure.instance_path,
+            line: source.map(|source| source.line),
+            scalar,
+            yaml11_only: false,
+            read_as: describe(value),
+        });
+    }
+
+    let mut strings = Vec::new();
+    collect_strings(&document, String::new(), &mut strings);
+    for (pointer, text) in strings {
+        let reading = match yaml11_reading(text) {
+            Some(reading) => reading,
+            None => continue,
+        };
+        let source = match locate_scalar(content, &pointer, &Value::String(text.to_string())) {
+            Some(source) if !source.quoted => source,
+            _ => continue,
+        };
+        if has_type_failure(validator, &document, &pointer, reading.clone()) {
+            ambiguities.push(YamlAmbiguity {
+                instance_path: pointer,
+                line: Some(source.line),
+                scalar: source.raw,
+                yaml11_only: true,
+                read_as: describe(&reading),
+            });
+        }
+    }
+
+    ambiguities.sort_by_key(|ambiguity| ambiguity.line);
+    ambiguities
+}
+
+/// Whether `document`, with the value at `pointer` replaced by `replacement`,
+/// fails a `type` check at `pointer`.
+fn has_type_failure(
+    validator: &Validator,
+    document: &Value,
+    pointer: &str,
+    replacement: Value,
+) -> bool {
+    let mut patched = document.clone();
+    match patched.pointer_mut(pointer) {
+        Some(slot) => *slot = replacement,
+        None => return false,
+    }
+    let failed = validator
+        .iter_errors(&patched)
+        .any(|failure| failure.instance_path == pointer && failure.keyword == "type");
+    failed
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Bool(_) => format!("the boolean {}", value),
+        Value::Number(_) => format!("the number {}", value),
+        _ => value.to_string(),
+    }
+}
+
+/// How a YAML 1.1 parser reads a plain scalar that YAML 1.2 reads as a string.
+pub(crate) fn yaml11_reading(scalar: &str) -> Option<Value> {
+    const TRUE: &[&str] = &["y", "Y", "yes", "Yes", "YES", "on", "On", "ON"];
+    const FALSE: &[&str] = &["n", "N", "no", "No", "NO", "off", "Off", "OFF"];
+    if TRUE.contains(&scalar) {
+        return Some(Value::Bool(true));
+    }
+    if FALSE.contains(&scalar) {
+        return Some(Value::Bool(false));
+    }
+    sexagesimal(scalar)
+        .or_else(|| radix_integer(scalar))
+        .map(Value::from)
+}
+
+/// The value of a YAML 1.1 integer written with a leading `0` (octal), a
+/// `0b` prefix or `_` separators, such as `0755` (493) or `1_000`.
+fn radix_integer(scalar: &str) -> Option<i64> {
+    let (sign, digits) = match scalar.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, scalar.strip_prefix('+').unwrap_or(scalar)),
+    };
+    let (radix, digits) = if let Some(binary) = digits.strip_prefix("0b") {
+        (2, binary)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (8, &digits[1..])
+    } else if digits.contains('_') {
+        (10, digits)
+    } else {
+        return None;
+    };
+    let digits = digits.replace('_', "");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    i64::from_str_radix(&digits, radix)
+        .ok()
+        .map(|value| sign * value)
+}
+
+/// The value of a base-60 integer such as `22:22` (1342), a YAML 1.1 form.
+fn sexagesimal(scalar: &str) -> Option<i64> {
+    let (sign, digits) = match scalar.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, scalar.strip_prefix('+').unwrap_or(scalar)),
+    };
+    let mut parts = digits.split(':');
+    let first = parts.next()?.replace('_', "");
+    if first.is_empty() || first.starts_with('0') || !first.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut value: i64 = first.parse().ok()?;
+    let mut any = false;
+    for part in parts {
+        let digit: i64 = match part.len() {
+            1 | 2 if part.bytes().all(|b| b.is_ascii_digit()) => part.parse().ok()?,
+            _ => return None,
+        };
+        if digit >= 60 {
+            return None;
+        }
+        value = value.checked_mul(60)?.checked_add(digit)?;
+        any = true;
+    }
+    any.then_some(sign * value)
+}
+
+fn collect_strings<'a>(value: &'a Value, pointer: String, out: &mut Vec<(String, &'a str)>) {
+    match value {
+        Value::String(text) => out.push((pointer, text)),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_strings(item, format!("{}/{}", pointer, index), out);
+            }
+        }
+        Value::Object(entries) => {
+            for (key, entry) in entries {
+                collect_strings(entry, format!("{}/{}", pointer, escape_token(key)), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A scalar as written in the source text.
+struct SourceScalar {
+    line: usize,
+    raw: String,
+    quoted: bool,
+}
+
+/// Find the block-style line that writes `value` at `pointer`.
+///
+/// This is a textual search for `key: scalar` (or `- scalar` for array items)
+/// where the scalar parses to `value`, not a full YAML parse, so flow-style
+/// collections are not found.
+fn locate_scalar(content: &str, pointer: &str, value: &Value) -> Option<SourceScalar> {
+    let token = pointer.rsplit('/').next()?;
+    let key = token.replace("~1", "/").replace("~0", "~");
+    let is_index = !pointer.is_empty() && token.parse::<usize>().is_ok();
+
+    for (index, line) in content.lines().enumerate() {
+        let mut rest = line.trim_start();
+        if let Some(item) = rest.strip_prefix("- ") {
+            rest = item.trim_start();
+            if is_index {
+                if let Some(scalar) = scalar_matching(rest, value) {
+                    return Some(SourceScalar {
+                        line: index + 1,
+                        ..scalar
+                    });
+                }
+            }
+        }
+        let after_key = [
+            format!("{}:", key),
+            format!("\"{}\":", key),
+            format!("'{}':", key),
+        ]
+        .iter()
+        .find_map(|prefix| rest.strip_prefix(prefix.as_str()));
+        if let Some(after_key) = after_key {
+            if after_key.starts_with([' ', '\t']) {
+                if let Some(scalar) = scalar_matching(after_key.trim(), value) {
+                    return Some(SourceScalar {
+                        line: index + 1,
+                        ..scalar
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn scalar_matching(text: &str, value: &Value) -> Option<SourceScalar> {
+    let raw = match text.find(" #") {
+        Some(comment) => text[..comment].trim_end(),
+        None => text,
+    };
+    if raw.is_empty() {
+        return None;
+    }
+    let parsed: Value = crate::yaml::from_str(raw).ok()?;
+    (&parsed == value).then(|| SourceScalar {
+        line: 0,
+        raw: raw.to_string(),
+        quoted: raw.starts_with(['"', '\'']),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_ambiguities() {
+        let validator = Validator::new(
+            r#"{
+                "properties": {
+                    "version": {"type": "string"},
+                    "country": {"type": "string"},
+                    "enabled": {"type": "boolean"},
+                    "port": {"type": "string"},
+                    "tags": {"items": {"type": "string"}}
+                }
+            }"#,
+        )
+        .unwrap();
+        let content = "version: 1.20  # release\n\
+                       country: NO\n\
+                       enabled: on\n\
+                       port: 22:22\n\
+                       tags:\n  - \"yes\"\n  - off\n";
+
+        let found: Vec<String> = find_yaml_ambiguities(&validator, content)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "line 1: /version: unquoted 1.20 is read as the number 1.2, \
+                 but the schema expects a string; quote it as \"1.20\"",
+                "line 2: /country: unquoted NO is read as the boolean false by YAML 1.1 \
+                 parsers, but the schema expects a string; quote it as \"NO\"",
+                "line 4: /port: unquoted 22:22 is read as the number 1342 by YAML 1.1 \
+                 parsers, but the schema expects a string; quote it as \"22:22\"",
+                "line 7: /tags/1: unquoted off is read as the boolean false by YAML 1.1 \
+                 parsers, but the schema expects a string; quote it as \"off\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sexagesimal() {
+        assert_eq!(sexagesimal("22:22"), Some(1342));
+        assert_eq!(sexagesimal("-1:30"), Some(-90));
+        assert_eq!(sexagesimal("1:60"), None);
+        assert_eq!(sexagesimal("12"), None);
+        assert_eq!(sexagesimal("08:30"), None);
+    }
+
+    #[test]
+    fn test_radix_integer() {
+        assert_eq!(radix_integer("0755"), Some(493));
+        assert_eq!(radix_integer("-0b1010"), Some(-10));
+        assert_eq!(radix_integer("1_000"), Some(1000));
+        assert_eq!(radix_integer("0889"), None);
+        assert_eq!(radix_integer("0.5"), None);
+        assert_eq!(radix_integer("123"), None);
+    }
+}
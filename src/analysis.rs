@@ -0,0 +1,847 @@
+//! Static analysis of schema documents.
+//!
+//! These checks look at the schema itself rather than at documents validated
+//! against it, catching mistakes that would otherwise only surface as
+//! confusing validation results.
+
+use crate::walk;
+use crate::{refs, Draft, Validator};
+use serde_json::Value;
+use std::fmt;
+
+/// The kind of problem found in a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaIssueKind {
+    /// An `examples` entry does not validate against its own subschema.
+    InvalidExample,
+    /// A `default` value does not validate against its own subschema.
+    InvalidDefault,
+    /// No instance can ever be valid against the subschema.
+    Unsatisfiable,
+    /// A keyword can never affect validation, e.g. `maximum` on `type: string`.
+    IneffectiveKeyword,
+    /// A keyword the schema's draft does not define, often a misspelling.
+    UnknownKeyword,
+    /// Type-specific keywords without a `type`, so other types pass unchecked.
+    MissingType,
+    /// `properties` without `additionalProperties`, so unknown properties pass.
+    OpenProperties,
+    /// An `enum` lists the same value more than once.
+    DuplicateEnumValue,
+}
+
+/// A problem found while analyzing a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaIssue {
+    /// What kind of problem this is.
+    pub kind: SchemaIssueKind,
+    /// JSON Pointer to the offending location in the schema.
+    pub pointer: String,
+    /// Human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+/// Run every schema analysis and return all issues found.
+pub fn analyze_schema(schema: &Value) -> Vec<SchemaIssue> {
+    let mut issues = check_examples(schema);
+    issues.extend(find_unsatisfiable(schema));
+    issues
+}
+
+/// Run every check suited to linting a schema: [`find_unsatisfiable`],
+/// [`find_unknown_keywords`] and [`find_smells`].
+///
+/// Unlike [`analyze_schema`], this reports constructs that are valid but
+/// usually unintended, so a schema may deliberately keep some of them.
+pub fn lint_schema(schema: &Value) -> Vec<SchemaIssue> {
+    let mut issues = find_unsatisfiable(schema);
+    issues.extend(find_unknown_keywords(schema));
+    issues.extend(find_smells(schema));
+    issues
+}
+
+/// Check that every `examples` entry and `default` value in `schema` is valid
+/// against the subschema it is declared in.
+///
+/// Documentation values drift from the constraints they describe surprisingly
+/// often; this catches that drift before users copy an example that fails.
+pub fn check_examples(schema: &Value) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+
+    walk::visit(schema, &mut |pointer, subschema| {
+        let default = subschema.get("default");
+        let examples = subschema.get("examples").and_then(Value::as_array);
+        if default.is_none() && examples.is_none_or(|examples| examples.is_empty()) {
+            return;
+        }
+
+        let validator = match Validator::from_subschema(schema, pointer) {
+            Ok(validator) => validator,
+            // Compilation problems are reported by schema compilation itself
+            Err(_) => return,
+        };
+
+        if let Some(default) = default {
+            if let Err(e) = validator.validate_value(default) {
+                issues.push(SchemaIssue {
+                    kind: SchemaIssueKind::InvalidDefault,
+                    pointer: format!("{}/default", pointer),
+                    message: format!(
+                        "default value {} is invalid: {}",
+                        default,
+                        failure_message(e)
+                    ),
+                });
+            }
+        }
+
+        for (index, example) in examples.into_iter().flatten().enumerate() {
+            if let Err(e) = validator.validate_value(example) {
+                issues.push(SchemaIssue {
+                    kind: SchemaIssueKind::InvalidExample,
+                    pointer: format!("{}/examples/{}", pointer, index),
+                    message: format!("example {} is invalid: {}", example, failure_message(e)),
+                });
+            }
+        }
+    });
+
+    issues
+}
+
+/// Find subschemas that can never match anything, and keywords that can never apply.
+///
+/// Detected contradictions include inverted bounds (`minLength > maxLength`,
+/// `minimum > maximum`, ...), `const` values outside `enum`, `enum`/`const`
+/// values of the wrong `type`, `allOf` branches requiring disjoint types, and
+/// type-specific keywords next to a `type` they cannot apply to.
+pub fn find_unsatisfiable(schema: &Value) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+
+    walk::visit(schema, &mut |pointer, subschema| {
+        let object = match subschema.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+        let mut unsatisfiable = |message: String| {
+            issues.push(SchemaIssue {
+                kind: SchemaIssueKind::Unsatisfiable,
+                pointer: pointer.to_string(),
+                message,
+            })
+        };
+
+        for (lower, upper) in [
+            ("minLength", "maxLength"),
+            ("minItems", "maxItems"),
+            ("minProperties", "maxProperties"),
+            ("minContains", "maxContains"),
+            ("minimum", "maximum"),
+        ] {
+            if let (Some(min), Some(max)) = (number(object.get(lower)), number(object.get(upper))) {
+                if min > max {
+                    unsatisfiable(format!(
+                        "`{}` ({}) is greater than `{}` ({})",
+                        lower, min, upper, max
+                    ));
+                }
+            }
+        }
+        for (lower, upper) in [
+            ("exclusiveMinimum", "maximum"),
+            ("minimum", "exclusiveMaximum"),
+            ("exclusiveMinimum", "exclusiveMaximum"),
+        ] {
+            if let (Some(min), Some(max)) = (number(object.get(lower)), number(object.get(upper))) {
+                if min >= max {
+                    unsatisfiable(format!(
+                        "`{}` ({}) leaves no room below `{}` ({})",
+                        lower, min, upper, max
+                    ));
+                }
+            }
+        }
+
+        let types = declared_types(subschema);
+        let unknown_types = unknown_type_names(subschema);
+        if types == Some(0) {
+            if unknown_types.is_empty() {
+                unsatisfiable("`type` is an empty list".to_string());
+            }
+            for name in &unknown_types {
+                unsatisfiable(unknown_type_message(name));
+            }
+        }
+
+        let enumeration = object.get("enum").and_then(Value::as_array);
+        if let Some(options) = enumeration {
+            if options.is_empty() {
+                unsatisfiable("`enum` is empty".to_string());
+            } else if let Some(types) = types {
+                if !options.iter().any(|option| value_matches(option, types)) {
+                    unsatisfiable(format!(
+                        "no `enum` value matches `type` {}",
+                        describe_types(types)
+                    ));
+                }
+            }
+        }
+
+        if let Some(constant) = object.get("const") {
+            if let Some(options) = enumeration {
+                if !options.contains(constant) {
+                    unsatisfiable(format!(
+                        "`const` value {} is not listed in `enum`",
+                        constant
+                    ));
+                }
+            }
+            if let Some(types) = types {
+                if !value_matches(constant, types) {
+                    unsatisfiable(format!(
+                        "`const` value {} does not match `type` {}",
+                        constant,
+                        describe_types(types)
+                    ));
+                }
+            }
+        }
+
+        if matches!(object.get("not"), Some(Value::Bool(true)))
+            || object.get("not").is_some_and(is_empty_object)
+        {
+            unsatisfiable("`not` negates a schema that accepts everything".to_string());
+        }
+
+        if let Some(branches) = object.get("allOf").and_then(Value::as_array) {
+            if branches.iter().any(|branch| branch == &Value::Bool(false)) {
+                unsatisfiable("`allOf` contains the `false` schema".to_string());
+            }
+            let mut combined = types.unwrap_or(ALL_TYPES);
+            for branch in branches {
+                if let Some(branch_types) = declared_types(refs::deref(schema, branch)) {
+                    combined &= branch_types;
+                }
+            }
+            if combined == 0 && types != Some(0) {
+                unsatisfiable(
+                    "`allOf` branches require types that have nothing in common".to_string(),
+                );
+            }
+        }
+
+        if let (Some(required), Some(properties)) = (
+            object.get("required").and_then(Value::as_array),
+            object.get("properties").and_then(Value::as_object),
+        ) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if properties.get(name) == Some(&Value::Bool(false)) {
+                    unsatisfiable(format!(
+                        "required property '{}' is forbidden by `properties`",
+                        name
+                    ));
+                }
+            }
+        }
+
+        if let Some(types) = types.filter(|types| *types != 0) {
+            for name in &unknown_types {
+                issues.push(SchemaIssue {
+                    kind: SchemaIssueKind::IneffectiveKeyword,
+                    pointer: pointer.to_string(),
+                    message: unknown_type_message(name),
+                });
+            }
+            for (keyword, applies_to) in TYPE_SPECIFIC_KEYWORDS {
+                if object.contains_key(*keyword) && types & applies_to == 0 {
+                    issues.push(SchemaIssue {
+                        kind: SchemaIssueKind::IneffectiveKeyword,
+                        pointer: pointer.to_string(),
+                        message: format!(
+                            "`{}` has no effect on `type` {}",
+                            keyword,
+                            describe_types(types)
+                        ),
+                    });
+                }
+            }
+            if let Some(options) = enumeration {
+                if options.iter().any(|option| value_matches(option, types)) {
+                    for option in options
+                        .iter()
+                        .filter(|option| !value_matches(option, types))
+                    {
+                        issues.push(SchemaIssue {
+                            kind: SchemaIssueKind::IneffectiveKeyword,
+                            pointer: pointer.to_string(),
+                            message: format!(
+                                "`enum` value {} can never match `type` {}",
+                                option,
+                                describe_types(types)
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+    });
+
+    issues
+}
+
+/// Find constructs that are valid but usually a mistake.
+///
+/// Reported are subschemas using type-specific keywords such as `properties`
+/// or `minLength` without declaring a `type` (so values of other types pass),
+/// object schemas listing `properties` without setting `additionalProperties`
+/// (so misspelled properties pass) and duplicate `enum` values. Branches of
+/// `allOf`, `anyOf`, `oneOf`, `not` and conditionals are exempt from the first
+/// two checks, since they are normally combined with a schema that declares
+/// both.
+pub fn find_smells(schema: &Value) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+
+    walk::visit(schema, &mut |pointer, subschema| {
+        let object = match subschema.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+
+        let combined = [
+            "type",
+            "$ref",
+            "$dynamicRef",
+            "$recursiveRef",
+            "enum",
+            "const",
+            "allOf",
+            "anyOf",
+            "oneOf",
+        ]
+        .iter()
+        .any(|keyword| object.contains_key(*keyword));
+        if !is_branch(pointer) && !combined {
+            let specific = TYPE_SPECIFIC_KEYWORDS
+                .iter()
+                .find(|(keyword, _)| object.contains_key(*keyword));
+            if let Some((keyword, applies_to)) = specific {
+                issues.push(SchemaIssue {
+                    kind: SchemaIssueKind::MissingType,
+                    pointer: pointer.to_string(),
+                    message: format!(
+                        "`{}` only applies to type {}, but there is no `type`, so values of \
+                         other types are accepted",
+                        keyword,
+                        describe_types(*applies_to)
+                    ),
+                });
+            }
+        }
+
+        let open = object.get("properties").is_some_and(Value::is_object)
+            && !object.contains_key("additionalProperties")
+            && !object.contains_key("unevaluatedProperties");
+        if open && !is_branch(pointer) {
+            issues.push(SchemaIssue {
+                kind: SchemaIssueKind::OpenProperties,
+                pointer: pointer.to_string(),
+                message: "`additionalProperties` is not set, so unknown or misspelled \
+                          properties are accepted"
+                    .to_string(),
+            });
+        }
+
+        if let Some(options) = object.get("enum").and_then(Value::as_array) {
+            for (index, option) in options.iter().enumerate() {
+                if options[..index].contains(option) {
+                    issues.push(SchemaIssue {
+                        kind: SchemaIssueKind::DuplicateEnumValue,
+                        pointer: format!("{}/enum/{}", pointer, index),
+                        message: format!("`enum` value {} is listed more than once", option),
+                    });
+                }
+            }
+        }
+    });
+
+    issues
+}
+
+/// Whether the subschema at `pointer` is a branch of a combinator or
+/// conditional rather than a schema on its own.
+fn is_branch(pointer: &str) -> bool {
+    let mut tokens = pointer.rsplit('/');
+    let last = tokens.next().unwrap_or_default();
+    let parent = tokens.next().unwrap_or_default();
+    matches!(last, "if" | "then" | "else" | "not")
+        || matches!(
+            parent,
+            "allOf" | "anyOf" | "oneOf" | "dependentSchemas" | "dependencies"
+        )
+}
+
+// JSON types as bit flags; `integer` is a subset of `number`.
+const NULL: u8 = 1;
+const BOOLEAN: u8 = 1 << 1;
+const OBJECT: u8 = 1 << 2;
+const ARRAY: u8 = 1 << 3;
+const INTEGER: u8 = 1 << 4;
+const NON_INTEGER_NUMBER: u8 = 1 << 5;
+const STRING: u8 = 1 << 6;
+const NUMBER: u8 = INTEGER | NON_INTEGER_NUMBER;
+pub(crate) const ALL_TYPES: u8 = NULL | BOOLEAN | OBJECT | ARRAY | NUMBER | STRING;
+const TYPE_NAMES: [&str; 7] = [
+    "null", "boolean", "object", "array", "integer", "number", "string",
+];
+
+/// Keywords that only constrain instances of particular types.
+const TYPE_SPECIFIC_KEYWORDS: &[(&str, u8)] = &[
+    ("minimum", NUMBER),
+    ("maximum", NUMBER),
+    ("exclusiveMinimum", NUMBER),
+    ("exclusiveMaximum", NUMBER),
+    ("multipleOf", NUMBER),
+    ("minLength", STRING),
+    ("maxLength", STRING),
+    ("pattern", STRING),
+    ("items", ARRAY),
+    ("prefixItems", ARRAY),
+    ("additionalItems", ARRAY),
+    ("contains", ARRAY),
+    ("minItems", ARRAY),
+    ("maxItems", ARRAY),
+    ("uniqueItems", ARRAY),
+    ("properties", OBJECT),
+    ("patternProperties", OBJECT),
+    ("additionalProperties", OBJECT),
+    ("required", OBJECT),
+    ("minProperties", OBJECT),
+    ("maxProperties", OBJECT),
+    ("propertyNames", OBJECT),
+    ("dependencies", OBJECT),
+    ("dependentRequired", OBJECT),
+    ("dependentSchemas", OBJECT),
+];
+
+/// Find keywords that the schema's draft does not define.
+///
+/// Unknown keywords are silently ignored during validation, so a misspelling
+/// such as `additionalProperites` quietly disables the constraint it was meant
+/// to add. The draft is taken from `$schema`, defaulting to Draft 7 like
+/// validation does. Extension keywords prefixed with `x-` and the
+/// `errorMessage` keyword, which the validator implements, are not reported.
+/// This check is not part of [`analyze_schema`] because schemas legitimately
+/// carry custom keywords.
+pub fn find_unknown_keywords(schema: &Value) -> Vec<SchemaIssue> {
+    let draft = Draft::detect(schema).unwrap_or(Draft::Draft7);
+    let known = draft.keywords();
+    let mut issues = Vec::new();
+
+    walk::visit(schema, &mut |pointer, subschema| {
+        let object = match subschema.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+        for keyword in object.keys() {
+            if known.contains(&keyword.as_str())
+                || keyword.starts_with("x-")
+                || keyword == "errorMessage"
+            {
+                continue;
+            }
+            let mut message = format!("unknown keyword '{}' is ignored by {}", keyword, draft);
+            if let Some(suggestion) = closest_name(keyword, &known) {
+                message.push_str(&format!(" (did you mean '{}'?)", suggestion));
+            }
+            issues.push(SchemaIssue {
+                kind: SchemaIssueKind::UnknownKeyword,
+                pointer: format!("{}/{}", pointer, walk::escape_token(keyword)),
+                message,
+            });
+        }
+    });
+
+    issues
+}
+
+/// The known name closest to `name`, if it is a plausible misspelling.
+fn closest_name<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, candidate)| {
+            *distance <= 2 && *distance < candidate.len() / 2
+                || candidate.eq_ignore_ascii_case(name)
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between `a` and `b`, counting a swap of two adjacent
+/// characters as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    rows[0] = (0..=b.len()).collect();
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+fn type_flag(name: &str) -> u8 {
+    match name {
+        "null" => NULL,
+        "boolean" => BOOLEAN,
+        "object" => OBJECT,
+        "array" => ARRAY,
+        "integer" => INTEGER,
+        "number" => NUMBER,
+        "string" => STRING,
+        _ => 0,
+    }
+}
+
+/// The names in a subschema's `type` keyword that are not JSON Schema types.
+fn unknown_type_names(schema: &Value) -> Vec<&str> {
+    let names = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    names
+        .into_iter()
+        .filter(|name| type_flag(name) == 0)
+        .collect()
+}
+
+fn unknown_type_message(name: &str) -> String {
+    let mut message = format!("`type` names unknown type '{}'", name);
+    if let Some(suggestion) = closest_name(name, &TYPE_NAMES) {
+        message.push_str(&format!(" (did you mean '{}'?)", suggestion));
+    }
+    message
+}
+
+/// The types allowed by a subschema's `type` keyword, or `None` if unconstrained.
+pub(crate) fn declared_types(schema: &Value) -> Option<u8> {
+    match schema.get("type")? {
+        Value::String(name) => Some(type_flag(name)),
+        Value::Array(names) => Some(
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .fold(0, |types, name| types | type_flag(name)),
+        ),
+        _ => None,
+    }
+}
+
+fn value_matches(value: &Value, types: u8) -> bool {
+    let flag = match value {
+        Value::Null => NULL,
+        Value::Bool(_) => BOOLEAN,
+        Value::Object(_) => OBJECT,
+        Value::Array(_) => ARRAY,
+        Value::String(_) => STRING,
+        Value::Number(number) => {
+            if number.is_i64()
+                || number.is_u64()
+                || number.as_f64().is_some_and(|f| f.fract() == 0.0)
+            {
+                INTEGER
+            } else {
+                NON_INTEGER_NUMBER
+            }
+        }
+    };
+    types & flag != 0
+}
+
+pub(crate) fn describe_types(types: u8) -> String {
+    let mut names = Vec::new();
+    for (flag, name) in [
+        (NULL, "null"),
+        (BOOLEAN, "boolean"),
+        (OBJECT, "object"),
+        (ARRAY, "array"),
+        (STRING, "string"),
+    ] {
+        if types & flag != 0 {
+            names.push(name);
+        }
+    }
+    if types & NUMBER == NUMBER {
+        names.push("number");
+    } else if types & INTEGER != 0 {
+        names.push("integer");
+    }
+    names.join("/")
+}
+
+fn number(value: Option<&Value>) -> Option<f64> {
+    value?.as_f64()
+}
+
+fn is_empty_object(value: &Value) -> bool {
+    value.as_object().is_some_and(|object| object.is_empty())
+}
+
+fn failure_message(error: crate::ValidationError) -> String {
+    match error {
+        crate::ValidationError::ValidationFailed(message) => message,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_satisfiable_schema_has_no_issues() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string", "minLength": 1, "maxLength": 10},
+                "size": {"type": "integer", "minimum": 1, "exclusiveMaximum": 10},
+                "mode": {"type": "string", "enum": ["a", "b"], "const": "a"},
+                "id": {"allOf": [{"type": "number"}, {"type": "integer"}]}
+            }
+        });
+        assert!(find_unsatisfiable(&schema).is_empty());
+    }
+
+    #[test]
+    fn test_unsatisfiable_subschemas() {
+        let schema = json!({
+            "definitions": {"text": {"type": "string"}},
+            "properties": {
+                "name": {"type": "string", "minLength": 5, "maxLength": 2},
+                "size": {"minimum": 10, "exclusiveMaximum": 10},
+                "mode": {"enum": ["a", "b"], "const": "c"},
+                "flag": {"type": "boolean", "enum": ["yes", "no"]},
+                "id": {"allOf": [{"$ref": "#/definitions/text"}, {"type": "integer"}]}
+            }
+        });
+
+        let issues = find_unsatisfiable(&schema);
+        let pointers: Vec<&str> = issues.iter().map(|issue| issue.pointer.as_str()).collect();
+        assert_eq!(issues.len(), 5, "{:?}", issues);
+        assert!(issues
+            .iter()
+            .all(|issue| issue.kind == SchemaIssueKind::Unsatisfiable));
+        for pointer in [
+            "/properties/name",
+            "/properties/size",
+            "/properties/mode",
+            "/properties/flag",
+            "/properties/id",
+        ] {
+            assert!(pointers.contains(&pointer), "missing {}", pointer);
+        }
+    }
+
+    #[test]
+    fn test_unknown_type_names() {
+        let issues = find_unsatisfiable(&json!({"type": "strnig"}));
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].kind, SchemaIssueKind::Unsatisfiable);
+        assert_eq!(
+            issues[0].message,
+            "`type` names unknown type 'strnig' (did you mean 'string'?)"
+        );
+
+        let issues = find_unsatisfiable(&json!({"type": ["string", "nul"]}));
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].kind, SchemaIssueKind::IneffectiveKeyword);
+        assert!(issues[0].message.contains("'nul'"));
+
+        let issues = find_unsatisfiable(&json!({"type": []}));
+        assert_eq!(issues[0].message, "`type` is an empty list");
+    }
+
+    #[test]
+    fn test_ineffective_keywords() {
+        let schema = json!({"type": "string", "maximum": 3, "enum": ["a", 1]});
+        let issues = find_unsatisfiable(&schema);
+        assert_eq!(issues.len(), 2);
+        assert!(issues
+            .iter()
+            .all(|issue| issue.kind == SchemaIssueKind::IneffectiveKeyword));
+        assert!(issues[0].message.contains("`maximum`"));
+        assert!(issues[1].message.contains("enum` value 1"));
+    }
+
+    #[test]
+    fn test_valid_examples_and_defaults() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "port": {"type": "integer", "default": 8080, "examples": [80, 443]}
+            },
+            "examples": [{"port": 1}]
+        });
+        assert!(check_examples(&schema).is_empty());
+    }
+
+    #[test]
+    fn test_drifted_examples_and_defaults() {
+        let schema = json!({
+            "definitions": {
+                "level": {"enum": ["debug", "info"]}
+            },
+            "properties": {
+                "port": {"type": "integer", "maximum": 65535, "default": "8080"},
+                "log level": {
+                    "$ref": "#/definitions/level",
+                    "examples": ["info", "verbose"]
+                }
+            }
+        });
+
+        let issues = check_examples(&schema);
+        assert_eq!(issues.len(), 2);
+
+        let default = issues
+            .iter()
+            .find(|issue| issue.kind == SchemaIssueKind::InvalidDefault)
+            .unwrap();
+        assert_eq!(default.pointer, "/properties/port/default");
+        assert!(default.message.contains("\"8080\""));
+
+        let example = issues
+            .iter()
+            .find(|issue| issue.kind == SchemaIssueKind::InvalidExample)
+            .unwrap();
+        assert_eq!(example.pointer, "/properties/log level/examples/1");
+        assert!(example.message.contains("verbose"));
+    }
+
+    #[test]
+    fn test_schema_smells() {
+        let schema = json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "name": {"minLength": 1},
+                "level": {"enum": ["debug", "info", "debug"]},
+                "config": {
+                    "type": "object",
+                    "properties": {"path": {"type": "string"}},
+                    "oneOf": [
+                        {"required": ["path"], "properties": {"path": {"pattern": "^/"}}},
+                        {"required": ["url"]}
+                    ]
+                },
+                "tags": {"type": "array", "items": {"$ref": "#/definitions/tag"}}
+            },
+            "definitions": {"tag": {"type": "string", "maxLength": 20}}
+        });
+
+        let issues = find_smells(&schema);
+        let found: Vec<(SchemaIssueKind, &str)> = issues
+            .iter()
+            .map(|issue| (issue.kind, issue.pointer.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (SchemaIssueKind::OpenProperties, "/properties/config"),
+                // Branch subschemas are exempt, but not the schemas inside them
+                (
+                    SchemaIssueKind::MissingType,
+                    "/properties/config/oneOf/0/properties/path"
+                ),
+                (
+                    SchemaIssueKind::DuplicateEnumValue,
+                    "/properties/level/enum/2"
+                ),
+                (SchemaIssueKind::MissingType, "/properties/name"),
+            ]
+        );
+        assert!(issues[3]
+            .message
+            .contains("`minLength` only applies to type string"));
+
+        let linted = lint_schema(&json!({
+            "properties": {"id": {"type": "string", "maximum": 3}},
+            "require": ["id"]
+        }));
+        let kinds: Vec<SchemaIssueKind> = linted.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SchemaIssueKind::IneffectiveKeyword,
+                SchemaIssueKind::UnknownKeyword,
+                SchemaIssueKind::MissingType,
+                SchemaIssueKind::OpenProperties,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keywords() {
+        let schema = json!({
+            "type": "object",
+            "additionalProperites": false,
+            "x-internal": true,
+            "errorMessage": "must be a service",
+            "properties": {
+                "name": {"type": "string", "minlength": 1},
+                "tags": {"items": {"$defs": {}}}
+            }
+        });
+
+        let issues = find_unknown_keywords(&schema);
+        let found: Vec<(&str, &str)> = issues
+            .iter()
+            .map(|issue| (issue.pointer.as_str(), issue.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "/additionalProperites",
+                    "unknown keyword 'additionalProperites' is ignored by draft7 \
+                     (did you mean 'additionalProperties'?)"
+                ),
+                (
+                    "/properties/name/minlength",
+                    "unknown keyword 'minlength' is ignored by draft7 (did you mean 'minLength'?)"
+                ),
+                (
+                    "/properties/tags/items/$defs",
+                    "unknown keyword '$defs' is ignored by draft7"
+                ),
+            ]
+        );
+
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$defs": {"item": {"prefixItems": [{"type": "string"}]}}
+        });
+        assert!(find_unknown_keywords(&schema).is_empty());
+    }
+}
@@ -0,0 +1,412 @@
+//! Suppressing known failures with a baseline.
+//!
+//! A large repository adopting a schema usually has more failures than
+//! anyone can fix at once. A [`Baseline`] records the failures of a run, and
+//! a [`BaselineReporter`] hides the recorded ones from later runs of
+//! [`report_files`](crate::report_files), so only new failures fail the run
+//! while the recorded ones are fixed over time.
+
+use crate::failure::ValidationFailure;
+use crate::report::{FileError, Reporter, Summary};
+use crate::{combine_failures, FileReport, FileStatus, ValidationError};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Version of the layout of baseline files.
+const BASELINE_VERSION: u64 = 1;
+
+/// The known failures of a set of files.
+///
+/// A failure is known if the baseline has a failure of the same file at the
+/// same instance path and schema path. Messages are not compared, so a value
+/// that changes from one invalid value to another stays suppressed. Each
+/// recorded failure suppresses one failure per run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Baseline {
+    /// Each known failure with its file, as the run named it.
+    failures: Vec<(String, ValidationFailure)>,
+}
+
+impl Baseline {
+    /// An empty baseline, which suppresses nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a baseline written by [`save`](Self::save).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a baseline.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ValidationError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_json(&serde_json::from_str(&content)?)
+    }
+
+    /// Read a baseline from the JSON of [`to_json`](Self::to_json).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` is not such a baseline.
+    pub fn from_json(value: &Value) -> Result<Self, ValidationError> {
+        let invalid = |message: &str| ValidationError::InvalidReport(message.to_string());
+        let failures = value
+            .get("failures")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("expected a baseline with a \"failures\" array"))?;
+        let failures = failures
+            .iter()
+            .map(|failure| {
+                let path = failure.get("path").and_then(Value::as_str);
+                match (path, ValidationFailure::from_json(failure)) {
+                    (Some(path), Some(failure)) => Ok((path.to_string(), failure)),
+                    _ => Err(invalid("a baseline failure is missing a field")),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { failures })
+    }
+
+    /// The baseline as JSON: each failure's object with a `path` field,
+    /// sorted so that rewriting a baseline gives a readable diff.
+    pub fn to_json(&self) -> Value {
+        let mut failures = self.failures.clone();
+        failures.sort_by(|(a_path, a), (b_path, b)| {
+            (a_path, &a.instance_path, &a.schema_path).cmp(&(
+                b_path,
+                &b.instance_path,
+                &b.schema_path,
+            ))
+        });
+        let failures: Vec<Value> = failures
+            .into_iter()
+            .map(|(path, failure)| {
+                let mut value = failure.to_json();
+                value["path"] = json!(path);
+                value
+            })
+            .collect();
+        json!({"version": BASELINE_VERSION, "failures": failures})
+    }
+
+    /// Write the baseline to `path` as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ValidationError> {
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// Add `failure` of the file at `path` to the known failures.
+    pub fn record(&mut self, path: impl Into<String>, failure: ValidationFailure) {
+        self.failures.push((path.into(), failure));
+    }
+
+    /// Number of known failures.
+    pub fn len(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// Whether the baseline has no known failures.
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// How many failures of each file, instance path and schema path are known.
+    fn counts(&self) -> HashMap<(String, String, String), usize> {
+        let mut counts = HashMap::new();
+        for (path, failure) in &self.failures {
+            let key = (
+                path.clone(),
+                failure.instance_path.clone(),
+                failure.schema_path.clone(),
+            );
+            *counts.entry(key).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// A [`Reporter`] that passes a run on to another, without the failures a
+/// [`Baseline`] knows.
+///
+/// An invalid file whose failures are all known is reported as valid, and
+/// the totals count the suppressed failures as
+/// [`baselined`](Summary::baselined). Files that could not be validated, and
+/// invalid files whose failures cannot be listed, such as YAML streams of
+/// several documents, are passed on unchanged. Every failure the run lists is
+/// recorded, so [`into_parts`](Self::into_parts) gives the baseline of the
+/// run.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use validate_json_schema::{report_files, Baseline, BaselineReporter, BatchOptions, Validator};
+///
+/// #[derive(Default)]
+/// struct Totals(validate_json_schema::Summary);
+///
+/// impl validate_json_schema::Reporter for Totals {
+///     fn on_summary(&mut self, summary: &validate_json_schema::Summary) {
+///         self.0 = summary.clone();
+///     }
+/// }
+///
+/// let validator = Arc::new(Validator::new(r#"{"required": ["name"]}"#)?);
+/// let files = ["tests/data/package.json", "tests/data/tree.json"];
+///
+/// // Record the failures of a first run, then suppress them in the next
+/// let mut first = BaselineReporter::new(&Baseline::new(), Totals::default());
+/// report_files(&validator, &files, &BatchOptions::default(), &mut first);
+/// let (totals, baseline) = first.into_parts();
+/// assert_eq!(totals.0.invalid, 1);
+///
+/// let mut next = BaselineReporter::new(&baseline, Totals::default());
+/// report_files(&validator, &files, &BatchOptions::default(), &mut next);
+/// let (totals, _) = next.into_parts();
+/// assert_eq!((totals.0.invalid, totals.0.baselined), (0, 1));
+/// # Ok::<(), validate_json_schema::ValidationError>(())
+/// ```
+pub struct BaselineReporter<R> {
+    inner: R,
+    /// How many more failures of each file, instance path and schema path
+    /// are suppressed.
+    known: HashMap<(String, String, String), usize>,
+    /// Whether every listed failure is suppressed.
+    all: bool,
+    /// The failures of the run.
+    recorded: Baseline,
+    /// The messages of the current file's failures that were passed on.
+    kept: Vec<String>,
+    /// Number of the current file's failures that were suppressed.
+    suppressed: usize,
+    /// Invalid files reported as valid, and failures suppressed, so far.
+    cleared: usize,
+    baselined: usize,
+}
+
+impl<R: Reporter> BaselineReporter<R> {
+    /// Pass the run on to `inner` without the failures `baseline` knows.
+    pub fn new(baseline: &Baseline, inner: R) -> Self {
+        Self {
+            inner,
+            known: baseline.counts(),
+            all: false,
+            recorded: Baseline::new(),
+            kept: Vec::new(),
+            suppressed: 0,
+            cleared: 0,
+            baselined: 0,
+        }
+    }
+
+    /// Pass the run on to `inner` as if every failure it lists were known,
+    /// to record a new baseline.
+    pub fn recording(inner: R) -> Self {
+        Self {
+            all: true,
+            ..Self::new(&Baseline::new(), inner)
+        }
+    }
+
+    /// The reporter the run was passed on to, and the baseline of the
+    /// failures the run listed.
+    pub fn into_parts(self) -> (R, Baseline) {
+        (self.inner, self.recorded)
+    }
+}
+
+impl<R: Reporter> Reporter for BaselineReporter<R> {
+    fn on_file_start(&mut self, path: &Path) {
+        self.inner.on_file_start(path);
+    }
+
+    fn on_error(&mut self, path: &Path, error: &FileError) {
+        let FileError::Failure(finding) = error else {
+            return self.inner.on_error(path, error);
+        };
+        let failure = &finding.failure;
+        let display = path.display().to_string();
+        self.recorded.record(display.clone(), failure.clone());
+        let key = (
+            display,
+            failure.instance_path.clone(),
+            failure.schema_path.clone(),
+        );
+        let known = match self.known.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => self.all,
+        };
+        if known {
+            self.suppressed += 1;
+            return;
+        }
+        self.kept.push(match finding.location {
+            Some(location) => format!("{}: {}", location, failure),
+            None => failure.to_string(),
+        });
+        self.inner.on_error(path, error);
+    }
+
+    fn on_file_end(&mut self, report: &FileReport, elapsed: Duration) {
+        let kept = std::mem::take(&mut self.kept);
+        let suppressed = std::mem::take(&mut self.suppressed);
+        if suppressed == 0 {
+            return self.inner.on_file_end(report, elapsed);
+        }
+        self.baselined += suppressed;
+        let status = match combine_failures(kept) {
+            Ok(()) => {
+                self.cleared += 1;
+                FileStatus::Valid
+            }
+            Err(e) => FileStatus::Failed(e),
+        };
+        let report = FileReport {
+            path: report.path.clone(),
+            status,
+        };
+        self.inner.on_file_end(&report, elapsed);
+    }
+
+    fn on_summary(&mut self, summary: &Summary) {
+        let summary = Summary {
+            valid: summary.valid + self.cleared,
+            invalid: summary.invalid - self.cleared,
+            failures: summary.failures - self.baselined,
+            baselined: summary.baselined + self.baselined,
+            ..summary.clone()
+        };
+        self.inner.on_summary(&summary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::Finding;
+
+    fn failure(instance_path: &str, message: &str) -> ValidationFailure {
+        ValidationFailure {
+            instance_path: instance_path.to_string(),
+            schema_path: format!("/properties{}/type", instance_path),
+            keyword: "type".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Reporter for Events {
+        fn on_error(&mut self, _path: &Path, error: &FileError) {
+            if let FileError::Failure(finding) = error {
+                self.0.push(finding.failure.to_string());
+            }
+        }
+
+        fn on_file_end(&mut self, report: &FileReport, _elapsed: Duration) {
+            self.0.push(match &report.status {
+                FileStatus::Failed(e) => e.to_string(),
+                status => format!("{:?}", status),
+            });
+        }
+    }
+
+    #[test]
+    fn test_baseline_json() {
+        let mut baseline = Baseline::new();
+        baseline.record(
+            "b.yml",
+            failure("/port", "\"x\" is not of type \"integer\""),
+        );
+        baseline.record("a.yml", failure("/name", "1 is not of type \"string\""));
+        let json = baseline.to_json();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["failures"][0]["path"], "a.yml");
+        assert_eq!(json["failures"][1]["instance_path"], "/port");
+
+        let read = Baseline::from_json(&json).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read.to_json(), json);
+        assert!(matches!(
+            Baseline::from_json(&json!({"failures": [{"path": "a.yml"}]})),
+            Err(ValidationError::InvalidReport(_))
+        ));
+    }
+
+    #[test]
+    fn test_baseline_reporter() {
+        let mut baseline = Baseline::new();
+        baseline.record(
+            "a.yml",
+            failure("/port", "\"x\" is not of type \"integer\""),
+        );
+        let mut reporter = BaselineReporter::new(&baseline, Events::default());
+
+        let path = Path::new("a.yml");
+        let report = |message: &str| FileReport {
+            path: path.to_path_buf(),
+            status: FileStatus::Failed(ValidationError::ValidationFailed(message.to_string())),
+        };
+        // The known failure is suppressed even though its value changed
+        for instance_path in ["/port", "/name"] {
+            let finding = Finding {
+                location: None,
+                failure: failure(instance_path, "true is not of type \"integer\""),
+            };
+            reporter.on_error(path, &FileError::Failure(finding));
+        }
+        reporter.on_file_end(&report("2 validation errors"), Duration::ZERO);
+        reporter.on_error(
+            path,
+            &FileError::Failure(Finding {
+                location: None,
+                failure: failure("/port", "1.5 is not of type \"integer\""),
+            }),
+        );
+        reporter.on_file_end(
+            &report("/port: 1.5 is not of type \"integer\""),
+            Duration::ZERO,
+        );
+
+        let (events, recorded) = reporter.into_parts();
+        assert_eq!(
+            events.0,
+            [
+                "/name: true is not of type \"integer\"",
+                "Validation failed: /name: true is not of type \"integer\"",
+                // Each known failure is suppressed once per run
+                "/port: 1.5 is not of type \"integer\"",
+                "Validation failed: /port: 1.5 is not of type \"integer\"",
+            ]
+        );
+        assert_eq!(recorded.len(), 3);
+
+        let mut recording = BaselineReporter::recording(Events::default());
+        recording.on_error(
+            path,
+            &FileError::Failure(Finding {
+                location: None,
+                failure: failure("/port", "1.5 is not of type \"integer\""),
+            }),
+        );
+        recording.on_file_end(
+            &report("/port: 1.5 is not of type \"integer\""),
+            Duration::ZERO,
+        );
+        let (events, recorded) = recording.into_parts();
+        assert_eq!(events.0, ["Valid"]);
+        assert_eq!(recorded.len(), 1);
+    }
+}
@@ -0,0 +1,403 @@
+//! Validating many files against one schema.
+//!
+//! Each file is validated on its own thread so that a pathological document
+//! (deep nesting, a giant line) can neither hang nor abort the whole run: a
+//! file that exceeds the timeout or panics is reported and the run continues.
+//! With [`BatchOptions::jobs`], [`report_files`](crate::report_files)
+//! validates several files at once, sharing the one compiled validator.
+//!
+//! Files are often generated or rewritten while a run is in progress. A file
+//! that was readable when the run started but is gone or unreadable by the
+//! time it is validated is reported as [`FileStatus::Vanished`], and one
+//! that is modified while it is being validated as [`FileStatus::Changed`],
+//! instead of as an I/O or parse error.
+
+use crate::github::Finding;
+use crate::{ValidationError, Validator};
+use rayon::prelude::*;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Settings for [`validate_files`].
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Give up on a file after this long. `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+    /// Start no further validations once this long has passed since the run
+    /// began. The remaining files are reported as [`FileStatus::Skipped`]; a
+    /// validation already running is still bounded only by `timeout`.
+    pub deadline: Option<Duration>,
+    /// Report files that vanished or changed during the run as
+    /// [`FileStatus::Failed`], as any other unreadable or malformed file is.
+    pub fail_on_race: bool,
+    /// How many files [`report_files`](crate::report_files) validates at
+    /// once. Their results are still reported in order. One at a time if 0
+    /// or 1.
+    pub jobs: usize,
+}
+
+/// The result of validating one file in a batch.
+#[derive(Debug)]
+pub enum FileStatus {
+    /// The file is valid.
+    Valid,
+    /// The file could not be read or parsed, or failed validation.
+    Failed(ValidationError),
+    /// Validation did not finish within the timeout.
+    TimedOut(Duration),
+    /// Validation panicked; the payload message if it was a string.
+    Panicked(String),
+    /// The file was not validated because the run's deadline had passed.
+    Skipped,
+    /// The file was readable when the run started but was deleted, renamed
+    /// or made unreadable before it was validated.
+    Vanished(io::Error),
+    /// The file was modified while it was validated, so the result may
+    /// describe neither version.
+    Changed,
+    /// The file holds no document and the validator skips such files
+    /// ([`EmptyDocuments::Skip`](crate::EmptyDocuments::Skip)).
+    Empty,
+}
+
+impl FileStatus {
+    /// Whether the file could not be processed at all (timeout or panic),
+    /// as opposed to being checked and found valid or invalid.
+    pub fn is_abnormal(&self) -> bool {
+        matches!(self, FileStatus::TimedOut(_) | FileStatus::Panicked(_))
+    }
+
+    /// Whether the file vanished or changed during the run, rather than
+    /// being checked as it was.
+    pub fn is_race(&self) -> bool {
+        matches!(self, FileStatus::Vanished(_) | FileStatus::Changed)
+    }
+}
+
+/// One file's outcome in a batch run.
+#[derive(Debug)]
+pub struct FileReport {
+    /// The file that was validated.
+    pub path: PathBuf,
+    /// What happened.
+    pub status: FileStatus,
+}
+
+/// Validate each of `files` against `validator`, isolating failures per file.
+///
+/// Files are processed in order. A timed-out validation keeps running on its
+/// detached thread until it finishes, but its result is discarded.
+pub fn validate_files<P: AsRef<Path>>(
+    validator: &Arc<Validator>,
+    files: &[P],
+    options: &BatchOptions,
+) -> Vec<FileReport> {
+    let mut reports = Vec::with_capacity(files.len());
+    validate_files_streaming(validator, files, options, |report| reports.push(report));
+    reports
+}
+
+/// Validate each of `files` as [`validate_files`] does, passing each file's
+/// report to `on_report` as soon as it is validated instead of collecting
+/// them, so that a run over many files can report progress as it goes.
+pub fn validate_files_streaming<P: AsRef<Path>>(
+    validator: &Arc<Validator>,
+    files: &[P],
+    options: &BatchOptions,
+    mut on_report: impl FnMut(FileReport),
+) {
+    let started = Instant::now();
+    for (path, readable) in files.iter().zip(readable_files(files)) {
+        let path = path.as_ref().to_path_buf();
+        let (status, _) = validate_file(validator, &path, options, started, readable, false);
+        on_report(FileReport { path, status });
+    }
+}
+
+/// Whether each of `files` can be opened, checked when a run starts so
+/// that files gone by the time they are validated can be told apart.
+pub(crate) fn readable_files<P: AsRef<Path>>(files: &[P]) -> Vec<bool> {
+    files.iter().map(|path| File::open(path).is_ok()).collect()
+}
+
+/// Validate the file at `path` in a run that began at `started`, applying
+/// the run's `options`. `readable` is whether the file could be opened when
+/// the run started. If `locate` is set and the file fails validation, its
+/// failures are returned too, located in the content that was validated.
+pub(crate) fn validate_file(
+    validator: &Arc<Validator>,
+    path: &Path,
+    options: &BatchOptions,
+    started: Instant,
+    readable: bool,
+    locate: bool,
+) -> (FileStatus, Vec<Finding>) {
+    if options
+        .deadline
+        .is_some_and(|deadline| started.elapsed() >= deadline)
+    {
+        return (FileStatus::Skipped, Vec::new());
+    }
+    let (status, findings) = validate_isolated(validator, path, options.timeout, readable, locate);
+    let status = match status {
+        FileStatus::Vanished(e) if options.fail_on_race => {
+            FileStatus::Failed(ValidationError::FileRead(e))
+        }
+        FileStatus::Changed if options.fail_on_race => FileStatus::Failed(
+            ValidationError::FileRead(io::Error::other("the file changed while it was validated")),
+        ),
+        status => status,
+    };
+    match status {
+        FileStatus::Failed(ValidationError::ValidationFailed(_)) => (status, findings),
+        status => (status, Vec::new()),
+    }
+}
+
+/// Apply `f` to each of `items` on up to `jobs` threads, passing each index
+/// and result to `on_result` in the order of `items` as soon as it and those
+/// before it are done. Runs on the calling thread if `jobs` is 0 or 1, or
+/// if the threads cannot be started.
+pub(crate) fn map_ordered<T, R>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync,
+    mut on_result: impl FnMut(usize, R),
+) where
+    T: Sync,
+    R: Send,
+{
+    let pool = (jobs > 1)
+        .then(|| rayon::ThreadPoolBuilder::new().num_threads(jobs).build())
+        .and_then(Result::ok);
+    let Some(pool) = pool else {
+        for (index, item) in items.iter().enumerate() {
+            on_result(index, f(item));
+        }
+        return;
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let f = &f;
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            pool.install(|| {
+                items
+                    .par_iter()
+                    .enumerate()
+                    .for_each_with(sender, |sender, (index, item)| {
+                        let _ = sender.send((index, f(item)));
+                    })
+            })
+        });
+        // Results that finished before those of earlier items
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (index, result) in receiver {
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&next) {
+                on_result(next, result);
+                next += 1;
+            }
+        }
+    });
+}
+
+/// Enough of a file's metadata to tell that it was modified.
+fn fingerprint(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+/// Validate `path` on its own thread. `readable` is whether the file could be
+/// opened when the run started. The failures are located only if `locate`
+/// is set.
+fn validate_isolated(
+    validator: &Arc<Validator>,
+    path: &Path,
+    timeout: Option<Duration>,
+    readable: bool,
+    locate: bool,
+) -> (FileStatus, Vec<Finding>) {
+    let (sender, receiver) = mpsc::channel();
+    let validator = Arc::clone(validator);
+    let worker_path = path.to_path_buf();
+    let spawned = thread::Builder::new()
+        .name(format!("validate {}", path.display()))
+        .spawn(move || {
+            let before = fingerprint(&worker_path);
+            let mut findings = Vec::new();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                validator.check_file_into(&worker_path, locate.then_some(&mut findings))
+            }));
+            let changed = before.is_some() && fingerprint(&worker_path) != before;
+            // The receiver is gone if the file already timed out
+            let _ = sender.send((result, changed, findings));
+        });
+    if let Err(e) = spawned {
+        return (FileStatus::Failed(ValidationError::FileRead(e)), Vec::new());
+    }
+
+    let received = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).map_err(|e| match e {
+            mpsc::RecvTimeoutError::Timeout => Some(timeout),
+            mpsc::RecvTimeoutError::Disconnected => None,
+        }),
+        None => receiver.recv().map_err(|_| None),
+    };
+    let (received, findings) = match received {
+        Ok((result, changed, findings)) => (Ok((result, changed)), findings),
+        Err(e) => (Err(e), Vec::new()),
+    };
+    let status = match received {
+        Ok((_, true)) => FileStatus::Changed,
+        Ok((Ok(Ok(true)), false)) => FileStatus::Valid,
+        Ok((Ok(Ok(false)), false)) => FileStatus::Empty,
+        Ok((Ok(Err(ValidationError::FileRead(e))), false))
+            if readable
+                && matches!(
+                    e.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied
+                ) =>
+        {
+            FileStatus::Vanished(e)
+        }
+        Ok((Ok(Err(e)), false)) => FileStatus::Failed(e),
+        Ok((Err(payload), false)) => FileStatus::Panicked(panic_message(payload.as_ref())),
+        Err(Some(timeout)) => FileStatus::TimedOut(timeout),
+        Err(None) => FileStatus::Panicked("validation thread exited unexpectedly".to_string()),
+    };
+    (status, findings)
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_reports_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = dir.path().join("valid.json");
+        let invalid = dir.path().join("invalid.json");
+        std::fs::write(&valid, r#"{"name": "a"}"#).unwrap();
+        std::fs::write(&invalid, r#"{"name": 1}"#).unwrap();
+        let missing = dir.path().join("missing.json");
+
+        let validator =
+            Arc::new(Validator::new(r#"{"properties": {"name": {"type": "string"}}}"#).unwrap());
+        let reports = validate_files(
+            &validator,
+            &[&valid, &invalid, &missing],
+            &BatchOptions {
+                timeout: Some(Duration::from_secs(10)),
+                ..BatchOptions::default()
+            },
+        );
+
+        assert!(matches!(reports[0].status, FileStatus::Valid));
+        assert!(matches!(
+            reports[1].status,
+            FileStatus::Failed(ValidationError::ValidationFailed(_))
+        ));
+        assert!(matches!(
+            reports[2].status,
+            FileStatus::Failed(ValidationError::FileRead(_))
+        ));
+        assert!(reports.iter().all(|report| !report.status.is_abnormal()));
+    }
+
+    #[test]
+    fn test_batch_deadline() {
+        let validator = Arc::new(Validator::new("{}").unwrap());
+        let options = BatchOptions {
+            deadline: Some(Duration::ZERO),
+            ..BatchOptions::default()
+        };
+        let reports = validate_files(&validator, &["a.json", "b.json"], &options);
+        assert!(reports
+            .iter()
+            .all(|report| matches!(report.status, FileStatus::Skipped)));
+    }
+
+    #[test]
+    fn test_batch_races() {
+        let dir = tempfile::tempdir().unwrap();
+        let changing = dir.path().join("changing.json");
+        let vanishing = dir.path().join("vanishing.json");
+        std::fs::write(&changing, r#"{"name": "a"}"#).unwrap();
+        std::fs::write(&vanishing, r#"{"name": "b"}"#).unwrap();
+
+        // Validating the first file rewrites it and deletes the second
+        let (rewrite, remove) = (changing.clone(), vanishing.clone());
+        let validator = Validator::builder()
+            .with_keyword("x-touch", move |_, _| {
+                let _ = std::fs::write(&rewrite, r#"{"name": "a", "more": true}"#);
+                let _ = std::fs::remove_file(&remove);
+                Ok(())
+            })
+            .build_str(r#"{"x-touch": true}"#)
+            .map(Arc::new)
+            .unwrap();
+
+        let files = [&changing, &vanishing];
+        let reports = validate_files(&validator, &files, &BatchOptions::default());
+        assert!(matches!(reports[0].status, FileStatus::Changed));
+        assert!(matches!(reports[1].status, FileStatus::Vanished(_)));
+        assert!(reports.iter().all(|report| report.status.is_race()));
+
+        std::fs::write(&changing, r#"{"name": "a"}"#).unwrap();
+        std::fs::write(&vanishing, r#"{"name": "b"}"#).unwrap();
+        let options = BatchOptions {
+            fail_on_race: true,
+            ..BatchOptions::default()
+        };
+        let reports = validate_files(&validator, &files, &options);
+        assert!(reports.iter().all(|report| matches!(
+            report.status,
+            FileStatus::Failed(ValidationError::FileRead(_))
+        )));
+    }
+
+    #[test]
+    fn test_map_ordered() {
+        let items: Vec<u64> = (0..64).collect();
+        for jobs in [0, 1, 4] {
+            let mut results = Vec::new();
+            map_ordered(
+                &items,
+                jobs,
+                |item| {
+                    // Later items finish first
+                    thread::sleep(Duration::from_micros(64 - item));
+                    item * 2
+                },
+                |index, result| results.push((index, result)),
+            );
+            let expected: Vec<(usize, u64)> = (0..64).map(|i| (i, i as u64 * 2)).collect();
+            assert_eq!(results, expected);
+        }
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = panic::catch_unwind(|| panic!("boom")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "boom");
+    }
+}
@@ -0,0 +1,185 @@
+//! Validating BSON documents against JSON Schemas.
+//!
+//! MongoDB stores and dumps documents as BSON. [`parse_bson`] converts a
+//! document into relaxed MongoDB Extended JSON, the form `mongoexport`
+//! writes, so schemas written for exported JSON apply unchanged: strings,
+//! booleans, doubles and 32- and 64-bit integers become plain JSON values,
+//! while types JSON lacks become objects such as `{"$oid": "..."}` for an
+//! ObjectId or `{"$date": "2024-05-01T12:00:00Z"}` for a date.
+//!
+//! `mongodump` writes a collection as its documents one after another;
+//! [`parse_bson_dump`] splits such a file.
+//!
+//! Available with the `bson` feature.
+
+use crate::ValidationError;
+use bson::raw::{RawBsonRef, RawDocument};
+use bson::{Bson, Document};
+use serde_json::Value;
+use std::path::Path;
+
+/// Deepest nesting accepted, the same as the JSON and YAML parsers.
+const MAX_DEPTH: usize = 128;
+
+/// Parse one BSON document into its relaxed Extended JSON value.
+///
+/// # Examples
+///
+/// ```rust
+/// use bson::doc;
+/// use serde_json::json;
+/// use validate_json_schema::bson::parse_bson;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let document = doc! {"name": "Ada", "logins": 3_i64};
+///
+/// assert_eq!(
+///     parse_bson(&bson::to_vec(&document)?)?,
+///     json!({"name": "Ada", "logins": 3})
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the bytes are not exactly one well-formed BSON
+/// document or it is nested more than 128 levels deep.
+pub fn parse_bson(bytes: &[u8]) -> Result<Value, ValidationError> {
+    let document = RawDocument::from_bytes(bytes).map_err(|e| invalid(e.to_string()))?;
+    to_json(document)
+}
+
+/// Parse the BSON documents stored one after another in `bytes`, as
+/// `mongodump` writes a collection.
+///
+/// # Errors
+///
+/// Returns an error if any document is malformed, as for [`parse_bson`], or
+/// the bytes end in the middle of a document.
+pub fn parse_bson_dump(bytes: &[u8]) -> Result<Vec<Value>, ValidationError> {
+    let mut documents = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let length = rest
+            .get(..4)
+            .map(|prefix| i32::from_le_bytes(prefix.try_into().expect("4 bytes")))
+            .and_then(|length| usize::try_from(length).ok())
+            .filter(|length| (5..=rest.len()).contains(length))
+            .ok_or_else(|| invalid(format!("document {} is truncated", documents.len() + 1)))?;
+        let (document, remaining) = rest.split_at(length);
+        documents.push(parse_bson(document).map_err(|e| match e {
+            ValidationError::BsonParse(message) => {
+                invalid(format!("document {}: {}", documents.len() + 1, message))
+            }
+            e => e,
+        })?);
+        rest = remaining;
+    }
+    Ok(documents)
+}
+
+/// Whether `path` has the `.bson` extension.
+pub(crate) fn is_bson_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("bson"))
+}
+
+fn invalid(message: String) -> ValidationError {
+    ValidationError::BsonParse(message)
+}
+
+/// Convert `document` after checking its nesting, which the conversion
+/// follows recursively.
+fn to_json(document: &RawDocument) -> Result<Value, ValidationError> {
+    let mut pending = vec![(document, 1)];
+    while let Some((document, depth)) = pending.pop() {
+        if depth > MAX_DEPTH {
+            return Err(ValidationError::LimitExceeded(format!(
+                "document is nested more than {} levels deep",
+                MAX_DEPTH
+            )));
+        }
+        for element in document {
+            let (_, value) = element.map_err(|e| invalid(e.to_string()))?;
+            match value {
+                RawBsonRef::Document(nested) => pending.push((nested, depth + 1)),
+                RawBsonRef::Array(items) => {
+                    let nested = RawDocument::from_bytes(items.as_bytes())
+                        .map_err(|e| invalid(e.to_string()))?;
+                    pending.push((nested, depth + 1));
+                }
+                RawBsonRef::JavaScriptCodeWithScope(code) => {
+                    pending.push((code.scope, depth + 1));
+                }
+                _ => {}
+            }
+        }
+    }
+    let document = Document::try_from(document).map_err(|e| invalid(e.to_string()))?;
+    Ok(Bson::Document(document).into_relaxed_extjson())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::oid::ObjectId;
+    use bson::{doc, DateTime};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_bson() {
+        let id = ObjectId::parse_str("65f1c0ffee0000000000beef").unwrap();
+        let document = doc! {
+            "_id": id,
+            "name": "sensor",
+            "readings": [1_i32, 2.5_f64, Bson::Null],
+            "seen": DateTime::from_millis(0),
+            "meta": {"tags": ["a"]}
+        };
+        assert_eq!(
+            parse_bson(&bson::to_vec(&document).unwrap()).unwrap(),
+            json!({
+                "_id": {"$oid": "65f1c0ffee0000000000beef"},
+                "name": "sensor",
+                "readings": [1, 2.5, null],
+                "seen": {"$date": "1970-01-01T00:00:00Z"},
+                "meta": {"tags": ["a"]}
+            })
+        );
+
+        let mut bytes = bson::to_vec(&document).unwrap();
+        bytes.push(0);
+        assert!(parse_bson(&bytes).is_err());
+        assert!(parse_bson(&[5, 0, 0]).is_err());
+
+        let mut deep = doc! {"leaf": true};
+        for _ in 0..200 {
+            deep = doc! {"nested": deep};
+        }
+        assert!(matches!(
+            parse_bson(&bson::to_vec(&deep).unwrap()),
+            Err(ValidationError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bson_dump() {
+        let mut dump = Vec::new();
+        for name in ["a", "b", "c"] {
+            dump.extend(bson::to_vec(&doc! {"name": name}).unwrap());
+        }
+        let documents = parse_bson_dump(&dump).unwrap();
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[2], json!({"name": "c"}));
+        assert_eq!(parse_bson_dump(&[]).unwrap(), Vec::<Value>::new());
+
+        dump.truncate(dump.len() - 1);
+        let error = parse_bson_dump(&dump).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse BSON: document 3 is truncated"
+        );
+    }
+}
@@ -0,0 +1,1691 @@
+//! Configurable construction of validators.
+
+use crate::compiled;
+use crate::error_message::ErrorMessages;
+use crate::ignore::{IgnoreRule, IgnoreRules};
+use crate::loader;
+use crate::numbers;
+use crate::registry::{self, RegistryClient};
+use crate::resolve::{self, CachePolicy, RefPrefetch, Resolver};
+use crate::schemas::SchemaRegistry;
+use crate::yaml;
+use crate::{
+    is_url, refs, vocabulary, CacheStore, Draft, EmptyDocuments, HttpConfig, HttpLoader,
+    SchemaLoader, ValidationError, Validator, YamlSchema, YamlTags, DEFAULT_BASE_URI,
+};
+use jsonschema::paths::{LazyLocation, Location};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Limits on the documents a validator accepts.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Limits {
+    /// Maximum size of document content, in bytes.
+    pub(crate) max_document_size: Option<usize>,
+    /// Maximum nesting depth of arrays and objects in a document.
+    pub(crate) max_depth: Option<usize>,
+    /// Bounds on the aliases of YAML documents.
+    pub(crate) aliases: yaml::AliasLimits,
+}
+
+/// A check for a custom `format`, returning whether the string matches it.
+type FormatCheck = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// A check for a custom keyword, given the instance and the keyword's value.
+type KeywordCheck = Arc<dyn Fn(&Value, &Value) -> Result<(), String> + Send + Sync>;
+
+/// Builds a [`Validator`] with non-default compilation options.
+///
+/// The `Validator::from_*` constructors use the defaults: the draft declared
+/// by `$schema` (Draft 7 if none), `format` handled as that draft specifies,
+/// remote documents
+/// fetched over HTTP and cached in the user's cache directory, and no limits.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::{Draft, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::builder()
+///     .draft(Draft::Draft202012)
+///     .validate_formats(false)
+///     .max_depth(32)
+///     .build_str(r#"{"type": "string", "format": "email"}"#)?;
+///
+/// validator.validate_json(r#""not an email""#)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ValidatorBuilder {
+    draft: Option<Draft>,
+    validate_formats: Option<bool>,
+    formats: Vec<(String, FormatCheck)>,
+    keywords: Vec<(String, KeywordCheck)>,
+    strict_vocabularies: bool,
+    exact_numbers: bool,
+    failure_groups: Option<Option<usize>>,
+    max_failures: Option<usize>,
+    warnings: bool,
+    warnings_as_errors: bool,
+    ignore: IgnoreRules,
+    loader: Option<Arc<dyn SchemaLoader>>,
+    http_config: Option<HttpConfig>,
+    cache: CachePolicy,
+    registry: Option<RegistryClient>,
+    schemas: SchemaRegistry,
+    limits: Limits,
+    allow_comments: bool,
+    merge_keys: bool,
+    allow_aliases: bool,
+    yaml_tags: YamlTags,
+    tag_handlers: Vec<(String, yaml::TagHandler)>,
+    yaml_schema: YamlSchema,
+    strict_keys: bool,
+    empty_documents: EmptyDocuments,
+    prefetch: RefPrefetch,
+}
+
+impl Default for ValidatorBuilder {
+    fn default() -> Self {
+        Self {
+            draft: None,
+            validate_formats: None,
+            formats: Vec::new(),
+            keywords: Vec::new(),
+            strict_vocabularies: false,
+            exact_numbers: false,
+            failure_groups: None,
+            max_failures: None,
+            warnings: false,
+            warnings_as_errors: false,
+            ignore: IgnoreRules::default(),
+            loader: None,
+            http_config: None,
+            cache: CachePolicy::Default,
+            registry: None,
+            schemas: SchemaRegistry::new(),
+            limits: Limits::default(),
+            allow_comments: false,
+            merge_keys: false,
+            allow_aliases: true,
+            yaml_tags: YamlTags::default(),
+            tag_handlers: Vec::new(),
+            yaml_schema: YamlSchema::default(),
+            strict_keys: false,
+            empty_documents: EmptyDocuments::default(),
+            prefetch: RefPrefetch::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ValidatorBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidatorBuilder")
+            .field("draft", &self.draft)
+            .field("validate_formats", &self.validate_formats)
+            .field(
+                "formats",
+                &self
+                    .formats
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("strict_vocabularies", &self.strict_vocabularies)
+            .field("exact_numbers", &self.exact_numbers)
+            .field("registry", &self.registry)
+            .field("schemas", &self.schemas.ids().collect::<Vec<_>>())
+            .field("limits", &self.limits)
+            .field("allow_comments", &self.allow_comments)
+            .field("merge_keys", &self.merge_keys)
+            .field("allow_aliases", &self.allow_aliases)
+            .field("yaml_tags", &self.yaml_tags)
+            .field(
+                "tag_handlers",
+                &self
+                    .tag_handlers
+                    .iter()
+                    .map(|(tag, _)| tag)
+                    .collect::<Vec<_>>(),
+            )
+            .field("yaml_schema", &self.yaml_schema)
+            .field("strict_keys", &self.strict_keys)
+            .field("empty_documents", &self.empty_documents)
+            .field("failure_groups", &self.failure_groups)
+            .field("max_failures", &self.max_failures)
+            .field("warnings", &self.warnings)
+            .field("warnings_as_errors", &self.warnings_as_errors)
+            .field("ignore", &self.ignore)
+            .field("prefetch", &self.prefetch)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ValidatorBuilder {
+    /// Create a builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile schemas as `draft`, ignoring their `$schema` keyword.
+    pub fn draft(mut self, draft: Draft) -> Self {
+        self.draft = Some(draft);
+        self
+    }
+
+    /// Whether `format` is asserted, so that e.g. `"format": "email"` rejects
+    /// strings that are not email addresses, or only an annotation.
+    ///
+    /// By default this follows the draft: Draft 4, 6 and 7 schemas assert
+    /// formats, while 2019-09 and 2020-12 schemas treat them as annotations,
+    /// as those specifications require.
+    pub fn validate_formats(mut self, validate: bool) -> Self {
+        self.validate_formats = Some(validate);
+        self
+    }
+
+    /// Check strings with `"format": name` using `check`, e.g. for internal
+    /// identifiers such as ticket IDs or ARNs. A check registered under the
+    /// name of a built-in format replaces it.
+    ///
+    /// Custom formats are asserted under the same rules as built-in ones, so
+    /// 2019-09 and 2020-12 schemas also need
+    /// [`validate_formats(true)`](Self::validate_formats).
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::builder()
+    ///     .with_format("ticket", |s| {
+    ///         s.strip_prefix("OPS-")
+    ///             .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    ///     })
+    ///     .build_str(r#"{"type": "string", "format": "ticket"}"#)?;
+    ///
+    /// assert!(validator.is_valid_json(r#""OPS-42""#));
+    /// assert!(!validator.is_valid_json(r#""42""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_format<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.formats.retain(|(existing, _)| *existing != name);
+        self.formats.push((name, Arc::new(check)));
+        self
+    }
+
+    /// Validate instances against the custom keyword `name` with `check`,
+    /// which receives the instance and the keyword's value in the schema and
+    /// returns an error message if the instance does not satisfy it.
+    ///
+    /// The keyword applies wherever it appears in the schema, alongside the
+    /// standard keywords; registering a standard keyword's name replaces it.
+    /// Prefixing custom keywords with `x-` keeps them out of unknown keyword
+    /// warnings.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::builder()
+    ///     .with_keyword("x-lowercase", |instance, enabled| match instance.as_str() {
+    ///         Some(s) if enabled == true && s.chars().any(char::is_uppercase) => {
+    ///             Err(format!("'{}' is not lowercase", s))
+    ///         }
+    ///         _ => Ok(()),
+    ///     })
+    ///     .build_str(r#"{"properties": {"name": {"x-lowercase": true}}}"#)?;
+    ///
+    /// assert!(validator.is_valid_yaml("name: web"));
+    /// assert!(!validator.is_valid_yaml("name: Web"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_keyword<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&Value, &Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.keywords.retain(|(existing, _)| *existing != name);
+        self.keywords.push((name, Arc::new(check)));
+        self
+    }
+
+    /// Fail to build if the schema's meta-schema requires vocabularies the
+    /// validator does not implement, as [`crate::check_vocabularies`] does.
+    pub fn strict_vocabularies(mut self, strict: bool) -> Self {
+        self.strict_vocabularies = strict;
+        self
+    }
+
+    /// Check `multipleOf`, `minimum`, `maximum`, `exclusiveMinimum` and
+    /// `exclusiveMaximum` on the exact decimal values of numbers if `exact`
+    /// is `true`, instead of on their nearest `f64`, which is wrong for
+    /// integers beyond 2^53 and decimals with more than 17 significant
+    /// digits.
+    ///
+    /// Integers up to 64 bits are parsed exactly either way; to keep larger
+    /// numbers and every decimal digit of JSON documents, build with the
+    /// `arbitrary-precision` feature.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = r#"{"multipleOf": 2}"#;
+    /// let odd = "9007199254740993";
+    ///
+    /// assert!(Validator::new(schema)?.validate_json(odd).is_ok());
+    /// let exact = Validator::builder().exact_numbers(true).build_str(schema)?;
+    /// assert!(exact.validate_json(odd).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exact_numbers(mut self, exact: bool) -> Self {
+        self.exact_numbers = exact;
+        self
+    }
+
+    /// Report the failures of each value together in the errors of the
+    /// `validate_*` methods if `group` is `true`, once per distinct message,
+    /// as [`group_failures`](crate::failure::group_failures) does. By
+    /// default each failure is reported on its own, however often it
+    /// repeats.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = r#"{"properties": {"port": {"allOf": [
+    ///     {"type": "integer"}, {"type": "integer"}, {"minimum": 1}
+    /// ]}}}"#;
+    /// let grouped = Validator::builder().group_failures(true).build_str(schema)?;
+    ///
+    /// assert_eq!(
+    ///     grouped.validate_json(r#"{"port": "http"}"#).unwrap_err().to_string(),
+    ///     "Validation failed: line 1 column 10: /port: \"http\" is not of type \"integer\""
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn group_failures(mut self, group: bool) -> Self {
+        self.failure_groups = match (group, self.failure_groups) {
+            (true, Some(max)) => Some(max),
+            (true, None) => Some(None),
+            (false, _) => None,
+        };
+        self
+    }
+
+    /// Group failures by value as [`group_failures`](Self::group_failures)
+    /// does, reporting at most `max` distinct failures per value and how
+    /// many more there are.
+    pub fn max_failures_per_path(mut self, max: usize) -> Self {
+        self.failure_groups = Some(Some(max));
+        self
+    }
+
+    /// Stop validating a document once `max` failures are found, so giant
+    /// documents with many mistakes report at most `max` of them and skip
+    /// the rest of the work. Applies to the `validate_*` methods and
+    /// [`validate_detailed`](Validator::validate_detailed); by default every
+    /// failure is reported. A `max` of 0 is taken as 1, since a document
+    /// that fails must report at least one failure.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::builder()
+    ///     .max_failures(2)
+    ///     .build_str(r#"{"items": {"type": "integer"}}"#)?;
+    ///
+    /// let failures = validator.validate_detailed(&serde_json::json!(["a", "b", "c"]));
+    /// assert_eq!(failures.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_failures(mut self, max: usize) -> Self {
+        self.max_failures = Some(max.max(1));
+        self
+    }
+
+    /// Stop validating a document at its first failure if `fail_fast` is
+    /// `true`, as [`max_failures(1)`](Self::max_failures) does.
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.max_failures = match (fail_fast, self.max_failures) {
+            (true, _) => Some(1),
+            (false, Some(1)) => None,
+            (false, max) => max,
+        };
+        self
+    }
+
+    /// Also check documents for warnings: values the schema marks
+    /// `"deprecated": true`, and strings that do not match their `format`
+    /// where formats are not asserted. Warnings do not fail validation;
+    /// [`Validator::warnings`] lists them. Checking them compiles the schema
+    /// a second time, so it is off by default.
+    ///
+    /// Warnings under `anyOf`, `oneOf` and `not` are not reported.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::builder().warnings(true).build_str(
+    ///     r#"{"properties": {"host": {"deprecated": true}, "port": {"type": "integer"}}}"#,
+    /// )?;
+    ///
+    /// let document = serde_json::json!({"host": "db", "port": 5432});
+    /// assert!(validator.is_valid_value(&document));
+    /// let warnings = validator.warnings(&document);
+    /// assert_eq!(warnings[0].to_string(), "/host: \"db\" is deprecated");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warnings(mut self, check: bool) -> Self {
+        self.warnings = check;
+        self
+    }
+
+    /// Report the [`warnings`](Self::warnings) of documents as failures if
+    /// `promote` is `true`, so that they fail validation, e.g. in strict CI.
+    pub fn warnings_as_errors(mut self, promote: bool) -> Self {
+        self.warnings_as_errors = promote;
+        self
+    }
+
+    /// Leave the failures `rule` matches out of everything the validator
+    /// reports, as an exception to the schema.
+    ///
+    /// ```rust
+    /// use validate_json_schema::{IgnoreRule, Validator};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::builder()
+    ///     .ignore(IgnoreRule {
+    ///         pointer: Some("/metadata/annotations".to_string()),
+    ///         keyword: Some("additionalProperties".to_string()),
+    ///         ..Default::default()
+    ///     })
+    ///     .build_str(r#"{"properties": {"metadata": {"properties": {
+    ///         "annotations": {"additionalProperties": false}
+    ///     }}}}"#)?;
+    ///
+    /// assert!(validator.is_valid_yaml("metadata:\n  annotations:\n    team: web"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ignore(mut self, rule: IgnoreRule) -> Self {
+        self.ignore.push(rule);
+        self
+    }
+
+    /// Leave out the failures any of `rules` matches, as
+    /// [`ignore`](Self::ignore) does for each.
+    pub fn ignore_rules(mut self, rules: IgnoreRules) -> Self {
+        for rule in rules.rules() {
+            self.ignore.push(rule.clone());
+        }
+        self
+    }
+
+    /// Fetch remote schemas and references with `loader` instead of HTTP.
+    pub fn loader<L: SchemaLoader + 'static>(mut self, loader: L) -> Self {
+        self.loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Fetch remote schemas with these HTTP settings instead of the
+    /// process-wide ones. Ignored if a [`loader`](Self::loader) is set.
+    pub fn http_config(mut self, config: HttpConfig) -> Self {
+        self.http_config = Some(config);
+        self
+    }
+
+    /// Give up on each remote schema request after `timeout`.
+    ///
+    /// The other settings are kept from [`http_config`](Self::http_config),
+    /// or from the process-wide HTTP settings if none was given.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        let config = self
+            .http_config
+            .take()
+            .unwrap_or_else(loader::default_http_config);
+        self.http_config = Some(config.timeout(timeout));
+        self
+    }
+
+    /// Identify the application in the `User-Agent` of remote schema
+    /// requests, e.g. `validate-json-schema/0.1.0 my-app/2.1`.
+    ///
+    /// The other settings are kept from [`http_config`](Self::http_config),
+    /// or from the process-wide HTTP settings if none was given.
+    pub fn user_agent_suffix(mut self, suffix: impl Into<String>) -> Self {
+        let config = self
+            .http_config
+            .take()
+            .unwrap_or_else(loader::default_http_config);
+        self.http_config = Some(config.user_agent_suffix(suffix));
+        self
+    }
+
+    /// Cache remote schemas in `cache` instead of the user's cache directory.
+    pub fn cache(mut self, cache: Arc<dyn CacheStore>) -> Self {
+        self.cache = CachePolicy::Store(cache);
+        self
+    }
+
+    /// Fetch remote schemas on every build without caching them.
+    pub fn no_cache(mut self) -> Self {
+        self.cache = CachePolicy::Bypass;
+        self
+    }
+
+    /// Resolve `registry://` schema inputs against `registry` instead of the
+    /// one named by the `SCHEMA_REGISTRY_URL` environment variable.
+    pub fn registry(mut self, registry: RegistryClient) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Resolve references to the schemas in `schemas` in memory, and build
+    /// them by identifier with [`build_url`](Self::build_url) without fetching.
+    pub fn schemas(mut self, schemas: SchemaRegistry) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    /// Reject documents larger than `bytes` before parsing them.
+    pub fn max_document_size(mut self, bytes: usize) -> Self {
+        self.limits.max_document_size = Some(bytes);
+        self
+    }
+
+    /// Reject documents with arrays and objects nested deeper than `depth`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.limits.max_depth = Some(depth);
+        self
+    }
+
+    /// Reject YAML documents with more than `count` aliases (`*name`).
+    pub fn max_aliases(mut self, count: usize) -> Self {
+        self.limits.aliases.max_aliases = Some(count);
+        self
+    }
+
+    /// Reject YAML documents whose aliases expand to more than `nodes` nodes
+    /// beyond those written out in the document. The default of 10,000
+    /// lets alias bombs fail before they exhaust memory, and before
+    /// `serde_yaml` stops them with a less specific "repetition limit
+    /// exceeded" error.
+    pub fn max_alias_expansion(mut self, nodes: usize) -> Self {
+        self.limits.aliases.max_expansion = nodes;
+        self
+    }
+
+    /// Accept `//` and `/* */` comments and trailing commas in JSON
+    /// documents, as in VS Code settings and `tsconfig.json` (JSONC). Other
+    /// JSON5 syntax is still rejected.
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Expand YAML merge keys (`<<: *base`) into the mappings that hold them
+    /// before validating, as most YAML 1.1 tools do. By default `<<` is kept
+    /// as an ordinary key, which the schema sees too.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = r#"{"properties": {"web": {"required": ["image"]}}}"#;
+    /// let content = "base: &base\n  image: app\nweb:\n  <<: *base\n";
+    ///
+    /// assert!(Validator::new(schema)?.validate_yaml(content).is_err());
+    /// let merging = Validator::builder().merge_keys(true).build_str(schema)?;
+    /// assert!(merging.validate_yaml(content).is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge_keys(mut self, merge: bool) -> Self {
+        self.merge_keys = merge;
+        self
+    }
+
+    /// Reject YAML documents that define anchors (`&name`) or use aliases
+    /// (`*name`), with [`ValidationError::YamlAlias`], if `allow` is
+    /// `false`. Aliases are allowed and expanded by default.
+    pub fn allow_aliases(mut self, allow: bool) -> Self {
+        self.allow_aliases = allow;
+        self
+    }
+
+    /// Choose how YAML nodes with tags outside the core schema, such as
+    /// `!!binary`, are validated. By default they fail ([`YamlTags::Error`]).
+    ///
+    /// ```rust
+    /// use validate_json_schema::{Validator, YamlTags};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = r#"{"properties": {"bucket": {"type": "string"}}}"#;
+    /// let content = "bucket: !Ref LogBucket\n";
+    ///
+    /// assert!(Validator::new(schema)?.validate_yaml(content).is_err());
+    /// let stripping = Validator::builder().yaml_tags(YamlTags::Strip).build_str(schema)?;
+    /// assert!(stripping.validate_yaml(content).is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn yaml_tags(mut self, tags: YamlTags) -> Self {
+        self.yaml_tags = tags;
+        self
+    }
+
+    /// Validate YAML nodes tagged `tag`, written as in the document (e.g.
+    /// `!Ref` or `!!binary`), as the value `convert` returns for their
+    /// untagged value; an `Err` fails the document with
+    /// [`ValidationError::YamlTag`]. Nested tagged nodes are converted first.
+    /// Registering a tag again replaces its handler. Tags without a handler
+    /// are treated as [`yaml_tags`](Self::yaml_tags) says.
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // GitLab CI's `!reference [.setup, script]`
+    /// let validator = Validator::builder()
+    ///     .with_yaml_tag("!reference", |value| Ok(json!({ "reference": value })))
+    ///     .build_str(r#"{"properties": {"script": {"type": "object"}}}"#)?;
+    ///
+    /// assert!(validator.validate_yaml("script: !reference [.setup, script]\n").is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_yaml_tag<F>(mut self, tag: impl Into<String>, convert: F) -> Self
+    where
+        F: Fn(&Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        let tag = tag.into();
+        self.tag_handlers.retain(|(existing, _)| *existing != tag);
+        self.tag_handlers.push((tag, Arc::new(convert)));
+        self
+    }
+
+    /// Convert CloudFormation's short-form tags (`!Ref`, `!GetAtt`, `!Sub`,
+    /// `!Join` and the other intrinsic functions) into the long form JSON
+    /// templates use, so YAML templates validate against the same schemas:
+    /// `!GetAtt Bucket.Arn` is validated as
+    /// `{"Fn::GetAtt": ["Bucket", "Arn"]}`.
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = r#"{"properties": {"Value": {"required": ["Fn::Join"]}}}"#;
+    /// let template = "Value: !Join ['', [!Ref Prefix, '-logs']]\n";
+    ///
+    /// let validator = Validator::builder().cloudformation_tags().build_str(schema)?;
+    /// assert!(validator.validate_yaml(template).is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cloudformation_tags(self) -> Self {
+        yaml::cloudformation_tags()
+            .into_iter()
+            .fold(self, |builder, (tag, handler)| {
+                builder.with_yaml_tag(tag, move |value| handler(value))
+            })
+    }
+
+    /// Choose how unquoted YAML scalars are typed. By default they follow
+    /// the YAML 1.2 core schema ([`YamlSchema::Core`]), so `country: NO` is
+    /// the string `"NO"`; [`YamlSchema::Yaml11`] reads it as YAML 1.1 tools
+    /// do, as `false`.
+    ///
+    /// ```rust
+    /// use validate_json_schema::{Validator, YamlSchema};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = r#"{"properties": {"country": {"type": "string"}}}"#;
+    /// let content = "country: NO\n";
+    ///
+    /// assert!(Validator::new(schema)?.validate_yaml(content).is_ok());
+    /// let yaml11 = Validator::builder().yaml_schema(YamlSchema::Yaml11).build_str(schema)?;
+    /// assert!(yaml11.validate_yaml(content).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn yaml_schema(mut self, schema: YamlSchema) -> Self {
+        self.yaml_schema = schema;
+        self
+    }
+
+    /// Reject JSON and YAML documents that repeat a key within an object or
+    /// mapping, with [`ValidationError::DuplicateKey`] naming the key and
+    /// its line, if `strict` is `true`. By default the last entry wins, as
+    /// the parsers read them.
+    pub fn strict_keys(mut self, strict: bool) -> Self {
+        self.strict_keys = strict;
+        self
+    }
+
+    /// Choose how documents with no content are validated: files that are
+    /// empty or hold only whitespace, comments and, in YAML, directives and
+    /// document markers. By default they are validated as `null`
+    /// ([`EmptyDocuments::Null`]).
+    ///
+    /// ```rust
+    /// use validate_json_schema::{EmptyDocuments, ValidationError, Validator};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::builder()
+    ///     .empty_documents(EmptyDocuments::Error)
+    ///     .build_str(r#"{"type": "object"}"#)?;
+    ///
+    /// assert!(matches!(
+    ///     validator.validate_yaml("# nothing here yet\n"),
+    ///     Err(ValidationError::EmptyDocument)
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn empty_documents(mut self, policy: EmptyDocuments) -> Self {
+        self.empty_documents = policy;
+        self
+    }
+
+    /// Choose when remote documents referenced by the schema are fetched:
+    /// while building, failing the build if one is unreachable
+    /// ([`RefPrefetch::Eager`]), or on first use ([`RefPrefetch::Lazy`]).
+    /// By default they are fetched while building and an unreachable one
+    /// fails only the validations that reach it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use validate_json_schema::{RefPrefetch, Validator};
+    ///
+    /// let schema = json!({"properties": {
+    ///     "port": {"$ref": "https://schemas.invalid/port.json"}
+    /// }});
+    ///
+    /// // Nothing is fetched until a document has a port
+    /// let lazy = Validator::builder()
+    ///     .prefetch(RefPrefetch::Lazy)
+    ///     .no_cache()
+    ///     .build(&schema)
+    ///     .unwrap();
+    /// assert!(lazy.is_valid_value(&json!({"name": "web"})));
+    /// assert!(!lazy.is_valid_value(&json!({"port": 8080})));
+    ///
+    /// let eager = Validator::builder().prefetch(RefPrefetch::Eager).no_cache();
+    /// assert!(eager.build(&schema).is_err());
+    /// ```
+    pub fn prefetch(mut self, prefetch: RefPrefetch) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Build a validator for an already parsed schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value is not a valid JSON Schema.
+    pub fn build(&self, schema: &Value) -> Result<Validator, ValidationError> {
+        self.compile(schema, None, self.resolver()?)
+    }
+
+    /// Build a validator for a JSON schema string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema is invalid JSON or not a valid JSON Schema.
+    pub fn build_str(&self, schema: &str) -> Result<Validator, ValidationError> {
+        let schema: Value = serde_json::from_str(schema)?;
+        self.build(&schema)
+    }
+
+    /// Build a validator for a local schema file.
+    ///
+    /// Relative references in the schema resolve against the file's location.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or contains invalid JSON Schema.
+    pub fn build_file<P: AsRef<Path>>(&self, path: P) -> Result<Validator, ValidationError> {
+        let path = path.as_ref();
+        let schema: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let base_uri = fs::canonicalize(path)
+            .ok()
+            .and_then(|path| Url::from_file_path(path).ok())
+            .map(String::from);
+        self.compile(&schema, base_uri, self.resolver()?)
+    }
+
+    /// Build a validator from a compiled schema artifact written by
+    /// [`compile_schema`](crate::compile_schema).
+    ///
+    /// The artifact's draft is used unless [`draft`](Self::draft) was set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not an artifact of
+    /// this version of the format, or does not match its digest.
+    pub fn build_compiled<P: AsRef<Path>>(&self, path: P) -> Result<Validator, ValidationError> {
+        let (schema, draft) = compiled::read_compiled(path.as_ref())?;
+        let mut builder = self.clone();
+        builder.draft = builder.draft.or(Some(draft));
+        builder.compile(&schema, None, self.resolver()?)
+    }
+
+    /// Build a validator for a remote schema URL.
+    ///
+    /// A schema registered under `url` with [`schemas`](Self::schemas) is used
+    /// without fetching it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid, the request or cache access
+    /// fails, or the response is not valid JSON Schema.
+    pub fn build_url(&self, url: &str) -> Result<Validator, ValidationError> {
+        let resolver = self.resolver()?;
+        if let Some(schema) = self.schemas.get(url) {
+            return self.compile(schema, Some(url.to_string()), resolver);
+        }
+        let schema: Value = serde_json::from_str(&resolver.fetch(url)?)?;
+        self.compile(&schema, Some(url.to_string()), resolver)
+    }
+
+    /// Build a validator for a schema registry subject, given as
+    /// `registry://<subject>/<version>` or `registry://<subject>` for the
+    /// latest version.
+    ///
+    /// Pinned versions are cached like remote schemas; the latest version is
+    /// fetched on every build.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no registry is configured, the input is malformed,
+    /// the request or cache access fails, or the response is not a JSON Schema.
+    pub fn build_registry(&self, input: &str) -> Result<Validator, ValidationError> {
+        let resolver = self.resolver()?;
+        let (url, schema) = registry::load_input(input, self.registry.as_ref(), &resolver)?;
+        self.compile(&schema, Some(url), resolver)
+    }
+
+    /// Build a validator from a local file path, a remote URL or a
+    /// `registry://` subject. Files with the `.vjsc` extension are read as
+    /// compiled artifacts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema cannot be loaded or is invalid.
+    pub fn build_input(&self, input: &str) -> Result<Validator, ValidationError> {
+        if input.starts_with(registry::REGISTRY_SCHEME) {
+            self.build_registry(input)
+        } else if is_url(input) {
+            self.build_url(input)
+        } else if compiled::is_compiled_path(Path::new(input)) {
+            self.build_compiled(input)
+        } else {
+            self.build_file(input)
+        }
+    }
+
+    fn resolver(&self) -> Result<Resolver, ValidationError> {
+        let resolver = Resolver::default()
+            .with_cache(self.cache.clone())
+            .with_fail_fast(self.prefetch == RefPrefetch::Eager);
+        Ok(match (&self.loader, &self.http_config) {
+            (Some(loader), _) => resolver.with_loader(Arc::clone(loader)),
+            (None, Some(config)) => {
+                resolver.with_loader(Arc::new(HttpLoader::with_config(config)?))
+            }
+            (None, None) => resolver,
+        })
+    }
+
+    fn compile(
+        &self,
+        schema: &Value,
+        base_uri: Option<String>,
+        resolver: Resolver,
+    ) -> Result<Validator, ValidationError> {
+        // Compile keywords, and so report failures, in key order even with
+        // serde_json's `preserve_order` feature, which `bson` enables
+        let mut sorted = schema.clone();
+        sorted.sort_all_objects();
+        let schema = &sorted;
+        if self.strict_vocabularies {
+            vocabulary::check_with(schema, &resolver)?;
+        }
+        let draft = self.draft_for(schema, &resolver);
+        let error_messages = ErrorMessages::new(schema).map(Arc::new);
+        let deferred;
+        let schema = if self.prefetch == RefPrefetch::Lazy {
+            let base = base_uri.as_deref().unwrap_or(DEFAULT_BASE_URI);
+            let known = |uri: &str| self.schemas.get(uri).is_some();
+            deferred = resolve::defer_remote_refs(schema, base, draft, &known);
+            &deferred
+        } else {
+            schema
+        };
+        let warnings = if self.warnings || self.warnings_as_errors {
+            Some(self.compile_warnings(schema, base_uri.clone(), draft, resolver.clone())?)
+        } else {
+            None
+        };
+        let mut options = self.engine_options(draft, resolver);
+        if let Some(base_uri) = base_uri {
+            options = options.with_base_uri(base_uri);
+        }
+        let compiled = options
+            .build(schema)
+            .map_err(|e| ValidationError::SchemaCompilation(e.to_string()))?;
+
+        Ok(Validator {
+            schema: compiled,
+            draft,
+            limits: self.limits,
+            allow_comments: self.allow_comments,
+            merge_keys: self.merge_keys,
+            allow_aliases: self.allow_aliases,
+            yaml_tags: self.yaml_tags,
+            tag_handlers: self.tag_handlers.clone(),
+            yaml_schema: self.yaml_schema,
+            strict_keys: self.strict_keys,
+            empty_documents: self.empty_documents,
+            failure_groups: self.failure_groups,
+            max_failures: self.max_failures,
+            warnings,
+            warnings_as_errors: self.warnings_as_errors,
+            ignore: self.ignore.clone(),
+            error_messages,
+        })
+    }
+
+    /// Compile `schema` to check for warnings: with formats asserted, where
+    /// they are not already, and `deprecated` failing every value it applies
+    /// to.
+    fn compile_warnings(
+        &self,
+        schema: &Value,
+        base_uri: Option<String>,
+        draft: Draft,
+        resolver: Resolver,
+    ) -> Result<Warnings, ValidationError> {
+        let formats = !self.validate_formats.unwrap_or(draft < Draft::Draft201909);
+        let builder = self.clone().validate_formats(true).with_keyword(
+            "deprecated",
+            |instance, deprecated| match deprecated {
+                Value::Bool(true) => Err(format!("{} is deprecated", instance)),
+                _ => Ok(()),
+            },
+        );
+        let mut options = builder.engine_options(draft, resolver);
+        if let Some(base_uri) = base_uri {
+            options = options.with_base_uri(base_uri);
+        }
+        let schema = options
+            .build(schema)
+            .map_err(|e| ValidationError::SchemaCompilation(e.to_string()))?;
+        Ok(Warnings { schema, formats })
+    }
+
+    /// Compile the subschema at `pointer` inside `root` as `draft`.
+    ///
+    /// References from the subschema into the rest of the document keep
+    /// working. The subschema is reached through a `$ref`, so schema paths of
+    /// its errors start with `/$ref`.
+    pub(crate) fn build_subschema(
+        &self,
+        root: &Value,
+        pointer: &str,
+        draft: Draft,
+    ) -> Result<Validator, ValidationError> {
+        let base_uri = root
+            .get("$id")
+            .and_then(Value::as_str)
+            .filter(|id| Url::parse(id).is_ok())
+            .unwrap_or(DEFAULT_BASE_URI)
+            .trim_end_matches('#');
+        let wrapper = serde_json::json!({
+            "$ref": format!("{}#{}", base_uri, refs::encode_fragment(pointer))
+        });
+        let resource = jsonschema::Resource::from_contents(root.clone())
+            .map_err(|e| ValidationError::SchemaCompilation(e.to_string()))?;
+        let schema = self
+            .engine_options(draft, self.resolver()?)
+            .with_resource(base_uri, resource)
+            .build(&wrapper)
+            .map_err(|e| ValidationError::SchemaCompilation(e.to_string()))?;
+
+        Ok(Validator {
+            schema,
+            draft,
+            limits: self.limits,
+            allow_comments: self.allow_comments,
+            merge_keys: self.merge_keys,
+            allow_aliases: self.allow_aliases,
+            yaml_tags: self.yaml_tags,
+            tag_handlers: self.tag_handlers.clone(),
+            yaml_schema: self.yaml_schema,
+            strict_keys: self.strict_keys,
+            empty_documents: self.empty_documents,
+            failure_groups: self.failure_groups,
+            max_failures: self.max_failures,
+            warnings: None,
+            warnings_as_errors: false,
+            ignore: self.ignore.clone(),
+            // Schema paths start at the wrapper, outside the document
+            error_messages: None,
+        })
+    }
+
+    /// Compile a validator for the absolute `reference`, fetching the
+    /// document it points into, for a lazily resolved `$ref`.
+    fn compile_reference(
+        &self,
+        reference: &str,
+        draft: Draft,
+        resolver: &Resolver,
+    ) -> Result<jsonschema::Validator, String> {
+        let mut document_url = Url::parse(reference).map_err(|e| e.to_string())?;
+        document_url.set_fragment(None);
+        let document = resolver.load_document(document_url.as_str()).map_err(|e| {
+            format!(
+                "referenced schema '{}' could not be loaded: {}",
+                reference, e
+            )
+        })?;
+        let draft = Draft::detect(&document).unwrap_or(draft);
+        let resource = draft.engine_draft().create_resource(document);
+        self.engine_options(draft, resolver.clone())
+            .with_resource(document_url.as_str(), resource)
+            .build(&serde_json::json!({ "$ref": reference }))
+            .map_err(|e| e.to_string())
+    }
+
+    /// The draft to compile `schema` as.
+    ///
+    /// An explicit [`draft`](Self::draft) wins, then the draft named by
+    /// `$schema`. A custom meta-schema is loaded to use the draft it is itself
+    /// written in. Anything else is compiled as Draft 7.
+    pub(crate) fn draft_for(&self, schema: &Value, resolver: &Resolver) -> Draft {
+        if let Some(draft) = self.draft.or_else(|| Draft::detect(schema)) {
+            return draft;
+        }
+        schema
+            .get("$schema")
+            .and_then(Value::as_str)
+            .and_then(|uri| resolver.load_document(uri).ok())
+            .and_then(|meta_schema| Draft::detect(&meta_schema))
+            .unwrap_or(Draft::Draft7)
+    }
+
+    /// The draft set with [`draft`](Self::draft), if any.
+    pub(crate) fn explicit_draft(&self) -> Option<Draft> {
+        self.draft
+    }
+
+    /// The settings that decide how documents are read and validated, each
+    /// as a label and its value, for `--explain` to list.
+    pub(crate) fn describe(&self) -> Vec<(&'static str, String)> {
+        let enabled = |on: bool| if on { "enabled" } else { "disabled" };
+        let allowed = |on: bool| if on { "allowed" } else { "rejected" };
+        let formats = match self.validate_formats {
+            Some(true) => "enabled",
+            Some(false) => "disabled",
+            None => "as the draft specifies (enabled up to draft7)",
+        };
+        let yaml_schema = match self.yaml_schema {
+            YamlSchema::Core => "core (YAML 1.2)",
+            YamlSchema::Yaml11 => "YAML 1.1",
+        };
+        let yaml_tags = match self.yaml_tags {
+            YamlTags::Error => "rejected",
+            YamlTags::Strip => "stripped",
+            YamlTags::Base64Decode => "!!binary decoded, others stripped",
+        };
+        let empty_documents = match self.empty_documents {
+            EmptyDocuments::Null => "validated as null",
+            EmptyDocuments::Skip => "skipped",
+            EmptyDocuments::Error => "rejected",
+        };
+        let max_failures = match self.max_failures {
+            Some(max) => max.to_string(),
+            None => "unlimited".to_string(),
+        };
+        let failure_groups = match self.failure_groups {
+            None => "disabled".to_string(),
+            Some(None) => "enabled".to_string(),
+            Some(Some(max)) => format!("at most {} per path", max),
+        };
+        let ignore_rules = match self.ignore.rules().len() {
+            0 => "none".to_string(),
+            rules => format!("{} rule(s)", rules),
+        };
+        let warnings = match (self.warnings, self.warnings_as_errors) {
+            (_, true) => "reported as failures",
+            (true, false) => "reported",
+            (false, false) => "disabled",
+        };
+        vec![
+            ("format assertions", formats.to_string()),
+            (
+                "strict vocabularies",
+                enabled(self.strict_vocabularies).to_string(),
+            ),
+            ("exact numbers", enabled(self.exact_numbers).to_string()),
+            ("comments in JSON", allowed(self.allow_comments).to_string()),
+            ("duplicate keys", allowed(!self.strict_keys).to_string()),
+            ("YAML schema", yaml_schema.to_string()),
+            ("YAML tags", yaml_tags.to_string()),
+            ("YAML merge keys", enabled(self.merge_keys).to_string()),
+            ("YAML aliases", allowed(self.allow_aliases).to_string()),
+            ("empty documents", empty_documents.to_string()),
+            ("max errors", max_failures),
+            ("error grouping", failure_groups),
+            ("ignore rules", ignore_rules),
+            ("warnings", warnings.to_string()),
+        ]
+    }
+
+    /// Engine options for compiling a schema as `draft`.
+    // Keyword factories return the engine's large error type
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn engine_options(
+        &self,
+        draft: Draft,
+        resolver: Resolver,
+    ) -> jsonschema::ValidationOptions<Arc<dyn jsonschema::Retrieve>> {
+        let deferred = {
+            let (builder, resolver) = (self.clone(), resolver.clone());
+            resolve::DeferredRefs::new(Arc::new(move |reference| {
+                builder.compile_reference(reference, draft, &resolver)
+            }))
+        };
+        let mut options = jsonschema::options()
+            .with_draft(draft.engine_draft())
+            .with_retriever(resolver)
+            .with_keyword(resolve::UNRESOLVED_KEYWORD, resolve::unresolved_keyword)
+            .with_keyword(resolve::DEFERRED_KEYWORD, move |_parent, value, path| {
+                resolve::deferred_keyword(value, path, &deferred)
+            });
+        if self.exact_numbers {
+            for keyword in numbers::EXACT_KEYWORDS {
+                options = options.with_keyword(*keyword, |parent, value, location| {
+                    Ok(numbers::exact_keyword(parent, keyword, value, location))
+                });
+            }
+        }
+        for (id, schema) in self.schemas.iter() {
+            let resource_draft = Draft::detect(schema).unwrap_or(draft);
+            options = options.with_resource(
+                id,
+                resource_draft
+                    .engine_draft()
+                    .create_resource(schema.clone()),
+            );
+        }
+        for (name, check) in &self.formats {
+            let check = Arc::clone(check);
+            options = options.with_format(name.clone(), move |value: &str| check(value));
+        }
+        for (name, check) in &self.keywords {
+            let check = Arc::clone(check);
+            options = options.with_keyword(name.clone(), move |_parent, value, location| {
+                Ok(Box::new(CustomKeyword {
+                    value: value.clone(),
+                    location,
+                    check: Arc::clone(&check),
+                }) as Box<dyn jsonschema::Keyword>)
+            });
+        }
+        match self.validate_formats {
+            Some(validate) => options.should_validate_formats(validate),
+            None => options,
+        }
+    }
+}
+
+/// A schema compiled to check documents for warnings.
+pub(crate) struct Warnings {
+    pub(crate) schema: jsonschema::Validator,
+    /// Whether `format` mismatches are warnings, as formats are not asserted.
+    pub(crate) formats: bool,
+}
+
+/// A registered custom keyword, compiled for one occurrence in a schema.
+struct CustomKeyword {
+    value: Value,
+    location: Location,
+    check: KeywordCheck,
+}
+
+impl jsonschema::Keyword for CustomKeyword {
+    fn validate<'i>(
+        &self,
+        instance: &'i Value,
+        location: &LazyLocation,
+    ) -> Result<(), jsonschema::ValidationError<'i>> {
+        (self.check)(instance, &self.value).map_err(|message| {
+            jsonschema::ValidationError::custom(
+                self.location.clone(),
+                location.into(),
+                instance,
+                message,
+            )
+        })
+    }
+
+    fn is_valid(&self, instance: &Value) -> bool {
+        (self.check)(instance, &self.value).is_ok()
+    }
+}
+
+impl Limits {
+    /// Check the size of unparsed `content`.
+    pub(crate) fn check_size(&self, content: impl AsRef<[u8]>) -> Result<(), ValidationError> {
+        let content = content.as_ref();
+        match self.max_document_size {
+            Some(max) if content.len() > max => Err(ValidationError::LimitExceeded(format!(
+                "document is {} bytes, more than the maximum of {}",
+                content.len(),
+                max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the nesting depth of a parsed document.
+    pub(crate) fn check_depth(&self, document: &Value) -> Result<(), ValidationError> {
+        match self.max_depth {
+            Some(max) if exceeds_depth(document, max) => Err(ValidationError::LimitExceeded(
+                format!("document is nested more than {} levels deep", max),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn exceeds_depth(value: &Value, remaining: usize) -> bool {
+    let children: Box<dyn Iterator<Item = &Value>> = match value {
+        Value::Array(items) => Box::new(items.iter()),
+        Value::Object(entries) => Box::new(entries.values()),
+        _ => return false,
+    };
+    remaining == 0
+        || children
+            .into_iter()
+            .any(|child| exceeds_depth(child, remaining - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_builder_options() {
+        let schema = json!({"type": "string", "format": "email"});
+        let strict = ValidatorBuilder::new().build(&schema).unwrap();
+        let lenient = ValidatorBuilder::new()
+            .validate_formats(false)
+            .build(&schema)
+            .unwrap();
+        assert!(!strict.is_valid_value(&json!("nope")));
+        assert!(lenient.is_valid_value(&json!("nope")));
+
+        // 2020-12 makes `format` an annotation unless assertions are enabled
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "properties": {
+                "email": {"format": "email"},
+                "homepage": {"format": "uri"},
+                "updated": {"format": "date-time"}
+            }
+        });
+        let annotating = ValidatorBuilder::new().build(&schema).unwrap();
+        let asserting = ValidatorBuilder::new()
+            .validate_formats(true)
+            .build(&schema)
+            .unwrap();
+        for invalid in [
+            json!({"email": "nope"}),
+            json!({"homepage": "not a uri"}),
+            json!({"updated": "yesterday"}),
+        ] {
+            assert!(annotating.is_valid_value(&invalid));
+            assert!(!asserting.is_valid_value(&invalid), "{}", invalid);
+        }
+        assert!(asserting.is_valid_value(&json!({
+            "email": "a@example.com",
+            "homepage": "https://example.com",
+            "updated": "2024-01-01T00:00:00Z"
+        })));
+
+        // Draft 4 has no `const`, so the override makes it an unknown keyword
+        let schema = json!({"const": 1});
+        let draft4 = ValidatorBuilder::new()
+            .draft(Draft::Draft4)
+            .build(&schema)
+            .unwrap();
+        assert!(draft4.is_valid_value(&json!(2)));
+    }
+
+    #[test]
+    fn test_custom_formats() {
+        let semver = |s: &str| {
+            let parts: Vec<&str> = s.split('.').collect();
+            parts.len() == 3 && parts.iter().all(|part| part.parse::<u64>().is_ok())
+        };
+        let schema = json!({
+            "properties": {
+                "version": {"format": "semver"},
+                "contact": {"format": "email"}
+            }
+        });
+        let validator = ValidatorBuilder::new()
+            .with_format("semver", semver)
+            .with_format("email", |s| s.ends_with("@example.com"))
+            .build(&schema)
+            .unwrap();
+        assert!(validator.is_valid_value(&json!({"version": "1.2.3"})));
+        assert!(!validator.is_valid_value(&json!({"version": "1.2"})));
+        // Replaces the built-in check
+        assert!(!validator.is_valid_value(&json!({"contact": "a@other.org"})));
+        // Non-strings are not checked
+        assert!(validator.is_valid_value(&json!({"version": 1})));
+
+        let annotating = ValidatorBuilder::new()
+            .with_format("semver", semver)
+            .build(&json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "format": "semver"
+            }))
+            .unwrap();
+        assert!(annotating.is_valid_value(&json!("1.2")));
+    }
+
+    #[test]
+    fn test_custom_keywords() {
+        let validator = ValidatorBuilder::new()
+            .with_keyword("x-max-words", |instance, max| {
+                let (text, max) = match (instance.as_str(), max.as_u64()) {
+                    (Some(text), Some(max)) => (text, max as usize),
+                    _ => return Ok(()),
+                };
+                let words = text.split_whitespace().count();
+                if words > max {
+                    return Err(format!("{} words is more than {}", words, max));
+                }
+                Ok(())
+            })
+            .build(&json!({
+                "properties": {
+                    "title": {"type": "string", "x-max-words": 3},
+                    "tags": {"items": {"$ref": "#/definitions/tag"}}
+                },
+                "definitions": {"tag": {"x-max-words": 1}}
+            }))
+            .unwrap();
+
+        assert!(validator.is_valid_value(&json!({"title": "a short title"})));
+        let failures = validator.validate_detailed(&json!({
+            "title": "a rather long title",
+            "tags": ["ok", "not ok"]
+        }));
+        let described: Vec<(String, String, String)> = failures
+            .into_iter()
+            .map(|failure| (failure.instance_path, failure.keyword, failure.message))
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                (
+                    "/tags/1".to_string(),
+                    "x-max-words".to_string(),
+                    "2 words is more than 1".to_string()
+                ),
+                (
+                    "/title".to_string(),
+                    "x-max-words".to_string(),
+                    "4 words is more than 3".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_draft_detection() {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "prefixItems": [{"type": "string"}]
+        });
+        let detected = ValidatorBuilder::new().build(&schema).unwrap();
+        assert_eq!(detected.draft(), Draft::Draft202012);
+        assert!(!detected.is_valid_value(&json!([1])));
+
+        let overridden = ValidatorBuilder::new()
+            .draft(Draft::Draft7)
+            .build(&schema)
+            .unwrap();
+        assert_eq!(overridden.draft(), Draft::Draft7);
+        assert!(overridden.is_valid_value(&json!([1])));
+
+        // A custom meta-schema is compiled as the draft it is written in
+        let cache = crate::MemoryCacheStore::new();
+        let meta = json!({"$schema": "https://json-schema.org/draft/2019-09/schema"});
+        cache
+            .put("https://example.com/meta", &meta.to_string())
+            .unwrap();
+        let custom = ValidatorBuilder::new()
+            .cache(Arc::new(cache))
+            .build(&json!({"$schema": "https://example.com/meta"}))
+            .unwrap();
+        assert_eq!(custom.draft(), Draft::Draft201909);
+        assert_eq!(
+            ValidatorBuilder::new().build(&json!({})).unwrap().draft(),
+            Draft::Draft7
+        );
+    }
+
+    #[test]
+    fn test_remote_refs_are_cached() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountingLoader(Arc<AtomicUsize>);
+        impl SchemaLoader for CountingLoader {
+            fn load(&self, url: &str) -> Result<String, ValidationError> {
+                assert_eq!(url, "https://example.com/defs/port.json");
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(r#"{"type": "integer", "minimum": 1}"#.to_string())
+            }
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache: Arc<dyn CacheStore> = Arc::new(crate::MemoryCacheStore::new());
+        let schema = json!({
+            "$id": "https://example.com/service.json",
+            "properties": {"port": {"$ref": "defs/port.json"}}
+        });
+        for _ in 0..2 {
+            let validator = ValidatorBuilder::new()
+                .loader(CountingLoader(calls.clone()))
+                .cache(cache.clone())
+                .build(&schema)
+                .unwrap();
+            assert!(validator.is_valid_value(&json!({"port": 80})));
+            assert!(!validator.is_valid_value(&json!({"port": 0})));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache
+            .get("https://example.com/defs/port.json")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_ref_prefetch() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct DefsLoader(Arc<AtomicUsize>);
+        impl SchemaLoader for DefsLoader {
+            fn load(&self, url: &str) -> Result<String, ValidationError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                match url {
+                    "https://example.com/defs.json" => Ok(json!({
+                        "definitions": {"port": {"type": "integer", "minimum": 1}}
+                    })
+                    .to_string()),
+                    _ => Err(ValidationError::SchemaLoad(format!("not found: {}", url))),
+                }
+            }
+        }
+
+        let schema = json!({
+            "$id": "https://example.com/service.json",
+            "properties": {
+                "port": {"$ref": "defs.json#/definitions/port"},
+                "ports": {"items": {"$ref": "#/properties/port"}},
+                "legacy": {"$ref": "missing.json"}
+            }
+        });
+        let calls = Arc::new(AtomicUsize::new(0));
+        let builder = |prefetch| {
+            ValidatorBuilder::new()
+                .loader(DefsLoader(calls.clone()))
+                .no_cache()
+                .prefetch(prefetch)
+        };
+
+        let lazy = builder(RefPrefetch::Lazy).build(&schema).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert!(lazy.is_valid_value(&json!({"name": "web"})));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let failures = lazy.validate_detailed(&json!({"ports": [80, 0]}));
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].instance_path, "/ports/1");
+        assert_eq!(
+            failures[0].schema_path,
+            "/properties/ports/items/$ref/$ref/minimum"
+        );
+        assert_eq!(failures[0].keyword, "minimum");
+        assert!(lazy.is_valid_value(&json!({"port": 80})));
+        assert!(!lazy.is_valid_value(&json!({"legacy": 1})));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        calls.store(0, Ordering::SeqCst);
+        let best_effort = builder(RefPrefetch::BestEffort).build(&schema).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(best_effort.is_valid_value(&json!({"port": 80})));
+        assert!(!best_effort.is_valid_value(&json!({"legacy": 1})));
+
+        let eager = builder(RefPrefetch::Eager);
+        assert!(matches!(
+            eager.build(&schema),
+            Err(ValidationError::SchemaCompilation(message)) if message.contains("missing.json")
+        ));
+        let reachable = json!({"properties": {"port": {"$ref": "https://example.com/defs.json#/definitions/port"}}});
+        assert!(!eager
+            .build(&reachable)
+            .unwrap()
+            .is_valid_value(&json!({"port": 0})));
+    }
+
+    #[test]
+    fn test_builder_limits() {
+        let validator = ValidatorBuilder::new()
+            .max_document_size(16)
+            .max_depth(2)
+            .build(&json!({}))
+            .unwrap();
+
+        assert!(validator.validate_json(r#"{"a": [1]}"#).is_ok());
+        assert!(matches!(
+            validator.validate_json(r#"{"a": [[1]]}"#),
+            Err(ValidationError::LimitExceeded(_))
+        ));
+        assert!(matches!(
+            validator.validate_yaml("name: a rather long value"),
+            Err(ValidationError::LimitExceeded(_))
+        ));
+        assert!(!validator.is_valid_json(r#"{"a": [[1]]}"#));
+    }
+
+    #[test]
+    fn test_allow_comments() {
+        let schema = json!({"properties": {"tabSize": {"type": "integer"}}});
+        let content = "{\n  // editor\n  \"tabSize\": 2,\n}";
+
+        let strict = ValidatorBuilder::new().build(&schema).unwrap();
+        assert!(matches!(
+            strict.validate_json(content),
+            Err(ValidationError::JsonSyntax { .. })
+        ));
+
+        let lenient = ValidatorBuilder::new()
+            .allow_comments(true)
+            .build(&schema)
+            .unwrap();
+        assert!(lenient.validate_json(content).is_ok());
+        assert!(lenient.is_valid_json(content));
+        assert!(lenient.validate_json("{tabSize: 2}").is_err());
+    }
+
+    #[test]
+    fn test_yaml_aliases() {
+        let schema = json!({"properties": {"web": {"additionalProperties": {"type": "string"}}}});
+        let content = "base: &base\n  image: app\nweb:\n  <<: *base\n";
+
+        let literal = ValidatorBuilder::new().build(&schema).unwrap();
+        assert!(literal.validate_yaml(content).is_err());
+        let merging = ValidatorBuilder::new()
+            .merge_keys(true)
+            .build(&schema)
+            .unwrap();
+        assert!(merging.validate_yaml(content).is_ok());
+
+        let strict = ValidatorBuilder::new()
+            .merge_keys(true)
+            .allow_aliases(false)
+            .build(&schema)
+            .unwrap();
+        let error = strict.validate_yaml(content).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "YAML anchors and aliases are not allowed: line 1: &base"
+        );
+        assert!(strict.validate_yaml("web:\n  image: app\n").is_ok());
+
+        let bounded = ValidatorBuilder::new()
+            .merge_keys(true)
+            .max_aliases(0)
+            .build(&schema)
+            .unwrap();
+        assert!(matches!(
+            bounded.validate_yaml(content),
+            Err(ValidationError::LimitExceeded(_))
+        ));
+
+        let mut bomb = "a: &a [lol, lol, lol, lol, lol, lol, lol, lol, lol]\n".to_string();
+        for (name, previous) in "bcdefghij".chars().zip("abcdefghi".chars()) {
+            let aliases = vec![format!("*{}", previous); 9].join(", ");
+            bomb += &format!("{}: &{} [{}]\n", name, name, aliases);
+        }
+        let error = ValidatorBuilder::new()
+            .build(&schema)
+            .unwrap()
+            .validate_yaml(&bomb)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Document limit exceeded: aliases expand the document to more than"));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let schema = json!({
+            "properties": {
+                "host": {"deprecated": true},
+                "email": {"format": "email"}
+            }
+        });
+        let document = json!({"host": "db", "email": "nope"});
+
+        // Draft 7 asserts formats, so only the deprecated value warns
+        let checked = ValidatorBuilder::new()
+            .warnings(true)
+            .build(&schema)
+            .unwrap();
+        let warnings = checked.warnings(&document);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].keyword, "deprecated");
+        assert_eq!(checked.validate_detailed(&document).len(), 1);
+
+        let lenient = ValidatorBuilder::new()
+            .warnings(true)
+            .validate_formats(false)
+            .build(&schema)
+            .unwrap();
+        assert!(lenient.is_valid_value(&document));
+        let keywords: Vec<String> = lenient
+            .warnings(&document)
+            .into_iter()
+            .map(|warning| warning.keyword)
+            .collect();
+        assert_eq!(keywords, ["format", "deprecated"]);
+
+        let strict = ValidatorBuilder::new()
+            .validate_formats(false)
+            .warnings_as_errors(true)
+            .build(&schema)
+            .unwrap();
+        assert!(strict.warnings(&document).is_empty());
+        assert!(!strict.is_valid_value(&document));
+        assert_eq!(strict.validate_detailed(&document).len(), 2);
+        assert!(strict
+            .validate_json(r#"{"email": "a@example.com"}"#)
+            .is_ok());
+
+        let unchecked = ValidatorBuilder::new().build(&schema).unwrap();
+        assert!(unchecked.warnings(&document).is_empty());
+    }
+
+    #[test]
+    fn test_empty_documents() {
+        let schema = json!({"type": "object"});
+        let blank = ["", "# comments only\n", "%YAML 1.2\n---\n"];
+
+        let null = ValidatorBuilder::new().build(&schema).unwrap();
+        for content in blank {
+            assert!(matches!(
+                null.validate_yaml(content),
+                Err(ValidationError::ValidationFailed(_))
+            ));
+        }
+        assert!(matches!(
+            null.validate_json(" \n"),
+            Err(ValidationError::ValidationFailed(_))
+        ));
+
+        let skip = ValidatorBuilder::new()
+            .empty_documents(EmptyDocuments::Skip)
+            .build(&schema)
+            .unwrap();
+        for content in blank {
+            assert!(skip.validate_yaml(content).is_ok());
+        }
+        assert!(skip.validate_json5("// todo").is_ok());
+        assert!(skip.validate_yaml("null").is_err());
+
+        let error = ValidatorBuilder::new()
+            .empty_documents(EmptyDocuments::Error)
+            .build(&schema)
+            .unwrap();
+        assert!(matches!(
+            error.validate_content(""),
+            Err(ValidationError::EmptyDocument)
+        ));
+        assert!(matches!(
+            error.validate_json("/* todo */"),
+            Err(ValidationError::JsonSyntax { .. })
+        ));
+    }
+}
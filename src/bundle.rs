@@ -0,0 +1,428 @@
+//! Bundling a schema and the documents it references into one document.
+//!
+//! Every external document reached through `$ref`, locally or over the
+//! network, is embedded under the root schema's `definitions` (`$defs` from
+//! Draft 2019-09), and every `$ref` is rewritten to a JSON Pointer into the
+//! bundle. The result validates exactly like the original schema but needs no
+//! filesystem or network access, so it can be vendored into a repository or
+//! used in air-gapped environments.
+//!
+//! Embedded documents lose their `$id`, `$anchor` and `$schema` keywords,
+//! since references to them are rewritten anyway. References to the JSON
+//! Schema meta-schemas are left alone; every validator knows them.
+
+use crate::refs::{encode_fragment, percent_decode};
+use crate::resolve::Resolver;
+use crate::walk::{escape_token, subschemas};
+use crate::{is_url, registry, Draft, ValidationError};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use url::Url;
+
+/// Base URI for schemas that have neither a location nor an `$id`.
+const ANONYMOUS_BASE: &str = "validate-json-schema:/schema.json";
+
+/// Load the schema at `input` (a local path, URL or `registry://` subject)
+/// and bundle it with everything it references.
+///
+/// Relative references in a schema file resolve against the file's location.
+/// Remote documents are fetched through the schema cache.
+///
+/// # Errors
+///
+/// Returns an error if the schema or any referenced document cannot be
+/// loaded, or a reference names an anchor that does not exist.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use validate_json_schema::{bundle_schema, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let bundled = bundle_schema("schemas/deployment.json")?;
+/// std::fs::write("vendor/deployment.json", bundled.to_string())?;
+///
+/// let validator = Validator::new(&bundled.to_string())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn bundle_schema(input: &str) -> Result<Value, ValidationError> {
+    let resolver = Resolver::default();
+    let (schema, base) = if input.starts_with(registry::REGISTRY_SCHEME) {
+        let (url, schema) = registry::load_input(input, None, &resolver)?;
+        (schema, Url::parse(&url).ok())
+    } else if is_url(input) {
+        let schema = serde_json::from_str(&resolver.fetch(input)?)?;
+        (schema, Url::parse(input).ok())
+    } else {
+        let schema = serde_json::from_str(&fs::read_to_string(input)?)?;
+        let base = fs::canonicalize(input)
+            .ok()
+            .and_then(|path| Url::from_file_path(path).ok());
+        (schema, base)
+    };
+    bundle(&schema, base, &resolver)
+}
+
+/// Bundle `schema`, retrieved from `base`, with the documents it references.
+pub(crate) fn bundle(
+    schema: &Value,
+    base: Option<Url>,
+    resolver: &Resolver,
+) -> Result<Value, ValidationError> {
+    let draft = Draft::detect(schema).unwrap_or(Draft::Draft7);
+    let container = match draft {
+        Draft::Draft4 | Draft::Draft6 | Draft::Draft7 => "definitions",
+        Draft::Draft201909 | Draft::Draft202012 => "$defs",
+    };
+    let taken: HashSet<String> = match schema.get(container) {
+        Some(Value::Object(existing)) => existing.keys().cloned().collect(),
+        Some(_) => {
+            return Err(ValidationError::SchemaLoad(format!(
+                "cannot bundle: '{}' is not an object",
+                container
+            )))
+        }
+        None => HashSet::new(),
+    };
+
+    let base = base.unwrap_or_else(|| Url::parse(ANONYMOUS_BASE).expect("valid URL"));
+    let mut bundler = Bundler {
+        resolver,
+        draft,
+        container,
+        documents: Vec::new(),
+        targets: HashMap::new(),
+        taken,
+    };
+    bundler.add(schema.clone(), base, String::new(), String::new());
+
+    let mut index = 0;
+    while index < bundler.documents.len() {
+        let references = std::mem::take(&mut bundler.documents[index].references);
+        for reference in references {
+            let target = bundler.resolve(&reference.base, &reference.value)?;
+            let document = &mut bundler.documents[index].schema;
+            if let Some(Value::Object(object)) = document.pointer_mut(&reference.pointer) {
+                object.insert("$ref".to_string(), Value::String(target));
+            }
+        }
+        index += 1;
+    }
+
+    let mut documents = bundler.documents.into_iter();
+    let mut root = documents.next().expect("the root document").schema;
+    let mut embedded = Map::new();
+    for document in documents {
+        embedded.insert(document.key, document.schema);
+    }
+    if !embedded.is_empty() {
+        if let Value::Object(object) = &mut root {
+            let entry = object
+                .entry(container)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(entries) = entry {
+                entries.extend(embedded);
+            }
+        }
+    }
+    Ok(root)
+}
+
+/// A document in the bundle.
+struct Document {
+    schema: Value,
+    /// Key the document is embedded under.
+    key: String,
+    /// References in the document that still need rewriting.
+    references: Vec<Reference>,
+}
+
+/// A `$ref` found in a document.
+struct Reference {
+    /// Pointer to the subschema containing the `$ref`, within its document.
+    pointer: String,
+    /// The base URI the reference resolves against.
+    base: Url,
+    value: String,
+}
+
+struct Bundler<'a> {
+    resolver: &'a Resolver,
+    draft: Draft,
+    container: &'static str,
+    documents: Vec<Document>,
+    /// Pointers into the bundle of every resource (by URI without fragment)
+    /// and anchor (by URI with the anchor as fragment).
+    targets: HashMap<String, String>,
+    /// Keys already used in the definitions container.
+    taken: HashSet<String>,
+}
+
+impl Bundler<'_> {
+    /// Add `schema`, retrieved from `url`, to be embedded under `key` at `prefix`.
+    fn add(&mut self, mut schema: Value, url: Url, key: String, prefix: String) {
+        let root = self.documents.is_empty();
+        self.targets.insert(without_fragment(&url), prefix.clone());
+
+        let mut scan = Scan::default();
+        self.scan(&schema, String::new(), url.clone(), &prefix, &mut scan);
+        for pointer in &scan.identified {
+            if let Some(Value::Object(object)) = schema.pointer_mut(pointer) {
+                // The root document keeps its own $id, which the bundle is known by
+                if !(root && pointer.is_empty()) {
+                    object.remove(self.id_keyword());
+                }
+                object.remove("$anchor");
+            }
+        }
+        if !root {
+            if let Value::Object(object) = &mut schema {
+                object.remove("$schema");
+            }
+        }
+
+        self.documents.push(Document {
+            schema,
+            key,
+            references: scan.references,
+        });
+    }
+
+    /// Record the identifiers and references in `schema` and its subschemas.
+    fn scan(
+        &mut self,
+        schema: &Value,
+        pointer: String,
+        mut base: Url,
+        prefix: &str,
+        scan: &mut Scan,
+    ) {
+        let object = match schema.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+        let location = format!("{}{}", prefix, pointer);
+
+        let id = object.get(self.id_keyword()).and_then(Value::as_str);
+        if let Some(id) = id {
+            if let Ok(url) = base.join(id) {
+                if id.starts_with('#') {
+                    // A plain-name fragment: an anchor before Draft 2019-09
+                    self.targets.insert(url.to_string(), location.clone());
+                } else {
+                    self.targets
+                        .entry(without_fragment(&url))
+                        .or_insert_with(|| location.clone());
+                    base = url;
+                }
+            }
+        }
+        let anchor = object.get("$anchor").and_then(Value::as_str);
+        if let Some(anchor) = anchor {
+            let url = format!("{}#{}", without_fragment(&base), anchor);
+            self.targets.insert(url, location.clone());
+        }
+        if id.is_some() || anchor.is_some() {
+            scan.identified.push(pointer.clone());
+        }
+
+        if let Some(Value::String(reference)) = object.get("$ref") {
+            scan.references.push(Reference {
+                pointer: pointer.clone(),
+                base: base.clone(),
+                value: reference.clone(),
+            });
+        }
+        for (relative, child) in subschemas(schema) {
+            self.scan(
+                child,
+                format!("{}{}", pointer, relative),
+                base.clone(),
+                prefix,
+                scan,
+            );
+        }
+    }
+
+    /// The `$ref` value pointing to the bundled target of `reference`.
+    fn resolve(&mut self, base: &Url, reference: &str) -> Result<String, ValidationError> {
+        let url = base.join(reference).map_err(|e| {
+            ValidationError::SchemaLoad(format!("invalid reference '{}': {}", reference, e))
+        })?;
+        let resource = without_fragment(&url);
+        if Draft::from_uri(&resource).is_some() {
+            return Ok(url.to_string());
+        }
+        if !self.targets.contains_key(&resource) {
+            let schema = self.resolver.load_document(&resource).map_err(|e| {
+                ValidationError::SchemaLoad(format!("cannot bundle '{}': {}", resource, e))
+            })?;
+            let url = Url::parse(&resource).expect("valid URL");
+            let key = unique_key(&document_name(&url), &mut self.taken);
+            let prefix = format!("/{}/{}", self.container, escape_token(&key));
+            self.add(schema, url, key, prefix);
+        }
+
+        let fragment = url.fragment().map(percent_decode).unwrap_or_default();
+        let target = if fragment.is_empty() || fragment.starts_with('/') {
+            format!("{}{}", self.targets[&resource], fragment)
+        } else {
+            self.targets.get(url.as_str()).cloned().ok_or_else(|| {
+                ValidationError::SchemaLoad(format!("reference '{}' names no anchor", url))
+            })?
+        };
+        Ok(format!("#{}", encode_fragment(&target)))
+    }
+
+    fn id_keyword(&self) -> &'static str {
+        match self.draft {
+            Draft::Draft4 => "id",
+            _ => "$id",
+        }
+    }
+}
+
+/// Pointers to the identified subschemas and the references of a document.
+#[derive(Default)]
+struct Scan {
+    identified: Vec<String>,
+    references: Vec<Reference>,
+}
+
+fn without_fragment(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// A readable key for the document at `url`: its file name without extension.
+fn document_name(url: &Url) -> String {
+    let file = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default();
+    let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    if stem.is_empty() {
+        "schema".to_string()
+    } else {
+        percent_decode(stem)
+    }
+}
+
+/// `name`, or `name-2`, `name-3`, ... if it is taken.
+fn unique_key(name: &str, taken: &mut HashSet<String>) -> String {
+    let mut key = name.to_string();
+    let mut suffix = 2;
+    while taken.contains(&key) {
+        key = format!("{}-{}", name, suffix);
+        suffix += 1;
+    }
+    taken.insert(key.clone());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve::CachePolicy;
+    use crate::{SchemaLoader, Validator};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    struct StaticLoader(HashMap<&'static str, Value>);
+
+    impl SchemaLoader for StaticLoader {
+        fn load(&self, url: &str) -> Result<String, ValidationError> {
+            self.0
+                .get(url)
+                .map(Value::to_string)
+                .ok_or_else(|| ValidationError::SchemaLoad(format!("not found: {}", url)))
+        }
+    }
+
+    #[test]
+    fn test_bundle_files() {
+        let bundled = bundle_schema("tests/schemas/split/deployment.json").unwrap();
+        assert_eq!(
+            bundled["properties"]["services"]["items"],
+            json!({"$ref": "#/definitions/service"})
+        );
+        assert_eq!(
+            bundled["definitions"]["service"]["properties"]["port"],
+            json!({"$ref": "#/definitions/common/definitions/port"})
+        );
+        assert!(bundled["definitions"]["service"].get("$schema").is_none());
+
+        let validator = Validator::new(&bundled.to_string()).unwrap();
+        assert!(validator.is_valid_value(&json!({"services": [{"name": "web", "port": 80}]})));
+        assert!(!validator.is_valid_value(&json!({"services": [{"name": "web", "port": 0}]})));
+    }
+
+    #[test]
+    fn test_bundle_remote() {
+        let loader = StaticLoader(HashMap::from([
+            (
+                "https://example.com/schemas/item.json",
+                json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "$id": "https://example.com/schemas/item.json",
+                    "properties": {
+                        "name": {"$ref": "root.json#/$defs/name"},
+                        "children": {"type": "array", "items": {"$ref": "#node"}}
+                    },
+                    "$defs": {"node": {"$anchor": "node", "$ref": "#"}}
+                }),
+            ),
+            (
+                "https://example.com/schemas/tag.json",
+                json!({"type": "string", "maxLength": 3}),
+            ),
+        ]));
+        let resolver = Resolver::default()
+            .with_loader(Arc::new(loader))
+            .with_cache(CachePolicy::Bypass);
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "https://example.com/schemas/root.json",
+            "properties": {
+                "item": {"$ref": "item.json"},
+                "tags": {"items": {"$ref": "tag.json"}},
+                "meta": {"$ref": "https://json-schema.org/draft/2020-12/schema"}
+            },
+            "$defs": {"name": {"type": "string"}, "item": {}}
+        });
+
+        let bundled = bundle(&schema, None, &resolver).unwrap();
+        assert_eq!(bundled["$id"], "https://example.com/schemas/root.json");
+        let properties = &bundled["properties"];
+        assert_eq!(properties["item"]["$ref"], "#/$defs/item-2");
+        assert_eq!(properties["tags"]["items"]["$ref"], "#/$defs/tag");
+        assert_eq!(
+            properties["meta"]["$ref"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        let item = &bundled["$defs"]["item-2"];
+        assert!(item.get("$id").is_none());
+        assert_eq!(item["properties"]["name"]["$ref"], "#/$defs/name");
+        assert_eq!(
+            item["properties"]["children"]["items"]["$ref"],
+            "#/$defs/item-2/$defs/node"
+        );
+        assert_eq!(item["$defs"]["node"], json!({"$ref": "#/$defs/item-2"}));
+
+        let validator = Validator::new(&bundled.to_string()).unwrap();
+        assert!(validator.is_valid_value(&json!({
+            "item": {"name": "a", "children": [{"name": "b"}]},
+            "tags": ["x"]
+        })));
+        assert!(!validator.is_valid_value(&json!({"item": {"children": [{"name": 1}]}})));
+        assert!(!validator.is_valid_value(&json!({"tags": ["long"]})));
+
+        let missing = json!({"$ref": "https://example.com/schemas/missing.json"});
+        assert!(bundle(&missing, None, &resolver).is_err());
+        let anchor = json!({"$ref": "https://example.com/schemas/item.json#nope"});
+        assert!(bundle(&anchor, None, &resolver).is_err());
+    }
+}
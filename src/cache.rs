@@ -0,0 +1,292 @@
+//! Storage backends for the remote schema cache.
+//!
+//! [`CacheStore`] abstracts where fetched schemas are kept. The CLI and
+//! [`Validator::from_url`](crate::Validator::from_url) use
+//! [`FsCacheStore`] in the user's cache directory; [`MemoryCacheStore`] keeps
+//! schemas for the lifetime of the process, and `RedisCacheStore` (behind the
+//! `redis` feature) lets several replicas share one cache.
+//!
+//! Concurrent fetches of the same URL within a process are coalesced with
+//! [`single_flight`], so parallel workers download an uncached schema once.
+
+use crate::{get_cache_filename, ValidationError};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+/// URLs being fetched, each with the lock its fetching thread holds.
+static IN_FLIGHT: OnceLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> = OnceLock::new();
+
+/// Run `fetch` for `url`, waiting while another thread runs one for the same URL.
+///
+/// The waiting thread runs its own `fetch` afterwards, which should find the
+/// document the first thread cached instead of downloading it again.
+pub(crate) fn single_flight<T>(url: &str, fetch: impl FnOnce() -> T) -> T {
+    let in_flight = IN_FLIGHT.get_or_init(Default::default);
+    let lock = Arc::clone(
+        in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(url.to_string())
+            .or_default(),
+    );
+
+    let result = {
+        let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        fetch()
+    };
+
+    let mut in_flight = in_flight.lock().unwrap_or_else(PoisonError::into_inner);
+    // Only the map and this thread hold the lock if nobody is waiting on it
+    if Arc::strong_count(&lock) == 2 {
+        in_flight.remove(url);
+    }
+    result
+}
+
+/// Storage for fetched schema documents, keyed by URL.
+pub trait CacheStore: Send + Sync {
+    /// Return the cached document for `url`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be read.
+    fn get(&self, url: &str) -> Result<Option<String>, ValidationError>;
+
+    /// Store the document fetched from `url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be written.
+    fn put(&self, url: &str, content: &str) -> Result<(), ValidationError>;
+
+    /// Remove every cached document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be cleared.
+    fn clear(&self) -> Result<(), ValidationError>;
+}
+
+/// Caches schemas as files in a directory, one file per URL.
+#[derive(Debug, Clone)]
+pub struct FsCacheStore {
+    dir: PathBuf,
+}
+
+impl FsCacheStore {
+    /// Create a store in `dir`. The directory is created on first write.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Create a store in the user's cache directory, as used by the CLI.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform cache directory cannot be determined.
+    pub fn default_location() -> Result<Self, ValidationError> {
+        let dir = dirs::cache_dir()
+            .ok_or_else(|| {
+                ValidationError::CacheDirectory("Could not determine cache directory".to_string())
+            })?
+            .join("validate-json-schema")
+            .join("schemas");
+        Ok(Self::new(dir))
+    }
+
+    /// The directory schemas are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl CacheStore for FsCacheStore {
+    fn get(&self, url: &str) -> Result<Option<String>, ValidationError> {
+        let path = self.dir.join(get_cache_filename(url));
+        if path.exists() {
+            Ok(Some(fs::read_to_string(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn put(&self, url: &str, content: &str) -> Result<(), ValidationError> {
+        if !self.dir.exists() {
+            fs::create_dir_all(&self.dir).map_err(|e| {
+                ValidationError::CacheDirectory(format!("Failed to create cache directory: {}", e))
+            })?;
+        }
+        // Write to a temporary file first so readers never see a partial document
+        static WRITES: AtomicUsize = AtomicUsize::new(0);
+        let path = self.dir.join(get_cache_filename(url));
+        let temporary = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, &path).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), ValidationError> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Caches schemas in memory for the lifetime of the store.
+#[derive(Debug, Default)]
+pub struct MemoryCacheStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryCacheStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    fn get(&self, url: &str) -> Result<Option<String>, ValidationError> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(url)
+            .cloned())
+    }
+
+    fn put(&self, url: &str, content: &str) -> Result<(), ValidationError> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(url.to_string(), content.to_string());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), ValidationError> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use self::redis_store::RedisCacheStore;
+
+#[cfg(feature = "redis")]
+mod redis_store {
+    use super::CacheStore;
+    use crate::{get_cache_filename, ValidationError};
+    use redis::Commands;
+
+    /// Caches schemas in Redis so several processes can share one cache.
+    ///
+    /// Keys are `<prefix><sha256 of url>.json`; [`CacheStore::clear`] only
+    /// removes keys under the store's prefix.
+    #[derive(Debug, Clone)]
+    pub struct RedisCacheStore {
+        client: redis::Client,
+        prefix: String,
+    }
+
+    impl RedisCacheStore {
+        /// Connect to the Redis server at `url` (e.g. `redis://127.0.0.1/`).
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the connection URL is invalid.
+        pub fn new(url: &str) -> Result<Self, ValidationError> {
+            let client = redis::Client::open(url).map_err(cache_error)?;
+            Ok(Self {
+                client,
+                prefix: "validate-json-schema:schemas:".to_string(),
+            })
+        }
+
+        /// Use `prefix` instead of the default key prefix.
+        pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+            self.prefix = prefix.into();
+            self
+        }
+
+        fn key(&self, url: &str) -> String {
+            format!("{}{}", self.prefix, get_cache_filename(url))
+        }
+
+        fn connection(&self) -> Result<redis::Connection, ValidationError> {
+            self.client.get_connection().map_err(cache_error)
+        }
+    }
+
+    impl CacheStore for RedisCacheStore {
+        fn get(&self, url: &str) -> Result<Option<String>, ValidationError> {
+            self.connection()?.get(self.key(url)).map_err(cache_error)
+        }
+
+        fn put(&self, url: &str, content: &str) -> Result<(), ValidationError> {
+            self.connection()?
+                .set(self.key(url), content)
+                .map_err(cache_error)
+        }
+
+        fn clear(&self) -> Result<(), ValidationError> {
+            let mut connection = self.connection()?;
+            let keys: Vec<String> = connection
+                .scan_match::<_, String>(format!("{}*", self.prefix))
+                .map_err(cache_error)?
+                .collect();
+            if !keys.is_empty() {
+                connection.del::<_, ()>(keys).map_err(cache_error)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn cache_error(error: redis::RedisError) -> ValidationError {
+        ValidationError::Cache(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(store: &dyn CacheStore) {
+        let url = "https://example.com/schema.json";
+        assert_eq!(store.get(url).unwrap(), None);
+
+        store.put(url, r#"{"type": "string"}"#).unwrap();
+        assert_eq!(
+            store.get(url).unwrap().as_deref(),
+            Some(r#"{"type": "string"}"#)
+        );
+
+        store.clear().unwrap();
+        assert_eq!(store.get(url).unwrap(), None);
+    }
+
+    #[test]
+    fn test_fs_store() {
+        let dir = tempfile::tempdir().unwrap();
+        round_trip(&FsCacheStore::new(dir.path().join("schemas")));
+    }
+
+    #[test]
+    fn test_memory_store() {
+        round_trip(&MemoryCacheStore::new());
+    }
+}
@@ -0,0 +1,190 @@
+//! Canonical JSON serialization (RFC 8785, the JSON Canonicalization Scheme).
+//!
+//! The same configuration written as YAML or as differently formatted JSON
+//! parses to the same value, and [`canonicalize`] serializes that value to
+//! exactly one string: object keys sorted by their UTF-16 code units, no
+//! whitespace, strings with only the escapes JSON requires and numbers in
+//! their shortest ECMAScript form. Hashes and diffs of the canonical form are
+//! stable across formatting changes.
+
+use serde_json::Value;
+
+/// Serialize `value` as RFC 8785 canonical JSON.
+///
+/// Numbers are IEEE 754 doubles in the canonical form, so integers beyond
+/// ±2^53 are rounded, as they would be by any JavaScript consumer. Numbers
+/// too large for a double, which only the `arbitrary-precision` feature
+/// keeps, are written as serde_json prints them, e.g. `1e+400`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::canonicalize;
+///
+/// let value = json!({"b": [1.50, 1e21, "é"], "a": {"z": null, "y": true}});
+/// assert_eq!(
+///     canonicalize(&value),
+///     r#"{"a":{"y":true,"z":null},"b":[1.5,1e+21,"é"]}"#
+/// );
+/// ```
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => match n.as_f64().filter(|n| n.is_finite()) {
+            Some(n) => write_number(out, n),
+            None => out.push_str(&n.to_string()),
+        },
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(entries) => {
+            let mut keys: Vec<&String> = entries.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, &entries[key]);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // serde_json escapes exactly what RFC 8785 requires: quotation marks,
+    // backslashes and control characters, with the short forms where they exist
+    out.push_str(&serde_json::to_string(s).expect("strings always serialize"));
+}
+
+/// Write the finite `n` as ECMAScript's `Number.prototype.toString` does.
+fn write_number(out: &mut String, n: f64) {
+    if n == 0.0 {
+        // Also covers -0
+        out.push('0');
+        return;
+    }
+    if n < 0.0 {
+        out.push('-');
+    }
+    // Rust's shortest round-trip digits, e.g. "1.2345e-7"
+    let scientific = format!("{:e}", n.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("finite numbers have an exponent");
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // The value is 0.<digits> × 10^n
+    let n = exponent.parse::<i32>().expect("exponent is an integer") + 1;
+
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', -n as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rfc_example() {
+        // RFC 8785 section 3.2.2
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            canonicalize(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn test_key_order() {
+        // RFC 8785 section 3.2.3: UTF-16 order puts U+1F600 before U+FB33
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis"
+        });
+        let canonical = canonicalize(&value);
+        let positions: Vec<usize> = [
+            "Carriage", "One", "Control", "Latin", "Euro", "Emoji", "Hebrew",
+        ]
+        .iter()
+        .map(|name| canonical.find(name).unwrap())
+        .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", canonical);
+    }
+
+    #[test]
+    fn test_numbers() {
+        let cases = [
+            (json!(0), "0"),
+            (json!(-0.0), "0"),
+            (json!(-12), "-12"),
+            (json!(1e20), "100000000000000000000"),
+            (json!(1e21), "1e+21"),
+            (json!(1.5e-6), "0.0000015"),
+            (json!(1e-7), "1e-7"),
+            (json!(-1.25e-8), "-1.25e-8"),
+            (json!(123.456), "123.456"),
+            (json!(9007199254740993u64), "9007199254740992"),
+            (json!(f64::MAX), "1.7976931348623157e+308"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(canonicalize(&value), expected, "{}", value);
+        }
+        #[cfg(feature = "arbitrary-precision")]
+        {
+            let value: Value = serde_json::from_str(r#"{"a": 1e400, "b": -1E400}"#).unwrap();
+            assert_eq!(canonicalize(&value), r#"{"a":1e+400,"b":-1e+400}"#);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! A machine-readable description of what this build supports.
+//!
+//! Editors and CI actions that wrap the CLI need to know which drafts, input
+//! formats and output formats they can rely on. Comparing version numbers
+//! breaks as soon as a feature is backported or compiled out, so
+//! [`capabilities`] reports them directly, and `--capabilities` prints the
+//! same document.
+//!
+//! Keys are only ever added; [`CAPABILITIES_VERSION`] is raised if an existing
+//! key changes meaning or is removed.
+
+use crate::Draft;
+use serde_json::{json, Value};
+
+/// Version of the layout of the [`capabilities`] document.
+pub const CAPABILITIES_VERSION: u64 = 1;
+
+/// Describe the drafts, features and formats supported by this build.
+///
+/// Compile-time features, such as the `tower` middleware, XML input or the
+/// Redis cache, are reported as they were enabled when the crate was built.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::capabilities;
+///
+/// let capabilities = capabilities();
+/// let drafts = capabilities["drafts"].as_array().unwrap();
+/// assert!(drafts.contains(&"2020-12".into()));
+/// ```
+pub fn capabilities() -> Value {
+    let drafts: Vec<String> = Draft::ALL.iter().map(Draft::to_string).collect();
+    let mut input_formats = vec!["json", "yaml", "json5", "jsonc", "csv", "ini", "properties"];
+    if cfg!(feature = "xml") {
+        input_formats.push("xml");
+    }
+    if cfg!(feature = "cbor") {
+        input_formats.push("cbor");
+    }
+    if cfg!(feature = "bson") {
+        input_formats.push("bson");
+    }
+    let mut cache_backends = vec!["filesystem"];
+    if cfg!(feature = "redis") {
+        cache_backends.push("redis");
+    }
+
+    json!({
+        "capabilities_version": CAPABILITIES_VERSION,
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "drafts": drafts,
+        "default_draft": Draft::Draft7.to_string(),
+        "input_formats": input_formats,
+        "features": {
+            "formats": {"assertion": true, "custom": true},
+            "custom_keywords": true,
+            "warnings": ["deprecated", "format"],
+            "schema_sources": ["file", "http", "https", "registry"],
+            "cache_backends": cache_backends,
+            "tower_middleware": cfg!(feature = "tower"),
+            "arbitrary_precision": cfg!(feature = "arbitrary-precision"),
+        },
+        "output_formats": {
+            "validate": ["text", "json"],
+            "batch": ["text", "json", "ndjson"],
+            "catalog": ["text", "json"],
+            "tail": ["ndjson"],
+            "get": ["text", "json"],
+            "canonicalize": ["json", "sha256"],
+            "env": ["text"],
+            "snippets": ["vscode", "intellij"],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_drafts_parse() {
+        let capabilities = capabilities();
+        let drafts: Vec<Draft> = capabilities["drafts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|draft| draft.as_str().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(drafts, Draft::ALL);
+        assert_eq!(capabilities["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            capabilities["features"]["tower_middleware"],
+            cfg!(feature = "tower")
+        );
+    }
+}
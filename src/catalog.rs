@@ -0,0 +1,814 @@
+//! Choosing schemas for files from a schema catalog.
+//!
+//! A catalog maps file name patterns to schemas, in the format of the
+//! [SchemaStore](https://www.schemastore.org/api/json/catalog.json) catalog:
+//!
+//! ```json
+//! {"schemas": [{"name": "GitHub Workflow", "fileMatch": [".github/workflows/*.yml"], "url": "..."}]}
+//! ```
+//!
+//! Patterns are globs where `*` and `?` stay within one path segment and `**`
+//! spans any number of them. A pattern without a `/` is matched against the
+//! file name; one with a `/` against the end of the path, starting at a
+//! segment boundary. Patterns starting with `!` exclude files an entry would
+//! otherwise match.
+//!
+//! When several entries match a file, the one with the highest `priority`
+//! (an integer, 0 if omitted) is used, and of those the first in catalog
+//! order, so put specific entries before general ones. [`SelectionReport`]
+//! records every candidate so conflicts can be audited.
+//!
+//! Alternatively, a [`SchemaPolicy`] can apply every matching entry in that
+//! order, e.g. a base schema and an environment-specific overlay, each
+//! failure labelled with the entry that produced it.
+
+use crate::{is_url, load_schema, ValidationError};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// File extensions that [`find_documents`] picks up.
+const DOCUMENT_EXTENSIONS: &[&str] = &["json", "json5", "jsonc", "yaml", "yml"];
+
+/// Directories [`find_documents`] and [`expand_glob`] never descend into.
+const SKIPPED_DIRECTORIES: &[&str] = &[".git", "node_modules", "target"];
+
+/// The file, in gitignore syntax, listing what [`find_documents`] and
+/// [`expand_glob`] skip in its directory and those under it.
+pub const IGNORE_FILE: &str = ".validationignore";
+
+/// How a file is assigned a schema when several catalog entries match it.
+pub const CONFLICT_RESOLUTION: &str =
+    "highest priority, then first matching entry in catalog order";
+
+/// Which of the entries matching a file it is validated against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaPolicy {
+    /// Only the preferred entry, as described by [`CONFLICT_RESOLUTION`].
+    #[default]
+    First,
+    /// Every matching entry in priority order, collecting the failures of
+    /// all of them.
+    All,
+    /// Every matching entry in priority order, stopping at the first one the
+    /// file fails.
+    FailFast,
+}
+
+impl std::str::FromStr for SchemaPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Self::First),
+            "all" => Ok(Self::All),
+            "fail-fast" => Ok(Self::FailFast),
+            other => Err(format!(
+                "unknown policy '{}': expected first, all or fail-fast",
+                other
+            )),
+        }
+    }
+}
+
+/// One schema in a catalog and the files it applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogEntry {
+    /// Display name of the schema.
+    pub name: String,
+    /// Glob patterns of the files the schema applies to.
+    pub file_match: Vec<String>,
+    /// Schema location: a URL, or a path relative to the catalog file.
+    pub url: String,
+    /// Preference among entries matching the same file; higher wins.
+    pub priority: i64,
+}
+
+/// A list of schemas and the files they apply to.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    entries: Vec<CatalogEntry>,
+    /// Compiled `fileMatch` patterns of each entry, and whether each excludes.
+    patterns: Vec<Vec<(Regex, bool)>>,
+}
+
+impl Catalog {
+    /// Read a catalog from its JSON value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `schemas` is missing or an entry lacks a `url` or
+    /// has a malformed `fileMatch` or a `priority` that is not an integer.
+    pub fn from_value(catalog: &Value) -> Result<Self, ValidationError> {
+        let invalid =
+            |message: String| ValidationError::SchemaLoad(format!("catalog: {}", message));
+        let schemas = catalog
+            .get("schemas")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid("expected a `schemas` array".to_string()))?;
+
+        let mut entries = Vec::new();
+        let mut patterns = Vec::new();
+        for (index, schema) in schemas.iter().enumerate() {
+            let url = schema
+                .get("url")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid(format!("entry {} has no `url`", index)))?;
+            let name = schema
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or(url)
+                .to_string();
+            let file_match = match schema.get("fileMatch") {
+                None => Vec::new(),
+                Some(Value::Array(globs)) => globs
+                    .iter()
+                    .map(|glob| glob.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid(format!("`fileMatch` of '{}' must be strings", name)))?,
+                Some(_) => {
+                    return Err(invalid(format!("`fileMatch` of '{}' must be a list", name)))
+                }
+            };
+            let priority = match schema.get("priority") {
+                None => 0,
+                Some(priority) => priority.as_i64().ok_or_else(|| {
+                    invalid(format!("`priority` of '{}' must be an integer", name))
+                })?,
+            };
+            patterns.push(file_match.iter().map(|glob| compile_glob(glob)).collect());
+            entries.push(CatalogEntry {
+                name,
+                file_match,
+                url: url.to_string(),
+                priority,
+            });
+        }
+        Ok(Catalog { entries, patterns })
+    }
+
+    /// Load a catalog from a local file or URL.
+    ///
+    /// Relative `url`s in a local catalog are resolved against the catalog's
+    /// directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the catalog cannot be loaded or is malformed.
+    pub fn load(input: &str) -> Result<Self, ValidationError> {
+        let mut catalog = Catalog::from_value(&load_schema(input)?)?;
+        if !is_url(input) {
+            let directory = Path::new(input).parent().unwrap_or(Path::new(""));
+            for entry in &mut catalog.entries {
+                if !is_url(&entry.url) && Path::new(&entry.url).is_relative() {
+                    entry.url = directory.join(&entry.url).to_string_lossy().into_owned();
+                }
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// The entries of the catalog, in order.
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    /// The entries whose patterns match `path`, highest priority first and
+    /// otherwise in catalog order. The first one is the schema the file is
+    /// validated against.
+    ///
+    /// `path` is matched with `/` separators, so pass it relative to the root
+    /// of the tree being validated.
+    pub fn candidates(&self, path: &Path) -> Vec<&CatalogEntry> {
+        let path = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut candidates: Vec<&CatalogEntry> = self
+            .entries
+            .iter()
+            .zip(&self.patterns)
+            .filter(|(_, patterns)| {
+                let matching = |exclude: bool| {
+                    patterns
+                        .iter()
+                        .any(|(regex, negated)| *negated == exclude && regex.is_match(&path))
+                };
+                matching(false) && !matching(true)
+            })
+            .map(|(entry, _)| entry)
+            .collect();
+        // Stable, so equal priorities keep catalog order
+        candidates.sort_by_key(|entry| std::cmp::Reverse(entry.priority));
+        candidates
+    }
+
+    /// Choose a schema for each of `files`, matching their paths relative to
+    /// `root`.
+    pub fn select(&self, root: &Path, files: &[PathBuf]) -> SelectionReport {
+        let selections = files
+            .iter()
+            .map(|file| {
+                let relative = file.strip_prefix(root).unwrap_or(file);
+                FileSelection {
+                    path: file.clone(),
+                    candidates: self.candidates(relative).into_iter().cloned().collect(),
+                }
+            })
+            .collect();
+        SelectionReport {
+            selections,
+            policy: SchemaPolicy::First,
+        }
+    }
+}
+
+/// The catalog entries matching one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSelection {
+    /// The file.
+    pub path: PathBuf,
+    /// Matching entries in priority order; empty if the file is unmatched.
+    pub candidates: Vec<CatalogEntry>,
+}
+
+impl FileSelection {
+    /// The entry the file is validated against.
+    pub fn chosen(&self) -> Option<&CatalogEntry> {
+        self.candidates.first()
+    }
+
+    /// Whether more than one entry matched the file.
+    pub fn is_conflict(&self) -> bool {
+        self.candidates.len() > 1
+    }
+
+    /// The entries the file is validated against under `policy`, in order.
+    pub fn applied(&self, policy: SchemaPolicy) -> &[CatalogEntry] {
+        match policy {
+            SchemaPolicy::First => &self.candidates[..self.candidates.len().min(1)],
+            SchemaPolicy::All | SchemaPolicy::FailFast => &self.candidates,
+        }
+    }
+}
+
+/// Which schema was chosen for each file of a catalog run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionReport {
+    /// One selection per file, in the order the files were given.
+    pub selections: Vec<FileSelection>,
+    /// Which matching entries the files are validated against.
+    pub policy: SchemaPolicy,
+}
+
+impl SelectionReport {
+    /// Files no catalog entry matched.
+    pub fn unmatched(&self) -> impl Iterator<Item = &FileSelection> {
+        self.selections
+            .iter()
+            .filter(|selection| selection.candidates.is_empty())
+    }
+
+    /// The report as JSON: the `matched` files with their entry, the
+    /// `unmatched` files, and the `conflicts` with every candidate and the
+    /// decision taken. Under a policy applying every matching entry, files
+    /// matching several are not conflicts, and list them all as `schemas`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use std::path::{Path, PathBuf};
+    /// use validate_json_schema::Catalog;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let catalog = Catalog::from_value(&json!({"schemas": [
+    ///     {"name": "Compose", "fileMatch": ["docker-compose.yml"], "url": "compose.json"}
+    /// ]}))?;
+    /// let files = [PathBuf::from("docker-compose.yml"), PathBuf::from("app.yml")];
+    /// let report = catalog.select(Path::new(""), &files).to_json();
+    ///
+    /// assert_eq!(report["matched"][0]["schema"], "Compose");
+    /// assert_eq!(report["unmatched"], json!(["app.yml"]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_json(&self) -> Value {
+        let mut matched = Vec::new();
+        let mut unmatched = Vec::new();
+        let mut conflicts = Vec::new();
+        for selection in &self.selections {
+            let path = selection.path.display().to_string();
+            let Some(chosen) = selection.chosen() else {
+                unmatched.push(json!(path));
+                continue;
+            };
+            let candidates: Vec<&str> = selection
+                .candidates
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect();
+            let mut file = json!({"path": path, "schema": chosen.name, "url": chosen.url});
+            if self.policy != SchemaPolicy::First {
+                file["schemas"] = json!(candidates);
+            } else if selection.is_conflict() {
+                conflicts.push(json!({
+                    "path": path,
+                    "candidates": candidates,
+                    "chosen": chosen.name,
+                    "reason": CONFLICT_RESOLUTION,
+                }));
+            }
+            matched.push(file);
+        }
+        json!({"matched": matched, "unmatched": unmatched, "conflicts": conflicts})
+    }
+}
+
+/// The YAML and JSON files under `root`, or `root` itself if it is a file,
+/// sorted by path. Version control and dependency directories are skipped,
+/// as are the paths listed in [`IGNORE_FILE`]s. Their patterns use the
+/// gitignore syntax, except for `[...]` classes: a pattern with no `/`
+/// other than a trailing one matches names at any depth, a trailing `/`
+/// only matches directories, and `!` re-includes what earlier patterns
+/// excluded.
+///
+/// As with `.gitignore`, the [`IGNORE_FILE`]s of the directories above
+/// `root` apply too, up to the top of the git repository it is in. Outside
+/// of a repository only those in `root` and below are read.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read.
+pub fn find_documents(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut documents = Vec::new();
+    if root.is_file() {
+        documents.push(root.to_path_buf());
+        return Ok(documents);
+    }
+
+    let (relative, ignores) = enclosing_ignores(root)?;
+    let mut pending = vec![(root.to_path_buf(), relative, ignores)];
+    while let Some((directory, relative, mut ignores)) = pending.pop() {
+        read_ignore_file(&directory, &relative, &mut ignores)?;
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative = format!("{}{}", relative, name);
+            let is_dir = entry.file_type()?.is_dir();
+            if is_ignored(&ignores, &relative, is_dir) {
+                continue;
+            }
+            if is_dir {
+                if !SKIPPED_DIRECTORIES.contains(&name.as_str()) {
+                    pending.push((path, relative + "/", ignores.clone()));
+                }
+            } else if path.extension().is_some_and(|extension| {
+                DOCUMENT_EXTENSIONS.contains(&extension.to_string_lossy().as_ref())
+            }) {
+                documents.push(path);
+            }
+        }
+    }
+    documents.sort();
+    Ok(documents)
+}
+
+/// The path of `root` relative to the top of the git repository it is in,
+/// ending in `/` unless empty, and the [`IGNORE_FILE`]s of the directories
+/// from that top down to the parent of `root`, outermost first. Outside of a
+/// repository, `root` is its own top.
+fn enclosing_ignores(root: &Path) -> io::Result<(String, Vec<Rc<IgnoreFile>>)> {
+    let root = fs::canonicalize(root)?;
+    let Some(top) = root.ancestors().find(|dir| dir.join(".git").exists()) else {
+        return Ok((String::new(), Vec::new()));
+    };
+    let mut ignores = Vec::new();
+    let mut relative = String::new();
+    let mut directory = top.to_path_buf();
+    for component in root.strip_prefix(top).unwrap_or(Path::new("")) {
+        read_ignore_file(&directory, &relative, &mut ignores)?;
+        directory.push(component);
+        relative = format!("{}{}/", relative, component.to_string_lossy());
+    }
+    Ok((relative, ignores))
+}
+
+/// Add the [`IGNORE_FILE`] of `directory`, at `relative` under the top of the
+/// walk, to `ignores` if there is one.
+fn read_ignore_file(
+    directory: &Path,
+    relative: &str,
+    ignores: &mut Vec<Rc<IgnoreFile>>,
+) -> io::Result<()> {
+    match fs::read_to_string(directory.join(IGNORE_FILE)) {
+        Ok(content) => ignores.push(Rc::new(IgnoreFile::parse(relative, &content))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// The patterns of an [`IGNORE_FILE`].
+struct IgnoreFile {
+    /// The path of its directory under the top of the walk, ending in `/`
+    /// unless empty.
+    directory: String,
+    /// Each pattern, whether it re-includes what it matches, and whether it
+    /// only matches directories.
+    patterns: Vec<(Regex, bool, bool)>,
+}
+
+impl IgnoreFile {
+    fn parse(directory: &str, content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (line, negated) = match line.strip_prefix('!') {
+                    Some(line) => (line, true),
+                    // `\#` and `\!` start patterns with those characters
+                    None => (line.strip_prefix('\\').unwrap_or(line), false),
+                };
+                let (line, directory_only) = match line.strip_suffix('/') {
+                    Some(line) => (line, true),
+                    None => (line, false),
+                };
+                let pattern = match line.strip_prefix('/') {
+                    Some(line) => format!("^{}$", glob_regex(line)),
+                    None if line.contains('/') => format!("^{}$", glob_regex(line)),
+                    None => format!("(?:^|/){}$", glob_regex(line)),
+                };
+                let regex = Regex::new(&pattern).expect("escaped glob is a valid regex");
+                (regex, negated, directory_only)
+            })
+            .collect();
+        Self {
+            directory: directory.to_string(),
+            patterns,
+        }
+    }
+
+    /// Whether the file ignores `path`, relative to the top of the walk, if any of its
+    /// patterns match it.
+    fn ignores(&self, path: &str, is_dir: bool) -> Option<bool> {
+        let path = path.strip_prefix(&self.directory)?;
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(regex, _, directory_only)| (is_dir || !directory_only) && regex.is_match(path))
+            .map(|(_, negated, _)| !negated)
+    }
+}
+
+/// Whether `ignores`, outermost first, ignore `path`. The last matching
+/// pattern of the innermost file with one decides.
+fn is_ignored(ignores: &[Rc<IgnoreFile>], path: &str, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .find_map(|ignore| ignore.ignores(path, is_dir))
+        .unwrap_or(false)
+}
+
+/// The files matching `pattern`, sorted by path, for shells that do not
+/// expand globs themselves. Patterns are globs as in the
+/// [module documentation](self), but always match whole paths: `*.yml` only
+/// matches files in the current directory, and `configs/**/*.yml` those
+/// anywhere under `configs`. Version control and dependency directories are
+/// skipped, as are the paths listed in [`IGNORE_FILE`]s, read as
+/// [`find_documents`] does from the directory the search starts in.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read.
+pub fn expand_glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let pattern = if cfg!(windows) {
+        pattern.replace('\\', "/")
+    } else {
+        pattern.to_string()
+    };
+    // The directory before the first segment with a wildcard
+    let wildcard = pattern.find(['*', '?']).unwrap_or(pattern.len());
+    let (base, glob) = pattern.split_at(pattern[..wildcard].rfind('/').map_or(0, |i| i + 1));
+    let regex =
+        Regex::new(&format!("^{}$", glob_regex(glob))).expect("escaped glob is a valid regex");
+    let depth = (!glob.contains("**")).then(|| glob.matches('/').count());
+
+    let mut matches = Vec::new();
+    let root = if base.is_empty() { "." } else { base };
+    if !Path::new(root).is_dir() {
+        return Ok(matches);
+    }
+    let (top, ignores) = enclosing_ignores(Path::new(root))?;
+    let mut pending = vec![(PathBuf::from(root), String::new(), 0, ignores)];
+    while let Some((directory, relative, level, mut ignores)) = pending.pop() {
+        read_ignore_file(&directory, &format!("{}{}", top, relative), &mut ignores)?;
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative = format!("{}{}", relative, name);
+            let is_dir = entry.file_type()?.is_dir();
+            if is_ignored(&ignores, &format!("{}{}", top, relative), is_dir) {
+                continue;
+            }
+            if is_dir {
+                if !SKIPPED_DIRECTORIES.contains(&name.as_str())
+                    && depth.is_none_or(|depth| level < depth)
+                {
+                    pending.push((entry.path(), relative + "/", level + 1, ignores.clone()));
+                }
+            } else if regex.is_match(&relative) {
+                matches.push(Path::new(base).join(relative));
+            }
+        }
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Compile a `fileMatch` glob into a regex over `/`-separated paths, and
+/// whether it is an exclusion.
+fn compile_glob(glob: &str) -> (Regex, bool) {
+    let (glob, negated) = match glob.strip_prefix('!') {
+        Some(glob) => (glob, true),
+        None => (glob, false),
+    };
+    let glob = glob.trim_start_matches("./");
+
+    // Without a `/`, the glob cannot match across segments, so matching at
+    // the last segment boundary is matching the file name
+    let pattern = format!("(?:^|/){}$", glob_regex(glob));
+    (
+        Regex::new(&pattern).expect("escaped glob is a valid regex"),
+        negated,
+    )
+}
+
+/// Translate a glob into the regex syntax, unanchored.
+fn glob_regex(glob: &str) -> String {
+    let mut pattern = String::new();
+    let mut rest = glob;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            pattern.push_str("(?:[^/]*/)*");
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            pattern.push_str(".*");
+            rest = after;
+            continue;
+        }
+        match c {
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        Catalog::from_value(&json!({"schemas": [
+            {"name": "Workflow", "fileMatch": [".github/workflows/*.yml"], "url": "workflow.json"},
+            {"name": "Compose", "fileMatch": ["docker-compose.yml", "docker-compose.*.yml"], "url": "compose.json"},
+            {"name": "Config", "fileMatch": ["config/**/*.yaml", "!config/**/local.yaml"], "url": "config.json"},
+            {"name": "YAML", "fileMatch": ["*.yml"], "url": "yaml.json"}
+        ]}))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_candidates() {
+        let catalog = catalog();
+        let names = |path: &str| -> Vec<String> {
+            catalog
+                .candidates(Path::new(path))
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(".github/workflows/ci.yml"), ["Workflow", "YAML"]);
+        assert_eq!(names("app/docker-compose.prod.yml"), ["Compose", "YAML"]);
+        assert_eq!(names("config/a/b/app.yaml"), ["Config"]);
+        assert_eq!(names("config/app.yaml"), ["Config"]);
+        assert!(names("config/a/local.yaml").is_empty());
+        assert!(names("workflows/ci.yaml").is_empty());
+        assert!(names("notgithub/workflows/ci.json").is_empty());
+    }
+
+    #[test]
+    fn test_selection_report() {
+        let files = [
+            PathBuf::from("repo/.github/workflows/ci.yml"),
+            PathBuf::from("repo/package.json"),
+        ];
+        let report = catalog().select(Path::new("repo"), &files);
+        assert_eq!(report.unmatched().count(), 1);
+        assert_eq!(
+            report.to_json(),
+            json!({
+                "matched": [{
+                    "path": "repo/.github/workflows/ci.yml",
+                    "schema": "Workflow",
+                    "url": "workflow.json"
+                }],
+                "unmatched": ["repo/package.json"],
+                "conflicts": [{
+                    "path": "repo/.github/workflows/ci.yml",
+                    "candidates": ["Workflow", "YAML"],
+                    "chosen": "Workflow",
+                    "reason": CONFLICT_RESOLUTION
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_priority_and_policy() {
+        let catalog = Catalog::from_value(&json!({"schemas": [
+            {"name": "Base", "fileMatch": ["config/*.yaml"], "url": "base.json"},
+            {"name": "Production", "fileMatch": ["config/prod.yaml"], "url": "prod.json", "priority": 1},
+            {"name": "Any", "fileMatch": ["*.yaml"], "url": "any.json"}
+        ]}))
+        .unwrap();
+        let files = [PathBuf::from("config/prod.yaml")];
+        let mut report = catalog.select(Path::new(""), &files);
+        let file = &report.selections[0];
+        let names = |entries: &[CatalogEntry]| -> Vec<String> {
+            entries.iter().map(|entry| entry.name.clone()).collect()
+        };
+        assert_eq!(names(&file.candidates), ["Production", "Base", "Any"]);
+        assert_eq!(names(file.applied(SchemaPolicy::First)), ["Production"]);
+        assert_eq!(file.applied(SchemaPolicy::FailFast).len(), 3);
+
+        report.policy = SchemaPolicy::All;
+        let json = report.to_json();
+        assert_eq!(
+            json["matched"][0]["schemas"],
+            json!(["Production", "Base", "Any"])
+        );
+        assert_eq!(json["conflicts"], json!([]));
+
+        assert_eq!("fail-fast".parse(), Ok(SchemaPolicy::FailFast));
+        assert!("every".parse::<SchemaPolicy>().is_err());
+        assert!(
+            Catalog::from_value(&json!({"schemas": [{"url": "x", "priority": "high"}]})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_catalog() {
+        assert!(Catalog::from_value(&json!({"schemas": [{"name": "x"}]})).is_err());
+        assert!(
+            Catalog::from_value(&json!({"schemas": [{"url": "x", "fileMatch": "*"}]})).is_err()
+        );
+        assert!(Catalog::from_value(&json!([])).is_err());
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in [
+            "a.yaml",
+            "configs/b.yaml",
+            "configs/c.json",
+            "configs/d/e.yaml",
+            "configs/node_modules/f.yaml",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+        let root = dir.path().to_str().unwrap();
+        let expand = |pattern: &str| -> Vec<PathBuf> {
+            expand_glob(&format!("{}/{}", root, pattern))
+                .unwrap()
+                .into_iter()
+                .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+                .collect()
+        };
+        assert_eq!(
+            expand("configs/**/*.yaml"),
+            [
+                PathBuf::from("configs/b.yaml"),
+                PathBuf::from("configs/d/e.yaml")
+            ]
+        );
+        assert_eq!(expand("*.yaml"), [PathBuf::from("a.yaml")]);
+        assert_eq!(
+            expand("configs/?.*"),
+            [
+                PathBuf::from("configs/b.yaml"),
+                PathBuf::from("configs/c.json")
+            ]
+        );
+        assert!(expand("missing/*.yaml").is_empty());
+    }
+
+    #[test]
+    fn test_ignore_file() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in [
+            "a.yml",
+            "generated.yml",
+            "fixtures/b.yml",
+            "fixtures/keep.yml",
+            "vendor/c.json",
+            "src/vendor.json",
+            "src/d.yaml",
+            "src/local.yaml",
+            "src/vendor/e.yml",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+        let ignore = "# Generated\n/generated.yml\nvendor/\nfixtures/*\n!fixtures/keep.yml\n";
+        fs::write(dir.path().join(IGNORE_FILE), ignore).unwrap();
+        fs::write(dir.path().join("src").join(IGNORE_FILE), "local.*\n").unwrap();
+
+        let documents: Vec<PathBuf> = find_documents(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            documents,
+            [
+                PathBuf::from("a.yml"),
+                PathBuf::from("fixtures/keep.yml"),
+                PathBuf::from("src/d.yaml"),
+                PathBuf::from("src/vendor.json"),
+            ]
+        );
+
+        let relative = |paths: Vec<PathBuf>| -> Vec<PathBuf> {
+            paths
+                .into_iter()
+                .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+                .collect()
+        };
+        let pattern = format!("{}/**/*.yml", dir.path().display());
+        assert_eq!(
+            relative(expand_glob(&pattern).unwrap()),
+            [PathBuf::from("a.yml"), PathBuf::from("fixtures/keep.yml")]
+        );
+
+        // The ignore files above a directory apply within a repository
+        let src = dir.path().join("src");
+        assert_eq!(find_documents(&src).unwrap().len(), 3);
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        assert_eq!(
+            relative(find_documents(&src).unwrap()),
+            [
+                PathBuf::from("src/d.yaml"),
+                PathBuf::from("src/vendor.json")
+            ]
+        );
+        let pattern = format!("{}/**/*.yml", src.display());
+        assert!(expand_glob(&pattern).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in [
+            "a.yml",
+            "b/c.json",
+            "b/readme.md",
+            ".git/config.json",
+            "b/d/e.yaml",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "{}").unwrap();
+        }
+        let documents: Vec<PathBuf> = find_documents(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            documents,
+            [
+                PathBuf::from("a.yml"),
+                PathBuf::from("b/c.json"),
+                PathBuf::from("b/d/e.yaml")
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Validating CBOR documents against JSON Schemas.
+//!
+//! Devices and message brokers often exchange CBOR, the binary counterpart of
+//! JSON. [`parse_cbor`] converts a CBOR item into the JSON value with the same
+//! meaning, as RFC 8949 section 6.1 suggests:
+//!
+//! - Byte strings become lowercase hexadecimal strings.
+//! - Map keys that are integers become their decimal strings; other keys
+//!   that are not text are rejected.
+//! - Tags are dropped and their content converted, so a date (tag 0) becomes
+//!   its string.
+//! - Integers outside the range of `i64` and `u64` become floats, as large
+//!   numbers in JSON do. NaN and infinities are rejected.
+//!
+//! Available with the `cbor` feature.
+
+use crate::ValidationError;
+use ciborium::value::Value as Cbor;
+use serde_json::{Map, Number, Value};
+use std::path::Path;
+
+/// Deepest nesting accepted, the same as the JSON and YAML parsers.
+const MAX_DEPTH: usize = 128;
+
+/// Parse CBOR `bytes` into a JSON value.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::cbor::parse_cbor;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // {"id": 7, "raw": h'00ff'}
+/// let bytes = [0xa2, 0x62, b'i', b'd', 0x07, 0x63, b'r', b'a', b'w', 0x42, 0x00, 0xff];
+///
+/// assert_eq!(parse_cbor(&bytes)?, json!({"id": 7, "raw": "00ff"}));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the bytes are not a single well-formed CBOR item,
+/// are nested more than 128 levels deep, or hold a value JSON cannot
+/// represent.
+pub fn parse_cbor(bytes: &[u8]) -> Result<Value, ValidationError> {
+    let mut reader = bytes;
+    let item: Cbor = ciborium::de::from_reader_with_recursion_limit(&mut reader, MAX_DEPTH)
+        .map_err(|e| invalid(e.to_string()))?;
+    if !reader.is_empty() {
+        return Err(invalid(format!(
+            "{} bytes follow the first item",
+            reader.len()
+        )));
+    }
+    to_json(item)
+}
+
+/// Whether `path` has the `.cbor` extension.
+pub(crate) fn is_cbor_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("cbor"))
+}
+
+fn invalid(message: String) -> ValidationError {
+    ValidationError::CborParse(message)
+}
+
+fn to_json(item: Cbor) -> Result<Value, ValidationError> {
+    Ok(match item {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(value) => Value::Bool(value),
+        Cbor::Integer(integer) => {
+            let integer = i128::from(integer);
+            if let Ok(value) = i64::try_from(integer) {
+                Value::from(value)
+            } else if let Ok(value) = u64::try_from(integer) {
+                Value::from(value)
+            } else {
+                Value::from(integer as f64)
+            }
+        }
+        Cbor::Float(value) => Number::from_f64(value)
+            .map(Value::Number)
+            .ok_or_else(|| invalid(format!("{} has no JSON equivalent", value)))?,
+        Cbor::Text(text) => Value::String(text),
+        Cbor::Bytes(bytes) => Value::String(hex::encode(bytes)),
+        Cbor::Tag(_, content) => to_json(*content)?,
+        Cbor::Array(items) => {
+            Value::Array(items.into_iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        Cbor::Map(entries) => {
+            let mut object = Map::new();
+            for (key, value) in entries {
+                let key = match key {
+                    Cbor::Text(key) => key,
+                    Cbor::Integer(key) => i128::from(key).to_string(),
+                    _ => return Err(invalid("a map key is not text or an integer".to_string())),
+                };
+                object.insert(key, to_json(value)?);
+            }
+            Value::Object(object)
+        }
+        _ => return Err(invalid("unsupported CBOR value".to_string())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(item: &Cbor) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(item, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_parse_cbor() {
+        let item = Cbor::Map(vec![
+            (Cbor::Text("device".into()), Cbor::Text("s-1".into())),
+            (Cbor::Integer(1.into()), Cbor::Float(21.5)),
+            (
+                Cbor::Text("seen".into()),
+                Cbor::Tag(0, Box::new(Cbor::Text("2024-05-01T12:00:00Z".into()))),
+            ),
+            (
+                Cbor::Text("readings".into()),
+                Cbor::Array(vec![
+                    Cbor::Integer((-3).into()),
+                    Cbor::Integer(u64::MAX.into()),
+                    Cbor::Null,
+                ]),
+            ),
+            (Cbor::Text("mac".into()), Cbor::Bytes(vec![0xde, 0xad])),
+        ]);
+        assert_eq!(
+            parse_cbor(&encode(&item)).unwrap(),
+            json!({
+                "device": "s-1",
+                "1": 21.5,
+                "seen": "2024-05-01T12:00:00Z",
+                "readings": [-3, u64::MAX, null],
+                "mac": "dead"
+            })
+        );
+
+        let float_key = Cbor::Map(vec![(Cbor::Float(1.5), Cbor::Null)]);
+        assert!(parse_cbor(&encode(&float_key)).is_err());
+        assert!(parse_cbor(&encode(&Cbor::Float(f64::NAN))).is_err());
+        assert!(parse_cbor(&[0x82, 0x01]).is_err());
+        assert!(parse_cbor(&[0x01, 0x02]).is_err());
+
+        let deep = [vec![0x81; 100_000], vec![0x01]].concat();
+        assert!(parse_cbor(&deep).is_err());
+    }
+}
@@ -0,0 +1,4722 @@
+//! The command-line interface, as a library.
+//!
+//! [`run`] parses arguments and runs a command exactly as the
+//! `validate-json-schema` binary does, but writes to the given output and
+//! returns the exit status instead of ending the process. Other binaries can
+//! embed the full CLI, for example a company wrapper that adds its own
+//! commands, and tests can drive it without spawning processes. Runs share
+//! no state, so several can proceed at once on different threads.
+
+use crate::batch::{map_ordered, readable_files, validate_file};
+use crate::diagnostic::document_code_frames;
+use crate::github::{self, CheckResult, FileResult, Finding};
+use crate::ini::{validate_ini, IniOptions};
+use crate::owners::CodeOwners;
+use crate::tail::{FollowReader, Framing, MessageStream};
+use crate::yaml::split_documents;
+use crate::{
+    analyze_schema, bundle_schema, canonicalize, capabilities, check_env, check_env_from,
+    check_hygiene, check_style, clear_schema_cache, compare_schemas, compile_schema,
+    default_user_agent, diff_failures, diff_reports, expand_glob, find_documents,
+    find_unknown_keywords, find_yaml_ambiguities, fix_style, generate_snippets, lint_schema,
+    load_schema, migrate_schema, outline_schema, parse_csv, parse_env_file, report_files,
+    suggest_fixes, trace, unified_diff, validate_csv, Baseline, BaselineReporter, BatchOptions,
+    CacheStore, Catalog, CsvOptions, DocumentFormat, Draft, EmptyDocuments, FileError, FileReport,
+    FileStatus, FsCacheStore, IgnoreRules, Localizer, Reporter, SchemaPolicy, SelectionReport,
+    SnippetFormat, SnippetOptions, StyleRules, Summary, ValidationError, Validator,
+    ValidatorBuilder, YamlAmbiguity, YamlSchema, YamlTags,
+};
+use clap::{Arg, ArgMatches, Command};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Exit status of invalid command-line arguments, as for those clap rejects,
+/// and of invalid configuration, ignore rules or baselines they name.
+const EXIT_USAGE: u8 = 2;
+
+/// Exit status when a file in a batch timed out or crashed the validator.
+const EXIT_ABNORMAL: u8 = 3;
+
+/// Exit status when a batch's deadline passed before every file was validated.
+const EXIT_DEADLINE: u8 = 4;
+
+/// Exit status when a document could not be parsed.
+const EXIT_PARSE: u8 = 5;
+
+/// Exit status when a schema could not be parsed or compiled.
+const EXIT_SCHEMA: u8 = 6;
+
+/// Exit status when a file could not be read or a schema fetched.
+const EXIT_IO: u8 = 7;
+
+/// Exit status when `get` finds no value at its pointer.
+const EXIT_MISSING: u8 = 8;
+
+/// Reported for files without a catalog entry under `--require-schema`.
+const UNMATCHED: &str = "No schema in the catalog matches this file";
+
+/// Largest record `tail --follow` buffers; longer lines are reported and
+/// skipped, so a runaway writer cannot exhaust memory.
+const FOLLOW_MAX_RECORD: usize = 16 * 1024 * 1024;
+
+/// Write a line to the command's output. Write errors are ignored, as there
+/// is nowhere left to report them.
+macro_rules! outln {
+    ($io:expr, $($arg:tt)*) => {{
+        let _ = writeln!($io.out, $($arg)*);
+    }};
+}
+
+/// Write to the command's output without a line break.
+macro_rules! out {
+    ($io:expr, $($arg:tt)*) => {{
+        let _ = write!($io.out, $($arg)*);
+    }};
+}
+
+/// Write a line to the command's diagnostics.
+macro_rules! errln {
+    ($io:expr, $($arg:tt)*) => {{
+        let _ = writeln!($io.err, $($arg)*);
+    }};
+}
+
+/// The streams a run reads from and writes to.
+struct Console<'a> {
+    /// Messages for `tail`; the process's stdin if `None`.
+    input: Option<&'a mut dyn BufRead>,
+    out: &'a mut (dyn Write + Send),
+    err: &'a mut (dyn Write + Send),
+    /// Whether usage errors may be colored.
+    color: bool,
+    /// The catalog of the `--locale` in effect.
+    messages: Localizer,
+    /// Results so far, for `--status-fd`.
+    tally: Tally,
+    /// Results of each file so far, if `--ci` reports them.
+    github: Option<Vec<FileResult>>,
+    /// Whether `--quiet` leaves out successes and warnings.
+    quiet: bool,
+}
+
+/// A failed run's exit status, returned once the failure has been reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Exit(u8);
+
+/// The exit status of failed validations and of errors without a status of
+/// their own.
+const FAILED: Exit = Exit(1);
+
+impl Exit {
+    /// The exit status for `error`, raised while reading, parsing or
+    /// validating a document.
+    fn of(error: &ValidationError) -> Exit {
+        use ValidationError::*;
+        match error {
+            FileRead(_) | HttpRequest(_) | HttpStatus(_) | HttpConfig(_) | InvalidUrl(_)
+            | CacheDirectory(_) | Cache(_) => Exit(EXIT_IO),
+            SchemaCompilation(_) | SchemaLoad(_) | UnsupportedMigration(_) | InvalidCompiled(_) => {
+                Exit(EXIT_SCHEMA)
+            }
+            YamlParse(_)
+            | YamlDirective(_)
+            | YamlAlias(_)
+            | YamlTag(_)
+            | DuplicateKey(_)
+            | JsonParse(_)
+            | CsvParse(_)
+            | XmlParse(_)
+            | CborParse(_)
+            | BsonParse(_)
+            | IniParse(_)
+            | PropertiesParse(_)
+            | JsonSyntax { .. }
+            | LimitExceeded(_)
+            | EmptyDocument => Exit(EXIT_PARSE),
+            InvalidPointer(_) | Config(_) | InvalidReport(_) => Exit(EXIT_USAGE),
+            ValidationFailed(_) => FAILED,
+        }
+    }
+
+    /// The exit status for `error`, raised while loading or compiling a
+    /// schema: anything but failing to read or fetch it is the schema's fault.
+    fn of_schema(error: &ValidationError) -> Exit {
+        match Exit::of(error) {
+            Exit(EXIT_IO) => Exit(EXIT_IO),
+            _ => Exit(EXIT_SCHEMA),
+        }
+    }
+}
+
+type Outcome = Result<(), Exit>;
+
+/// Counts of a run's validation results, reported by `--status-fd`.
+#[derive(Debug, Default)]
+struct Tally {
+    valid: usize,
+    invalid: usize,
+    errors: usize,
+    skipped: usize,
+}
+
+impl Tally {
+    fn record(&mut self, status: &FileStatus) {
+        match status {
+            FileStatus::Valid => self.valid += 1,
+            FileStatus::Failed(ValidationError::ValidationFailed(_)) => self.invalid += 1,
+            FileStatus::Failed(_) | FileStatus::TimedOut(_) | FileStatus::Panicked(_) => {
+                self.errors += 1
+            }
+            FileStatus::Skipped
+            | FileStatus::Empty
+            | FileStatus::Vanished(_)
+            | FileStatus::Changed => self.skipped += 1,
+        }
+    }
+}
+
+/// Run the CLI with `args`, the first of which is the program name, writing
+/// results to `out` and diagnostics to stderr. `tail` reads stdin.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::process::ExitCode;
+/// use validate_json_schema::cli;
+///
+/// let mut out = Vec::new();
+/// let status = cli::run(["validate-json-schema", "--capabilities"], &mut out);
+/// assert_eq!(status, ExitCode::SUCCESS);
+///
+/// let capabilities: serde_json::Value = serde_json::from_slice(&out).unwrap();
+/// assert!(capabilities["drafts"].is_array());
+/// ```
+pub fn run<I, T>(args: I, out: &mut (impl Write + Send)) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut err = io::stderr();
+    execute(
+        args,
+        Console {
+            input: None,
+            out,
+            color: err.is_terminal(),
+            err: &mut err,
+            messages: Localizer::default(),
+            tally: Tally::default(),
+            github: None,
+            quiet: false,
+        },
+    )
+}
+
+/// Run the CLI with `args` as [`run`] does, with `input` for `tail` and
+/// diagnostics written to `err`.
+pub fn run_with<I, T>(
+    args: I,
+    input: &mut impl BufRead,
+    out: &mut (impl Write + Send),
+    err: &mut (impl Write + Send),
+) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    execute(
+        args,
+        Console {
+            input: Some(input),
+            out,
+            err,
+            color: false,
+            messages: Localizer::default(),
+            tally: Tally::default(),
+            github: None,
+            quiet: false,
+        },
+    )
+}
+
+fn execute<I, T>(args: I, mut io: Console<'_>) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let started = Instant::now();
+    let matches = match command().try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(e) if e.use_stderr() => {
+            let _ = if io.color {
+                write!(io.err, "{}", e.render().ansi())
+            } else {
+                write!(io.err, "{}", e.render())
+            };
+            return ExitCode::from(e.exit_code() as u8);
+        }
+        Err(e) => {
+            out!(io, "{}", e.render());
+            return ExitCode::SUCCESS;
+        }
+    };
+    io.messages = match matches.get_one::<String>("locale") {
+        Some(locale) => Localizer::new(locale),
+        None => Localizer::from_env(),
+    };
+    if matches.get_one::<String>("ci").is_some() {
+        io.github = Some(Vec::new());
+    }
+    io.quiet = matches.get_flag("quiet");
+    let Exit(status) = dispatch(&matches, &mut io).err().unwrap_or(Exit(0));
+    if let Some(results) = io.github.take() {
+        report_github(&results, &matches, &mut io);
+    }
+    if let Some(&fd) = matches.get_one::<u32>("status-fd") {
+        let tally = &io.tally;
+        let line = serde_json::json!({
+            "command": matches.subcommand_name().unwrap_or("validate"),
+            "exit_code": status,
+            "duration_ms": started.elapsed().as_millis() as u64,
+            "valid": tally.valid,
+            "invalid": tally.invalid,
+            "errors": tally.errors,
+            "skipped": tally.skipped,
+        });
+        if let Err(e) = write_status(fd, &line.to_string(), &mut io) {
+            errln!(
+                io,
+                "Warning: cannot write the run status to fd {}: {}",
+                fd,
+                e
+            );
+        }
+    }
+    let _ = io.out.flush();
+    ExitCode::from(status)
+}
+
+/// Write the `--ci github` outputs for `results`: annotations to the output,
+/// a job summary appended to `--step-summary` or `$GITHUB_STEP_SUMMARY`, and
+/// a SARIF log to `--sarif-file`.
+fn report_github(results: &[FileResult], matches: &ArgMatches, io: &mut Console) {
+    out!(io, "{}", github::annotations(results));
+    let summary_path = matches
+        .get_one::<String>("step-summary")
+        .cloned()
+        .or_else(|| std::env::var("GITHUB_STEP_SUMMARY").ok());
+    if let Some(path) = summary_path {
+        let written = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(github::step_summary(results).as_bytes()));
+        if let Err(e) = written {
+            errln!(
+                io,
+                "Warning: cannot write the job summary to {}: {}",
+                path,
+                e
+            );
+        }
+    }
+    let sarif_path = matches.get_one::<String>("sarif-file").expect("defaulted");
+    let log = serde_json::to_string_pretty(&github::sarif(results)).expect("serializable");
+    if let Err(e) = fs::write(sarif_path, log + "\n") {
+        errln!(
+            io,
+            "Warning: cannot write the SARIF log to {}: {}",
+            sarif_path,
+            e
+        );
+    }
+}
+
+/// Record `result` for the file at `path` if `--ci` reports results.
+fn record_result(io: &mut Console, path: impl fmt::Display, result: impl FnOnce() -> CheckResult) {
+    if let Some(results) = &mut io.github {
+        results.push(FileResult {
+            path: path.to_string(),
+            result: result(),
+        });
+    }
+}
+
+/// The failures of `content`, located in it, or `None` if it is not a single
+/// document.
+fn locate_failures(
+    validator: &Validator,
+    content: &str,
+    format: DocumentFormat,
+) -> Option<Vec<Finding>> {
+    let document = validator.parse_text(content, format).ok()?;
+    let failures = validator.validate_detailed(&document);
+    Some(Finding::locate(content, format, failures)).filter(|found| !found.is_empty())
+}
+
+/// The result of a file that failed validation with `message`, listing its
+/// `findings` if they could be located.
+fn failed_result(findings: Option<Vec<Finding>>, message: &str) -> CheckResult {
+    match findings {
+        Some(findings) => CheckResult::Failed(findings),
+        None => CheckResult::Error(format!("Validation failed: {}", message)),
+    }
+}
+
+/// The result of a file that ended with `status`, with `findings` the
+/// failures located when it was validated.
+fn status_result(status: &FileStatus, findings: Vec<Finding>) -> CheckResult {
+    match status {
+        FileStatus::Valid => CheckResult::Valid,
+        FileStatus::Failed(ValidationError::ValidationFailed(msg)) => {
+            failed_result(Some(findings).filter(|found| !found.is_empty()), msg)
+        }
+        FileStatus::Failed(e) => CheckResult::Error(e.to_string()),
+        FileStatus::TimedOut(timeout) => {
+            CheckResult::Error(format!("Timed out after {}s", timeout.as_secs()))
+        }
+        FileStatus::Panicked(msg) => CheckResult::Error(format!("Internal error: {}", msg)),
+        FileStatus::Skipped => CheckResult::Skipped("deadline exceeded".to_string()),
+        FileStatus::Empty => CheckResult::Skipped("empty".to_string()),
+        FileStatus::Vanished(e) => CheckResult::Skipped(format!("vanished: {}", e)),
+        FileStatus::Changed => CheckResult::Skipped("changed while validated".to_string()),
+    }
+}
+
+/// Write the `--status-fd` line to file descriptor `fd`.
+fn write_status(fd: u32, line: &str, io: &mut Console) -> io::Result<()> {
+    match fd {
+        1 => writeln!(io.out, "{}", line),
+        2 => writeln!(io.err, "{}", line),
+        #[cfg(unix)]
+        _ => {
+            // Reopening the descriptor through /dev/fd avoids taking
+            // ownership of it, so it is not closed twice
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(format!("/dev/fd/{}", fd))?;
+            writeln!(file, "{}", line)
+        }
+        #[cfg(not(unix))]
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "only 1 and 2 are supported on this platform",
+        )),
+    }
+}
+
+/// The command-line arguments and subcommands.
+fn command() -> Command {
+    let command = Command::new("validate-json-schema")
+        .version(env!("CARGO_PKG_VERSION"))
+        .author("Your Name <your.email@example.com>")
+        .about("Validates YAML and JSON files against JSON schemas")
+        .long_about(
+            "A fast, ergonomic tool for validating YAML and JSON files against JSON schemas.\n\
+             Supports both local schema files and remote schema URLs with automatic caching.\n\
+             Automatically detects file format based on extension and content.",
+        )
+        .arg(
+            Arg::new("files")
+                .help("The YAML or JSON files to validate, then the schema unless --schema is given")
+                .long_help(
+                    "Paths to the YAML or JSON files to validate, followed by the schema\n\
+                     unless --schema names it. Formats are auto-detected. All files are\n\
+                     validated with the schema compiled once, and each result names its file\n\
+                     when there are several. Quoted glob patterns are expanded, skipping the\n\
+                     paths listed in .validationignore files.",
+                )
+                .required(false)
+                .index(1)
+                .num_args(1..)
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("schema")
+                .long("schema")
+                .help("The JSON schema file path or URL")
+                .long_help(
+                    "Path to a local JSON schema file or URL to a remote schema.\n\
+                     Remote schemas are automatically cached for faster subsequent validations.",
+                )
+                .value_name("SCHEMA"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Enable verbose output")
+                .long_help("Show detailed information about the validation process")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("hygiene")
+                .long("hygiene")
+                .help("Report document hygiene issues")
+                .long_help(
+                    "Report byte-order marks, mixed line endings, tab indentation (YAML)\n\
+                     and trailing whitespace alongside the validation result.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sort-keys")
+                .long("sort-keys")
+                .help("Require mapping keys in alphabetical order (YAML)")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("indent")
+                .long("indent")
+                .help("Require nested values to be indented by N spaces (YAML)")
+                .value_parser(clap::value_parser!(usize))
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("max-flow-items")
+                .long("max-flow-items")
+                .help("Require block style for flow collections with more than N items (YAML)")
+                .value_parser(clap::value_parser!(usize))
+                .value_name("N"),
+        )
+        .arg(
+            Arg::new("fix-style")
+                .long("fix-style")
+                .help("Rewrite FILE to follow the style rules before checking it")
+                .long_help(
+                    "Rewrite FILE in place to follow --sort-keys, --indent and\n\
+                     --max-flow-items. Comments and untouched lines are preserved.\n\
+                     Violations that cannot be fixed are still reported.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strict-vocabularies")
+                .long("strict-vocabularies")
+                .help("Fail if the schema requires vocabularies the validator does not implement")
+                .long_help(
+                    "Fail instead of ignoring unknown keywords when the schema's meta-schema\n\
+                     marks a vocabulary as required ($vocabulary) that the validator does not\n\
+                     implement.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("draft")
+                .long("draft")
+                .help("Validate with this draft instead of the one named by $schema")
+                .long_help(
+                    "Compile the schema as draft4, draft6, draft7, 2019-09 or 2020-12,\n\
+                     ignoring its $schema keyword. By default the draft comes from $schema\n\
+                     (or the custom meta-schema it names), else draft7.",
+                )
+                .value_name("DRAFT"),
+        )
+        .arg(
+            Arg::new("check-formats")
+                .long("check-formats")
+                .help("Fail values that do not match their 'format' (email, uri, date-time, ...)")
+                .long_help(
+                    "Assert the 'format' keyword, so that e.g. \"format\": \"email\" rejects\n\
+                     strings that are not email addresses. By default formats follow the\n\
+                     schema's draft: asserted up to draft7, annotations only in 2019-09\n\
+                     and 2020-12. Unknown formats are always ignored.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-comments")
+                .long("allow-comments")
+                .help("Allow comments and trailing commas in JSON (JSONC)")
+                .long_help(
+                    "Strip // and /* */ comments and trailing commas from a JSON FILE\n\
+                     before parsing it, as in VS Code settings and tsconfig.json. Unlike\n\
+                     --json5, other non-standard syntax is still an error.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .args(document_args())
+        .arg(
+            Arg::new("json5")
+                .long("json5")
+                .help("Parse FILE as JSON5, allowing comments and trailing commas")
+                .long_help(
+                    "Parse FILE as JSON with comments (// and /* */), trailing commas,\n\
+                     single-quoted strings and unquoted keys, whatever its extension.\n\
+                     Files ending in .json5 or .jsonc are parsed this way by default.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stdin-format")
+                .long("stdin-format")
+                .help("The format of the document read from stdin for FILE -")
+                .long_help(
+                    "The format of the document read from stdin when FILE is -, as in\n\
+                     `cat config.yml | validate-json-schema - schema.json`. Detected from\n\
+                     its content if omitted: JSON if it starts with { or [, YAML otherwise.",
+                )
+                .value_parser(["json", "yaml", "json5"])
+                .conflicts_with("json5")
+                .value_name("FORMAT"),
+        )
+        .arg(
+            Arg::new("pretty-errors")
+                .long("pretty-errors")
+                .help("Show each failure in its source lines, with carets under the value")
+                .long_help(
+                    "Print each validation failure as a code frame: the failing line of FILE\n\
+                     with the lines around it and carets under the failing value, followed\n\
+                     by its JSON Pointer. Failures of the whole document, and of YAML files\n\
+                     with several documents, are printed without a frame.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .help("Format of the result: text, json or ndjson")
+                .long_help(
+                    "Print the result as FORMAT. With json, print a JSON array with an\n\
+                     object per FILE, even if there is only one: the file, the schema, the\n\
+                     status (valid, invalid, empty or error) and an array of the failures,\n\
+                     each with its JSON Pointer, keyword, message, schema path and line and\n\
+                     column in FILE, or an error message if FILE could not be validated.\n\
+                     With ndjson, print each of those objects on a line of its own as soon\n\
+                     as its FILE is validated. The exit status is the same as with text.",
+                )
+                .value_parser(["text", "json", "ndjson"])
+                .default_value("text")
+                .value_name("FORMAT"),
+        )
+        .arg(
+            Arg::new("warn-unknown-keywords")
+                .long("warn-unknown-keywords")
+                .help("Warn about schema keywords the schema's draft does not define")
+                .long_help(
+                    "Print a warning with the schema location for every keyword the schema's\n\
+                     draft does not define, such as a misspelled 'additionalProperites'.\n\
+                     Such keywords are otherwise silently ignored. Keywords starting with\n\
+                     'x-' are not reported.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("suggest")
+                .long("suggest")
+                .help("Print suggested fixes as a unified diff (experimental, YAML only)")
+                .long_help(
+                    "On validation failure, print a unified diff against FILE that quotes\n\
+                     scalars misread as numbers or booleans, removes properties the schema\n\
+                     does not allow and adds missing required properties from their default\n\
+                     or example. Other failures get no suggestion. Apply with 'patch -p0'.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .help("Explain how each file would be validated, without validating it")
+                .long_help(
+                    "Print, for each FILE, which schema would be used and where it comes\n\
+                     from, the detected document format, the schema draft and the options in\n\
+                     effect. Remote schemas are not fetched; only the cache is consulted.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("trace")
+                .long("trace")
+                .help("Show the schema keywords evaluated at a JSON Pointer in the file")
+                .long_help(
+                    "Print every subschema evaluated against the value at POINTER (e.g.\n\
+                     /services/0/env), how it was reached through $ref, allOf/anyOf/oneOf and\n\
+                     if/then/else, and whether each of its keywords passed or failed.",
+                )
+                .value_name("POINTER"),
+        )
+        .arg(
+            Arg::new("capabilities")
+                .long("capabilities")
+                .help("Print the supported drafts, features and output formats as JSON")
+                .long_help(
+                    "Print a JSON description of this build: the schema drafts, input formats,\n\
+                     optional features and output formats it supports, so wrapper tools can\n\
+                     detect features instead of comparing version numbers.",
+                )
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("locale")
+                .long("locale")
+                .help("Language of the messages, e.g. de or es")
+                .long_help(
+                    "Print validation results and errors in LOCALE (en, de or es; regional\n\
+                     tags such as de-AT match their language). Defaults to the\n\
+                     VALIDATE_JSON_SCHEMA_LOCALE environment variable, then English.\n\
+                     Failure descriptions from the validation engine stay in English.",
+                )
+                .value_name("LOCALE")
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .help("Print nothing unless there are failures")
+                .long_help(
+                    "Leave out the lines of valid and skipped files, warnings and hints, and\n\
+                     a batch's summary table unless it failed, so that a run prints nothing\n\
+                     unless there are failures. JSON output is unaffected.",
+                )
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("status-fd")
+                .long("status-fd")
+                .help("Write a JSON summary of the run to file descriptor N")
+                .long_help(
+                    "After the run, write one line of JSON to file descriptor N (1 for stdout,\n\
+                     2 for stderr) with the command, its exit code, its duration in\n\
+                     milliseconds and how many results were valid, invalid, errors or skipped,\n\
+                     so wrapper scripts need not parse the human-readable output.",
+                )
+                .value_name("N")
+                .value_parser(clap::value_parser!(u32))
+                .global(true),
+        )
+        .arg(
+            Arg::new("ci")
+                .long("ci")
+                .help("Also report results to a CI system: github")
+                .long_help(
+                    "Also report results to GitHub Actions: print an ::error annotation for\n\
+                     every failure, append a Markdown summary to $GITHUB_STEP_SUMMARY and\n\
+                     write a SARIF log for code scanning. Applies to validating a file, batch\n\
+                     and catalog.",
+                )
+                .value_parser(["github"])
+                .value_name("SYSTEM")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sarif-file")
+                .long("sarif-file")
+                .help("Where --ci writes its SARIF log")
+                .value_name("FILE")
+                .default_value("validate-json-schema.sarif")
+                .global(true),
+        )
+        .arg(
+            Arg::new("step-summary")
+                .long("step-summary")
+                .help("Where --ci appends its job summary, instead of $GITHUB_STEP_SUMMARY")
+                .value_name("FILE")
+                .global(true),
+        )
+        .subcommand(
+            Command::new("snippets")
+                .about("Generate editor snippets from a schema")
+                .long_about(
+                    "Generate VS Code snippets or IntelliJ live templates that scaffold the\n\
+                     required fields of a schema with placeholders.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Snippet file format")
+                        .value_parser(["vscode", "intellij"])
+                        .default_value("vscode"),
+                )
+                .arg(
+                    Arg::new("language")
+                        .long("language")
+                        .help("Document language of the snippet bodies")
+                        .value_parser(["yaml", "json"])
+                        .default_value("yaml"),
+                )
+                .arg(
+                    Arg::new("prefix")
+                        .long("prefix")
+                        .help("Trigger prefix for the generated snippets")
+                        .default_value("schema"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Write snippets to a file instead of stdout")
+                        .value_name("PATH"),
+                ),
+        )
+        .subcommand(
+            Command::new("schema-migrate")
+                .about("Rewrite a schema for a newer JSON Schema draft")
+                .long_about(
+                    "Mechanically rewrite draft-specific keywords (definitions, items arrays,\n\
+                     boolean exclusive bounds, ...) and report constructs that need manual attention.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Draft the schema is written for (default: from $schema, else draft7)")
+                        .value_name("DRAFT"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .help("Draft to migrate to")
+                        .default_value("2020-12")
+                        .value_name("DRAFT"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Write the migrated schema to a file instead of stdout")
+                        .value_name("PATH"),
+                ),
+        )
+        .subcommand(
+            Command::new("bundle")
+                .about("Combine a schema and everything it references into one file")
+                .long_about(
+                    "Embed every document the schema references through $ref, local or remote,\n\
+                     under its definitions ($defs from draft 2019-09) and rewrite the references\n\
+                     to point there. The bundled schema needs no filesystem or network access.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Write the bundled schema to a file instead of stdout")
+                        .value_name("PATH"),
+                ),
+        )
+        .subcommand(
+            Command::new("schema-compile")
+                .about("Compile a schema into an artifact for distribution")
+                .long_about(
+                    "Bundle SCHEMA with everything it references, check that it compiles and\n\
+                     write a versioned artifact with a digest of the schema. Name it with the\n\
+                     .vjsc extension and pass it wherever a schema is expected; it is loaded\n\
+                     without resolving references or network access.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("Write the artifact to a file instead of stdout")
+                        .value_name("PATH"),
+                ),
+        )
+        .subcommand(
+            Command::new("check-schema")
+                .about("Validate a schema against its draft's meta-schema")
+                .long_about(
+                    "Check that SCHEMA is a valid JSON Schema for the draft named by its\n\
+                     $schema (draft7 if there is none or it is a custom meta-schema), and\n\
+                     report each problem with its location in the schema. Exits with status\n\
+                     1 if the schema is invalid.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                ),
+        )
+        .subcommand(
+            Command::new("analyze-schema")
+                .about("Check a schema for internal mistakes")
+                .long_about(
+                    "Check that every `examples` entry and `default` value in a schema is valid\n\
+                     against the subschema it belongs to, and find subschemas that can never match\n\
+                     (contradictory bounds, types or enums). Exits with status 1 if problems are found.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                ),
+        )
+        .subcommand(
+            Command::new("lint-schema")
+                .about("Flag common schema smells")
+                .long_about(
+                    "Report subschemas that can never match, misspelled or unknown keywords,\n\
+                     type-specific keywords without a `type`, `properties` without\n\
+                     `additionalProperties` and duplicate `enum` values. Exits with status 1\n\
+                     if anything is found.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                ),
+        )
+        .subcommand(
+            Command::new("schema-outline")
+                .about("Summarize the fields a schema requires")
+                .long_about(
+                    "Print a tree of the fields described by a schema with their types and\n\
+                     constraints, marking required fields [x] and optional fields [ ].\n\
+                     Local $refs are resolved and allOf branches are merged.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                ),
+        )
+        .subcommand(
+            Command::new("batch")
+                .about("Validate many files against one schema")
+                .long_about(
+                    "Validate each FILE against SCHEMA and report every file, continuing past\n\
+                     failures. Files that exceed --timeout or crash the validator are reported\n\
+                     with status 3, and files left when --deadline passes are listed as\n\
+                     skipped with status 4. The run exits with the highest status of its\n\
+                     files, so one that could not be parsed (5) outweighs one that timed\n\
+                     out, and an invalid file alone exits with 1. Files deleted, made\n\
+                     unreadable or modified while the run is in progress are reported as\n\
+                     skipped or changed without failing the run, unless --fail-on-race is\n\
+                     given.\n\n\
+                     With --codeowners, each file is assigned to its owners in the repository's\n\
+                     CODEOWNERS file (found in .github/, the root or docs/ of the current\n\
+                     directory or the nearest parent that has one, unless FILE is given) and\n\
+                     the failures are listed again by owner at the end; with --json, the\n\
+                     report has each file's owners and a summary per owner.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("files")
+                        .help("The YAML or JSON files to validate")
+                        .required(true)
+                        .num_args(1..)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .help("Give up on a file after this many seconds")
+                        .value_parser(clap::value_parser!(NonZeroU64))
+                        .value_name("SECONDS"),
+                )
+                .arg(
+                    Arg::new("deadline")
+                        .long("deadline")
+                        .help("Stop starting validations after this long, e.g. 60s or 2m")
+                        .value_parser(parse_duration)
+                        .value_name("DURATION"),
+                )
+                .args(document_args())
+                .arg(
+                    Arg::new("fail-on-race")
+                        .long("fail-on-race")
+                        .help("Fail files that vanish or change during the run")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("codeowners")
+                        .long("codeowners")
+                        .help("Group failures by the owners a CODEOWNERS file assigns")
+                        .num_args(0..=1)
+                        .default_missing_value("")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print a JSON report instead of one line per file")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .help("Format of the report: text, json or ndjson")
+                        .long_help(
+                            "Print the report as FORMAT: text, one line per file; json, the\n\
+                             array of per-file results the main command prints, once every\n\
+                             file is validated, as --json does; or ndjson, one of those\n\
+                             results per line for each file as soon as it is validated, so\n\
+                             large runs are not buffered. With --codeowners the json report\n\
+                             is an object that adds a summary per owner to the files.",
+                        )
+                        .value_parser(["text", "json", "ndjson"])
+                        .default_value("text")
+                        .value_name("FORMAT"),
+                )
+                .arg(
+                    Arg::new("summary-only")
+                        .long("summary-only")
+                        .help("Print only the summary table, without each file's result")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("baseline")
+                        .long("baseline")
+                        .help("Suppress the failures recorded in FILE, failing only on new ones")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("update-baseline")
+                        .long("update-baseline")
+                        .help("Record the failures of this run in the --baseline file")
+                        .requires("baseline")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("catalog")
+                .about("Validate a tree of files against the schemas a catalog assigns")
+                .long_about(
+                    "Find the YAML and JSON files under each PATH (default: the current\n\
+                     directory) and validate each against the first CATALOG entry whose\n\
+                     fileMatch patterns match it, in SchemaStore catalog format. Files no\n\
+                     entry matches are skipped, or fail the run with --require-schema. With\n\
+                     --json, print a report of every file's result and of which files matched\n\
+                     which entry, which were unmatched and which matched several entries.\n\
+                     Exits with 1 if any file is invalid.\n\n\
+                     When several entries match a file, the one with the highest priority is\n\
+                     used, then the first in catalog order. With --schemas all, the file is\n\
+                     validated against every matching entry in that order, e.g. a base schema\n\
+                     and an environment overlay, and each result names its entry; with\n\
+                     --schemas fail-fast, the remaining entries are skipped once one fails.\n\n\
+                     With --compare-to, print only the violations that are new or resolved\n\
+                     since the --json report of an earlier run, and how their number changed;\n\
+                     exits with 1 only if there are new violations.",
+                )
+                .arg(
+                    Arg::new("catalog")
+                        .help("The catalog file path or URL")
+                        .required(true)
+                        .value_name("CATALOG"),
+                )
+                .arg(
+                    Arg::new("paths")
+                        .help("Files or directories to validate")
+                        .long_help(
+                            "Files or directories to validate. Directories are searched for\n\
+                             YAML and JSON files, skipping those listed in .validationignore\n\
+                             files (gitignore syntax) found along the way or above them, up to\n\
+                             the top of the git repository.",
+                        )
+                        .num_args(1..)
+                        .default_value(".")
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print a JSON report instead of one line per file")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("schemas")
+                        .long("schemas")
+                        .help("Which matching entries to validate each file against: first, all or fail-fast")
+                        .value_parser(clap::value_parser!(SchemaPolicy))
+                        .default_value("first")
+                        .value_name("POLICY"),
+                )
+                .arg(
+                    Arg::new("require-schema")
+                        .long("require-schema")
+                        .help("Fail on files no catalog entry matches instead of skipping them")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("compare-to")
+                        .long("compare-to")
+                        .help("Report only the changes since an earlier run's --json report")
+                        .value_name("REPORT"),
+                )
+                .arg(
+                    Arg::new("explain")
+                        .long("explain")
+                        .help("Explain how each file would be validated, without validating it")
+                        .long_help(
+                            "Print, for each file found, which catalog entries it would be\n\
+                             validated against, its detected format, the schema drafts and the\n\
+                             options in effect. Remote schemas are not fetched; only the cache\n\
+                             is consulted.",
+                        )
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("diff-validate")
+                .about("Compare the validation failures of two versions of a file")
+                .long_about(
+                    "Validate OLD and NEW against SCHEMA and report which failures NEW\n\
+                     introduces, which it fixes and which it leaves unchanged. Exits with\n\
+                     status 1 if NEW introduces any failure, even if OLD was already invalid.",
+                )
+                .arg(
+                    Arg::new("old")
+                        .help("The previous version of the file")
+                        .required(true)
+                        .value_name("OLD"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .help("The changed version of the file")
+                        .required(true)
+                        .value_name("NEW"),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .long("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                ),
+        )
+        .subcommand(
+            Command::new("schema-diff")
+                .about("Report breaking changes between two versions of a schema")
+                .long_about(
+                    "Compare OLD and NEW and list the changes that can reject documents OLD\n\
+                     accepted (newly required fields, narrowed types, removed enum values,\n\
+                     tightened bounds) and the compatible ones. Exits with status 1 if any\n\
+                     change is breaking.",
+                )
+                .arg(
+                    Arg::new("old")
+                        .help("The previous version of the schema")
+                        .required(true)
+                        .value_name("OLD"),
+                )
+                .arg(
+                    Arg::new("new")
+                        .help("The changed version of the schema")
+                        .required(true)
+                        .value_name("NEW"),
+                ),
+        )
+        .subcommand(
+            Command::new("csv")
+                .about("Validate every row of a CSV file against a schema")
+                .long_about(
+                    "Read FILE as CSV, turn each row into an object keyed by the header row\n\
+                     and validate it against SCHEMA. Failures are reported by row number,\n\
+                     counting the header as row 1. Cells are strings unless --infer-types\n\
+                     is given. Exits with status 1 if any row is invalid.",
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("The CSV file to validate")
+                        .required(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("infer-types")
+                        .long("infer-types")
+                        .help("Convert cells to the types the schema declares and drop empty cells")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("delimiter")
+                        .long("delimiter")
+                        .help("The field separator")
+                        .default_value(",")
+                        .value_name("CHAR"),
+                ),
+        )
+        .subcommand(
+            Command::new("ini")
+                .about("Validate an INI or Java properties file against a schema")
+                .long_about(
+                    "Convert FILE to JSON and validate it against SCHEMA. In INI files each\n\
+                     [section] becomes an object of its keys; in .properties files keys are\n\
+                     split on dots, so server.port becomes {\"server\": {\"port\": ...}}.\n\
+                     Values are strings unless --infer-types is given. Exits with status 1\n\
+                     if the file is invalid.",
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("The INI or properties file to validate")
+                        .required(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("properties")
+                        .long("properties")
+                        .help("Read FILE as Java properties whatever its extension")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("infer-types")
+                        .long("infer-types")
+                        .help("Convert values to the types the schema declares")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("tail")
+                .about("Validate a stream of JSON messages as they arrive")
+                .long_about(
+                    "Read framed JSON messages from stdin (or connections to a unix socket,\n\
+                     or records appended to a file) until the input ends, validate each\n\
+                     against SCHEMA and print one JSON event per message to stdout:\n\
+                     {\"sequence\": N, \"valid\": true} or\n\
+                     {\"sequence\": N, \"valid\": false, \"errors\": [...]}. Exits with 1\n\
+                     if any message was invalid.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("framing")
+                        .long("framing")
+                        .help("How messages are delimited: lines (NDJSON) or length-prefixed")
+                        .value_parser(clap::value_parser!(Framing))
+                        .default_value("lines")
+                        .value_name("FRAMING"),
+                )
+                .arg(
+                    Arg::new("socket")
+                        .long("socket")
+                        .help("Listen on this unix socket instead of reading stdin")
+                        .long_help(
+                            "Create a unix socket at PATH and validate the messages sent on\n\
+                             each connection. Events carry a \"connection\" number, and\n\
+                             sequence numbers restart for every connection. Runs until killed.",
+                        )
+                        .value_name("PATH")
+                        .conflicts_with("follow"),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .short('f')
+                        .help("Validate records appended to FILE, like tail -F")
+                        .long_help(
+                            "Start at the end of FILE and validate each record appended to it,\n\
+                             waiting for more at the end. When FILE is rotated (renamed and\n\
+                             recreated, or truncated), continue from the start of the new file.\n\
+                             Records longer than 16 MiB are reported and skipped rather than\n\
+                             buffered. Runs until killed, or until --idle-timeout.",
+                        )
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("idle-timeout")
+                        .long("idle-timeout")
+                        .help("With --follow, stop after SECONDS without new records")
+                        .value_parser(clap::value_parser!(u64))
+                        .value_name("SECONDS")
+                        .requires("follow"),
+                ),
+        )
+        .subcommand(
+            Command::new("get")
+                .about("Print one value of a validated document")
+                .long_about(
+                    "Validate FILE against SCHEMA, then print the value at POINTER for use in\n\
+                     shell scripts: strings are printed as they are, other values as JSON.\n\
+                     Nothing is printed and the exit status is 1 if the document is invalid,\n\
+                     or 8 if it has no value at POINTER.",
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("The YAML or JSON file to read")
+                        .required(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("pointer")
+                        .long("pointer")
+                        .help("JSON Pointer to the value, e.g. /server/port")
+                        .required(true)
+                        .allow_hyphen_values(true)
+                        .value_name("POINTER"),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .long("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print strings as JSON too, with quotes and escapes")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("canonicalize")
+                .about("Print a validated document as canonical JSON")
+                .long_about(
+                    "Validate FILE against SCHEMA and print it as RFC 8785 canonical JSON:\n\
+                     sorted keys, no whitespace and normalized numbers and strings, so the\n\
+                     same data written as differently formatted YAML or JSON gives the same\n\
+                     bytes. Nothing is printed if the document is invalid.",
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("The YAML or JSON file to canonicalize")
+                        .required(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("sha256")
+                        .long("sha256")
+                        .help("Print the SHA-256 hex digest of the canonical form instead")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("env")
+                .about("Check environment variables against a schema")
+                .long_about(
+                    "Check the environment against SCHEMA, whose properties are variable\n\
+                     names: `required` lists the variables that must be set, and `properties`\n\
+                     and `patternProperties` constrain their values, which are converted to\n\
+                     the declared types first. Variables the schema does not name are ignored.\n\
+                     Values are left out of failure messages, as they are often secrets.",
+                )
+                .arg(
+                    Arg::new("schema")
+                        .help("The JSON schema file path or URL")
+                        .required(true)
+                        .value_name("SCHEMA"),
+                )
+                .arg(
+                    Arg::new("env-file")
+                        .long("env-file")
+                        .help("Check the NAME=value lines of FILE instead of the process environment")
+                        .long_help(
+                            "Check the variables of a dotenv-style FILE instead of the process\n\
+                             environment. Repeat to combine files; later files override earlier ones.",
+                        )
+                        .value_name("FILE")
+                        .action(clap::ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("clear-cache")
+                .about("Clear the schema cache")
+                .long_about("Remove all cached remote schemas from the local cache directory"),
+        );
+    #[cfg(feature = "xml")]
+    let command = command.subcommand(xml_command());
+    command
+}
+
+/// Options for reading documents, shared by the commands that read files.
+fn document_args() -> [Arg; 15] {
+    [
+        Arg::new("empty-documents")
+            .long("empty-documents")
+            .help("How to treat files with no content: null, skip or error")
+            .long_help(
+                "How to treat files that are empty or hold only comments (and, in YAML,\n\
+                 directives and --- markers): validate them as null (the default), skip\n\
+                 them, or fail with an empty document error.",
+            )
+            .value_parser(clap::value_parser!(EmptyDocuments))
+            .default_value("null")
+            .value_name("POLICY"),
+        Arg::new("merge-keys")
+            .long("merge-keys")
+            .help("Expand YAML merge keys (<<: *base) before validating")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("no-aliases")
+            .long("no-aliases")
+            .help("Fail YAML documents that use anchors or aliases")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("yaml-tags")
+            .long("yaml-tags")
+            .help("How to treat YAML tags such as !!binary or !Ref: error, strip or base64-decode")
+            .long_help(
+                "How to treat YAML nodes tagged outside the core schema, such as !!binary\n\
+                 or !Ref: fail naming the node and tag (the default), validate the value\n\
+                 as if untagged, or check that !!binary values are base64 and validate\n\
+                 them with line breaks removed, for contentEncoding, stripping other tags.",
+            )
+            .value_parser(clap::value_parser!(YamlTags))
+            .default_value("error")
+            .value_name("POLICY"),
+        Arg::new("cloudformation")
+            .long("cloudformation")
+            .help("Validate CloudFormation short-form tags (!Ref, !GetAtt, ...) in their JSON form")
+            .long_help(
+                "Convert CloudFormation's short-form tags into the long form of JSON\n\
+                 templates before validating, so !Ref Bucket is validated as\n\
+                 {\"Ref\": \"Bucket\"} and !GetAtt Bucket.Arn as\n\
+                 {\"Fn::GetAtt\": [\"Bucket\", \"Arn\"]}. Other tags follow --yaml-tags.",
+            )
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("yaml-schema")
+            .long("yaml-schema")
+            .help("How unquoted YAML scalars are typed: core (YAML 1.2) or yaml-1.1")
+            .long_help(
+                "How unquoted YAML scalars are typed: by the YAML 1.2 core schema (the\n\
+                 default), where NO and on are strings, as is 0755 since numbers with a\n\
+                 leading zero are kept as written, or by YAML 1.1 rules, where they are\n\
+                 false, true and 493, to validate what YAML 1.1 tools will read.",
+            )
+            .value_parser(clap::value_parser!(YamlSchema))
+            .default_value("core")
+            .value_name("SCHEMA"),
+        Arg::new("strict-keys")
+            .long("strict-keys")
+            .help("Fail JSON and YAML documents that repeat a key in an object or mapping")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("exact-numbers")
+            .long("exact-numbers")
+            .help("Check multipleOf and numeric ranges on exact decimal values")
+            .long_help(
+                "Check multipleOf, minimum, maximum, exclusiveMinimum and exclusiveMaximum\n\
+                 on the exact decimal values of numbers instead of the nearest 64-bit\n\
+                 float. Numbers beyond 64-bit integers keep every digit only in builds\n\
+                 with the `arbitrary-precision` feature.",
+            )
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("group-errors")
+            .long("group-errors")
+            .help("Report the failures of each value together, once per message")
+            .long_help(
+                "Report all failures of the same value on one line, naming the value once\n\
+                 and dropping repeated messages, which schemas with anyOf, oneOf or deep\n\
+                 references produce for the same mistake.",
+            )
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("max-errors-per-path")
+            .long("max-errors-per-path")
+            .help("Group failures by value and report at most N per value")
+            .value_parser(clap::value_parser!(usize))
+            .value_name("N"),
+        Arg::new("max-errors")
+            .long("max-errors")
+            .help("Stop validating a document after N failures (at least 1)")
+            .value_parser(clap::value_parser!(NonZeroUsize))
+            .value_name("N"),
+        Arg::new("jobs")
+            .long("jobs")
+            .short('j')
+            .help("Validate up to N files at once (default: one per CPU)")
+            .long_help(
+                "Validate up to N files at once, sharing the compiled schema. Results\n\
+                 are reported in the order of the files all the same. Defaults to the\n\
+                 number of CPUs; 1 validates one file at a time.",
+            )
+            .value_parser(clap::value_parser!(usize))
+            .value_name("N"),
+        Arg::new("fail-fast")
+            .long("fail-fast")
+            .help("Stop validating a document at its first failure")
+            .conflicts_with("max-errors")
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("warnings-as-errors")
+            .long("warnings-as-errors")
+            .help("Fail on deprecated values and format mismatches")
+            .long_help(
+                "Report warnings as validation failures: values the schema marks\n\
+                 \"deprecated\": true, and strings that do not match their format where\n\
+                 formats are not asserted.",
+            )
+            .action(clap::ArgAction::SetTrue),
+        Arg::new("ignore-rules")
+            .long("ignore-rules")
+            .help("Ignore the failures matched by the rules in FILE")
+            .long_help(
+                "Ignore the failures matched by the rules in FILE, a JSON or YAML object\n\
+                 whose \"ignore\" array lists them. Each rule has a JSON Pointer, under\n\
+                 which failures are ignored (* matches any one token), a keyword, or both,\n\
+                 and an optional reason.",
+            )
+            .value_name("FILE"),
+    ]
+}
+
+/// How many files `--jobs` validates at once: one per CPU by default.
+fn jobs_arg(matches: &ArgMatches) -> usize {
+    matches
+        .get_one::<usize>("jobs")
+        .copied()
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        })
+}
+
+/// Apply the options of [`document_args`] to `builder`, reporting an ignore
+/// rules file that cannot be loaded.
+fn with_document_args(
+    builder: ValidatorBuilder,
+    matches: &ArgMatches,
+    io: &mut Console,
+) -> Result<ValidatorBuilder, Exit> {
+    let builder = match matches.get_one::<String>("ignore-rules") {
+        Some(path) => match IgnoreRules::load(path) {
+            Ok(rules) => builder.ignore_rules(rules),
+            Err(e) => {
+                errln!(io, "Error: {}: {}", path, e);
+                return Err(Exit::of(&e));
+            }
+        },
+        None => builder,
+    };
+    let builder = if matches.get_flag("cloudformation") {
+        builder.cloudformation_tags()
+    } else {
+        builder
+    };
+    let builder = builder
+        .empty_documents(
+            *matches
+                .get_one::<EmptyDocuments>("empty-documents")
+                .expect("defaulted"),
+        )
+        .merge_keys(matches.get_flag("merge-keys"))
+        .allow_aliases(!matches.get_flag("no-aliases"))
+        .yaml_tags(*matches.get_one::<YamlTags>("yaml-tags").expect("defaulted"))
+        .yaml_schema(
+            *matches
+                .get_one::<YamlSchema>("yaml-schema")
+                .expect("defaulted"),
+        )
+        .strict_keys(matches.get_flag("strict-keys"))
+        .exact_numbers(matches.get_flag("exact-numbers"))
+        .fail_fast(matches.get_flag("fail-fast"))
+        .warnings_as_errors(matches.get_flag("warnings-as-errors"));
+    let builder = match matches.get_one::<NonZeroUsize>("max-errors") {
+        Some(max) => builder.max_failures(max.get()),
+        None => builder,
+    };
+    Ok(match matches.get_one::<usize>("max-errors-per-path") {
+        Some(max) => builder.max_failures_per_path(*max),
+        None => builder.group_failures(matches.get_flag("group-errors")),
+    })
+}
+
+/// The builder of the validator for the FILEs of the main command, with the
+/// options given on the command line.
+fn validator_builder(matches: &ArgMatches, io: &mut Console) -> Result<ValidatorBuilder, Exit> {
+    let mut builder = ValidatorBuilder::new()
+        .strict_vocabularies(matches.get_flag("strict-vocabularies"))
+        .warnings(true);
+    if let Some(draft) = parse_draft_arg(matches, "draft", io)? {
+        builder = builder.draft(draft);
+    }
+    if matches.get_flag("check-formats") {
+        builder = builder.validate_formats(true);
+    }
+    if matches.get_flag("allow-comments") {
+        builder = builder.allow_comments(true);
+    }
+    with_document_args(builder, matches, io)
+}
+
+fn dispatch(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    if matches.get_flag("capabilities") {
+        let capabilities = serde_json::to_string_pretty(&capabilities()).expect("serializable");
+        outln!(io, "{}", capabilities);
+        return Ok(());
+    }
+
+    if matches.subcommand_matches("clear-cache").is_some() {
+        return handle_clear_cache(io);
+    }
+
+    if let Some(snippet_matches) = matches.subcommand_matches("snippets") {
+        return handle_snippets(snippet_matches, io);
+    }
+
+    if let Some(migrate_matches) = matches.subcommand_matches("schema-migrate") {
+        return handle_schema_migrate(migrate_matches, io);
+    }
+
+    if let Some(bundle_matches) = matches.subcommand_matches("bundle") {
+        return handle_bundle(bundle_matches, io);
+    }
+
+    if let Some(compile_matches) = matches.subcommand_matches("schema-compile") {
+        return handle_schema_compile(compile_matches, io);
+    }
+
+    if let Some(check_matches) = matches.subcommand_matches("check-schema") {
+        return handle_check_schema(check_matches, io);
+    }
+
+    if let Some(analyze_matches) = matches.subcommand_matches("analyze-schema") {
+        return handle_analyze_schema(analyze_matches, io);
+    }
+
+    if let Some(lint_matches) = matches.subcommand_matches("lint-schema") {
+        return handle_lint_schema(lint_matches, io);
+    }
+
+    if let Some(outline_matches) = matches.subcommand_matches("schema-outline") {
+        return handle_schema_outline(outline_matches, io);
+    }
+
+    if let Some(batch_matches) = matches.subcommand_matches("batch") {
+        return handle_batch(batch_matches, io);
+    }
+
+    if let Some(catalog_matches) = matches.subcommand_matches("catalog") {
+        return handle_catalog(catalog_matches, io);
+    }
+
+    if let Some(diff_matches) = matches.subcommand_matches("diff-validate") {
+        return handle_diff_validate(diff_matches, io);
+    }
+
+    if let Some(schema_diff_matches) = matches.subcommand_matches("schema-diff") {
+        return handle_schema_diff(schema_diff_matches, io);
+    }
+
+    if let Some(csv_matches) = matches.subcommand_matches("csv") {
+        return handle_csv(csv_matches, io);
+    }
+
+    if let Some(ini_matches) = matches.subcommand_matches("ini") {
+        return handle_ini(ini_matches, io);
+    }
+
+    #[cfg(feature = "xml")]
+    if let Some(xml_matches) = matches.subcommand_matches("xml") {
+        return handle_xml(xml_matches, io);
+    }
+
+    if let Some(tail_matches) = matches.subcommand_matches("tail") {
+        return handle_tail(tail_matches, io);
+    }
+
+    if let Some(get_matches) = matches.subcommand_matches("get") {
+        return handle_get(get_matches, io);
+    }
+
+    if let Some(canonical_matches) = matches.subcommand_matches("canonicalize") {
+        return handle_canonicalize(canonical_matches, io);
+    }
+
+    if let Some(env_matches) = matches.subcommand_matches("env") {
+        return handle_env(env_matches, io);
+    }
+
+    // Handle main validation command: FILE... SCHEMA, or FILE... --schema SCHEMA
+    let files: Vec<&String> = matches
+        .get_many::<String>("files")
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let (files, schema_input) = match (matches.get_one::<String>("schema"), files.split_last()) {
+        (Some(schema), _) => (&files[..], Some(schema)),
+        (None, Some((schema, files))) if !files.is_empty() => (files, Some(*schema)),
+        (None, _) => (&files[..], None),
+    };
+    let files = expand_inputs(files, io)?;
+    let single = matches.get_flag("suggest") || matches.contains_id("trace");
+    match files.iter().filter(|file| *file == STDIN).count() {
+        0 => {}
+        1 if single || matches.get_flag("fix-style") => {
+            errln!(
+                io,
+                "Error: --suggest, --trace and --fix-style take a FILE, not stdin"
+            );
+            return Err(Exit(EXIT_USAGE));
+        }
+        1 => {}
+        _ => {
+            errln!(io, "Error: stdin (-) can only be read once");
+            return Err(Exit(EXIT_USAGE));
+        }
+    }
+
+    match (&files[..], schema_input) {
+        ([_, ..], Some(schema)) if matches.get_flag("explain") => {
+            handle_explain(&files, schema, matches, io)
+        }
+        ([file], Some(schema)) if matches.get_flag("suggest") => handle_suggest(file, schema, io),
+        ([file], Some(schema)) if matches.contains_id("trace") => {
+            let pointer = matches.get_one::<String>("trace").expect("checked");
+            handle_trace(file, schema, pointer, io)
+        }
+        ([_, _, ..], Some(_)) if single => {
+            errln!(io, "Error: --suggest and --trace take a single FILE");
+            Err(Exit(EXIT_USAGE))
+        }
+        ([_, ..], Some(schema)) => {
+            let verbose = matches.get_flag("verbose");
+            if matches.get_flag("warn-unknown-keywords") && !io.quiet {
+                report_unknown_keywords(schema, io);
+            }
+            let rules = StyleRules {
+                sorted_keys: matches.get_flag("sort-keys"),
+                indent: matches.get_one::<usize>("indent").copied(),
+                max_flow_items: matches.get_one::<usize>("max-flow-items").copied(),
+            };
+            let builder = validator_builder(matches, io)?;
+            let stdin = match files.iter().any(|file| file == STDIN) {
+                true => Some(read_stdin(matches, io)?),
+                false => None,
+            };
+            let options = ValidationOptions {
+                verbose,
+                report: match matches.get_one::<String>("output").map(String::as_str) {
+                    Some("json") => Report::Json,
+                    Some("ndjson") => Report::Ndjson,
+                    _ if matches.get_flag("pretty-errors") => Report::CodeFrames,
+                    _ => Report::Text,
+                },
+                format: matches.get_flag("json5").then_some(DocumentFormat::Json5),
+                named: files.len() > 1,
+            };
+            let (check_style, fix_style) = (
+                rules != StyleRules::default(),
+                matches.get_flag("fix-style"),
+            );
+            let mut styled = vec![true; files.len()];
+            // Fixes are written before validating any file reads it
+            if check_style && fix_style {
+                for (file, styled) in files.iter().zip(&mut styled) {
+                    *styled = report_style(file, stdin.as_ref(), &rules, true, io)?;
+                }
+            }
+
+            let validator = builder.build_input(schema);
+            let compiled = validator.as_ref().ok();
+            let locate = io.github.is_some() || options.report.is_json();
+            let (format, report) = (options.format, options.report);
+            let check = |file: &String| {
+                let validator = compiled?;
+                let mut check = FileCheck::default();
+                let outcome = check_file(
+                    file,
+                    validator,
+                    stdin.as_ref(),
+                    format,
+                    report,
+                    locate,
+                    &mut check,
+                );
+                Some((outcome, check))
+            };
+            let mut results = Vec::new();
+            // The highest exit status of the files that failed
+            let mut failed = 0;
+            map_ordered(&files, jobs_arg(matches), check, |index, checked| {
+                let file = &files[index];
+                if matches.get_flag("hygiene") && !io.quiet {
+                    report_hygiene(file, stdin.as_ref(), io);
+                }
+                if check_style && !fix_style {
+                    let checked = report_style(file, stdin.as_ref(), &rules, false, io);
+                    styled[index] = matches!(checked, Ok(true));
+                }
+                let checked = match (&validator, checked) {
+                    (Err(e), _) => Err(e),
+                    (Ok(_), checked) => Ok(checked.expect("checked with the validator")),
+                };
+                let outcome = handle_validation(file, schema, checked, &options, &mut results, io);
+                if let Err(Exit(status)) = outcome {
+                    failed = failed.max(status);
+                } else if !styled[index] {
+                    failed = failed.max(FAILED.0);
+                }
+            });
+            if options.report == Report::Json {
+                let json = serde_json::to_string_pretty(&results).expect("serializable");
+                outln!(io, "{}", json);
+            }
+            match failed {
+                0 => Ok(()),
+                status => Err(Exit(status)),
+            }
+        }
+        _ => {
+            errln!(
+                io,
+                "Error: Both FILE and SCHEMA arguments are required for validation"
+            );
+            errln!(io, "Usage: validate-json-schema <FILE>... <SCHEMA>");
+            errln!(
+                io,
+                "       validate-json-schema <FILE>... --schema <SCHEMA>"
+            );
+            errln!(io, "       validate-json-schema clear-cache");
+            errln!(
+                io,
+                "Try 'validate-json-schema --help' for more information."
+            );
+            Err(FAILED)
+        }
+    }
+}
+
+fn handle_clear_cache(io: &mut Console) -> Outcome {
+    match clear_schema_cache() {
+        Ok(()) => {
+            outln!(io, "Schema cache cleared successfully");
+        }
+        Err(e) => {
+            errln!(io, "Error clearing cache: {}", e);
+            return Err(FAILED);
+        }
+    }
+    Ok(())
+}
+
+fn handle_snippets(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let options = SnippetOptions {
+        format: matches
+            .get_one::<String>("format")
+            .and_then(|format| format.parse().ok())
+            .unwrap_or(SnippetFormat::VsCode),
+        language: match matches.get_one::<String>("language").map(String::as_str) {
+            Some("json") => DocumentFormat::Json,
+            _ => DocumentFormat::Yaml,
+        },
+        prefix: matches
+            .get_one::<String>("prefix")
+            .cloned()
+            .unwrap_or_default(),
+    };
+
+    let schema = match load_schema(schema_input) {
+        Ok(schema) => schema,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let snippets = generate_snippets(&schema, &options);
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = fs::write(path, snippets) {
+                errln!(io, "Error writing snippets: {}", e);
+                return Err(FAILED);
+            }
+        }
+        None => outln!(io, "{}", snippets),
+    }
+    Ok(())
+}
+
+fn parse_draft_arg(
+    matches: &ArgMatches,
+    name: &str,
+    io: &mut Console,
+) -> Result<Option<Draft>, Exit> {
+    match matches.get_one::<String>(name).map(|value| value.parse()) {
+        None => Ok(None),
+        Some(Ok(draft)) => Ok(Some(draft)),
+        Some(Err(e)) => {
+            errln!(io, "Error: {}", e);
+            Err(FAILED)
+        }
+    }
+}
+
+fn handle_schema_migrate(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let schema = match load_schema(schema_input) {
+        Ok(schema) => schema,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let from = parse_draft_arg(matches, "from", io)?
+        .or_else(|| Draft::detect(&schema))
+        .unwrap_or(Draft::Draft7);
+    let to = parse_draft_arg(matches, "to", io)?.unwrap_or(Draft::Draft202012);
+
+    let migration = match migrate_schema(&schema, from, to) {
+        Ok(migration) => migration,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    for note in &migration.notes {
+        errln!(io, "Note: {}", note);
+    }
+
+    let output = serde_json::to_string_pretty(&migration.schema).expect("serializable");
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = fs::write(path, output + "\n") {
+                errln!(io, "Error writing schema: {}", e);
+                return Err(FAILED);
+            }
+        }
+        None => outln!(io, "{}", output),
+    }
+    Ok(())
+}
+
+fn handle_bundle(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let bundled = match bundle_schema(schema_input) {
+        Ok(bundled) => bundled,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let output = serde_json::to_string_pretty(&bundled).expect("serializable");
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = fs::write(path, output + "\n") {
+                errln!(io, "Error writing schema: {}", e);
+                return Err(FAILED);
+            }
+        }
+        None => outln!(io, "{}", output),
+    }
+    Ok(())
+}
+
+fn handle_schema_compile(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let artifact = match compile_schema(schema_input) {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let output = serde_json::to_string(&artifact).expect("serializable");
+    match matches.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = fs::write(path, output + "\n") {
+                errln!(io, "Error writing artifact: {}", e);
+                return Err(FAILED);
+            }
+        }
+        None => outln!(io, "{}", output),
+    }
+    Ok(())
+}
+
+fn handle_check_schema(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let schema = match load_schema(schema_input) {
+        Ok(schema) => schema,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let draft = Draft::detect(&schema).unwrap_or(Draft::Draft7);
+    let failures = Validator::check_schema(&schema);
+    if failures.is_empty() {
+        outln!(io, "Valid {} schema", draft);
+        return Ok(());
+    }
+
+    for failure in &failures {
+        errln!(io, "{} (violates {})", failure, failure.schema_path);
+    }
+    errln!(
+        io,
+        "{} problem(s) found against the {} meta-schema",
+        failures.len(),
+        draft
+    );
+    Err(FAILED)
+}
+
+fn handle_analyze_schema(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let schema = match load_schema(schema_input) {
+        Ok(schema) => schema,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let issues = analyze_schema(&schema);
+    if issues.is_empty() {
+        outln!(io, "No problems found");
+        return Ok(());
+    }
+
+    for issue in &issues {
+        errln!(io, "{}", issue);
+    }
+    errln!(io, "{} problem(s) found", issues.len());
+    Err(FAILED)
+}
+
+fn handle_lint_schema(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let schema = match load_schema(schema_input) {
+        Ok(schema) => schema,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let issues = lint_schema(&schema);
+    if issues.is_empty() {
+        outln!(io, "No problems found");
+        return Ok(());
+    }
+
+    for issue in &issues {
+        errln!(io, "{}", issue);
+    }
+    errln!(io, "{} problem(s) found", issues.len());
+    Err(FAILED)
+}
+
+fn handle_explain(
+    files: &[String],
+    schema_input: &str,
+    matches: &ArgMatches,
+    io: &mut Console,
+) -> Outcome {
+    let builder = validator_builder(matches, io)?;
+    let stdin = match files.iter().any(|file| file == STDIN) {
+        true => Some(read_stdin(matches, io)?),
+        false => None,
+    };
+    for file_path in files {
+        explain_file(file_path, stdin.as_ref(), matches.get_flag("json5"), io);
+        explain_schema(schema_input, "given on the command line", &builder, io);
+        for (label, flag) in [
+            ("hygiene report", "hygiene"),
+            ("unknown keyword warnings", "warn-unknown-keywords"),
+        ] {
+            let state = if matches.get_flag(flag) {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            outln!(io, "    {}: {}", label, state);
+        }
+    }
+    Ok(())
+}
+
+/// Print the `--explain` lines naming the file at `file_path` and how its
+/// format is decided.
+fn explain_file(file_path: &str, stdin: Option<&StdinDocument>, json5: bool, io: &mut Console) {
+    outln!(io, "File: {}", file_path);
+    let format = match read_document(file_path, stdin) {
+        Err(e) => format!("unknown (cannot read file: {})", e),
+        Ok(_) if json5 => "JSON5 (from --json5)".to_string(),
+        Ok((_, format)) => {
+            let origin = match stdin.filter(|_| file_path == STDIN) {
+                Some(StdinDocument {
+                    format: Some(_), ..
+                }) => "from --stdin-format",
+                Some(_) => "detected from content",
+                None if DocumentFormat::from_path(file_path).is_some() => "from file extension",
+                None => "detected from content",
+            };
+            format!("{} ({})", format_name(format), origin)
+        }
+    };
+    outln!(io, "  Format: {}", format);
+}
+
+/// Print the `--explain` lines of the schema at `schema_input`, which is
+/// `origin`, and of the options `builder` validates with.
+fn explain_schema(schema_input: &str, origin: &str, builder: &ValidatorBuilder, io: &mut Console) {
+    let remote = schema_input.starts_with("http://") || schema_input.starts_with("https://");
+    outln!(io, "  Schema: {} ({})", schema_input, origin);
+
+    let schema = if remote {
+        let cached = FsCacheStore::default_location()
+            .and_then(|cache| cache.get(schema_input))
+            .ok()
+            .flatten();
+        match cached {
+            Some(content) => {
+                outln!(io, "  Schema source: remote, cached");
+                serde_json::from_str(&content).ok()
+            }
+            None => {
+                outln!(io, "  Schema source: remote, not cached (would be fetched)");
+                None
+            }
+        }
+    } else {
+        outln!(io, "  Schema source: local file");
+        load_schema(schema_input)
+            .map_err(|e| outln!(io, "  Schema error: {}", e))
+            .ok()
+    };
+
+    let declared = schema.as_ref().and_then(Draft::detect);
+    match (&schema, declared) {
+        (Some(_), Some(draft)) => outln!(io, "  Declared draft: {} (from $schema)", draft),
+        (Some(_), None) => outln!(io, "  Declared draft: none"),
+        (None, _) => outln!(io, "  Declared draft: unknown"),
+    }
+    match (builder.explicit_draft(), declared, &schema) {
+        (Some(draft), _, _) => outln!(io, "  Validation draft: {} (from --draft)", draft),
+        (None, Some(draft), _) => outln!(io, "  Validation draft: {}", draft),
+        (None, None, Some(schema)) if schema.get("$schema").is_some() => {
+            outln!(
+                io,
+                "  Validation draft: from the custom meta-schema, else draft7"
+            )
+        }
+        (None, None, _) => outln!(io, "  Validation draft: draft7 (default)"),
+    }
+
+    outln!(io, "  Options:");
+    for (label, value) in builder.describe() {
+        outln!(io, "    {}: {}", label, value);
+    }
+}
+
+fn format_name(format: DocumentFormat) -> &'static str {
+    match format {
+        DocumentFormat::Json => "JSON",
+        DocumentFormat::Yaml => "YAML",
+        DocumentFormat::Json5 => "JSON5",
+    }
+}
+
+fn handle_schema_outline(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    match load_schema(schema_input) {
+        Ok(schema) => out!(io, "{}", outline_schema(&schema)),
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    }
+    Ok(())
+}
+
+/// Expand the glob patterns among `inputs` into the files they match, as
+/// the shell would on Unix. Inputs that name existing files, or have no `*`
+/// or `?`, are kept as they are.
+fn expand_inputs(inputs: &[&String], io: &mut Console) -> Result<Vec<String>, Exit> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if !input.contains(['*', '?']) || Path::new(input).exists() {
+            files.push(input.to_string());
+            continue;
+        }
+        match expand_glob(input) {
+            Ok(matches) if matches.is_empty() => {
+                errln!(io, "Error: no files match {}", input);
+                return Err(Exit(EXIT_IO));
+            }
+            Ok(matches) => files.extend(matches.iter().map(|path| path.display().to_string())),
+            Err(e) => {
+                errln!(io, "Error: {}: {}", input, e);
+                return Err(Exit(EXIT_IO));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn handle_batch(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let files: Vec<&String> = matches.get_many("files").expect("required").collect();
+    let files = expand_inputs(&files, io)?;
+    let options = BatchOptions {
+        timeout: matches
+            .get_one::<NonZeroU64>("timeout")
+            .map(|seconds| Duration::from_secs(seconds.get())),
+        deadline: matches.get_one::<Duration>("deadline").copied(),
+        fail_on_race: matches.get_flag("fail-on-race"),
+        jobs: jobs_arg(matches),
+    };
+
+    let output = match matches.get_one::<String>("output").map(String::as_str) {
+        _ if matches.get_flag("json") => "json",
+        Some(output) => output,
+        None => "text",
+    };
+    let codeowners = match matches.get_one::<String>("codeowners").map(|path| {
+        if path.is_empty() {
+            find_codeowners()
+        } else {
+            CodeOwners::load(path).map(Some)
+        }
+    }) {
+        None => None,
+        Some(Ok(Some(codeowners))) => Some(codeowners),
+        Some(Ok(None)) => {
+            errln!(io, "Error: no CODEOWNERS file found");
+            return Err(FAILED);
+        }
+        Some(Err(e)) => {
+            errln!(io, "Error: CODEOWNERS: {}", e);
+            return Err(Exit::of(&e));
+        }
+    };
+
+    let baseline_path = matches.get_one::<String>("baseline");
+    let update_baseline = matches.get_flag("update-baseline");
+    let baseline = match baseline_path {
+        Some(path) if !update_baseline => match Baseline::load(path) {
+            Ok(baseline) => baseline,
+            Err(e) => {
+                errln!(io, "Error: {}: {}", path, e);
+                return Err(Exit::of(&e));
+            }
+        },
+        _ => Baseline::new(),
+    };
+
+    let builder = with_document_args(ValidatorBuilder::new(), matches, io)?;
+    let validator = match builder.build_input(schema_input) {
+        Ok(validator) => Arc::new(validator),
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    let reporter = BatchReporter {
+        io,
+        schema: schema_input,
+        output,
+        summary_only: matches.get_flag("summary-only"),
+        codeowners: codeowners.as_ref(),
+        findings: Vec::new(),
+        errors: 0,
+        rows: Vec::new(),
+        results: Vec::new(),
+        by_owner: BTreeMap::new(),
+        failed: 0,
+        abnormal: 0,
+        skipped: Vec::new(),
+    };
+    let mut reporter = if update_baseline {
+        BaselineReporter::recording(reporter)
+    } else {
+        BaselineReporter::new(&baseline, reporter)
+    };
+    report_files(&validator, &files, &options, &mut reporter);
+    let (
+        BatchReporter {
+            io,
+            failed,
+            abnormal,
+            skipped,
+            ..
+        },
+        recorded,
+    ) = reporter.into_parts();
+    if let (true, Some(path)) = (update_baseline, baseline_path) {
+        if let Err(e) = recorded.save(path) {
+            errln!(io, "Error: {}: {}", path, e);
+            return Err(Exit::of(&e));
+        }
+        errln!(io, "Recorded {} failure(s) in {}", recorded.len(), path);
+    }
+    if let (false, Some(deadline)) = (skipped.is_empty(), options.deadline) {
+        let deadline = format!("{:?}", deadline);
+        let count = skipped.len().to_string();
+        let message = io.messages.message(
+            "deadline-exceeded",
+            &[("deadline", &deadline), ("count", &count)],
+        );
+        errln!(io, "{}", message);
+        for path in &skipped {
+            errln!(io, "  {}", path);
+        }
+    }
+
+    batch_exit(failed, abnormal, skipped.len())
+}
+
+/// The exit status of a run over many files: the highest of `failed`, the
+/// status of the files that failed, and those of the `abnormal` files and of
+/// the files `skipped` after the deadline.
+fn batch_exit(failed: u8, abnormal: usize, skipped: usize) -> Outcome {
+    let abnormal = if abnormal > 0 { EXIT_ABNORMAL } else { 0 };
+    let deadline = if skipped > 0 { EXIT_DEADLINE } else { 0 };
+    match failed.max(abnormal).max(deadline) {
+        0 => Ok(()),
+        status => Err(Exit(status)),
+    }
+}
+
+/// Reports a batch run in the `--output` format: text, json or ndjson.
+struct BatchReporter<'a, 'b> {
+    io: &'a mut Console<'b>,
+    /// The schema the files are validated against, as given.
+    schema: &'a str,
+    output: &'a str,
+    summary_only: bool,
+    codeowners: Option<&'a CodeOwners>,
+    /// The located failures of the current file.
+    findings: Vec<Finding>,
+    /// Number of errors of the current file.
+    errors: usize,
+    rows: Vec<SummaryRow>,
+    /// Each file's result, for the json report.
+    results: Vec<serde_json::Value>,
+    by_owner: BTreeMap<Option<String>, OwnerSummary>,
+    /// The highest exit status of the files that failed validation or could
+    /// not be validated, or 0.
+    failed: u8,
+    /// Files that timed out or crashed the validator.
+    abnormal: usize,
+    /// Files left when the deadline passed.
+    skipped: Vec<String>,
+}
+
+impl Reporter for BatchReporter<'_, '_> {
+    fn on_error(&mut self, _path: &Path, error: &FileError) {
+        self.errors += 1;
+        if let FileError::Failure(finding) = error {
+            self.findings.push(finding.clone());
+        }
+    }
+
+    fn on_file_end(&mut self, report: &FileReport, elapsed: Duration) {
+        let io = &mut *self.io;
+        let findings = std::mem::take(&mut self.findings);
+        let errors = std::mem::take(&mut self.errors);
+        io.tally.record(&report.status);
+        let path = report.path.display();
+        let (status, error) = status_json(&report.status, &io.messages);
+        match &report.status {
+            FileStatus::Failed(e) => self.failed = self.failed.max(Exit::of(e).0),
+            FileStatus::TimedOut(_) | FileStatus::Panicked(_) => self.abnormal += 1,
+            FileStatus::Skipped => self.skipped.push(path.to_string()),
+            _ => {}
+        }
+        if self.output == "text" {
+            self.rows.push(SummaryRow {
+                path: path.to_string(),
+                status,
+                errors: matches!(status, "valid" | "invalid").then_some(errors),
+                elapsed,
+            });
+        }
+        if self.output == "text" && !self.summary_only {
+            let messages = &io.messages;
+            match (status, &error) {
+                ("valid" | "empty", _) if io.quiet => {}
+                ("valid", _) => outln!(io, "{}: {}", path, messages.message("valid", &[])),
+                ("empty", _) => outln!(io, "{}: {}", path, messages.message("skipped-empty", &[])),
+                ("invalid", Some(error)) => {
+                    let message = messages.message("validation-failed", &[("detail", error)]);
+                    errln!(io, "{}: {}", path, message)
+                }
+                ("error", Some(error)) => {
+                    errln!(
+                        io,
+                        "{}: {}",
+                        path,
+                        messages.message("error", &[("detail", error)])
+                    )
+                }
+                (_, Some(error)) => errln!(io, "{}: {}", path, error),
+                (_, None) => {}
+            }
+        }
+        let mut result = result_json(
+            &path.to_string(),
+            Some(self.schema),
+            status,
+            &findings,
+            &[],
+            error.as_deref(),
+        );
+        record_result(io, &path, || status_result(&report.status, findings));
+        if let Some(codeowners) = self.codeowners {
+            let owners = codeowners.owners_of_path(&report.path);
+            let failing = matches!(
+                report.status,
+                FileStatus::Failed(_) | FileStatus::TimedOut(_) | FileStatus::Panicked(_)
+            );
+            let keys: Vec<Option<String>> = if owners.is_empty() {
+                vec![None]
+            } else {
+                owners.iter().cloned().map(Some).collect()
+            };
+            for key in keys {
+                self.by_owner
+                    .entry(key)
+                    .or_default()
+                    .record(status, failing.then(|| path.to_string()));
+            }
+            result["owners"] = serde_json::json!(owners);
+        }
+        if self.output == "ndjson" {
+            outln!(io, "{}", result);
+            let _ = io.out.flush();
+        } else {
+            self.results.push(result);
+        }
+    }
+
+    fn on_summary(&mut self, summary: &Summary) {
+        let io = &mut *self.io;
+        if self.output == "json" {
+            let results = std::mem::take(&mut self.results);
+            // The summary per owner needs a report around the results
+            let report = match self.codeowners {
+                Some(_) => serde_json::json!({
+                    "files": results,
+                    "owners": self
+                        .by_owner
+                        .iter()
+                        .map(|(owner, summary)| summary.to_json(owner.as_deref()))
+                        .collect::<Vec<_>>(),
+                }),
+                None => serde_json::Value::Array(results),
+            };
+            outln!(
+                io,
+                "{}",
+                serde_json::to_string_pretty(&report).expect("serializable")
+            );
+        }
+        if self.output != "text" {
+            return;
+        }
+        if self
+            .by_owner
+            .values()
+            .any(|summary| !summary.failing.is_empty())
+        {
+            outln!(io, "Failures by owner:");
+            for (owner, summary) in &self.by_owner {
+                if summary.failing.is_empty() {
+                    continue;
+                }
+                outln!(
+                    io,
+                    "  {} ({} of {} files):",
+                    owner.as_deref().unwrap_or("(no owner)"),
+                    summary.failing.len(),
+                    summary.files
+                );
+                for path in &summary.failing {
+                    outln!(io, "    {}", path);
+                }
+            }
+        }
+        if !io.quiet || !summary.is_success() {
+            print_summary(&self.rows, summary, io);
+        }
+    }
+}
+
+/// One file's line in the summary table of a batch run.
+struct SummaryRow {
+    /// The file, as given on the command line.
+    path: String,
+    status: &'static str,
+    /// Number of validation errors, if the file could be validated.
+    errors: Option<usize>,
+    elapsed: Duration,
+}
+
+/// Print the summary table of a batch run: a line per file with its status,
+/// number of errors and validation time, then the totals.
+fn print_summary(rows: &[SummaryRow], summary: &Summary, io: &mut Console) {
+    let width = rows
+        .iter()
+        .map(|row| row.path.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("File".len());
+    outln!(
+        io,
+        "{:<width$}  {:<9}  {:>6}  {:>8}",
+        "File",
+        "Status",
+        "Errors",
+        "Time",
+        width = width
+    );
+    for row in rows {
+        let errors = row
+            .errors
+            .map_or_else(|| "-".to_string(), |count| count.to_string());
+        outln!(
+            io,
+            "{:<width$}  {:<9}  {:>6}  {:>8}",
+            row.path,
+            row.status,
+            errors,
+            format_elapsed(row.elapsed),
+            width = width
+        );
+    }
+    outln!(
+        io,
+        "\n{} file(s) validated in {}: {} passed, {} failed ({} validation error(s)), \
+         {} error(s), {} skipped",
+        summary.files,
+        format_elapsed(summary.elapsed),
+        summary.valid,
+        summary.invalid,
+        summary.failures,
+        summary.errors,
+        summary.skipped
+    );
+    if summary.baselined > 0 {
+        outln!(
+            io,
+            "{} known failure(s) suppressed by the baseline",
+            summary.baselined
+        );
+    }
+}
+
+/// `elapsed` in milliseconds, or in seconds from one second.
+fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{}ms", elapsed.as_millis())
+    } else {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    }
+}
+
+/// The `CODEOWNERS` file of the repository containing the current
+/// directory, from the nearest directory that has one.
+fn find_codeowners() -> Result<Option<CodeOwners>, ValidationError> {
+    let cwd = std::env::current_dir()?;
+    for directory in cwd.ancestors() {
+        if let Some(codeowners) = CodeOwners::discover(directory)? {
+            return Ok(Some(codeowners));
+        }
+    }
+    Ok(None)
+}
+
+/// The files of one owner in a batch run with `--codeowners`.
+#[derive(Default)]
+struct OwnerSummary {
+    files: usize,
+    valid: usize,
+    invalid: usize,
+    errors: usize,
+    /// The files that failed, as given on the command line.
+    failing: Vec<String>,
+}
+
+impl OwnerSummary {
+    fn record(&mut self, status: &str, failing: Option<String>) {
+        self.files += 1;
+        match status {
+            "valid" => self.valid += 1,
+            "invalid" => self.invalid += 1,
+            _ if failing.is_some() => self.errors += 1,
+            _ => {}
+        }
+        self.failing.extend(failing);
+    }
+
+    fn to_json(&self, owner: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "owner": owner,
+            "files": self.files,
+            "valid": self.valid,
+            "invalid": self.invalid,
+            "errors": self.errors,
+            "failing": self.failing,
+        })
+    }
+}
+
+fn handle_catalog(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let catalog_input = matches.get_one::<String>("catalog").expect("required");
+    let json_report = matches.get_flag("json");
+    let require_schema = matches.get_flag("require-schema");
+    let policy = *matches
+        .get_one::<SchemaPolicy>("schemas")
+        .expect("defaulted");
+    let previous = match matches.get_one::<String>("compare-to").map(|path| {
+        fs::read_to_string(path)
+            .map_err(ValidationError::from)
+            .and_then(|content| Ok(serde_json::from_str::<serde_json::Value>(&content)?))
+            .map_err(|e| (path, e))
+    }) {
+        None => None,
+        Some(Ok(previous)) => Some(previous),
+        Some(Err((path, e))) => {
+            errln!(io, "Error: {}: {}", path, e);
+            return Err(FAILED);
+        }
+    };
+    // Only the comparison is printed when there is one
+    let per_file = !json_report && previous.is_none();
+
+    let catalog = match Catalog::load(catalog_input) {
+        Ok(catalog) => catalog,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of(&e));
+        }
+    };
+    let mut selection = SelectionReport {
+        policy,
+        ..SelectionReport::default()
+    };
+    for root in matches.get_many::<String>("paths").expect("defaulted") {
+        let root = Path::new(root);
+        match find_documents(root) {
+            Ok(files) => selection
+                .selections
+                .extend(catalog.select(root, &files).selections),
+            Err(e) => {
+                errln!(io, "Error: {}: {}", root.display(), e);
+                return Err(FAILED);
+            }
+        }
+    }
+
+    if matches.get_flag("explain") {
+        // Catalog entries are validated with the default options
+        let builder = ValidatorBuilder::new();
+        for file in &selection.selections {
+            explain_file(&file.path.display().to_string(), None, false, io);
+            if file.candidates.is_empty() {
+                let outcome = match require_schema {
+                    true => "fails with --require-schema",
+                    false => "skipped",
+                };
+                outln!(
+                    io,
+                    "  Schema: none (no catalog entry matches, so the file is {})",
+                    outcome
+                );
+            }
+            for entry in file.applied(policy) {
+                let origin = format!("from catalog entry '{}'", entry.name);
+                explain_schema(&entry.url, &origin, &builder, io);
+            }
+        }
+        return Ok(());
+    }
+
+    // Each schema is compiled once, however many files it applies to
+    let mut validators: HashMap<&str, Result<Arc<Validator>, String>> = HashMap::new();
+    let mut results = Vec::new();
+    // The highest exit status of the files that failed
+    let mut failed = 0;
+    let mut abnormal = 0;
+    for file in &selection.selections {
+        let path = file.path.display();
+        if file.candidates.is_empty() {
+            if require_schema {
+                failed = failed.max(FAILED.0);
+                io.tally.invalid += 1;
+                record_result(io, &path, || CheckResult::Error(UNMATCHED.to_string()));
+                if per_file {
+                    errln!(io, "{}: {}", path, UNMATCHED);
+                }
+                let result = result_json(
+                    &path.to_string(),
+                    None,
+                    "unmatched",
+                    &[],
+                    &[],
+                    Some(UNMATCHED),
+                );
+                results.push(result);
+            }
+            continue;
+        }
+        let mut short_circuited = false;
+        for entry in file.applied(policy) {
+            if short_circuited {
+                io.tally.skipped += 1;
+                record_result(io, &path, || CheckResult::Skipped(entry.name.clone()));
+                if per_file && !io.quiet {
+                    outln!(io, "{}: Skipped ({})", path, entry.name);
+                }
+                let result = result_json(
+                    &path.to_string(),
+                    Some(&entry.name),
+                    "skipped",
+                    &[],
+                    &[],
+                    None,
+                );
+                results.push(result);
+                continue;
+            }
+            let validator = validators.entry(&entry.url).or_insert_with(|| {
+                Validator::from_schema_input(&entry.url)
+                    .map(Arc::new)
+                    .map_err(|e| e.to_string())
+            });
+            let (status, findings) = match validator {
+                Ok(validator) => {
+                    let readable = readable_files(&[&file.path])[0];
+                    let options = BatchOptions::default();
+                    validate_file(
+                        validator,
+                        &file.path,
+                        &options,
+                        Instant::now(),
+                        readable,
+                        true,
+                    )
+                }
+                Err(e) => (
+                    FileStatus::Failed(ValidationError::SchemaLoad(e.clone())),
+                    Vec::new(),
+                ),
+            };
+            io.tally.record(&status);
+            match &status {
+                FileStatus::Failed(e) => failed = failed.max(Exit::of(e).0),
+                FileStatus::TimedOut(_) | FileStatus::Panicked(_) => abnormal += 1,
+                _ => {}
+            }
+            short_circuited = policy == SchemaPolicy::FailFast
+                && !matches!(
+                    status,
+                    FileStatus::Valid | FileStatus::Skipped | FileStatus::Empty
+                );
+            let (status_name, error) = status_json(&status, &io.messages);
+            if per_file {
+                match &error {
+                    None if io.quiet => {}
+                    None if status_name == "valid" => {
+                        outln!(io, "{}: Valid ({})", path, entry.name)
+                    }
+                    None => outln!(io, "{}: Skipped ({})", path, entry.name),
+                    Some(error) => errln!(io, "{}: {} ({})", path, error, entry.name),
+                }
+            }
+            results.push(result_json(
+                &path.to_string(),
+                Some(&entry.name),
+                status_name,
+                &findings,
+                &[],
+                error.as_deref(),
+            ));
+            record_result(io, &path, || match validator {
+                Ok(_) => status_result(&status, findings),
+                Err(e) => CheckResult::Error(e.clone()),
+            });
+        }
+    }
+
+    let report = serde_json::json!({"files": results, "selection": selection.to_json()});
+    if let Some(previous) = previous {
+        let diff = match diff_reports(&previous, &report) {
+            Ok(diff) => diff,
+            Err(e) => {
+                errln!(io, "Error: {}", e);
+                return Err(FAILED);
+            }
+        };
+        if json_report {
+            outln!(
+                io,
+                "{}",
+                serde_json::to_string_pretty(&diff.to_json()).expect("serializable")
+            );
+        } else {
+            for (label, violations) in [("New", &diff.new), ("Resolved", &diff.resolved)] {
+                outln!(io, "{} ({}):", label, violations.len());
+                for violation in violations {
+                    outln!(io, "  {}", violation);
+                }
+            }
+            outln!(
+                io,
+                "Violations: {} -> {} ({:+})",
+                diff.previous_total,
+                diff.current_total,
+                diff.change()
+            );
+        }
+        if abnormal > 0 {
+            return Err(Exit(EXIT_ABNORMAL));
+        }
+        if !diff.new.is_empty() {
+            return Err(FAILED);
+        }
+        return Ok(());
+    }
+
+    if json_report {
+        outln!(
+            io,
+            "{}",
+            serde_json::to_string_pretty(&report).expect("serializable")
+        );
+    } else {
+        for file in selection
+            .selections
+            .iter()
+            .filter(|file| policy == SchemaPolicy::First && file.is_conflict())
+        {
+            let names: Vec<&str> = file
+                .candidates
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect();
+            errln!(
+                io,
+                "{}: matches {}; using {}",
+                file.path.display(),
+                names.join(", "),
+                names[0]
+            );
+        }
+        let unmatched = selection.unmatched().count();
+        if unmatched > 0 && !require_schema {
+            errln!(
+                io,
+                "Skipped {} file(s) with no schema in the catalog",
+                unmatched
+            );
+        }
+    }
+
+    batch_exit(failed, abnormal, 0)
+}
+
+/// Parse a duration such as `60s`, `2m`, `500ms` or `1h`; a bare number is
+/// seconds.
+fn parse_duration(input: &str) -> Result<Duration, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", input))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "" | "s" => Ok(Duration::from_secs(amount)),
+        "m" => Ok(Duration::from_secs(amount * 60)),
+        "h" => Ok(Duration::from_secs(amount * 60 * 60)),
+        _ => Err(format!(
+            "invalid duration '{}': expected a unit of ms, s, m or h",
+            input
+        )),
+    }
+}
+
+fn handle_diff_validate(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let old_path = matches.get_one::<String>("old").expect("required");
+    let new_path = matches.get_one::<String>("new").expect("required");
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+
+    let validator = match Validator::from_schema_input(schema_input) {
+        Ok(validator) => validator,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+    let mut failures = |path: &str| {
+        let result = fs::read_to_string(path)
+            .map_err(ValidationError::from)
+            .and_then(|content| DocumentFormat::detect(path, &content).parse(&content));
+        match result {
+            Ok(document) => Ok(validator.validate_detailed(&document)),
+            Err(e) => {
+                errln!(io, "Error: {}: {}", path, e);
+                Err(Exit::of(&e))
+            }
+        }
+    };
+    let old = failures(old_path)?;
+    let new = failures(new_path)?;
+    let diff = diff_failures(&old, &new);
+
+    for (label, failures) in [
+        ("New", &diff.new),
+        ("Fixed", &diff.fixed),
+        ("Unchanged", &diff.unchanged),
+    ] {
+        outln!(io, "{} ({}):", label, failures.len());
+        for failure in failures {
+            outln!(io, "  {}", failure);
+        }
+    }
+    if !diff.is_clean() {
+        return Err(FAILED);
+    }
+    Ok(())
+}
+
+fn handle_schema_diff(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let mut load = |name: &str| {
+        let input = matches.get_one::<String>(name).expect("required");
+        load_schema(input).map_err(|e| {
+            errln!(io, "Error: {}: {}", input, e);
+            Exit::of_schema(&e)
+        })
+    };
+    let old = load("old")?;
+    let new = load("new")?;
+    let changes = compare_schemas(&old, &new);
+    if changes.is_empty() {
+        outln!(io, "No changes found");
+        return Ok(());
+    }
+
+    for change in &changes {
+        let label = if change.breaking {
+            "Breaking"
+        } else {
+            "Compatible"
+        };
+        outln!(io, "{}: {}", label, change);
+    }
+    let breaking = changes.iter().filter(|change| change.breaking).count();
+    outln!(io, "{} breaking change(s)", breaking);
+    if breaking > 0 {
+        return Err(FAILED);
+    }
+    Ok(())
+}
+
+fn handle_csv(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let file_path = matches.get_one::<String>("file").expect("required");
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let delimiter = matches.get_one::<String>("delimiter").expect("defaulted");
+    let mut chars = delimiter.chars();
+    let delimiter = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => {
+            errln!(io, "Error: the delimiter must be a single character");
+            return Err(Exit(EXIT_USAGE));
+        }
+    };
+    let options = CsvOptions {
+        delimiter,
+        infer_types: matches.get_flag("infer-types"),
+    };
+
+    let (schema, validator) = load_validator(schema_input, io)?;
+    let result = fs::read_to_string(file_path)
+        .map_err(ValidationError::from)
+        .and_then(|content| {
+            let rows = parse_csv(&content, &options)?.len();
+            Ok((validate_csv(&validator, &schema, &content, &options)?, rows))
+        });
+    let (failures, rows) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            errln!(io, "Error: {}: {}", file_path, e);
+            return Err(Exit::of(&e));
+        }
+    };
+
+    if failures.is_empty() {
+        outln!(io, "Valid ({} rows)", rows);
+        return Ok(());
+    }
+    for failure in &failures {
+        errln!(io, "{}: {}", file_path, failure);
+    }
+    let mut failed: Vec<usize> = failures.iter().map(|failure| failure.row).collect();
+    failed.dedup();
+    errln!(io, "{} of {} rows failed", failed.len(), rows);
+    Err(FAILED)
+}
+
+/// Load the schema `schema_input` and compile it, reporting a failure.
+fn load_validator(
+    schema_input: &str,
+    io: &mut Console,
+) -> Result<(serde_json::Value, Validator), Exit> {
+    let loaded = load_schema(schema_input)
+        .and_then(|schema| Validator::from_value(&schema).map(|validator| (schema, validator)));
+    loaded.map_err(|e| {
+        errln!(io, "Error: {}: {}", schema_input, e);
+        Exit::of_schema(&e)
+    })
+}
+
+fn handle_ini(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let file_path = matches.get_one::<String>("file").expect("required");
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let defaults = IniOptions::for_path(file_path);
+    let options = IniOptions {
+        properties: defaults.properties || matches.get_flag("properties"),
+        infer_types: matches.get_flag("infer-types"),
+    };
+
+    let (schema, validator) = load_validator(schema_input, io)?;
+    let result = fs::read_to_string(file_path)
+        .map_err(ValidationError::from)
+        .and_then(|content| validate_ini(&validator, &schema, &content, &options));
+    match result {
+        Ok(failures) if failures.is_empty() => outln!(io, "Valid"),
+        Ok(failures) => {
+            for failure in &failures {
+                errln!(io, "{}: {}", file_path, failure);
+            }
+            return Err(FAILED);
+        }
+        Err(e) => {
+            errln!(io, "Error: {}: {}", file_path, e);
+            return Err(Exit::of(&e));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "xml")]
+fn xml_command() -> Command {
+    Command::new("xml")
+        .about("Validate an XML file against a schema")
+        .long_about(
+            "Convert FILE from XML to JSON and validate it against SCHEMA. The document\n\
+             becomes an object keyed by the root element; elements with only text\n\
+             become strings, others objects of their child elements, and repeated\n\
+             elements arrays. Exits with status 1 if the document is invalid.",
+        )
+        .arg(
+            Arg::new("file")
+                .help("The XML file to validate")
+                .required(true)
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("schema")
+                .help("The JSON schema file path or URL")
+                .required(true)
+                .value_name("SCHEMA"),
+        )
+        .arg(
+            Arg::new("attributes")
+                .long("attributes")
+                .help("How attributes are mapped: prefixed (@name), merged or ignored")
+                .value_parser(["prefixed", "merged", "ignored"])
+                .default_value("prefixed"),
+        )
+        .arg(
+            Arg::new("array")
+                .long("array")
+                .help("Always make elements with this name an array (repeatable)")
+                .action(clap::ArgAction::Append)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("infer-types")
+                .long("infer-types")
+                .help("Convert text to the types the schema declares")
+                .action(clap::ArgAction::SetTrue),
+        )
+}
+
+#[cfg(feature = "xml")]
+fn handle_xml(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    use crate::xml::{validate_xml, AttributeMode, XmlOptions};
+
+    let file_path = matches.get_one::<String>("file").expect("required");
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let options = XmlOptions {
+        attributes: match matches.get_one::<String>("attributes").map(String::as_str) {
+            Some("merged") => AttributeMode::Merged,
+            Some("ignored") => AttributeMode::Ignored,
+            _ => AttributeMode::Prefixed,
+        },
+        arrays: matches
+            .get_many::<String>("array")
+            .map(|names| names.cloned().collect())
+            .unwrap_or_default(),
+        infer_types: matches.get_flag("infer-types"),
+        ..XmlOptions::default()
+    };
+
+    let (schema, validator) = load_validator(schema_input, io)?;
+    let result = fs::read_to_string(file_path)
+        .map_err(ValidationError::from)
+        .and_then(|content| validate_xml(&validator, &schema, &content, &options));
+    match result {
+        Ok(failures) if failures.is_empty() => outln!(io, "Valid"),
+        Ok(failures) => {
+            for failure in &failures {
+                errln!(io, "{}: {}", file_path, failure);
+            }
+            return Err(FAILED);
+        }
+        Err(e) => {
+            errln!(io, "Error: {}: {}", file_path, e);
+            return Err(Exit::of(&e));
+        }
+    }
+    Ok(())
+}
+
+fn handle_tail(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let framing = *matches.get_one::<Framing>("framing").expect("defaulted");
+
+    let follow = matches.get_one::<String>("follow");
+    let builder = match follow {
+        Some(_) => ValidatorBuilder::new().max_document_size(FOLLOW_MAX_RECORD),
+        None => ValidatorBuilder::new(),
+    };
+    let validator = match builder.build_input(schema_input) {
+        Ok(validator) => validator,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+
+    if let Some(path) = matches.get_one::<String>("socket") {
+        return serve_socket(&validator, path, framing, io);
+    }
+    let follower = match follow.map(FollowReader::open).transpose() {
+        Ok(follower) => follower,
+        Err(e) => {
+            errln!(io, "Error: {}: {}", follow.expect("following"), e);
+            return Err(Exit(EXIT_IO));
+        }
+    };
+    let follower = match (follower, matches.get_one::<u64>("idle-timeout")) {
+        (Some(reader), Some(seconds)) => Some(reader.idle_timeout(Duration::from_secs(*seconds))),
+        (follower, _) => follower,
+    };
+    let out = Mutex::new(&mut *io.out);
+    let result = match (follower, io.input.as_mut()) {
+        (Some(reader), _) => {
+            emit_events(&validator, io::BufReader::new(reader), framing, None, &out)
+        }
+        (None, Some(input)) => emit_events(&validator, input, framing, None, &out),
+        (None, None) => emit_events(&validator, io::stdin().lock(), framing, None, &out),
+    };
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(FAILED),
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            Err(Exit(EXIT_IO))
+        }
+    }
+}
+
+/// Write an event line to `out` for every message in `reader`. Returns
+/// whether all messages were valid.
+fn emit_events(
+    validator: &Validator,
+    reader: impl BufRead,
+    framing: Framing,
+    connection: Option<u64>,
+    out: &Mutex<impl Write>,
+) -> io::Result<bool> {
+    let mut all_valid = true;
+    for event in MessageStream::new(validator, reader, framing) {
+        let event = event?;
+        all_valid &= event.is_valid();
+        let mut line = event.to_json();
+        if let Some(connection) = connection {
+            line["connection"] = connection.into();
+        }
+        // Flush per event so downstream consumers see results immediately
+        let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(out, "{}", line)?;
+        out.flush()?;
+    }
+    Ok(all_valid)
+}
+
+#[cfg(unix)]
+fn serve_socket(validator: &Validator, path: &str, framing: Framing, io: &mut Console) -> Outcome {
+    use std::os::unix::net::UnixListener;
+
+    let listener = match UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            errln!(io, "Error: cannot listen on {}: {}", path, e);
+            return Err(FAILED);
+        }
+    };
+    let out = Mutex::new(&mut *io.out);
+    let err = Mutex::new(&mut *io.err);
+    std::thread::scope(|scope| {
+        for (connection, stream) in (1..).zip(listener.incoming()) {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let mut err = err.lock().unwrap_or_else(|e| e.into_inner());
+                    let _ = writeln!(err, "Error: {}: {}", path, e);
+                    continue;
+                }
+            };
+            let (out, err) = (&out, &err);
+            scope.spawn(move || {
+                let reader = io::BufReader::new(stream);
+                if let Err(e) = emit_events(validator, reader, framing, Some(connection), out) {
+                    let mut err = err.lock().unwrap_or_else(|e| e.into_inner());
+                    let _ = writeln!(err, "Error: connection {}: {}", connection, e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(
+    _validator: &Validator,
+    _path: &str,
+    _framing: Framing,
+    io: &mut Console,
+) -> Outcome {
+    errln!(io, "Error: --socket is only supported on unix platforms");
+    Err(FAILED)
+}
+
+fn handle_get(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let file_path = matches.get_one::<String>("file").expect("required");
+    let pointer = matches.get_one::<String>("pointer").expect("required");
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        let e = ValidationError::InvalidPointer(format!("'{}' does not start with '/'", pointer));
+        errln!(io, "Error: {}", e);
+        return Err(Exit::of(&e));
+    }
+    let validator = match Validator::from_schema_input(schema_input) {
+        Ok(validator) => validator,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+    let result = fs::read_to_string(file_path)
+        .map_err(ValidationError::from)
+        .and_then(|content| {
+            let format = DocumentFormat::detect(file_path, &content);
+            validator.validate_as(&content, format)?;
+            format.parse(&content)
+        });
+
+    match result {
+        Ok(document) => match document.pointer(pointer) {
+            Some(serde_json::Value::String(text)) if !matches.get_flag("json") => {
+                outln!(io, "{}", text)
+            }
+            Some(value) => outln!(io, "{}", value),
+            None => {
+                errln!(io, "Error: {} has no value at '{}'", file_path, pointer);
+                return Err(Exit(EXIT_MISSING));
+            }
+        },
+        Err(ValidationError::ValidationFailed(msg)) => {
+            errln!(io, "Validation failed: {}", msg);
+            return Err(FAILED);
+        }
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of(&e));
+        }
+    }
+    Ok(())
+}
+
+fn handle_canonicalize(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let file_path = matches.get_one::<String>("file").expect("required");
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let validator = match Validator::from_schema_input(schema_input) {
+        Ok(validator) => validator,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+    let result = fs::read_to_string(file_path)
+        .map_err(ValidationError::from)
+        .and_then(|content| {
+            let format = DocumentFormat::detect(file_path, &content);
+            validator.validate_as(&content, format)?;
+            format.parse(&content)
+        });
+
+    match result {
+        Ok(document) => {
+            let canonical = canonicalize(&document);
+            if matches.get_flag("sha256") {
+                outln!(io, "{}", hex::encode(Sha256::digest(canonical.as_bytes())));
+            } else {
+                // The canonical form is exact bytes; a trailing newline would change its hash
+                out!(io, "{}", canonical);
+            }
+        }
+        Err(ValidationError::ValidationFailed(msg)) => {
+            errln!(io, "Validation failed: {}", msg);
+            return Err(FAILED);
+        }
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of(&e));
+        }
+    }
+    Ok(())
+}
+
+fn handle_env(matches: &ArgMatches, io: &mut Console) -> Outcome {
+    let schema_input = matches.get_one::<String>("schema").expect("required");
+    let schema = load_schema(schema_input);
+    let loaded = schema.is_ok();
+    let result = schema.and_then(|schema| {
+        let Some(files) = matches.get_many::<String>("env-file") else {
+            return check_env(&schema);
+        };
+        let mut vars = std::collections::BTreeMap::new();
+        for file in files {
+            vars.extend(parse_env_file(&fs::read_to_string(file)?)?);
+        }
+        check_env_from(vars, &schema)
+    });
+
+    match result {
+        Ok(()) => {
+            io.tally.valid += 1;
+            if !io.quiet {
+                outln!(io, "{}", io.messages.message("valid", &[]));
+            }
+            Ok(())
+        }
+        Err(ValidationError::ValidationFailed(msg)) => {
+            io.tally.invalid += 1;
+            let failed = io
+                .messages
+                .message("validation-failed", &[("detail", &msg)]);
+            errln!(io, "{}", failed);
+            Err(FAILED)
+        }
+        Err(e) => {
+            io.tally.errors += 1;
+            let error = io
+                .messages
+                .message("error", &[("detail", &io.messages.error(&e))]);
+            errln!(io, "{}", error);
+            Err(if loaded {
+                Exit::of(&e)
+            } else {
+                Exit::of_schema(&e)
+            })
+        }
+    }
+}
+
+fn handle_trace(file_path: &str, schema_input: &str, pointer: &str, io: &mut Console) -> Outcome {
+    let schema = load_schema(schema_input);
+    let loaded = schema.is_ok();
+    let result = schema.and_then(|schema| {
+        let content = fs::read_to_string(file_path)?;
+        let instance = DocumentFormat::detect(file_path, &content).parse(&content)?;
+        trace(&schema, &instance, pointer)
+    });
+    let steps = match result {
+        Ok(steps) => steps,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(if loaded {
+                Exit::of(&e)
+            } else {
+                Exit::of_schema(&e)
+            });
+        }
+    };
+
+    if steps.is_empty() {
+        outln!(io, "No subschema applies to {}", pointer);
+        return Ok(());
+    }
+    outln!(io, "Evaluation of {} in {}:", pointer, file_path);
+    for step in &steps {
+        outln!(io, "{}", step);
+    }
+    Ok(())
+}
+
+fn handle_suggest(file_path: &str, schema_input: &str, io: &mut Console) -> Outcome {
+    let loaded = load_schema(schema_input).and_then(|schema| {
+        Validator::from_schema_input(schema_input).map(|validator| (schema, validator))
+    });
+    let (schema, validator) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            errln!(io, "Error: {}", e);
+            return Err(Exit::of_schema(&e));
+        }
+    };
+    let result = fs::read_to_string(file_path)
+        .map_err(ValidationError::from)
+        .map(|content| {
+            let format = DocumentFormat::detect(file_path, &content);
+            let outcome = validator.validate_as(&content, format);
+            let diff = if format == DocumentFormat::Yaml {
+                unified_diff(
+                    &content,
+                    &suggest_fixes(&validator, &schema, &content),
+                    file_path,
+                )
+            } else {
+                String::new()
+            };
+            (outcome, format, diff)
+        });
+
+    match result {
+        Ok((Ok(()), _, _)) => {
+            outln!(io, "Valid");
+            Ok(())
+        }
+        Ok((Err(ValidationError::ValidationFailed(msg)), format, diff)) => {
+            errln!(io, "Validation failed: {}", msg);
+            if format != DocumentFormat::Yaml {
+                errln!(io, "Suggestions are only available for YAML documents");
+            } else if diff.is_empty() {
+                errln!(io, "No fixes to suggest");
+            } else {
+                out!(io, "{}", diff);
+            }
+            Err(FAILED)
+        }
+        Ok((Err(e), _, _)) | Err(e) => {
+            errln!(io, "Error: {}", e);
+            Err(Exit::of(&e))
+        }
+    }
+}
+
+/// How the main command reports the result of validating a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Report {
+    /// A line of text, with the failures' messages.
+    Text,
+    /// Failures as code frames, from `--pretty-errors`.
+    CodeFrames,
+    /// A JSON object, from `--output json`.
+    Json,
+    /// A JSON object per line, from `--output ndjson`.
+    Ndjson,
+}
+
+impl Report {
+    /// Whether the result is a JSON object rather than text.
+    fn is_json(self) -> bool {
+        matches!(self, Report::Json | Report::Ndjson)
+    }
+}
+
+/// The JSON result of validating `file` against `schema`, as every command
+/// prints it: its `status`, the located `findings` if it failed, and the
+/// `error` of a file that could not be validated or whose failures could not
+/// be listed.
+fn result_json(
+    file: &str,
+    schema: Option<&str>,
+    status: &str,
+    findings: &[Finding],
+    warnings: &[Finding],
+    error: Option<&str>,
+) -> serde_json::Value {
+    let to_json = |findings: &[Finding]| -> Vec<serde_json::Value> {
+        findings
+            .iter()
+            .map(|finding| {
+                let failure = &finding.failure;
+                serde_json::json!({
+                    "pointer": failure.instance_path,
+                    "keyword": failure.keyword,
+                    "message": failure.message,
+                    "schema_path": failure.schema_path,
+                    "location": finding.location.map(|location| {
+                        serde_json::json!({"line": location.line, "column": location.column})
+                    }),
+                })
+            })
+            .collect()
+    };
+    let mut result = serde_json::json!({
+        "file": file,
+        "schema": schema,
+        "status": status,
+        "errors": to_json(findings),
+        "warnings": to_json(warnings),
+    });
+    // The message of failures that are listed would only repeat them
+    if let Some(error) = error.filter(|_| status != "invalid" || findings.is_empty()) {
+        result["error"] = serde_json::json!(error);
+    }
+    result
+}
+
+/// The `status` of the JSON result of a file that ended with `status`, and
+/// the message of its `error`, if any.
+fn status_json(status: &FileStatus, messages: &Localizer) -> (&'static str, Option<String>) {
+    match status {
+        FileStatus::Valid => ("valid", None),
+        FileStatus::Failed(ValidationError::ValidationFailed(msg)) => {
+            ("invalid", Some(msg.clone()))
+        }
+        FileStatus::Failed(e) => ("error", Some(messages.error(e))),
+        FileStatus::TimedOut(timeout) => {
+            let seconds = timeout.as_secs().to_string();
+            let message = messages.message("timed-out", &[("seconds", &seconds)]);
+            ("timed-out", Some(message))
+        }
+        FileStatus::Panicked(msg) => {
+            let message = messages.message("internal-error", &[("detail", msg)]);
+            ("panicked", Some(message))
+        }
+        FileStatus::Skipped => ("skipped", None),
+        FileStatus::Empty => ("empty", None),
+        FileStatus::Vanished(e) => {
+            let message = messages.message("vanished", &[("detail", &e.to_string())]);
+            ("vanished", Some(message))
+        }
+        FileStatus::Changed => ("changed", Some(messages.message("changed", &[]))),
+    }
+}
+
+/// How the main command validates and reports each of its files.
+struct ValidationOptions {
+    verbose: bool,
+    report: Report,
+    /// The format of every file, instead of detecting each one's.
+    format: Option<DocumentFormat>,
+    /// Whether text results name their file, as when there are several.
+    named: bool,
+}
+
+/// What validating one file found besides its result, for
+/// [`handle_validation`] to report.
+#[derive(Default)]
+struct FileCheck {
+    ambiguities: Vec<YamlAmbiguity>,
+    /// The located failures, if the results need them.
+    findings: Option<Vec<Finding>>,
+    warnings: Vec<Finding>,
+    /// The code frames of the failures and how many there are.
+    frames: Option<(String, usize)>,
+}
+
+/// The file name that stands for the document on stdin.
+const STDIN: &str = "-";
+
+/// The document read from stdin for the file [`STDIN`].
+struct StdinDocument {
+    content: String,
+    /// Its `--stdin-format`, detected from its content if `None`.
+    format: Option<DocumentFormat>,
+}
+
+/// Read the document on stdin, or the run's input if it has one.
+fn read_stdin(matches: &ArgMatches, io: &mut Console) -> Result<StdinDocument, Exit> {
+    let mut content = String::new();
+    let read = match io.input.as_mut() {
+        Some(input) => input.read_to_string(&mut content),
+        None => io::stdin().lock().read_to_string(&mut content),
+    };
+    if let Err(e) = read {
+        errln!(io, "Error: stdin: {}", e);
+        return Err(Exit(EXIT_IO));
+    }
+    let format = matches
+        .get_one::<String>("stdin-format")
+        .map(|format| match format.as_str() {
+            "json" => DocumentFormat::Json,
+            "yaml" => DocumentFormat::Yaml,
+            _ => DocumentFormat::Json5,
+        });
+    Ok(StdinDocument { content, format })
+}
+
+/// The content of the file at `file_path`, or of `stdin` for [`STDIN`], and
+/// its format.
+fn read_document(
+    file_path: &str,
+    stdin: Option<&StdinDocument>,
+) -> io::Result<(String, DocumentFormat)> {
+    match stdin.filter(|_| file_path == STDIN) {
+        Some(stdin) => {
+            let content = stdin.content.clone();
+            let format = stdin
+                .format
+                .unwrap_or_else(|| DocumentFormat::from_content(&content));
+            Ok((content, format))
+        }
+        None => {
+            let content = fs::read_to_string(file_path)?;
+            let format = DocumentFormat::detect(file_path, &content);
+            Ok((content, format))
+        }
+    }
+}
+
+/// Validate the file at `file_path`, collecting what `report` and `--ci`
+/// need besides the result in `check`. Returns whether a document was
+/// validated.
+fn check_file(
+    file_path: &str,
+    validator: &Validator,
+    stdin: Option<&StdinDocument>,
+    format: Option<DocumentFormat>,
+    report: Report,
+    locate: bool,
+    check: &mut FileCheck,
+) -> Result<bool, ValidationError> {
+    #[cfg(feature = "cbor")]
+    if crate::cbor::is_cbor_path(Path::new(file_path)) {
+        return validator
+            .validate_cbor(&fs::read(file_path)?)
+            .map(|()| true);
+    }
+    #[cfg(feature = "bson")]
+    if crate::bson::is_bson_path(Path::new(file_path)) {
+        return validator
+            .validate_bson(&fs::read(file_path)?)
+            .map(|()| true);
+    }
+    let (content, detected) = read_document(file_path, stdin)?;
+    let format = format.unwrap_or(detected);
+    if format == DocumentFormat::Yaml {
+        check.ambiguities = find_yaml_ambiguities(validator, &content);
+    }
+    let result = validator.validate_stream(&content, format);
+    if matches!(result, Ok(true) | Err(ValidationError::ValidationFailed(_))) {
+        if let Ok(document) = validator.parse_text(&content, format) {
+            check.warnings = Finding::locate(&content, format, validator.warnings(&document));
+        }
+    }
+    if locate && matches!(result, Err(ValidationError::ValidationFailed(_))) {
+        check.findings = locate_failures(validator, &content, format);
+    }
+    if report == Report::CodeFrames && matches!(result, Err(ValidationError::ValidationFailed(_))) {
+        // Documents keep their lines in a YAML stream, so each is framed in it
+        let documents = match format {
+            DocumentFormat::Yaml => split_documents(&content),
+            _ => vec![Cow::Borrowed(content.as_str())],
+        };
+        let (mut frames, mut count) = (Vec::new(), 0);
+        for document in documents
+            .iter()
+            .filter(|document| !format.is_blank(document))
+        {
+            let Ok(parsed) = validator.parse_text(document, format) else {
+                continue;
+            };
+            let failures = validator.validate_detailed(&parsed);
+            if !failures.is_empty() {
+                count += failures.len();
+                frames.push(document_code_frames(
+                    file_path, &content, document, format, &failures,
+                ));
+            }
+        }
+        check.frames = (count > 0).then(|| (frames.join("\n"), count));
+    }
+    result
+}
+
+/// Report the file at `file_path` as [`check_file`] `checked` it against the
+/// schema at `schema_input`, or why the schema could not be compiled.
+/// `--output json` results are added to `results` rather than printed, and
+/// `--output ndjson` results are printed at once.
+fn handle_validation(
+    file_path: &str,
+    schema_input: &str,
+    checked: Result<(Result<bool, ValidationError>, FileCheck), &ValidationError>,
+    options: &ValidationOptions,
+    results: &mut Vec<serde_json::Value>,
+    io: &mut Console,
+) -> Outcome {
+    let ValidationOptions {
+        verbose,
+        report,
+        named,
+        ..
+    } = *options;
+    if verbose && !io.quiet {
+        print_verbose_info(file_path, schema_input, io);
+    }
+    let label = if named {
+        format!("{}: ", file_path)
+    } else {
+        String::new()
+    };
+
+    let (outcome, check) = match checked {
+        Ok((outcome, check)) => (Ok(outcome), check),
+        Err(e) => (Err(e), FileCheck::default()),
+    };
+    let (result, exit): (_, fn(&ValidationError) -> Exit) = match &outcome {
+        Ok(outcome) => (outcome.as_ref().copied(), Exit::of),
+        Err(e) => (Err(*e), Exit::of_schema),
+    };
+    let FileCheck {
+        ambiguities,
+        findings,
+        warnings,
+        frames,
+    } = check;
+
+    let report_warnings = |io: &mut Console| {
+        for warning in &warnings {
+            let detail = match warning.location {
+                Some(location) => format!("{}: {}", location, warning.failure),
+                None => warning.failure.to_string(),
+            };
+            let warning = io
+                .messages
+                .message("warning", &[("path", file_path), ("detail", &detail)]);
+            errln!(io, "{}", warning);
+        }
+    };
+    let report_ambiguities = |io: &mut Console| {
+        for ambiguity in &ambiguities {
+            let hint = io.messages.message(
+                "hint",
+                &[("path", file_path), ("detail", &ambiguity.to_string())],
+            );
+            errln!(io, "{}", hint);
+        }
+    };
+    if report.is_json() {
+        let (status, error) = match result {
+            Ok(false) => ("empty", None),
+            Ok(true) => ("valid", None),
+            // Failures of several documents are only described by the message
+            Err(ValidationError::ValidationFailed(msg)) if findings.is_none() => {
+                ("invalid", Some(msg.clone()))
+            }
+            Err(ValidationError::ValidationFailed(_)) => ("invalid", None),
+            Err(e) => ("error", Some(io.messages.error(e))),
+        };
+        let result = result_json(
+            file_path,
+            Some(schema_input),
+            status,
+            findings.as_deref().unwrap_or_default(),
+            &warnings,
+            error.as_deref(),
+        );
+        if report == Report::Ndjson {
+            outln!(io, "{}", result);
+            let _ = io.out.flush();
+        } else {
+            results.push(result);
+        }
+        report_ambiguities(io);
+    }
+    let text = !report.is_json();
+    match result {
+        Ok(false) => {
+            io.tally.skipped += 1;
+            record_result(io, file_path, || CheckResult::Skipped("empty".to_string()));
+            if text && !io.quiet {
+                let skipped = io.messages.message("skipped-empty", &[]);
+                outln!(io, "{}{}", label, skipped);
+            }
+            Ok(())
+        }
+        Ok(true) => {
+            io.tally.valid += 1;
+            record_result(io, file_path, || CheckResult::Valid);
+            if text && !io.quiet {
+                report_warnings(io);
+                report_ambiguities(io);
+                let id = if verbose {
+                    "validation-successful"
+                } else {
+                    "valid"
+                };
+                let valid = io.messages.message(id, &[]);
+                outln!(io, "{}{}", label, valid);
+            }
+            Ok(())
+        }
+        Err(ValidationError::ValidationFailed(msg)) => {
+            io.tally.invalid += 1;
+            record_result(io, file_path, || failed_result(findings, msg));
+            if !text {
+                return Err(FAILED);
+            }
+            let detail = match frames {
+                Some((frames, count)) => {
+                    errln!(io, "{}", frames);
+                    match count {
+                        1 => "1 validation error".to_string(),
+                        n => format!("{} validation errors", n),
+                    }
+                }
+                None => msg.clone(),
+            };
+            let failed = io
+                .messages
+                .message("validation-failed", &[("detail", &detail)]);
+            errln!(io, "{}{}", label, failed);
+            report_warnings(io);
+            report_ambiguities(io);
+            Err(FAILED)
+        }
+        Err(e) => {
+            io.tally.errors += 1;
+            record_result(io, file_path, || CheckResult::Error(e.to_string()));
+            if text {
+                let error = io
+                    .messages
+                    .message("error", &[("detail", &io.messages.error(e))]);
+                errln!(io, "{}{}", label, error);
+            }
+            Err(exit(e))
+        }
+    }
+}
+
+fn report_unknown_keywords(schema_input: &str, io: &mut Console) {
+    // Load errors are reported by the validation step that follows
+    if let Ok(schema) = load_schema(schema_input) {
+        for issue in find_unknown_keywords(&schema) {
+            errln!(io, "Warning: {} {}", schema_input, issue);
+        }
+    }
+}
+
+fn report_hygiene(file_path: &str, stdin: Option<&StdinDocument>, io: &mut Console) {
+    // Read errors are reported by the validation step that follows
+    if let Ok((content, format)) = read_document(file_path, stdin) {
+        for issue in check_hygiene(&content, format) {
+            errln!(io, "Warning: {}: {}", file_path, issue);
+        }
+    }
+}
+
+/// Check (and with `fix`, first rewrite) the file against the style rules.
+/// Returns whether it follows them.
+fn report_style(
+    file_path: &str,
+    stdin: Option<&StdinDocument>,
+    rules: &StyleRules,
+    fix: bool,
+    io: &mut Console,
+) -> Result<bool, Exit> {
+    // Read errors are reported by the validation step that follows
+    let (mut content, format) = match read_document(file_path, stdin) {
+        Ok(document) => document,
+        Err(_) => return Ok(true),
+    };
+    if format != DocumentFormat::Yaml {
+        errln!(
+            io,
+            "Warning: {}: style rules only apply to YAML documents",
+            file_path
+        );
+        return Ok(true);
+    }
+
+    if fix {
+        let fixed = fix_style(&content, rules);
+        if fixed != content {
+            if let Err(e) = fs::write(file_path, &fixed) {
+                errln!(io, "Error writing {}: {}", file_path, e);
+                return Err(FAILED);
+            }
+            content = fixed;
+        }
+    }
+    let issues = check_style(&content, rules);
+    for issue in &issues {
+        errln!(io, "Style: {}: {}", file_path, issue);
+    }
+    Ok(issues.is_empty())
+}
+
+fn print_verbose_info(file_path: &str, schema_input: &str, io: &mut Console) {
+    // Schema source info
+    if schema_input.starts_with("http://") || schema_input.starts_with("https://") {
+        outln!(io, "Using remote schema: {}", schema_input);
+        outln!(io, "User-Agent: {}", default_user_agent());
+    } else {
+        outln!(io, "Using local schema: {}", schema_input);
+    }
+
+    outln!(io, "Validating file: {}", file_path);
+
+    // File type detection
+    let file_type = detect_file_type(file_path);
+    outln!(io, "File type: {}", file_type);
+}
+
+fn detect_file_type(file_path: &str) -> &'static str {
+    if file_path.ends_with(".json") {
+        "JSON"
+    } else if file_path.ends_with(".yaml") || file_path.ends_with(".yml") {
+        "YAML"
+    } else if cfg!(feature = "cbor") && file_path.ends_with(".cbor") {
+        "CBOR"
+    } else if cfg!(feature = "bson") && file_path.ends_with(".bson") {
+        "BSON"
+    } else {
+        "Auto-detected"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_captured(args: &[&str], input: &str) -> (ExitCode, String, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let args = std::iter::once("validate-json-schema").chain(args.iter().copied());
+        let status = run_with(args, &mut input.as_bytes(), &mut out, &mut err);
+        (
+            status,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_run() {
+        let (status, out, _) = run_captured(
+            &["tests/data/package.json", "tests/schemas/package.json"],
+            "",
+        );
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert_eq!(out, "Valid\n");
+
+        let (status, out, err) = run_captured(
+            &[
+                "tests/data/invalid-package.json",
+                "tests/schemas/package.json",
+            ],
+            "",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        assert!(out.is_empty());
+        assert!(err.starts_with("Validation failed: "));
+
+        let (status, out, _) = run_captured(&["--version"], "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert!(out.contains(env!("CARGO_PKG_VERSION")));
+
+        let (status, out, err) = run_captured(&["--no-such-flag"], "");
+        assert_eq!(status, ExitCode::from(2));
+        assert!(out.is_empty());
+        assert!(err.contains("--no-such-flag"));
+    }
+
+    #[test]
+    fn test_run_tail_reads_input() {
+        let (status, out, _) = run_captured(
+            &["tail", "tests/schemas/package.json"],
+            "{\"name\": \"a\", \"version\": \"1.0.0\"}\n{\"name\": 1}\n",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        let events: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_pretty_errors() {
+        let (status, _, err) = run_captured(
+            &[
+                "tests/data/invalid-package.yml",
+                "tests/schemas/package.json",
+                "--pretty-errors",
+            ],
+            "",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.contains(
+            "error[type]: 123 is not of type \"string\"\n \
+             --> tests/data/invalid-package.yml:1:7\n  |\n\
+             1 | name: 123 # Invalid: should be string, not number\n  \
+             |       ^^^ /name\n"
+        ));
+        assert!(err.contains("\nValidation failed: 6 validation errors\n"));
+
+        // Each document of a stream is framed on its lines in the stream
+        let stream = "name: a\nversion: \"1.0.0\"\n---\nname: 1\nversion: \"1.0.0\"\n";
+        let (status, _, err) = run_captured(
+            &["-", "tests/schemas/package.json", "--pretty-errors"],
+            stream,
+        );
+        assert_eq!(status, ExitCode::from(1));
+        assert!(
+            err.contains(" --> -:4:7\n  |\n3 | ---\n4 | name: 1\n"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = dir.path().join("rules.yml");
+        fs::write(
+            &rules,
+            "ignore:\n  - keyword: type\n    pointer: /description\n    reason: legacy\n  \
+             - pointer: /dependencies\n",
+        )
+        .unwrap();
+        let args = [
+            "tests/data/invalid-package.yml",
+            "tests/schemas/package.json",
+            "--ignore-rules",
+            rules.to_str().unwrap(),
+        ];
+
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.starts_with("Validation failed: 4 validation errors: "));
+        assert!(!err.contains("/description: 42"));
+        assert!(!err.contains("/dependencies: "));
+
+        fs::write(&rules, "ignore:\n  - reason: nothing\n").unwrap();
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert!(err.ends_with("ignore rule 1: needs a pointer or a keyword\n"));
+    }
+
+    #[test]
+    fn test_batch_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let baseline = dir.path().join("baseline.json");
+        let baseline = baseline.to_str().unwrap();
+        let args = [
+            "batch",
+            "tests/schemas/package.json",
+            "tests/data/package.json",
+            "tests/data/invalid-package.yml",
+            "--baseline",
+            baseline,
+        ];
+
+        let (status, _, err) = run_captured(&[&args[..], &["--update-baseline"]].concat(), "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert!(err.ends_with(&format!("Recorded 6 failure(s) in {}\n", baseline)));
+
+        let (status, out, _) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert!(out.contains("tests/data/invalid-package.yml: Valid\n"));
+        assert!(out.ends_with("6 known failure(s) suppressed by the baseline\n"));
+
+        // Failures of files the baseline does not know still fail the run
+        let (status, _, err) = run_captured(
+            &[&args[..], &["tests/data/invalid-package.json"]].concat(),
+            "",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.starts_with("tests/data/invalid-package.json: Validation failed: "));
+
+        fs::write(baseline, "[]").unwrap();
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert!(err.starts_with(&format!("Error: {}: Invalid report: ", baseline)));
+    }
+
+    #[test]
+    fn test_warnings_as_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("schema.json");
+        let data = dir.path().join("data.yml");
+        fs::write(
+            &schema,
+            r#"{"properties": {"host": {"deprecated": true}, "port": {"type": "integer"}}}"#,
+        )
+        .unwrap();
+        fs::write(&data, "host: db\nport: 5432\n").unwrap();
+        let args = [data.to_str().unwrap(), schema.to_str().unwrap()];
+
+        let (status, out, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert_eq!(out, "Valid\n");
+        assert!(err.starts_with("Warning: "));
+        assert!(err.ends_with(": line 1 column 7: /host: \"db\" is deprecated\n"));
+
+        let (status, _, err) = run_captured(&[&args[..], &["--warnings-as-errors"]].concat(), "");
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.starts_with("Validation failed: line 1 column 7: /host: \"db\" is deprecated"));
+        assert!(!err.contains("Warning: "));
+    }
+
+    #[test]
+    fn test_max_errors() {
+        let args = [
+            "tests/data/invalid-package.yml",
+            "tests/schemas/package.json",
+        ];
+        let (status, _, err) = run_captured(&[&args[..], &["--max-errors", "2"]].concat(), "");
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.starts_with("Validation failed: 2 validation errors: "));
+
+        let (status, _, err) = run_captured(&[&args[..], &["--max-errors", "0"]].concat(), "");
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert!(err.contains("--max-errors"), "{}", err);
+
+        let (status, _, err) = run_captured(&[&args[..], &["--fail-fast"]].concat(), "");
+        assert_eq!(status, ExitCode::from(1));
+        let message = err.lines().next().unwrap();
+        assert!(message.starts_with("Validation failed: line "));
+        assert!(!message.contains(';'));
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let bad_schema = dir.path().join("schema.json");
+        let bad_yaml = dir.path().join("data.yml");
+        fs::write(&bad_schema, "{\"type\": ").unwrap();
+        fs::write(&bad_yaml, "name: [a\n").unwrap();
+        let (bad_schema, bad_yaml) = (bad_schema.to_str().unwrap(), bad_yaml.to_str().unwrap());
+        let schema = "tests/schemas/package.json";
+
+        let (status, _, _) = run_captured(&["tests/data/package.json", bad_schema], "");
+        assert_eq!(status, ExitCode::from(EXIT_SCHEMA));
+        let (status, _, _) = run_captured(&[bad_yaml, schema], "");
+        assert_eq!(status, ExitCode::from(EXIT_PARSE));
+        let (status, _, _) = run_captured(&["missing.json", schema], "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+
+        // An HTTP error status is a failed fetch, not an invalid document
+        let server = crate::stub::StubServer::start().unwrap();
+        let missing = server.url("/nope.json");
+        let args = ["tests/data/package.json", "--schema", &missing];
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        assert!(err.contains("404 Not Found"), "{}", err);
+        let (status, out, _) = run_captured(&[&args[..], &["--output", "json"]].concat(), "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(results[0]["status"], "error");
+
+        // A batch exits with the highest status of its files
+        let args = ["batch", schema, "tests/data/invalid-package.json", bad_yaml];
+        let (status, _, _) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_PARSE));
+
+        // Invalid configuration and reports are the arguments' fault
+        let config = ValidationError::Config("unknown field".to_string());
+        assert_eq!(Exit::of(&config), Exit(EXIT_USAGE));
+        let report = ValidationError::InvalidReport("expected an object".to_string());
+        assert_eq!(Exit::of(&report), Exit(EXIT_USAGE));
+    }
+
+    #[test]
+    fn test_multiple_files() {
+        let schema = "tests/schemas/package.json";
+        let (valid, invalid) = ("tests/data/package.json", "tests/data/invalid-package.json");
+        let (status, out, err) = run_captured(&[valid, invalid, "--schema", schema], "");
+        assert_eq!(status, ExitCode::from(1));
+        assert_eq!(out, format!("{}: Valid\n", valid));
+        assert!(err.starts_with(&format!("{}: Validation failed", invalid)));
+
+        // Without --schema the last file is the schema, with the highest status
+        let (status, out, _) =
+            run_captured(&[valid, "missing.json", schema, "--output", "json"], "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(results[0]["status"], "valid");
+        assert_eq!(results[1]["file"], "missing.json");
+        assert_eq!(results[1]["status"], "error");
+
+        let (status, _, err) = run_captured(&[valid, invalid, schema, "--suggest"], "");
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert!(err.contains("take a single FILE"));
+
+        // Files validated at once are still reported in order
+        let files = ["tests/data/*.yml", "tests/data/*.json", "--schema", schema];
+        let serial = run_captured(&[&files[..], &["--jobs", "1"]].concat(), "");
+        let parallel = run_captured(&[&files[..], &["--jobs", "4"]].concat(), "");
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn test_glob_inputs() {
+        let schema = "tests/schemas/package.json";
+        let (status, out, err) = run_captured(&["tests/data/*package.json", schema], "");
+        assert_eq!(status, ExitCode::from(1));
+        assert_eq!(out, "tests/data/package.json: Valid\n");
+        assert!(err.starts_with("tests/data/invalid-package.json: Validation failed"));
+
+        let (status, _, err) = run_captured(&["tests/data/**/*.toml", schema], "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        assert_eq!(err, "Error: no files match tests/data/**/*.toml\n");
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("gen")).unwrap();
+        fs::write(dir.path().join("a.yml"), "name: a\nversion: 1.0.0\n").unwrap();
+        fs::write(dir.path().join("gen/b.yml"), "name: 1\n").unwrap();
+        fs::write(dir.path().join(".validationignore"), "gen/\n").unwrap();
+        let pattern = format!("{}/**/*.yml", dir.path().display());
+        let (status, out, err) = run_captured(&[&pattern, "--schema", schema], "");
+        assert_eq!(status, ExitCode::SUCCESS, "{}", err);
+        assert_eq!(out, "Valid\n");
+    }
+
+    #[test]
+    fn test_stdin() {
+        let schema = "tests/schemas/package.json";
+        let valid = fs::read_to_string("tests/data/package.yml").unwrap();
+        let (status, out, _) = run_captured(&["-", schema], &valid);
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert_eq!(out, "Valid\n");
+
+        // The hint decides how content that is not obviously JSON is parsed
+        let invalid = fs::read_to_string("tests/data/invalid-package.json").unwrap();
+        let (status, _, err) = run_captured(&["-", schema, "--stdin-format", "json5"], &invalid);
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.starts_with("Validation failed"));
+        let (status, _, _) = run_captured(&["-", schema, "--stdin-format", "json"], "name: x\n");
+        assert_eq!(status, ExitCode::from(EXIT_PARSE));
+
+        let (status, _, err) = run_captured(&["-", "-", schema], &valid);
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert!(err.contains("only be read once"));
+        let (status, _, _) = run_captured(&["-", schema, "--suggest"], &valid);
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+    }
+
+    #[test]
+    fn test_quiet() {
+        let schema = "tests/schemas/package.json";
+        let (valid, invalid) = ("tests/data/package.json", "tests/data/invalid-package.json");
+        let (status, out, err) = run_captured(&[valid, schema, "--quiet", "--hygiene"], "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert_eq!((out.as_str(), err.as_str()), ("", ""));
+
+        let (status, out, err) = run_captured(&["-q", valid, invalid, "--schema", schema], "");
+        assert_eq!(status, ExitCode::from(1));
+        assert_eq!(out, "");
+        assert!(err.starts_with(&format!("{}: Validation failed", invalid)));
+
+        // A batch only prints its summary when a file failed
+        let (status, out, _) = run_captured(&["batch", schema, valid, "--quiet"], "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert_eq!(out, "");
+        let (status, out, _) = run_captured(&["batch", schema, valid, invalid, "-q"], "");
+        assert_eq!(status, ExitCode::from(1));
+        assert!(!out.contains(&format!("{}: Valid", valid)));
+        assert!(out.contains("2 file(s) validated"));
+    }
+
+    #[test]
+    fn test_output_json() {
+        let schema = "tests/schemas/package.json";
+        let (status, out, err) = run_captured(
+            &["tests/data/invalid-package.yml", schema, "--output", "json"],
+            "",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        // Hints stay on stderr, the result is only in the JSON
+        assert!(err.starts_with("Hint: ") && !err.contains("Validation failed"));
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        // One file is still an array, so scripts need not check
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        let result = &results[0];
+        assert_eq!(result["file"], "tests/data/invalid-package.yml");
+        assert_eq!(result["schema"], schema);
+        assert_eq!(result["status"], "invalid");
+        let errors = result["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 6);
+        assert!(errors.contains(&serde_json::json!({
+                "pointer": "/name",
+                "keyword": "type",
+                "message": "123 is not of type \"string\"",
+                "schema_path": "/properties/name/type",
+                "location": {"line": 1, "column": 7}
+        })));
+
+        let (status, out, _) =
+            run_captured(&["tests/data/package.json", schema, "--output", "json"], "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(results[0]["status"], "valid");
+        assert_eq!(results[0]["errors"], serde_json::json!([]));
+
+        let (status, out, _) = run_captured(&["missing.yml", schema, "--output", "json"], "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(results[0]["status"], "error");
+        assert!(results[0]["error"].is_string());
+    }
+
+    #[test]
+    fn test_output_ndjson() {
+        let schema = "tests/schemas/package.json";
+        let files = ["tests/data/package.json", "tests/data/invalid-package.yml"];
+        let (status, out, _) =
+            run_captured(&[&files[..], &[schema, "--output", "ndjson"]].concat(), "");
+        assert_eq!(status, ExitCode::from(1));
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let (_, out, _) = run_captured(&[&files[..], &[schema, "--output", "json"]].concat(), "");
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(serde_json::Value::Array(lines), results);
+    }
+
+    #[test]
+    fn test_batch_ndjson() {
+        let args = [
+            "batch",
+            "tests/schemas/package.json",
+            "tests/data/package.json",
+            "tests/data/invalid-package.yml",
+            "--output",
+            "ndjson",
+        ];
+        let (status, out, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(1));
+        assert_eq!(err, "");
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["file"], "tests/data/package.json");
+        assert_eq!(lines[0]["schema"], "tests/schemas/package.json");
+        assert_eq!(lines[0]["status"], "valid");
+        assert_eq!(lines[1]["status"], "invalid");
+        assert!(lines[1]["errors"][0]["pointer"].is_string());
+        assert!(lines[1]["errors"][0]["location"]["line"].is_u64());
+
+        let json_args = [&args[..args.len() - 1], &["json"]].concat();
+        let (status, out, _) = run_captured(&json_args, "");
+        assert_eq!(status, ExitCode::from(1));
+        let results: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(results, serde_json::Value::Array(lines));
+    }
+
+    #[test]
+    fn test_batch_exit() {
+        assert_eq!(batch_exit(0, 0, 0), Ok(()));
+        assert_eq!(batch_exit(FAILED.0, 1, 0), Err(Exit(EXIT_ABNORMAL)));
+        assert_eq!(batch_exit(FAILED.0, 1, 2), Err(Exit(EXIT_DEADLINE)));
+        assert_eq!(batch_exit(EXIT_PARSE, 1, 2), Err(Exit(EXIT_PARSE)));
+        assert_eq!(batch_exit(EXIT_IO, 0, 1), Err(Exit(EXIT_IO)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let unparsable = dir.path().join("unparsable.json");
+        fs::write(&unparsable, "{").unwrap();
+        let unparsable = unparsable.display().to_string();
+        let schema = "tests/schemas/package.json";
+        let files = ["tests/data/invalid-package.yml", &unparsable];
+        let (status, _, _) = run_captured(&[&["batch", schema], &files[..]].concat(), "");
+        assert_eq!(status, ExitCode::from(EXIT_PARSE));
+
+        let args = [&["batch", schema], &files[..], &["--deadline", "0s"]].concat();
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_DEADLINE));
+        assert!(err.contains(&unparsable));
+
+        let args = [&["batch", schema], &files[..], &["--timeout", "0"]].concat();
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert!(err.contains("--timeout"), "{}", err);
+    }
+
+    #[test]
+    fn test_batch_summary() {
+        let args = [
+            "batch",
+            "tests/schemas/package.json",
+            "tests/data/package.json",
+            "tests/data/invalid-package.yml",
+            "missing.yml",
+        ];
+        let (status, out, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        let table: Vec<&str> = out.lines().skip(1).collect();
+        assert!(table[0].starts_with("File                            Status     Errors"));
+        assert!(table[2].starts_with("tests/data/invalid-package.yml  invalid         6  "));
+        assert!(table[3].starts_with("missing.yml                     error           -  "));
+        assert!(table[5].starts_with("3 file(s) validated in "));
+        assert!(table[5]
+            .ends_with(": 1 passed, 1 failed (6 validation error(s)), 1 error(s), 0 skipped"));
+        assert!(err.contains("missing.yml: "));
+
+        let (status, out, err) = run_captured(&[&args[..], &["--summary-only"]].concat(), "");
+        assert_eq!(status, ExitCode::from(EXIT_IO));
+        assert!(out.starts_with("File "));
+        assert!(err.is_empty());
+    }
+
+    #[test]
+    fn test_batch_codeowners() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::create_dir_all(dir.path().join("web")).unwrap();
+        let codeowners = dir.path().join("docs/CODEOWNERS");
+        let valid = dir.path().join("web/package.json");
+        let invalid = dir.path().join("web/invalid-package.yml");
+        let unowned = dir.path().join("other.yml");
+        fs::copy("tests/data/package.json", &valid).unwrap();
+        fs::copy("tests/data/invalid-package.yml", &invalid).unwrap();
+        fs::copy("tests/data/invalid-package.yml", &unowned).unwrap();
+        fs::write(
+            &codeowners,
+            "* @org/platform\n/web/ @org/web @alice\n/other.yml\n",
+        )
+        .unwrap();
+        let mut args = vec![
+            "batch".to_string(),
+            "tests/schemas/package.json".to_string(),
+        ];
+        args.extend([&valid, &invalid, &unowned].map(|p| p.display().to_string()));
+        args.extend(["--codeowners".to_string(), codeowners.display().to_string()]);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let (status, out, _) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::from(1));
+        let section = out.split("Failures by owner:\n").nth(1).unwrap();
+        assert!(section.starts_with(&format!(
+            "  (no owner) (1 of 1 files):\n    {}\n  @alice (1 of 2 files):\n",
+            unowned.display()
+        )));
+        assert!(!section.contains("@org/platform"));
+
+        let (status, out, err) = run_captured(&[&args[..], &["--json"]].concat(), "");
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.is_empty());
+        let report: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(report["files"][1]["status"], "invalid");
+        assert_eq!(
+            report["files"][1]["owners"],
+            serde_json::json!(["@org/web", "@alice"])
+        );
+        assert_eq!(
+            report["owners"][2],
+            serde_json::json!({
+                "owner": "@org/web",
+                "files": 2,
+                "valid": 1,
+                "invalid": 1,
+                "errors": 0,
+                "failing": [invalid.display().to_string()],
+            })
+        );
+        assert_eq!(report["owners"][0]["owner"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_catalog_require_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = fs::canonicalize("tests/schemas/package.json").unwrap();
+        let catalog = dir.path().join("catalog.json");
+        let entry =
+            serde_json::json!({"name": "Package", "fileMatch": ["package.json"], "url": schema});
+        fs::write(
+            &catalog,
+            serde_json::json!({ "schemas": [entry] }).to_string(),
+        )
+        .unwrap();
+        let tree = dir.path().join("tree");
+        fs::create_dir(&tree).unwrap();
+        fs::copy("tests/data/package.json", tree.join("package.json")).unwrap();
+        fs::write(tree.join("settings.json"), "{}").unwrap();
+        let args = ["catalog", catalog.to_str().unwrap(), tree.to_str().unwrap()];
+
+        let (status, _, err) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert!(err.ends_with("Skipped 1 file(s) with no schema in the catalog\n"));
+
+        let (status, _, err) = run_captured(&[&args[..], &["--require-schema"]].concat(), "");
+        assert_eq!(status, ExitCode::from(1));
+        assert!(err.contains(&format!("settings.json: {}", UNMATCHED)));
+    }
+
+    #[test]
+    fn test_explain() {
+        let schema = "tests/schemas/package.json";
+        let args = [
+            "tests/data/package.yml",
+            schema,
+            "--explain",
+            "--yaml-schema",
+            "yaml-1.1",
+        ];
+        let (status, out, _) = run_captured(&[&args[..], &["--strict-keys", "-q"]].concat(), "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert!(out.starts_with(
+            "File: tests/data/package.yml\n  Format: YAML (from file extension)\n  \
+             Schema: tests/schemas/package.json (given on the command line)\n"
+        ));
+        // The options are those the run would validate with
+        assert!(out.contains("    duplicate keys: rejected\n"));
+        assert!(out.contains("    YAML schema: YAML 1.1\n"));
+        assert!(out.contains("    max errors: unlimited\n"));
+        let (_, out, _) = run_captured(&[&args[..], &["--max-errors", "3"]].concat(), "");
+        assert!(out.contains("    duplicate keys: allowed\n    YAML schema: YAML 1.1\n"));
+        assert!(out.contains("    max errors: 3\n"));
+
+        // Each file is explained, stdin included
+        let files = [
+            "tests/data/package.json",
+            "-",
+            "--schema",
+            schema,
+            "--explain",
+        ];
+        let (status, out, _) = run_captured(
+            &[&files[..], &["--stdin-format", "yaml"]].concat(),
+            "name: x\n",
+        );
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert!(out
+            .starts_with("File: tests/data/package.json\n  Format: JSON (from file extension)\n"));
+        assert!(out.contains("File: -\n  Format: YAML (from --stdin-format)\n"));
+        let (_, out, _) = run_captured(&files, "{\"name\": \"x\"}");
+        assert!(out.contains("File: -\n  Format: JSON (detected from content)\n"));
+    }
+
+    #[test]
+    fn test_catalog_explain() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = fs::canonicalize("tests/schemas/package.json").unwrap();
+        let catalog = dir.path().join("catalog.json");
+        let entry =
+            serde_json::json!({"name": "Package", "fileMatch": ["package.json"], "url": schema});
+        fs::write(
+            &catalog,
+            serde_json::json!({ "schemas": [entry] }).to_string(),
+        )
+        .unwrap();
+        let tree = dir.path().join("tree");
+        fs::create_dir(&tree).unwrap();
+        fs::write(tree.join("package.json"), "{}").unwrap();
+        fs::write(tree.join("settings.json"), "{}").unwrap();
+        let args = [
+            "catalog",
+            catalog.to_str().unwrap(),
+            tree.to_str().unwrap(),
+            "--explain",
+        ];
+
+        // Nothing is validated, so the invalid package.json passes
+        let (status, out, _) = run_captured(&args, "");
+        assert_eq!(status, ExitCode::SUCCESS);
+        let package = format!(
+            "File: {}\n  Format: JSON (from file extension)\n  Schema: {} (from catalog entry 'Package')\n",
+            tree.join("package.json").display(),
+            schema.display()
+        );
+        assert!(out.contains(&package), "{}", out);
+        let settings = format!(
+            "File: {}\n  Format: JSON (from file extension)\n  \
+             Schema: none (no catalog entry matches, so the file is skipped)\n",
+            tree.join("settings.json").display()
+        );
+        assert!(out.contains(&settings), "{}", out);
+        assert!(out.contains("    YAML schema: core (YAML 1.2)\n"));
+    }
+
+    #[test]
+    fn test_get() {
+        let file = "tests/data/package.json";
+        let schema = "tests/schemas/package.json";
+        let get = |pointer: &str| {
+            run_captured(&["get", file, "--schema", schema, "--pointer", pointer], "")
+        };
+
+        let (status, out, _) = get("/name");
+        assert_eq!(status, ExitCode::SUCCESS);
+        assert_eq!(out, "my-awesome-package\n");
+        let (status, out, _) = get("/keywords/1");
+        assert_eq!((status, out.as_str()), (ExitCode::SUCCESS, "sample\n"));
+        let (_, out, _) = get("/scripts");
+        assert!(out.starts_with('{'));
+
+        let (status, out, err) = get("/license/name");
+        assert_eq!(status, ExitCode::from(EXIT_MISSING));
+        assert_eq!(out, "");
+        assert_eq!(
+            err,
+            format!("Error: {} has no value at '/license/name'\n", file)
+        );
+
+        let (status, _, err) = get("name");
+        assert_eq!(status, ExitCode::from(EXIT_USAGE));
+        assert_eq!(
+            err,
+            "Error: Invalid pointer: 'name' does not start with '/'\n"
+        );
+    }
+
+    #[test]
+    fn test_ci_github() {
+        let dir = tempfile::tempdir().unwrap();
+        let sarif = dir.path().join("results.sarif");
+        let summary = dir.path().join("summary.md");
+        fs::write(&summary, "## Build\n").unwrap();
+        let (status, out, _) = run_captured(
+            &[
+                "batch",
+                "tests/schemas/package.json",
+                "tests/data/package.json",
+                "tests/data/invalid-package.yml",
+                "--ci",
+                "github",
+                "--sarif-file",
+                sarif.to_str().unwrap(),
+                "--step-summary",
+                summary.to_str().unwrap(),
+            ],
+            "",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        assert!(out.contains("::error file=tests/data/invalid-package.yml,line="));
+        let summary = fs::read_to_string(&summary).unwrap();
+        assert!(summary.starts_with("## Build\n## Schema validation"));
+        assert!(summary.contains("2 file(s): 1 valid, 1 invalid"));
+        let log: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&sarif).unwrap()).unwrap();
+        assert!(!log["runs"][0]["results"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_status_fd() {
+        let (status, out, err) = run_captured(
+            &[
+                "batch",
+                "tests/schemas/package.json",
+                "tests/data/package.json",
+                "tests/data/invalid-package.json",
+                "--status-fd",
+                "2",
+            ],
+            "",
+        );
+        assert_eq!(status, ExitCode::from(1));
+        assert!(out.starts_with("tests/data/package.json: Valid\n"));
+        assert!(!out.contains('{'));
+        let line: serde_json::Value = serde_json::from_str(err.lines().last().unwrap()).unwrap();
+        assert_eq!(line["command"], "batch");
+        assert_eq!(line["exit_code"], 1);
+        assert_eq!(
+            (line["valid"].as_u64(), line["invalid"].as_u64()),
+            (Some(1), Some(1))
+        );
+        assert!(line["duration_ms"].is_u64());
+
+        let (status, out, _) = run_captured(
+            &[
+                "tests/data/package.json",
+                "tests/schemas/package.json",
+                "--status-fd",
+                "1",
+            ],
+            "",
+        );
+        assert_eq!(status, ExitCode::SUCCESS);
+        let line: serde_json::Value = serde_json::from_str(out.lines().last().unwrap()).unwrap();
+        assert_eq!(
+            (line["command"].as_str(), line["valid"].as_u64()),
+            (Some("validate"), Some(1))
+        );
+    }
+
+    #[test]
+    fn test_concurrent_runs() {
+        let outputs: Vec<(ExitCode, String)> = std::thread::scope(|scope| {
+            let runs: Vec<_> = (0..8)
+                .map(|index| {
+                    scope.spawn(move || {
+                        let file = if index % 2 == 0 {
+                            "tests/data/package.json"
+                        } else {
+                            "tests/data/invalid-package.json"
+                        };
+                        let (status, out, _) =
+                            run_captured(&[file, "tests/schemas/package.json"], "");
+                        (status, out)
+                    })
+                })
+                .collect();
+            runs.into_iter().map(|run| run.join().unwrap()).collect()
+        });
+        for (index, (status, out)) in outputs.into_iter().enumerate() {
+            if index % 2 == 0 {
+                assert_eq!((status, out.as_str()), (ExitCode::SUCCESS, "Valid\n"));
+            } else {
+                assert_eq!((status, out.as_str()), (ExitCode::from(1), ""));
+            }
+        }
+    }
+}
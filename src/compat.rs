@@ -0,0 +1,384 @@
+//! Compatibility of a schema with its previous version.
+//!
+//! A schema change is breaking when a document that was valid against the old
+//! schema can be invalid against the new one: a field becomes required, a
+//! type or bound is narrowed, an enum value is removed. [`compare_schemas`]
+//! walks both versions side by side, through `properties`, `items` and local
+//! `$ref`s, and reports every breaking and compatible change it recognizes,
+//! so API teams can gate schema evolution in CI.
+//!
+//! The comparison is structural. Changes inside `allOf`, `anyOf`, `oneOf` and
+//! conditionals are not analyzed, and a changed `pattern` is reported as
+//! breaking since regular expressions cannot be compared in general.
+
+use crate::analysis::{declared_types, describe_types, ALL_TYPES};
+use crate::refs;
+use crate::walk::{escape_token, sorted_entries};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Keywords that reject more documents as their value grows.
+const LOWER_BOUNDS: &[&str] = &[
+    "minimum",
+    "exclusiveMinimum",
+    "minLength",
+    "minItems",
+    "minProperties",
+];
+
+/// Keywords that reject more documents as their value shrinks.
+const UPPER_BOUNDS: &[&str] = &[
+    "maximum",
+    "exclusiveMaximum",
+    "maxLength",
+    "maxItems",
+    "maxProperties",
+];
+
+/// One difference between two versions of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    /// JSON Pointer to the affected location in documents, with `*` standing
+    /// for every array item; empty for the root.
+    pub path: String,
+    /// Whether documents valid against the old schema may be rejected.
+    pub breaking: bool,
+    /// Human-readable description.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "root"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Compare the `old` and `new` versions of a schema.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::compare_schemas;
+///
+/// let old = json!({"properties": {"port": {"type": "number"}}});
+/// let new = json!({"properties": {"port": {"type": "integer"}}, "required": ["port"]});
+///
+/// let changes = compare_schemas(&old, &new);
+/// assert!(changes.iter().all(|change| change.breaking));
+/// assert_eq!(changes[0].to_string(), "root: property 'port' is now required");
+/// assert_eq!(changes[1].to_string(), "/port: type narrowed from number to integer");
+/// ```
+pub fn compare_schemas(old: &Value, new: &Value) -> Vec<SchemaChange> {
+    let mut comparison = Comparison {
+        old_root: old,
+        new_root: new,
+        seen: HashSet::new(),
+        changes: Vec::new(),
+    };
+    comparison.compare(old, new, String::new());
+    comparison.changes
+}
+
+struct Comparison<'a> {
+    old_root: &'a Value,
+    new_root: &'a Value,
+    /// Pairs of subschemas already compared, which ends recursive schemas.
+    seen: HashSet<(*const Value, *const Value)>,
+    changes: Vec<SchemaChange>,
+}
+
+impl<'a> Comparison<'a> {
+    fn compare(&mut self, old: &'a Value, new: &'a Value, path: String) {
+        let old = refs::deref(self.old_root, old);
+        let new = refs::deref(self.new_root, new);
+        if !self.seen.insert((old as *const Value, new as *const Value)) {
+            return;
+        }
+
+        match (old, new) {
+            (_, Value::Bool(false)) if old != &Value::Bool(false) => {
+                self.push(&path, true, "no value is accepted any more".to_string());
+                return;
+            }
+            (Value::Bool(false), _) if new != &Value::Bool(false) => {
+                self.push(&path, false, "values are accepted again".to_string());
+                return;
+            }
+            _ => {}
+        }
+
+        self.compare_types(old, new, &path);
+        self.compare_required(old, new, &path);
+        self.compare_values(old, new, &path);
+        self.compare_bounds(old, new, &path);
+        self.compare_properties(old, new, &path);
+
+        if let (Some(old_items), Some(new_items)) = (
+            old.get("items").filter(|items| !items.is_array()),
+            new.get("items").filter(|items| !items.is_array()),
+        ) {
+            self.compare(old_items, new_items, format!("{}/*", path));
+        }
+    }
+
+    fn compare_types(&mut self, old: &Value, new: &Value, path: &str) {
+        let old_types = declared_types(old).unwrap_or(ALL_TYPES);
+        let new_types = declared_types(new).unwrap_or(ALL_TYPES);
+        if old_types == new_types {
+            return;
+        }
+        let describe = |types: u8| {
+            if types == ALL_TYPES {
+                "any type".to_string()
+            } else {
+                describe_types(types)
+            }
+        };
+        let (breaking, verb) = if old_types & !new_types != 0 {
+            (true, "narrowed")
+        } else {
+            (false, "widened")
+        };
+        self.push(
+            path,
+            breaking,
+            format!(
+                "type {} from {} to {}",
+                verb,
+                describe(old_types),
+                describe(new_types)
+            ),
+        );
+    }
+
+    fn compare_required(&mut self, old: &'a Value, new: &'a Value, path: &str) {
+        let names = |schema: &'a Value| -> Vec<&'a str> {
+            schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|names| names.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default()
+        };
+        let (old_names, new_names) = (names(old), names(new));
+        for name in &new_names {
+            if !old_names.contains(name) {
+                self.push(path, true, format!("property '{}' is now required", name));
+            }
+        }
+        for name in &old_names {
+            if !new_names.contains(name) {
+                self.push(
+                    path,
+                    false,
+                    format!("property '{}' is no longer required", name),
+                );
+            }
+        }
+    }
+
+    fn compare_values(&mut self, old: &Value, new: &Value, path: &str) {
+        match (old.get("const"), new.get("const")) {
+            (old_value, Some(new_value)) if old_value != Some(new_value) => {
+                self.push(path, true, format!("value must now be {}", new_value))
+            }
+            (Some(_), None) => self.push(path, false, "value is no longer constant".to_string()),
+            _ => {}
+        }
+
+        let old_values = old.get("enum").and_then(Value::as_array);
+        let new_values = new.get("enum").and_then(Value::as_array);
+        match (old_values, new_values) {
+            (None, Some(_)) => self.push(
+                path,
+                true,
+                "values are now restricted by `enum`".to_string(),
+            ),
+            (Some(_), None) => self.push(path, false, "`enum` restriction removed".to_string()),
+            (Some(old_values), Some(new_values)) => {
+                for value in old_values {
+                    if !new_values.contains(value) {
+                        self.push(path, true, format!("enum value {} removed", value));
+                    }
+                }
+                for value in new_values {
+                    if !old_values.contains(value) {
+                        self.push(path, false, format!("enum value {} added", value));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn compare_bounds(&mut self, old: &Value, new: &Value, path: &str) {
+        for (keywords, tightens) in [
+            (LOWER_BOUNDS, (|old, new| new > old) as fn(f64, f64) -> bool),
+            (UPPER_BOUNDS, |old, new| new < old),
+        ] {
+            for keyword in keywords {
+                let old_bound = old.get(*keyword).and_then(Value::as_f64);
+                let new_bound = new.get(*keyword).and_then(Value::as_f64);
+                match (old_bound, new_bound) {
+                    (None, Some(bound)) => {
+                        self.push(path, true, format!("`{}` {} added", keyword, bound))
+                    }
+                    (Some(bound), None) => {
+                        self.push(path, false, format!("`{}` {} removed", keyword, bound))
+                    }
+                    (Some(old_bound), Some(new_bound)) if old_bound != new_bound => self.push(
+                        path,
+                        tightens(old_bound, new_bound),
+                        format!("`{}` changed from {} to {}", keyword, old_bound, new_bound),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+
+        match (old.get("pattern"), new.get("pattern")) {
+            (old_pattern, Some(pattern)) if old_pattern != Some(pattern) => {
+                self.push(path, true, format!("`pattern` is now {}", pattern))
+            }
+            (Some(_), None) => self.push(path, false, "`pattern` removed".to_string()),
+            _ => {}
+        }
+    }
+
+    fn compare_properties(&mut self, old: &'a Value, new: &'a Value, path: &str) {
+        let closed =
+            |schema: &Value| schema.get("additionalProperties") == Some(&Value::Bool(false));
+        if closed(new) && !closed(old) {
+            self.push(
+                path,
+                true,
+                "properties not listed in `properties` are no longer allowed".to_string(),
+            );
+        } else if closed(old) && !closed(new) {
+            self.push(
+                path,
+                false,
+                "additional properties are now allowed".to_string(),
+            );
+        }
+
+        let properties = |schema: &'a Value| schema.get("properties").and_then(Value::as_object);
+        let (old_properties, new_properties) = (properties(old), properties(new));
+        for (name, old_property) in old_properties.into_iter().flat_map(sorted_entries) {
+            match new_properties.and_then(|properties| properties.get(name)) {
+                Some(new_property) => self.compare(
+                    old_property,
+                    new_property,
+                    format!("{}/{}", path, escape_token(name)),
+                ),
+                None if closed(new) => {
+                    self.push(path, true, format!("property '{}' removed", name))
+                }
+                None => self.push(
+                    path,
+                    false,
+                    format!("property '{}' is no longer described", name),
+                ),
+            }
+        }
+        for (name, _) in new_properties.into_iter().flat_map(sorted_entries) {
+            if !old_properties.is_some_and(|properties| properties.contains_key(name)) {
+                self.push(path, false, format!("property '{}' added", name));
+            }
+        }
+    }
+
+    fn push(&mut self, path: &str, breaking: bool, message: String) {
+        self.changes.push(SchemaChange {
+            path: path.to_string(),
+            breaking,
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare_schemas() {
+        let old = json!({
+            "type": "object",
+            "required": ["name", "mode"],
+            "properties": {
+                "name": {"type": "string", "maxLength": 20},
+                "mode": {"enum": ["fast", "safe"]},
+                "port": {"$ref": "#/definitions/port"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "legacy": {}
+            },
+            "definitions": {"port": {"type": "integer", "minimum": 1}}
+        });
+        let new = json!({
+            "type": "object",
+            "required": ["name", "port"],
+            "additionalProperties": false,
+            "properties": {
+                "name": {"type": ["string", "null"], "maxLength": 10},
+                "mode": {"enum": ["fast", "auto"]},
+                "port": {"type": "integer", "minimum": 1024},
+                "tags": {"type": "array", "items": {"type": "string", "pattern": "^[a-z]+$"}},
+                "owner": {"type": "string"}
+            }
+        });
+
+        let changes: Vec<(bool, String)> = compare_schemas(&old, &new)
+            .iter()
+            .map(|change| (change.breaking, change.to_string()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (true, "root: property 'port' is now required".to_string()),
+                (
+                    false,
+                    "root: property 'mode' is no longer required".to_string()
+                ),
+                (
+                    true,
+                    "root: properties not listed in `properties` are no longer allowed".to_string()
+                ),
+                (true, "root: property 'legacy' removed".to_string()),
+                (true, "/mode: enum value \"safe\" removed".to_string()),
+                (false, "/mode: enum value \"auto\" added".to_string()),
+                (
+                    false,
+                    "/name: type widened from string to null/string".to_string()
+                ),
+                (true, "/name: `maxLength` changed from 20 to 10".to_string()),
+                (true, "/port: `minimum` changed from 1 to 1024".to_string()),
+                (true, "/tags/*: `pattern` is now \"^[a-z]+$\"".to_string()),
+                (false, "root: property 'owner' added".to_string()),
+            ]
+        );
+
+        assert!(compare_schemas(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_recursive_schemas() {
+        let schema = json!({
+            "properties": {"children": {"items": {"$ref": "#"}}}
+        });
+        let stricter = json!({
+            "required": ["id"],
+            "properties": {"children": {"items": {"$ref": "#"}}}
+        });
+        let changes = compare_schemas(&schema, &stricter);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].breaking);
+    }
+}
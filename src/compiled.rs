@@ -0,0 +1,183 @@
+//! Compiled schema artifacts.
+//!
+//! Build systems that validate on many runners can compile a schema once with
+//! [`compile_schema`] and ship the artifact, conventionally with the `.vjsc`
+//! extension, instead of the schema and everything it references. Loading an
+//! artifact with [`Validator::from_compiled`] needs no filesystem or network
+//! access beyond the artifact itself, and skips resolving references.
+//!
+//! An artifact is a JSON document:
+//!
+//! ```json
+//! {
+//!   "format": "validate-json-schema/compiled",
+//!   "version": 1,
+//!   "generator": "validate-json-schema 0.1.0",
+//!   "source": "schemas/deployment.json",
+//!   "draft": "2020-12",
+//!   "sha256": "9f86d08...",
+//!   "schema": {"$schema": "https://json-schema.org/draft/2020-12/schema"}
+//! }
+//! ```
+//!
+//! `schema` is the schema bundled with every document it references, as
+//! [`bundle_schema`] does, and `sha256` the digest of its RFC 8785 canonical
+//! form, checked on load. [`COMPILED_FORMAT_VERSION`] is raised whenever the
+//! layout changes; artifacts of other versions are rejected rather than
+//! misread, so they must be compiled again.
+
+use crate::{bundle_schema, canonicalize, Draft, ValidationError, Validator};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Version of the artifact layout written by [`compile_schema`].
+pub const COMPILED_FORMAT_VERSION: u64 = 1;
+
+/// Value of the `format` property identifying an artifact.
+const FORMAT: &str = "validate-json-schema/compiled";
+
+/// Load the schema at `input` (a local path, URL or `registry://` subject),
+/// bundle it with everything it references and check that it compiles, and
+/// return the artifact.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use validate_json_schema::{compile_schema, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let artifact = compile_schema("schemas/deployment.json")?;
+/// std::fs::write("deployment.vjsc", artifact.to_string())?;
+///
+/// let validator = Validator::from_compiled("deployment.vjsc")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the schema or a document it references cannot be
+/// loaded, or the schema is invalid, e.g. has a `pattern` that is not a
+/// valid regular expression.
+pub fn compile_schema(input: &str) -> Result<Value, ValidationError> {
+    let schema = bundle_schema(input)?;
+    if let Some(failure) = Validator::check_schema(&schema).into_iter().next() {
+        return Err(ValidationError::SchemaCompilation(failure.to_string()));
+    }
+    Validator::from_value(&schema)?;
+    let draft = Draft::detect(&schema).unwrap_or(Draft::Draft7);
+    Ok(json!({
+        "format": FORMAT,
+        "version": COMPILED_FORMAT_VERSION,
+        "generator": concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")),
+        "source": input,
+        "draft": draft.to_string(),
+        "sha256": digest(&schema),
+        "schema": schema,
+    }))
+}
+
+/// Whether `path` has the `.vjsc` extension of artifacts.
+pub(crate) fn is_compiled_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("vjsc"))
+}
+
+/// Read the artifact at `path` and return its schema and draft.
+pub(crate) fn read_compiled(path: &Path) -> Result<(Value, Draft), ValidationError> {
+    let invalid = |message: String| {
+        ValidationError::InvalidCompiled(format!("{}: {}", path.display(), message))
+    };
+    let mut artifact: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if artifact["format"] != FORMAT {
+        return Err(invalid("not a compiled schema".to_string()));
+    }
+    match artifact["version"].as_u64() {
+        Some(COMPILED_FORMAT_VERSION) => {}
+        Some(version) => {
+            return Err(invalid(format!(
+                "format version {} is not supported (expected {}); compile the schema again",
+                version, COMPILED_FORMAT_VERSION
+            )))
+        }
+        None => return Err(invalid("the format version is missing".to_string())),
+    }
+    let draft: Draft = artifact["draft"]
+        .as_str()
+        .ok_or_else(|| invalid("the draft is missing".to_string()))?
+        .parse()
+        .map_err(invalid)?;
+    let schema = artifact["schema"].take();
+    if artifact["sha256"] != digest(&schema) {
+        return Err(invalid(
+            "the schema does not match its digest; the file is corrupt or was edited".to_string(),
+        ));
+    }
+    Ok((schema, draft))
+}
+
+fn digest(schema: &Value) -> String {
+    hex::encode(Sha256::digest(canonicalize(schema).as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_round_trip() {
+        let sources = tempfile::tempdir().unwrap();
+        let schema_path = sources.path().join("schema.json");
+        fs::write(
+            &schema_path,
+            r#"{"$schema": "https://json-schema.org/draft/2020-12/schema",
+                "properties": {"port": {"$ref": "port.json"}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            sources.path().join("port.json"),
+            r#"{"type": "integer", "maximum": 65535}"#,
+        )
+        .unwrap();
+
+        let artifact = compile_schema(schema_path.to_str().unwrap()).unwrap();
+        assert_eq!(artifact["version"], COMPILED_FORMAT_VERSION);
+        assert_eq!(artifact["draft"], "2020-12");
+
+        // The referenced file is bundled, so the artifact stands alone
+        drop(sources);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.vjsc");
+        fs::write(&path, artifact.to_string()).unwrap();
+        assert!(is_compiled_path(&path));
+        let validator = Validator::from_compiled(&path).unwrap();
+        assert!(validator.validate_json(r#"{"port": 8080}"#).is_ok());
+        assert!(validator.validate_json(r#"{"port": 70000}"#).is_err());
+
+        let mut edited = artifact.clone();
+        edited["schema"]["properties"] = json!({});
+        fs::write(&path, edited.to_string()).unwrap();
+        let error = Validator::from_compiled(&path).unwrap_err().to_string();
+        assert!(error.contains("does not match its digest"), "{}", error);
+
+        let mut newer = artifact;
+        newer["version"] = json!(COMPILED_FORMAT_VERSION + 1);
+        fs::write(&path, newer.to_string()).unwrap();
+        let error = Validator::from_compiled(&path).unwrap_err().to_string();
+        assert!(error.contains("is not supported"), "{}", error);
+
+        fs::write(&path, "{}").unwrap();
+        assert!(Validator::from_compiled(&path).is_err());
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.json");
+        fs::write(&path, r#"{"properties": {"id": {"pattern": "(unclosed"}}}"#).unwrap();
+        assert!(compile_schema(path.to_str().unwrap()).is_err());
+    }
+}
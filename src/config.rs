@@ -0,0 +1,600 @@
+//! Service configuration assembled from environment variables.
+//!
+//! Variables named `<PREFIX>__<KEY>__<KEY>...` become nested properties of one
+//! JSON object, e.g. `APP__SERVER__PORT=8080` with the prefix `APP` gives
+//! `{"server": {"port": 8080}}`. Keys are lowercased unless the schema declares
+//! a property that matches them ignoring case, with `_` also matching `-`, in
+//! which case the declared name is used.
+//!
+//! Every value starts out as a string and is converted to the type the schema
+//! declares for its location: `integer`, `number`, `boolean` and `null` are
+//! parsed, and `array` or `object` values are read as JSON, with comma-separated
+//! lists also accepted for arrays. A value the schema allows as a string, or
+//! that has no declared type, is kept as written. The result is validated
+//! before it is returned, so a value that cannot be converted is reported as
+//! a validation failure.
+//!
+//! [`check_env`] instead checks the variables themselves against a schema
+//! whose properties are variable names, for services that read their
+//! environment directly rather than through a nested configuration object.
+
+use crate::partial::{applicable_subschemas, expand};
+use crate::walk::escape_token;
+use crate::{failures_to_result, ValidationError, ValidationFailure, ValidatorBuilder};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+/// Separates the prefix and the keys in a variable name.
+const SEPARATOR: &str = "__";
+
+/// Shown in place of variable values in failure messages.
+const REDACTED: &str = "<redacted>";
+
+/// Load configuration for `schema` from the process environment.
+///
+/// See [`env_config_from`] for how variables are read.
+///
+/// # Errors
+///
+/// Returns an error if the schema is invalid, two variables conflict, or the
+/// configuration fails the schema or cannot be deserialized into `T`.
+pub fn load_env_config<T: DeserializeOwned>(
+    prefix: &str,
+    schema: &Value,
+) -> Result<T, ValidationError> {
+    // Variables that are not valid Unicode cannot be JSON strings
+    let vars = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    env_config_from(vars, prefix, schema)
+}
+
+/// Assemble configuration for `schema` from the `<prefix>__`-prefixed
+/// entries of `vars`, validate it and deserialize it into `T`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::{json, Value};
+/// use validate_json_schema::env_config_from;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = json!({
+///     "type": "object",
+///     "properties": {
+///         "server": {
+///             "properties": {
+///                 "port": {"type": "integer"},
+///                 "maxConnections": {"type": "integer"}
+///             }
+///         }
+///     }
+/// });
+/// let vars = [
+///     ("APP__SERVER__PORT", "8080"),
+///     ("APP__SERVER__MAXCONNECTIONS", "64"),
+///     ("HOME", "/root"),
+/// ];
+///
+/// let config: Value = env_config_from(vars, "APP", &schema)?;
+/// assert_eq!(config, json!({"server": {"port": 8080, "maxConnections": 64}}));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the schema is invalid, two variables conflict, or the
+/// configuration fails the schema or cannot be deserialized into `T`.
+pub fn env_config_from<T, I, K, V>(
+    vars: I,
+    prefix: &str,
+    schema: &Value,
+) -> Result<T, ValidationError>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let validator = ValidatorBuilder::new().build(schema)?;
+    let draft = validator.draft();
+    let prefix = format!("{}{}", prefix, SEPARATOR);
+
+    let mut entries: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.as_ref().starts_with(&prefix))
+        .map(|(name, value)| (name.as_ref().to_string(), value.as_ref().to_string()))
+        .collect();
+    entries.sort();
+
+    let mut document = Value::Object(Map::new());
+    for (name, value) in entries {
+        let mut pointer = String::new();
+        let keys: Vec<&str> = name[prefix.len()..].split(SEPARATOR).collect();
+        for (index, key) in keys.iter().enumerate() {
+            if key.is_empty() {
+                return Err(ValidationError::Config(format!(
+                    "'{}' has an empty key",
+                    name
+                )));
+            }
+            let key = declared_key(schema, draft, &document, &pointer, key);
+            let last = index == keys.len() - 1;
+            let parent = document
+                .pointer_mut(&pointer)
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| conflict(&name, &pointer))?;
+            if last {
+                if parent.contains_key(&key) {
+                    return Err(conflict(&name, &pointer));
+                }
+                parent.insert(key.clone(), Value::String(value.clone()));
+            } else {
+                parent
+                    .entry(key.clone())
+                    .or_insert_with(|| Value::Object(Map::new()));
+            }
+            pointer = format!("{}/{}", pointer, escape_token(&key));
+        }
+    }
+
+    coerce(schema, draft, &mut document, "");
+    validator.validate_value(&document)?;
+    serde_json::from_value(document).map_err(|e| ValidationError::Config(e.to_string()))
+}
+
+/// Check the process environment against `schema`.
+///
+/// See [`check_env_from`] for which variables are checked.
+///
+/// # Errors
+///
+/// Returns an error if the schema is invalid or the variables fail it.
+pub fn check_env(schema: &Value) -> Result<(), ValidationError> {
+    let vars = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    check_env_from(vars, schema)
+}
+
+/// Check the entries of `vars` against `schema`, which describes them as the
+/// properties of an object: `required` lists the variables that must be set,
+/// and `properties` and `patternProperties` constrain their values.
+///
+/// Only variables the schema names in `properties` or matches with a
+/// `patternProperties` pattern are checked, so it need not mention `PATH` or
+/// `HOME`. Values are converted to the types the schema declares, as
+/// [`env_config_from`] does. Failure messages leave out the values, which
+/// are often secrets.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::check_env_from;
+///
+/// let schema = json!({
+///     "required": ["DATABASE_URL", "PORT"],
+///     "properties": {
+///         "DATABASE_URL": {"type": "string", "pattern": "^postgres://"},
+///         "PORT": {"type": "integer", "maximum": 65535}
+///     }
+/// });
+///
+/// assert!(check_env_from([("DATABASE_URL", "postgres://db"), ("PORT", "5432")], &schema).is_ok());
+/// let error = check_env_from([("DATABASE_URL", "mysql://root:hunter2@db")], &schema).unwrap_err();
+/// assert!(error.to_string().contains("\"PORT\" is a required property"));
+/// assert!(!error.to_string().contains("hunter2"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the schema is invalid or the variables fail it.
+pub fn check_env_from<I, K, V>(vars: I, schema: &Value) -> Result<(), ValidationError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let validator = ValidatorBuilder::new().build(schema)?;
+    let draft = validator.draft();
+    let root = subschemas_at(schema, draft, &Value::Object(Map::new()), "");
+    let names: Vec<&String> = root
+        .iter()
+        .filter_map(|subschema| subschema.get("properties")?.as_object())
+        .flat_map(|properties| properties.keys())
+        .collect();
+    let patterns: Vec<Regex> = root
+        .iter()
+        .filter_map(|subschema| subschema.get("patternProperties")?.as_object())
+        .flat_map(|patterns| patterns.keys())
+        .filter_map(|pattern| Regex::new(pattern).ok())
+        .collect();
+
+    let mut document = Value::Object(
+        vars.into_iter()
+            .map(|(name, value)| (name.as_ref().to_string(), value.as_ref().to_string()))
+            .filter(|(name, _)| {
+                names.contains(&name) || patterns.iter().any(|pattern| pattern.is_match(name))
+            })
+            .map(|(name, value)| (name, Value::String(value)))
+            .collect(),
+    );
+    coerce(schema, draft, &mut document, "");
+
+    let failures: Vec<ValidationFailure> = validator
+        .validate_detailed(&document)
+        .into_iter()
+        .map(|mut failure| {
+            // Messages start with the value; strings are also replaced
+            // wherever they appear, as they cannot be mistaken for limits
+            match failure.instance(&document) {
+                Some(value @ Value::String(_)) => {
+                    failure.message = failure.message.replace(&value.to_string(), REDACTED);
+                }
+                Some(value) => {
+                    if let Some(rest) = failure.message.strip_prefix(&value.to_string()) {
+                        failure.message = format!("{}{}", REDACTED, rest);
+                    }
+                }
+                None => {}
+            }
+            failure
+        })
+        .collect();
+    failures_to_result(&failures)
+}
+
+/// Read the variables of an env file, as Docker Compose and dotenv libraries
+/// do: `NAME=value` lines, optionally starting with `export`, with blank
+/// lines and `#` comments ignored. Values may be single-quoted (taken as
+/// written) or double-quoted (with `\n`, `\"` and `\\` escapes); unquoted
+/// values end at a ` #` comment and are trimmed.
+///
+/// # Errors
+///
+/// Returns [`ValidationError::Config`] naming the line of an entry without
+/// `=`, with an invalid name or with an unterminated quote.
+pub fn parse_env_file(content: &str) -> Result<Vec<(String, String)>, ValidationError> {
+    let invalid = |line: usize, problem: &str| {
+        ValidationError::Config(format!("env file line {}: {}", line + 1, problem))
+    };
+    let mut vars = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(index, "expected NAME=value"))?;
+        let name = name.trim();
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(invalid(index, &format!("invalid variable name {:?}", name)));
+        }
+        let value = value.trim_start();
+        let value = if let Some(quoted) = value.strip_prefix('\'') {
+            let (value, _) = quoted
+                .split_once('\'')
+                .ok_or_else(|| invalid(index, "unterminated single quote"))?;
+            value.to_string()
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            let mut unescaped = String::new();
+            let mut chars = quoted.chars();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => unescaped.push('\n'),
+                        Some(other) => unescaped.push(other),
+                        None => return Err(invalid(index, "unterminated double quote")),
+                    },
+                    Some(c) => unescaped.push(c),
+                    None => return Err(invalid(index, "unterminated double quote")),
+                }
+            }
+            unescaped
+        } else {
+            let end = value.find(" #").unwrap_or(value.len());
+            value[..end].trim_end().to_string()
+        };
+        vars.push((name.to_string(), value));
+    }
+    Ok(vars)
+}
+
+fn conflict(name: &str, pointer: &str) -> ValidationError {
+    ValidationError::Config(format!(
+        "'{}' conflicts with another variable setting '{}'",
+        name,
+        if pointer.is_empty() { "/" } else { pointer }
+    ))
+}
+
+/// The subschemas that apply to the value at `pointer`, with `$ref`s and
+/// `allOf`s expanded.
+fn subschemas_at<'s>(
+    schema: &'s Value,
+    draft: crate::Draft,
+    document: &Value,
+    pointer: &str,
+) -> Vec<&'s Value> {
+    let mut expanded = Vec::new();
+    for schema_pointer in applicable_subschemas(schema, draft, document, pointer) {
+        expand(schema, draft, &schema_pointer, &mut expanded, 0);
+    }
+    expanded
+        .iter()
+        .filter_map(|schema_pointer| schema.pointer(schema_pointer))
+        .collect()
+}
+
+/// The property name the schema declares for the variable key `key` in the
+/// object at `pointer`, or the key lowercased.
+fn declared_key(
+    schema: &Value,
+    draft: crate::Draft,
+    document: &Value,
+    pointer: &str,
+    key: &str,
+) -> String {
+    let normalize = |name: &str| name.to_lowercase().replace('-', "_");
+    let wanted = normalize(key);
+    subschemas_at(schema, draft, document, pointer)
+        .into_iter()
+        .filter_map(|subschema| subschema.get("properties")?.as_object())
+        .flat_map(|properties| properties.keys())
+        .find(|name| normalize(name) == wanted)
+        .cloned()
+        .unwrap_or_else(|| key.to_lowercase())
+}
+
+/// Convert the strings in the document built from variables to the types the
+/// schema declares for them.
+pub(crate) fn coerce(schema: &Value, draft: crate::Draft, document: &mut Value, pointer: &str) {
+    let types: Vec<String> = subschemas_at(schema, draft, document, pointer)
+        .into_iter()
+        .filter_map(|subschema| subschema.get("type"))
+        .flat_map(|declared| match declared {
+            Value::String(name) => vec![name.clone()],
+            Value::Array(names) => names
+                .iter()
+                .filter_map(|name| name.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+
+    let slot = match document.pointer_mut(pointer) {
+        Some(slot) => slot,
+        None => return,
+    };
+    let children: Vec<String> = match slot {
+        Value::String(raw) => match convert(raw, &types) {
+            Some(Value::Array(items))
+                if items.iter().all(Value::is_string) && !raw.starts_with('[') =>
+            {
+                // A comma-separated list, whose items are converted in turn
+                let count = items.len();
+                *slot = Value::Array(items);
+                (0..count).map(|index| index.to_string()).collect()
+            }
+            Some(value) => {
+                *slot = value;
+                Vec::new()
+            }
+            None => Vec::new(),
+        },
+        Value::Object(entries) => entries.keys().map(|key| escape_token(key)).collect(),
+        Value::Array(items) => (0..items.len()).map(|index| index.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    for child in children {
+        coerce(schema, draft, document, &format!("{}/{}", pointer, child));
+    }
+}
+
+/// `raw` converted to the first of `types` it can represent, or `None` to
+/// keep it as a string.
+fn convert(raw: &str, types: &[String]) -> Option<Value> {
+    if types.is_empty() || types.iter().any(|name| name == "string") {
+        return None;
+    }
+    types.iter().find_map(|name| match name.as_str() {
+        "null" if raw.is_empty() || raw == "null" => Some(Value::Null),
+        "boolean" => match raw.to_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        "integer" => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| raw.parse::<u64>().map(Value::from))
+            .ok(),
+        "number" => raw.parse::<i64>().map(Value::from).ok().or_else(|| {
+            raw.parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+        }),
+        "object" => serde_json::from_str::<Value>(raw)
+            .ok()
+            .filter(Value::is_object),
+        "array" => serde_json::from_str::<Value>(raw)
+            .ok()
+            .filter(Value::is_array)
+            .or_else(|| {
+                let items = if raw.is_empty() {
+                    Vec::new()
+                } else {
+                    raw.split(',')
+                        .map(|item| Value::String(item.trim().to_string()))
+                        .collect()
+                };
+                Some(Value::Array(items))
+            }),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["server"],
+            "properties": {
+                "server": {"$ref": "#/definitions/server"},
+                "debug": {"type": "boolean"},
+                "ratio": {"type": "number"},
+                "hosts": {"type": "array", "items": {"type": "string"}},
+                "ports": {"type": "array", "items": {"type": "integer"}},
+                "labels": {"type": "object"},
+                "name": {"type": ["string", "null"]}
+            },
+            "definitions": {
+                "server": {
+                    "required": ["port"],
+                    "properties": {
+                        "port": {"type": "integer", "maximum": 65535},
+                        "log-level": {"enum": ["info", "debug"]}
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_env_config() {
+        let vars = [
+            ("APP__SERVER__PORT", "8080"),
+            ("APP__SERVER__LOG_LEVEL", "debug"),
+            ("APP__DEBUG", "TRUE"),
+            ("APP__RATIO", "0.5"),
+            ("APP__HOSTS", "a.example.com, b.example.com"),
+            ("APP__PORTS", "80,443"),
+            ("APP__LABELS", r#"{"team": "core"}"#),
+            ("APP__NAME", "007"),
+            ("APP__EXTRA__KEY", "1"),
+            ("APPLICATION__SERVER__PORT", "1"),
+        ];
+        let config: Value = env_config_from(vars, "APP", &schema()).unwrap();
+        assert_eq!(
+            config,
+            json!({
+                "server": {"port": 8080, "log-level": "debug"},
+                "debug": true,
+                "ratio": 0.5,
+                "hosts": ["a.example.com", "b.example.com"],
+                "ports": [80, 443],
+                "labels": {"team": "core"},
+                "name": "007",
+                "extra": {"key": "1"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_env_config_errors() {
+        let error = env_config_from::<Value, _, _, _>([("APP__DEBUG", "1")], "APP", &schema())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("\"server\" is a required property"),
+            "{}",
+            error
+        );
+        assert!(
+            error.contains("\"1\" is not of type \"boolean\""),
+            "{}",
+            error
+        );
+
+        let vars = [("APP__SERVER", "x"), ("APP__SERVER__PORT", "1")];
+        assert!(matches!(
+            env_config_from::<Value, _, _, _>(vars, "APP", &schema()),
+            Err(ValidationError::Config(_))
+        ));
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Typed {
+            debug: bool,
+        }
+        let vars = [("APP__SERVER__PORT", "1")];
+        let error = env_config_from::<Typed, _, _, _>(vars, "APP", &schema()).unwrap_err();
+        assert!(error.to_string().contains("missing field `debug`"));
+    }
+
+    #[test]
+    fn test_check_env() {
+        let schema = json!({
+            "required": ["PORT"],
+            "properties": {
+                "PORT": {"type": "integer", "minimum": 1024},
+                "TOKEN": {"type": "string", "minLength": 8},
+                "HOSTS": {"type": "array", "items": {"type": "string"}}
+            },
+            "patternProperties": {"^FEATURE_": {"type": "boolean"}},
+            "additionalProperties": false
+        });
+        let vars = [
+            ("PORT", "8080"),
+            ("HOSTS", "a,b"),
+            ("FEATURE_X", "true"),
+            ("HOME", "/root"),
+        ];
+        assert!(check_env_from(vars, &schema).is_ok());
+
+        let vars = [("PORT", "80"), ("TOKEN", "s3cret"), ("FEATURE_Y", "maybe")];
+        let error = check_env_from(vars, &schema).unwrap_err().to_string();
+        assert!(
+            error.contains("/PORT: <redacted> is less than the minimum of 1024"),
+            "{}",
+            error
+        );
+        assert!(error.contains("/TOKEN: <redacted> is shorter"), "{}", error);
+        assert!(
+            error.contains("/FEATURE_Y: <redacted> is not of type"),
+            "{}",
+            error
+        );
+        assert!(
+            !error.contains("s3cret") && !error.contains("maybe"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let content = "# settings\n\nexport PORT=8080\nNAME = web # the name\n\
+                       GREETING=\"hello \\\"you\\\"\\nbye\"\nRAW='a\\n#b'\nEMPTY=\n";
+        assert_eq!(
+            parse_env_file(content).unwrap(),
+            [
+                ("PORT", "8080"),
+                ("NAME", "web"),
+                ("GREETING", "hello \"you\"\nbye"),
+                ("RAW", "a\\n#b"),
+                ("EMPTY", ""),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+        assert_eq!(
+            parse_env_file("A=1\nnot a variable\n")
+                .unwrap_err()
+                .to_string(),
+            "Invalid configuration: env file line 2: expected NAME=value"
+        );
+        assert!(parse_env_file("1A=x\n").is_err());
+        assert!(parse_env_file("A='x\n").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Validating the rows of a CSV file.
+//!
+//! Spreadsheet exports and data feeds often arrive as CSV. [`parse_csv`]
+//! turns each row into an object keyed by the header row, and
+//! [`validate_csv`] validates every row against the schema and reports the
+//! failures by row number, counting the header as row 1 as spreadsheets do.
+//!
+//! Fields follow RFC 4180: they may be quoted with `"`, contain the delimiter
+//! or line breaks when quoted, and escape a quote by doubling it. Every value
+//! is a string unless [`CsvOptions::infer_types`] is set, in which case cells
+//! are converted to the type the schema declares for their column, the same
+//! way [`env_config_from`](crate::env_config_from) converts variables.
+
+use crate::config::coerce;
+use crate::{ValidationError, ValidationFailure, Validator};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Settings for [`parse_csv`] and [`validate_csv`].
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Field separator, `,` by default.
+    pub delimiter: char,
+    /// Convert cells to the types the schema declares for their column and
+    /// leave empty cells out of the row, so `required` catches them.
+    pub infer_types: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            infer_types: false,
+        }
+    }
+}
+
+/// A failure of one row of a CSV file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowFailure {
+    /// The row, counting the header as row 1.
+    pub row: usize,
+    /// Why the row's object failed.
+    pub failure: ValidationFailure,
+}
+
+impl fmt::Display for RowFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}: {}", self.row, self.failure)
+    }
+}
+
+/// Parse CSV `content` into one object per row, keyed by the header row.
+///
+/// Cells are strings; a row with fewer fields than the header leaves the
+/// remaining properties out.
+///
+/// # Errors
+///
+/// Returns an error if the content has no header row, the header repeats a
+/// name, a row has more fields than the header, or a quote is not closed.
+pub fn parse_csv(content: &str, options: &CsvOptions) -> Result<Vec<Value>, ValidationError> {
+    let mut records = read_records(content, options.delimiter)?.into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| invalid("the file has no header row".to_string()))?;
+    for (index, name) in header.iter().enumerate() {
+        if header[..index].contains(name) {
+            return Err(invalid(format!("the header repeats the column '{}'", name)));
+        }
+    }
+
+    records
+        .enumerate()
+        .map(|(index, fields)| {
+            if fields.len() > header.len() {
+                return Err(invalid(format!(
+                    "row {} has {} fields but the header has {}",
+                    index + 2,
+                    fields.len(),
+                    header.len()
+                )));
+            }
+            let row: Map<String, Value> = header
+                .iter()
+                .cloned()
+                .zip(fields.into_iter().map(Value::String))
+                .collect();
+            Ok(Value::Object(row))
+        })
+        .collect()
+}
+
+/// Validate every row of CSV `content` against `validator`, built from
+/// `schema`, and return the failures of all rows.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::{validate_csv, CsvOptions, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = json!({
+///     "required": ["name", "age"],
+///     "properties": {"age": {"type": "integer", "minimum": 0}}
+/// });
+/// let validator = Validator::from_value(&schema)?;
+/// let content = "name,age\nAda,36\nBob,-1\n,\n";
+/// let options = CsvOptions { infer_types: true, ..CsvOptions::default() };
+///
+/// let failures = validate_csv(&validator, &schema, content, &options)?;
+/// assert_eq!(failures[0].to_string(), "row 3: /age: -1 is less than the minimum of 0");
+/// assert_eq!(failures[1].row, 4);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the content is not well-formed CSV, as for
+/// [`parse_csv`].
+pub fn validate_csv(
+    validator: &Validator,
+    schema: &Value,
+    content: &str,
+    options: &CsvOptions,
+) -> Result<Vec<RowFailure>, ValidationError> {
+    let mut failures = Vec::new();
+    for (index, mut row) in parse_csv(content, options)?.into_iter().enumerate() {
+        if options.infer_types {
+            if let Some(cells) = row.as_object_mut() {
+                cells.retain(|_, cell| cell.as_str() != Some(""));
+            }
+            coerce(schema, validator.draft(), &mut row, "");
+        }
+        failures.extend(validator.iter_errors(&row).map(|failure| RowFailure {
+            row: index + 2,
+            failure,
+        }));
+    }
+    Ok(failures)
+}
+
+fn invalid(message: String) -> ValidationError {
+    ValidationError::CsvParse(message)
+}
+
+/// Split `content` into records of fields. Blank lines are skipped.
+fn read_records(content: &str, delimiter: char) -> Result<Vec<Vec<String>>, ValidationError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record != [""] {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(invalid("a quoted field is not closed".to_string()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "\u{feff}name;note;tags\r\n\"Doe; Jane\";\"said \"\"hi\"\"\nthen left\";\"a,b\"\r\n\nBob;\r\n";
+        let options = CsvOptions {
+            delimiter: ';',
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            parse_csv(content, &options).unwrap(),
+            vec![
+                json!({"name": "Doe; Jane", "note": "said \"hi\"\nthen left", "tags": "a,b"}),
+                json!({"name": "Bob", "note": ""}),
+            ]
+        );
+
+        let options = CsvOptions::default();
+        assert!(parse_csv("", &options).is_err());
+        assert!(parse_csv("a,a\n1,2", &options).is_err());
+        assert!(parse_csv("a\n1,2", &options).is_err());
+        assert!(parse_csv("a\n\"1", &options).is_err());
+    }
+
+    #[test]
+    fn test_validate_csv() {
+        let schema = json!({
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "integer"},
+                "zip": {"type": "string", "pattern": "^[0-9]{5}$"},
+                "active": {"type": "boolean"},
+                "tags": {"type": "array", "maxItems": 2}
+            }
+        });
+        let validator = Validator::from_value(&schema).unwrap();
+        let content =
+            "id,zip,active,tags\n1,02134,true,\"a,b\"\nx,2134,yes,\n,02134,false,\"a,b,c\"\n";
+
+        let failures = validate_csv(&validator, &schema, content, &CsvOptions::default()).unwrap();
+        assert!(failures.iter().all(|failure| failure.row > 1));
+        assert!(failures.iter().any(|failure| failure.row == 2));
+
+        let options = CsvOptions {
+            infer_types: true,
+            ..CsvOptions::default()
+        };
+        let failures: Vec<(usize, String)> = validate_csv(&validator, &schema, content, &options)
+            .unwrap()
+            .into_iter()
+            .map(|failure| (failure.row, failure.failure.instance_path))
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (3, "/active".to_string()),
+                (3, "/id".to_string()),
+                (3, "/zip".to_string()),
+                (4, "/tags".to_string()),
+                (4, String::new()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Rendering failures as code frames.
+//!
+//! A failure message names the failing value by its JSON Pointer, which is
+//! precise but hard to find in a large file. [`code_frames`] prints each
+//! failure the way compilers do, with the lines around the value and carets
+//! under it:
+//!
+//! ```text
+//! error[type]: "many" is not of type "integer"
+//!  --> deploy/web.yaml:3:13
+//!   |
+//! 2 | spec:
+//! 3 |   replicas: many
+//!   |             ^^^^ /spec/replicas
+//! 4 |   ports:
+//!   |
+//! ```
+
+use crate::failure::locate_spans;
+use crate::{DocumentFormat, ValidationFailure};
+use std::fmt::Write;
+
+/// Lines of source shown before and after the failing line.
+const CONTEXT_LINES: usize = 1;
+
+/// Render each of `failures` of the document `content`, read from `path`,
+/// as a code frame, separated by blank lines. Failures that cannot be
+/// located in the source are rendered without one.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::diagnostic::code_frames;
+/// use validate_json_schema::{DocumentFormat, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(r#"{"properties": {"port": {"type": "integer"}}}"#)?;
+/// let content = "name: web\nport: http # default\n";
+/// let document = DocumentFormat::Yaml.parse(content)?;
+/// let failures = validator.validate_detailed(&document);
+///
+/// assert_eq!(
+///     code_frames("web.yaml", content, DocumentFormat::Yaml, &failures),
+///     "error[type]: \"http\" is not of type \"integer\"\n \
+///      --> web.yaml:2:7\n  \
+///       |\n\
+///      1 | name: web\n\
+///      2 | port: http # default\n  \
+///       |       ^^^^ /port\n  \
+///       |\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn code_frames(
+    path: &str,
+    content: &str,
+    format: DocumentFormat,
+    failures: &[ValidationFailure],
+) -> String {
+    document_code_frames(path, content, content, format, failures)
+}
+
+/// Render the `failures` of `document`, one of the documents of the stream
+/// `content`, as [`code_frames`] does. The document must start with a blank
+/// line for each line of the stream before it, as
+/// [`split_documents`](crate::yaml::split_documents) leaves it, so that its
+/// failures are located on the lines of the stream they are shown in.
+pub(crate) fn document_code_frames(
+    path: &str,
+    content: &str,
+    document: &str,
+    format: DocumentFormat,
+    failures: &[ValidationFailure],
+) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let spans = locate_spans(document, format, failures);
+    let mut out = String::new();
+    for (index, (failure, span)) in failures.iter().zip(spans).enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let pointer = if failure.instance_path.is_empty() {
+            "root"
+        } else {
+            &failure.instance_path
+        };
+        let _ = writeln!(out, "error[{}]: {}", failure.keyword, failure.message);
+        let Some((location, width)) = span else {
+            let _ = writeln!(out, " --> {}\n  = at {}", path, pointer);
+            continue;
+        };
+        let (start, end) = (location.column - 1, location.column - 1 + width);
+        let first = location.line.saturating_sub(CONTEXT_LINES).max(1);
+        let last = (location.line + CONTEXT_LINES).min(lines.len());
+        let width = last.to_string().len();
+        let gutter = " ".repeat(width);
+        let _ = writeln!(
+            out,
+            "{}--> {}:{}:{}",
+            gutter, path, location.line, location.column
+        );
+        let _ = writeln!(out, "{} |", gutter);
+        for number in first..=last {
+            let line = lines[number - 1];
+            let _ = writeln!(out, "{:>width$} | {}", number, line, width = width);
+            if number == location.line {
+                // Keep tabs so the carets line up however they are shown
+                let indent: String = line
+                    .chars()
+                    .take(start)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                let _ = writeln!(
+                    out,
+                    "{} | {}{} {}",
+                    gutter,
+                    indent,
+                    "^".repeat(end - start),
+                    pointer
+                );
+            }
+        }
+        let _ = writeln!(out, "{} |", gutter);
+    }
+    out
+}
+
+/// The columns, in characters from 0, of the value on line `line` of
+/// `lines`, counting from 0, whose key, `- ` indicator or first character is
+/// at `column`. A value that starts on a later line is represented by its
+/// key or indicator.
+pub(crate) fn value_span(
+    lines: &[&str],
+    line: usize,
+    column: usize,
+    format: DocumentFormat,
+) -> (usize, usize) {
+    let chars: Vec<char> = lines[line].chars().collect();
+    let start = column.min(chars.len());
+    let key_end = match format {
+        DocumentFormat::Yaml => yaml_key_end(&chars, start),
+        DocumentFormat::Json | DocumentFormat::Json5 => json_key_end(&chars, start),
+    };
+    let value = skip_space(&chars, key_end.unwrap_or(start));
+    let end = match format {
+        DocumentFormat::Yaml => yaml_value_end(&chars, value),
+        DocumentFormat::Json | DocumentFormat::Json5 => json_value_end(&chars, value),
+    };
+    if end > value {
+        return (value, end);
+    }
+    // The value is on the following lines; mark the key or indicator
+    let marker = key_end.map_or(start + 1, |end| end - 1);
+    (start, marker.clamp(start + 1, chars.len().max(start + 1)))
+}
+
+fn skip_space(chars: &[char], mut at: usize) -> usize {
+    while chars.get(at).is_some_and(|c| c.is_whitespace()) {
+        at += 1;
+    }
+    at
+}
+
+/// The column after the closing quote of the string starting at `at`.
+fn quoted_end(chars: &[char], at: usize) -> usize {
+    let quote = chars[at];
+    let mut index = at + 1;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' if quote != '\'' => index += 1,
+            // YAML escapes a single quote by doubling it
+            '\'' if quote == '\'' && chars.get(index + 1) == Some(&'\'') => index += 1,
+            c if c == quote => return index + 1,
+            _ => {}
+        }
+        index += 1;
+    }
+    chars.len()
+}
+
+/// The column after `key:` or `- ` at `at`, if there is one.
+fn yaml_key_end(chars: &[char], at: usize) -> Option<usize> {
+    match chars.get(at)? {
+        '-' if chars.get(at + 1).is_none_or(|c| *c == ' ') => Some(at + 1),
+        '"' | '\'' => {
+            let end = quoted_end(chars, at);
+            (chars.get(end) == Some(&':')).then_some(end + 1)
+        }
+        _ => (at..chars.len())
+            .find(|&index| chars[index] == ':' && chars.get(index + 1).is_none_or(|c| *c == ' '))
+            .map(|colon| colon + 1),
+    }
+}
+
+/// The column after the YAML scalar or flow collection at `at`, without a
+/// trailing comment.
+fn yaml_value_end(chars: &[char], at: usize) -> usize {
+    match chars.get(at) {
+        None | Some('#') => at,
+        Some('"' | '\'') => quoted_end(chars, at),
+        Some('|' | '>') => at,
+        Some(_) => {
+            let comment = (at..chars.len())
+                .find(|&index| chars[index] == '#' && chars[index - 1] == ' ')
+                .unwrap_or(chars.len());
+            let mut end = comment;
+            while end > at && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            end
+        }
+    }
+}
+
+/// The column after `"key":` at `at`, if there is a key.
+fn json_key_end(chars: &[char], at: usize) -> Option<usize> {
+    let end = match chars.get(at)? {
+        '"' | '\'' => quoted_end(chars, at),
+        _ => (at..chars.len())
+            .find(|&index| chars[index] == ':' || chars[index].is_whitespace())
+            .unwrap_or(chars.len()),
+    };
+    let colon = skip_space(chars, end);
+    (chars.get(colon) == Some(&':')).then_some(colon + 1)
+}
+
+/// The column after the JSON value at `at`, or the end of the line if the
+/// value continues on the next.
+fn json_value_end(chars: &[char], at: usize) -> usize {
+    match chars.get(at) {
+        None => at,
+        Some('"' | '\'') => quoted_end(chars, at),
+        Some('{' | '[') => {
+            let mut depth = 0;
+            let mut index = at;
+            while index < chars.len() {
+                match chars[index] {
+                    '"' | '\'' => {
+                        index = quoted_end(chars, index);
+                        continue;
+                    }
+                    '{' | '[' => depth += 1,
+                    '}' | ']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return index + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                index += 1;
+            }
+            let mut end = chars.len();
+            while end > at && chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            end
+        }
+        Some(_) => (at..chars.len())
+            .find(|&index| matches!(chars[index], ',' | '}' | ']') || chars[index].is_whitespace())
+            .unwrap_or(chars.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+
+    fn frames(schema: &str, content: &str, format: DocumentFormat) -> String {
+        let validator = Validator::new(schema).unwrap();
+        let document = format.parse(content).unwrap();
+        code_frames(
+            "doc",
+            content,
+            format,
+            &validator.validate_detailed(&document),
+        )
+    }
+
+    #[test]
+    fn test_json_frames() {
+        let schema = r#"{"properties": {"spec": {"properties": {
+            "ports": {"items": {"type": "integer"}},
+            "env": {"type": "string"}
+        }}}}"#;
+        let content = "{\n  \"spec\": {\n    \"ports\": [80, \"http\"],\n    \"env\": {\n      \"a\": 1\n    }\n  }\n}\n";
+        let rendered = frames(schema, content, DocumentFormat::Json);
+        assert!(
+            rendered.contains(
+                "error[type]: \"http\" is not of type \"integer\"\n \
+                 --> doc:3:19\n  |\n2 |   \"spec\": {\n3 |     \"ports\": [80, \"http\"],\n  \
+                 |                   ^^^^^^ /spec/ports/1\n4 |     \"env\": {\n  |\n"
+            ),
+            "{}",
+            rendered
+        );
+        // A value over several lines is marked at its first line
+        assert!(
+            rendered.contains("4 |     \"env\": {\n  |            ^ /spec/env\n"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn test_yaml_frames() {
+        let schema =
+            r#"{"required": ["name"], "properties": {"tags": {"items": {"type": "string"}}}}"#;
+        let content = "tags:\n  - ok\n  - [1]\n  -\n    a: 1\n";
+        let rendered = frames(schema, content, DocumentFormat::Yaml);
+        assert!(
+            rendered.ends_with(
+                "error[required]: \"name\" is a required property\n --> doc\n  = at root\n"
+            ),
+            "{}",
+            rendered
+        );
+        assert!(
+            rendered.contains("3 |   - [1]\n  |     ^^^ /tags/1\n"),
+            "{}",
+            rendered
+        );
+        // A value on the following lines is marked at its indicator
+        assert!(
+            rendered.contains("4 |   -\n  |   ^ /tags/2\n"),
+            "{}",
+            rendered
+        );
+
+        // Documents of a stream are framed in the lines of the whole stream
+        let content = "tags: [a]\n---\n# second\ntags:\n  - [1]\n";
+        let document = &crate::yaml::split_documents(content)[1];
+        let validator = Validator::new(schema).unwrap();
+        let failures = validator.validate_detailed(&DocumentFormat::Yaml.parse(document).unwrap());
+        let rendered =
+            document_code_frames("doc", content, document, DocumentFormat::Yaml, &failures);
+        assert!(
+            rendered
+                .contains(" --> doc:5:5\n  |\n4 | tags:\n5 |   - [1]\n  |     ^^^ /tags/0\n  |\n"),
+            "{}",
+            rendered
+        );
+
+        let chars: Vec<char> = "- 'it''s' # note".chars().collect();
+        assert_eq!(yaml_value_end(&chars, 2), 9);
+    }
+}
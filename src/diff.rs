@@ -0,0 +1,367 @@
+//! Comparing the validation failures of two versions of a document.
+//!
+//! Reviewers of a configuration change care about what the change breaks,
+//! not about problems the file already had. [`diff_failures`] splits the
+//! failures of the old and new version into those the change introduced,
+//! those it fixed and those it left alone.
+//!
+//! [`diff_reports`] does the same for whole runs: given the JSON reports of
+//! two `catalog --json` runs, it finds the violations the later run
+//! introduced and resolved, so a pull request comment can show what a commit
+//! changed rather than the full backlog.
+
+use crate::{ValidationError, ValidationFailure};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// The failures of two versions of a document, compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FailureDiff {
+    /// Failures of the new version the old one did not have.
+    pub new: Vec<ValidationFailure>,
+    /// Failures of the old version the new one no longer has.
+    pub fixed: Vec<ValidationFailure>,
+    /// Failures both versions have.
+    pub unchanged: Vec<ValidationFailure>,
+}
+
+impl FailureDiff {
+    /// Whether the new version introduced no failures.
+    pub fn is_clean(&self) -> bool {
+        self.new.is_empty()
+    }
+}
+
+/// One violation in a run's report: a failure of one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The file, as the report names it.
+    pub path: String,
+    /// The failure. A file that could not be checked at all, e.g. because it
+    /// failed to parse, has one failure at the root whose keyword is the
+    /// file's status in the report, such as `error`.
+    pub failure: ValidationFailure,
+    /// The catalog entry whose schema produced the failure, if the report
+    /// names one.
+    pub schema: Option<String>,
+}
+
+impl Violation {
+    /// The violation as the failure's JSON object with `path` and `schema`
+    /// fields.
+    pub fn to_json(&self) -> Value {
+        let mut value = self.failure.to_json();
+        value["path"] = json!(self.path);
+        value["schema"] = json!(self.schema);
+        value
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.failure)?;
+        match &self.schema {
+            Some(schema) => write!(f, " ({})", schema),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The violations of two runs' reports, compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportDiff {
+    /// Violations of the current run the previous one did not have.
+    pub new: Vec<Violation>,
+    /// Violations of the previous run the current one no longer has.
+    pub resolved: Vec<Violation>,
+    /// How many violations the previous run had.
+    pub previous_total: usize,
+    /// How many violations the current run has.
+    pub current_total: usize,
+}
+
+impl ReportDiff {
+    /// The change in the number of violations; negative if it went down.
+    pub fn change(&self) -> i64 {
+        self.current_total as i64 - self.previous_total as i64
+    }
+
+    /// The new and resolved violations and a summary of the trend, as JSON.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "new": self.new.iter().map(Violation::to_json).collect::<Vec<_>>(),
+            "resolved": self.resolved.iter().map(Violation::to_json).collect::<Vec<_>>(),
+            "summary": {
+                "previous": self.previous_total,
+                "current": self.current_total,
+                "new": self.new.len(),
+                "resolved": self.resolved.len(),
+                "change": self.change(),
+            },
+        })
+    }
+}
+
+/// Compare the failures of the `old` and `new` versions of a document.
+///
+/// Two failures are the same if their instance path, schema path and message
+/// all match, so a value that changes from one invalid value to another is
+/// reported as fixed and new. Each failure is matched at most once.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::{diff_failures, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(r#"{"required": ["name", "port"]}"#)?;
+/// let old = validator.validate_detailed(&json!({"port": 80}));
+/// let new = validator.validate_detailed(&json!({"name": "web"}));
+///
+/// let diff = diff_failures(&old, &new);
+/// assert_eq!(diff.new[0].message, r#""port" is a required property"#);
+/// assert_eq!(diff.fixed[0].message, r#""name" is a required property"#);
+/// assert!(diff.unchanged.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub fn diff_failures(old: &[ValidationFailure], new: &[ValidationFailure]) -> FailureDiff {
+    let mut remaining: Vec<Option<&ValidationFailure>> = old.iter().map(Some).collect();
+    let mut diff = FailureDiff::default();
+
+    for failure in new {
+        let matched = remaining
+            .iter_mut()
+            .find(|candidate| *candidate == &Some(failure))
+            .and_then(Option::take);
+        match matched {
+            Some(_) => diff.unchanged.push(failure.clone()),
+            None => diff.new.push(failure.clone()),
+        }
+    }
+    diff.fixed = remaining.into_iter().flatten().cloned().collect();
+    diff
+}
+
+/// Compare the `previous` and `current` reports of `catalog --json` runs.
+///
+/// Violations match as in [`diff_failures`], and only within the same file
+/// and schema. Schemas a file was not checked against because an earlier one
+/// failed contribute no violations.
+/// Reports written before per-file failures were recorded compare by each
+/// file's error message instead, and reports that name each file's `path`
+/// and list its `failures` rather than its `errors` are still read.
+///
+/// # Errors
+///
+/// Returns an error if either value is not such a report.
+pub fn diff_reports(previous: &Value, current: &Value) -> Result<ReportDiff, ValidationError> {
+    let previous = report_violations(previous)?;
+    let current = report_violations(current)?;
+    let mut remaining: Vec<Option<&Violation>> = previous.iter().map(Some).collect();
+    let mut diff = ReportDiff {
+        previous_total: previous.len(),
+        current_total: current.len(),
+        ..ReportDiff::default()
+    };
+
+    for violation in &current {
+        let matched = remaining
+            .iter_mut()
+            .find(|candidate| *candidate == &Some(violation))
+            .and_then(Option::take);
+        if matched.is_none() {
+            diff.new.push(violation.clone());
+        }
+    }
+    diff.resolved = remaining.into_iter().flatten().cloned().collect();
+    Ok(diff)
+}
+
+/// The violations recorded in a `catalog --json` report.
+fn report_violations(report: &Value) -> Result<Vec<Violation>, ValidationError> {
+    let invalid = |message: &str| ValidationError::InvalidReport(message.to_string());
+    let files = report
+        .get("files")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("expected an object with a \"files\" array"))?;
+
+    let mut violations = Vec::new();
+    for file in files {
+        let path = file.get("file").or_else(|| file.get("path"));
+        let path = path.and_then(Value::as_str);
+        let status = file.get("status").and_then(Value::as_str);
+        let (Some(path), Some(status)) = (path, status) else {
+            return Err(invalid("every file needs a \"file\" and a \"status\""));
+        };
+        if matches!(status, "valid" | "skipped" | "empty") {
+            continue;
+        }
+        let schema = file
+            .get("schema")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let errors = file.get("errors").and_then(Value::as_array);
+        let failures: Vec<ValidationFailure> = errors
+            .map(|errors| errors.iter().map(report_error).collect())
+            .or_else(|| {
+                let failures = file.get("failures").and_then(Value::as_array)?;
+                Some(failures.iter().map(ValidationFailure::from_json).collect())
+            })
+            .map(|failures: Option<Vec<_>>| {
+                failures.ok_or_else(|| invalid("a failure is missing a field"))
+            })
+            .transpose()?
+            .unwrap_or_default();
+        if failures.is_empty() {
+            let message = file.get("error").and_then(Value::as_str).unwrap_or(status);
+            violations.push(Violation {
+                path: path.to_string(),
+                failure: ValidationFailure {
+                    instance_path: String::new(),
+                    schema_path: String::new(),
+                    keyword: status.to_string(),
+                    message: message.to_string(),
+                },
+                schema: schema.clone(),
+            });
+        }
+        violations.extend(failures.into_iter().map(|failure| Violation {
+            path: path.to_string(),
+            failure,
+            schema: schema.clone(),
+        }));
+    }
+    Ok(violations)
+}
+
+/// A failure from the `errors` of a file's result in a report.
+fn report_error(error: &Value) -> Option<ValidationFailure> {
+    let field = |name: &str| Some(error.get(name)?.as_str()?.to_string());
+    Some(ValidationFailure {
+        instance_path: field("pointer")?,
+        schema_path: field("schema_path")?,
+        keyword: field("keyword")?,
+        message: field("message")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_failures() {
+        let validator = Validator::new(
+            r#"{
+                "properties": {
+                    "replicas": {"type": "integer", "minimum": 1},
+                    "image": {"type": "string"},
+                    "ports": {"items": {"type": "integer"}}
+                }
+            }"#,
+        )
+        .unwrap();
+        let old = validator.validate_detailed(&json!({
+            "replicas": 0,
+            "image": 1,
+            "ports": ["a", "a"]
+        }));
+        let new = validator.validate_detailed(&json!({
+            "replicas": "two",
+            "image": 1,
+            "ports": ["a", 80]
+        }));
+
+        let diff = diff_failures(&old, &new);
+        let paths = |failures: &[ValidationFailure]| -> Vec<String> {
+            failures
+                .iter()
+                .map(|failure| failure.instance_path.clone())
+                .collect()
+        };
+        assert_eq!(paths(&diff.new), ["/replicas"]);
+        assert_eq!(diff.new[0].keyword, "type");
+        assert_eq!(paths(&diff.fixed), ["/ports/1", "/replicas"]);
+        assert_eq!(paths(&diff.unchanged), ["/image", "/ports/0"]);
+        assert!(!diff.is_clean());
+
+        assert!(diff_failures(&new, &new).is_clean());
+    }
+
+    #[test]
+    fn test_diff_reports() {
+        let error = |path: &str, message: &str| {
+            json!({
+                "pointer": path,
+                "schema_path": "/properties/port/type",
+                "keyword": "type",
+                "message": message,
+                "location": null,
+            })
+        };
+        let failure = |path: &str, message: &str| {
+            json!({
+                "instance_path": path,
+                "schema_path": "/properties/port/type",
+                "keyword": "type",
+                "message": message,
+            })
+        };
+        let previous = json!({"files": [
+            {"file": "a.yml", "status": "invalid",
+             "errors": [error("/port", "\"x\" is not of type \"integer\"")]},
+            {"file": "b.yml", "status": "invalid",
+             "errors": [error("/port", "\"y\" is not of type \"integer\"")]},
+            {"file": "c.yml", "status": "error", "errors": [], "error": "Failed to parse YAML"},
+            {"file": "d.yml", "status": "valid", "errors": []},
+            {"file": "e.yml", "status": "empty", "errors": []},
+        ]});
+        let current = json!({"files": [
+            {"file": "a.yml", "status": "valid", "errors": []},
+            {"file": "b.yml", "status": "invalid",
+             "errors": [error("/port", "\"y\" is not of type \"integer\"")]},
+            {"file": "c.yml", "status": "error", "errors": [], "error": "Failed to parse YAML"},
+            {"file": "d.yml", "status": "invalid",
+             "errors": [error("/port", "\"y\" is not of type \"integer\"")]},
+        ]});
+
+        let diff = diff_reports(&previous, &current).unwrap();
+        assert_eq!(diff.new.len(), 1);
+        assert_eq!(diff.new[0].path, "d.yml");
+        assert_eq!(diff.resolved.len(), 1);
+        assert_eq!(
+            diff.resolved[0].to_string(),
+            "a.yml: /port: \"x\" is not of type \"integer\""
+        );
+        assert_eq!((diff.previous_total, diff.current_total), (3, 3));
+        assert_eq!(diff.to_json()["summary"]["change"], 0);
+
+        // The same failure under another schema is a different violation, and
+        // reports in the older shape are still read
+        let overlay = json!({"files": [
+            {"path": "b.yml", "schema": "Base", "status": "invalid", "error": "...",
+             "failures": [failure("/port", "\"y\" is not of type \"integer\"")]},
+            {"path": "b.yml", "schema": "Production", "status": "skipped", "error": null},
+        ]});
+        let relabelled = json!({"files": [
+            {"path": "b.yml", "schema": "Base", "status": "valid", "error": null},
+            {"path": "b.yml", "schema": "Production", "status": "invalid", "error": "...",
+             "failures": [failure("/port", "\"y\" is not of type \"integer\"")]},
+        ]});
+        let diff = diff_reports(&overlay, &relabelled).unwrap();
+        assert_eq!(
+            diff.new[0].to_string(),
+            "b.yml: /port: \"y\" is not of type \"integer\" (Production)"
+        );
+        assert_eq!(diff.resolved[0].schema.as_deref(), Some("Base"));
+
+        assert!(diff_reports(&json!([]), &current).is_err());
+        assert!(diff_reports(&json!({"files": [{"file": "a.yml"}]}), &current).is_err());
+        let missing = json!({"files": [{"file": "a.yml", "status": "invalid", "errors": [{}]}]});
+        assert!(diff_reports(&missing, &current).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! JSON Schema draft identification.
+
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// A JSON Schema specification draft.
+///
+/// Variants are ordered by publication date, so drafts can be compared to
+/// tell which one is newer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Draft {
+    /// Draft 4.
+    Draft4,
+    /// Draft 6.
+    Draft6,
+    /// Draft 7.
+    Draft7,
+    /// Draft 2019-09.
+    Draft201909,
+    /// Draft 2020-12.
+    Draft202012,
+}
+
+impl Draft {
+    /// All drafts, oldest first.
+    pub const ALL: [Draft; 5] = [
+        Draft::Draft4,
+        Draft::Draft6,
+        Draft::Draft7,
+        Draft::Draft201909,
+        Draft::Draft202012,
+    ];
+
+    /// The canonical `$schema` URI of the draft's meta-schema.
+    pub fn meta_schema_uri(self) -> &'static str {
+        match self {
+            Draft::Draft4 => "http://json-schema.org/draft-04/schema#",
+            Draft::Draft6 => "http://json-schema.org/draft-06/schema#",
+            Draft::Draft7 => "http://json-schema.org/draft-07/schema#",
+            Draft::Draft201909 => "https://json-schema.org/draft/2019-09/schema",
+            Draft::Draft202012 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    /// Identify a draft from a `$schema` URI.
+    ///
+    /// The scheme and a trailing empty fragment are ignored, so
+    /// `https://json-schema.org/draft-07/schema` and
+    /// `http://json-schema.org/draft-07/schema#` both map to Draft 7.
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        let normalized = uri
+            .trim()
+            .trim_end_matches('#')
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        Draft::ALL.into_iter().find(|draft| {
+            let canonical = draft
+                .meta_schema_uri()
+                .trim_end_matches('#')
+                .trim_start_matches("https://")
+                .trim_start_matches("http://");
+            canonical == normalized
+        })
+    }
+
+    /// The validation engine's identifier for this draft.
+    pub(crate) fn engine_draft(self) -> jsonschema::Draft {
+        match self {
+            Draft::Draft4 => jsonschema::Draft::Draft4,
+            Draft::Draft6 => jsonschema::Draft::Draft6,
+            Draft::Draft7 => jsonschema::Draft::Draft7,
+            Draft::Draft201909 => jsonschema::Draft::Draft201909,
+            Draft::Draft202012 => jsonschema::Draft::Draft202012,
+        }
+    }
+
+    /// The engine's validator for this draft's meta-schema.
+    pub(crate) fn meta_validator(self) -> &'static jsonschema::Validator {
+        match self {
+            Draft::Draft4 => &jsonschema::draft4::meta::VALIDATOR,
+            Draft::Draft6 => &jsonschema::draft6::meta::VALIDATOR,
+            Draft::Draft7 => &jsonschema::draft7::meta::VALIDATOR,
+            Draft::Draft201909 => &jsonschema::draft201909::meta::VALIDATOR,
+            Draft::Draft202012 => &jsonschema::draft202012::meta::VALIDATOR,
+        }
+    }
+
+    /// The keywords this draft defines, including annotations such as `title`.
+    pub(crate) fn keywords(self) -> Vec<&'static str> {
+        const DRAFT4: &[&str] = &[
+            "$ref",
+            "$schema",
+            "additionalItems",
+            "additionalProperties",
+            "allOf",
+            "anyOf",
+            "default",
+            "definitions",
+            "dependencies",
+            "description",
+            "enum",
+            "exclusiveMaximum",
+            "exclusiveMinimum",
+            "format",
+            "items",
+            "maxItems",
+            "maxLength",
+            "maxProperties",
+            "maximum",
+            "minItems",
+            "minLength",
+            "minProperties",
+            "minimum",
+            "multipleOf",
+            "not",
+            "oneOf",
+            "pattern",
+            "patternProperties",
+            "properties",
+            "required",
+            "title",
+            "type",
+            "uniqueItems",
+        ];
+        const DRAFT6: &[&str] = &["$id", "const", "contains", "examples", "propertyNames"];
+        const DRAFT7: &[&str] = &[
+            "$comment",
+            "contentEncoding",
+            "contentMediaType",
+            "else",
+            "if",
+            "readOnly",
+            "then",
+            "writeOnly",
+        ];
+        const DRAFT201909: &[&str] = &[
+            "$anchor",
+            "$defs",
+            "$vocabulary",
+            "contentSchema",
+            "dependentRequired",
+            "dependentSchemas",
+            "deprecated",
+            "maxContains",
+            "minContains",
+            "unevaluatedItems",
+            "unevaluatedProperties",
+        ];
+
+        let mut keywords = DRAFT4.to_vec();
+        if self == Draft::Draft4 {
+            keywords.push("id");
+            return keywords;
+        }
+        keywords.extend(DRAFT6);
+        if self >= Draft::Draft7 {
+            keywords.extend(DRAFT7);
+        }
+        if self >= Draft::Draft201909 {
+            keywords.extend(DRAFT201909);
+        }
+        match self {
+            Draft::Draft201909 => keywords.extend(["$recursiveAnchor", "$recursiveRef"]),
+            Draft::Draft202012 => keywords.extend(["$dynamicAnchor", "$dynamicRef", "prefixItems"]),
+            _ => {}
+        }
+        keywords
+    }
+
+    /// Identify the draft a schema declares through its `$schema` keyword.
+    pub fn detect(schema: &Value) -> Option<Self> {
+        schema
+            .get("$schema")
+            .and_then(Value::as_str)
+            .and_then(Self::from_uri)
+    }
+}
+
+impl FromStr for Draft {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "4" | "draft4" | "draft-04" => Ok(Draft::Draft4),
+            "6" | "draft6" | "draft-06" => Ok(Draft::Draft6),
+            "7" | "draft7" | "draft-07" => Ok(Draft::Draft7),
+            "2019-09" | "201909" | "draft2019-09" | "draft201909" => Ok(Draft::Draft201909),
+            "2020-12" | "202012" | "draft2020-12" | "draft202012" => Ok(Draft::Draft202012),
+            other => Err(format!(
+                "unknown draft '{}' (expected draft4, draft6, draft7, 2019-09 or 2020-12)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Draft {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Draft::Draft4 => "draft4",
+            Draft::Draft6 => "draft6",
+            Draft::Draft7 => "draft7",
+            Draft::Draft201909 => "2019-09",
+            Draft::Draft202012 => "2020-12",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_draft_from_uri() {
+        assert_eq!(
+            Draft::from_uri("http://json-schema.org/draft-07/schema#"),
+            Some(Draft::Draft7)
+        );
+        assert_eq!(
+            Draft::from_uri("https://json-schema.org/draft-07/schema"),
+            Some(Draft::Draft7)
+        );
+        assert_eq!(
+            Draft::from_uri("https://json-schema.org/draft/2020-12/schema#"),
+            Some(Draft::Draft202012)
+        );
+        assert_eq!(Draft::from_uri("https://example.com/schema"), None);
+
+        let schema = json!({"$schema": "https://json-schema.org/draft/2019-09/schema"});
+        assert_eq!(Draft::detect(&schema), Some(Draft::Draft201909));
+        assert_eq!(Draft::detect(&json!({})), None);
+    }
+
+    #[test]
+    fn test_draft_names_round_trip() {
+        for draft in Draft::ALL {
+            assert_eq!(draft.to_string().parse::<Draft>(), Ok(draft));
+        }
+        assert!("draft3".parse::<Draft>().is_err());
+        assert!(Draft::Draft4 < Draft::Draft202012);
+    }
+}
@@ -0,0 +1,317 @@
+//! Custom failure messages from the `errorMessage` keyword.
+//!
+//! Following the `ajv-errors` extension, a schema can replace the engine's
+//! messages with its own guidance:
+//!
+//! - `"errorMessage": "..."` replaces every failure of the schema and its
+//!   subschemas with one failure of the value the schema applies to;
+//! - `"errorMessage": {"<keyword>": "..."}` replaces the failures of that
+//!   keyword of the schema, with `required` also accepting an object of a
+//!   message per property;
+//! - `"errorMessage": {"properties": {"<name>": "..."}}` replaces the
+//!   failures of the property's subschema with one failure of the property;
+//! - `"errorMessage": {"_": "..."}` replaces all other failures of the
+//!   schema and its subschemas, as a string does.
+//!
+//! The innermost `errorMessage` that covers a failure wins. Messages may
+//! interpolate the value the schema applies to with `${0}`, and values
+//! inside it with `${0/name}` or `${/name}`, written as JSON.
+//!
+//! Failures are traced back to their schema by their schema path, through
+//! `$ref`s within the schema document; references to other documents end
+//! the trace, so an `errorMessage` across them does not apply.
+
+use crate::refs;
+use crate::walk;
+use crate::ValidationFailure;
+use serde_json::Value;
+
+/// The keyword failures replaced by an `errorMessage` are reported under.
+const KEYWORD: &str = "errorMessage";
+
+/// The `errorMessage`s of a schema document.
+#[derive(Debug)]
+pub(crate) struct ErrorMessages {
+    schema: Value,
+}
+
+/// A schema on the evaluation path of a failure.
+struct Scope<'a> {
+    schema: &'a Value,
+    /// Where evaluation reached it, as a JSON Pointer.
+    pointer: String,
+    /// Index of the first schema path segment within it.
+    segment: usize,
+    /// Number of instance path segments of the value it applies to.
+    depth: usize,
+}
+
+impl ErrorMessages {
+    /// The `errorMessage`s of `schema`, or `None` if it has none.
+    pub(crate) fn new(schema: &Value) -> Option<Self> {
+        let mut found = false;
+        walk::visit(schema, &mut |_, subschema| {
+            found |= subschema.get(KEYWORD).is_some();
+        });
+        found.then(|| ErrorMessages {
+            schema: schema.clone(),
+        })
+    }
+
+    /// `failure` of `instance`, with the message the schema gives it if it
+    /// has one.
+    pub(crate) fn apply(&self, instance: &Value, failure: ValidationFailure) -> ValidationFailure {
+        let segments: Vec<String> = failure
+            .schema_path
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        let tokens: Vec<&str> = failure.instance_path.split('/').skip(1).collect();
+        for scope in self.scopes(&segments).iter().rev() {
+            let Some((message, pointer, extra)) = scope_message(scope, &segments, &failure) else {
+                continue;
+            };
+            let scope_path: String = tokens[..scope.depth.min(tokens.len())]
+                .iter()
+                .flat_map(|token| ["/", token])
+                .collect();
+            // Templates point into the value the schema applies to
+            let value = instance.pointer(&scope_path).unwrap_or(&Value::Null);
+            let message = interpolate(message, value);
+            return ValidationFailure {
+                instance_path: match extra {
+                    Some(property) => format!("{}/{}", scope_path, walk::escape_token(property)),
+                    None => scope_path,
+                },
+                schema_path: pointer,
+                keyword: KEYWORD.to_string(),
+                message,
+            };
+        }
+        failure
+    }
+
+    /// The schemas that have an `errorMessage` along the evaluation path
+    /// `segments`, outermost first.
+    fn scopes(&self, segments: &[String]) -> Vec<Scope<'_>> {
+        let mut scopes = Vec::new();
+        let mut schema = &self.schema;
+        let mut pointer = String::new();
+        let mut index = 0;
+        let mut depth = 0;
+        loop {
+            if schema.get(KEYWORD).is_some() {
+                scopes.push(Scope {
+                    schema,
+                    pointer: pointer.clone(),
+                    segment: index,
+                    depth,
+                });
+            }
+            let Some(keyword) = segments.get(index) else {
+                break;
+            };
+            let next = segments.get(index + 1).map(String::as_str);
+            let (target, consumed, descends) = match keyword.as_str() {
+                "$ref" => {
+                    let target = schema
+                        .get("$ref")
+                        .and_then(Value::as_str)
+                        .and_then(|reference| refs::resolve_local(&self.schema, reference));
+                    (target, 1, false)
+                }
+                "properties" | "patternProperties" => (
+                    next.and_then(|name| schema.get(keyword)?.get(name)),
+                    2,
+                    true,
+                ),
+                "dependentSchemas" | "dependencies" => (
+                    next.and_then(|name| schema.get(keyword)?.get(name)),
+                    2,
+                    false,
+                ),
+                "allOf" | "anyOf" | "oneOf" => (item(schema.get(keyword), next), 2, false),
+                "prefixItems" => (item(schema.get(keyword), next), 2, true),
+                "items" => match schema.get(keyword) {
+                    Some(Value::Array(_)) => (item(schema.get(keyword), next), 2, true),
+                    items => (items, 1, true),
+                },
+                "additionalProperties"
+                | "additionalItems"
+                | "unevaluatedProperties"
+                | "unevaluatedItems"
+                | "contains" => (schema.get(keyword), 1, true),
+                "not" | "if" | "then" | "else" | "propertyNames" => (schema.get(keyword), 1, false),
+                // The failing keyword
+                _ => break,
+            };
+            let Some(target) = target else {
+                break;
+            };
+            for segment in &segments[index..index + consumed] {
+                pointer = format!("{}/{}", pointer, walk::escape_token(segment));
+            }
+            schema = target;
+            index += consumed;
+            depth += descends as usize;
+        }
+        scopes
+    }
+}
+
+/// The subschema at index `next` of the array `schemas`.
+fn item<'a>(schemas: Option<&'a Value>, next: Option<&str>) -> Option<&'a Value> {
+    schemas?.get(next?.parse::<usize>().ok()?)
+}
+
+/// The message of `scope`'s `errorMessage` for `failure`, the schema path of
+/// the message and, for a message of a property, the property.
+fn scope_message<'a>(
+    scope: &Scope<'a>,
+    segments: &'a [String],
+    failure: &ValidationFailure,
+) -> Option<(&'a str, String, Option<&'a str>)> {
+    let messages = scope.schema.get(KEYWORD)?;
+    let pointer = format!("{}/{}", scope.pointer, KEYWORD);
+    if let Some(message) = messages.as_str() {
+        return Some((message, pointer, None));
+    }
+    let rest = &segments[scope.segment..];
+    let keyword = rest.first().map(String::as_str);
+    let direct = rest.len() == 1;
+    match (keyword, messages.get(keyword.unwrap_or_default())) {
+        (Some(keyword), Some(Value::String(message))) if direct => {
+            return Some((message, format!("{}/{}", pointer, keyword), None));
+        }
+        (Some("required"), Some(Value::Object(properties))) if direct => {
+            // The engine names the missing property first: `"name" is a required property`
+            let name = serde_json::Deserializer::from_str(&failure.message)
+                .into_iter::<String>()
+                .next()?
+                .ok()?;
+            if let Some((name, Value::String(message))) = properties.get_key_value(&name) {
+                let pointer = format!("{}/required/{}", pointer, walk::escape_token(name));
+                return Some((message, pointer, None));
+            }
+        }
+        (Some("properties"), Some(Value::Object(properties))) => {
+            let name = rest.get(1)?;
+            if let Some((name, Value::String(message))) = properties.get_key_value(name.as_str()) {
+                let pointer = format!("{}/properties/{}", pointer, walk::escape_token(name));
+                return Some((message, pointer, Some(name.as_str())));
+            }
+        }
+        _ => {}
+    }
+    let message = messages.get("_")?.as_str()?;
+    Some((message, format!("{}/_", pointer), None))
+}
+
+/// `message` with `${0}`, `${0/pointer}` and `${/pointer}` replaced by the
+/// values they point to within `value`, as JSON.
+fn interpolate(message: &str, value: &Value) -> String {
+    let mut out = String::new();
+    let mut rest = message;
+    while let Some(start) = rest.find("${") {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        let pointer = &rest[start + 2..start + length];
+        let pointer = pointer.strip_prefix('0').unwrap_or(pointer);
+        out.push_str(&rest[..start]);
+        match value.pointer(pointer) {
+            Some(found) => out.push_str(&found.to_string()),
+            None => out.push_str(&rest[start..=start + length]),
+        }
+        rest = &rest[start + length + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Validator;
+    use serde_json::{json, Value};
+
+    fn messages(schema: Value, instance: Value) -> Vec<(String, String)> {
+        Validator::from_value(&schema)
+            .unwrap()
+            .validate_detailed(&instance)
+            .into_iter()
+            .map(|failure| (failure.instance_path, failure.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let schema = json!({
+            "$defs": {"replicas": {
+                "type": "integer", "minimum": 1, "maximum": 10,
+                "errorMessage": "replicas must be between 1 and 10, not ${0}"
+            }},
+            "type": "object",
+            "required": ["name", "image"],
+            "properties": {
+                "name": {"type": "string", "pattern": "^[a-z]+$"},
+                "replicas": {"$ref": "#/$defs/replicas"},
+                "ports": {"items": {"type": "integer"}},
+                "labels": {"additionalProperties": {"type": "string"}}
+            },
+            "errorMessage": {
+                "required": {"image": "set the container image"},
+                "properties": {"name": "name must be lowercase letters, not ${/name}"},
+                "_": "invalid deployment"
+            }
+        });
+        let found = messages(
+            schema.clone(),
+            json!({"name": "Web1", "replicas": 20, "ports": ["http"]}),
+        );
+        let expected = [
+            ("", "set the container image"),
+            ("/name", "name must be lowercase letters, not \"Web1\""),
+            ("/replicas", "replicas must be between 1 and 10, not 20"),
+            ("", "invalid deployment"),
+        ];
+        for (path, message) in expected {
+            assert!(
+                found.contains(&(path.to_string(), message.to_string())),
+                "{:?}",
+                found
+            );
+        }
+        // Each replacement is reported once
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        assert_eq!(
+            messages(
+                schema,
+                json!({"name": "web", "image": "nginx", "labels": {"a": 1}})
+            ),
+            [("".to_string(), "invalid deployment".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_keyword_messages() {
+        let schema = json!({
+            "properties": {"port": {
+                "type": "integer",
+                "maximum": 65535,
+                "errorMessage": {"type": "port must be a number"}
+            }}
+        });
+        assert_eq!(
+            messages(schema.clone(), json!({"port": "http"})),
+            [("/port".to_string(), "port must be a number".to_string())]
+        );
+        assert_eq!(
+            messages(schema, json!({"port": 70000})),
+            [(
+                "/port".to_string(),
+                "70000 is greater than the maximum of 65535".to_string()
+            )]
+        );
+    }
+}
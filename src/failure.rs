@@ -0,0 +1,283 @@
+//! Structured descriptions of validation errors.
+
+use crate::diagnostic::value_span;
+use crate::source::SourceMap;
+use crate::DocumentFormat;
+use jsonschema::error::ValidationErrorKind;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Keywords whose subschemas are keyed by name rather than being the value
+/// of the keyword itself.
+const NAMED_SUBSCHEMAS: [&str; 6] = [
+    "properties",
+    "patternProperties",
+    "dependentSchemas",
+    "dependencies",
+    "$defs",
+    "definitions",
+];
+
+/// One reason a document failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationFailure {
+    /// JSON Pointer to the failing value in the document; empty for the root.
+    pub instance_path: String,
+    /// JSON Pointer to the failing keyword in the schema, following `$ref`s.
+    pub schema_path: String,
+    /// The keyword that failed, e.g. `required` or `type`, or `false` for a
+    /// `false` schema that is not the value of a keyword, such as a property's.
+    pub keyword: String,
+    /// Human-readable description of the failure.
+    pub message: String,
+}
+
+impl ValidationFailure {
+    pub(crate) fn from_engine(error: &jsonschema::ValidationError<'_>) -> Self {
+        let schema_path = error.schema_path.as_str().to_string();
+        let segments: Vec<&str> = schema_path.split('/').skip(1).collect();
+        let keyword = match (&error.kind, segments.as_slice()) {
+            // A `false` schema at the root or under a name, e.g. in
+            // `properties`, is not the value of any keyword
+            (ValidationErrorKind::FalseSchema, []) => "false",
+            (ValidationErrorKind::FalseSchema, [.., parent, _])
+                if NAMED_SUBSCHEMAS.contains(parent) =>
+            {
+                "false"
+            }
+            _ => segments
+                .iter()
+                .rev()
+                .find(|segment| segment.parse::<usize>().is_err())
+                .copied()
+                .unwrap_or_default(),
+        }
+        .to_string();
+        Self {
+            instance_path: error.instance_path.as_str().to_string(),
+            schema_path,
+            keyword,
+            message: error.to_string(),
+        }
+    }
+
+    /// The failing value within `document`, if the path still resolves.
+    pub fn instance<'a>(&self, document: &'a Value) -> Option<&'a Value> {
+        document.pointer(&self.instance_path)
+    }
+
+    /// The failure as a JSON object with the same field names, for
+    /// machine-readable output.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "instance_path": self.instance_path,
+            "schema_path": self.schema_path,
+            "keyword": self.keyword,
+            "message": self.message,
+        })
+    }
+
+    /// Read a failure written by [`to_json`](Self::to_json).
+    pub(crate) fn from_json(value: &Value) -> Option<Self> {
+        let field = |name: &str| Some(value.get(name)?.as_str()?.to_string());
+        Some(Self {
+            instance_path: field("instance_path")?,
+            schema_path: field("schema_path")?,
+            keyword: field("keyword")?,
+            message: field("message")?,
+        })
+    }
+}
+
+impl fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "root"
+        } else {
+            &self.instance_path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// The failures of one value, as [`group_failures`] collects them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureGroup {
+    /// JSON Pointer to the failing value in the document; empty for the root.
+    pub instance_path: String,
+    /// Its failures in the order they were found, without repeated
+    /// messages. Never empty.
+    pub failures: Vec<ValidationFailure>,
+    /// How many more failures with different messages were left out.
+    pub omitted: usize,
+}
+
+impl fmt::Display for FailureGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "root"
+        } else {
+            &self.instance_path
+        };
+        let messages: Vec<&str> = self
+            .failures
+            .iter()
+            .map(|failure| failure.message.as_str())
+            .collect();
+        write!(f, "{}: {}", path, messages.join("; "))?;
+        if self.omitted > 0 {
+            write!(f, " (and {} more)", self.omitted)?;
+        }
+        Ok(())
+    }
+}
+
+/// Group `failures` by the value they are about, in the order each value
+/// first fails.
+///
+/// Schemas with `anyOf`, `oneOf` or deeply nested references often fail one
+/// value several times over with the same message; each message is kept
+/// once per value. With `max_per_path`, at most that many failures are kept
+/// for each value and the rest are counted in [`FailureGroup::omitted`].
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::failure::group_failures;
+/// use validate_json_schema::Validator;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(
+///     r##"{
+///         "$defs": {"port": {"type": "integer", "minimum": 1}},
+///         "properties": {"port": {"allOf": [{"$ref": "#/$defs/port"}, {"type": "integer"}]}}
+///     }"##,
+/// )?;
+/// let failures = validator.validate_detailed(&serde_json::json!({"port": true}));
+/// assert_eq!(failures.len(), 2);
+///
+/// let groups = group_failures(&failures, None);
+/// assert_eq!(groups.len(), 1);
+/// assert_eq!(groups[0].to_string(), "/port: true is not of type \"integer\"");
+/// # Ok(())
+/// # }
+/// ```
+pub fn group_failures(
+    failures: &[ValidationFailure],
+    max_per_path: Option<usize>,
+) -> Vec<FailureGroup> {
+    let mut groups: Vec<FailureGroup> = Vec::new();
+    let mut seen: Vec<HashSet<&str>> = Vec::new();
+    for failure in failures {
+        let index = match groups
+            .iter()
+            .position(|group| group.instance_path == failure.instance_path)
+        {
+            Some(index) => index,
+            None => {
+                groups.push(FailureGroup {
+                    instance_path: failure.instance_path.clone(),
+                    failures: Vec::new(),
+                    omitted: 0,
+                });
+                seen.push(HashSet::new());
+                groups.len() - 1
+            }
+        };
+        if !seen[index].insert(&failure.message) {
+            continue;
+        }
+        let group = &mut groups[index];
+        if max_per_path.is_some_and(|max| group.failures.len() >= max.max(1)) {
+            group.omitted += 1;
+        } else {
+            group.failures.push(failure.clone());
+        }
+    }
+    groups
+}
+
+/// A position in the source text of a document, counting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceLocation {
+    /// Line number.
+    pub line: usize,
+    /// Column number, in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}", self.line, self.column)
+    }
+}
+
+/// Locate each of `failures` of a document in its source `content`.
+///
+/// Values are located at their first character, or, if a value of a
+/// block-style YAML document starts on the line after its key or `- `
+/// indicator, at that key or indicator. A value that cannot be found, such
+/// as one inside a YAML flow collection, is located at its closest enclosing
+/// value that can; failures of the whole document and of other formats have
+/// no location.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::failure::{locate_failures, SourceLocation};
+/// use validate_json_schema::{DocumentFormat, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(
+///     r#"{"properties": {"spec": {"properties": {"replicas": {"type": "integer"}}}}}"#,
+/// )?;
+/// let content = "name: web\nspec:\n  replicas: many\n";
+/// let document = DocumentFormat::Yaml.parse(content)?;
+/// let failures = validator.validate_detailed(&document);
+///
+/// assert_eq!(
+///     locate_failures(content, DocumentFormat::Yaml, &failures),
+///     [Some(SourceLocation { line: 3, column: 13 })]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn locate_failures(
+    content: &str,
+    format: DocumentFormat,
+    failures: &[ValidationFailure],
+) -> Vec<Option<SourceLocation>> {
+    locate_spans(content, format, failures)
+        .into_iter()
+        .map(|span| span.map(|(location, _)| location))
+        .collect()
+}
+
+/// Locate each of `failures` as [`locate_failures`] does, with the width in
+/// characters of the part of the value on its first line, or of the key or
+/// indicator it is located at.
+pub(crate) fn locate_spans(
+    content: &str,
+    format: DocumentFormat,
+    failures: &[ValidationFailure],
+) -> Vec<Option<(SourceLocation, usize)>> {
+    let map = match format {
+        DocumentFormat::Yaml => SourceMap::parse(content),
+        DocumentFormat::Json | DocumentFormat::Json5 => SourceMap::parse_json(content),
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    failures
+        .iter()
+        .map(|failure| {
+            let node = map.nearest(&failure.instance_path)?;
+            let column = lines.get(node.line)?[..node.column].chars().count();
+            let (start, end) = value_span(&lines, node.line, column, format);
+            let location = SourceLocation {
+                line: node.line + 1,
+                column: start + 1,
+            };
+            Some((location, end - start))
+        })
+        .collect()
+}
@@ -0,0 +1,350 @@
+//! Reporting results to GitHub Actions.
+//!
+//! A workflow gets the most out of a run from three outputs: annotations,
+//! which GitHub shows on the changed lines of a pull request; a Markdown job
+//! summary, shown on the run's page; and a SARIF log, which code scanning
+//! turns into alerts. [`annotations`], [`step_summary`] and [`sarif`] render
+//! them from the same [`FileResult`]s, which the CLI's `--ci github` writes
+//! in one go.
+
+use crate::failure::{locate_failures, SourceLocation};
+use crate::{DocumentFormat, ValidationFailure};
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+/// Version of the SARIF format written by [`sarif`].
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Schema of the SARIF format written by [`sarif`].
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// How checking one file ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckResult {
+    /// The file is valid.
+    Valid,
+    /// The file fails the schema.
+    Failed(Vec<Finding>),
+    /// The file could not be checked, e.g. because it does not parse.
+    Error(String),
+    /// The file was not checked, for the reason given.
+    Skipped(String),
+}
+
+/// The result of checking one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileResult {
+    /// The file, as given on the command line.
+    pub path: String,
+    /// How checking it ended.
+    pub result: CheckResult,
+}
+
+/// A validation failure, located in its file where possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Where the failing value is, if it could be located.
+    pub location: Option<SourceLocation>,
+    /// The failure.
+    pub failure: ValidationFailure,
+}
+
+impl Finding {
+    /// Locate each of `failures` of a document in its source `content`, as
+    /// [`locate_failures`] does.
+    pub fn locate(
+        content: &str,
+        format: DocumentFormat,
+        failures: Vec<ValidationFailure>,
+    ) -> Vec<Finding> {
+        let locations = locate_failures(content, format, &failures);
+        failures
+            .into_iter()
+            .zip(locations)
+            .map(|(failure, location)| Finding { location, failure })
+            .collect()
+    }
+}
+
+/// GitHub Actions workflow commands that annotate each failure and error of
+/// `results`, one per line.
+pub fn annotations(results: &[FileResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let file = escape_property(&result.path);
+        match &result.result {
+            CheckResult::Failed(findings) => {
+                for finding in findings {
+                    let failure = &finding.failure;
+                    let location = finding.location.map_or_else(String::new, |location| {
+                        format!(",line={},col={}", location.line, location.column)
+                    });
+                    let _ = writeln!(
+                        out,
+                        "::error file={}{},title={}::{}",
+                        file,
+                        location,
+                        escape_property(&format!("Schema: {}", failure.keyword)),
+                        escape_data(&failure.to_string())
+                    );
+                }
+            }
+            CheckResult::Error(message) => {
+                let _ = writeln!(
+                    out,
+                    "::error file={},title={}::{}",
+                    file,
+                    escape_property("Schema: error"),
+                    escape_data(message)
+                );
+            }
+            CheckResult::Valid | CheckResult::Skipped(_) => {}
+        }
+    }
+    out
+}
+
+/// A Markdown job summary of `results`: the counts, then a table of every
+/// failure and error.
+pub fn step_summary(results: &[FileResult]) -> String {
+    let count = |matches: fn(&CheckResult) -> bool| {
+        results
+            .iter()
+            .filter(|result| matches(&result.result))
+            .count()
+    };
+    let valid = count(|result| matches!(result, CheckResult::Valid));
+    let failed = count(|result| matches!(result, CheckResult::Failed(_)));
+    let errors = count(|result| matches!(result, CheckResult::Error(_)));
+    let skipped = count(|result| matches!(result, CheckResult::Skipped(_)));
+
+    let mut out = String::from("## Schema validation\n\n");
+    let status = if failed + errors == 0 { "✅" } else { "❌" };
+    let _ = writeln!(
+        out,
+        "{} {} file(s): {} valid, {} invalid, {} error(s), {} skipped",
+        status,
+        results.len(),
+        valid,
+        failed,
+        errors,
+        skipped
+    );
+    if failed + errors == 0 {
+        return out;
+    }
+    out.push_str("\n| File | Line | Location | Problem |\n| --- | --- | --- | --- |\n");
+    for result in results {
+        let file = escape_cell(&result.path);
+        match &result.result {
+            CheckResult::Failed(findings) => {
+                for finding in findings {
+                    let failure = &finding.failure;
+                    let location = if failure.instance_path.is_empty() {
+                        "root"
+                    } else {
+                        &failure.instance_path
+                    };
+                    let _ = writeln!(
+                        out,
+                        "| {} | {} | `{}` | {} |",
+                        file,
+                        finding
+                            .location
+                            .map_or_else(String::new, |location| location.line.to_string()),
+                        escape_cell(location),
+                        escape_cell(&failure.message)
+                    );
+                }
+            }
+            CheckResult::Error(message) => {
+                let _ = writeln!(out, "| {} | | | {} |", file, escape_cell(message));
+            }
+            CheckResult::Valid | CheckResult::Skipped(_) => {}
+        }
+    }
+    out
+}
+
+/// A SARIF 2.1.0 log of the failures and errors of `results`, with one
+/// rule per failing keyword.
+pub fn sarif(results: &[FileResult]) -> Value {
+    let mut rules: Vec<&str> = Vec::new();
+    let mut entries = Vec::new();
+    for result in results {
+        let location = |location: Option<SourceLocation>| {
+            let mut physical = json!({"artifactLocation": {"uri": result.path}});
+            if let Some(location) = location {
+                physical["region"] =
+                    json!({"startLine": location.line, "startColumn": location.column});
+            }
+            json!([{"physicalLocation": physical}])
+        };
+        match &result.result {
+            CheckResult::Failed(findings) => {
+                for finding in findings {
+                    let failure = &finding.failure;
+                    let rule = format!("schema/{}", failure.keyword);
+                    if !rules.contains(&failure.keyword.as_str()) {
+                        rules.push(&failure.keyword);
+                    }
+                    entries.push(json!({
+                        "ruleId": rule,
+                        "level": "error",
+                        "message": {"text": failure.to_string()},
+                        "locations": location(finding.location),
+                        "properties": {
+                            "instancePath": failure.instance_path,
+                            "schemaPath": failure.schema_path,
+                        },
+                    }));
+                }
+            }
+            CheckResult::Error(message) => entries.push(json!({
+                "ruleId": "error",
+                "level": "error",
+                "message": {"text": message},
+                "locations": location(None),
+            })),
+            CheckResult::Valid | CheckResult::Skipped(_) => {}
+        }
+    }
+    let mut rules: Vec<Value> = rules
+        .into_iter()
+        .map(|keyword| {
+            json!({
+                "id": format!("schema/{}", keyword),
+                "shortDescription": {"text": format!("Value fails the `{}` keyword", keyword)},
+            })
+        })
+        .collect();
+    rules.push(json!({
+        "id": "error",
+        "shortDescription": {"text": "File could not be validated"},
+    }));
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {"driver": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules,
+            }},
+            "results": entries,
+        }],
+    })
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property of a workflow command, such as its file.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Escape text for a Markdown table cell.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<FileResult> {
+        let content = "name: web\nspec:\n  replicas: many\n  ports:\n    - 80\n";
+        let failures = vec![
+            ValidationFailure {
+                instance_path: "/spec/replicas".to_string(),
+                schema_path: "/properties/spec/properties/replicas/type".to_string(),
+                keyword: "type".to_string(),
+                message: "\"many\" is not of type \"integer\"".to_string(),
+            },
+            ValidationFailure {
+                instance_path: "/spec/ports/0/name".to_string(),
+                schema_path: "/required".to_string(),
+                keyword: "required".to_string(),
+                message: "\"protocol\" is a required property".to_string(),
+            },
+        ];
+        vec![
+            FileResult {
+                path: "deploy/web.yaml".to_string(),
+                result: CheckResult::Failed(Finding::locate(
+                    content,
+                    DocumentFormat::Yaml,
+                    failures,
+                )),
+            },
+            FileResult {
+                path: "deploy/ok.yaml".to_string(),
+                result: CheckResult::Valid,
+            },
+            FileResult {
+                path: "deploy/bad,name.json".to_string(),
+                result: CheckResult::Error("Failed to parse JSON: EOF\nat line 1".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_annotations() {
+        assert_eq!(
+            annotations(&results()),
+            "::error file=deploy/web.yaml,line=3,col=13,title=Schema%3A type::\
+             /spec/replicas: \"many\" is not of type \"integer\"\n\
+             ::error file=deploy/web.yaml,line=5,col=7,title=Schema%3A required::\
+             /spec/ports/0/name: \"protocol\" is a required property\n\
+             ::error file=deploy/bad%2Cname.json,title=Schema%3A error::\
+             Failed to parse JSON: EOF%0Aat line 1\n"
+        );
+    }
+
+    #[test]
+    fn test_step_summary() {
+        let summary = step_summary(&results());
+        assert!(summary.contains("❌ 3 file(s): 1 valid, 1 invalid, 1 error(s), 0 skipped"));
+        assert!(summary.contains(
+            "| deploy/web.yaml | 3 | `/spec/replicas` | \"many\" is not of type \"integer\" |"
+        ));
+        assert!(
+            summary.contains("| deploy/bad,name.json | | | Failed to parse JSON: EOF at line 1 |")
+        );
+        let clean = step_summary(&results()[1..2]);
+        assert!(clean.contains("✅") && !clean.contains('|'));
+    }
+
+    #[test]
+    fn test_sarif() {
+        let log = sarif(&results());
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, ["schema/type", "schema/required", "error"]);
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["ruleId"], "schema/type");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": {"uri": "deploy/web.yaml"},
+                "region": {"startLine": 3, "startColumn": 13}
+            })
+        );
+        assert!(results[2]["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Document hygiene checks.
+//!
+//! These checks look at the raw text of a document rather than its parsed
+//! value. Byte-order marks, mixed line endings, tab indentation and trailing
+//! whitespace never change the result of schema validation, but they regularly
+//! break other tools that consume the same configuration files.
+
+use crate::DocumentFormat;
+use std::fmt;
+
+/// The kind of hygiene problem found in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HygieneIssueKind {
+    /// The document starts with a UTF-8 byte-order mark.
+    ByteOrderMark,
+    /// The document uses both `\r\n` and `\n` line endings.
+    MixedLineEndings { crlf: usize, lf: usize },
+    /// A YAML line is indented with tab characters.
+    TabIndentation,
+    /// A line ends with spaces or tabs.
+    TrailingWhitespace,
+}
+
+/// A single hygiene finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HygieneIssue {
+    /// What was found.
+    pub kind: HygieneIssueKind,
+    /// 1-based line number, or `None` for document-wide findings.
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for HygieneIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        match &self.kind {
+            HygieneIssueKind::ByteOrderMark => write!(f, "byte-order mark at start of file"),
+            HygieneIssueKind::MixedLineEndings { crlf, lf } => {
+                write!(f, "mixed line endings ({} CRLF, {} LF)", crlf, lf)
+            }
+            HygieneIssueKind::TabIndentation => write!(f, "tab character in indentation"),
+            HygieneIssueKind::TrailingWhitespace => write!(f, "trailing whitespace"),
+        }
+    }
+}
+
+/// Check a document for hygiene problems.
+///
+/// Tab indentation is only reported for YAML, where tabs are not allowed as
+/// indentation and are a common source of confusing parse errors.
+pub fn check_hygiene(content: &str, format: DocumentFormat) -> Vec<HygieneIssue> {
+    let mut issues = Vec::new();
+
+    if content.starts_with('\u{feff}') {
+        issues.push(HygieneIssue {
+            kind: HygieneIssueKind::ByteOrderMark,
+            line: None,
+        });
+    }
+
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    if crlf > 0 && lf > 0 {
+        issues.push(HygieneIssue {
+            kind: HygieneIssueKind::MixedLineEndings { crlf, lf },
+            line: None,
+        });
+    }
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_number = Some(index + 1);
+
+        if format == DocumentFormat::Yaml {
+            let indentation = &line[..line.len() - line.trim_start().len()];
+            if indentation.contains('\t') {
+                issues.push(HygieneIssue {
+                    kind: HygieneIssueKind::TabIndentation,
+                    line: line_number,
+                });
+            }
+        }
+
+        if line.ends_with(' ') || line.ends_with('\t') {
+            issues.push(HygieneIssue {
+                kind: HygieneIssueKind::TrailingWhitespace,
+                line: line_number,
+            });
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_document() {
+        assert!(check_hygiene("name: test\nage: 3\n", DocumentFormat::Yaml).is_empty());
+        assert!(check_hygiene("{\r\n  \"a\": 1\r\n}\r\n", DocumentFormat::Json).is_empty());
+    }
+
+    #[test]
+    fn test_bom_and_mixed_line_endings() {
+        let issues = check_hygiene("\u{feff}a: 1\r\nb: 2\n", DocumentFormat::Yaml);
+        assert_eq!(issues[0].kind, HygieneIssueKind::ByteOrderMark);
+        assert_eq!(
+            issues[1].kind,
+            HygieneIssueKind::MixedLineEndings { crlf: 1, lf: 1 }
+        );
+    }
+
+    #[test]
+    fn test_line_level_issues() {
+        let issues = check_hygiene("a:\n\tb: 1\nc: 2 \n", DocumentFormat::Yaml);
+        assert_eq!(
+            issues,
+            vec![
+                HygieneIssue {
+                    kind: HygieneIssueKind::TabIndentation,
+                    line: Some(2),
+                },
+                HygieneIssue {
+                    kind: HygieneIssueKind::TrailingWhitespace,
+                    line: Some(3),
+                },
+            ]
+        );
+
+        // Tabs are only an indentation problem for YAML
+        assert!(check_hygiene("{\n\t\"a\": 1\n}", DocumentFormat::Json).is_empty());
+    }
+}
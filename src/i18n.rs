@@ -0,0 +1,309 @@
+//! Localized messages.
+//!
+//! [`Localizer`] renders the command-line output and [`ValidationError`]s in
+//! one of the built-in locales, from [Fluent](https://projectfluent.org)
+//! message catalogs in the `locales` directory. Messages missing from a
+//! catalog fall back to English, which is also used for unknown locales.
+//! Teams can add their own messages, or a whole catalog for a language that
+//! is not built in, with [`Localizer::add_catalog`].
+//!
+//! Failures against the schema keep the descriptions of the validation
+//! engine, which are English; catalogs can still reword a failure line per
+//! keyword with a `failure-<keyword>` message, given `$path`, `$keyword` and
+//! `$message`.
+
+use crate::{ValidationError, ValidationFailure};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::fmt;
+use unic_langid::LanguageIdentifier;
+
+/// Environment variable naming the locale when none is given explicitly.
+pub const LOCALE_VARIABLE: &str = "VALIDATE_JSON_SCHEMA_LOCALE";
+
+/// The built-in catalogs; the first is the fallback.
+const CATALOGS: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+/// Renders messages in one locale.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::i18n::Localizer;
+/// use validate_json_schema::ValidationError;
+///
+/// let localizer = Localizer::new("de-CH");
+/// assert_eq!(localizer.locale(), "de");
+/// assert_eq!(localizer.message("valid", &[]), "Gültig");
+///
+/// let error = ValidationError::LimitExceeded("document is 9 bytes".to_string());
+/// assert_eq!(
+///     localizer.error(&error),
+///     "Dokumentgrenze überschritten: document is 9 bytes"
+/// );
+/// ```
+pub struct Localizer {
+    locale: &'static str,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Localizer {
+    /// A localizer for the built-in locale closest to `locale`, a language
+    /// tag such as `de-AT` or `es_MX.UTF-8`, matched by its language. Other
+    /// languages get English.
+    pub fn new(locale: &str) -> Self {
+        let language = locale
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('_', "-")
+            .parse::<LanguageIdentifier>()
+            .map(|tag| tag.language.to_string())
+            .unwrap_or_default();
+        let (locale, _) = CATALOGS
+            .iter()
+            .find(|(candidate, _)| candidate.split('-').next() == Some(language.as_str()))
+            .unwrap_or(&CATALOGS[0]);
+        Localizer {
+            locale,
+            bundle: bundle(locale),
+            fallback: bundle(CATALOGS[0].0),
+        }
+    }
+
+    /// A localizer for the locale named by the
+    /// `VALIDATE_JSON_SCHEMA_LOCALE` environment variable, or English if it
+    /// is not set.
+    pub fn from_env() -> Self {
+        Self::new(&std::env::var(LOCALE_VARIABLE).unwrap_or_default())
+    }
+
+    /// The built-in locales.
+    pub fn locales() -> impl Iterator<Item = &'static str> {
+        CATALOGS.iter().map(|(locale, _)| *locale)
+    }
+
+    /// The locale messages are rendered in.
+    pub fn locale(&self) -> &'static str {
+        self.locale
+    }
+
+    /// Add the messages of the Fluent catalog `source`, replacing built-in
+    /// messages with the same identifiers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `source` is not valid Fluent syntax.
+    pub fn add_catalog(&mut self, source: &str) -> Result<(), ValidationError> {
+        let resource = FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+            ValidationError::Config(format!(
+                "invalid message catalog: {}",
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ))
+        })?;
+        self.bundle.add_resource_overriding(resource);
+        Ok(())
+    }
+
+    /// The message `id` with `args` filled in, or `id` itself if no catalog
+    /// has it.
+    pub fn message(&self, id: &str, args: &[(&str, &str)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, *value);
+        }
+        [&self.bundle, &self.fallback]
+            .into_iter()
+            .find_map(|bundle| {
+                let pattern = bundle.get_message(id)?.value()?;
+                let mut errors = Vec::new();
+                Some(
+                    bundle
+                        .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                        .into_owned(),
+                )
+            })
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// `error` as its `Display` shows it, in this locale.
+    pub fn error(&self, error: &ValidationError) -> String {
+        use ValidationError::*;
+        let (id, detail) = match error {
+            FileRead(e) => ("error-file-read", e.to_string()),
+            YamlParse(e) => ("error-yaml-parse", e.to_string()),
+            YamlDirective(detail) => ("error-yaml-directive", detail.clone()),
+            YamlAlias(detail) => ("error-yaml-alias", detail.clone()),
+            YamlTag(detail) => ("error-yaml-tag", detail.clone()),
+            DuplicateKey(detail) => ("error-duplicate-key", detail.clone()),
+            JsonParse(e) => ("error-json-parse", e.to_string()),
+            CsvParse(detail) => ("error-csv-parse", detail.clone()),
+            XmlParse(detail) => ("error-xml-parse", detail.clone()),
+            CborParse(detail) => ("error-cbor-parse", detail.clone()),
+            BsonParse(detail) => ("error-bson-parse", detail.clone()),
+            IniParse(detail) => ("error-ini-parse", detail.clone()),
+            PropertiesParse(detail) => ("error-properties-parse", detail.clone()),
+            JsonSyntax { source, hint } => {
+                return self.message(
+                    "error-json-syntax",
+                    &[("detail", &source.to_string()), ("hint", hint)],
+                )
+            }
+            SchemaCompilation(detail) => ("error-schema-compilation", detail.clone()),
+            ValidationFailed(detail) => ("error-validation-failed", detail.clone()),
+            HttpRequest(e) => ("error-http-request", e.to_string()),
+            HttpStatus(detail) => ("error-http-status", detail.clone()),
+            HttpConfig(detail) => ("error-http-config", detail.clone()),
+            InvalidUrl(e) => ("error-invalid-url", e.to_string()),
+            CacheDirectory(detail) => ("error-cache-directory", detail.clone()),
+            Cache(detail) => ("error-cache", detail.clone()),
+            SchemaLoad(detail) => ("error-schema-load", detail.clone()),
+            UnsupportedMigration(detail) => ("error-unsupported-migration", detail.clone()),
+            LimitExceeded(detail) => ("error-limit-exceeded", detail.clone()),
+            InvalidPointer(detail) => ("error-invalid-pointer", detail.clone()),
+            Config(detail) => ("error-config", detail.clone()),
+            InvalidReport(detail) => ("error-invalid-report", detail.clone()),
+            InvalidCompiled(detail) => ("error-invalid-compiled", detail.clone()),
+            EmptyDocument => ("error-empty-document", String::new()),
+        };
+        self.message(id, &[("detail", &detail)])
+    }
+
+    /// `failure` as its `Display` shows it, reworded by the catalog's
+    /// `failure-<keyword>` or `failure` message.
+    pub fn failure(&self, failure: &ValidationFailure) -> String {
+        let path = if failure.instance_path.is_empty() {
+            "root"
+        } else {
+            &failure.instance_path
+        };
+        let args = [
+            ("path", path),
+            ("keyword", failure.keyword.as_str()),
+            ("message", failure.message.as_str()),
+        ];
+        let keyword = format!("failure-{}", failure.keyword);
+        if [&self.bundle, &self.fallback]
+            .iter()
+            .any(|bundle| bundle.has_message(&keyword))
+        {
+            self.message(&keyword, &args)
+        } else {
+            self.message("failure", &args)
+        }
+    }
+}
+
+impl Default for Localizer {
+    fn default() -> Self {
+        Self::new(CATALOGS[0].0)
+    }
+}
+
+impl fmt::Debug for Localizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Localizer")
+            .field("locale", &self.locale)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The bundle of the built-in catalog for `locale`.
+fn bundle(locale: &str) -> FluentBundle<FluentResource> {
+    let (_, source) = CATALOGS
+        .iter()
+        .find(|(candidate, _)| *candidate == locale)
+        .expect("built-in locale");
+    let mut bundle = FluentBundle::new_concurrent(vec![locale.parse().expect("valid tag")]);
+    // Output goes to terminals and logs, not bidirectional text
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(FluentResource::try_new(source.to_string()).expect("valid catalog"))
+        .expect("no duplicate messages");
+    bundle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_matches_display() {
+        let json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let errors = [
+            ValidationError::FileRead(std::io::Error::other("gone")),
+            ValidationError::JsonParse(json),
+            ValidationError::DuplicateKey("line 2 column 3: \"a\" (/a)".to_string()),
+            ValidationError::ValidationFailed("root: 1 is not of type \"string\"".to_string()),
+            ValidationError::EmptyDocument,
+        ];
+        let english = Localizer::default();
+        for error in &errors {
+            assert_eq!(english.error(error), error.to_string());
+        }
+        let failure = ValidationFailure {
+            instance_path: String::new(),
+            schema_path: "/type".to_string(),
+            keyword: "type".to_string(),
+            message: "1 is not of type \"string\"".to_string(),
+        };
+        assert_eq!(english.failure(&failure), failure.to_string());
+    }
+
+    #[test]
+    fn test_locales() {
+        assert_eq!(Localizer::new("es_MX.UTF-8").locale(), "es");
+        assert_eq!(Localizer::new("ja").locale(), "en-US");
+        assert_eq!(Localizer::new("").locale(), "en-US");
+        assert_eq!(
+            Localizer::new("de").message("error", &[("detail", "x")]),
+            "Fehler: x"
+        );
+        // Every message has a translation
+        let english = bundle("en-US");
+        for locale in Localizer::locales() {
+            let source = CATALOGS.iter().find(|(l, _)| *l == locale).unwrap().1;
+            for line in CATALOGS[0].1.lines() {
+                if let Some((id, _)) = line.split_once(" = ") {
+                    assert!(english.has_message(id));
+                    assert!(
+                        source.contains(&format!("\n{} = ", id)),
+                        "{} {}",
+                        locale,
+                        id
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_catalog() {
+        let mut localizer = Localizer::new("fi");
+        localizer
+            .add_catalog("valid = Kelvollinen\nfailure-type = { $path }: väärä tyyppi\n")
+            .unwrap();
+        assert_eq!(localizer.message("valid", &[]), "Kelvollinen");
+        assert_eq!(
+            localizer.message("validation-failed", &[("detail", "x")]),
+            "Validation failed: x"
+        );
+        let failure = ValidationFailure {
+            instance_path: "/port".to_string(),
+            schema_path: "/properties/port/type".to_string(),
+            keyword: "type".to_string(),
+            message: "\"x\" is not of type \"integer\"".to_string(),
+        };
+        assert_eq!(localizer.failure(&failure), "/port: väärä tyyppi");
+        assert!(localizer.add_catalog("valid = {").is_err());
+    }
+}
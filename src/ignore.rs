@@ -0,0 +1,239 @@
+//! Ignoring failures by where they are and which keyword reports them.
+//!
+//! Shared schemas are often stricter than one team's documents can be, e.g.
+//! a schema with `additionalProperties: false` everywhere and a deployment
+//! that sets free-form annotations. Rather than forking the schema, an
+//! [`IgnoreRules`] file lists the intentional exceptions:
+//!
+//! ```yaml
+//! ignore:
+//!   - keyword: additionalProperties
+//!     pointer: /metadata/annotations
+//!     reason: annotations are free-form
+//!   - pointer: /spec/containers/*/env
+//! ```
+//!
+//! A rule matches failures of values at or under its `pointer`, where a `*`
+//! token matches any one property name or index, and with its `keyword`; a
+//! rule with only one of them matches on that alone. Validators built with
+//! [`ignore_rules`](crate::ValidatorBuilder::ignore_rules) leave matching
+//! failures out of everything they report.
+
+use crate::{yaml, DocumentFormat, ValidationError, ValidationFailure};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// The fields a rule may have.
+const RULE_FIELDS: &[&str] = &["pointer", "keyword", "reason"];
+
+/// An exception to a schema: failures to ignore.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRule {
+    /// JSON Pointer of the values whose failures, and those of the values
+    /// under them, are ignored; `*` tokens match any one token. Any value
+    /// if `None`.
+    pub pointer: Option<String>,
+    /// The keyword whose failures are ignored, e.g. `additionalProperties`.
+    /// Any keyword if `None`.
+    pub keyword: Option<String>,
+    /// Why the exception exists, for readers of the rules.
+    pub reason: Option<String>,
+}
+
+impl IgnoreRule {
+    /// Whether the rule ignores `failure`.
+    ///
+    /// ```rust
+    /// use validate_json_schema::{IgnoreRule, ValidationFailure};
+    ///
+    /// let rule = IgnoreRule {
+    ///     pointer: Some("/metadata/annotations".to_string()),
+    ///     keyword: Some("additionalProperties".to_string()),
+    ///     reason: None,
+    /// };
+    /// let failure = ValidationFailure {
+    ///     instance_path: "/metadata/annotations".to_string(),
+    ///     schema_path: "/properties/metadata/properties/annotations/additionalProperties"
+    ///         .to_string(),
+    ///     keyword: "additionalProperties".to_string(),
+    ///     message: "Additional properties are not allowed ('team' was unexpected)".to_string(),
+    /// };
+    /// assert!(rule.matches(&failure));
+    /// ```
+    pub fn matches(&self, failure: &ValidationFailure) -> bool {
+        let keyword = self
+            .keyword
+            .as_ref()
+            .is_none_or(|keyword| *keyword == failure.keyword);
+        let pointer = self
+            .pointer
+            .as_ref()
+            .is_none_or(|pointer| is_under(&failure.instance_path, pointer));
+        keyword && pointer
+    }
+}
+
+/// Whether the value at `path` is the one at `pointer` or under it.
+fn is_under(path: &str, pointer: &str) -> bool {
+    let mut tokens = path.split('/').skip(1);
+    pointer.split('/').skip(1).all(|expected| {
+        tokens
+            .next()
+            .is_some_and(|token| expected == "*" || token == expected)
+    })
+}
+
+/// Read one rule of an `ignore` array.
+fn parse_rule(rule: &Value) -> Result<IgnoreRule, String> {
+    let rule = rule.as_object().ok_or("not an object")?;
+    if let Some(field) = rule
+        .keys()
+        .find(|field| !RULE_FIELDS.contains(&field.as_str()))
+    {
+        return Err(format!(
+            "unknown field '{}': expected pointer, keyword or reason",
+            field
+        ));
+    }
+    let field = |name: &str| match rule.get(name) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(format!("the {} is not a string", name)),
+    };
+    let rule = IgnoreRule {
+        pointer: field("pointer")?,
+        keyword: field("keyword")?,
+        reason: field("reason")?,
+    };
+    match rule.pointer.as_deref() {
+        None if rule.keyword.is_none() => Err("needs a pointer or a keyword".to_string()),
+        Some(pointer) if !pointer.is_empty() && !pointer.starts_with('/') => {
+            Err(format!("the pointer '{}' does not start with '/'", pointer))
+        }
+        _ => Ok(rule),
+    }
+}
+
+/// A set of [`IgnoreRule`]s, as read from a rules file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Rules from a JSON or YAML object whose `ignore` array lists them, as
+    /// in the [module documentation](self).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` is not such an object, a rule has neither
+    /// a `pointer` nor a `keyword`, or has fields other than those and
+    /// `reason`.
+    pub fn from_value(value: &Value) -> Result<Self, ValidationError> {
+        let rules = value
+            .get("ignore")
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                ValidationError::Config("expected an object with an \"ignore\" array".to_string())
+            })?;
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                parse_rule(rule).map_err(|message| {
+                    ValidationError::Config(format!("ignore rule {}: {}", index + 1, message))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Read rules from a JSON or YAML file, as [`from_value`](Self::from_value)
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or its rules
+    /// are malformed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ValidationError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let value = match DocumentFormat::detect(path, &content) {
+            DocumentFormat::Yaml => yaml::from_str(&content)?,
+            _ => serde_json::from_str(&content)?,
+        };
+        Self::from_value(&value)
+    }
+
+    /// The rules, in order.
+    pub fn rules(&self) -> &[IgnoreRule] {
+        &self.rules
+    }
+
+    /// Whether there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The first rule that ignores `failure`, if any.
+    pub fn matching(&self, failure: &ValidationFailure) -> Option<&IgnoreRule> {
+        self.rules.iter().find(|rule| rule.matches(failure))
+    }
+
+    pub(crate) fn push(&mut self, rule: IgnoreRule) {
+        self.rules.push(rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn failure(instance_path: &str, keyword: &str) -> ValidationFailure {
+        ValidationFailure {
+            instance_path: instance_path.to_string(),
+            schema_path: format!("/{}", keyword),
+            keyword: keyword.to_string(),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let rules = IgnoreRules::from_value(&json!({"ignore": [
+            {"keyword": "additionalProperties", "pointer": "/metadata/annotations"},
+            {"pointer": "/containers/*/env"},
+        ]}))
+        .unwrap();
+        let ignored = |path: &str, keyword: &str| rules.matching(&failure(path, keyword)).is_some();
+
+        assert!(ignored("/metadata/annotations", "additionalProperties"));
+        assert!(ignored(
+            "/metadata/annotations/team",
+            "additionalProperties"
+        ));
+        assert!(!ignored("/metadata/annotations", "type"));
+        assert!(!ignored("/metadata/annotationsx", "additionalProperties"));
+        assert!(!ignored("/metadata", "additionalProperties"));
+        assert!(ignored("/containers/0/env/1", "type"));
+        assert!(!ignored("/containers/0", "type"));
+    }
+
+    #[test]
+    fn test_malformed_rules() {
+        for rules in [
+            json!([]),
+            json!({"ignore": [{"reason": "no selector"}]}),
+            json!({"ignore": [{"keyword": "type", "pointers": "/a"}]}),
+            json!({"ignore": [{"pointer": "metadata"}]}),
+            json!({"ignore": [{"keyword": 1}]}),
+        ] {
+            assert!(matches!(
+                IgnoreRules::from_value(&rules),
+                Err(ValidationError::Config(_))
+            ));
+        }
+    }
+}
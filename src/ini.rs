@@ -0,0 +1,408 @@
+//! Validating INI and Java properties files against JSON Schemas.
+//!
+//! Legacy services often keep their configuration in INI or `.properties`
+//! files. [`parse_ini`] and [`parse_properties`] convert them into nested
+//! objects, so the same JSON Schemas as for YAML or JSON configuration can
+//! describe them:
+//!
+//! - In INI files, each `[section]` becomes an object of its keys, and keys
+//!   before the first section become top-level properties. A section that
+//!   appears twice is merged; a key that appears twice in a section is an
+//!   error. Lines starting with `;` or `#` are comments, and a value wrapped
+//!   in double quotes loses them.
+//! - In properties files, keys are split on dots, so `server.port=80`
+//!   becomes `{"server": {"port": "80"}}`. Separators, comments, line
+//!   continuations and escapes follow `java.util.Properties`, as does a
+//!   repeated key: the last value wins.
+//!
+//! Every value is a string unless [`IniOptions::infer_types`] is set.
+
+use crate::config::coerce;
+use crate::{ValidationError, ValidationFailure, Validator};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Settings for [`validate_ini`].
+#[derive(Debug, Clone, Default)]
+pub struct IniOptions {
+    /// Read the content as a Java properties file instead of INI.
+    pub properties: bool,
+    /// Convert values to the types the schema declares for their location,
+    /// as [`env_config_from`](crate::env_config_from) does.
+    pub infer_types: bool,
+}
+
+impl IniOptions {
+    /// Options for the format of `path`: properties for the `.properties`
+    /// extension, INI otherwise.
+    pub fn for_path<P: AsRef<Path>>(path: P) -> Self {
+        let properties = path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("properties"));
+        Self {
+            properties,
+            ..Self::default()
+        }
+    }
+}
+
+/// Parse INI `content` into an object of sections.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::ini::parse_ini;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let content = "name = web\n\n[database]\nhost = db.internal\nport = 5432\n";
+///
+/// assert_eq!(
+///     parse_ini(content)?,
+///     json!({"name": "web", "database": {"host": "db.internal", "port": "5432"}})
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if a line is neither a section, a key and value nor a
+/// comment, a key repeats within its section, or a section has the name of
+/// a top-level key.
+pub fn parse_ini(content: &str) -> Result<Value, ValidationError> {
+    let invalid = |line: usize, message: String| {
+        Err(ValidationError::IniParse(format!(
+            "line {}: {}",
+            line, message
+        )))
+    };
+    let mut document = Map::new();
+    let mut section: Option<String> = None;
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let Some(name) = name.strip_suffix(']') else {
+                return invalid(line_number, "the section header is not closed".to_string());
+            };
+            let name = name.trim().to_string();
+            match document
+                .entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(_) => section = Some(name),
+                _ => {
+                    return invalid(
+                        line_number,
+                        format!("section '{}' has the name of a key", name),
+                    )
+                }
+            }
+            continue;
+        }
+        let Some(separator) = line.find(['=', ':']) else {
+            return invalid(
+                line_number,
+                format!("expected 'key = value', found '{}'", line),
+            );
+        };
+        let key = line[..separator].trim_end().to_string();
+        let value = line[separator + 1..].trim_start();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        let keys = match &section {
+            Some(name) => match document.get_mut(name) {
+                Some(Value::Object(keys)) => keys,
+                _ => unreachable!("sections are objects"),
+            },
+            None => &mut document,
+        };
+        if keys.contains_key(&key) {
+            let location = match &section {
+                Some(name) => format!("section '{}'", name),
+                None => "the top level".to_string(),
+            };
+            return invalid(
+                line_number,
+                format!("key '{}' appears twice in {}", key, location),
+            );
+        }
+        keys.insert(key, Value::String(value.to_string()));
+    }
+    Ok(Value::Object(document))
+}
+
+/// Parse Java properties `content` into an object, nesting keys on dots.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::ini::parse_properties;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let content = "# deployed by ops\nserver.port=8080\nserver.name = web \\\n    01\n";
+///
+/// assert_eq!(
+///     parse_properties(content)?,
+///     json!({"server": {"port": "8080", "name": "web 01"}})
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if a key both holds a value and is the prefix of
+/// another key, e.g. `server=web` and `server.port=80`, or a `\u` escape is
+/// malformed.
+pub fn parse_properties(content: &str) -> Result<Value, ValidationError> {
+    let mut document = Value::Object(Map::new());
+    for (line_number, line) in logical_lines(content) {
+        let invalid = |message: String| {
+            ValidationError::PropertiesParse(format!("line {}: {}", line_number, message))
+        };
+        let (key, value) = split_property(&line);
+        let key = unescape(key).map_err(invalid)?;
+        let value = unescape(value).map_err(invalid)?;
+
+        let mut slot = &mut document;
+        let mut parts = key.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let Value::Object(object) = slot else {
+                return Err(invalid(format!(
+                    "key '{}' is below a key that holds a value",
+                    key
+                )));
+            };
+            if parts.peek().is_none() {
+                if object.get(part).is_some_and(Value::is_object) {
+                    return Err(invalid(format!(
+                        "key '{}' is also the prefix of other keys",
+                        key
+                    )));
+                }
+                object.insert(part.to_string(), Value::String(value));
+                break;
+            }
+            slot = object
+                .entry(part)
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+    }
+    Ok(document)
+}
+
+/// Validate `content` against `validator`, built from `schema`, and return
+/// the failures.
+///
+/// # Errors
+///
+/// Returns an error if the content is malformed, as for [`parse_ini`] and
+/// [`parse_properties`].
+pub fn validate_ini(
+    validator: &Validator,
+    schema: &Value,
+    content: &str,
+    options: &IniOptions,
+) -> Result<Vec<ValidationFailure>, ValidationError> {
+    let mut document = if options.properties {
+        parse_properties(content)?
+    } else {
+        parse_ini(content)?
+    };
+    if options.infer_types {
+        coerce(schema, validator.draft(), &mut document, "");
+    }
+    Ok(validator.validate_detailed(&document))
+}
+
+/// Join the lines of `content` continued with a trailing backslash, skipping
+/// blank lines and comments, and number each by the line it starts on.
+fn logical_lines(content: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_start();
+        let (start, mut logical) = match pending.take() {
+            Some(pending) => pending,
+            None if line.is_empty() || line.starts_with(['#', '!']) => continue,
+            None => (index + 1, String::new()),
+        };
+        let trailing = line.len() - line.trim_end_matches('\\').len();
+        if trailing % 2 == 1 {
+            logical.push_str(&line[..line.len() - 1]);
+            pending = Some((start, logical));
+        } else {
+            logical.push_str(line);
+            lines.push((start, logical));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// Split a logical line into its key and value, both still escaped. The key
+/// ends at the first unescaped `=`, `:` or whitespace.
+fn split_property(line: &str) -> (&str, &str) {
+    const BLANK: [char; 3] = [' ', '\t', '\x0c'];
+    let mut escaped = false;
+    let end = line.char_indices().find_map(|(index, c)| {
+        let separator = !escaped && (c == '=' || c == ':' || BLANK.contains(&c));
+        escaped = !escaped && c == '\\';
+        separator.then_some(index)
+    });
+    let Some(end) = end else {
+        return (line, "");
+    };
+    let rest = line[end..].trim_start_matches(BLANK);
+    let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+    (&line[..end], rest.trim_start_matches(BLANK))
+}
+
+/// Resolve the escapes of `java.util.Properties`.
+fn unescape(text: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('f') => unescaped.push('\x0c'),
+            Some('u') => {
+                let digits: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 4)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("'\\u{}' is not a valid escape", digits))?;
+                unescaped.push(c);
+            }
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ini() {
+        let content = "; deployed by ops\n\
+                       name = web\n\
+                       [server]\n\
+                       host: 0.0.0.0\n\
+                       port = 8080\n\
+                       banner = \"hello; world\"\n\
+                       \n\
+                       [database]\n\
+                       url = postgres://db/app?sslmode=require\n\
+                       [server]\n\
+                       # merged into the first section\n\
+                       workers = 4\n";
+        assert_eq!(
+            parse_ini(content).unwrap(),
+            json!({
+                "name": "web",
+                "server": {"host": "0.0.0.0", "port": "8080", "banner": "hello; world", "workers": "4"},
+                "database": {"url": "postgres://db/app?sslmode=require"}
+            })
+        );
+
+        let error = parse_ini("[server]\nport = 1\nport = 2\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse INI: line 3: key 'port' appears twice in section 'server'"
+        );
+        assert!(parse_ini("name = web\n[name]\n").is_err());
+        assert!(parse_ini("[server\n").is_err());
+        assert!(parse_ini("just text\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_properties() {
+        let content = "# comment\n\
+                       ! also a comment\n\
+                       app.name = My App\n\
+                       app.port:8080\n\
+                       app.greeting Hello\\tthere\n\
+                       app.path = C:\\\\temp\n\
+                       app.list = a, \\\n    b, \\\n    c\n\
+                       app.unicode = caf\\u00e9\n\
+                       key\\=with\\:separators = 1\n\
+                       app.port = 9090\n\
+                       empty\n";
+        assert_eq!(
+            parse_properties(content).unwrap(),
+            json!({
+                "app": {
+                    "name": "My App",
+                    "port": "9090",
+                    "greeting": "Hello\tthere",
+                    "path": "C:\\temp",
+                    "list": "a, b, c",
+                    "unicode": "café"
+                },
+                "key=with:separators": "1",
+                "empty": ""
+            })
+        );
+
+        let error = parse_properties("server = web\nserver.port = 80\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse properties: line 2: key 'server.port' is below a key that holds a value"
+        );
+        assert!(parse_properties("server.port = 80\nserver = web\n").is_err());
+        assert!(parse_properties("bad = \\u12\n").is_err());
+    }
+
+    #[test]
+    fn test_validate_ini() {
+        let schema = json!({"properties": {"server": {
+            "required": ["host"],
+            "properties": {"port": {"type": "integer", "maximum": 9000}}
+        }}});
+        let validator = Validator::from_value(&schema).unwrap();
+        let content = "[server]\nport = 9090\n";
+
+        let failures = validate_ini(&validator, &schema, content, &IniOptions::default()).unwrap();
+        assert_eq!(failures.len(), 2);
+
+        let options = IniOptions {
+            infer_types: true,
+            ..IniOptions::default()
+        };
+        let failures = validate_ini(&validator, &schema, content, &options).unwrap();
+        assert_eq!(failures.len(), 2);
+        assert!(failures
+            .iter()
+            .any(|failure| failure.instance_path == "/server/port"));
+
+        let options = IniOptions {
+            infer_types: true,
+            ..IniOptions::for_path("app.properties")
+        };
+        let content = "server.host = web\nserver.port = 80\n";
+        assert!(validate_ini(&validator, &schema, content, &options)
+            .unwrap()
+            .is_empty());
+    }
+}
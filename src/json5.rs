@@ -0,0 +1,309 @@
+//! Recovery from JSON that uses common non-standard syntax.
+//!
+//! Hand-edited JSON files often contain comments, trailing commas, single
+//! quoted strings or unquoted keys, which `serde_json` rejects with an error
+//! that rarely names the cause. [`diagnose`] finds the first such construct to
+//! explain the failure, and [`to_json`] rewrites a document using them into
+//! standard JSON for [`DocumentFormat::Json5`](crate::DocumentFormat::Json5).
+//!
+//! Only these four extensions are understood; other JSON5 features such as
+//! hexadecimal numbers or multi-line strings still fail to parse.
+//! [`strip_comments`] handles only comments and trailing commas, for JSON
+//! with comments (JSONC) as VS Code and TypeScript write it.
+
+use crate::ValidationError;
+use serde_json::Value;
+
+/// Parse strict JSON, explaining the failure if `content` uses JSON5 syntax.
+pub(crate) fn parse_json(content: &str) -> Result<Value, ValidationError> {
+    serde_json::from_str(content).map_err(|source| match diagnose(content) {
+        Some(hint) => ValidationError::JsonSyntax { source, hint },
+        None => ValidationError::JsonParse(source),
+    })
+}
+
+/// A non-standard construct found in a JSON document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Construct {
+    Comment,
+    TrailingComma(char),
+    SingleQuotes,
+    UnquotedKey,
+}
+
+/// Explain why `content` is not valid JSON, if it uses syntax JSON5 allows.
+pub(crate) fn diagnose(content: &str) -> Option<String> {
+    let (construct, line, column) = first_construct(content)?;
+    let problem = match construct {
+        Construct::Comment => "JSON does not allow comments".to_string(),
+        Construct::TrailingComma(close) => {
+            format!("JSON does not allow a trailing comma before '{}'", close)
+        }
+        Construct::SingleQuotes => "JSON strings must use double quotes".to_string(),
+        Construct::UnquotedKey => "JSON object keys must be quoted".to_string(),
+    };
+    Some(format!(
+        "line {} column {}: {}; fix it or parse the document as JSON5 (--json5)",
+        line, column, problem
+    ))
+}
+
+/// Rewrite `content` into standard JSON, removing comments and trailing
+/// commas and quoting single-quoted strings and bare keys. Line breaks are
+/// kept, so parse errors still point at the right line.
+pub(crate) fn to_json(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut out = String::with_capacity(content.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+        match c {
+            '"' => {
+                let end = string_end(&chars, index, '"');
+                out.extend(&chars[index..end]);
+                index = end;
+            }
+            '\'' => {
+                let end = string_end(&chars, index, '\'');
+                out.push('"');
+                let mut inner = index + 1;
+                let close = if end > index + 1 && chars[end - 1] == '\'' {
+                    end - 1
+                } else {
+                    end
+                };
+                while inner < close {
+                    match chars[inner] {
+                        '\\' if chars.get(inner + 1) == Some(&'\'') => {
+                            out.push('\'');
+                            inner += 2;
+                            continue;
+                        }
+                        '\\' => {
+                            out.push('\\');
+                            if let Some(&escaped) = chars.get(inner + 1) {
+                                out.push(escaped);
+                            }
+                            inner += 2;
+                            continue;
+                        }
+                        '"' => out.push_str("\\\""),
+                        other => out.push(other),
+                    }
+                    inner += 1;
+                }
+                out.push('"');
+                index = end;
+            }
+            '/' if matches!(chars.get(index + 1), Some('/') | Some('*')) => {
+                let end = comment_end(&chars, index);
+                out.extend(chars[index..end].iter().filter(|c| **c == '\n'));
+                index = end;
+            }
+            ',' if matches!(next_significant(&chars, index + 1), Some('}') | Some(']')) => {
+                index += 1;
+            }
+            c if is_identifier_start(c) => {
+                let mut end = index;
+                while end < chars.len() && is_identifier_part(chars[end]) {
+                    end += 1;
+                }
+                let word: String = chars[index..end].iter().collect();
+                if next_significant(&chars, end) == Some(':') {
+                    out.push('"');
+                    out.push_str(&word);
+                    out.push('"');
+                } else {
+                    out.push_str(&word);
+                }
+                index = end;
+            }
+            other => {
+                out.push(other);
+                index += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Remove comments and trailing commas from `content`, as JSONC allows,
+/// leaving any other non-standard syntax for the JSON parser to reject.
+/// Line breaks are kept, so parse errors still point at the right line.
+pub(crate) fn strip_comments(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut out = String::with_capacity(content.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        match chars[index] {
+            '"' => {
+                let end = string_end(&chars, index, '"');
+                out.extend(&chars[index..end]);
+                index = end;
+            }
+            '/' if matches!(chars.get(index + 1), Some('/') | Some('*')) => {
+                let end = comment_end(&chars, index);
+                out.extend(chars[index..end].iter().filter(|c| **c == '\n'));
+                index = end;
+            }
+            ',' if matches!(next_significant(&chars, index + 1), Some('}') | Some(']')) => {
+                index += 1;
+            }
+            other => {
+                out.push(other);
+                index += 1;
+            }
+        }
+    }
+    out
+}
+
+/// The first non-standard construct in `content` with its 1-based position.
+fn first_construct(content: &str) -> Option<(Construct, usize, usize)> {
+    let chars: Vec<char> = content.chars().collect();
+    let position = |index: usize| {
+        let line = chars[..index].iter().filter(|c| **c == '\n').count() + 1;
+        let column = index
+            - chars[..index]
+                .iter()
+                .rposition(|c| *c == '\n')
+                .map_or(0, |newline| newline + 1)
+            + 1;
+        (line, column)
+    };
+
+    let mut index = 0;
+    while index < chars.len() {
+        let construct = match chars[index] {
+            '"' => {
+                index = string_end(&chars, index, '"');
+                continue;
+            }
+            '\'' => Some(Construct::SingleQuotes),
+            '/' if matches!(chars.get(index + 1), Some('/') | Some('*')) => {
+                Some(Construct::Comment)
+            }
+            ',' => match next_significant(&chars, index + 1) {
+                Some(close @ ('}' | ']')) => Some(Construct::TrailingComma(close)),
+                _ => None,
+            },
+            c if is_identifier_start(c) => {
+                let mut end = index;
+                while end < chars.len() && is_identifier_part(chars[end]) {
+                    end += 1;
+                }
+                let key = next_significant(&chars, end) == Some(':');
+                if !key {
+                    index = end;
+                    continue;
+                }
+                Some(Construct::UnquotedKey)
+            }
+            _ => None,
+        };
+        if let Some(construct) = construct {
+            let (line, column) = position(index);
+            return Some((construct, line, column));
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Index just past the string starting with the quote at `start`.
+fn string_end(chars: &[char], start: usize, quote: char) -> usize {
+    let mut index = start + 1;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 2,
+            c if c == quote => return index + 1,
+            _ => index += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Index just past the comment starting at `start`.
+fn comment_end(chars: &[char], start: usize) -> usize {
+    if chars.get(start + 1) == Some(&'/') {
+        chars[start..]
+            .iter()
+            .position(|c| *c == '\n')
+            .map_or(chars.len(), |newline| start + newline)
+    } else {
+        (start + 2..chars.len().saturating_sub(1))
+            .find(|&index| chars[index] == '*' && chars[index + 1] == '/')
+            .map_or(chars.len(), |close| close + 2)
+    }
+}
+
+/// The next character from `start` that is not whitespace or in a comment.
+fn next_significant(chars: &[char], mut index: usize) -> Option<char> {
+    while index < chars.len() {
+        match chars[index] {
+            c if c.is_whitespace() => index += 1,
+            '/' if matches!(chars.get(index + 1), Some('/') | Some('*')) => {
+                index = comment_end(chars, index);
+            }
+            c => return Some(c),
+        }
+    }
+    None
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_part(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diagnose() {
+        assert_eq!(diagnose(r#"{"a": [1, 2], "b": "x // y"}"#), None);
+        assert_eq!(
+            diagnose("{\n  \"a\": 1 // one\n}"),
+            Some(
+                "line 2 column 10: JSON does not allow comments; \
+                 fix it or parse the document as JSON5 (--json5)"
+                    .to_string()
+            )
+        );
+        assert!(diagnose("{\"a\": [1, 2,]}")
+            .unwrap()
+            .starts_with("line 1 column 12: JSON does not allow a trailing comma before ']'"));
+        assert!(diagnose("{\"a\": 'x'}")
+            .unwrap()
+            .contains("JSON strings must use double quotes"));
+        assert!(diagnose("{a: true}")
+            .unwrap()
+            .contains("JSON object keys must be quoted"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let content =
+            "{\n  // settings\n  name: 'it\\'s \"ok\"',\n  /* list */ tags: [true, null,],\n}";
+        let value: Value = serde_json::from_str(&to_json(content)).unwrap();
+        assert_eq!(value, json!({"name": "it's \"ok\"", "tags": [true, null]}));
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let content = "{\n  // editor\n  \"url\": \"http://x/*y*/\", /* size */ \"tabs\": [2,],\n}";
+        let stripped = strip_comments(content);
+        assert_eq!(stripped.lines().count(), content.lines().count());
+        let value: Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value, json!({"url": "http://x/*y*/", "tabs": [2]}));
+
+        assert!(serde_json::from_str::<Value>(&strip_comments("{a: 'b'}")).is_err());
+    }
+}
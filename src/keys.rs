@@ -0,0 +1,285 @@
+//! Rejecting duplicate mapping keys.
+//!
+//! `serde_json` and `serde_yaml` keep the last of several entries with the
+//! same key, so a repeated key silently discards the value before it.
+//! [`check_json`] and [`check_yaml`] walk a document without building it and
+//! fail on the first key that repeats, naming it and where it is. YAML keys
+//! are compared as the strings JSON reads them as, so `1` and `'1'` collide.
+
+use crate::source::SourceMap;
+use crate::walk::escape_token;
+use crate::ValidationError;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, Visitor};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Fail if an object of the JSON `content` repeats a key. Content that is
+/// not JSON passes, for the parser to report.
+pub(crate) fn check_json(content: &str) -> Result<(), ValidationError> {
+    let duplicate = RefCell::new(None);
+    let mut deserializer = serde_json::Deserializer::from_str(content);
+    match UniqueKeys::root(&duplicate).deserialize(&mut deserializer) {
+        Err(error) => match duplicate.take() {
+            Some(pointer) => Err(duplicate_key(error.line(), error.column(), &pointer)),
+            None => Ok(()),
+        },
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Fail if a mapping of the YAML `content`, a single document, repeats a
+/// key. Content that is not YAML passes, for the parser to report.
+pub(crate) fn check_yaml(content: &str) -> Result<(), ValidationError> {
+    let duplicate = RefCell::new(None);
+    let deserializer = serde_yaml::Deserializer::from_str(content);
+    let Err(error) = UniqueKeys::root(&duplicate).deserialize(deserializer) else {
+        return Ok(());
+    };
+    let Some(pointer) = duplicate.take() else {
+        return Ok(());
+    };
+    // `serde_yaml` places errors at the start of the mapping, so look for
+    // the second entry in the source; entries of flow mappings are not there
+    let (line, column) = match SourceMap::parse(content)
+        .nodes
+        .iter()
+        .filter(|node| node.pointer == pointer)
+        .nth(1)
+    {
+        Some(node) => (node.line + 1, node.column + 1),
+        None => error
+            .location()
+            .map_or((0, 0), |location| (location.line(), location.column())),
+    };
+    Err(duplicate_key(line, column, &pointer))
+}
+
+fn duplicate_key(line: usize, column: usize, pointer: &str) -> ValidationError {
+    let key = pointer.rsplit('/').next().unwrap_or_default();
+    let key = key.replace("~1", "/").replace("~0", "~");
+    ValidationError::DuplicateKey(format!(
+        "line {} column {}: {:?} ({})",
+        line, column, key, pointer
+    ))
+}
+
+/// Walks a value, recording the pointer of the first repeated key in
+/// `duplicate` and failing there.
+struct UniqueKeys<'a> {
+    pointer: String,
+    duplicate: &'a RefCell<Option<String>>,
+}
+
+impl<'a> UniqueKeys<'a> {
+    fn root(duplicate: &'a RefCell<Option<String>>) -> Self {
+        Self {
+            pointer: String::new(),
+            duplicate,
+        }
+    }
+
+    fn child(&self, token: &str) -> Self {
+        Self {
+            pointer: format!("{}/{}", self.pointer, token),
+            duplicate: self.duplicate,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for UniqueKeys<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for UniqueKeys<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i128<E: de::Error>(self, _: i128) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u128<E: de::Error>(self, _: u128) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        while seq
+            .next_element_seed(self.child(&index.to_string()))?
+            .is_some()
+        {
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = HashSet::new();
+        while let Some(key) = map.next_key_seed(Key)? {
+            let Some(key) = key else {
+                map.next_value::<IgnoredAny>()?;
+                continue;
+            };
+            let child = self.child(&escape_token(&key));
+            if !seen.insert(key) {
+                *self.duplicate.borrow_mut() = Some(child.pointer);
+                return Err(de::Error::custom("duplicate key"));
+            }
+            map.next_value_seed(child)?;
+        }
+        Ok(())
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<(), A::Error> {
+        // A tagged YAML node
+        let (IgnoredAny, variant) = data.variant()?;
+        de::VariantAccess::newtype_variant_seed(variant, self)
+    }
+}
+
+/// Reads a mapping key as the string JSON would have, or `None` for a
+/// collection, which JSON cannot have as a key.
+struct Key;
+
+impl<'de> DeserializeSeed<'de> for Key {
+    type Value = Option<String>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Key {
+    type Value = Option<String>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a mapping key")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(Some(value.to_string()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Some("null".to_string()))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(None)
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(None)
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        let (IgnoredAny, variant) = data.variant()?;
+        de::VariantAccess::newtype_variant_seed(variant, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_json() {
+        assert!(check_json(r#"{"a": {"b": 1}, "c": [{"b": 2}, {"b": 3}]}"#).is_ok());
+        assert_eq!(
+            check_json("{\n  \"a\": {\n    \"b\": 1,\n    \"b\": 2\n  }\n}")
+                .unwrap_err()
+                .to_string(),
+            "Duplicate key: line 4 column 7: \"b\" (/a/b)"
+        );
+        assert_eq!(
+            check_json(r#"[{"x/y": 1, "x/y": 2}]"#)
+                .unwrap_err()
+                .to_string(),
+            "Duplicate key: line 1 column 17: \"x/y\" (/0/x~1y)"
+        );
+        assert!(check_json("{\"a\": ").is_ok());
+    }
+
+    #[test]
+    fn test_check_yaml() {
+        assert!(check_yaml("a:\n  b: 1\nc:\n  b: 2\n").is_ok());
+        assert_eq!(
+            check_yaml("a: 1\nspec:\n  name: x\n  items: [1]\n  name: y\n")
+                .unwrap_err()
+                .to_string(),
+            "Duplicate key: line 5 column 3: \"name\" (/spec/name)"
+        );
+        let error = check_yaml("a: {b: 1, c: 2, b: 3}\n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"b\" (/a/b)"), "{}", error);
+        assert!(check_yaml("1: a\n'1': b\n").is_err());
+        assert!(check_yaml("a: !Ref {b: 1, b: 2}\n").is_err());
+    }
+}
@@ -32,17 +32,139 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Untrusted Input
+//!
+//! Parsing and validating documents never panics, whatever the input:
+//! malformed content, nesting deeper than the parsers' limit of 128 levels,
+//! numbers too large for a float and YAML alias bombs are all reported as
+//! errors. [`ValidatorBuilder::max_document_size`],
+//! [`ValidatorBuilder::max_depth`], [`ValidatorBuilder::max_aliases`] and
+//! [`ValidatorBuilder::max_alias_expansion`] bound the work further. The `fuzz`
+//! directory has `cargo fuzz` targets for the parsers and validation.
 
 use anyhow::Result;
-use jsonschema::{Draft, JSONSchema};
-use reqwest::blocking::Client;
 use serde_json::Value;
 use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs;
-use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::path::Path;
+use std::sync::Arc;
 use url::Url;
 
+pub mod ambiguity;
+pub mod analysis;
+pub mod baseline;
+pub mod batch;
+#[cfg(feature = "bson")]
+pub mod bson;
+mod builder;
+pub mod bundle;
+pub mod cache;
+pub mod canonical;
+pub mod capabilities;
+pub mod catalog;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod cli;
+pub mod compat;
+pub mod compiled;
+pub mod config;
+pub mod csv;
+pub mod diagnostic;
+pub mod diff;
+mod draft;
+mod error_message;
+pub mod failure;
+pub mod github;
+pub mod hygiene;
+pub mod i18n;
+pub mod ignore;
+pub mod ini;
+mod json5;
+mod keys;
+pub mod loader;
+pub mod merge;
+#[cfg(feature = "tower")]
+pub mod middleware;
+pub mod migrate;
+mod numbers;
+pub mod outline;
+pub mod owners;
+pub mod partial;
+mod refs;
+pub mod registry;
+pub mod report;
+mod resolve;
+pub mod sanitize;
+pub mod schemas;
+pub mod snippets;
+mod source;
+#[cfg(any(test, feature = "test-support"))]
+pub mod stub;
+pub mod style;
+pub mod suggest;
+pub mod tail;
+pub mod trace;
+pub mod vocabulary;
+mod walk;
+#[cfg(feature = "xml")]
+pub mod xml;
+mod yaml;
+
+pub use ambiguity::{find_yaml_ambiguities, YamlAmbiguity};
+pub use analysis::{
+    analyze_schema, check_examples, find_smells, find_unknown_keywords, find_unsatisfiable,
+    lint_schema, SchemaIssue, SchemaIssueKind,
+};
+pub use baseline::{Baseline, BaselineReporter};
+pub use batch::{validate_files, validate_files_streaming, BatchOptions, FileReport, FileStatus};
+pub use builder::ValidatorBuilder;
+pub use bundle::bundle_schema;
+#[cfg(feature = "redis")]
+pub use cache::RedisCacheStore;
+pub use cache::{CacheStore, FsCacheStore, MemoryCacheStore};
+pub use canonical::canonicalize;
+pub use capabilities::capabilities;
+pub use catalog::{
+    expand_glob, find_documents, Catalog, CatalogEntry, FileSelection, SchemaPolicy,
+    SelectionReport, IGNORE_FILE,
+};
+pub use compat::{compare_schemas, SchemaChange};
+pub use compiled::{compile_schema, COMPILED_FORMAT_VERSION};
+pub use config::{check_env, check_env_from, env_config_from, load_env_config, parse_env_file};
+pub use csv::{parse_csv, validate_csv, CsvOptions, RowFailure};
+pub use diff::{diff_failures, diff_reports, FailureDiff, ReportDiff, Violation};
+pub use draft::Draft;
+pub use failure::{
+    group_failures, locate_failures, FailureGroup, SourceLocation, ValidationFailure,
+};
+pub use hygiene::{check_hygiene, HygieneIssue, HygieneIssueKind};
+pub use i18n::Localizer;
+pub use ignore::{IgnoreRule, IgnoreRules};
+pub use loader::{
+    default_user_agent, set_default_http_config, FixtureMode, HttpConfig, HttpLoader,
+    RecordReplayLoader, SchemaLoader,
+};
+pub use merge::{merge_schemas, merge_schemas_with, MergeOptions};
+#[cfg(feature = "tower")]
+pub use middleware::{SchemaStore, ValidationLayer, ValidationService};
+pub use migrate::{migrate_schema, Migration, MigrationNote};
+pub use outline::{outline_schema, Outline, OutlineField};
+pub use partial::DocumentBuilder;
+pub use registry::{RegisteredSchema, RegistryClient};
+pub use report::{report_files, FileError, Reporter, Summary};
+pub use resolve::RefPrefetch;
+use resolve::Resolver;
+pub use sanitize::{sanitize, Sanitized};
+pub use schemas::SchemaRegistry;
+pub use snippets::{generate_snippets, SnippetFormat, SnippetOptions};
+pub use style::{check_style, fix_style, StyleIssue, StyleIssueKind, StyleRules};
+pub use suggest::{suggest_fixes, unified_diff, Suggestion};
+pub use trace::{trace, KeywordOutcome, TraceStep};
+pub use vocabulary::{check_vocabularies, unsupported_vocabularies};
+
 /// Custom error types for validation operations
 #[derive(thiserror::Error, Debug)]
 pub enum ValidationError {
@@ -52,9 +174,45 @@ pub enum ValidationError {
     #[error("Failed to parse YAML: {0}")]
     YamlParse(#[from] serde_yaml::Error),
 
+    #[error("Invalid YAML directive: {0}")]
+    YamlDirective(String),
+
+    #[error("YAML anchors and aliases are not allowed: {0}")]
+    YamlAlias(String),
+
+    #[error("Unsupported YAML tag: {0}")]
+    YamlTag(String),
+
+    #[error("Duplicate key: {0}")]
+    DuplicateKey(String),
+
     #[error("Failed to parse JSON: {0}")]
     JsonParse(#[from] serde_json::Error),
 
+    #[error("Failed to parse CSV: {0}")]
+    CsvParse(String),
+
+    #[error("Failed to parse XML: {0}")]
+    XmlParse(String),
+
+    #[error("Failed to parse CBOR: {0}")]
+    CborParse(String),
+
+    #[error("Failed to parse BSON: {0}")]
+    BsonParse(String),
+
+    #[error("Failed to parse INI: {0}")]
+    IniParse(String),
+
+    #[error("Failed to parse properties: {0}")]
+    PropertiesParse(String),
+
+    #[error("Failed to parse JSON: {source} (hint: {hint})")]
+    JsonSyntax {
+        source: serde_json::Error,
+        hint: String,
+    },
+
     #[error("Invalid schema: {0}")]
     SchemaCompilation(String),
 
@@ -64,11 +222,227 @@ pub enum ValidationError {
     #[error("HTTP request failed: {0}")]
     HttpRequest(#[from] reqwest::Error),
 
+    #[error("HTTP error status: {0}")]
+    HttpStatus(String),
+
+    #[error("Invalid HTTP configuration: {0}")]
+    HttpConfig(String),
+
     #[error("Invalid URL: {0}")]
     InvalidUrl(#[from] url::ParseError),
 
     #[error("Cache directory error: {0}")]
     CacheDirectory(String),
+
+    #[error("Schema cache error: {0}")]
+    Cache(String),
+
+    #[error("Failed to load schema: {0}")]
+    SchemaLoad(String),
+
+    #[error("Unsupported schema migration: {0}")]
+    UnsupportedMigration(String),
+
+    #[error("Document limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    #[error("Invalid pointer: {0}")]
+    InvalidPointer(String),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Invalid report: {0}")]
+    InvalidReport(String),
+
+    #[error("Invalid compiled schema: {0}")]
+    InvalidCompiled(String),
+
+    #[error("Empty document: the file has no content besides comments")]
+    EmptyDocument,
+}
+
+/// Input formats understood by the validator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// JSON documents.
+    Json,
+    /// YAML documents.
+    Yaml,
+    /// JSON with comments, trailing commas, single-quoted strings or unquoted
+    /// keys, as written by hand in many configuration files.
+    Json5,
+}
+
+impl DocumentFormat {
+    /// Detect the format from a file extension (`.json`, `.yaml`, `.yml`,
+    /// `.json5`, `.jsonc`).
+    ///
+    /// Returns `None` when the extension is missing or not recognized.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        match extension.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json5" | "jsonc" => Some(Self::Json5),
+            _ => None,
+        }
+    }
+
+    /// Detect the format from content: JSON if it starts with `{` or `[`, YAML otherwise.
+    pub fn from_content(content: &str) -> Self {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+
+    /// Detect the format from the file extension, falling back to the content.
+    pub fn detect<P: AsRef<Path>>(path: P, content: &str) -> Self {
+        Self::from_path(path).unwrap_or_else(|| Self::from_content(content))
+    }
+
+    /// Whether `content` holds no document: only whitespace, comments in
+    /// formats that allow them and, in YAML, directives and `---` or `...`
+    /// markers.
+    ///
+    /// ```rust
+    /// use validate_json_schema::DocumentFormat;
+    ///
+    /// assert!(DocumentFormat::Yaml.is_blank("%YAML 1.2\n---\n# to do\n"));
+    /// assert!(!DocumentFormat::Yaml.is_blank("--- null"));
+    /// assert!(!DocumentFormat::Json.is_blank("// to do"));
+    /// ```
+    pub fn is_blank(self, content: &str) -> bool {
+        match self {
+            Self::Json => content.trim().is_empty(),
+            Self::Yaml => yaml::is_blank(content),
+            Self::Json5 => json5::strip_comments(content).trim().is_empty(),
+        }
+    }
+
+    /// Parse `content` in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is malformed. Malformed JSON that uses
+    /// syntax [`Json5`](Self::Json5) accepts is reported with a hint.
+    pub fn parse(self, content: &str) -> Result<Value, ValidationError> {
+        match self {
+            Self::Json => json5::parse_json(content),
+            Self::Yaml => Ok(yaml::from_str(content)?),
+            Self::Json5 => Ok(serde_json::from_str(&json5::to_json(content))?),
+        }
+    }
+}
+
+/// How a [`Validator`] treats documents with no content, as described by
+/// [`DocumentFormat::is_blank`]. Set with
+/// [`ValidatorBuilder::empty_documents`].
+///
+/// Repositories often hold placeholder files that are empty or only
+/// comments; without a policy they fail as `null` against most schemas, or
+/// to parse at all as JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyDocuments {
+    /// Validate the document as `null`, as YAML parsers read it.
+    #[default]
+    Null,
+    /// Accept the document without validating it.
+    Skip,
+    /// Fail with [`ValidationError::EmptyDocument`].
+    Error,
+}
+
+impl std::str::FromStr for EmptyDocuments {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "null" => Ok(Self::Null),
+            "skip" => Ok(Self::Skip),
+            "error" => Ok(Self::Error),
+            other => Err(format!(
+                "unknown policy '{}': expected null, skip or error",
+                other
+            )),
+        }
+    }
+}
+
+/// How a [`Validator`] treats YAML nodes with tags outside the core schema,
+/// such as `!!binary` or an application's own `!Ref`. Set with
+/// [`ValidatorBuilder::yaml_tags`].
+///
+/// Global tags (`!!binary`, `!<tag:...>`) are only seen on values written in
+/// block style, on their own `key:` or `- ` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YamlTags {
+    /// Fail with [`ValidationError::YamlTag`], naming the node, its tag and
+    /// the start of its value.
+    #[default]
+    Error,
+    /// Validate the value as if it had no tag.
+    Strip,
+    /// Check that `!!binary` values are base64, and validate them as the
+    /// base64 text with line breaks removed, so `contentEncoding: base64`
+    /// applies. Other tags are stripped.
+    Base64Decode,
+}
+
+impl std::str::FromStr for YamlTags {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "strip" => Ok(Self::Strip),
+            "base64-decode" => Ok(Self::Base64Decode),
+            other => Err(format!(
+                "unknown policy '{}': expected error, strip or base64-decode",
+                other
+            )),
+        }
+    }
+}
+
+/// How a [`Validator`] types unquoted YAML scalars. Set with
+/// [`ValidatorBuilder::yaml_schema`].
+///
+/// Under YAML 1.1 rules `country: NO` is the boolean `false` and `mode: 0755`
+/// the number 493 (the "Norway problem"). `serde_yaml` reads both as strings:
+/// `NO` by YAML 1.2's core schema, and `0755` because it keeps numbers with a
+/// leading zero as written, where the core schema would read 755.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YamlSchema {
+    /// The YAML 1.2 core schema as `serde_yaml` applies it: `NO` and `0755`
+    /// stay strings, and only scalars such as `true`, `null`, `12`, `0o755` or
+    /// `0x1F` are typed.
+    #[default]
+    Core,
+    /// YAML 1.1 rules, as PyYAML and other YAML 1.1 parsers read documents:
+    /// `yes`/`no`/`on`/`off`/`y`/`n` are booleans, and `0755`, `0b1010`,
+    /// `1_000` and `22:22` are numbers. Use it to validate what such a tool
+    /// will see. Only values written on their own `key:` or `- ` line are
+    /// retyped.
+    Yaml11,
+}
+
+impl std::str::FromStr for YamlSchema {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "core" | "yaml-1.2" => Ok(Self::Core),
+            "yaml-1.1" => Ok(Self::Yaml11),
+            other => Err(format!(
+                "unknown YAML schema '{}': expected core or yaml-1.1",
+                other
+            )),
+        }
+    }
 }
 
 /// A high-performance validator for YAML and JSON content against JSON schemas.
@@ -93,35 +467,99 @@ pub enum ValidationError {
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Debug)]
 pub struct Validator {
-    schema: JSONSchema,
+    schema: jsonschema::Validator,
+    draft: Draft,
+    limits: builder::Limits,
+    allow_comments: bool,
+    merge_keys: bool,
+    allow_aliases: bool,
+    yaml_tags: YamlTags,
+    tag_handlers: Vec<(String, yaml::TagHandler)>,
+    yaml_schema: YamlSchema,
+    strict_keys: bool,
+    empty_documents: EmptyDocuments,
+    /// `Some` to group failures by value, with the most kept per value.
+    failure_groups: Option<Option<usize>>,
+    /// The most failures reported per document, if bounded.
+    max_failures: Option<usize>,
+    /// The schema compiled to check for warnings, if they are checked.
+    warnings: Option<builder::Warnings>,
+    warnings_as_errors: bool,
+    /// Failures to leave out of every report.
+    ignore: IgnoreRules,
+    error_messages: Option<Arc<error_message::ErrorMessages>>,
 }
 
+impl std::fmt::Debug for Validator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Validator")
+            .field("draft", &self.draft)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Base URI of schemas that were not loaded from a file or URL and have no `$id`.
+const DEFAULT_BASE_URI: &str = "json-schema:///root.json";
+
 impl Validator {
+    /// Start configuring a validator, e.g. to pick the draft or set limits.
+    pub fn builder() -> ValidatorBuilder {
+        ValidatorBuilder::new()
+    }
+
     /// Create a new validator from a JSON schema string.
     ///
     /// # Errors
     ///
     /// Returns an error if the schema is invalid JSON or not a valid JSON Schema.
     pub fn new(schema_content: &str) -> Result<Self, ValidationError> {
-        let schema_value: Value = serde_json::from_str(schema_content)?;
-        let schema = JSONSchema::options()
-            .with_draft(Draft::Draft7)
-            .compile(&schema_value)
-            .map_err(|e| ValidationError::SchemaCompilation(e.to_string()))?;
+        ValidatorBuilder::new().build_str(schema_content)
+    }
+
+    /// Create a validator from an already parsed JSON schema.
+    ///
+    /// The draft is taken from the schema's `$schema` keyword, defaulting to
+    /// Draft 7.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value is not a valid JSON Schema.
+    pub fn from_value(schema_value: &Value) -> Result<Self, ValidationError> {
+        ValidatorBuilder::new().build(schema_value)
+    }
 
-        Ok(Self { schema })
+    /// Compile the subschema at `pointer` inside `root`.
+    ///
+    /// References from the subschema into the rest of the document keep
+    /// working. The subschema is reached through a `$ref`, so schema paths of
+    /// its errors start with `/$ref`.
+    pub(crate) fn from_subschema(root: &Value, pointer: &str) -> Result<Self, ValidationError> {
+        // Analysis compiles many subschemas, so custom meta-schemas are not fetched
+        let draft = Draft::detect(root).unwrap_or(Draft::Draft7);
+        ValidatorBuilder::new().build_subschema(root, pointer, draft)
     }
 
     /// Create a validator from a local schema file.
     ///
+    /// Relative references in the schema resolve against the file's location.
+    ///
     /// # Errors
     ///
     /// Returns an error if the file cannot be read or contains invalid JSON Schema.
     pub fn from_file<P: AsRef<Path>>(schema_path: P) -> Result<Self, ValidationError> {
-        let schema_content = fs::read_to_string(schema_path)?;
-        Self::new(&schema_content)
+        ValidatorBuilder::new().build_file(schema_path)
+    }
+
+    /// Create a validator from a compiled schema artifact written by
+    /// [`compile_schema`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not an artifact of
+    /// this version of the format, or does not match its digest.
+    pub fn from_compiled<P: AsRef<Path>>(path: P) -> Result<Self, ValidationError> {
+        ValidatorBuilder::new().build_compiled(path)
     }
 
     /// Create a validator from a remote schema URL.
@@ -133,8 +571,61 @@ impl Validator {
     /// Returns an error if the URL is invalid, the request fails, or the
     /// response is not valid JSON Schema.
     pub fn from_url(schema_url: &str) -> Result<Self, ValidationError> {
-        let schema_content = fetch_and_cache_schema(schema_url)?;
-        Self::new(&schema_content)
+        ValidatorBuilder::new().build_url(schema_url)
+    }
+
+    /// Create a validator from a remote schema URL, caching it in `cache`.
+    ///
+    /// Use this to share a cache between processes, e.g. with a Redis-backed
+    /// store, or to avoid touching the user's cache directory. Remote
+    /// references from the schema are cached in the same store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid, the request or cache access
+    /// fails, or the response is not valid JSON Schema.
+    pub fn from_url_with_cache(
+        schema_url: &str,
+        cache: Arc<dyn CacheStore>,
+    ) -> Result<Self, ValidationError> {
+        ValidatorBuilder::new().cache(cache).build_url(schema_url)
+    }
+
+    /// Create a validator from a remote schema URL with its own HTTP settings.
+    ///
+    /// Overrides the process-wide configuration from
+    /// [`set_default_http_config`] for this validator; the schema cache is still used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration or URL is invalid, the request
+    /// fails, or the response is not valid JSON Schema.
+    pub fn from_url_with_config(
+        schema_url: &str,
+        config: &HttpConfig,
+    ) -> Result<Self, ValidationError> {
+        ValidatorBuilder::new()
+            .http_config(config.clone())
+            .build_url(schema_url)
+    }
+
+    /// Create a validator from a remote schema URL using a custom loader.
+    ///
+    /// The loader is used directly for the schema and everything it
+    /// references, bypassing the schema cache, which makes this suitable for
+    /// tests replaying recorded responses with [`RecordReplayLoader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the loader fails or the response is not valid JSON Schema.
+    pub fn from_url_with_loader<L: SchemaLoader + 'static>(
+        schema_url: &str,
+        loader: L,
+    ) -> Result<Self, ValidationError> {
+        ValidatorBuilder::new()
+            .loader(loader)
+            .no_cache()
+            .build_url(schema_url)
     }
 
     /// Create a validator from either a local file path or remote URL.
@@ -145,33 +636,153 @@ impl Validator {
     ///
     /// Returns an error if the schema cannot be loaded or is invalid.
     pub fn from_schema_input(schema_input: &str) -> Result<Self, ValidationError> {
-        if is_url(schema_input) {
-            Self::from_url(schema_input)
-        } else {
-            Self::from_file(schema_input)
-        }
+        ValidatorBuilder::new().build_input(schema_input)
+    }
+
+    /// The draft the schema was compiled as.
+    pub fn draft(&self) -> Draft {
+        self.draft
+    }
+
+    /// Validate a schema document against its draft's meta-schema.
+    ///
+    /// The draft is the one named by `$schema`, or Draft 7 if there is none
+    /// or it names a custom meta-schema. Returns an empty list if the schema
+    /// is valid. Unlike the compilation error of an invalid schema, every
+    /// failure points at its location in the schema: `instance_path` is the
+    /// offending value in `schema` and `schema_path` the meta-schema keyword
+    /// it violates.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use validate_json_schema::Validator;
+    ///
+    /// let failures = Validator::check_schema(&json!({
+    ///     "properties": {"age": {"type": "integr"}}
+    /// }));
+    /// assert_eq!(failures[0].instance_path, "/properties/age/type");
+    /// ```
+    pub fn check_schema(schema: &Value) -> Vec<ValidationFailure> {
+        let draft = Draft::detect(schema).unwrap_or(Draft::Draft7);
+        draft
+            .meta_validator()
+            .iter_errors(schema)
+            .map(|error| ValidationFailure::from_engine(&error))
+            .collect()
     }
 
     /// Validate YAML content against the schema.
     ///
+    /// The content must be a single document; use
+    /// [`validate_yaml_documents`](Self::validate_yaml_documents) for a stream
+    /// of documents separated by `---`.
+    ///
     /// # Errors
     ///
     /// Returns an error if the YAML is malformed or fails validation.
     pub fn validate_yaml(&self, yaml_content: &str) -> Result<(), ValidationError> {
-        let yaml_value: Value = serde_yaml::from_str(yaml_content)?;
-        self.validate_value(&yaml_value)
+        self.validate_text(yaml_content, DocumentFormat::Yaml)
+            .map(drop)
+    }
+
+    /// Validate every document of a YAML stream, such as a file of Kubernetes
+    /// manifests separated by `---`, against the schema.
+    ///
+    /// The empty document policy applies to each document, so
+    /// [`EmptyDocuments::Skip`] ignores a trailing `---`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use validate_json_schema::Validator;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let validator = Validator::new(r#"{"required": ["kind"]}"#)?;
+    /// let manifests = "kind: Service\n---\nkind: Deployment\n---\nmetadata: {}\n";
+    ///
+    /// let error = validator.validate_yaml_documents(manifests).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Validation failed: document 3: root: \"kind\" is a required property"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a document is malformed or fails validation. When
+    /// the stream has several documents, failures name the document they
+    /// belong to, counting from 1.
+    pub fn validate_yaml_documents(&self, yaml_content: &str) -> Result<(), ValidationError> {
+        self.validate_stream(yaml_content, DocumentFormat::Yaml)
+            .map(drop)
     }
 
     /// Validate JSON content against the schema.
     ///
+    /// Comments and trailing commas are accepted if the validator was built
+    /// with [`ValidatorBuilder::allow_comments`].
+    ///
     /// # Errors
     ///
     /// Returns an error if the JSON is malformed or fails validation.
     pub fn validate_json(&self, json_content: &str) -> Result<(), ValidationError> {
-        let json_value: Value = serde_json::from_str(json_content)?;
+        self.validate_text(json_content, DocumentFormat::Json)
+            .map(drop)
+    }
+
+    /// Validate JSON5 content against the schema.
+    ///
+    /// Comments, trailing commas, single-quoted strings and unquoted keys are
+    /// accepted, as for [`DocumentFormat::Json5`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is malformed or fails validation.
+    pub fn validate_json5(&self, json5_content: &str) -> Result<(), ValidationError> {
+        self.validate_text(json5_content, DocumentFormat::Json5)
+            .map(drop)
+    }
+
+    /// Validate a CBOR document against the schema, converted to JSON as
+    /// [`cbor::parse_cbor`] describes.
+    ///
+    /// Available with the `cbor` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CBOR is malformed or fails validation.
+    #[cfg(feature = "cbor")]
+    pub fn validate_cbor(&self, cbor_content: &[u8]) -> Result<(), ValidationError> {
+        self.limits.check_size(cbor_content)?;
+        let json_value = cbor::parse_cbor(cbor_content)?;
         self.validate_value(&json_value)
     }
 
+    /// Validate BSON content against the schema: one document, or several
+    /// stored one after another as `mongodump` writes them. Documents are
+    /// converted to JSON as [`bson::parse_bson`] describes.
+    ///
+    /// Available with the `bson` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the BSON is malformed or any document fails
+    /// validation. The failures of a dump name the document they belong to,
+    /// counting from 1.
+    #[cfg(feature = "bson")]
+    pub fn validate_bson(&self, bson_content: &[u8]) -> Result<(), ValidationError> {
+        self.limits.check_size(bson_content)?;
+        let documents = bson::parse_bson_dump(bson_content)?;
+        validate_each(&documents, |document| {
+            self.validate_value(document).map(|()| true)
+        })
+        .map(drop)
+    }
+
     /// Validate content with automatic format detection.
     ///
     /// Detects JSON (starts with `{` or `[`) vs YAML and validates accordingly.
@@ -180,12 +791,141 @@ impl Validator {
     ///
     /// Returns an error if the content is malformed or fails validation.
     pub fn validate_content(&self, content: &str) -> Result<(), ValidationError> {
-        let trimmed = content.trim_start();
-        if trimmed.starts_with('{') || trimmed.starts_with('[') {
-            self.validate_json(content)
+        self.validate_as(content, DocumentFormat::from_content(content))
+    }
+
+    /// Validate content in an explicitly chosen format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is malformed or fails validation.
+    pub fn validate_as(
+        &self,
+        content: &str,
+        format: DocumentFormat,
+    ) -> Result<(), ValidationError> {
+        match format {
+            DocumentFormat::Json => self.validate_json(content),
+            DocumentFormat::Yaml => self.validate_yaml(content),
+            DocumentFormat::Json5 => self.validate_json5(content),
+        }
+    }
+
+    /// Validate a parsed document and describe every failure.
+    ///
+    /// Returns an empty list if the document is valid. Unlike the `validate_*`
+    /// methods, each failure keeps its location and keyword, so tooling can
+    /// consume them without parsing messages. Validation stops early once the
+    /// [`max_failures`](ValidatorBuilder::max_failures) are found. With
+    /// [`warnings_as_errors`](ValidatorBuilder::warnings_as_errors), the
+    /// warnings of the document follow its other failures.
+    pub fn validate_detailed(&self, instance: &Value) -> Vec<ValidationFailure> {
+        let max = self.max_failures.unwrap_or(usize::MAX);
+        let failures = self.iter_errors(instance);
+        if self.warnings_as_errors {
+            failures
+                .chain(self.find_warnings(instance))
+                .take(max)
+                .collect()
         } else {
-            self.validate_yaml(content)
+            failures.take(max).collect()
+        }
+    }
+
+    /// The warnings of a parsed document, if the validator was built to
+    /// check them with [`warnings`](ValidatorBuilder::warnings): values the
+    /// schema marks deprecated, with the keyword `deprecated`, and strings
+    /// that do not match their unasserted `format`. Empty if warnings are not
+    /// checked or are [reported as failures](ValidatorBuilder::warnings_as_errors).
+    pub fn warnings(&self, instance: &Value) -> Vec<ValidationFailure> {
+        if self.warnings_as_errors {
+            return Vec::new();
         }
+        self.find_warnings(instance).collect()
+    }
+
+    fn find_warnings<'a>(
+        &'a self,
+        instance: &'a Value,
+    ) -> impl Iterator<Item = ValidationFailure> + 'a {
+        self.warnings.iter().flat_map(move |warnings| {
+            warnings
+                .schema
+                .iter_errors(instance)
+                .map(|error| ValidationFailure::from_engine(&error))
+                .filter(|failure| {
+                    failure.keyword == "deprecated"
+                        || (warnings.formats && failure.keyword == "format")
+                })
+                .filter(|failure| self.ignore.matching(failure).is_none())
+        })
+    }
+
+    /// Lazily yield the failures of a parsed document.
+    ///
+    /// Failures are produced as validation proceeds, so taking only the first
+    /// one (or stopping early) skips the rest of the work. Failures the
+    /// schema gives a message with the `errorMessage` keyword have that
+    /// message and the keyword `errorMessage`, once per message and value.
+    pub fn iter_errors<'a>(
+        &'a self,
+        instance: &'a Value,
+    ) -> impl Iterator<Item = ValidationFailure> + 'a {
+        let mut replaced = HashSet::new();
+        self.schema
+            .iter_errors(instance)
+            .map(|error| ValidationFailure::from_engine(&error))
+            .filter(|failure| self.ignore.matching(failure).is_none())
+            .map(move |failure| match &self.error_messages {
+                Some(messages) => messages.apply(instance, failure),
+                None => failure,
+            })
+            .filter(move |failure| {
+                failure.keyword != "errorMessage"
+                    || replaced.insert((failure.instance_path.clone(), failure.schema_path.clone()))
+            })
+    }
+
+    /// Check whether a parsed document is valid.
+    ///
+    /// Stops at the first failure and allocates no error messages. A document
+    /// over the validator's limits is not valid.
+    pub fn is_valid_value(&self, instance: &Value) -> bool {
+        self.limits.check_depth(instance).is_ok()
+            && if self.ignore.is_empty() {
+                self.schema.is_valid(instance)
+            } else {
+                self.iter_errors(instance).next().is_none()
+            }
+            && !(self.warnings_as_errors && self.find_warnings(instance).next().is_some())
+    }
+
+    /// Check whether JSON content is valid. Malformed JSON is not valid.
+    pub fn is_valid_json(&self, json_content: &str) -> bool {
+        self.is_valid_text(json_content, DocumentFormat::Json)
+    }
+
+    /// Check whether YAML content is valid. Malformed YAML is not valid.
+    pub fn is_valid_yaml(&self, yaml_content: &str) -> bool {
+        self.is_valid_text(yaml_content, DocumentFormat::Yaml)
+    }
+
+    /// Whether [`validate_text`](Self::validate_text) would accept `content`,
+    /// without building failure messages.
+    fn is_valid_text(&self, content: &str, format: DocumentFormat) -> bool {
+        if self.limits.check_size(content).is_err() {
+            return false;
+        }
+        let document = match (self.is_blank(content, format), self.empty_documents) {
+            (true, EmptyDocuments::Null) => Value::Null,
+            (true, EmptyDocuments::Skip) => return true,
+            (true, EmptyDocuments::Error) => return false,
+            (false, _) => match self.parse_text(content, format) {
+                Ok(document) => document,
+                Err(_) => return false,
+            },
+        };
+        self.is_valid_value(&document)
     }
 
     /// Validate a YAML file against the schema.
@@ -201,74 +941,272 @@ impl Validator {
     /// Validate a file with automatic format detection.
     ///
     /// Supports `.json`, `.yaml`, `.yml` extensions with fallback to content-based detection.
+    /// With the `cbor` and `bson` features, `.cbor` and `.bson` files are
+    /// validated as CBOR and BSON.
     ///
     /// # Errors
     ///
     /// Returns an error if the file cannot be read, is malformed, or fails validation.
     pub fn validate_file<P: AsRef<Path>>(&self, file_path: P) -> Result<(), ValidationError> {
-        let path = file_path.as_ref();
+        self.check_file(file_path.as_ref()).map(drop)
+    }
+
+    /// Validate a file as [`validate_file`](Self::validate_file) does.
+    /// Returns `false` if the file was skipped as empty under
+    /// [`EmptyDocuments::Skip`].
+    pub(crate) fn check_file(&self, path: &Path) -> Result<bool, ValidationError> {
+        self.check_file_into(path, None)
+    }
+
+    /// Check a file as [`check_file`](Self::check_file) does, adding the
+    /// failures of its text documents, located in the file, to `findings`.
+    pub(crate) fn check_file_into(
+        &self,
+        path: &Path,
+        findings: Option<&mut Vec<github::Finding>>,
+    ) -> Result<bool, ValidationError> {
+        #[cfg(feature = "cbor")]
+        if cbor::is_cbor_path(path) {
+            return self.validate_cbor(&fs::read(path)?).map(|()| true);
+        }
+        #[cfg(feature = "bson")]
+        if bson::is_bson_path(path) {
+            return self.validate_bson(&fs::read(path)?).map(|()| true);
+        }
         let content = fs::read_to_string(path)?;
+        self.validate_stream_into(&content, DocumentFormat::detect(path, &content), findings)
+    }
+
+    /// Validate text `content` in `format` as [`validate_text`](Self::validate_text)
+    /// does, taking YAML as a stream of documents. Returns `false` if every
+    /// document was skipped.
+    pub(crate) fn validate_stream(
+        &self,
+        content: &str,
+        format: DocumentFormat,
+    ) -> Result<bool, ValidationError> {
+        self.validate_stream_into(content, format, None)
+    }
 
-        // Try extension-based detection first
-        if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
-            match extension.to_lowercase().as_str() {
-                "json" => return self.validate_json(&content),
-                "yaml" | "yml" => return self.validate_yaml(&content),
-                _ => {} // Fall through to content-based detection
+    /// Validate text `content` as [`validate_stream`](Self::validate_stream)
+    /// does, adding each failure, located in `content`, to `findings`.
+    fn validate_stream_into(
+        &self,
+        content: &str,
+        format: DocumentFormat,
+        mut findings: Option<&mut Vec<github::Finding>>,
+    ) -> Result<bool, ValidationError> {
+        if format != DocumentFormat::Yaml {
+            return self.validate_text_into(content, format, findings);
+        }
+        self.limits.check_size(content)?;
+        // Documents keep their lines in the stream, so failures are located
+        // in the whole of it
+        let documents = yaml::split_documents(content);
+        validate_each(&documents, |document| {
+            self.validate_text_into(document, format, findings.as_deref_mut())
+        })
+    }
+
+    /// Validate text `content` in `format`, applying the empty document
+    /// policy. Returns `false` if the document was skipped.
+    pub(crate) fn validate_text(
+        &self,
+        content: &str,
+        format: DocumentFormat,
+    ) -> Result<bool, ValidationError> {
+        self.validate_text_into(content, format, None)
+    }
+
+    /// Validate text `content` as [`validate_text`](Self::validate_text)
+    /// does, adding each failure, located in `content`, to `findings`.
+    fn validate_text_into(
+        &self,
+        content: &str,
+        format: DocumentFormat,
+        findings: Option<&mut Vec<github::Finding>>,
+    ) -> Result<bool, ValidationError> {
+        self.limits.check_size(content)?;
+        let document = match (self.is_blank(content, format), self.empty_documents) {
+            (true, EmptyDocuments::Null) => Value::Null,
+            (true, EmptyDocuments::Skip) => return Ok(false),
+            (true, EmptyDocuments::Error) => return Err(ValidationError::EmptyDocument),
+            (false, _) => self.parse_text(content, format)?,
+        };
+        self.limits.check_depth(&document)?;
+        let failures = self.validate_detailed(&document);
+        if failures.is_empty() {
+            return Ok(true);
+        }
+        let result = combine_failures(self.failure_messages(&failures, |failures| {
+            locate_failures(content, format, failures)
+        }));
+        if let (Err(_), Some(findings)) = (&result, findings) {
+            findings.extend(github::Finding::locate(content, format, failures));
+        }
+        result.map(|()| true)
+    }
+
+    /// Whether `content` holds no document, counting comments as blank where
+    /// they are allowed.
+    fn is_blank(&self, content: &str, format: DocumentFormat) -> bool {
+        format.is_blank(content)
+            || (format == DocumentFormat::Json
+                && self.allow_comments
+                && DocumentFormat::Json5.is_blank(content))
+    }
+
+    /// Parse text `content` in `format` with the configured document options.
+    pub(crate) fn parse_text(
+        &self,
+        content: &str,
+        format: DocumentFormat,
+    ) -> Result<Value, ValidationError> {
+        match format {
+            DocumentFormat::Json => self.parse_json(content),
+            DocumentFormat::Yaml => self.parse_yaml(content),
+            _ => format.parse(content),
+        }
+    }
+
+    /// Parse YAML content, rejecting anchors and duplicate keys and expanding
+    /// merge keys as configured.
+    fn parse_yaml(&self, yaml_content: &str) -> Result<Value, ValidationError> {
+        if !self.allow_aliases {
+            if let Some((line, anchor)) = yaml::find_anchor(yaml_content) {
+                return Err(ValidationError::YamlAlias(format!(
+                    "line {}: {}",
+                    line, anchor
+                )));
             }
         }
+        // Tags need a `!`, so documents without one take the direct path
+        let mut document = if self.merge_keys || yaml_content.contains('!') {
+            yaml::from_str_tagged(
+                yaml_content,
+                self.limits.aliases,
+                self.merge_keys,
+                self.yaml_tags,
+                &self.tag_handlers,
+            )?
+        } else {
+            yaml::from_str_limited(yaml_content, self.limits.aliases)?
+        };
+        // Checked after parsing, so alias limits bound the walk
+        if self.strict_keys {
+            keys::check_yaml(yaml_content)?;
+        }
+        if self.yaml_schema == YamlSchema::Yaml11 {
+            yaml::resolve_yaml11(yaml_content, &mut document);
+        }
+        Ok(document)
+    }
 
-        // Fall back to content-based detection
-        self.validate_content(&content)
+    /// Parse JSON content, stripping comments first if they are allowed and
+    /// rejecting duplicate keys if configured.
+    fn parse_json(&self, json_content: &str) -> Result<Value, ValidationError> {
+        let content = if self.allow_comments {
+            Cow::Owned(json5::strip_comments(json_content))
+        } else {
+            Cow::Borrowed(json_content)
+        };
+        let document = json5::parse_json(&content)?;
+        if self.strict_keys {
+            keys::check_json(&content)?;
+        }
+        Ok(document)
     }
 
     /// Internal method to validate a serde_json::Value against the schema.
     fn validate_value(&self, value: &Value) -> Result<(), ValidationError> {
-        if let Err(errors) = self.schema.validate(value) {
-            let error_messages: Vec<String> = errors
-                .map(|error| {
-                    let path = if error.instance_path.to_string().is_empty() {
-                        "root".to_string()
-                    } else {
-                        error.instance_path.to_string()
-                    };
-                    format!("{}: {}", path, error)
-                })
-                .collect();
-
-            let detailed_message = match error_messages.len() {
-                1 => error_messages[0].clone(),
-                n => format!("{} validation errors: {}", n, error_messages.join("; ")),
-            };
+        self.limits.check_depth(value)?;
+        let failures = self.validate_detailed(value);
+        combine_failures(self.failure_messages(&failures, |failures| vec![None; failures.len()]))
+    }
 
-            return Err(ValidationError::ValidationFailed(detailed_message));
+    /// The messages of `failures`, grouped by value if configured, each
+    /// prefixed with where `locate` places the failure, or its group's first.
+    fn failure_messages(
+        &self,
+        failures: &[ValidationFailure],
+        locate: impl FnOnce(&[ValidationFailure]) -> Vec<Option<SourceLocation>>,
+    ) -> Vec<String> {
+        let located = |message: String, location: Option<SourceLocation>| match location {
+            Some(location) => format!("{}: {}", location, message),
+            None => message,
+        };
+        match self.failure_groups {
+            None => failures
+                .iter()
+                .zip(locate(failures))
+                .map(|(failure, location)| located(failure.to_string(), location))
+                .collect(),
+            Some(max) => {
+                let groups = group_failures(failures, max);
+                let first: Vec<ValidationFailure> = groups
+                    .iter()
+                    .map(|group| group.failures[0].clone())
+                    .collect();
+                groups
+                    .iter()
+                    .zip(locate(&first))
+                    .map(|(group, location)| located(group.to_string(), location))
+                    .collect()
+            }
         }
+    }
+}
 
-        Ok(())
+/// Validate each of `documents`, naming the failing ones, counting from 1,
+/// when there are several. Returns whether any document was validated.
+fn validate_each<T>(
+    documents: &[T],
+    mut validate: impl FnMut(&T) -> Result<bool, ValidationError>,
+) -> Result<bool, ValidationError> {
+    if let [document] = documents {
+        return validate(document);
+    }
+    let mut validated = false;
+    let mut messages = Vec::new();
+    for (index, document) in documents.iter().enumerate() {
+        match validate(document) {
+            Ok(checked) => validated |= checked,
+            Err(ValidationError::ValidationFailed(message)) => {
+                messages.push(format!("document {}: {}", index + 1, message));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if messages.is_empty() {
+        Ok(validated)
+    } else {
+        Err(ValidationError::ValidationFailed(messages.join("; ")))
     }
 }
 
-/// Check if a string is a URL (starts with http:// or https://).
-fn is_url(input: &str) -> bool {
-    input.starts_with("http://") || input.starts_with("https://")
+/// Combine `failures` into a single `ValidationFailed` error, if there are any.
+pub(crate) fn failures_to_result(failures: &[ValidationFailure]) -> Result<(), ValidationError> {
+    combine_failures(failures.iter().map(ToString::to_string).collect())
 }
 
-/// Get the cache directory for schemas.
-fn get_cache_dir() -> Result<PathBuf, ValidationError> {
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| {
-            ValidationError::CacheDirectory("Could not determine cache directory".to_string())
-        })?
-        .join("validate-json-schema")
-        .join("schemas");
+/// Combine the messages of failures into a single `ValidationFailed` error,
+/// if there are any.
+pub(crate) fn combine_failures(error_messages: Vec<String>) -> Result<(), ValidationError> {
+    if !error_messages.is_empty() {
+        let detailed_message = match error_messages.len() {
+            1 => error_messages[0].clone(),
+            n => format!("{} validation errors: {}", n, error_messages.join("; ")),
+        };
 
-    if !cache_dir.exists() {
-        fs::create_dir_all(&cache_dir).map_err(|e| {
-            ValidationError::CacheDirectory(format!("Failed to create cache directory: {}", e))
-        })?;
+        return Err(ValidationError::ValidationFailed(detailed_message));
     }
 
-    Ok(cache_dir)
+    Ok(())
+}
+
+/// Check if a string is a URL (starts with http:// or https://).
+fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
 }
 
 /// Generate a cache filename from a URL using SHA-256 hash.
@@ -279,45 +1217,37 @@ fn get_cache_filename(url: &str) -> String {
     format!("{}.json", hex::encode(hash))
 }
 
-/// Fetch a schema from a URL and cache it locally.
-fn fetch_and_cache_schema(url: &str) -> Result<String, ValidationError> {
+/// Fetch a schema from a URL, going through `cache`.
+fn fetch_and_cache_schema(
+    url: &str,
+    loader: &dyn SchemaLoader,
+    cache: &dyn CacheStore,
+) -> Result<String, ValidationError> {
     // Validate URL
     let _parsed_url = Url::parse(url)?;
 
-    let cache_dir = get_cache_dir()?;
-    let cache_filename = get_cache_filename(url);
-    let cache_path = cache_dir.join(cache_filename);
-
     // Check if cached version exists
-    if cache_path.exists() {
-        return Ok(fs::read_to_string(cache_path)?);
+    if let Some(schema_content) = cache.get(url)? {
+        return Ok(schema_content);
     }
 
-    // Fetch from remote
-    let client = Client::builder()
-        .user_agent("validate-json-schema/0.1.0")
-        .timeout(Duration::from_secs(30))
-        .build()?;
-
-    let response = client.get(url).send()?;
-
-    if !response.status().is_success() {
-        return Err(ValidationError::ValidationFailed(format!(
-            "HTTP {}: Failed to fetch schema from {}",
-            response.status(),
-            url
-        )));
-    }
+    cache::single_flight(url, || {
+        // Another thread may have cached it while this one waited
+        if let Some(schema_content) = cache.get(url)? {
+            return Ok(schema_content);
+        }
 
-    let schema_content = response.text()?;
+        // Fetch from remote
+        let schema_content = loader.load(url)?;
 
-    // Validate that it's valid JSON before caching
-    let _: Value = serde_json::from_str(&schema_content)?;
+        // Validate that it's valid JSON before caching
+        let _: Value = serde_json::from_str(&schema_content)?;
 
-    // Cache the schema
-    fs::write(&cache_path, &schema_content)?;
+        // Cache the schema
+        cache.put(url, &schema_content)?;
 
-    Ok(schema_content)
+        Ok(schema_content)
+    })
 }
 
 /// Clear the schema cache directory.
@@ -326,11 +1256,30 @@ fn fetch_and_cache_schema(url: &str) -> Result<String, ValidationError> {
 ///
 /// Returns an error if the cache directory cannot be accessed or removed.
 pub fn clear_schema_cache() -> Result<(), ValidationError> {
-    let cache_dir = get_cache_dir()?;
-    if cache_dir.exists() {
-        fs::remove_dir_all(&cache_dir)?;
+    FsCacheStore::default_location()?.clear()
+}
+
+/// Load a raw schema document from a local file path, remote URL or
+/// `registry://` subject.
+///
+/// Remote schemas go through the same cache as [`Validator::from_url`]. The
+/// schema is parsed but not compiled, which makes this useful for tooling that
+/// inspects the schema itself.
+///
+/// # Errors
+///
+/// Returns an error if the schema cannot be loaded or is not valid JSON.
+pub fn load_schema(schema_input: &str) -> Result<Value, ValidationError> {
+    if schema_input.starts_with(registry::REGISTRY_SCHEME) {
+        return registry::load_input(schema_input, None, &Resolver::default())
+            .map(|(_, schema)| schema);
     }
-    Ok(())
+    let schema_content = if is_url(schema_input) {
+        Resolver::default().fetch(schema_input)?
+    } else {
+        fs::read_to_string(schema_input)?
+    };
+    Ok(serde_json::from_str(&schema_content)?)
 }
 
 // Convenience functions for one-off validations
@@ -418,6 +1367,181 @@ mod tests {
         assert!(validator.validate_json(r#"{"name": 123}"#).is_err());
     }
 
+    #[test]
+    fn test_validate_detailed() {
+        let schema = r#"{
+            "type": "object",
+            "required": ["name"],
+            "properties": {"tags": {"type": "array", "items": {"type": "string"}}}
+        }"#;
+        let validator = Validator::new(schema).unwrap();
+
+        let document = serde_json::json!({"tags": ["a", 1]});
+        let failures = validator.validate_detailed(&document);
+        assert_eq!(failures.len(), 2);
+
+        let required = failures.iter().find(|f| f.keyword == "required").unwrap();
+        assert_eq!(required.instance_path, "");
+        assert_eq!(required.schema_path, "/required");
+
+        let item = failures.iter().find(|f| f.keyword == "type").unwrap();
+        assert_eq!(item.instance_path, "/tags/1");
+        assert_eq!(item.schema_path, "/properties/tags/items/type");
+        assert_eq!(item.instance(&document), Some(&serde_json::json!(1)));
+
+        assert!(validator
+            .validate_detailed(&serde_json::json!({"name": "x"}))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_is_valid_agrees_with_validate() {
+        let schema = r#"{"properties": {"a": {"type": "integer"}, "b": {"type": "boolean"}}}"#;
+        let cases = [
+            (Validator::builder().merge_keys(true), "<<: {a: s}\n"),
+            (
+                Validator::builder().allow_aliases(false),
+                "x: &x 1\na: *x\n",
+            ),
+            (Validator::builder().strict_keys(true), "a: 1\na: 2\n"),
+            (
+                Validator::builder().yaml_schema(YamlSchema::Yaml11),
+                "b: yes\n",
+            ),
+            (
+                Validator::builder().empty_documents(EmptyDocuments::Error),
+                "",
+            ),
+            (
+                Validator::builder().empty_documents(EmptyDocuments::Skip),
+                "",
+            ),
+        ];
+        for (builder, yaml) in cases {
+            let validator = builder.build_str(schema).unwrap();
+            assert_eq!(
+                validator.is_valid_yaml(yaml),
+                validator.validate_yaml(yaml).is_ok(),
+                "{:?}",
+                yaml
+            );
+        }
+
+        let validator = Validator::builder()
+            .strict_keys(true)
+            .build_str(schema)
+            .unwrap();
+        assert!(!validator.is_valid_json(r#"{"a": 1, "a": 2}"#));
+        let validator = Validator::builder()
+            .allow_comments(true)
+            .empty_documents(EmptyDocuments::Skip)
+            .build_str(schema)
+            .unwrap();
+        assert!(validator.is_valid_json("// nothing yet"));
+    }
+
+    #[test]
+    fn test_false_schema_keyword() {
+        let validator = Validator::new(r#"{"properties": {"foo": false}}"#).unwrap();
+        let failures = validator.validate_detailed(&serde_json::json!({"foo": 1}));
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].keyword, "false");
+        assert_eq!(failures[0].schema_path, "/properties/foo");
+
+        let validator = Validator::new(r#"{"additionalProperties": false}"#).unwrap();
+        let failures = validator.validate_detailed(&serde_json::json!({"foo": 1}));
+        assert_eq!(failures[0].keyword, "additionalProperties");
+        let validator = Validator::new("false").unwrap();
+        assert_eq!(
+            validator.validate_detailed(&serde_json::json!(1))[0].keyword,
+            "false"
+        );
+    }
+
+    #[test]
+    fn test_is_valid_and_iter_errors() {
+        let schema = r#"{"type": "object", "required": ["a", "b"]}"#;
+        let validator = Validator::new(schema).unwrap();
+
+        assert!(validator.is_valid_json(r#"{"a": 1, "b": 2}"#));
+        assert!(validator.is_valid_yaml("a: 1\nb: 2"));
+        assert!(!validator.is_valid_yaml("a: 1"));
+        assert!(!validator.is_valid_json("{not json"));
+        assert!(!validator.is_valid_value(&serde_json::json!([])));
+
+        let document = serde_json::json!({});
+        let mut errors = validator.iter_errors(&document);
+        assert_eq!(errors.next().unwrap().keyword, "required");
+        assert_eq!(errors.count(), 1);
+    }
+
+    #[test]
+    fn test_check_schema() {
+        use serde_json::json;
+
+        assert!(Validator::check_schema(&json!({"type": "object"})).is_empty());
+
+        // Schemas are checked against the draft they declare, where
+        // exclusiveMinimum is still a boolean
+        let draft4 = json!({
+            "$schema": "http://json-schema.org/draft-04/schema#",
+            "properties": {"n": {"minimum": 0, "exclusiveMinimum": 0}}
+        });
+        let failures = Validator::check_schema(&draft4);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].instance_path, "/properties/n/exclusiveMinimum");
+        assert_eq!(failures[0].keyword, "type");
+
+        let draft202012 = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "required": "name",
+            "minItems": -1
+        });
+        let paths: Vec<String> = Validator::check_schema(&draft202012)
+            .into_iter()
+            .map(|failure| failure.instance_path)
+            .collect();
+        assert_eq!(paths, ["/minItems", "/required"]);
+    }
+
+    #[test]
+    fn test_concurrent_fetches_are_coalesced() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Barrier;
+
+        struct SlowLoader(AtomicUsize);
+        impl SchemaLoader for SlowLoader {
+            fn load(&self, _url: &str) -> Result<String, ValidationError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                Ok(r#"{"type": "string"}"#.to_string())
+            }
+        }
+
+        let loader = Arc::new(SlowLoader(AtomicUsize::new(0)));
+        let cache = Arc::new(MemoryCacheStore::new());
+        let barrier = Arc::new(Barrier::new(8));
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let (loader, cache, barrier) = (loader.clone(), cache.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    fetch_and_cache_schema(
+                        "https://example.com/coalesced.json",
+                        loader.as_ref(),
+                        cache.as_ref(),
+                    )
+                    .unwrap()
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), r#"{"type": "string"}"#);
+        }
+        assert_eq!(loader.0.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_validator_reuse() {
         let schema = r#"{
@@ -466,6 +1590,201 @@ mod tests {
         assert!(validator.validate_json(r#"{"count": 42"#).is_err()); // Missing closing brace
     }
 
+    #[test]
+    fn test_json5_validation() {
+        let schema = r#"{"type": "object", "properties": {"count": {"type": "integer"}}}"#;
+        let validator = Validator::new(schema).unwrap();
+
+        let content = "{\n  // how many\n  count: 42,\n  'name': 'x',\n}";
+        assert!(validator.validate_json5(content).is_ok());
+        assert!(validator.validate_json(content).is_err());
+        assert!(validator.validate_json5("{count: 'many'}").is_err());
+        assert_eq!(
+            DocumentFormat::from_path("settings.json5"),
+            Some(DocumentFormat::Json5)
+        );
+    }
+
+    #[test]
+    fn test_yaml_documents() {
+        let schema = r#"{"type": "object", "required": ["kind"]}"#;
+        let validator = Validator::new(schema).unwrap();
+
+        let manifests = "---\nkind: Service\n---\nkind: Deployment\n";
+        assert!(validator.validate_yaml_documents(manifests).is_ok());
+        assert!(validator.validate_yaml(manifests).is_err());
+
+        let error = validator
+            .validate_yaml_documents("kind: A\n---\nname: b\n---\n[1]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("document 2: root:"), "{}", error);
+        assert!(error.contains("document 3: root: [1]"), "{}", error);
+        assert!(!error.contains("document 1"), "{}", error);
+
+        // A trailing marker opens an empty document
+        let trailing = "kind: A\n---\n";
+        assert!(validator.validate_yaml_documents(trailing).is_err());
+        let skipping = Validator::builder()
+            .empty_documents(EmptyDocuments::Skip)
+            .build_str(schema)
+            .unwrap();
+        assert!(skipping.validate_yaml_documents(trailing).is_ok());
+        assert!(skipping.validate_yaml_documents("# nothing\n").is_ok());
+    }
+
+    #[test]
+    fn test_yaml_failure_locations() {
+        let validator = Validator::new(
+            r#"{"properties": {"spec": {"properties": {
+                "replicas": {"type": "integer"},
+                "ports": {"items": {"type": "integer"}}
+            }}}}"#,
+        )
+        .unwrap();
+        let error = validator
+            .validate_yaml("# web\nspec:\n    replicas: many\n    ports: [80, http]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("line 3 column 15: /spec/replicas: \"many\""),
+            "{}",
+            error
+        );
+        // Inside a flow collection, the closest mapped value
+        assert!(
+            error.contains("line 4 column 12: /spec/ports/1: \"http\""),
+            "{}",
+            error
+        );
+
+        // Lines count from the start of the stream
+        let error = validator
+            .validate_yaml_documents("spec: {}\n---\nspec:\n  replicas: 1.5\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("document 2: line 4 column 13: /spec/replicas"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_grouped_failures() {
+        let schema = r#"{"properties": {"port": {"allOf": [
+            {"type": "integer"},
+            {"type": "integer"},
+            {"enum": [80, 443]},
+            {"const": 80}
+        ]}, "name": {"type": "string"}}}"#;
+        let document = "name: 1\nport: http\n";
+        let grouped = Validator::builder()
+            .group_failures(true)
+            .build_str(schema)
+            .unwrap();
+        assert_eq!(
+            grouped.validate_yaml(document).unwrap_err().to_string(),
+            "Validation failed: 2 validation errors: line 1 column 7: /name: 1 is not of type \
+             \"string\"; line 2 column 7: /port: \"http\" is not of type \"integer\"; \
+             \"http\" is not one of [80,443]; 80 was expected"
+        );
+        let capped = Validator::builder()
+            .max_failures_per_path(1)
+            .build_str(schema)
+            .unwrap();
+        assert_eq!(
+            capped
+                .validate_value(&serde_json::json!({"port": "http"}))
+                .unwrap_err()
+                .to_string(),
+            "Validation failed: /port: \"http\" is not of type \"integer\" (and 2 more)"
+        );
+        let ungrouped = Validator::new(schema).unwrap();
+        assert!(ungrouped
+            .validate_yaml(document)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Validation failed: 5 validation errors"));
+    }
+
+    #[test]
+    fn test_json_failure_locations() {
+        let validator = Validator::new(
+            r#"{"properties": {"spec": {"properties": {
+                "replicas": {"type": "integer"},
+                "ports": {"items": {"type": "integer"}}
+            }}}}"#,
+        )
+        .unwrap();
+        let error = validator
+            .validate_json("{\n  \"spec\": {\n    \"replicas\": \"many\",\n    \"ports\": [80, \"http\"]\n  }\n}")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("line 3 column 17: /spec/replicas: \"many\""),
+            "{}",
+            error
+        );
+        assert!(
+            error.contains("line 4 column 19: /spec/ports/1: \"http\""),
+            "{}",
+            error
+        );
+        let error = validator
+            .validate_json5("// web\n{spec: {replicas: 'x', /* a: { */ ports: [1.5,],},}")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("line 2 column 19: /spec/replicas"),
+            "{}",
+            error
+        );
+        assert!(
+            error.contains("line 2 column 43: /spec/ports/0"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_adversarial_input() {
+        let validator = Validator::new(r#"{"properties": {"a": {"type": "string"}}}"#).unwrap();
+        let alias_bomb = (1..10).fold(
+            "a0: &a0 [x, x, x, x, x, x, x, x, x]\n".to_string(),
+            |yaml, level| {
+                let aliases = vec![format!("*a{}", level - 1); 9].join(", ");
+                format!("{}a{}: &a{} [{}]\n", yaml, level, level, aliases)
+            },
+        );
+        let inputs = [
+            "[".repeat(10_000),
+            "{\"a\":".repeat(10_000),
+            "a: ".to_string() + &"[".repeat(10_000),
+            "a: ".to_string() + &"{b: ".repeat(10_000),
+            alias_bomb,
+            r#"{"a": 1e99999}"#.to_string(),
+            "a: 1e99999\nb: 123456789012345678901234567890\nc: .nan".to_string(),
+            "'\\".to_string(),
+            "/*".to_string(),
+            "\0\u{feff}".to_string(),
+        ];
+        for input in &inputs {
+            for format in [
+                DocumentFormat::Json,
+                DocumentFormat::Yaml,
+                DocumentFormat::Json5,
+            ] {
+                let _ = validator.validate_as(input, format);
+            }
+            find_yaml_ambiguities(&validator, input);
+        }
+        assert!(matches!(
+            validator.validate_yaml(&inputs[2]),
+            Err(ValidationError::LimitExceeded(_))
+        ));
+    }
+
     #[test]
     fn test_content_auto_detection() {
         let schema = r#"{"type": "object", "properties": {"name": {"type": "string"}}}"#;
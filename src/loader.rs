@@ -0,0 +1,428 @@
+//! Loading remote schema documents.
+//!
+//! [`SchemaLoader`] is the extension point for how remote schemas are fetched.
+//! [`HttpLoader`] is what [`Validator::from_url`](crate::Validator::from_url)
+//! uses, configured through [`HttpConfig`]; [`RecordReplayLoader`] records responses to disk and replays them so
+//! remote-schema code paths can be tested without network access.
+
+use crate::{get_cache_filename, ValidationError};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use url::Url;
+
+/// The client shared by every [`HttpLoader::new`], built when the first of
+/// them sends a request, and the configuration it was built from.
+static DEFAULT_CLIENT: OnceLock<(Client, HttpConfig)> = OnceLock::new();
+
+/// Fetches raw schema documents from URLs.
+pub trait SchemaLoader: Send + Sync {
+    /// Fetch the schema document at `url` and return its raw content.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document cannot be fetched.
+    fn load(&self, url: &str) -> Result<String, ValidationError>;
+}
+
+/// Networking settings for fetching remote schemas.
+///
+/// Install one process-wide with [`set_default_http_config`], or pass one to
+/// [`HttpLoader::with_config`] to override it for a single validator.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    user_agent: String,
+    user_agent_suffix: Option<String>,
+    proxy: Option<String>,
+    headers: Vec<(String, String)>,
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: None,
+            user_agent: concat!("validate-json-schema/", env!("CARGO_PKG_VERSION")).to_string(),
+            user_agent_suffix: None,
+            proxy: None,
+            headers: Vec::new(),
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Create the default configuration: a 30 second timeout and no proxy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the total timeout for each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for establishing a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Replace the `User-Agent` header, `validate-json-schema/<version>` by default.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Append `suffix`, e.g. `my-app/2.1`, to the `User-Agent` header so
+    /// schema registries can tell which application is calling.
+    pub fn user_agent_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.user_agent_suffix = Some(suffix.into());
+        self
+    }
+
+    /// The `User-Agent` header sent with these settings.
+    pub fn effective_user_agent(&self) -> String {
+        match &self.user_agent_suffix {
+            Some(suffix) => format!("{} {}", self.user_agent, suffix),
+            None => self.user_agent.clone(),
+        }
+    }
+
+    /// Route all requests through the proxy at `url`.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Send an extra header with every request, e.g. for authentication.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Trust an additional PEM-encoded root certificate.
+    pub fn root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Accept invalid TLS certificates. Only use this against trusted hosts.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Build an HTTP client with these settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a header, proxy or certificate is invalid.
+    pub fn build_client(&self) -> Result<Client, ValidationError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                ValidationError::HttpConfig(format!("invalid header name '{}': {}", name, e))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                ValidationError::HttpConfig(format!("invalid value for header '{}': {}", name, e))
+            })?;
+            headers.append(name, value);
+        }
+
+        let mut builder = Client::builder()
+            .user_agent(self.effective_user_agent())
+            .timeout(self.timeout)
+            .default_headers(headers)
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+        }
+        for pem in &self.root_certificates {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(pem)?);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Install the HTTP configuration shared by all [`Validator::from_url`] calls.
+///
+/// This must be called before the first remote schema is fetched; the default
+/// client cannot be replaced once it has been built. Creating loaders and
+/// validators does not build it, nor does a schema served from the cache.
+///
+/// # Errors
+///
+/// Returns an error if the configuration is invalid or a default client is
+/// already in use.
+///
+/// [`Validator::from_url`]: crate::Validator::from_url
+pub fn set_default_http_config(config: HttpConfig) -> Result<(), ValidationError> {
+    let client = config.build_client()?;
+    DEFAULT_CLIENT.set((client, config)).map_err(|_| {
+        ValidationError::HttpConfig("the default HTTP client is already initialized".to_string())
+    })
+}
+
+/// The `User-Agent` header sent when fetching remote schemas with the
+/// process-wide configuration.
+pub fn default_user_agent() -> String {
+    default_http_config().effective_user_agent()
+}
+
+/// The process-wide HTTP configuration, which is the default one until
+/// [`set_default_http_config`] installs another.
+pub(crate) fn default_http_config() -> HttpConfig {
+    match DEFAULT_CLIENT.get() {
+        Some((_, config)) => config.clone(),
+        None => HttpConfig::default(),
+    }
+}
+
+fn default_client() -> Result<Client, ValidationError> {
+    if let Some((client, _)) = DEFAULT_CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let config = HttpConfig::default();
+    let client = config.build_client()?;
+    let (client, _) = DEFAULT_CLIENT.get_or_init(|| (client, config));
+    Ok(client.clone())
+}
+
+/// Loads schemas over HTTP(S).
+#[derive(Debug, Clone)]
+pub struct HttpLoader {
+    /// The loader's own client, or `None` for the process-wide default.
+    client: Option<Client>,
+}
+
+impl HttpLoader {
+    /// Create a loader using the process-wide default client, which is built
+    /// when the first request is sent.
+    ///
+    /// # Errors
+    ///
+    /// Currently never fails: an error building the default client is
+    /// returned by [`load`](SchemaLoader::load) instead.
+    pub fn new() -> Result<Self, ValidationError> {
+        Ok(Self { client: None })
+    }
+
+    /// Create a loader with its own configuration instead of the default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid.
+    pub fn with_config(config: &HttpConfig) -> Result<Self, ValidationError> {
+        Ok(Self {
+            client: Some(config.build_client()?),
+        })
+    }
+
+    /// The client to send requests with.
+    fn client(&self) -> Result<Client, ValidationError> {
+        match &self.client {
+            Some(client) => Ok(client.clone()),
+            None => default_client(),
+        }
+    }
+}
+
+impl SchemaLoader for HttpLoader {
+    fn load(&self, url: &str) -> Result<String, ValidationError> {
+        // Validate URL
+        let _parsed_url = Url::parse(url)?;
+
+        let response = self.client()?.get(url).send()?;
+
+        if !response.status().is_success() {
+            return Err(ValidationError::HttpStatus(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        Ok(response.text()?)
+    }
+}
+
+/// How a [`RecordReplayLoader`] uses its fixture directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Always fetch through the inner loader and overwrite the fixture.
+    Record,
+    /// Only serve fixtures; a missing fixture is an error.
+    Replay,
+    /// Serve the fixture if it exists, otherwise fetch and record it.
+    Auto,
+}
+
+/// A loader that records responses to a fixture directory and replays them.
+///
+/// Fixtures are stored as one file per URL, named by the SHA-256 hash of the
+/// URL (the same naming as the schema cache). Check the directory into the
+/// repository and use [`FixtureMode::Replay`] in tests to exercise remote
+/// schemas hermetically.
+#[derive(Debug, Clone)]
+pub struct RecordReplayLoader<L = HttpLoader> {
+    dir: PathBuf,
+    mode: FixtureMode,
+    inner: L,
+}
+
+impl RecordReplayLoader<HttpLoader> {
+    /// Create a loader that records from the network into `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Currently never fails, as [`HttpLoader::new`] does not.
+    pub fn new<P: AsRef<Path>>(dir: P, mode: FixtureMode) -> Result<Self, ValidationError> {
+        Ok(Self::with_loader(dir, mode, HttpLoader::new()?))
+    }
+}
+
+impl<L: SchemaLoader> RecordReplayLoader<L> {
+    /// Create a loader that records responses from `inner` into `dir`.
+    pub fn with_loader<P: AsRef<Path>>(dir: P, mode: FixtureMode, inner: L) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            mode,
+            inner,
+        }
+    }
+
+    /// The path of the fixture file for `url`.
+    pub fn fixture_path(&self, url: &str) -> PathBuf {
+        self.dir.join(get_cache_filename(url))
+    }
+
+    fn record(&self, url: &str, path: &Path) -> Result<String, ValidationError> {
+        let content = self.inner.load(url)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(path, &content)?;
+        Ok(content)
+    }
+}
+
+impl<L: SchemaLoader> SchemaLoader for RecordReplayLoader<L> {
+    fn load(&self, url: &str) -> Result<String, ValidationError> {
+        let path = self.fixture_path(url);
+        match self.mode {
+            FixtureMode::Record => self.record(url, &path),
+            FixtureMode::Replay => fs::read_to_string(&path).map_err(|e| {
+                ValidationError::SchemaLoad(format!(
+                    "no recorded fixture for {} at {}: {}",
+                    url,
+                    path.display(),
+                    e
+                ))
+            }),
+            FixtureMode::Auto if path.exists() => Ok(fs::read_to_string(&path)?),
+            FixtureMode::Auto => self.record(url, &path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves a fixed document and counts how often it was asked to.
+    struct StaticLoader {
+        calls: AtomicUsize,
+    }
+
+    impl SchemaLoader for StaticLoader {
+        fn load(&self, _url: &str) -> Result<String, ValidationError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(r#"{"type": "string"}"#.to_string())
+        }
+    }
+
+    fn static_loader() -> StaticLoader {
+        StaticLoader {
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn test_invalid_http_config() {
+        assert!(HttpConfig::new().build_client().is_ok());
+        assert!(matches!(
+            HttpConfig::new().header("bad header", "x").build_client(),
+            Err(ValidationError::HttpConfig(_))
+        ));
+        assert!(matches!(
+            HttpConfig::new()
+                .header("X-Token", "bad\nvalue")
+                .build_client(),
+            Err(ValidationError::HttpConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let default = HttpConfig::new().effective_user_agent();
+        assert_eq!(
+            default,
+            format!("validate-json-schema/{}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(
+            HttpConfig::new()
+                .user_agent_suffix("my-app/2.1")
+                .effective_user_agent(),
+            format!("{} my-app/2.1", default)
+        );
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = "https://example.com/schema.json";
+
+        let recorder =
+            RecordReplayLoader::with_loader(dir.path(), FixtureMode::Record, static_loader());
+        assert_eq!(recorder.load(url).unwrap(), r#"{"type": "string"}"#);
+        assert!(recorder.fixture_path(url).exists());
+
+        let replayer =
+            RecordReplayLoader::with_loader(dir.path(), FixtureMode::Replay, static_loader());
+        assert_eq!(replayer.load(url).unwrap(), r#"{"type": "string"}"#);
+        assert_eq!(replayer.inner.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_replay_without_fixture_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let replayer =
+            RecordReplayLoader::with_loader(dir.path(), FixtureMode::Replay, static_loader());
+        assert!(matches!(
+            replayer.load("https://example.com/missing.json"),
+            Err(ValidationError::SchemaLoad(_))
+        ));
+    }
+
+    #[test]
+    fn test_auto_records_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let loader =
+            RecordReplayLoader::with_loader(dir.path(), FixtureMode::Auto, static_loader());
+        loader.load("https://example.com/schema.json").unwrap();
+        loader.load("https://example.com/schema.json").unwrap();
+        assert_eq!(loader.inner.calls.load(Ordering::SeqCst), 1);
+    }
+}
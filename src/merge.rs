@@ -0,0 +1,310 @@
+//! Combining layered schemas into one.
+//!
+//! Organizations often layer schemas: an org-wide base, a product schema and
+//! a team's additions. [`merge_schemas`] composes the layers with `allOf`, so
+//! a document is valid exactly when it satisfies every layer, and the result
+//! can be passed to [`Validator::from_value`](crate::Validator::from_value).
+//!
+//! Local references such as `#/definitions/port` are rewritten to point into
+//! the layer's new location, and the first layer's `$schema` is moved to the
+//! top. With [`MergeOptions::flatten`], layers that only list `properties`
+//! and `required` are combined into a single object schema, which reads
+//! better in generated documentation and error messages.
+
+use crate::walk::{self, subschemas};
+use serde_json::{Map, Value};
+
+/// Keywords a layer may use and still be flattened.
+const FLATTENABLE_KEYWORDS: &[&str] = &["type", "properties", "required", "title", "description"];
+
+/// Settings for [`merge_schemas_with`].
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// Combine layers that only use `type: object`, `properties`, `required`,
+    /// `title` and `description` into one schema instead of `allOf` members.
+    /// The last layer's `title` and `description` are kept.
+    pub flatten: bool,
+}
+
+/// Compose `schemas` into one schema that accepts what all of them accept.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::{merge_schemas, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let base = json!({"required": ["owner"]});
+/// let team = json!({"properties": {"replicas": {"type": "integer"}}});
+///
+/// let merged = merge_schemas(&[base, team]);
+/// assert_eq!(merged["allOf"].as_array().map(Vec::len), Some(2));
+///
+/// let validator = Validator::from_value(&merged)?;
+/// assert!(validator.is_valid_value(&json!({"owner": "web", "replicas": 2})));
+/// assert!(!validator.is_valid_value(&json!({"replicas": 2})));
+/// # Ok(())
+/// # }
+/// ```
+pub fn merge_schemas(schemas: &[Value]) -> Value {
+    merge_schemas_with(schemas, &MergeOptions::default())
+}
+
+/// Compose `schemas` into one schema, as [`merge_schemas`] does, with the
+/// given options.
+pub fn merge_schemas_with(schemas: &[Value], options: &MergeOptions) -> Value {
+    let mut dialect = None;
+    let mut layers = Vec::new();
+    for schema in schemas {
+        let mut schema = schema.clone();
+        if let Some(object) = schema.as_object_mut() {
+            if let Some(uri) = object.remove("$schema") {
+                dialect.get_or_insert(uri);
+            }
+            if object.is_empty() {
+                continue;
+            }
+        } else if schema == Value::Bool(true) {
+            continue;
+        }
+        layers.push(schema);
+    }
+
+    if options.flatten {
+        layers = flatten(layers);
+    }
+
+    let mut merged = match layers.len() {
+        0 => Value::Object(Map::new()),
+        1 => layers.remove(0),
+        _ => {
+            for (index, layer) in layers.iter_mut().enumerate() {
+                relocate_refs(layer, &format!("/allOf/{}", index));
+            }
+            let mut merged = Map::new();
+            merged.insert("allOf".to_string(), Value::Array(layers));
+            Value::Object(merged)
+        }
+    };
+    if let (Some(uri), Some(object)) = (dialect, merged.as_object_mut()) {
+        object.insert("$schema".to_string(), uri);
+    }
+    merged
+}
+
+/// Replace the flattenable layers with their combination, at the position
+/// of the first of them.
+fn flatten(layers: Vec<Value>) -> Vec<Value> {
+    let (simple, other): (Vec<Value>, Vec<Value>) =
+        layers.iter().cloned().partition(is_flattenable);
+    if simple.len() < 2 {
+        return layers;
+    }
+    let position = layers
+        .iter()
+        .position(is_flattenable)
+        .expect("two layers are flattenable");
+
+    let mut combined = Map::new();
+    let mut properties: Map<String, Value> = Map::new();
+    let mut required: Vec<Value> = Vec::new();
+    for layer in &simple {
+        for (keyword, value) in layer.as_object().expect("flattenable layers are objects") {
+            match keyword.as_str() {
+                "properties" => {
+                    let Some(entries) = value.as_object() else {
+                        continue;
+                    };
+                    for (name, property) in entries {
+                        match properties.get_mut(name) {
+                            None => {
+                                properties.insert(name.clone(), property.clone());
+                            }
+                            Some(existing) if existing == property => {}
+                            Some(existing) => {
+                                let mut all = match existing.take() {
+                                    Value::Object(mut object)
+                                        if object.len() == 1 && object.contains_key("allOf") =>
+                                    {
+                                        object.remove("allOf").expect("checked")
+                                    }
+                                    existing => Value::Array(vec![existing]),
+                                };
+                                if let Some(all) = all.as_array_mut() {
+                                    all.push(property.clone());
+                                }
+                                let mut conjunction = Map::new();
+                                conjunction.insert("allOf".to_string(), all);
+                                *existing = Value::Object(conjunction);
+                            }
+                        }
+                    }
+                }
+                "required" => {
+                    for name in value.as_array().into_iter().flatten() {
+                        if !required.contains(name) {
+                            required.push(name.clone());
+                        }
+                    }
+                }
+                _ => {
+                    combined.insert(keyword.clone(), value.clone());
+                }
+            }
+        }
+    }
+    if !properties.is_empty() {
+        combined.insert("properties".to_string(), Value::Object(properties));
+    }
+    if !required.is_empty() {
+        combined.insert("required".to_string(), Value::Array(required));
+    }
+
+    let mut layers = other;
+    layers.insert(position, Value::Object(combined));
+    layers
+}
+
+/// Whether `layer` can be combined with other such layers without changing
+/// what the merge accepts.
+fn is_flattenable(layer: &Value) -> bool {
+    let Some(object) = layer.as_object() else {
+        return false;
+    };
+    if !object
+        .keys()
+        .all(|keyword| FLATTENABLE_KEYWORDS.contains(&keyword.as_str()))
+    {
+        return false;
+    }
+    if object.get("type").is_some_and(|kind| kind != "object") {
+        return false;
+    }
+    // Local references would point somewhere else once the layer is dissolved
+    let mut local_refs = false;
+    walk::visit(layer, &mut |_, subschema| {
+        local_refs |= subschema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .is_some_and(|reference| reference.starts_with('#'));
+    });
+    !local_refs
+}
+
+/// Rewrite the JSON Pointer `$ref`s of `schema` for its new location at
+/// `prefix` in the merged document. Subschemas with their own `$id` are left
+/// alone, since their references resolve against that.
+fn relocate_refs(schema: &mut Value, prefix: &str) {
+    if is_resource(schema) {
+        return;
+    }
+    let mut pointers = Vec::new();
+    collect_refs(schema, String::new(), &mut pointers);
+    for pointer in pointers {
+        let Some(reference) = schema
+            .pointer_mut(&pointer)
+            .and_then(|subschema| subschema.get_mut("$ref"))
+        else {
+            continue;
+        };
+        if let Some(fragment) = reference.as_str().and_then(|r| r.strip_prefix('#')) {
+            if fragment.is_empty() || fragment.starts_with('/') {
+                *reference = Value::String(format!("#{}{}", prefix, fragment));
+            }
+        }
+    }
+}
+
+/// Pointers to the subschemas of `schema` with a `$ref`, outside nested
+/// resources.
+fn collect_refs(schema: &Value, pointer: String, pointers: &mut Vec<String>) {
+    if schema.get("$ref").is_some() {
+        pointers.push(pointer.clone());
+    }
+    for (relative, child) in subschemas(schema) {
+        if !is_resource(child) {
+            collect_refs(child, format!("{}{}", pointer, relative), pointers);
+        }
+    }
+}
+
+fn is_resource(schema: &Value) -> bool {
+    schema.get("$id").or_else(|| schema.get("id")).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_relocates_refs() {
+        let base = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "properties": {"port": {"$ref": "#/definitions/port"}},
+            "definitions": {"port": {"type": "integer", "maximum": 65535}}
+        });
+        let team = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "properties": {"children": {"items": {"$ref": "#"}}, "port": {"minimum": 1024}},
+            "required": ["port"]
+        });
+
+        let merged = merge_schemas(&[json!(true), base, json!({}), team]);
+        assert_eq!(merged["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(
+            merged["allOf"][0]["properties"]["port"]["$ref"],
+            "#/allOf/0/definitions/port"
+        );
+        assert_eq!(
+            merged["allOf"][1]["properties"]["children"]["items"]["$ref"],
+            "#/allOf/1"
+        );
+
+        let validator = Validator::from_value(&merged).unwrap();
+        assert!(validator.is_valid_value(&json!({"port": 8080, "children": [{"port": 2048}]})));
+        assert!(!validator.is_valid_value(&json!({"port": 80})));
+        assert!(!validator.is_valid_value(&json!({"port": 70000})));
+        assert!(!validator.is_valid_value(&json!({"port": 8080, "children": [{}]})));
+    }
+
+    #[test]
+    fn test_merge_flatten() {
+        let options = MergeOptions { flatten: true };
+        let org = json!({
+            "type": "object",
+            "title": "Service",
+            "properties": {"owner": {"type": "string"}},
+            "required": ["owner"]
+        });
+        let closed = json!({"additionalProperties": false, "properties": {"owner": true}});
+        let team = json!({
+            "title": "Web service",
+            "properties": {"owner": {"minLength": 3}, "replicas": {"type": "integer"}},
+            "required": ["replicas", "owner"]
+        });
+
+        assert_eq!(
+            merge_schemas_with(&[org.clone(), closed.clone(), team.clone()], &options),
+            json!({"allOf": [
+                {
+                    "type": "object",
+                    "title": "Web service",
+                    "properties": {
+                        "owner": {"allOf": [{"type": "string"}, {"minLength": 3}]},
+                        "replicas": {"type": "integer"}
+                    },
+                    "required": ["owner", "replicas"]
+                },
+                closed
+            ]})
+        );
+        assert_eq!(
+            merge_schemas_with(std::slice::from_ref(&org), &options),
+            org
+        );
+        assert_eq!(merge_schemas_with(&[], &options), json!({}));
+    }
+}
@@ -0,0 +1,375 @@
+//! Validating HTTP request and response bodies in a tower service stack.
+//!
+//! [`ValidationLayer`] wraps any [`Service`] over `http` requests, so it works
+//! with every framework built on tower rather than one in particular. Each
+//! request is looked up in a [`SchemaStore`] by method and path: on routes
+//! with a request schema the body is buffered and validated before the inner
+//! service sees it, and on routes with a response schema successful responses
+//! are validated before they are returned.
+//!
+//! A rejected request gets a JSON response: `422 Unprocessable Entity` listing
+//! the failures, `400 Bad Request` if the body is not JSON, or `413 Payload Too
+//! Large` if it exceeds the validator's document size limit. A successful
+//! response that fails its schema is a bug in the service, so it is replaced
+//! by `500 Internal Server Error` with the same details.
+//!
+//! Available with the `tower` feature.
+
+use crate::tail::{check_message, decode, MessageOutcome};
+use crate::{ValidationFailure, Validator};
+use bytes::Bytes;
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::{Method, Request, Response, StatusCode};
+use http_body::Body;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Schemas for request and response bodies, by route.
+///
+/// A route is a method and a path pattern whose segments are either literal
+/// or a `{name}` placeholder matching any one segment, e.g. `/users/{id}`.
+/// Routes are matched in the order they were first added, and the first
+/// match decides which schemas apply.
+///
+/// # Examples
+///
+/// ```rust
+/// use http::Method;
+/// use validate_json_schema::{SchemaStore, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let user = r#"{"type": "object", "required": ["name"]}"#;
+/// let store = SchemaStore::new()
+///     .request(Method::POST, "/users", Validator::new(user)?)
+///     .response(Method::GET, "/users/{id}", Validator::new(user)?);
+///
+/// assert!(store.request_schema(&Method::POST, "/users").is_some());
+/// assert!(store.response_schema(&Method::GET, "/users/42").is_some());
+/// assert!(store.request_schema(&Method::GET, "/users/42").is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaStore {
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    method: Method,
+    pattern: String,
+    request: Option<Arc<Validator>>,
+    response: Option<Arc<Validator>>,
+}
+
+impl SchemaStore {
+    /// Create a store with no routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate the bodies of `method` requests to `path` against `schema`.
+    pub fn request(mut self, method: Method, path: &str, schema: Validator) -> Self {
+        self.route(method, path).request = Some(Arc::new(schema));
+        self
+    }
+
+    /// Validate the bodies of successful responses to `method` requests to
+    /// `path` against `schema`.
+    pub fn response(mut self, method: Method, path: &str, schema: Validator) -> Self {
+        self.route(method, path).response = Some(Arc::new(schema));
+        self
+    }
+
+    /// The schema for the bodies of `method` requests to `path`, if any.
+    pub fn request_schema(&self, method: &Method, path: &str) -> Option<&Validator> {
+        self.find(method, path)?.request.as_deref()
+    }
+
+    /// The schema for the bodies of responses to `method` requests to
+    /// `path`, if any.
+    pub fn response_schema(&self, method: &Method, path: &str) -> Option<&Validator> {
+        self.find(method, path)?.response.as_deref()
+    }
+
+    fn route(&mut self, method: Method, pattern: &str) -> &mut Route {
+        let existing = self
+            .routes
+            .iter()
+            .position(|route| route.method == method && route.pattern == pattern);
+        let index = existing.unwrap_or_else(|| {
+            self.routes.push(Route {
+                method,
+                pattern: pattern.to_string(),
+                request: None,
+                response: None,
+            });
+            self.routes.len() - 1
+        });
+        &mut self.routes[index]
+    }
+
+    fn find(&self, method: &Method, path: &str) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|route| route.method == *method && matches_path(&route.pattern, path))
+    }
+}
+
+/// Whether `path` matches `pattern`, where `{name}` segments match any
+/// non-empty segment.
+fn matches_path(pattern: &str, path: &str) -> bool {
+    pattern.split('/').count() == path.split('/').count()
+        && pattern
+            .split('/')
+            .zip(path.split('/'))
+            .all(|(expected, actual)| {
+                expected == actual
+                    || (expected.starts_with('{') && expected.ends_with('}') && !actual.is_empty())
+            })
+}
+
+/// A [`Layer`] that validates bodies against the schemas in a [`SchemaStore`].
+///
+/// The wrapped service's request and response body types must be buildable
+/// from [`Bytes`], since validated bodies are buffered and handed on whole.
+#[derive(Debug, Clone)]
+pub struct ValidationLayer {
+    store: Arc<SchemaStore>,
+}
+
+impl ValidationLayer {
+    /// Validate bodies on the routes in `store`.
+    pub fn new(store: SchemaStore) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+}
+
+impl<S> Layer<S> for ValidationLayer {
+    type Service = ValidationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidationService {
+            inner,
+            store: self.store.clone(),
+        }
+    }
+}
+
+/// The service produced by [`ValidationLayer`].
+#[derive(Debug, Clone)]
+pub struct ValidationService<S> {
+    inner: S,
+    store: Arc<SchemaStore>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ValidationService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    ReqBody: Body + From<Bytes> + Send + 'static,
+    ReqBody::Data: Send,
+    ReqBody::Error: Into<BoxError>,
+    ResBody: Body + From<Bytes> + Send + 'static,
+    ResBody::Data: Send,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // Call the instance that was polled ready and keep the fresh clone
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let route = self.store.find(request.method(), request.uri().path());
+        let request_schema = route.and_then(|route| route.request.clone());
+        let response_schema = route.and_then(|route| route.response.clone());
+
+        Box::pin(async move {
+            let request = match request_schema {
+                Some(schema) => {
+                    let (parts, body) = request.into_parts();
+                    match check_body(&schema, body).await {
+                        Ok(bytes) => Request::from_parts(parts, ReqBody::from(bytes)),
+                        Err((status, problem)) => return Ok(rejection(status, &problem)),
+                    }
+                }
+                None => request,
+            };
+
+            let response = inner.call(request).await?;
+            match response_schema {
+                Some(schema) if response.status().is_success() => {
+                    let (parts, body) = response.into_parts();
+                    Ok(match check_body(&schema, body).await {
+                        Ok(bytes) => Response::from_parts(parts, ResBody::from(bytes)),
+                        Err((_, problem)) => rejection(StatusCode::INTERNAL_SERVER_ERROR, &problem),
+                    })
+                }
+                _ => Ok(response),
+            }
+        })
+    }
+}
+
+/// Buffer `body` and validate it, returning its bytes if it is valid or the
+/// status and JSON description of the problem.
+async fn check_body<B>(validator: &Validator, body: B) -> Result<Bytes, (StatusCode, Value)>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    // Stop reading at the size limit instead of buffering an oversized body
+    let limit = validator.limits.max_document_size.unwrap_or(usize::MAX);
+    let bytes = match Limited::new(body, limit).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(error) if error.is::<LengthLimitError>() => {
+            let message = format!("body is larger than the maximum of {} bytes", limit);
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, json!({ "error": message })));
+        }
+        Err(error) => {
+            let message = format!("failed to read body: {}", error);
+            return Err((StatusCode::BAD_REQUEST, json!({ "error": message })));
+        }
+    };
+
+    let outcome = match decode(bytes.to_vec()) {
+        Ok(message) => check_message(validator, &message),
+        Err(error) => MessageOutcome::Malformed(error),
+    };
+    match outcome {
+        MessageOutcome::Valid => Ok(bytes),
+        MessageOutcome::Invalid(failures) => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            json!({
+                "errors": failures.iter().map(ValidationFailure::to_json).collect::<Vec<_>>()
+            }),
+        )),
+        MessageOutcome::Malformed(error) => Err((
+            StatusCode::BAD_REQUEST,
+            json!({ "error": error.to_string() }),
+        )),
+    }
+}
+
+fn rejection<B: From<Bytes>>(status: StatusCode, problem: &Value) -> Response<B> {
+    let mut response = Response::new(B::from(Bytes::from(problem.to_string())));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::Full;
+    use std::future::ready;
+
+    /// Responds with the request body, or with `{}` for `/broken`.
+    #[derive(Clone)]
+    struct Echo;
+
+    impl Service<Request<Full<Bytes>>> for Echo {
+        type Response = Response<Full<Bytes>>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<Full<Bytes>>) -> Self::Future {
+            let body = if request.uri().path() == "/broken" {
+                Full::from(Bytes::from("{}"))
+            } else {
+                request.into_body()
+            };
+            ready(Ok(Response::new(body)))
+        }
+    }
+
+    /// Every future here is ready when first polled.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(std::task::Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    fn send(service: &mut ValidationService<Echo>, path: &str, body: &str) -> (u16, Value) {
+        let request = Request::post(path)
+            .body(Full::from(Bytes::from(body.to_string())))
+            .unwrap();
+        let response = block_on(service.call(request)).unwrap();
+        let status = response.status().as_u16();
+        let body = block_on(response.into_body().collect()).unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn test_validation_layer() {
+        let user = || {
+            Validator::builder()
+                .max_document_size(64)
+                .build_str(r#"{"type": "object", "required": ["name"]}"#)
+                .unwrap()
+        };
+        let store = SchemaStore::new()
+            .request(Method::POST, "/users/{id}", user())
+            .response(Method::POST, "/broken", user());
+        let mut service = ValidationLayer::new(store).layer(Echo);
+
+        let (status, body) = send(&mut service, "/users/1", r#"{"name": "Ada"}"#);
+        assert_eq!((status, body), (200, json!({"name": "Ada"})));
+
+        let (status, body) = send(&mut service, "/users/1", r#"{"age": 36}"#);
+        assert_eq!(status, 422);
+        assert_eq!(body["errors"][0]["keyword"], "required");
+
+        let (status, body) = send(&mut service, "/users/1", "{name: 'Ada'}");
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("must be quoted"));
+
+        let (status, _) = send(&mut service, "/users/1", &format!("[{}]", "0,".repeat(40)));
+        assert_eq!(status, 413);
+
+        // Routes without a request schema pass through unchecked
+        assert_eq!(send(&mut service, "/users", "[]"), (200, json!([])));
+        assert_eq!(send(&mut service, "/users/1/posts", "[]"), (200, json!([])));
+
+        let (status, body) = send(&mut service, "/broken", "[]");
+        assert_eq!(status, 500);
+        assert_eq!(body["errors"][0]["instance_path"], "");
+    }
+
+    #[test]
+    fn test_matches_path() {
+        assert!(matches_path("/users/{id}", "/users/42"));
+        assert!(matches_path("/", "/"));
+        assert!(!matches_path("/users/{id}", "/users/"));
+        assert!(!matches_path("/users/{id}", "/users/42/posts"));
+        assert!(!matches_path("/users", "/accounts"));
+    }
+}
@@ -0,0 +1,369 @@
+//! Mechanical migration of schemas between JSON Schema drafts.
+//!
+//! Only rewrites with an unambiguous equivalent are applied. Constructs whose
+//! meaning changed between drafts are left in place and reported as notes so
+//! the schema author can review them by hand.
+
+use crate::walk::{
+    escape_token, ARRAY_SCHEMA_KEYWORDS, MAP_SCHEMA_KEYWORDS, SINGLE_SCHEMA_KEYWORDS,
+};
+use crate::{Draft, ValidationError};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// A construct that could not be migrated mechanically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationNote {
+    /// JSON Pointer to the subschema in the migrated schema.
+    pub pointer: String,
+    /// What needs attention.
+    pub message: String,
+}
+
+impl fmt::Display for MigrationNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+/// The result of migrating a schema.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// The rewritten schema.
+    pub schema: Value,
+    /// Constructs that need manual attention.
+    pub notes: Vec<MigrationNote>,
+}
+
+/// Rewrite `schema` from draft `from` to draft `to`.
+///
+/// Applied rewrites:
+///
+/// - Draft 6: `id` becomes `$id`; boolean `exclusiveMinimum`/`exclusiveMaximum`
+///   are folded into numeric bounds.
+/// - Draft 2019-09: `definitions` becomes `$defs` (local `$ref`s are updated);
+///   `dependencies` is split into `dependentRequired` and `dependentSchemas`.
+/// - Draft 2020-12: array-form `items` becomes `prefixItems` and
+///   `additionalItems` becomes `items`.
+///
+/// # Errors
+///
+/// Returns an error when `to` is older than `from`; downgrades are not supported.
+pub fn migrate_schema(
+    schema: &Value,
+    from: Draft,
+    to: Draft,
+) -> Result<Migration, ValidationError> {
+    if to < from {
+        return Err(ValidationError::UnsupportedMigration(format!(
+            "cannot migrate from {} down to {}",
+            from, to
+        )));
+    }
+
+    let mut migrated = schema.clone();
+    let mut notes = Vec::new();
+    migrate_node(&mut migrated, "", from, to, &mut notes);
+
+    if from != to {
+        if let Some(object) = migrated.as_object_mut() {
+            object.insert(
+                "$schema".to_string(),
+                Value::String(to.meta_schema_uri().to_string()),
+            );
+        }
+    }
+
+    Ok(Migration {
+        schema: migrated,
+        notes,
+    })
+}
+
+fn note(notes: &mut Vec<MigrationNote>, pointer: &str, message: impl Into<String>) {
+    notes.push(MigrationNote {
+        pointer: pointer.to_string(),
+        message: message.into(),
+    });
+}
+
+fn migrate_node(
+    node: &mut Value,
+    pointer: &str,
+    from: Draft,
+    to: Draft,
+    notes: &mut Vec<MigrationNote>,
+) {
+    let object = match node.as_object_mut() {
+        Some(object) => object,
+        None => return,
+    };
+    let crosses = |draft: Draft| from < draft && to >= draft;
+
+    if crosses(Draft::Draft6) {
+        migrate_to_draft6(object, pointer, notes);
+    }
+    if crosses(Draft::Draft201909) {
+        migrate_to_draft201909(object, pointer, notes);
+    }
+    if crosses(Draft::Draft202012) {
+        migrate_to_draft202012(object, pointer, notes);
+    }
+
+    for (keyword, value) in object.iter_mut() {
+        let base = format!("{}/{}", pointer, escape_token(keyword));
+        match value {
+            Value::Object(_) if SINGLE_SCHEMA_KEYWORDS.contains(&keyword.as_str()) => {
+                migrate_node(value, &base, from, to, notes);
+            }
+            Value::Array(items) if ARRAY_SCHEMA_KEYWORDS.contains(&keyword.as_str()) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    migrate_node(item, &format!("{}/{}", base, index), from, to, notes);
+                }
+            }
+            Value::Object(entries) if MAP_SCHEMA_KEYWORDS.contains(&keyword.as_str()) => {
+                for (name, entry) in entries.iter_mut() {
+                    let child = format!("{}/{}", base, escape_token(name));
+                    migrate_node(entry, &child, from, to, notes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn migrate_to_draft6(
+    object: &mut Map<String, Value>,
+    pointer: &str,
+    notes: &mut Vec<MigrationNote>,
+) {
+    if matches!(object.get("id"), Some(Value::String(_))) {
+        if let Some(id) = object.remove("id") {
+            object.insert("$id".to_string(), id);
+        }
+    }
+
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        match object.get(exclusive) {
+            Some(Value::Bool(true)) => match object.remove(bound) {
+                Some(limit) => {
+                    object.insert(exclusive.to_string(), limit);
+                }
+                None => {
+                    object.remove(exclusive);
+                    note(
+                        notes,
+                        pointer,
+                        format!(
+                            "`{}: true` without `{}` had no effect and was removed",
+                            exclusive, bound
+                        ),
+                    );
+                }
+            },
+            Some(Value::Bool(false)) => {
+                object.remove(exclusive);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn migrate_to_draft201909(
+    object: &mut Map<String, Value>,
+    pointer: &str,
+    notes: &mut Vec<MigrationNote>,
+) {
+    if object.contains_key("definitions") {
+        if object.contains_key("$defs") {
+            note(
+                notes,
+                pointer,
+                "both `definitions` and `$defs` are present; merge them manually",
+            );
+        } else if let Some(definitions) = object.remove("definitions") {
+            object.insert("$defs".to_string(), definitions);
+        }
+    }
+
+    if let Some(Value::String(reference)) = object.get_mut("$ref") {
+        if let Some(rest) = reference.strip_prefix("#/definitions/") {
+            *reference = format!("#/$defs/{}", rest);
+        } else if reference.contains("#/definitions/") {
+            note(
+                notes,
+                pointer,
+                format!(
+                    "`$ref` \"{}\" points into another document's `definitions`; update it if that document is migrated too",
+                    reference
+                ),
+            );
+        }
+    }
+
+    if object.contains_key("$ref") {
+        let siblings: Vec<&str> = object
+            .keys()
+            .map(String::as_str)
+            .filter(|key| {
+                !matches!(
+                    *key,
+                    "$ref"
+                        | "$id"
+                        | "$schema"
+                        | "$comment"
+                        | "$defs"
+                        | "definitions"
+                        | "title"
+                        | "description"
+                )
+            })
+            .collect();
+        if !siblings.is_empty() {
+            note(
+                notes,
+                pointer,
+                format!(
+                    "keywords next to `$ref` ({}) were ignored before 2019-09 and are now applied",
+                    siblings.join(", ")
+                ),
+            );
+        }
+    }
+
+    if let Some(Value::Object(dependencies)) = object.remove("dependencies") {
+        let mut required = Map::new();
+        let mut schemas = Map::new();
+        for (name, dependency) in dependencies {
+            if dependency.is_array() {
+                required.insert(name, dependency);
+            } else {
+                schemas.insert(name, dependency);
+            }
+        }
+        if !required.is_empty() {
+            object.insert("dependentRequired".to_string(), Value::Object(required));
+        }
+        if !schemas.is_empty() {
+            object.insert("dependentSchemas".to_string(), Value::Object(schemas));
+        }
+    }
+}
+
+fn migrate_to_draft202012(
+    object: &mut Map<String, Value>,
+    pointer: &str,
+    notes: &mut Vec<MigrationNote>,
+) {
+    if matches!(object.get("items"), Some(Value::Array(_))) {
+        if let Some(items) = object.remove("items") {
+            object.insert("prefixItems".to_string(), items);
+        }
+        if let Some(additional) = object.remove("additionalItems") {
+            object.insert("items".to_string(), additional);
+        }
+    } else if object.remove("additionalItems").is_some() {
+        note(
+            notes,
+            pointer,
+            "`additionalItems` without an `items` array had no effect and was removed",
+        );
+    }
+
+    for keyword in ["$recursiveRef", "$recursiveAnchor"] {
+        if object.contains_key(keyword) {
+            note(
+                notes,
+                pointer,
+                format!(
+                    "`{}` has no direct 2020-12 equivalent; rewrite it with `$dynamicRef`/`$dynamicAnchor`",
+                    keyword
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_draft4_to_draft7() {
+        let schema = json!({
+            "id": "https://example.com/schema",
+            "properties": {
+                "id": {"type": "string"},
+                "count": {"minimum": 0, "exclusiveMinimum": true},
+                "ratio": {"maximum": 1, "exclusiveMaximum": false}
+            }
+        });
+
+        let migration = migrate_schema(&schema, Draft::Draft4, Draft::Draft7).unwrap();
+        assert_eq!(
+            migration.schema,
+            json!({
+                "$schema": "http://json-schema.org/draft-07/schema#",
+                "$id": "https://example.com/schema",
+                "properties": {
+                    "id": {"type": "string"},
+                    "count": {"exclusiveMinimum": 0},
+                    "ratio": {"maximum": 1}
+                }
+            })
+        );
+        assert!(migration.notes.is_empty());
+    }
+
+    #[test]
+    fn test_draft7_to_2020_12() {
+        let schema = json!({
+            "definitions": {"name": {"type": "string"}},
+            "properties": {
+                "name": {"$ref": "#/definitions/name"},
+                "pair": {"items": [{"type": "string"}, {"type": "integer"}], "additionalItems": false},
+                "list": {"items": {"type": "string"}, "additionalItems": false}
+            },
+            "dependencies": {"a": ["b"], "c": {"required": ["d"]}}
+        });
+
+        let migration = migrate_schema(&schema, Draft::Draft7, Draft::Draft202012).unwrap();
+        assert_eq!(
+            migration.schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$defs": {"name": {"type": "string"}},
+                "properties": {
+                    "name": {"$ref": "#/$defs/name"},
+                    "pair": {"prefixItems": [{"type": "string"}, {"type": "integer"}], "items": false},
+                    "list": {"items": {"type": "string"}}
+                },
+                "dependentRequired": {"a": ["b"]},
+                "dependentSchemas": {"c": {"required": ["d"]}}
+            })
+        );
+        assert_eq!(migration.notes.len(), 1);
+        assert_eq!(migration.notes[0].pointer, "/properties/list");
+    }
+
+    #[test]
+    fn test_ref_siblings_are_reported() {
+        let schema = json!({"$ref": "#/definitions/a", "type": "object", "definitions": {"a": {}}});
+        let migration = migrate_schema(&schema, Draft::Draft7, Draft::Draft201909).unwrap();
+        assert_eq!(migration.notes.len(), 1);
+        assert!(migration.notes[0].message.contains("type"));
+    }
+
+    #[test]
+    fn test_downgrade_is_rejected() {
+        let result = migrate_schema(&json!({}), Draft::Draft202012, Draft::Draft7);
+        assert!(matches!(
+            result,
+            Err(ValidationError::UnsupportedMigration(_))
+        ));
+    }
+}
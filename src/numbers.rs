@@ -0,0 +1,310 @@
+//! Exact numeric keywords.
+//!
+//! The validation engine compares numbers as `f64`, which rounds integers
+//! beyond 2^53 and decimals with more than 17 significant digits, so
+//! `multipleOf` and range checks on them can go wrong either way: `2^53 + 1`
+//! passes `"multipleOf": 2`. [`exact_keyword`] implements those keywords on
+//! the decimal text of the numbers instead. Documents keep numbers beyond
+//! 64-bit integers as written only when built with the `arbitrary-precision`
+//! feature, which turns on `serde_json`'s; otherwise they are rounded to the
+//! nearest `f64` when parsed.
+
+use jsonschema::paths::{LazyLocation, Location};
+use num_bigint::{BigInt, Sign};
+use num_traits::{Signed, Zero};
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+/// The keywords [`exact_keyword`] implements.
+pub(crate) const EXACT_KEYWORDS: &[&str] = &[
+    "multipleOf",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+];
+
+/// A decimal number, `mantissa × 10^exponent`, with no trailing zeros in
+/// the mantissa so that each number has one representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    mantissa: BigInt,
+    exponent: i64,
+    /// Number of digits of the mantissa.
+    digits: i64,
+}
+
+impl Decimal {
+    /// Parse the JSON number `text`, such as `-12.5e-3`.
+    pub(crate) fn parse(text: &str) -> Option<Decimal> {
+        let (significand, exponent) = match text.find(['e', 'E']) {
+            Some(at) => (&text[..at], exponent(&text[at + 1..])?),
+            None => (text, 0),
+        };
+        let (integer, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+        let mantissa: BigInt = format!("{}{}", integer, fraction).parse().ok()?;
+        Some(Self::new(
+            mantissa,
+            exponent.saturating_sub(fraction.len() as i64),
+        ))
+    }
+
+    /// The exact value of `number` as `serde_json` holds it.
+    pub(crate) fn from_number(number: &Number) -> Option<Decimal> {
+        Self::parse(&number.to_string())
+    }
+
+    fn new(mut mantissa: BigInt, mut exponent: i64) -> Decimal {
+        let ten = BigInt::from(10);
+        if mantissa.is_zero() {
+            exponent = 0;
+        }
+        while !mantissa.is_zero() && (&mantissa % &ten).is_zero() {
+            mantissa /= &ten;
+            exponent = exponent.saturating_add(1);
+        }
+        let digits = mantissa.magnitude().to_string().len() as i64;
+        Decimal {
+            mantissa,
+            exponent,
+            digits,
+        }
+    }
+
+    /// Whether dividing by `divisor` leaves an integer.
+    pub(crate) fn is_multiple_of(&self, divisor: &Decimal) -> bool {
+        if divisor.mantissa.is_zero() {
+            return false;
+        }
+        if self.mantissa.is_zero() {
+            return true;
+        }
+        // Without trailing zeros, the mantissa is never a multiple of 10
+        if self.exponent < divisor.exponent {
+            return false;
+        }
+        let modulus = divisor.mantissa.abs();
+        let scale =
+            BigInt::from(10).modpow(&(BigInt::from(self.exponent) - divisor.exponent), &modulus);
+        ((self.mantissa.abs() % &modulus) * scale % &modulus).is_zero()
+    }
+
+    /// Compare the absolute values of two non-zero numbers.
+    fn cmp_magnitude(&self, other: &Decimal) -> Ordering {
+        let order = (self.digits.saturating_add(self.exponent))
+            .cmp(&other.digits.saturating_add(other.exponent));
+        if order != Ordering::Equal {
+            return order;
+        }
+        // Of the same order of magnitude, the exponents differ by fewer
+        // places than the mantissas have digits
+        let shift = |mantissa: &BigInt, places: i64| {
+            mantissa.abs() * BigInt::from(10).pow(places.unsigned_abs() as u32)
+        };
+        match self.exponent.cmp(&other.exponent) {
+            Ordering::Greater => {
+                shift(&self.mantissa, self.exponent - other.exponent).cmp(&other.mantissa.abs())
+            }
+            Ordering::Less => self
+                .mantissa
+                .abs()
+                .cmp(&shift(&other.mantissa, other.exponent - self.exponent)),
+            Ordering::Equal => self.mantissa.abs().cmp(&other.mantissa.abs()),
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = self.mantissa.sign().cmp(&other.mantissa.sign());
+        match (sign, self.mantissa.sign()) {
+            (Ordering::Equal, Sign::NoSign) => Ordering::Equal,
+            (Ordering::Equal, Sign::Minus) => self.cmp_magnitude(other).reverse(),
+            (Ordering::Equal, Sign::Plus) => self.cmp_magnitude(other),
+            (order, _) => order,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Parse an exponent, saturating those too large for any document to
+/// reach a different result.
+fn exponent(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let magnitude = digits
+        .parse::<i64>()
+        .unwrap_or(i64::MAX / 4)
+        .min(i64::MAX / 4);
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// What one occurrence of a numeric keyword requires of a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    MultipleOf,
+    Minimum,
+    Maximum,
+    ExclusiveMinimum,
+    ExclusiveMaximum,
+}
+
+/// A numeric keyword compiled for one occurrence in a schema.
+struct ExactKeyword {
+    /// The requirement and its operand, or `None` for Draft 4's boolean
+    /// `exclusiveMinimum`/`exclusiveMaximum`, which `minimum`/`maximum`
+    /// apply.
+    check: Option<(Bound, Decimal)>,
+    limit: Value,
+    location: Location,
+}
+
+/// Compile `keyword`, one of [`EXACT_KEYWORDS`], with `value` in the schema
+/// object `parent`.
+pub(crate) fn exact_keyword(
+    parent: &Map<String, Value>,
+    keyword: &str,
+    value: &Value,
+    location: Location,
+) -> Box<dyn jsonschema::Keyword> {
+    let exclusive = |flag: &str| parent.get(flag) == Some(&Value::Bool(true));
+    let bound = match keyword {
+        "multipleOf" => Bound::MultipleOf,
+        "minimum" if exclusive("exclusiveMinimum") => Bound::ExclusiveMinimum,
+        "minimum" => Bound::Minimum,
+        "maximum" if exclusive("exclusiveMaximum") => Bound::ExclusiveMaximum,
+        "maximum" => Bound::Maximum,
+        "exclusiveMinimum" => Bound::ExclusiveMinimum,
+        _ => Bound::ExclusiveMaximum,
+    };
+    let operand = value.as_number().and_then(Decimal::from_number);
+    Box::new(ExactKeyword {
+        check: operand.map(|operand| (bound, operand)),
+        limit: value.clone(),
+        location,
+    })
+}
+
+impl ExactKeyword {
+    /// The failure message for `instance`, if it does not satisfy the keyword.
+    fn failure(&self, instance: &Value) -> Option<String> {
+        let (bound, operand) = self.check.as_ref()?;
+        let number = Decimal::from_number(instance.as_number()?)?;
+        let (passes, relation) = match bound {
+            Bound::MultipleOf => (number.is_multiple_of(operand), "not a multiple of"),
+            Bound::Minimum => (number >= *operand, "less than the minimum of"),
+            Bound::Maximum => (number <= *operand, "greater than the maximum of"),
+            Bound::ExclusiveMinimum => (number > *operand, "less than or equal to the minimum of"),
+            Bound::ExclusiveMaximum => {
+                (number < *operand, "greater than or equal to the maximum of")
+            }
+        };
+        (!passes).then(|| format!("{} is {} {}", instance, relation, self.limit))
+    }
+}
+
+impl jsonschema::Keyword for ExactKeyword {
+    fn validate<'i>(
+        &self,
+        instance: &'i Value,
+        location: &LazyLocation,
+    ) -> Result<(), jsonschema::ValidationError<'i>> {
+        match self.failure(instance) {
+            Some(message) => Err(jsonschema::ValidationError::custom(
+                self.location.clone(),
+                location.into(),
+                instance,
+                message,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn is_valid(&self, instance: &Value) -> bool {
+        self.failure(instance).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_decimal_order() {
+        assert_eq!(decimal("1.50"), decimal("15e-1"));
+        assert_eq!(decimal("0.0"), decimal("-0"));
+        assert!(decimal("9007199254740993") > decimal("9007199254740992"));
+        assert!(decimal("0.30000000000000000001") > decimal("0.3"));
+        assert!(decimal("-1e400") < decimal("-9.99e399"));
+        assert!(decimal("1e-400") > decimal("0"));
+        assert_eq!(
+            decimal("123456789012345678901234567890"),
+            decimal("1.2345678901234567890123456789e29")
+        );
+        assert!(decimal("1e99999999999999999999") > decimal("1e400"));
+        assert!(Decimal::parse("1e").is_none());
+    }
+
+    #[test]
+    fn test_multiple_of() {
+        assert!(!decimal("9007199254740993").is_multiple_of(&decimal("2")));
+        assert!(decimal("18014398509481986").is_multiple_of(&decimal("2")));
+        assert!(decimal("0.3").is_multiple_of(&decimal("0.1")));
+        assert!(!decimal("0.30000000000000000001").is_multiple_of(&decimal("0.1")));
+        assert!(!decimal("1e400").is_multiple_of(&decimal("7e399")));
+        assert!(decimal("7e400").is_multiple_of(&decimal("0.7")));
+        assert!(decimal("-12").is_multiple_of(&decimal("4")));
+        assert!(decimal("0").is_multiple_of(&decimal("0.01")));
+        assert!(!decimal("1").is_multiple_of(&decimal("0")));
+    }
+
+    #[test]
+    fn test_exact_keywords() {
+        let validator = |schema: &str| {
+            crate::Validator::builder()
+                .exact_numbers(true)
+                .build_str(schema)
+                .unwrap()
+        };
+        let maximum = validator(r#"{"maximum": 9007199254740992}"#);
+        assert!(maximum.validate_json("9007199254740992").is_ok());
+        assert_eq!(
+            maximum
+                .validate_json("9007199254740993")
+                .unwrap_err()
+                .to_string(),
+            "Validation failed: root: 9007199254740993 is greater than the maximum of \
+             9007199254740992"
+        );
+        let draft4 = validator(
+            r#"{"$schema": "http://json-schema.org/draft-04/schema#",
+                "minimum": 1, "exclusiveMinimum": true}"#,
+        );
+        assert!(draft4.validate_json("1").is_err());
+        assert!(draft4.validate_json("1.5").is_ok());
+        #[cfg(feature = "arbitrary-precision")]
+        {
+            let cents = validator(r#"{"multipleOf": 0.01, "exclusiveMaximum": 1e30}"#);
+            assert!(cents.validate_json("12345678901234567890.12").is_ok());
+            assert!(cents.validate_json("12345678901234567890.123").is_err());
+            assert!(cents
+                .validate_json("1000000000000000000000000000000")
+                .is_err());
+        }
+    }
+}
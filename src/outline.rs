@@ -0,0 +1,275 @@
+//! Human-readable outlines of what a schema requires.
+//!
+//! [`outline_schema`] flattens a schema into a tree of fields, resolving local
+//! `$ref`s and merging `allOf`, so the required and optional fields of a valid
+//! document can be read at a glance.
+
+use crate::refs;
+use crate::walk::sorted_entries;
+use serde_json::Value;
+use std::fmt;
+
+/// One field in a schema outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineField {
+    /// Property name, `[]` for array items, or e.g. `oneOf variant 1` for branches.
+    pub name: String,
+    /// Nesting level, 0 for top-level fields.
+    pub depth: usize,
+    /// The allowed types, e.g. `string` or `string | null`; `any` if unconstrained.
+    pub types: String,
+    /// Whether the field must be present.
+    pub required: bool,
+    /// Constraints on the value, e.g. `pattern ^[a-z]+$` or `min 1`.
+    pub constraints: Vec<String>,
+}
+
+/// The fields of a schema, in depth-first order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Outline {
+    /// Every field, with nested fields directly after their parent.
+    pub fields: Vec<OutlineField>,
+}
+
+impl fmt::Display for Outline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            let marker = if field.required { "[x]" } else { "[ ]" };
+            write!(
+                f,
+                "{}{} {}: {}",
+                "  ".repeat(field.depth),
+                marker,
+                field.name,
+                field.types
+            )?;
+            if !field.constraints.is_empty() {
+                write!(f, " ({})", field.constraints.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maximum nesting depth, which also stops recursive schemas.
+const MAX_DEPTH: usize = 16;
+
+/// Build the outline of `schema`.
+///
+/// Required fields are marked `[x]` and optional ones `[ ]` when displayed.
+/// Recursive definitions are expanded once.
+pub fn outline_schema(schema: &Value) -> Outline {
+    let mut builder = Builder {
+        root: schema,
+        fields: Vec::new(),
+        stack: Vec::new(),
+    };
+    builder.children(schema, 0);
+    Outline {
+        fields: builder.fields,
+    }
+}
+
+struct Builder<'a> {
+    root: &'a Value,
+    fields: Vec<OutlineField>,
+    /// Schemas being expanded, to stop at recursive references.
+    stack: Vec<*const Value>,
+}
+
+impl<'a> Builder<'a> {
+    /// Add the fields nested inside `schema`: properties, items and variants.
+    fn children(&mut self, schema: &'a Value, depth: usize) {
+        let schema = refs::deref(self.root, schema);
+        if depth > MAX_DEPTH || self.stack.contains(&(schema as *const Value)) {
+            return;
+        }
+        self.stack.push(schema);
+
+        let parts = self.merged(schema);
+        let required: Vec<&str> = parts
+            .iter()
+            .filter_map(|part| part.get("required").and_then(Value::as_array))
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+
+        let mut seen: Vec<&str> = Vec::new();
+        for part in &parts {
+            if let Some(Value::Object(properties)) = part.get("properties") {
+                for (name, property) in sorted_entries(properties) {
+                    if seen.contains(&name.as_str()) {
+                        continue;
+                    }
+                    seen.push(name);
+                    self.field(name, property, required.contains(&name.as_str()), depth);
+                }
+            }
+        }
+        // Required names without a property schema still have to be present
+        for name in &required {
+            if !seen.contains(name) {
+                seen.push(name);
+                self.field(name, &Value::Bool(true), true, depth);
+            }
+        }
+
+        for part in &parts {
+            if let Some(items) = part.get("items").filter(|items| items.is_object()) {
+                self.field("[]", items, false, depth);
+            }
+            for keyword in ["anyOf", "oneOf"] {
+                if let Some(Value::Array(variants)) = part.get(keyword) {
+                    for (index, variant) in variants.iter().enumerate() {
+                        let name = format!("{} variant {}", keyword, index + 1);
+                        self.field(&name, variant, false, depth);
+                    }
+                }
+            }
+        }
+
+        self.stack.pop();
+    }
+
+    fn field(&mut self, name: &str, schema: &'a Value, required: bool, depth: usize) {
+        let resolved = refs::deref(self.root, schema);
+        let parts = self.merged(resolved);
+        let mut constraints = constraints(&parts);
+        if self.stack.contains(&(resolved as *const Value)) {
+            constraints.push("recursive, see above".to_string());
+        }
+        self.fields.push(OutlineField {
+            name: name.to_string(),
+            depth,
+            types: types(&parts),
+            required,
+            constraints,
+        });
+        self.children(resolved, depth + 1);
+    }
+
+    /// `schema` together with its `allOf` branches, with references resolved.
+    fn merged(&self, schema: &'a Value) -> Vec<&'a Value> {
+        let mut parts = vec![refs::deref(self.root, schema)];
+        let mut index = 0;
+        while index < parts.len() && parts.len() < 64 {
+            if let Some(Value::Array(branches)) = parts[index].get("allOf") {
+                for branch in branches {
+                    parts.push(refs::deref(self.root, branch));
+                }
+            }
+            index += 1;
+        }
+        parts
+    }
+}
+
+fn types(parts: &[&Value]) -> String {
+    for part in parts {
+        match part.get("type") {
+            Some(Value::String(name)) => return name.clone(),
+            Some(Value::Array(names)) => {
+                let names: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+                return names.join(" | ");
+            }
+            _ => {}
+        }
+    }
+    if parts
+        .iter()
+        .any(|part| part.get("properties").is_some() || part.get("required").is_some())
+    {
+        return "object".to_string();
+    }
+    "any".to_string()
+}
+
+fn constraints(parts: &[&Value]) -> Vec<String> {
+    const BOUNDS: &[(&str, &str)] = &[
+        ("minimum", ">="),
+        ("exclusiveMinimum", ">"),
+        ("maximum", "<="),
+        ("exclusiveMaximum", "<"),
+        ("multipleOf", "multiple of"),
+        ("minLength", "min length"),
+        ("maxLength", "max length"),
+        ("minItems", "min items"),
+        ("maxItems", "max items"),
+        ("minProperties", "min properties"),
+        ("maxProperties", "max properties"),
+    ];
+
+    let mut constraints = Vec::new();
+    for part in parts {
+        if let Some(value) = part.get("const") {
+            constraints.push(format!("= {}", value));
+        }
+        if let Some(Value::Array(values)) = part.get("enum") {
+            let values: Vec<String> = values.iter().map(Value::to_string).collect();
+            constraints.push(format!("one of {}", values.join(", ")));
+        }
+        if let Some(Value::String(pattern)) = part.get("pattern") {
+            constraints.push(format!("pattern {}", pattern));
+        }
+        if let Some(Value::String(format)) = part.get("format") {
+            constraints.push(format!("format {}", format));
+        }
+        for (keyword, label) in BOUNDS {
+            if let Some(bound) = part.get(*keyword).filter(|bound| bound.is_number()) {
+                constraints.push(format!("{} {}", label, bound));
+            }
+        }
+        if part.get("uniqueItems") == Some(&Value::Bool(true)) {
+            constraints.push("unique items".to_string());
+        }
+        if part.get("additionalProperties") == Some(&Value::Bool(false)) {
+            constraints.push("no other properties".to_string());
+        }
+        if let Some(default) = part.get("default") {
+            constraints.push(format!("default {}", default));
+        }
+    }
+    constraints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_outline() {
+        let schema = json!({
+            "type": "object",
+            "required": ["services"],
+            "properties": {
+                "services": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {"$ref": "#/definitions/service"}
+                }
+            },
+            "definitions": {
+                "service": {
+                    "required": ["name"],
+                    "properties": {
+                        "name": {"type": "string", "pattern": "^[a-z]+$"},
+                        "children": {"type": "array", "items": {"$ref": "#/definitions/service"}}
+                    },
+                    "allOf": [{"properties": {"plan": {"enum": ["free", "pro"]}}}]
+                }
+            }
+        });
+
+        assert_eq!(
+            outline_schema(&schema).to_string(),
+            "[x] services: array (min items 1)\n\
+             \x20 [ ] []: object\n\
+             \x20   [ ] children: array\n\
+             \x20     [ ] []: object (recursive, see above)\n\
+             \x20   [x] name: string (pattern ^[a-z]+$)\n\
+             \x20   [ ] plan: any (one of \"free\", \"pro\")\n"
+        );
+    }
+}
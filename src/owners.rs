@@ -0,0 +1,290 @@
+//! Assigning files to their owners with a `CODEOWNERS` file.
+//!
+//! Each line of a `CODEOWNERS` file is a pattern followed by owners (`@user`,
+//! `@org/team` or an email address); the last pattern that matches a file
+//! decides its owners, and a pattern with no owners leaves the file unowned.
+//! Patterns follow the `gitignore`-like rules GitHub and GitLab use: a
+//! pattern with a `/` at the start or in the middle is relative to the
+//! repository root, otherwise it matches at any depth; a match on a
+//! directory covers everything beneath it, except that a trailing `/*`
+//! covers only the files directly inside; `*` and `?` stay within a path
+//! segment and `**` spans any number of them.
+
+use crate::ValidationError;
+use regex::Regex;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Where [`CodeOwners::discover`] looks for the file, relative to the
+/// repository root, in the order GitHub does.
+pub const CODEOWNERS_LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The rules of a `CODEOWNERS` file.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::owners::CodeOwners;
+///
+/// let owners = CodeOwners::parse(
+///     "* @org/platform\n/deploy/ @org/sre\n*.md\n",
+///     ".",
+/// );
+/// assert_eq!(owners.owners_of("deploy/web/values.yml"), ["@org/sre"]);
+/// assert_eq!(owners.owners_of("config/app.yml"), ["@org/platform"]);
+/// assert!(owners.owners_of("README.md").is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<(Regex, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Parse the `CODEOWNERS` file `content` of the repository at `root`.
+    /// Lines whose pattern cannot be read are ignored, as GitHub does.
+    pub fn parse(content: &str, root: impl Into<PathBuf>) -> Self {
+        let rules = content
+            .lines()
+            .filter_map(|line| {
+                let line = strip_comment(line);
+                let mut fields = split_fields(&line).into_iter();
+                let pattern = compile_pattern(&fields.next()?)?;
+                Some((pattern, fields.collect()))
+            })
+            .collect();
+        Self {
+            root: root.into(),
+            rules,
+        }
+    }
+
+    /// Read the `CODEOWNERS` file at `path`. The repository root is the
+    /// directory containing it, or its parent if that is `.github` or
+    /// `docs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ValidationError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let root = match directory.file_name().and_then(|name| name.to_str()) {
+            Some(".github" | "docs") => directory.parent().unwrap_or(Path::new("")),
+            _ => directory,
+        };
+        let root = if root.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            root
+        };
+        Ok(Self::parse(&content, root))
+    }
+
+    /// Find and read the `CODEOWNERS` file of the repository at `root`,
+    /// from [`CODEOWNERS_LOCATIONS`]. Returns `None` if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn discover(root: impl AsRef<Path>) -> Result<Option<Self>, ValidationError> {
+        let root = root.as_ref();
+        CODEOWNERS_LOCATIONS
+            .iter()
+            .map(|location| root.join(location))
+            .find(|path| path.is_file())
+            .map(Self::load)
+            .transpose()
+    }
+
+    /// The owners of the file at `path`, relative to the repository root
+    /// with `/` separators.
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        let path = path.trim_start_matches("./");
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.is_match(path))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+
+    /// The owners of the file at `path`, relative to the current directory
+    /// or absolute, which must be inside the repository.
+    pub fn owners_of_path(&self, path: &Path) -> &[String] {
+        match relative_to(path, &self.root) {
+            Some(relative) => self.owners_of(&relative),
+            None => &[],
+        }
+    }
+}
+
+/// `path` relative to `root` with `/` separators, if it is inside it.
+fn relative_to(path: &Path, root: &Path) -> Option<String> {
+    // Compare real paths where the files exist, so `..` and symlinks resolve
+    let absolute = |path: &Path| {
+        fs::canonicalize(path)
+            .ok()
+            .or_else(|| std::env::current_dir().ok().map(|cwd| cwd.join(path)))
+    };
+    let relative = match (absolute(path), absolute(root)) {
+        (Some(path), Some(root)) => path.strip_prefix(root).ok()?.to_path_buf(),
+        _ => path.to_path_buf(),
+    };
+    let segments: Vec<String> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(segment) => Some(segment.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    Some(segments.join("/"))
+}
+
+/// `line` without its comment; `\#` is a literal `#`.
+fn strip_comment(line: &str) -> String {
+    let mut kept = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                kept.push(c);
+                kept.extend(chars.next());
+            }
+            '#' => break,
+            c => kept.push(c),
+        }
+    }
+    kept
+}
+
+/// Split `line` at unescaped whitespace, unescaping `\ ` and `\#`.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ (' ' | '#')) => field.push(next),
+                Some(next) => {
+                    field.push('\\');
+                    field.push(next);
+                }
+                None => field.push('\\'),
+            },
+            c if c.is_whitespace() => {
+                if !field.is_empty() {
+                    fields.push(std::mem::take(&mut field));
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() {
+        fields.push(field);
+    }
+    fields
+}
+
+/// Compile a `CODEOWNERS` pattern into a regex over `/`-separated paths
+/// relative to the repository root.
+fn compile_pattern(pattern: &str) -> Option<Regex> {
+    if pattern.starts_with('!') || pattern.starts_with('[') {
+        // Negation and character ranges are not supported by GitHub either
+        return None;
+    }
+    let body = pattern.trim_matches('/');
+    if body.is_empty() {
+        return None;
+    }
+    let anchored = pattern.starts_with('/') || body.contains('/');
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut rest = body;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**/") {
+            regex.push_str("(?:[^/]*/)*");
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            regex.push_str(".*");
+            rest = after;
+            continue;
+        }
+        match c {
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '\\' => {
+                let escaped = rest[1..].chars().next().unwrap_or('\\');
+                regex.push_str(&regex::escape(&escaped.to_string()));
+                rest = &rest[escaped.len_utf8().min(rest.len() - 1)..];
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    // A directory covers everything beneath it, but `dir/*` only its files
+    if !body.ends_with("/*") {
+        regex.push_str("(?:/.*)?");
+    }
+    regex.push('$');
+    Regex::new(&regex).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owners_of() {
+        let owners = CodeOwners::parse(
+            "# Default owners\n\
+             *       @org/platform\n\
+             *.yml   @org/config  ops@example.com\n\
+             /deploy/ @org/sre # production\n\
+             docs/*  @org/docs\n\
+             **/secrets/** @org/security\n\
+             apps/ @org/apps\n\
+             /vendor/\n\
+             my\\ file.json @someone\n",
+            ".",
+        );
+        let of = |path: &str| owners.owners_of(path).to_vec();
+        assert_eq!(of("README.md"), ["@org/platform"]);
+        assert_eq!(of("config/app.yml"), ["@org/config", "ops@example.com"]);
+        assert_eq!(of("deploy/web/values.json"), ["@org/sre"]);
+        assert_eq!(of("src/deploy/values.json"), ["@org/platform"]);
+        assert_eq!(of("docs/index.json"), ["@org/docs"]);
+        assert_eq!(of("docs/api/index.json"), ["@org/platform"]);
+        assert_eq!(of("deploy/secrets/db.json"), ["@org/security"]);
+        assert_eq!(of("services/apps/web.json"), ["@org/apps"]);
+        assert!(of("vendor/lib/schema.json").is_empty());
+        assert_eq!(of("./my file.json"), ["@someone"]);
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".github")).unwrap();
+        fs::create_dir_all(dir.path().join("config")).unwrap();
+        fs::write(
+            dir.path().join(".github/CODEOWNERS"),
+            "/config/ @org/config\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("config/app.yml"), "a: 1\n").unwrap();
+
+        let owners = CodeOwners::discover(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            owners.owners_of_path(&dir.path().join("config/app.yml")),
+            ["@org/config"]
+        );
+        assert!(owners
+            .owners_of_path(&dir.path().join(".github/CODEOWNERS"))
+            .is_empty());
+        assert!(CodeOwners::discover(dir.path().join("config"))
+            .unwrap()
+            .is_none());
+    }
+}
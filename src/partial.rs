@@ -0,0 +1,481 @@
+//! Building a document piece by piece, validating finished parts early.
+//!
+//! A producer generating a large document can [`insert`](DocumentBuilder::insert)
+//! values as it produces them and [`complete`](DocumentBuilder::complete) each
+//! subtree once it is finished, to fail fast instead of discovering every
+//! problem only when the whole document exists.
+//!
+//! A completed subtree is checked against the subschemas that certainly apply
+//! to it: those reached from the root through `properties`,
+//! `patternProperties`, `additionalProperties`, `items`, `prefixItems`,
+//! `additionalItems`, `allOf` and local `$ref`s. Subschemas that apply only
+//! under a condition (`anyOf`, `oneOf`, `if`, ...) and keywords of the
+//! subtree's ancestors are left to [`finish`](DocumentBuilder::finish), so a
+//! subtree that passes early can still fail there, but a failure reported
+//! early never goes away.
+
+use crate::walk::escape_token;
+use crate::{
+    failures_to_result, refs, Draft, ValidationError, ValidationFailure, Validator,
+    ValidatorBuilder,
+};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Maximum number of `$ref` and `allOf` hops followed at one location.
+const MAX_DEPTH: usize = 32;
+
+/// Assembles a document from fragments, validating completed subtrees early.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::DocumentBuilder;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = json!({
+///     "required": ["name", "items"],
+///     "properties": {
+///         "name": {"type": "string"},
+///         "items": {"items": {"required": ["id"]}}
+///     }
+/// });
+/// let mut builder = DocumentBuilder::new(schema)?;
+///
+/// builder.insert("/name", json!("inventory"))?;
+/// builder.insert("/items/-/id", json!(1))?;
+/// builder.complete("/items/0")?;
+/// builder.insert("/items/-/label", json!("no id"))?;
+/// assert!(builder.complete("/items/1").is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DocumentBuilder {
+    schema: Value,
+    options: ValidatorBuilder,
+    validator: Validator,
+    document: Value,
+    completed: Vec<String>,
+    /// Compiled subschemas by schema pointer; `None` if compilation failed.
+    compiled: HashMap<String, Option<Validator>>,
+}
+
+impl DocumentBuilder {
+    /// Start an empty document for `schema`, compiled with the default options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value is not a valid JSON Schema.
+    pub fn new(schema: Value) -> Result<Self, ValidationError> {
+        Self::with_options(schema, ValidatorBuilder::new())
+    }
+
+    /// Start an empty document for `schema`, compiled with `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value is not a valid JSON Schema.
+    pub fn with_options(schema: Value, options: ValidatorBuilder) -> Result<Self, ValidationError> {
+        let validator = options.build(&schema)?;
+        Ok(Self {
+            schema,
+            options,
+            validator,
+            document: Value::Null,
+            completed: Vec::new(),
+            compiled: HashMap::new(),
+        })
+    }
+
+    /// Set the value at the JSON Pointer `pointer`.
+    ///
+    /// Missing parents are created as objects, or as arrays when the token
+    /// that follows is `-`, which appends an item. Array indexes must refer to
+    /// an existing item or be the array's length.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pointer` is malformed, passes through a scalar or
+    /// an out-of-range index, or lies inside a completed subtree.
+    pub fn insert(&mut self, pointer: &str, value: Value) -> Result<(), ValidationError> {
+        let tokens = parse_pointer(pointer)?;
+        let resolved = self.resolve(pointer, &tokens)?;
+        if let Some(completed) = self
+            .completed
+            .iter()
+            .find(|completed| contains(completed, &resolved))
+        {
+            return Err(ValidationError::InvalidPointer(format!(
+                "'{}' is inside the completed subtree '{}'",
+                pointer, completed
+            )));
+        }
+
+        let mut slot = &mut self.document;
+        for (index, token) in tokens.iter().enumerate() {
+            if slot.is_null() {
+                *slot = if token == "-" {
+                    Value::Array(Vec::new())
+                } else {
+                    Value::Object(Map::new())
+                };
+            }
+            slot = match slot {
+                Value::Object(entries) => entries.entry(token.as_str()).or_insert(Value::Null),
+                Value::Array(items) => {
+                    let position: usize = if token == "-" {
+                        items.len()
+                    } else {
+                        token.parse().map_err(|_| not_found(pointer, index))?
+                    };
+                    if position == items.len() {
+                        items.push(Value::Null);
+                    }
+                    items
+                        .get_mut(position)
+                        .ok_or_else(|| not_found(pointer, index))?
+                }
+                _ => return Err(not_found(pointer, index)),
+            };
+        }
+        *slot = value;
+        Ok(())
+    }
+
+    /// Mark the subtree at `pointer` as finished and validate it against the
+    /// subschemas that certainly apply to it.
+    ///
+    /// The subtree can no longer be changed. Completing the root (`""`)
+    /// validates the whole document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no value at `pointer`, or a validation
+    /// error describing every failure in the subtree.
+    pub fn complete(&mut self, pointer: &str) -> Result<(), ValidationError> {
+        let value = self.document.pointer(pointer).ok_or_else(|| {
+            ValidationError::InvalidPointer(format!("no value at '{}' in the document", pointer))
+        })?;
+        let draft = self.validator.draft();
+
+        let mut failures: Vec<ValidationFailure> = Vec::new();
+        for schema_pointer in applicable_subschemas(&self.schema, draft, &self.document, pointer) {
+            let validator =
+                self.compiled
+                    .entry(schema_pointer)
+                    .or_insert_with_key(|schema_pointer| {
+                        // Subschemas that cannot be compiled on their own, e.g.
+                        // with relative references, are only checked by `finish`
+                        self.options
+                            .build_subschema(&self.schema, schema_pointer, draft)
+                            .ok()
+                    });
+            if let Some(validator) = validator {
+                for mut failure in validator.iter_errors(value) {
+                    failure.instance_path = format!("{}{}", pointer, failure.instance_path);
+                    if !failures.contains(&failure) {
+                        failures.push(failure);
+                    }
+                }
+            }
+        }
+
+        self.completed
+            .retain(|completed| !contains(pointer, completed));
+        self.completed.push(pointer.to_string());
+        failures_to_result(&failures)
+    }
+
+    /// The document assembled so far; `null` until something is inserted.
+    pub fn document(&self) -> &Value {
+        &self.document
+    }
+
+    /// Validate the whole document and return it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the document fails the schema.
+    pub fn finish(self) -> Result<Value, ValidationError> {
+        self.validator.validate_value(&self.document)?;
+        Ok(self.document)
+    }
+
+    /// The pointer `tokens` will refer to once inserted, with `-` replaced by
+    /// the index it appends at.
+    fn resolve(&self, pointer: &str, tokens: &[String]) -> Result<String, ValidationError> {
+        let mut resolved = String::new();
+        let mut current = Some(&self.document);
+        for (index, token) in tokens.iter().enumerate() {
+            let token = match (current, token.as_str()) {
+                (Some(Value::Array(items)), "-") => items.len().to_string(),
+                (None | Some(Value::Null), "-") => "0".to_string(),
+                (Some(Value::Bool(_) | Value::Number(_) | Value::String(_)), _) => {
+                    return Err(not_found(pointer, index));
+                }
+                _ => token.clone(),
+            };
+            current = current.and_then(|value| match value {
+                Value::Object(entries) => entries.get(&token),
+                Value::Array(items) => token.parse().ok().and_then(|i: usize| items.get(i)),
+                _ => None,
+            });
+            resolved.push('/');
+            resolved.push_str(&escape_token(&token));
+        }
+        Ok(resolved)
+    }
+}
+
+/// Whether the location `inner` is `outer` or lies inside it.
+fn contains(outer: &str, inner: &str) -> bool {
+    inner
+        .strip_prefix(outer)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, ValidationError> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(ValidationError::InvalidPointer(format!(
+            "'{}' does not start with '/'",
+            pointer
+        )));
+    }
+    Ok(pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn not_found(pointer: &str, index: usize) -> ValidationError {
+    let parent: String = pointer
+        .split('/')
+        .take(index + 1)
+        .collect::<Vec<_>>()
+        .join("/");
+    ValidationError::InvalidPointer(format!(
+        "cannot insert '{}': no object or array item at '{}'",
+        pointer, parent
+    ))
+}
+
+/// Schema pointers of the subschemas that certainly apply to the value at
+/// `pointer` in `document`.
+pub(crate) fn applicable_subschemas(
+    root: &Value,
+    draft: Draft,
+    document: &Value,
+    pointer: &str,
+) -> Vec<String> {
+    let mut direct = vec![String::new()];
+    let mut instance = document;
+    for token in pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+    {
+        let mut expanded = Vec::new();
+        for schema_pointer in &direct {
+            expand(root, draft, schema_pointer, &mut expanded, 0);
+        }
+        direct = expanded
+            .iter()
+            .filter_map(|schema_pointer| Some((schema_pointer, root.pointer(schema_pointer)?)))
+            .flat_map(|(schema_pointer, schema)| {
+                children(schema, draft, instance, &token)
+                    .into_iter()
+                    .map(move |child| format!("{}/{}", schema_pointer, child))
+            })
+            .collect();
+        instance = match instance {
+            Value::Object(entries) => &entries[token.as_str()],
+            Value::Array(items) => token
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index))
+                .unwrap_or(&Value::Null),
+            _ => &Value::Null,
+        };
+    }
+    direct
+}
+
+/// Add the subschema at `schema_pointer` and those it applies in place
+/// through local `$ref`s and `allOf` to `out`.
+pub(crate) fn expand(
+    root: &Value,
+    draft: Draft,
+    schema_pointer: &str,
+    out: &mut Vec<String>,
+    depth: usize,
+) {
+    if depth > MAX_DEPTH || out.iter().any(|seen| seen == schema_pointer) {
+        return;
+    }
+    let schema = match root.pointer(schema_pointer) {
+        Some(Value::Object(schema)) => schema,
+        _ => return,
+    };
+    let reference = schema.get("$ref").and_then(Value::as_str);
+    if let Some(target) = reference.and_then(refs::local_pointer) {
+        expand(root, draft, &target, out, depth + 1);
+    }
+    // Up to Draft 7, `$ref` replaces the rest of its schema
+    let ref_overrides = matches!(draft, Draft::Draft4 | Draft::Draft6 | Draft::Draft7);
+    if reference.is_some() && ref_overrides {
+        return;
+    }
+    out.push(schema_pointer.to_string());
+    if let Some(Value::Array(branches)) = schema.get("allOf") {
+        for index in 0..branches.len() {
+            let branch = format!("{}/allOf/{}", schema_pointer, index);
+            expand(root, draft, &branch, out, depth + 1);
+        }
+    }
+}
+
+/// Relative pointers of the subschemas of `schema` that apply to the child
+/// `token` of `instance`.
+fn children(schema: &Value, draft: Draft, instance: &Value, token: &str) -> Vec<String> {
+    let mut children = Vec::new();
+    match instance {
+        Value::Object(_) => {
+            if schema
+                .get("properties")
+                .and_then(|p| p.get(token))
+                .is_some()
+            {
+                children.push(format!("properties/{}", escape_token(token)));
+            }
+            if let Some(Value::Object(patterns)) = schema.get("patternProperties") {
+                for pattern in patterns.keys() {
+                    if Regex::new(pattern).is_ok_and(|regex| regex.is_match(token)) {
+                        children.push(format!("patternProperties/{}", escape_token(pattern)));
+                    }
+                }
+            }
+            if children.is_empty() && schema.get("additionalProperties").is_some() {
+                children.push("additionalProperties".to_string());
+            }
+        }
+        Value::Array(_) => {
+            let index: usize = match token.parse() {
+                Ok(index) => index,
+                Err(_) => return children,
+            };
+            let (tuple, rest) = match (draft, schema.get("items")) {
+                (Draft::Draft202012, _) => (Some("prefixItems"), "items"),
+                (_, Some(Value::Array(_))) => (Some("items"), "additionalItems"),
+                _ => (None, "items"),
+            };
+            let tuple = tuple.and_then(|name| Some((name, schema.get(name)?.as_array()?)));
+            match tuple {
+                Some((name, tuple)) if index < tuple.len() => {
+                    children.push(format!("{}/{}", name, index))
+                }
+                _ if schema.get(rest).is_some() => children.push(rest.to_string()),
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["metadata", "services"],
+            "properties": {
+                "metadata": {"$ref": "#/definitions/metadata"},
+                "services": {
+                    "type": "array",
+                    "items": {
+                        "allOf": [{"required": ["name"]}],
+                        "properties": {"port": {"type": "integer"}}
+                    }
+                }
+            },
+            "anyOf": [{"properties": {"metadata": {"required": ["owner"]}}}],
+            "definitions": {
+                "metadata": {
+                    "type": "object",
+                    "required": ["name"],
+                    "additionalProperties": false,
+                    "properties": {"name": {"type": "string"}, "owner": {}}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_early_validation() {
+        let mut builder = DocumentBuilder::new(schema()).unwrap();
+        builder.insert("/metadata/name", json!("app")).unwrap();
+        // The `anyOf` at the root is conditional, so only `finish` checks it
+        builder.complete("/metadata").unwrap();
+
+        builder.insert("/services/-/name", json!("web")).unwrap();
+        builder.insert("/services/0/port", json!(80)).unwrap();
+        builder.complete("/services/0").unwrap();
+        builder.insert("/services/-/port", json!("http")).unwrap();
+        let error = builder.complete("/services/1").unwrap_err().to_string();
+        assert!(error.contains("2 validation errors"), "{}", error);
+        assert!(error.contains("/services/1: \"name\" is a required property"));
+        assert!(error.contains("/services/1/port: \"http\" is not of type \"integer\""));
+
+        assert_eq!(
+            builder.document(),
+            &json!({
+                "metadata": {"name": "app"},
+                "services": [{"name": "web", "port": 80}, {"port": "http"}]
+            })
+        );
+        let error = builder.finish().unwrap_err().to_string();
+        assert!(error.contains("anyOf") || error.contains("is not valid under any"));
+    }
+
+    #[test]
+    fn test_ref_and_additional_properties() {
+        let mut builder = DocumentBuilder::new(schema()).unwrap();
+        builder
+            .insert("/metadata", json!({"name": 1, "extra": true}))
+            .unwrap();
+        let error = builder.complete("/metadata").unwrap_err().to_string();
+        assert!(
+            error.contains("/metadata/name: 1 is not of type \"string\""),
+            "{}",
+            error
+        );
+        assert!(error.contains("'extra' was unexpected"), "{}", error);
+    }
+
+    #[test]
+    fn test_insert_errors() {
+        let mut builder = DocumentBuilder::new(json!({})).unwrap();
+        builder.insert("/a/b", json!(1)).unwrap();
+        builder.complete("/a").unwrap();
+        assert!(matches!(
+            builder.insert("/a/c", json!(2)),
+            Err(ValidationError::InvalidPointer(_))
+        ));
+        assert!(builder.insert("/a/b/c", json!(2)).is_err());
+        assert!(builder.insert("/list/-", json!(0)).is_ok());
+        assert!(builder.insert("/list/5", json!(0)).is_err());
+        assert!(builder.insert("no-slash", json!(0)).is_err());
+        assert!(builder.complete("/missing").is_err());
+        assert_eq!(
+            builder.finish().unwrap(),
+            json!({"a": {"b": 1}, "list": [0]})
+        );
+    }
+}
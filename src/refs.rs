@@ -0,0 +1,114 @@
+//! Helpers for following local `$ref`s inside a single schema document.
+
+use serde_json::Value;
+
+/// Maximum number of `$ref` hops followed before giving up on a cycle.
+const MAX_REF_DEPTH: usize = 32;
+
+/// Resolve a local reference such as `#/definitions/service` against `root`.
+///
+/// Returns `None` for references that point outside the document.
+pub(crate) fn resolve_local<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(&local_pointer(reference)?)
+}
+
+/// The decoded JSON Pointer of a local reference such as `#/definitions/service`.
+///
+/// Returns `None` for references that point outside the document.
+pub(crate) fn local_pointer(reference: &str) -> Option<String> {
+    reference.strip_prefix('#').map(percent_decode)
+}
+
+/// Follow `$ref` chains until reaching a schema without a local `$ref`.
+///
+/// Unresolvable or cyclic references leave the last schema reached in place.
+pub(crate) fn deref<'a>(root: &'a Value, mut schema: &'a Value) -> &'a Value {
+    for _ in 0..MAX_REF_DEPTH {
+        match schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| resolve_local(root, reference))
+        {
+            Some(target) => schema = target,
+            None => break,
+        }
+    }
+    schema
+}
+
+/// Percent-encode a JSON Pointer for use as a URI fragment.
+pub(crate) fn encode_fragment(pointer: &str) -> String {
+    let mut encoded = String::with_capacity(pointer.len());
+    for byte in pointer.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(byte as char),
+            b'-' | b'.' | b'_' | b'~' | b'/' | b'$' | b'!' | b'&' | b'\'' | b'(' | b')' | b'*'
+            | b'+' | b',' | b';' | b'=' | b':' | b'@' | b'?' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decode `%XX` escapes, which JSON Pointers in URI fragments may contain.
+pub(crate) fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_local() {
+        let root = json!({
+            "definitions": {
+                "name": {"type": "string"},
+                "alias": {"$ref": "#/definitions/name"},
+                "with space": {"type": "integer"}
+            }
+        });
+
+        assert_eq!(resolve_local(&root, "#"), Some(&root));
+        assert_eq!(
+            resolve_local(&root, "#/definitions/name"),
+            Some(&json!({"type": "string"}))
+        );
+        assert_eq!(
+            resolve_local(&root, "#/definitions/with%20space"),
+            Some(&json!({"type": "integer"}))
+        );
+        assert_eq!(resolve_local(&root, "other.json#/definitions/name"), None);
+
+        assert_eq!(
+            encode_fragment("/definitions/with space"),
+            "/definitions/with%20space"
+        );
+        assert_eq!(
+            resolve_local(
+                &root,
+                &format!("#{}", encode_fragment("/definitions/with space"))
+            ),
+            Some(&json!({"type": "integer"}))
+        );
+
+        let alias = &root["definitions"]["alias"];
+        assert_eq!(deref(&root, alias), &json!({"type": "string"}));
+    }
+}
@@ -0,0 +1,374 @@
+//! Schemas managed by a schema registry.
+//!
+//! Event pipelines often keep their schemas in a registry such as Confluent
+//! Schema Registry, addressed by subject and version instead of by URL.
+//! Schema inputs of the form `registry://<subject>/<version>` (or
+//! `registry://<subject>` for the latest version) are fetched from
+//! `<base>/subjects/<subject>/versions/<version>` on the configured
+//! [`RegistryClient`], the same path Confluent, Karapace and Apicurio's
+//! Confluent-compatible API use.
+//!
+//! Responses may be a Confluent-style envelope, whose `schema` field holds
+//! the schema as a string, or the schema itself, as custom registries often
+//! serve. Pinned versions never change, so they are cached like remote
+//! schemas; `latest` is fetched every time.
+
+use crate::resolve::Resolver;
+use crate::{refs, HttpConfig, HttpLoader, SchemaLoader, ValidationError};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+/// Scheme prefix of schema inputs that name a registry subject.
+pub const REGISTRY_SCHEME: &str = "registry://";
+
+/// Environment variable holding the base URL of the default registry.
+pub const REGISTRY_URL_ENV: &str = "SCHEMA_REGISTRY_URL";
+
+/// Media types the client understands, most preferred first, as the
+/// Confluent API expects them in `Accept`.
+const ACCEPT: &str = "application/vnd.schemaregistry.v1+json, \
+                      application/vnd.schemaregistry+json; q=0.9, \
+                      application/json; q=0.5";
+
+/// A client for a schema registry reachable over HTTP.
+#[derive(Clone)]
+pub struct RegistryClient {
+    base_url: String,
+    loader: Arc<dyn SchemaLoader>,
+}
+
+impl fmt::Debug for RegistryClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegistryClient")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A schema version retrieved from a registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredSchema {
+    /// The subject the schema is registered under.
+    pub subject: String,
+    /// The concrete version, if the registry reported one.
+    pub version: Option<u64>,
+    /// The registry-wide schema ID, if the registry reported one.
+    pub id: Option<u64>,
+    /// The schema document.
+    pub schema: Value,
+}
+
+impl RegistryClient {
+    /// Use the registry at `base_url` with the default HTTP settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid or the HTTP client cannot be
+    /// initialized.
+    pub fn new(base_url: &str) -> Result<Self, ValidationError> {
+        Self::with_config(base_url, &HttpConfig::default())
+    }
+
+    /// Use the registry at `base_url` with `config`, e.g. to send
+    /// credentials with [`HttpConfig::header`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL or configuration is invalid.
+    pub fn with_config(base_url: &str, config: &HttpConfig) -> Result<Self, ValidationError> {
+        let loader = HttpLoader::with_config(&config.clone().header("Accept", ACCEPT))?;
+        Self::with_loader(base_url, loader)
+    }
+
+    /// Use the registry at `base_url`, fetching responses with `loader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn with_loader<L: SchemaLoader + 'static>(
+        base_url: &str,
+        loader: L,
+    ) -> Result<Self, ValidationError> {
+        Url::parse(base_url)?;
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            loader: Arc::new(loader),
+        })
+    }
+
+    /// The registry named by the `SCHEMA_REGISTRY_URL` environment variable,
+    /// if it is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable holds an invalid URL.
+    pub fn from_env() -> Result<Option<Self>, ValidationError> {
+        match std::env::var(REGISTRY_URL_ENV) {
+            Ok(url) if !url.is_empty() => Self::new(&url).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The base URL requests are made against.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The URL of `version` (a number or `latest`) of `subject`.
+    pub fn schema_url(&self, subject: &str, version: &str) -> String {
+        let mut url = Url::parse(&self.base_url).expect("checked on construction");
+        url.path_segments_mut()
+            .expect("http(s) URLs have a path")
+            .pop_if_empty()
+            .extend(["subjects", subject, "versions", version]);
+        url.into()
+    }
+
+    /// Fetch `version` (a number or `latest`) of `subject`, bypassing the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, the version is malformed, or
+    /// the response is not a JSON Schema.
+    pub fn fetch(&self, subject: &str, version: &str) -> Result<RegisteredSchema, ValidationError> {
+        check_version(version)?;
+        let url = self.schema_url(subject, version);
+        let body = self.loader.load(&url)?;
+        let response: Value = serde_json::from_str(&body)?;
+
+        let envelope = match &response {
+            Value::Object(fields) => fields.get("schema").and_then(Value::as_str),
+            _ => None,
+        };
+        let schema = match envelope {
+            Some(schema) => {
+                let schema_type = response
+                    .get("schemaType")
+                    .and_then(Value::as_str)
+                    // Confluent omits the type for Avro, its default
+                    .unwrap_or("AVRO");
+                if !schema_type.eq_ignore_ascii_case("JSON") {
+                    return Err(ValidationError::SchemaLoad(format!(
+                        "{} is a {} schema, not a JSON Schema",
+                        url, schema_type
+                    )));
+                }
+                serde_json::from_str(schema)?
+            }
+            None => response.clone(),
+        };
+        Ok(RegisteredSchema {
+            subject: subject.to_string(),
+            version: response
+                .get("version")
+                .and_then(Value::as_u64)
+                .or_else(|| version.parse().ok()),
+            id: response.get("id").and_then(Value::as_u64),
+            schema,
+        })
+    }
+}
+
+/// Serves the schema documents at registry URLs, unwrapped from their
+/// envelopes, so they can be cached like any remote schema.
+impl SchemaLoader for RegistryClient {
+    fn load(&self, url: &str) -> Result<String, ValidationError> {
+        let (subject, version) = url
+            .strip_prefix(&self.base_url)
+            .and_then(|path| path.strip_prefix("/subjects/"))
+            .and_then(|path| path.rsplit_once("/versions/"))
+            .ok_or_else(|| {
+                ValidationError::SchemaLoad(format!("{} is not a schema in this registry", url))
+            })?;
+        let subject = refs::percent_decode(subject);
+        Ok(self.fetch(&subject, version)?.schema.to_string())
+    }
+}
+
+/// Load the schema for a `registry://` input from `registry`, or the one
+/// named by the environment, through `resolver`'s cache. Returns the schema's
+/// URL with it.
+pub(crate) fn load_input(
+    input: &str,
+    registry: Option<&RegistryClient>,
+    resolver: &Resolver,
+) -> Result<(String, Value), ValidationError> {
+    let (subject, version) = parse_registry_input(input).ok_or_else(|| {
+        ValidationError::SchemaLoad(format!("'{}' is not a {} input", input, REGISTRY_SCHEME))
+    })??;
+    let registry = match registry {
+        Some(registry) => registry.clone(),
+        None => RegistryClient::from_env()?.ok_or_else(|| {
+            ValidationError::SchemaLoad(format!(
+                "cannot load '{}': no schema registry configured; set {}",
+                input, REGISTRY_URL_ENV
+            ))
+        })?,
+    };
+
+    let url = registry.schema_url(&subject, &version);
+    let content = if version == "latest" {
+        registry.load(&url)?
+    } else {
+        resolver.fetch_with(&url, &registry)?
+    };
+    Ok((url, serde_json::from_str(&content)?))
+}
+
+/// Split a `registry://<subject>[/<version>]` input into subject and version.
+///
+/// Returns `None` for inputs without the `registry://` prefix.
+///
+/// # Errors
+///
+/// Returns an error if the subject is empty or the version is neither a
+/// positive number nor `latest`.
+pub fn parse_registry_input(input: &str) -> Option<Result<(String, String), ValidationError>> {
+    let reference = input.strip_prefix(REGISTRY_SCHEME)?;
+    let (subject, version) = match reference.rsplit_once('/') {
+        Some((subject, version))
+            if version == "latest"
+                || (!version.is_empty() && version.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            (subject, version)
+        }
+        _ => (reference, "latest"),
+    };
+    if let Err(e) = check_version(version) {
+        return Some(Err(e));
+    }
+    if subject.is_empty() {
+        return Some(Err(ValidationError::SchemaLoad(format!(
+            "'{}' does not name a subject",
+            input
+        ))));
+    }
+    Some(Ok((subject.to_string(), version.to_string())))
+}
+
+fn check_version(version: &str) -> Result<(), ValidationError> {
+    match version.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(()),
+        _ if version == "latest" => Ok(()),
+        _ => Err(ValidationError::SchemaLoad(format!(
+            "invalid schema version '{}': expected a positive number or 'latest'",
+            version
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records requested URLs and answers with a fixed body.
+    struct FakeRegistry {
+        body: String,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl SchemaLoader for Arc<FakeRegistry> {
+        fn load(&self, url: &str) -> Result<String, ValidationError> {
+            self.requests.lock().unwrap().push(url.to_string());
+            Ok(self.body.clone())
+        }
+    }
+
+    fn fake(body: Value) -> Arc<FakeRegistry> {
+        Arc::new(FakeRegistry {
+            body: body.to_string(),
+            requests: Mutex::new(Vec::new()),
+        })
+    }
+
+    #[test]
+    fn test_parse_registry_input() {
+        let parse = |input| parse_registry_input(input).map(|result| result.ok());
+        assert_eq!(parse("schema.json"), None);
+        assert_eq!(
+            parse("registry://orders-value/3"),
+            Some(Some(("orders-value".to_string(), "3".to_string())))
+        );
+        assert_eq!(
+            parse("registry://orders-value"),
+            Some(Some(("orders-value".to_string(), "latest".to_string())))
+        );
+        assert_eq!(
+            parse("registry://team/orders/latest"),
+            Some(Some(("team/orders".to_string(), "latest".to_string())))
+        );
+        assert_eq!(parse("registry://orders-value/0"), Some(None));
+        assert_eq!(parse("registry:///3"), Some(None));
+    }
+
+    #[test]
+    fn test_fetch_envelope() {
+        let schema = serde_json::json!({"type": "object"});
+        let server = fake(serde_json::json!({
+            "subject": "team/orders",
+            "version": 4,
+            "id": 17,
+            "schemaType": "JSON",
+            "schema": schema.to_string()
+        }));
+        let registry =
+            RegistryClient::with_loader("https://registry.example.com/", Arc::clone(&server))
+                .unwrap();
+
+        let fetched = registry.fetch("team/orders", "latest").unwrap();
+        assert_eq!(fetched.version, Some(4));
+        assert_eq!(fetched.id, Some(17));
+        assert_eq!(fetched.schema, schema);
+        let url = "https://registry.example.com/subjects/team%2Forders/versions/latest";
+        assert_eq!(server.requests.lock().unwrap().as_slice(), [url]);
+
+        assert_eq!(registry.load(url).unwrap(), schema.to_string());
+        assert!(registry.fetch("team/orders", "first").is_err());
+    }
+
+    #[test]
+    fn test_build_caches_pinned_versions() {
+        let server = fake(serde_json::json!({
+            "version": 2,
+            "schemaType": "JSON",
+            "schema": r#"{"required": ["id"]}"#
+        }));
+        let registry =
+            RegistryClient::with_loader("https://r.example.com", Arc::clone(&server)).unwrap();
+        let builder = crate::ValidatorBuilder::new()
+            .registry(registry)
+            .cache(Arc::new(crate::MemoryCacheStore::new()));
+
+        for _ in 0..2 {
+            let validator = builder.build_input("registry://orders/2").unwrap();
+            assert!(validator.is_valid_json(r#"{"id": 1}"#));
+            assert!(!validator.is_valid_json("{}"));
+            builder.build_input("registry://orders").unwrap();
+        }
+        assert_eq!(
+            server.requests.lock().unwrap().as_slice(),
+            [
+                "https://r.example.com/subjects/orders/versions/2",
+                "https://r.example.com/subjects/orders/versions/latest",
+                "https://r.example.com/subjects/orders/versions/latest",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fetch_raw_and_non_json_schemas() {
+        let raw = serde_json::json!({"type": "string"});
+        let registry = RegistryClient::with_loader("https://r.example.com", fake(raw.clone()));
+        assert_eq!(registry.unwrap().fetch("s", "1").unwrap().schema, raw);
+
+        let avro = fake(serde_json::json!({"version": 1, "schema": "\"string\""}));
+        let registry = RegistryClient::with_loader("https://r.example.com", avro).unwrap();
+        assert!(matches!(
+            registry.fetch("s", "1"),
+            Err(ValidationError::SchemaLoad(message)) if message.contains("AVRO")
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+//! Streaming the results of validating many files.
+//!
+//! [`validate_files`](crate::validate_files) hands back every file's report
+//! once the run is over. A [`Reporter`] follows the run instead: [`report_files`]
+//! tells it when each file starts, gives it each of the file's errors and its
+//! outcome as soon as the file is done, and ends with the totals. The CLI's
+//! `batch` output formats are reporters, and embedders can implement one to
+//! feed results into their own logging or metrics as they come.
+
+use crate::batch::{map_ordered, readable_files, validate_file};
+use crate::github::Finding;
+use crate::{BatchOptions, FileReport, FileStatus, ValidationError, Validator};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Receives the results of a run of [`report_files`] as they happen. Every
+/// method does nothing by default, so a reporter implements only those it
+/// needs.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// use validate_json_schema::{report_files, BatchOptions, FileError, Reporter, Validator};
+///
+/// /// Counts the failures of each keyword.
+/// #[derive(Default)]
+/// struct KeywordCounts(std::collections::BTreeMap<String, usize>);
+///
+/// impl Reporter for KeywordCounts {
+///     fn on_error(&mut self, _path: &Path, error: &FileError) {
+///         if let FileError::Failure(finding) = error {
+///             *self.0.entry(finding.failure.keyword.clone()).or_default() += 1;
+///         }
+///     }
+/// }
+///
+/// let validator = Arc::new(Validator::new(r#"{"required": ["name"]}"#)?);
+/// let mut counts = KeywordCounts::default();
+/// let summary = report_files(
+///     &validator,
+///     &["tests/data/package.json", "tests/data/tree.json"],
+///     &BatchOptions::default(),
+///     &mut counts,
+/// );
+/// assert_eq!((summary.valid, summary.invalid), (1, 1));
+/// assert_eq!(counts.0["required"], 1);
+/// # Ok::<(), validate_json_schema::ValidationError>(())
+/// ```
+pub trait Reporter {
+    /// Called before the file at `path` is validated or, when
+    /// [`BatchOptions::jobs`] validates several at once, once it is done and
+    /// the files before it are reported.
+    fn on_file_start(&mut self, _path: &Path) {}
+
+    /// Called for each error of the file at `path`, once it is validated
+    /// and before [`on_file_end`](Reporter::on_file_end).
+    fn on_error(&mut self, _path: &Path, _error: &FileError) {}
+
+    /// Called once a file is done, with its report and how long it took.
+    fn on_file_end(&mut self, _report: &FileReport, _elapsed: Duration) {}
+
+    /// Called once every file is done, with the totals of the run.
+    fn on_summary(&mut self, _summary: &Summary) {}
+}
+
+/// An error of a file, as given to [`Reporter::on_error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileError {
+    /// A validation failure, located in the file where possible.
+    Failure(Finding),
+    /// Why the file failed without failures to list: it could not be read,
+    /// parsed or validated, or it is a binary format such as CBOR.
+    Message(String),
+}
+
+/// The totals of a run of [`report_files`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Files in the run.
+    pub files: usize,
+    /// Files that are valid.
+    pub valid: usize,
+    /// Files that failed validation.
+    pub invalid: usize,
+    /// Files that could not be validated: unreadable, malformed, timed out
+    /// or crashed.
+    pub errors: usize,
+    /// Files that were not validated: empty, past the deadline, or vanished
+    /// or changed during the run.
+    pub skipped: usize,
+    /// Validation failures of the invalid files.
+    pub failures: usize,
+    /// Validation failures a [`BaselineReporter`](crate::BaselineReporter)
+    /// suppressed as known.
+    pub baselined: usize,
+    /// How long the run took.
+    pub elapsed: Duration,
+}
+
+impl Summary {
+    /// Whether every file was valid or skipped.
+    pub fn is_success(&self) -> bool {
+        self.invalid == 0 && self.errors == 0
+    }
+
+    fn record(&mut self, status: &FileStatus) {
+        self.files += 1;
+        match status {
+            FileStatus::Valid => self.valid += 1,
+            FileStatus::Failed(ValidationError::ValidationFailed(_)) => self.invalid += 1,
+            FileStatus::Failed(_) | FileStatus::TimedOut(_) | FileStatus::Panicked(_) => {
+                self.errors += 1
+            }
+            FileStatus::Skipped
+            | FileStatus::Empty
+            | FileStatus::Vanished(_)
+            | FileStatus::Changed => self.skipped += 1,
+        }
+    }
+}
+
+/// Validate each of `files` against `validator` as
+/// [`validate_files`](crate::validate_files) does, following the run with
+/// `reporter`, and return its totals. Files are reported in order, even when
+/// [`BatchOptions::jobs`] validates several at once.
+pub fn report_files<P: AsRef<Path>>(
+    validator: &Arc<Validator>,
+    files: &[P],
+    options: &BatchOptions,
+    reporter: &mut dyn Reporter,
+) -> Summary {
+    let started = Instant::now();
+    let mut summary = Summary::default();
+    let check = |path: &Path, readable: bool| {
+        let file_started = Instant::now();
+        let (status, findings) = validate_file(validator, path, options, started, readable, true);
+        let elapsed = file_started.elapsed();
+        let errors = file_errors(&status, findings);
+        (status, elapsed, errors)
+    };
+    let mut finish =
+        |reporter: &mut dyn Reporter,
+         path: &Path,
+         (status, elapsed, errors): (FileStatus, Duration, Vec<FileError>)| {
+            if matches!(
+                status,
+                FileStatus::Failed(ValidationError::ValidationFailed(_))
+            ) {
+                summary.failures += errors.len();
+            }
+            for error in &errors {
+                reporter.on_error(path, error);
+            }
+            summary.record(&status);
+            let report = FileReport {
+                path: path.to_path_buf(),
+                status,
+            };
+            reporter.on_file_end(&report, elapsed);
+        };
+    let files: Vec<(&Path, bool)> = files
+        .iter()
+        .map(AsRef::as_ref)
+        .zip(readable_files(files))
+        .collect();
+    if options.jobs > 1 {
+        let checks = |&(path, readable): &(&Path, bool)| check(path, readable);
+        map_ordered(&files, options.jobs, checks, |index, checked| {
+            reporter.on_file_start(files[index].0);
+            finish(reporter, files[index].0, checked);
+        });
+    } else {
+        for &(path, readable) in &files {
+            reporter.on_file_start(path);
+            finish(reporter, path, check(path, readable));
+        }
+    }
+    summary.elapsed = started.elapsed();
+    reporter.on_summary(&summary);
+    summary
+}
+
+/// The errors of a file that ended with `status`, with `findings` its
+/// failures as located when it was validated.
+fn file_errors(status: &FileStatus, findings: Vec<Finding>) -> Vec<FileError> {
+    let message = match status {
+        FileStatus::Failed(ValidationError::ValidationFailed(_)) if !findings.is_empty() => {
+            return findings.into_iter().map(FileError::Failure).collect();
+        }
+        // Binary documents are not located
+        FileStatus::Failed(ValidationError::ValidationFailed(message)) => {
+            format!("Validation failed: {}", message)
+        }
+        FileStatus::Failed(e) => e.to_string(),
+        FileStatus::TimedOut(timeout) => format!("Timed out after {}s", timeout.as_secs()),
+        FileStatus::Panicked(message) => format!("Internal error: {}", message),
+        FileStatus::Valid
+        | FileStatus::Skipped
+        | FileStatus::Empty
+        | FileStatus::Vanished(_)
+        | FileStatus::Changed => return Vec::new(),
+    };
+    vec![FileError::Message(message)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Reporter for Events {
+        fn on_file_start(&mut self, path: &Path) {
+            self.0.push(format!("start {}", path.display()));
+        }
+
+        fn on_error(&mut self, _path: &Path, error: &FileError) {
+            self.0.push(match error {
+                FileError::Failure(finding) => format!("failure {}", finding.failure),
+                FileError::Message(message) => format!("error {}", message),
+            });
+        }
+
+        fn on_file_end(&mut self, report: &FileReport, _elapsed: Duration) {
+            self.0.push(format!("end {}", report.path.display()));
+        }
+
+        fn on_summary(&mut self, summary: &Summary) {
+            self.0.push(format!("summary {} files", summary.files));
+        }
+    }
+
+    #[derive(Default)]
+    struct Findings(Vec<Finding>);
+
+    impl Reporter for Findings {
+        fn on_error(&mut self, _path: &Path, error: &FileError) {
+            if let FileError::Failure(finding) = error {
+                self.0.push(finding.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_report_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = dir.path().join("valid.json");
+        let invalid = dir.path().join("invalid.yml");
+        let missing = dir.path().join("missing.json");
+        fs::write(&valid, r#"{"name": "a"}"#).unwrap();
+        fs::write(&invalid, "name: 1\n").unwrap();
+
+        let validator =
+            Arc::new(Validator::new(r#"{"properties": {"name": {"type": "string"}}}"#).unwrap());
+        let mut events = Events::default();
+        let summary = report_files(
+            &validator,
+            &[&valid, &invalid, &missing],
+            &BatchOptions::default(),
+            &mut events,
+        );
+        assert_eq!(
+            (
+                summary.valid,
+                summary.invalid,
+                summary.errors,
+                summary.failures
+            ),
+            (1, 1, 1, 1)
+        );
+        assert!(!summary.is_success());
+        let events: Vec<&str> = events.0.iter().map(String::as_str).collect();
+        assert_eq!(events.len(), 9);
+        assert_eq!(events[2], format!("start {}", invalid.display()));
+        assert_eq!(events[3], "failure /name: 1 is not of type \"string\"");
+        assert!(events[6].starts_with("error Failed to read file: "));
+        assert_eq!(events[8], "summary 3 files");
+
+        // Failures come from the validated content, each document of a
+        // stream located in the whole file
+        let stream = dir.path().join("stream.yml");
+        fs::write(&stream, "name: a\n---\nname: 2\n").unwrap();
+        let mut findings = Findings::default();
+        report_files(
+            &validator,
+            &[&stream],
+            &BatchOptions::default(),
+            &mut findings,
+        );
+        let findings = findings.0;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].failure.instance_path, "/name");
+        assert_eq!(findings[0].location.map(|location| location.line), Some(3));
+    }
+}
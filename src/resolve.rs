@@ -0,0 +1,424 @@
+//! Retrieval of external documents referenced while compiling a schema.
+//!
+//! Every validator resolves `$ref`, `$dynamicRef` and `$recursiveRef` targets
+//! outside the schema document through [`Resolver`], so remote references use
+//! the same loader and cache as the root schema and relative references in a
+//! schema file resolve against the file's own location. The JSON Schema
+//! meta-schemas are bundled with the validation engine and never fetched.
+//!
+//! When remote documents are fetched is chosen with [`RefPrefetch`]. By
+//! default the engine resolves every reference while compiling, and a
+//! document that cannot be retrieved is replaced by a placeholder that fails
+//! with the retrieval error only when validation actually reaches it, so an
+//! unreachable reference in a branch the document never uses does not break
+//! validation. [`RefPrefetch::Eager`] fails compilation instead, and
+//! [`RefPrefetch::Lazy`] replaces remote references with a placeholder that
+//! fetches and compiles its target the first time validation reaches it.
+
+use crate::walk::subschemas;
+use crate::{fetch_and_cache_schema, CacheStore, Draft, FsCacheStore, HttpLoader, SchemaLoader};
+use jsonschema::paths::{LazyLocation, Location, LocationSegment};
+use jsonschema::{Keyword, Retrieve, Uri};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::sync::{Arc, Mutex, OnceLock};
+use url::Url;
+
+/// When a validator retrieves the remote documents its schema references.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RefPrefetch {
+    /// Fetch every referenced document while building the validator. A
+    /// document that cannot be fetched fails only the validations that reach
+    /// the reference, with the retrieval error.
+    #[default]
+    BestEffort,
+    /// Fetch every referenced document while building the validator and fail
+    /// the build if one cannot be fetched, so a broken deployment is noticed
+    /// at startup and validation never waits on the network.
+    Eager,
+    /// Fetch a remote document the first time validation reaches a reference
+    /// to it, so building is fast and unused references are never fetched.
+    /// Documents the reached one references are fetched along with it. A
+    /// failed fetch is not retried. Only the first failure inside a lazily
+    /// compiled reference is reported.
+    Lazy,
+}
+
+/// Where a [`Resolver`] caches remote documents.
+#[derive(Clone)]
+pub(crate) enum CachePolicy {
+    /// The user's cache directory, as used by the CLI.
+    Default,
+    /// A caller-provided store.
+    Store(Arc<dyn CacheStore>),
+    /// Always go through the loader.
+    Bypass,
+}
+
+/// Retrieves referenced documents from local files and remote URLs.
+#[derive(Clone)]
+pub(crate) struct Resolver {
+    loader: Option<Arc<dyn SchemaLoader>>,
+    cache: CachePolicy,
+    fail_fast: bool,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self {
+            loader: None,
+            cache: CachePolicy::Default,
+            fail_fast: false,
+        }
+    }
+}
+
+impl Resolver {
+    /// Fetch remote documents with `loader` instead of the default HTTP client.
+    pub(crate) fn with_loader(mut self, loader: Arc<dyn SchemaLoader>) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Cache remote documents according to `cache`.
+    pub(crate) fn with_cache(mut self, cache: CachePolicy) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Fail compilation when a referenced document cannot be retrieved,
+    /// instead of compiling a placeholder for it.
+    pub(crate) fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Fetch the raw content of the remote document at `url`.
+    pub(crate) fn fetch(&self, url: &str) -> Result<String, crate::ValidationError> {
+        let default_loader;
+        let loader: &dyn SchemaLoader = match &self.loader {
+            Some(loader) => loader.as_ref(),
+            None => {
+                default_loader = HttpLoader::new()?;
+                &default_loader
+            }
+        };
+        self.fetch_with(url, loader)
+    }
+
+    /// Fetch the raw content at `url` through this resolver's cache, but
+    /// with `loader` instead of its own.
+    pub(crate) fn fetch_with(
+        &self,
+        url: &str,
+        loader: &dyn SchemaLoader,
+    ) -> Result<String, crate::ValidationError> {
+        match &self.cache {
+            CachePolicy::Default => {
+                fetch_and_cache_schema(url, loader, &FsCacheStore::default_location()?)
+            }
+            CachePolicy::Store(cache) => fetch_and_cache_schema(url, loader, cache.as_ref()),
+            CachePolicy::Bypass => loader.load(url),
+        }
+    }
+}
+
+impl Retrieve for Resolver {
+    fn retrieve(&self, uri: &Uri<String>) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        match self.load_document(uri.as_str()) {
+            Ok(document) => Ok(document),
+            Err(e) => {
+                let message = format!("referenced schema '{}' could not be loaded: {}", uri, e);
+                if self.fail_fast {
+                    return Err(message.into());
+                }
+                Ok(serde_json::json!({ UNRESOLVED_KEYWORD: message }))
+            }
+        }
+    }
+}
+
+impl Resolver {
+    /// Load and parse the document at `uri`, an http(s) or file URL.
+    pub(crate) fn load_document(&self, uri: &str) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let url = Url::parse(uri)?;
+        let content = match url.scheme() {
+            "http" | "https" => self.fetch(url.as_str())?,
+            "file" => {
+                let path = url
+                    .to_file_path()
+                    .map_err(|_| format!("invalid file URL: {}", url))?;
+                fs::read_to_string(path)?
+            }
+            _ => {
+                return Err(format!(
+                    "cannot retrieve '{}': only http(s) and file references are supported",
+                    uri
+                )
+                .into())
+            }
+        };
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Keyword of the placeholder standing in for a document that could not be retrieved.
+pub(crate) const UNRESOLVED_KEYWORD: &str = "x-validate-json-schema-unresolved";
+
+/// Fails every instance with the retrieval error recorded in the placeholder.
+struct Unresolved {
+    message: String,
+}
+
+impl Keyword for Unresolved {
+    fn validate<'i>(
+        &self,
+        instance: &'i Value,
+        location: &LazyLocation,
+    ) -> Result<(), jsonschema::ValidationError<'i>> {
+        Err(jsonschema::ValidationError::custom(
+            Location::new(),
+            location.into(),
+            instance,
+            self.message.clone(),
+        ))
+    }
+
+    fn is_valid(&self, _instance: &Value) -> bool {
+        false
+    }
+}
+
+/// Compiles the [`UNRESOLVED_KEYWORD`] placeholder.
+// The signature is fixed by the engine's keyword factory API
+#[allow(clippy::result_large_err)]
+pub(crate) fn unresolved_keyword<'a>(
+    _parent: &'a Map<String, Value>,
+    value: &'a Value,
+    _path: Location,
+) -> Result<Box<dyn Keyword>, jsonschema::ValidationError<'a>> {
+    Ok(Box::new(Unresolved {
+        message: value.as_str().unwrap_or_default().to_string(),
+    }))
+}
+
+/// Keyword of the placeholder standing in for a lazily resolved remote `$ref`.
+pub(crate) const DEFERRED_KEYWORD: &str = "x-validate-json-schema-deferred";
+
+/// Compiles a validator for the schema at an absolute reference.
+pub(crate) type CompileReference =
+    Arc<dyn Fn(&str) -> Result<jsonschema::Validator, String> + Send + Sync>;
+
+/// A lazily compiled reference target, or why it could not be compiled.
+type DeferredTarget = Arc<OnceLock<Result<jsonschema::Validator, String>>>;
+
+/// The lazily resolved references of one validator, so every `$ref` to the
+/// same target shares a single fetch and compilation.
+#[derive(Clone)]
+pub(crate) struct DeferredRefs {
+    compile: CompileReference,
+    targets: Arc<Mutex<HashMap<String, DeferredTarget>>>,
+}
+
+impl DeferredRefs {
+    pub(crate) fn new(compile: CompileReference) -> Self {
+        Self {
+            compile,
+            targets: Arc::default(),
+        }
+    }
+
+    fn target(&self, reference: &str) -> DeferredTarget {
+        let mut targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(targets.entry(reference.to_string()).or_default())
+    }
+}
+
+/// Keywords kept next to a deferred `$ref` in drafts that ignore the other
+/// keywords of a subschema with `$ref`.
+const REF_SIBLINGS: &[&str] = &[
+    "$id",
+    "id",
+    "$schema",
+    "definitions",
+    "title",
+    "description",
+];
+
+/// Replace the `$ref`s in `schema` that point at remote documents with
+/// [`DEFERRED_KEYWORD`] placeholders holding the absolute reference.
+///
+/// References into the document itself, into a resource it embeds with
+/// `$id`, into a schema `known` reports as available without fetching, or to
+/// a JSON Schema meta-schema are left alone.
+pub(crate) fn defer_remote_refs(
+    schema: &Value,
+    base: &str,
+    draft: Draft,
+    known: &dyn Fn(&str) -> bool,
+) -> Value {
+    let Ok(base) = Url::parse(base) else {
+        return schema.clone();
+    };
+    let id_keyword = if draft == Draft::Draft4 { "id" } else { "$id" };
+    let mut resources = HashSet::new();
+    let mut references = Vec::new();
+    scan_refs(
+        schema,
+        String::new(),
+        base,
+        id_keyword,
+        &mut resources,
+        &mut references,
+    );
+
+    let mut schema = schema.clone();
+    for (pointer, reference) in references {
+        let mut document = reference.clone();
+        document.set_fragment(None);
+        let document = document.as_str();
+        if !matches!(reference.scheme(), "http" | "https")
+            || resources.contains(document)
+            || known(document)
+            || Draft::from_uri(document).is_some()
+        {
+            continue;
+        }
+        let Some(Value::Object(object)) = schema.pointer_mut(&pointer) else {
+            continue;
+        };
+        object.remove("$ref");
+        if draft <= Draft::Draft7 {
+            object.retain(|keyword, _| REF_SIBLINGS.contains(&keyword.as_str()));
+        }
+        object.insert(
+            DEFERRED_KEYWORD.to_string(),
+            Value::String(reference.to_string()),
+        );
+    }
+    schema
+}
+
+/// Record the resources `schema` identifies with `$id` and the absolute
+/// target of every `$ref` in it, by pointer to the subschema holding it.
+fn scan_refs(
+    schema: &Value,
+    pointer: String,
+    mut base: Url,
+    id_keyword: &str,
+    resources: &mut HashSet<String>,
+    references: &mut Vec<(String, Url)>,
+) {
+    if let Some(id) = schema.get(id_keyword).and_then(Value::as_str) {
+        if let Ok(url) = base.join(id) {
+            base = url;
+        }
+    }
+    let mut resource = base.clone();
+    resource.set_fragment(None);
+    resources.insert(resource.to_string());
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if let Ok(url) = base.join(reference) {
+            references.push((pointer.clone(), url));
+        }
+    }
+    for (relative, child) in subschemas(schema) {
+        scan_refs(
+            child,
+            format!("{}{}", pointer, relative),
+            base.clone(),
+            id_keyword,
+            resources,
+            references,
+        );
+    }
+}
+
+/// Validates against the target of a remote reference, compiled on first use.
+struct Deferred {
+    reference: String,
+    /// Location of the subschema holding the reference.
+    parent: String,
+    compile: CompileReference,
+    target: DeferredTarget,
+}
+
+impl Deferred {
+    fn validator(&self) -> &Result<jsonschema::Validator, String> {
+        self.target.get_or_init(|| (self.compile)(&self.reference))
+    }
+}
+
+impl Keyword for Deferred {
+    fn validate<'i>(
+        &self,
+        instance: &'i Value,
+        location: &LazyLocation,
+    ) -> Result<(), jsonschema::ValidationError<'i>> {
+        let location: Location = location.into();
+        let validator = match self.validator() {
+            Ok(validator) => validator,
+            Err(message) => {
+                return Err(jsonschema::ValidationError::custom(
+                    pointer_location(&format!("{}/$ref", self.parent)),
+                    location,
+                    instance,
+                    message.clone(),
+                ))
+            }
+        };
+        let Some(error) = validator.iter_errors(instance).next() else {
+            return Ok(());
+        };
+        let nested = error.instance_path.as_str();
+        Err(jsonschema::ValidationError::custom(
+            pointer_location(&format!("{}{}", self.parent, error.schema_path.as_str())),
+            pointer_location(&format!("{}{}", location.as_str(), nested)),
+            instance.pointer(nested).unwrap_or(instance),
+            error.to_string(),
+        ))
+    }
+
+    fn is_valid(&self, instance: &Value) -> bool {
+        self.validator()
+            .as_ref()
+            .is_ok_and(|validator| validator.is_valid(instance))
+    }
+}
+
+/// Compiles the [`DEFERRED_KEYWORD`] placeholder.
+// The signature is fixed by the engine's keyword factory API
+#[allow(clippy::result_large_err)]
+pub(crate) fn deferred_keyword<'a>(
+    value: &'a Value,
+    path: Location,
+    refs: &DeferredRefs,
+) -> Result<Box<dyn Keyword>, jsonschema::ValidationError<'a>> {
+    let reference = value.as_str().unwrap_or_default().to_string();
+    let parent = path
+        .as_str()
+        .rsplit_once('/')
+        .map_or("", |(parent, _)| parent)
+        .to_string();
+    Ok(Box::new(Deferred {
+        target: refs.target(&reference),
+        reference,
+        parent,
+        compile: Arc::clone(&refs.compile),
+    }))
+}
+
+/// The engine location of the JSON Pointer `pointer`.
+fn pointer_location(pointer: &str) -> Location {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .iter()
+        .map(|token| LocationSegment::from(token.as_str()))
+        .collect()
+}
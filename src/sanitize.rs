@@ -0,0 +1,139 @@
+//! Removing properties a schema does not allow.
+//!
+//! Ingestion services often prefer to accept a document and drop what the
+//! schema does not know about rather than reject it. [`sanitize`] removes every
+//! property that fails `additionalProperties: false` and reports where each one
+//! was. Properties are only removed where validation reports them: in a branch
+//! of `anyOf`, `oneOf` or `if` that fails for other reasons, unknown properties
+//! are left alone, since removing them might select a different branch.
+
+use crate::walk::escape_token;
+use crate::Validator;
+use jsonschema::error::ValidationErrorKind;
+use serde_json::Value;
+
+/// A document with its disallowed properties removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sanitized {
+    /// The document without the removed properties.
+    pub document: Value,
+    /// JSON Pointers to the removed properties, in the order they were found.
+    pub removed: Vec<String>,
+}
+
+/// Remove the properties of `document` that `additionalProperties: false`
+/// rejects.
+///
+/// Removing a property can change which subschemas apply, so this repeats
+/// until no more properties are rejected. The result may still fail the
+/// schema for other reasons; validate it before using it.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::{sanitize, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(
+///     r#"{"properties": {"id": {}}, "additionalProperties": false}"#,
+/// )?;
+/// let sanitized = sanitize(&validator, &json!({"id": 1, "debug": true}));
+///
+/// assert_eq!(sanitized.document, json!({"id": 1}));
+/// assert_eq!(sanitized.removed, ["/debug"]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn sanitize(validator: &Validator, document: &Value) -> Sanitized {
+    let mut document = document.clone();
+    let mut removed = Vec::new();
+    loop {
+        let rejected: Vec<(String, Vec<String>)> = validator
+            .schema
+            .iter_errors(&document)
+            .filter_map(|error| match &error.kind {
+                ValidationErrorKind::AdditionalProperties { unexpected } => {
+                    Some((error.instance_path.as_str().to_string(), unexpected.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let before = removed.len();
+        for (path, names) in rejected {
+            if let Some(Value::Object(entries)) = document.pointer_mut(&path) {
+                for name in names {
+                    if entries.remove(&name).is_some() {
+                        removed.push(format!("{}/{}", path, escape_token(&name)));
+                    }
+                }
+            }
+        }
+        if removed.len() == before {
+            return Sanitized { document, removed };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sanitize() {
+        let validator = Validator::new(
+            r##"{
+                "type": "object",
+                "properties": {
+                    "metadata": {"$ref": "#/definitions/metadata"},
+                    "items": {
+                        "type": "array",
+                        "items": {
+                            "properties": {"id": {"type": "integer"}},
+                            "patternProperties": {"^x-": {}},
+                            "additionalProperties": false
+                        }
+                    },
+                    "payload": {
+                        "anyOf": [
+                            {"required": ["kind"], "additionalProperties": false, "properties": {"kind": {}}},
+                            {"type": "string"}
+                        ]
+                    }
+                },
+                "definitions": {
+                    "metadata": {"properties": {"name": {}}, "additionalProperties": false}
+                }
+            }"##,
+        )
+        .unwrap();
+        let document = json!({
+            "metadata": {"name": "app", "a/b": 1},
+            "items": [{"id": 1, "x-note": "kept", "debug": true}, {"id": "two", "tmp": 0}],
+            "payload": {"extra": true},
+            "other": "kept"
+        });
+
+        let sanitized = sanitize(&validator, &document);
+        assert_eq!(
+            sanitized.document,
+            json!({
+                "metadata": {"name": "app"},
+                "items": [{"id": 1, "x-note": "kept"}, {"id": "two"}],
+                "payload": {"extra": true},
+                "other": "kept"
+            })
+        );
+        assert_eq!(
+            sanitized.removed,
+            ["/items/0/debug", "/items/1/tmp", "/metadata/a~1b"]
+        );
+
+        // A document with nothing to remove is returned unchanged
+        let clean = sanitize(&validator, &sanitized.document);
+        assert_eq!(clean.document, sanitized.document);
+        assert!(clean.removed.is_empty());
+    }
+}
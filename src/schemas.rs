@@ -0,0 +1,216 @@
+//! Schemas kept in memory and identified by their `$id`.
+//!
+//! Projects with many schemas that reference each other by URI can load them
+//! once into a [`SchemaRegistry`] and build validators from it. References
+//! between schemas in the registry resolve in memory, so unit tests and
+//! servers need no filesystem or network access; references to anything else
+//! are retrieved as usual. Unlike [`RegistryClient`](crate::RegistryClient),
+//! which fetches from a registry service, this registry lives in the process.
+
+use crate::{ValidationError, Validator, ValidatorBuilder};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// A collection of schemas by `$id`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::SchemaRegistry;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut schemas = SchemaRegistry::new();
+/// schemas.insert(json!({
+///     "$id": "https://example.com/address.json",
+///     "required": ["city"]
+/// }))?;
+/// schemas.insert(json!({
+///     "$id": "https://example.com/customer.json",
+///     "properties": {"address": {"$ref": "address.json"}}
+/// }))?;
+///
+/// let validator = schemas.validator("https://example.com/customer.json")?;
+/// assert!(!validator.is_valid_value(&json!({"address": {}})));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<String, Value>,
+}
+
+impl SchemaRegistry {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `schema` under its `$id` (`id` in Draft 4), replacing any schema
+    /// with the same identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema has no `$id` or it is not an absolute URI.
+    pub fn insert(&mut self, schema: Value) -> Result<(), ValidationError> {
+        let id = schema
+            .get("$id")
+            .or_else(|| schema.get("id"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                ValidationError::SchemaLoad("schema has no $id to register it under".to_string())
+            })?
+            .to_string();
+        self.insert_as(&id, schema)
+    }
+
+    /// Add `schema` under `id`, which need not match its `$id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `id` is not an absolute URI.
+    pub fn insert_as(&mut self, id: &str, schema: Value) -> Result<(), ValidationError> {
+        Url::parse(id).map_err(|e| {
+            ValidationError::SchemaLoad(format!("'{}' is not an absolute URI: {}", id, e))
+        })?;
+        self.schemas.insert(normalize(id).to_string(), schema);
+        Ok(())
+    }
+
+    /// The schema registered under `id`.
+    pub fn get(&self, id: &str) -> Option<&Value> {
+        self.schemas.get(normalize(id))
+    }
+
+    /// The identifiers of the schemas in the registry, in sorted order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.schemas.keys().map(String::as_str)
+    }
+
+    /// The number of schemas in the registry.
+    pub fn len(&self) -> usize {
+        self.schemas.len()
+    }
+
+    /// Whether the registry is empty.
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+
+    /// Build a validator for the schema registered under `id`, with the
+    /// default options.
+    ///
+    /// Use [`ValidatorBuilder::schemas`] and
+    /// [`build_url`](ValidatorBuilder::build_url) for other options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no schema under `id` or it is invalid.
+    pub fn validator(&self, id: &str) -> Result<Validator, ValidationError> {
+        if self.get(id).is_none() {
+            return Err(ValidationError::SchemaLoad(format!(
+                "no schema with $id '{}' in the registry",
+                id
+            )));
+        }
+        ValidatorBuilder::new().schemas(self.clone()).build_url(id)
+    }
+
+    /// Every schema paired with its identifier.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.schemas
+            .iter()
+            .map(|(id, schema)| (id.as_str(), schema))
+    }
+}
+
+/// `id` without an empty fragment, which does not change the resource it names.
+fn normalize(id: &str) -> &str {
+    id.strip_suffix('#').unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaLoader;
+    use serde_json::json;
+
+    struct Offline;
+
+    impl SchemaLoader for Offline {
+        fn load(&self, url: &str) -> Result<String, ValidationError> {
+            panic!("unexpected fetch of {}", url)
+        }
+    }
+
+    #[test]
+    fn test_cross_references() {
+        let mut schemas = SchemaRegistry::new();
+        schemas
+            .insert(json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$id": "https://example.com/schemas/order.json#",
+                "type": "object",
+                "properties": {
+                    "customer": {"$ref": "customer.json"},
+                    "lines": {"type": "array", "items": {"$ref": "common.json#/$defs/line"}}
+                }
+            }))
+            .unwrap();
+        schemas
+            .insert(json!({
+                "$id": "https://example.com/schemas/customer.json",
+                "required": ["email"],
+                "properties": {"email": {"$ref": "common.json#/definitions/email"}}
+            }))
+            .unwrap();
+        schemas
+            .insert_as(
+                "https://example.com/schemas/common.json",
+                json!({
+                    "definitions": {"email": {"type": "string", "pattern": "@"}},
+                    "$defs": {"line": {"required": ["sku"]}}
+                }),
+            )
+            .unwrap();
+        assert_eq!(schemas.len(), 3);
+        assert!(schemas
+            .get("https://example.com/schemas/order.json")
+            .is_some());
+
+        let validator = ValidatorBuilder::new()
+            .loader(Offline)
+            .no_cache()
+            .schemas(schemas.clone())
+            .build_url("https://example.com/schemas/order.json")
+            .unwrap();
+        assert_eq!(validator.draft(), crate::Draft::Draft202012);
+        assert!(validator.is_valid_value(&json!({
+            "customer": {"email": "a@example.com"},
+            "lines": [{"sku": "x"}]
+        })));
+        let failures = validator.validate_detailed(&json!({
+            "customer": {"email": "nope"},
+            "lines": [{}]
+        }));
+        let paths: Vec<&str> = failures.iter().map(|f| f.instance_path.as_str()).collect();
+        assert_eq!(paths, ["/customer/email", "/lines/0"]);
+
+        let customer = schemas
+            .validator("https://example.com/schemas/customer.json")
+            .unwrap();
+        assert!(!customer.is_valid_value(&json!({})));
+    }
+
+    #[test]
+    fn test_insert_errors() {
+        let mut schemas = SchemaRegistry::new();
+        assert!(schemas.insert(json!({"type": "string"})).is_err());
+        assert!(schemas.insert(json!({"$id": "relative.json"})).is_err());
+        assert!(schemas.is_empty());
+        assert!(schemas
+            .validator("https://example.com/missing.json")
+            .is_err());
+    }
+}
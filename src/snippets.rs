@@ -0,0 +1,728 @@
+//! Editor snippet generation.
+//!
+//! Builds VS Code snippets or IntelliJ live templates from a schema. Each
+//! snippet scaffolds the required properties of an object schema with
+//! placeholders, so documents written from it start out close to valid.
+
+use crate::refs;
+use crate::DocumentFormat;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// Nesting depth at which scaffolding stops, protecting against recursive schemas.
+const MAX_SCAFFOLD_DEPTH: usize = 8;
+
+/// The schema that accepts anything, used for properties without a definition.
+static ANY_SCHEMA: Value = Value::Bool(true);
+
+/// Editor snippet file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnippetFormat {
+    /// VS Code `*.code-snippets` JSON.
+    VsCode,
+    /// IntelliJ live template XML.
+    IntelliJ,
+}
+
+impl FromStr for SnippetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vscode" => Ok(Self::VsCode),
+            "intellij" => Ok(Self::IntelliJ),
+            other => Err(format!(
+                "unknown snippet format '{}' (expected 'vscode' or 'intellij')",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SnippetFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VsCode => write!(f, "vscode"),
+            Self::IntelliJ => write!(f, "intellij"),
+        }
+    }
+}
+
+/// Options controlling snippet generation.
+#[derive(Debug, Clone)]
+pub struct SnippetOptions {
+    /// Output file format.
+    pub format: SnippetFormat,
+    /// Document language the snippet bodies are written in.
+    pub language: DocumentFormat,
+    /// Trigger prefix for the root snippet. Definition snippets append their name.
+    pub prefix: String,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self {
+            format: SnippetFormat::VsCode,
+            language: DocumentFormat::Yaml,
+            prefix: "schema".to_string(),
+        }
+    }
+}
+
+/// Generate a snippet file for `schema`.
+///
+/// One snippet is produced for the root schema, plus one for every object
+/// definition under `definitions` or `$defs` that has required properties.
+pub fn generate_snippets(schema: &Value, options: &SnippetOptions) -> String {
+    let mut snippets = vec![Snippet::build(
+        schema,
+        schema,
+        options.prefix.clone(),
+        options.language,
+    )];
+
+    for key in ["definitions", "$defs"] {
+        if let Some(definitions) = schema.get(key).and_then(Value::as_object) {
+            for (name, definition) in definitions {
+                if required_properties(schema, definition).is_empty() {
+                    continue;
+                }
+                let prefix = format!("{}-{}", options.prefix, name);
+                snippets.push(Snippet::build(schema, definition, prefix, options.language));
+            }
+        }
+    }
+
+    match options.format {
+        SnippetFormat::VsCode => render_vscode(&snippets),
+        SnippetFormat::IntelliJ => render_intellij(&snippets, options.language),
+    }
+}
+
+/// A placeholder the user tabs through when expanding a snippet.
+struct Field {
+    variable: String,
+    default: String,
+    choices: Vec<String>,
+}
+
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+type Line = Vec<Segment>;
+
+struct Snippet {
+    prefix: String,
+    description: String,
+    lines: Vec<Line>,
+}
+
+impl Snippet {
+    fn build(root: &Value, schema: &Value, prefix: String, language: DocumentFormat) -> Self {
+        let schema = refs::deref(root, schema);
+        let description = schema
+            .get("description")
+            .or_else(|| schema.get("title"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Scaffold a document for '{}'", prefix));
+
+        let lines = match language {
+            DocumentFormat::Json | DocumentFormat::Json5 => json_value(root, schema, 0, "", 0),
+            DocumentFormat::Yaml => match yaml_value(root, schema, 0, "", 0) {
+                Scaffold::Inline(line) => vec![line],
+                Scaffold::Block(lines) => lines,
+            },
+        };
+
+        Self {
+            prefix,
+            description,
+            lines,
+        }
+    }
+}
+
+fn required_properties<'a>(root: &'a Value, schema: &'a Value) -> Vec<(&'a str, &'a Value)> {
+    let schema = refs::deref(root, schema);
+    let properties = schema.get("properties").and_then(Value::as_object);
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| {
+            required
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|name| {
+                    let property = properties
+                        .and_then(|properties| properties.get(name))
+                        .map(|property| refs::deref(root, property))
+                        .unwrap_or(&ANY_SCHEMA);
+                    (name, property)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Object,
+    Array,
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Null,
+}
+
+fn kind_of(schema: &Value) -> Kind {
+    let declared = match schema.get("type") {
+        Some(Value::String(name)) => Some(name.as_str()),
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|n| *n != "null"),
+        _ => None,
+    };
+    match declared {
+        Some("object") => Kind::Object,
+        Some("array") => Kind::Array,
+        Some("string") => Kind::String,
+        Some("number") => Kind::Number,
+        Some("integer") => Kind::Integer,
+        Some("boolean") => Kind::Boolean,
+        Some("null") => Kind::Null,
+        _ if schema.get("properties").is_some() => Kind::Object,
+        _ if schema.get("items").is_some() => Kind::Array,
+        _ => match schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .and_then(|e| e.first())
+        {
+            Some(Value::Number(_)) => Kind::Number,
+            Some(Value::Bool(_)) => Kind::Boolean,
+            _ => Kind::String,
+        },
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// `value` as a YAML scalar, quoting strings that YAML would read as another
+/// type, such as `1.0`, `true` or `null`.
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => match serde_yaml::from_str::<Value>(s) {
+            Ok(Value::String(parsed)) if parsed == *s => s.clone(),
+            _ => Value::String(s.clone()).to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+/// The placeholder for the value at `path`, its default and choices written
+/// with `scalar`.
+fn field(schema: &Value, path: &str, kind: Kind, scalar: fn(&Value) -> String) -> Field {
+    let variable = if path.is_empty() {
+        "VALUE".to_string()
+    } else {
+        path.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+
+    let choices: Vec<String> = match schema.get("enum").and_then(Value::as_array) {
+        Some(options) => options.iter().map(scalar).collect(),
+        None if kind == Kind::Boolean => vec!["true".to_string(), "false".to_string()],
+        None => Vec::new(),
+    };
+
+    let default = schema
+        .get("default")
+        .or_else(|| schema.get("examples").and_then(|e| e.get(0)))
+        .or_else(|| schema.get("const"))
+        .map(scalar)
+        .or_else(|| choices.first().cloned())
+        .unwrap_or_else(|| match kind {
+            Kind::Number | Kind::Integer => placeholder_number(schema, kind == Kind::Integer),
+            _ => scalar(&Value::from(path.rsplit('.').next().unwrap_or("value"))),
+        });
+
+    Field {
+        variable,
+        default,
+        choices,
+    }
+}
+
+/// A number that `schema`'s `minimum`, `exclusiveMinimum`, `maximum` and
+/// `exclusiveMaximum` allow: zero if it is in range, or else one at or just
+/// past the bound it breaks, a whole number if `integer`.
+fn placeholder_number(schema: &Value, integer: bool) -> String {
+    // The tighter of a bound and its exclusive form, and whether it is exclusive
+    let bound = |name: &str, exclusive_name: &str, tighter: fn(f64, f64) -> bool| {
+        let exclusive_flag = schema.get(exclusive_name) == Some(&Value::Bool(true));
+        let inclusive = schema.get(name).and_then(Value::as_f64);
+        let inclusive = inclusive.map(|value| (value, exclusive_flag));
+        let exclusive = schema.get(exclusive_name).and_then(Value::as_f64);
+        match (inclusive, exclusive.map(|value| (value, true))) {
+            (Some(a), Some(b)) if tighter(a.0, b.0) => Some(a),
+            (a, b) => b.or(a),
+        }
+    };
+    let lower = bound("minimum", "exclusiveMinimum", |a, b| a > b);
+    let upper = bound("maximum", "exclusiveMaximum", |a, b| a < b);
+
+    let mut value: f64 = 0.0;
+    if let Some((min, exclusive)) = lower {
+        if value < min || (exclusive && value == min) {
+            value = match (integer, exclusive) {
+                (true, true) => min.floor() + 1.0,
+                (true, false) => min.ceil(),
+                (false, true) => min + 1.0,
+                (false, false) => min,
+            };
+        }
+    }
+    if let Some((max, exclusive)) = upper {
+        if value > max || (exclusive && value == max) {
+            value = match (integer, exclusive, lower) {
+                (true, true, _) => max.ceil() - 1.0,
+                (true, false, _) => max.floor(),
+                (false, _, Some((min, _))) => (min + max) / 2.0,
+                (false, true, None) => max - 1.0,
+                (false, false, None) => max,
+            };
+        }
+    }
+    if value.fract() == 0.0 {
+        Value::from(value as i64).to_string()
+    } else {
+        Value::from(value).to_string()
+    }
+}
+
+fn child_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+enum Scaffold {
+    Inline(Line),
+    Block(Vec<Line>),
+}
+
+fn yaml_key(name: &str) -> String {
+    let plain = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+    if plain {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+fn yaml_value(root: &Value, schema: &Value, indent: usize, path: &str, depth: usize) -> Scaffold {
+    let kind = kind_of(schema);
+    let pad = " ".repeat(indent);
+
+    match kind {
+        Kind::Object => {
+            let required = required_properties(root, schema);
+            if required.is_empty() || depth >= MAX_SCAFFOLD_DEPTH {
+                return Scaffold::Inline(vec![Segment::Text("{}".to_string())]);
+            }
+            let mut lines = Vec::new();
+            for (name, property) in required {
+                let key = format!("{}{}:", pad, yaml_key(name));
+                let property_path = child_path(path, name);
+                match yaml_value(root, property, indent + 2, &property_path, depth + 1) {
+                    Scaffold::Inline(mut line) => {
+                        line.insert(0, Segment::Text(format!("{} ", key)));
+                        lines.push(line);
+                    }
+                    Scaffold::Block(block) => {
+                        lines.push(vec![Segment::Text(key)]);
+                        lines.extend(block);
+                    }
+                }
+            }
+            Scaffold::Block(lines)
+        }
+        Kind::Array => {
+            let items = schema
+                .get("items")
+                .map(|items| refs::deref(root, items))
+                .unwrap_or(&ANY_SCHEMA);
+            match yaml_value(root, items, indent + 2, path, depth + 1) {
+                Scaffold::Inline(mut line) => {
+                    line.insert(0, Segment::Text(format!("{}- ", pad)));
+                    Scaffold::Block(vec![line])
+                }
+                Scaffold::Block(mut block) => {
+                    // Replace the first item line's indentation with the list marker
+                    if let Some(Segment::Text(text)) = block.first_mut().and_then(|l| l.first_mut())
+                    {
+                        *text = format!("{}- {}", pad, &text[(indent + 2).min(text.len())..]);
+                    }
+                    Scaffold::Block(block)
+                }
+            }
+        }
+        Kind::Null => Scaffold::Inline(vec![Segment::Text("null".to_string())]),
+        _ => Scaffold::Inline(vec![Segment::Field(field(schema, path, kind, yaml_scalar))]),
+    }
+}
+
+fn json_value(root: &Value, schema: &Value, indent: usize, path: &str, depth: usize) -> Vec<Line> {
+    let kind = kind_of(schema);
+
+    match kind {
+        Kind::Object => {
+            let required = required_properties(root, schema);
+            if required.is_empty() || depth >= MAX_SCAFFOLD_DEPTH {
+                return vec![vec![Segment::Text("{}".to_string())]];
+            }
+            let pad = " ".repeat(indent + 2);
+            let count = required.len();
+            let mut lines = vec![vec![Segment::Text("{".to_string())]];
+            for (index, (name, property)) in required.into_iter().enumerate() {
+                let property_path = child_path(path, name);
+                let mut value = json_value(root, property, indent + 2, &property_path, depth + 1);
+                if let Some(first) = value.first_mut() {
+                    first.insert(0, Segment::Text(format!("{}{:?}: ", pad, name)));
+                }
+                if index + 1 < count {
+                    if let Some(last) = value.last_mut() {
+                        last.push(Segment::Text(",".to_string()));
+                    }
+                }
+                lines.extend(value);
+            }
+            lines.push(vec![Segment::Text(format!("{}}}", " ".repeat(indent)))]);
+            lines
+        }
+        Kind::Array => vec![vec![Segment::Text("[]".to_string())]],
+        Kind::Null => vec![vec![Segment::Text("null".to_string())]],
+        Kind::String => vec![vec![
+            Segment::Text("\"".to_string()),
+            Segment::Field(field(schema, path, kind, scalar_text)),
+            Segment::Text("\"".to_string()),
+        ]],
+        _ => vec![vec![Segment::Field(field(schema, path, kind, scalar_text))]],
+    }
+}
+
+fn escape_vscode(text: &str, extra: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '$' || c == '\\' || extra.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn render_vscode(snippets: &[Snippet]) -> String {
+    let mut output = Map::new();
+    for snippet in snippets {
+        let mut tabstop = 0;
+        let mut body: Vec<Value> = snippet
+            .lines
+            .iter()
+            .map(|line| {
+                let text: String = line
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Text(text) => escape_vscode(text, &[]),
+                        Segment::Field(field) => {
+                            tabstop += 1;
+                            if field.choices.is_empty() {
+                                format!(
+                                    "${{{}:{}}}",
+                                    tabstop,
+                                    escape_vscode(&field.default, &['}'])
+                                )
+                            } else {
+                                let choices: Vec<String> = field
+                                    .choices
+                                    .iter()
+                                    .map(|choice| escape_vscode(choice, &[',', '|']))
+                                    .collect();
+                                format!("${{{}|{}|}}", tabstop, choices.join(","))
+                            }
+                        }
+                    })
+                    .collect();
+                Value::String(text)
+            })
+            .collect();
+        body.push(Value::String("$0".to_string()));
+
+        let mut entry = Map::new();
+        entry.insert("prefix".to_string(), Value::String(snippet.prefix.clone()));
+        entry.insert("body".to_string(), Value::Array(body));
+        entry.insert(
+            "description".to_string(),
+            Value::String(snippet.description.clone()),
+        );
+        output.insert(snippet.prefix.clone(), Value::Object(entry));
+    }
+
+    serde_json::to_string_pretty(&Value::Object(output)).unwrap_or_default()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+fn render_intellij(snippets: &[Snippet], language: DocumentFormat) -> String {
+    let context = match language {
+        DocumentFormat::Json | DocumentFormat::Json5 => "JSON",
+        DocumentFormat::Yaml => "YAML",
+    };
+
+    let mut output = String::from("<templateSet group=\"validate-json-schema\">\n");
+    for snippet in snippets {
+        // Variable names must be unique within a template
+        let mut variables: Vec<(String, &Field)> = Vec::new();
+        let mut value = String::new();
+        for (index, line) in snippet.lines.iter().enumerate() {
+            if index > 0 {
+                value.push('\n');
+            }
+            for segment in line {
+                match segment {
+                    Segment::Text(text) => value.push_str(&text.replace('$', "$$")),
+                    Segment::Field(field) => {
+                        let mut name = field.variable.clone();
+                        let mut suffix = 1;
+                        while name == "END" || variables.iter().any(|(used, _)| *used == name) {
+                            suffix += 1;
+                            name = format!("{}_{}", field.variable, suffix);
+                        }
+                        value.push_str(&format!("${}$", name));
+                        variables.push((name, field));
+                    }
+                }
+            }
+        }
+        value.push_str("$END$");
+
+        output.push_str(&format!(
+            "  <template name=\"{}\" value=\"{}\" description=\"{}\" toReformat=\"false\" toShortenFQNames=\"false\">\n",
+            escape_xml(&snippet.prefix),
+            escape_xml(&value),
+            escape_xml(&snippet.description)
+        ));
+        for (name, field) in variables {
+            let expression = if field.choices.is_empty() {
+                String::new()
+            } else {
+                let choices: Vec<String> = field
+                    .choices
+                    .iter()
+                    .map(|choice| format!("{:?}", choice))
+                    .collect();
+                format!("enum({})", choices.join(","))
+            };
+            output.push_str(&format!(
+                "    <variable name=\"{}\" expression=\"{}\" defaultValue=\"{}\" alwaysStopAt=\"true\" />\n",
+                escape_xml(&name),
+                escape_xml(&expression),
+                escape_xml(&format!("{:?}", field.default))
+            ));
+        }
+        output.push_str(&format!(
+            "    <context>\n      <option name=\"{}\" value=\"true\" />\n    </context>\n",
+            context
+        ));
+        output.push_str("  </template>\n");
+    }
+    output.push_str("</templateSet>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "title": "Service",
+            "type": "object",
+            "required": ["name", "replicas", "mode", "tags"],
+            "properties": {
+                "name": {"type": "string"},
+                "replicas": {"type": "integer", "default": 1},
+                "mode": {"enum": ["fast", "safe"]},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "optional": {"type": "string"}
+            },
+            "definitions": {
+                "port": {
+                    "type": "object",
+                    "required": ["number"],
+                    "properties": {"number": {"type": "integer"}}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_vscode_yaml_snippets() {
+        let output = generate_snippets(&schema(), &SnippetOptions::default());
+        let snippets: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(
+            snippets["schema"]["body"],
+            json!([
+                "name: ${1:name}",
+                "replicas: ${2:1}",
+                "mode: ${3|fast,safe|}",
+                "tags:",
+                "  - ${4:tags}",
+                "$0"
+            ])
+        );
+        assert_eq!(snippets["schema"]["description"], "Service");
+        assert_eq!(
+            snippets["schema-port"]["body"],
+            json!(["number: ${1:0}", "$0"])
+        );
+    }
+
+    /// The document a VS Code snippet body expands to when every placeholder
+    /// keeps its default or first choice.
+    fn expand_vscode(body: &Value) -> String {
+        let mut document = String::new();
+        for line in body.as_array().unwrap() {
+            let mut rest = line.as_str().unwrap();
+            while let Some(start) = rest.find("${") {
+                document.push_str(&rest[..start]);
+                let field = &rest[start + 2..];
+                let end = field.find('}').unwrap();
+                let field = &field[..end];
+                let digits = field.find(|c: char| !c.is_ascii_digit()).unwrap();
+                let value = match &field[digits..digits + 1] {
+                    ":" => &field[digits + 1..],
+                    _ => field[digits + 1..].split([',', '|']).next().unwrap(),
+                };
+                document.push_str(value);
+                rest = &rest[start + 2 + end + 1..];
+            }
+            if rest != "$0" {
+                document.push_str(rest);
+                document.push('\n');
+            }
+        }
+        document
+    }
+
+    #[test]
+    fn test_yaml_snippet_quotes_ambiguous_strings() {
+        let schema = json!({
+            "type": "object",
+            "required": ["version", "flag", "note", "~"],
+            "properties": {
+                "version": {"type": "string", "default": "1.0"},
+                "flag": {"type": "string", "enum": ["true", "false"]},
+                "note": {"type": "string", "default": "plain text"},
+                "~": {"type": "string"}
+            }
+        });
+        let output = generate_snippets(&schema, &SnippetOptions::default());
+        let snippets: Value = serde_json::from_str(&output).unwrap();
+
+        let body = &snippets["schema"]["body"];
+        assert_eq!(body[0], "version: ${1:\"1.0\"}");
+        assert_eq!(body[1], "flag: ${2|\"true\",\"false\"|}");
+        assert_eq!(body[2], "note: ${3:plain text}");
+        assert_eq!(body[3], "\"~\": ${4:\"~\"}");
+        let validator = crate::Validator::new(&schema.to_string()).unwrap();
+        validator.validate_yaml(&expand_vscode(body)).unwrap();
+    }
+
+    #[test]
+    fn test_number_placeholders() {
+        let placeholder = |schema: Value| placeholder_number(&schema, false);
+        let integer = |schema: Value| placeholder_number(&schema, true);
+        assert_eq!(placeholder(json!({})), "0");
+        assert_eq!(integer(json!({"minimum": 1})), "1");
+        assert_eq!(integer(json!({"minimum": 0.5})), "1");
+        assert_eq!(integer(json!({"exclusiveMinimum": 0})), "1");
+        assert_eq!(
+            integer(json!({"minimum": 0, "exclusiveMinimum": true})),
+            "1"
+        );
+        assert_eq!(integer(json!({"maximum": -3})), "-3");
+        assert_eq!(integer(json!({"minimum": -5, "maximum": 5})), "0");
+        assert_eq!(placeholder(json!({"minimum": 2.5})), "2.5");
+        assert_eq!(
+            placeholder(json!({"exclusiveMinimum": 0, "maximum": 1})),
+            "1"
+        );
+        assert_eq!(
+            placeholder(json!({"exclusiveMinimum": 0, "exclusiveMaximum": 1})),
+            "0.5"
+        );
+        assert_eq!(placeholder(json!({"exclusiveMaximum": -1})), "-2");
+    }
+
+    #[test]
+    fn test_vscode_json_snippets() {
+        let options = SnippetOptions {
+            language: DocumentFormat::Json,
+            ..SnippetOptions::default()
+        };
+        let output = generate_snippets(&schema(), &options);
+        let snippets: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(
+            snippets["schema-port"]["body"],
+            json!(["{", "  \"number\": ${1:0}", "}", "$0"])
+        );
+    }
+
+    #[test]
+    fn test_intellij_templates() {
+        let options = SnippetOptions {
+            format: SnippetFormat::IntelliJ,
+            ..SnippetOptions::default()
+        };
+        let output = generate_snippets(&schema(), &options);
+
+        assert!(output.starts_with("<templateSet"));
+        assert!(output.contains("value=\"name: $NAME$&#10;"));
+        assert!(output.contains(
+            "<variable name=\"MODE\" expression=\"enum(&quot;fast&quot;,&quot;safe&quot;)\""
+        ));
+        assert!(output.contains("<option name=\"YAML\" value=\"true\" />"));
+    }
+}
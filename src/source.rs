@@ -0,0 +1,460 @@
+//! Locating the values of a YAML or JSON document in its source text.
+//!
+//! Parsed documents carry no positions, so suggestions that edit a file need
+//! their own map from JSON Pointers back to lines. [`SourceMap`] understands
+//! block mappings and sequences, which is how configuration files are almost
+//! always written; values inside flow collections (`{a: 1}`, `[1, 2]`) and
+//! block scalars are not mapped. JSON documents, which have no such
+//! shortcuts, are mapped in full by [`SourceMap::parse_json`].
+
+use crate::walk::escape_token;
+
+/// A mapping entry or sequence item written on its own line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Node {
+    /// JSON Pointer to the value in the document.
+    pub(crate) pointer: String,
+    /// 0-based line of the key or `- ` indicator.
+    pub(crate) line: usize,
+    /// Column of the key or `- ` indicator.
+    pub(crate) column: usize,
+    /// Whether this is a sequence item rather than a mapping entry.
+    pub(crate) item: bool,
+    /// The text after `key:` or `- ` on the same line, without a comment.
+    pub(crate) inline: String,
+    /// 0-based line after the last line of the node's value.
+    pub(crate) end: usize,
+}
+
+/// The block-style nodes of a YAML document, in source order.
+#[derive(Debug, Default)]
+pub(crate) struct SourceMap {
+    pub(crate) nodes: Vec<Node>,
+}
+
+impl SourceMap {
+    pub(crate) fn parse(content: &str) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut nodes: Vec<Node> = Vec::new();
+        // Open nodes as (column, pointer token, is item, sequence index)
+        let mut stack: Vec<(usize, String, bool, usize)> = Vec::new();
+        let mut block_scalar: Option<usize> = None;
+
+        for (line_index, line) in lines.iter().enumerate() {
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let mut rest = line[indent..].trim_end();
+            if let Some(column) = block_scalar {
+                if rest.is_empty() || indent > column {
+                    continue;
+                }
+                block_scalar = None;
+            }
+            if rest.is_empty() || rest.starts_with('#') || rest == "---" || rest == "..." {
+                continue;
+            }
+
+            let mut column = indent;
+            while rest == "-" || rest.starts_with("- ") {
+                while stack.last().is_some_and(|open| open.0 > column) {
+                    stack.pop();
+                }
+                let index = match stack.last() {
+                    Some(&(open, _, true, index)) if open == column => {
+                        stack.pop();
+                        index + 1
+                    }
+                    _ => 0,
+                };
+                stack.push((column, index.to_string(), true, index));
+                let after = rest.get(1..).unwrap_or_default();
+                let inline = after.trim_start();
+                nodes.push(node(&stack, line_index, column, true, inline));
+                column += 1 + after.len() - inline.len();
+                rest = inline;
+            }
+
+            if let Some((key, value)) = split_key(rest) {
+                while stack.last().is_some_and(|open| open.0 >= column) {
+                    stack.pop();
+                }
+                stack.push((column, escape_token(&key), false, 0));
+                nodes.push(node(&stack, line_index, column, false, value));
+                if value.starts_with(['|', '>']) {
+                    block_scalar = Some(column);
+                }
+            } else if rest.starts_with(['|', '>']) {
+                block_scalar = Some(column.saturating_sub(1));
+            }
+        }
+
+        let significant: Vec<(usize, usize, bool)> = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    return None;
+                }
+                let indent = line.len() - line.trim_start_matches(' ').len();
+                Some((index, indent, trimmed == "-" || trimmed.starts_with("- ")))
+            })
+            .collect();
+        for node in &mut nodes {
+            let mut end = node.line + 1;
+            for &(index, indent, dash) in significant.iter().filter(|line| line.0 > node.line) {
+                let nested = indent > node.column
+                    || (!node.item && node.inline.is_empty() && indent == node.column && dash);
+                if !nested {
+                    break;
+                }
+                end = index + 1;
+            }
+            node.end = end;
+        }
+
+        SourceMap { nodes }
+    }
+
+    /// Map every member and item of the JSON `content`, which may use the
+    /// syntax JSON5 adds, such as comments and unquoted keys. Mapping stops
+    /// where the content stops being JSON.
+    pub(crate) fn parse_json(content: &str) -> Self {
+        let mut scanner = JsonScanner {
+            bytes: content.as_bytes(),
+            position: 0,
+            line: 0,
+            line_start: 0,
+            nodes: Vec::new(),
+        };
+        scanner.value("", 0);
+        SourceMap {
+            nodes: scanner.nodes,
+        }
+    }
+
+    /// The node for the value at `pointer`.
+    pub(crate) fn find(&self, pointer: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.pointer == pointer)
+    }
+
+    /// The node for the value at `pointer`, or else for its closest
+    /// enclosing value that has one.
+    pub(crate) fn nearest(&self, pointer: &str) -> Option<&Node> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(node) = self.find(pointer) {
+                return Some(node);
+            }
+            pointer = pointer.rsplit_once('/')?.0;
+        }
+    }
+
+    /// The nodes directly inside the value at `pointer`.
+    pub(crate) fn children<'a>(&'a self, pointer: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.nodes.iter().filter(move |node| {
+            node.pointer
+                .strip_prefix(pointer)
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|rest| !rest.contains('/'))
+        })
+    }
+}
+
+/// Nesting beyond which [`JsonScanner`] stops mapping; deeper documents
+/// fail the parser's own limit first.
+const MAX_JSON_DEPTH: usize = 256;
+
+/// Walks JSON text, recording a node at each member's key and each item.
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    line: usize,
+    line_start: usize,
+    nodes: Vec<Node>,
+}
+
+impl JsonScanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn advance(&mut self) {
+        if self.peek() == Some(b'\n') {
+            self.line += 1;
+            self.line_start = self.position + 1;
+        }
+        self.position += 1;
+    }
+
+    fn skip_space(&mut self) {
+        loop {
+            match (self.peek(), self.bytes.get(self.position + 1)) {
+                (Some(b' ' | b'\t' | b'\r' | b'\n'), _) => self.advance(),
+                (Some(b'/'), Some(b'/')) => {
+                    while self.peek().is_some_and(|byte| byte != b'\n') {
+                        self.advance();
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.position += 2;
+                    while self.peek().is_some() && !self.bytes[self.position..].starts_with(b"*/") {
+                        self.advance();
+                    }
+                    self.position = (self.position + 2).min(self.bytes.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Record a node for the value at `pointer` starting here, scan the
+    /// value and set where it ends.
+    fn child(
+        &mut self,
+        pointer: String,
+        (line, column): (usize, usize),
+        item: bool,
+        depth: usize,
+    ) -> Option<()> {
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            pointer: pointer.clone(),
+            line,
+            column,
+            item,
+            inline: String::new(),
+            end: line + 1,
+        });
+        let scanned = self.value(&pointer, depth + 1);
+        self.nodes[index].end = self.line + 1;
+        scanned
+    }
+
+    fn value(&mut self, pointer: &str, depth: usize) -> Option<()> {
+        if depth > MAX_JSON_DEPTH {
+            return None;
+        }
+        self.skip_space();
+        match self.peek()? {
+            b'{' => {
+                self.advance();
+                loop {
+                    self.skip_space();
+                    match self.peek()? {
+                        b'}' => break,
+                        b',' => {
+                            self.advance();
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    let (line, column) = (self.line, self.position - self.line_start);
+                    let key = self.key()?;
+                    self.skip_space();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+                    self.advance();
+                    let pointer = format!("{}/{}", pointer, escape_token(&key));
+                    self.child(pointer, (line, column), false, depth)?;
+                }
+            }
+            b'[' => {
+                self.advance();
+                let mut index = 0;
+                loop {
+                    self.skip_space();
+                    match self.peek()? {
+                        b']' => break,
+                        b',' => {
+                            self.advance();
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    let (line, column) = (self.line, self.position - self.line_start);
+                    let pointer = format!("{}/{}", pointer, index);
+                    self.child(pointer, (line, column), true, depth)?;
+                    index += 1;
+                }
+            }
+            b'"' | b'\'' => {
+                self.string()?;
+                return Some(());
+            }
+            _ => {
+                while self.peek().is_some_and(|byte| {
+                    !matches!(byte, b',' | b']' | b'}' | b'/') && !byte.is_ascii_whitespace()
+                }) {
+                    self.advance();
+                }
+                return Some(());
+            }
+        }
+        self.advance();
+        Some(())
+    }
+
+    /// Scan a quoted string, returning its value.
+    fn string(&mut self) -> Option<String> {
+        let start = self.position;
+        let quote = self.peek()?;
+        self.advance();
+        loop {
+            match self.peek()? {
+                b'\\' => {
+                    self.advance();
+                    self.advance();
+                }
+                byte if byte == quote => break,
+                _ => self.advance(),
+            }
+        }
+        self.advance();
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).ok()?;
+        Some(if quote == b'"' {
+            serde_json::from_str(text).unwrap_or_else(|_| text[1..text.len() - 1].to_string())
+        } else {
+            text[1..text.len() - 1].to_string()
+        })
+    }
+
+    /// Scan a member's key, quoted or a JSON5 identifier.
+    fn key(&mut self) -> Option<String> {
+        if matches!(self.peek()?, b'"' | b'\'') {
+            return self.string();
+        }
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|byte| byte != b':' && !byte.is_ascii_whitespace())
+        {
+            self.advance();
+        }
+        let key = std::str::from_utf8(&self.bytes[start..self.position]).ok()?;
+        (!key.is_empty()).then(|| key.to_string())
+    }
+}
+
+fn node(
+    stack: &[(usize, String, bool, usize)],
+    line: usize,
+    column: usize,
+    item: bool,
+    inline: &str,
+) -> Node {
+    let pointer: String = stack.iter().flat_map(|open| ["/", &open.1]).collect();
+    Node {
+        pointer,
+        line,
+        column,
+        item,
+        inline: strip_comment(inline).to_string(),
+        end: line + 1,
+    }
+}
+
+/// Split `key: value` (or `key:`) into the unquoted key and the value text.
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let (key, after) = match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let close = text[1..].find(quote)? + 1;
+            let key = crate::yaml::from_str::<String>(&text[..=close]).ok()?;
+            (key, text[close + 1..].strip_prefix(':')?)
+        }
+        '{' | '[' => return None,
+        _ => {
+            let colon = text
+                .match_indices(':')
+                .map(|(index, _)| index)
+                .find(|&index| {
+                    text[index + 1..].is_empty() || text[index + 1..].starts_with(' ')
+                })?;
+            (text[..colon].trim_end().to_string(), &text[colon + 1..])
+        }
+    };
+    if !after.is_empty() && !after.starts_with(' ') {
+        return None;
+    }
+    Some((key, after.trim()))
+}
+
+fn strip_comment(text: &str) -> &str {
+    if text.starts_with('#') {
+        return "";
+    }
+    match text.find(" #") {
+        Some(comment) => text[..comment].trim_end(),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_map() {
+        let content = "\
+name: app # the name
+services:
+- name: web
+  ports:
+    - 80
+  env: |
+    not: a key
+- name: db
+
+\"a/b\": 1
+";
+        let map = SourceMap::parse(content);
+        let located: Vec<(&str, usize, usize, usize)> = map
+            .nodes
+            .iter()
+            .map(|node| (node.pointer.as_str(), node.line, node.column, node.end))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                ("/name", 0, 0, 1),
+                ("/services", 1, 0, 8),
+                ("/services/0", 2, 0, 7),
+                ("/services/0/name", 2, 2, 3),
+                ("/services/0/ports", 3, 2, 5),
+                ("/services/0/ports/0", 4, 4, 5),
+                ("/services/0/env", 5, 2, 7),
+                ("/services/1", 7, 0, 8),
+                ("/services/1/name", 7, 2, 8),
+                ("/a~1b", 9, 0, 10),
+            ]
+        );
+        assert_eq!(map.find("/name").unwrap().inline, "app");
+        assert_eq!(map.children("/services/0").count(), 3);
+    }
+
+    #[test]
+    fn test_json_source_map() {
+        let content = "{\n  \"name\": \"app\",\n  \"a/b\\\"c\": [\n    {\"x\": [1, \"]\"]},\n    // {\"y\": 2}\n    true\n  ]\n}\n";
+        let map = SourceMap::parse_json(content);
+        let located: Vec<(&str, usize, usize, usize)> = map
+            .nodes
+            .iter()
+            .map(|node| (node.pointer.as_str(), node.line, node.column, node.end))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                ("/name", 1, 2, 2),
+                ("/a~1b\"c", 2, 2, 7),
+                ("/a~1b\"c/0", 3, 4, 4),
+                ("/a~1b\"c/0/x", 3, 5, 4),
+                ("/a~1b\"c/0/x/0", 3, 11, 4),
+                ("/a~1b\"c/0/x/1", 3, 14, 4),
+                ("/a~1b\"c/1", 5, 4, 6),
+            ]
+        );
+        assert!(map.find("/a~1b\"c/1").unwrap().item);
+        // Mapping stops at the first error
+        assert_eq!(SourceMap::parse_json("[1, {\"a\" 2}, 3]").nodes.len(), 2);
+    }
+}
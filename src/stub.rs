@@ -0,0 +1,375 @@
+//! A local HTTP server serving schema fixtures, for tests.
+//!
+//! Tests of remote schemas should not depend on the network. [`StubServer`]
+//! listens on a free port of `127.0.0.1` and serves the schemas it is given,
+//! so tests can point validators at [`StubServer::url`] and cover caching,
+//! failures and slow servers deterministically:
+//!
+//! - Every response carries an `ETag`, and a request whose `If-None-Match`
+//!   matches it gets `304 Not Modified`.
+//! - [`StubServer::set_latency`] delays every response.
+//! - [`StubServer::fail_next`] makes the next requests fail with a status or
+//!   a dropped connection, and [`StubServer::set_offline`] drops every
+//!   connection until it is turned off again.
+//! - [`StubServer::requests`] lists the requests received, so tests can check
+//!   what was fetched from the cache instead of the server.
+//!
+//! Available with the `test-support` feature.
+
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How a request made to fail by [`StubServer::fail_next`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Respond with this HTTP status and an empty body.
+    Status(u16),
+    /// Close the connection without responding.
+    Disconnect,
+}
+
+/// A request received by a [`StubServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StubRequest {
+    /// The request method, e.g. `GET`.
+    pub method: String,
+    /// The request path, e.g. `/schemas/service.json`.
+    pub path: String,
+    /// The `If-None-Match` header, if the request had one.
+    pub if_none_match: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    routes: HashMap<String, String>,
+    latency: Duration,
+    faults: VecDeque<Fault>,
+    offline: bool,
+    requests: Vec<StubRequest>,
+}
+
+/// A local HTTP server for tests, stopped when dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use validate_json_schema::stub::StubServer;
+/// use validate_json_schema::{MemoryCacheStore, Validator};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let server = StubServer::start()?;
+/// server.serve("/port.json", r#"{"type": "integer"}"#);
+///
+/// let cache = Arc::new(MemoryCacheStore::new());
+/// for _ in 0..2 {
+///     let validator = Validator::from_url_with_cache(&server.url("/port.json"), cache.clone())?;
+///     assert!(validator.validate_json("8080").is_ok());
+/// }
+/// assert_eq!(server.hits("/port.json"), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct StubServer {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StubServer {
+    /// Start a server on a free port of `127.0.0.1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no port can be bound.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = Arc::clone(&state);
+            let stopped = Arc::clone(&stopped);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let state = Arc::clone(&state);
+                    thread::spawn(move || {
+                        // The client sees a failed request; nothing to report here
+                        let _ = respond(stream, &state);
+                    });
+                }
+            })
+        };
+        Ok(Self {
+            address,
+            state,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// The URL of `path` on this server, e.g. `http://127.0.0.1:41235/a.json`
+    /// for `/a.json`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.address, path.trim_start_matches('/'))
+    }
+
+    /// Serve `body` at `path`, replacing what was served there before.
+    pub fn serve(&self, path: &str, body: impl Into<String>) {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.lock().routes.insert(path, body.into());
+    }
+
+    /// Serve every file in `dir` at its file name, e.g. `schemas/a.json` at
+    /// `/a.json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or one of its files cannot be read.
+    pub fn serve_dir<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if let (true, Some(name)) = (path.is_file(), path.file_name()) {
+                self.serve(&name.to_string_lossy(), fs::read_to_string(&path)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delay every response by `latency`.
+    pub fn set_latency(&self, latency: Duration) {
+        self.lock().latency = latency;
+    }
+
+    /// Make the next `count` requests fail with `fault`, after any faults
+    /// already queued.
+    pub fn fail_next(&self, count: usize, fault: Fault) {
+        self.lock().faults.extend(std::iter::repeat_n(fault, count));
+    }
+
+    /// Drop every connection without responding while `offline` is set.
+    pub fn set_offline(&self, offline: bool) {
+        self.lock().offline = offline;
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<StubRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// How many requests for `path` were received.
+    pub fn hits(&self, path: &str) -> usize {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.lock()
+            .requests
+            .iter()
+            .filter(|request| request.path == path)
+            .count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake the accepting thread so it sees the flag
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for StubServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StubServer")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The entity tag of `body`.
+fn etag(body: &str) -> String {
+    format!(
+        "\"{}\"",
+        &hex::encode(Sha256::digest(body.as_bytes()))[..16]
+    )
+}
+
+fn respond(stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let mut if_none_match = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("if-none-match") {
+                if_none_match = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let (latency, fault, body) = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.requests.push(StubRequest {
+            method,
+            path: path.clone(),
+            if_none_match: if_none_match.clone(),
+        });
+        let fault = if state.offline {
+            Some(Fault::Disconnect)
+        } else {
+            state.faults.pop_front()
+        };
+        (state.latency, fault, state.routes.get(&path).cloned())
+    };
+    thread::sleep(latency);
+
+    let mut stream = stream;
+    let (status, headers, body) = match (fault, body) {
+        (Some(Fault::Disconnect), _) => return Ok(()),
+        (Some(Fault::Status(status)), _) => (status, String::new(), String::new()),
+        (None, None) => (404, String::new(), String::new()),
+        (None, Some(body)) => {
+            let tag = etag(&body);
+            let headers = format!("ETag: {}\r\nContent-Type: application/json\r\n", tag);
+            if if_none_match.as_deref() == Some(tag.as_str()) {
+                (304, headers, String::new())
+            } else {
+                (200, headers, body)
+            }
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        headers,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        304 => "Not Modified",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HttpConfig, MemoryCacheStore, SchemaLoader, Validator, ValidatorBuilder};
+    use std::time::Instant;
+
+    #[test]
+    fn test_stub_server_serves_and_caches() {
+        let server = StubServer::start().unwrap();
+        server.serve(
+            "/service.json",
+            r#"{"properties": {"port": {"$ref": "port.json"}}}"#,
+        );
+        server.serve("port.json", r#"{"type": "integer", "minimum": 1}"#);
+
+        let cache = Arc::new(MemoryCacheStore::new());
+        for _ in 0..2 {
+            let validator = ValidatorBuilder::new()
+                .cache(cache.clone())
+                .build_url(&server.url("/service.json"))
+                .unwrap();
+            assert!(validator.validate_json(r#"{"port": 80}"#).is_ok());
+            assert!(validator.validate_json(r#"{"port": 0}"#).is_err());
+        }
+        assert_eq!(server.hits("/service.json"), 1);
+        assert_eq!(server.hits("/port.json"), 1);
+
+        // Cached schemas keep working while the server is unreachable
+        server.set_offline(true);
+        let validator =
+            Validator::from_url_with_cache(&server.url("/service.json"), cache).unwrap();
+        assert!(validator.validate_json(r#"{"port": 80}"#).is_ok());
+        assert!(ValidatorBuilder::new()
+            .no_cache()
+            .build_url(&server.url("/service.json"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_stub_server_faults() {
+        let server = StubServer::start().unwrap();
+        server.serve("/schema.json", r#"{"type": "string"}"#);
+        server.fail_next(1, Fault::Status(503));
+        server.fail_next(1, Fault::Disconnect);
+
+        let loader = crate::HttpLoader::new().unwrap();
+        let url = server.url("/schema.json");
+        let error = loader.load(&url).unwrap_err().to_string();
+        assert!(error.contains("503"), "{}", error);
+        assert!(loader.load(&url).is_err());
+        assert_eq!(loader.load(&url).unwrap(), r#"{"type": "string"}"#);
+        assert!(loader.load(&server.url("/missing.json")).is_err());
+        assert_eq!(server.requests().len(), 4);
+
+        server.set_latency(Duration::from_millis(300));
+        let impatient =
+            crate::HttpLoader::with_config(&HttpConfig::new().timeout(Duration::from_millis(50)))
+                .unwrap();
+        let started = Instant::now();
+        assert!(impatient.load(&url).is_err());
+        assert!(started.elapsed() < Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_stub_server_etags() {
+        let server = StubServer::start().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.json"), r#"{"type": "object"}"#).unwrap();
+        server.serve_dir(dir.path()).unwrap();
+
+        let client = reqwest::blocking::Client::new();
+        let response = client.get(server.url("a.json")).send().unwrap();
+        assert_eq!(response.status(), 200);
+        let tag = response.headers()["etag"].to_str().unwrap().to_string();
+
+        let response = client
+            .get(server.url("a.json"))
+            .header("If-None-Match", &tag)
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 304);
+        assert_eq!(server.requests()[1].if_none_match, Some(tag));
+    }
+}
@@ -0,0 +1,403 @@
+//! Style rules for YAML documents.
+//!
+//! Schema validation checks what a configuration file says; these rules check
+//! how it is written: mapping keys in alphabetical order, a fixed indentation
+//! width and no long flow collections such as `ports: [80, 443, 8080, 8443]`.
+//! Every rule is off by default. [`fix_style`] rewrites the document line by
+//! line, so comments and the formatting of untouched lines are preserved.
+//!
+//! Like [`suggest_fixes`](crate::suggest_fixes), the rules only look at
+//! block-style mappings and sequences; keys inside flow collections are not
+//! checked.
+
+use crate::source::{Node, SourceMap};
+use crate::yaml;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Which style rules to enforce.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleRules {
+    /// Require the keys of every block mapping to be in alphabetical order.
+    pub sorted_keys: bool,
+    /// Require nested values to be indented by this many spaces.
+    ///
+    /// Sequences may be written at the same column as their key
+    /// (`key:\n- item`) or indented.
+    pub indent: Option<usize>,
+    /// Require flow collections (`[...]`, `{...}`) with more than this many
+    /// items to be written in block style.
+    pub max_flow_items: Option<usize>,
+}
+
+/// The kind of style problem found in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleIssueKind {
+    /// A mapping key sorts before the key above it.
+    UnsortedKey { key: String, previous: String },
+    /// A value is indented by the wrong number of spaces.
+    Indentation { expected: usize, found: usize },
+    /// A flow collection has too many items.
+    FlowCollection { items: usize, max: usize },
+}
+
+/// A single style finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleIssue {
+    /// What was found.
+    pub kind: StyleIssueKind,
+    /// JSON Pointer to the value concerned.
+    pub pointer: String,
+    /// 1-based line number.
+    pub line: usize,
+}
+
+impl fmt::Display for StyleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            StyleIssueKind::UnsortedKey { key, previous } => {
+                write!(f, "key '{}' should come before '{}'", key, previous)
+            }
+            StyleIssueKind::Indentation { expected, found } => {
+                write!(f, "indented by {} spaces, expected {}", found, expected)
+            }
+            StyleIssueKind::FlowCollection { items, max } => write!(
+                f,
+                "flow collection with {} items, use block style above {}",
+                items, max
+            ),
+        }
+    }
+}
+
+/// Check the YAML `content` against `rules`.
+///
+/// Issues are returned in line order.
+pub fn check_style(content: &str, rules: &StyleRules) -> Vec<StyleIssue> {
+    let map = SourceMap::parse(content);
+    let mut issues = Vec::new();
+
+    for node in &map.nodes {
+        if let Some(indent) = rules.indent {
+            let parent = parent(&map, node);
+            let expected =
+                parent.and_then(|parent| expected_column(node, parent, parent.column, indent));
+            if let Some(expected) = expected.filter(|&expected| expected != node.column) {
+                let base = parent.map_or(0, |parent| parent.column);
+                issues.push(StyleIssue {
+                    kind: StyleIssueKind::Indentation {
+                        expected: expected - base,
+                        found: node.column.saturating_sub(base),
+                    },
+                    pointer: node.pointer.clone(),
+                    line: node.line + 1,
+                });
+            }
+        }
+
+        if let Some(max) = rules.max_flow_items {
+            if let Some((_, items)) = flow_collection(node).filter(|&(_, items)| items > max) {
+                issues.push(StyleIssue {
+                    kind: StyleIssueKind::FlowCollection { items, max },
+                    pointer: node.pointer.clone(),
+                    line: node.line + 1,
+                });
+            }
+        }
+    }
+
+    if rules.sorted_keys {
+        for entries in mappings(&map) {
+            for pair in entries.windows(2) {
+                let (previous, key) = (key_of(pair[0]), key_of(pair[1]));
+                if key < previous {
+                    issues.push(StyleIssue {
+                        kind: StyleIssueKind::UnsortedKey { key, previous },
+                        pointer: pair[1].pointer.clone(),
+                        line: pair[1].line + 1,
+                    });
+                }
+            }
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// Rewrite the YAML `content` to follow `rules` where possible.
+///
+/// Flow collections are only expanded when they are the value of a mapping
+/// entry, and mappings whose first key shares a line with a sequence item's
+/// `- ` are not reordered. Remaining issues can be found with [`check_style`].
+pub fn fix_style(content: &str, rules: &StyleRules) -> String {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let trailing = content.ends_with('\n');
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    if let Some(max) = rules.max_flow_items {
+        expand_flow_collections(&mut lines, max, rules.indent.unwrap_or(2));
+    }
+    if let Some(indent) = rules.indent {
+        reindent(&mut lines, indent);
+    }
+    if rules.sorted_keys {
+        sort_keys(&mut lines);
+    }
+
+    let mut fixed = lines.join(newline);
+    if trailing {
+        fixed.push_str(newline);
+    }
+    fixed
+}
+
+/// Replace flow collections with more than `max` items by block style.
+fn expand_flow_collections(lines: &mut Vec<String>, max: usize, indent: usize) {
+    let map = SourceMap::parse(&lines.join("\n"));
+    // Edit from the bottom so earlier line numbers stay valid
+    for node in map.nodes.iter().rev().filter(|node| !node.item) {
+        let value = match flow_collection(node) {
+            Some((value, items)) if items > max => value,
+            _ => continue,
+        };
+        let rendered = match serde_yaml::to_string(&yaml::yaml_value(&value)) {
+            Ok(rendered) => rendered,
+            Err(_) => continue,
+        };
+        let line = &lines[node.line];
+        let start = match line[node.column..].find(node.inline.as_str()) {
+            Some(start) => node.column + start,
+            None => continue,
+        };
+        let comment = line[start + node.inline.len()..].trim();
+        let mut head = line[..start].trim_end().to_string();
+        if !comment.is_empty() {
+            head = format!("{} {}", head, comment);
+        }
+
+        let padding = " ".repeat(node.column + indent);
+        let mut replacement = vec![head];
+        replacement.extend(rendered.lines().map(|text| format!("{}{}", padding, text)));
+        lines.splice(node.line..node.line + 1, replacement);
+    }
+}
+
+/// Indent every nested value by `indent` spaces relative to its parent.
+fn reindent(lines: &mut [String], indent: usize) {
+    let map = SourceMap::parse(&lines.join("\n"));
+    let mut columns: HashMap<&str, usize> = HashMap::new();
+    let mut shifts: Vec<Option<isize>> = vec![None; lines.len()];
+
+    for node in &map.nodes {
+        let column = match parent(&map, node) {
+            Some(parent) => {
+                let parent_column = columns[parent.pointer.as_str()];
+                expected_column(node, parent, parent_column, indent)
+                    .unwrap_or(parent_column + node.column - parent.column)
+            }
+            None => node.column,
+        };
+        columns.insert(&node.pointer, column);
+        // Nested nodes come later and override the lines they cover
+        let shift = column as isize - node.column as isize;
+        let end = node.end.min(lines.len());
+        shifts[node.line..end].fill(Some(shift));
+    }
+
+    // Comments move with the value below them
+    let mut below = 0;
+    for index in (0..lines.len()).rev() {
+        let trimmed = lines[index].trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            shifts[index] = Some(below);
+        } else {
+            below = shifts[index].unwrap_or(0);
+        }
+    }
+
+    for (line, shift) in lines.iter_mut().zip(shifts) {
+        match shift.unwrap_or(0) {
+            0 => {}
+            shift if shift > 0 => line.insert_str(0, &" ".repeat(shift as usize)),
+            shift => {
+                let leading = line.len() - line.trim_start_matches(' ').len();
+                line.drain(..leading.min(-shift as usize));
+            }
+        }
+    }
+}
+
+/// Reorder the entries of every block mapping alphabetically, moving the
+/// comments above an entry with it.
+fn sort_keys(lines: &mut Vec<String>) {
+    // Sorting one mapping moves the lines of the ones inside it, so find the
+    // next unsorted mapping in a fresh map each time
+    for _ in 0..=lines.len() {
+        let map = SourceMap::parse(&lines.join("\n"));
+        let unsorted = mappings(&map).into_iter().find(|entries| {
+            let sortable = parent(&map, entries[0])
+                .is_none_or(|parent| !(parent.item && parent.line == entries[0].line));
+            sortable
+                && entries
+                    .windows(2)
+                    .any(|pair| key_of(pair[1]) < key_of(pair[0]))
+        });
+        let entries = match unsorted {
+            Some(entries) => entries,
+            None => return,
+        };
+
+        let mut blocks: Vec<(String, Vec<String>)> = Vec::new();
+        let mut start = entries[0].line;
+        for entry in &entries {
+            blocks.push((key_of(entry), lines[start..entry.end].to_vec()));
+            start = entry.end;
+        }
+        blocks.sort_by(|a, b| a.0.cmp(&b.0));
+        let sorted: Vec<String> = blocks.into_iter().flat_map(|(_, block)| block).collect();
+        lines.splice(entries[0].line..start, sorted);
+    }
+}
+
+/// The node containing `node`, if it is not at the top level.
+fn parent<'a>(map: &'a SourceMap, node: &Node) -> Option<&'a Node> {
+    let (pointer, _) = node.pointer.rsplit_once('/')?;
+    map.find(pointer)
+}
+
+/// The column `node` should start at, given its parent's column, or `None`
+/// if any column is acceptable.
+fn expected_column(
+    node: &Node,
+    parent: &Node,
+    parent_column: usize,
+    indent: usize,
+) -> Option<usize> {
+    if parent.item {
+        // Values after `- ` are placed by the item indicator
+        None
+    } else if node.item && node.column == parent.column {
+        // Sequences may be written at the column of their key
+        None
+    } else {
+        Some(parent_column + indent)
+    }
+}
+
+/// The entries of every block mapping, in source order.
+fn mappings(map: &SourceMap) -> Vec<Vec<&Node>> {
+    let mut groups: Vec<(&str, Vec<&Node>)> = Vec::new();
+    for node in map.nodes.iter().filter(|node| !node.item) {
+        let (parent, _) = node.pointer.rsplit_once('/').unwrap_or_default();
+        match groups.iter_mut().find(|(pointer, _)| *pointer == parent) {
+            Some((_, entries)) => entries.push(node),
+            None => groups.push((parent, vec![node])),
+        }
+    }
+    groups.into_iter().map(|(_, entries)| entries).collect()
+}
+
+/// The unescaped key of the mapping entry `node`.
+fn key_of(node: &Node) -> String {
+    let token = node.pointer.rsplit('/').next().unwrap_or_default();
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// The value and item count of a flow collection written on the node's line.
+fn flow_collection(node: &Node) -> Option<(Value, usize)> {
+    if !node.inline.starts_with(['[', '{']) {
+        return None;
+    }
+    let value: Value = crate::yaml::from_str(&node.inline).ok()?;
+    let items = match &value {
+        Value::Array(items) => items.len(),
+        Value::Object(entries) => entries.len(),
+        _ => return None,
+    };
+    Some((value, items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "\
+name: app
+# Deployment settings
+deploy:
+   replicas: 2
+   env: {A: 1, B: 2, C: 3}
+services:
+- name: web
+  ports: [80, 443]
+  image: nginx # pinned below
+";
+
+    #[test]
+    fn test_check_style() {
+        assert!(check_style(DOCUMENT, &StyleRules::default()).is_empty());
+
+        let rules = StyleRules {
+            sorted_keys: true,
+            indent: Some(2),
+            max_flow_items: Some(2),
+        };
+        let issues: Vec<String> = check_style(DOCUMENT, &rules)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "line 3: key 'deploy' should come before 'name'",
+                "line 4: indented by 3 spaces, expected 2",
+                "line 5: indented by 3 spaces, expected 2",
+                "line 5: flow collection with 3 items, use block style above 2",
+                "line 5: key 'env' should come before 'replicas'",
+                "line 9: key 'image' should come before 'ports'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_style() {
+        let rules = StyleRules {
+            sorted_keys: true,
+            indent: Some(2),
+            max_flow_items: Some(2),
+        };
+        let fixed = fix_style(DOCUMENT, &rules);
+        assert_eq!(
+            fixed,
+            "\
+# Deployment settings
+deploy:
+  env:
+    A: 1
+    B: 2
+    C: 3
+  replicas: 2
+name: app
+services:
+- name: web
+  ports: [80, 443]
+  image: nginx # pinned below
+"
+        );
+        // The mapping that starts on the item's line is left in place
+        let remaining = check_style(&fixed, &rules);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].pointer, "/services/0/image");
+        assert_eq!(fix_style(&fixed, &rules), fixed);
+    }
+}
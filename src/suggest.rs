@@ -0,0 +1,400 @@
+//! Suggested edits that bring an invalid YAML document closer to valid.
+//!
+//! This is experimental. [`suggest_fixes`] handles the failures whose fix is
+//! unambiguous: scalars that only need quoting, properties the schema does not
+//! allow, and missing required properties whose schema provides a `default`,
+//! an example, a `const` or an `enum`. Other failures are left alone, so
+//! applying every suggestion does not guarantee a valid document.
+//! [`unified_diff`] renders the suggestions against the original text.
+
+use crate::ambiguity::find_yaml_ambiguities;
+use crate::source::SourceMap;
+use crate::walk::escape_token;
+use crate::yaml;
+use crate::{refs, Validator};
+use jsonschema::error::ValidationErrorKind;
+use serde_json::Value;
+use std::fmt;
+
+/// One suggested change to a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// JSON Pointer to the value the change affects.
+    pub instance_path: String,
+    /// What the change does, e.g. `remove property 'debug'`.
+    pub description: String,
+    edit: Edit,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "root"
+        } else {
+            &self.instance_path
+        };
+        write!(f, "{}: {}", path, self.description)
+    }
+}
+
+/// Replace `remove` lines starting at the 0-based line `start` with `insert`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edit {
+    start: usize,
+    remove: usize,
+    insert: Vec<String>,
+}
+
+/// Suggest edits to the YAML `content` for failures against `validator`.
+///
+/// `schema` must be the schema `validator` was compiled from; it supplies
+/// the values for missing required properties. Only block-style YAML is
+/// edited, and suggestions never overlap. Returns nothing for a valid or
+/// unparseable document.
+pub fn suggest_fixes(validator: &Validator, schema: &Value, content: &str) -> Vec<Suggestion> {
+    let document: Value = match crate::yaml::from_str(content) {
+        Ok(document) => document,
+        Err(_) => return Vec::new(),
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let map = SourceMap::parse(content);
+    let mut suggestions = Vec::new();
+
+    for ambiguity in find_yaml_ambiguities(validator, content) {
+        if ambiguity.yaml11_only {
+            continue;
+        }
+        let line = match ambiguity.line {
+            Some(line) => line - 1,
+            None => continue,
+        };
+        if let Some(text) = quote_in_line(lines[line], &ambiguity.scalar, &ambiguity.quoted()) {
+            suggestions.push(Suggestion {
+                description: format!("quote {} as {}", ambiguity.scalar, ambiguity.quoted()),
+                instance_path: ambiguity.instance_path,
+                edit: Edit {
+                    start: line,
+                    remove: 1,
+                    insert: vec![text],
+                },
+            });
+        }
+    }
+
+    for error in validator.schema.iter_errors(&document) {
+        let object = error.instance_path.as_str().to_string();
+        match &error.kind {
+            ValidationErrorKind::AdditionalProperties { unexpected }
+            | ValidationErrorKind::UnevaluatedProperties { unexpected } => {
+                for name in unexpected {
+                    let pointer = format!("{}/{}", object, escape_token(name));
+                    if let Some(node) = map.find(&pointer) {
+                        suggestions.push(Suggestion {
+                            instance_path: pointer.clone(),
+                            description: format!("remove property '{}'", name),
+                            edit: Edit {
+                                start: node.line,
+                                remove: node.end - node.line,
+                                insert: Vec::new(),
+                            },
+                        });
+                    }
+                }
+            }
+            ValidationErrorKind::Required { property } => {
+                let name = match property.as_str() {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let (value, source) = match property_value(schema, error.schema_path.as_str(), name)
+                {
+                    Some(found) => found,
+                    None => continue,
+                };
+                if let Some(edit) = add_property(&map, &object, name, &value) {
+                    suggestions.push(Suggestion {
+                        instance_path: format!("{}/{}", object, escape_token(name)),
+                        description: format!(
+                            "add required property '{}' with its {} {}",
+                            name, source, value
+                        ),
+                        edit,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Insertions at the same line go innermost first, so nested properties
+    // stay inside their mapping
+    suggestions.sort_by_key(|suggestion| {
+        let edit = &suggestion.edit;
+        let indent = edit
+            .insert
+            .first()
+            .map_or(0, |line| line.len() - line.trim_start().len());
+        (edit.start, edit.remove > 0, std::cmp::Reverse(indent))
+    });
+    let mut accepted: Vec<Suggestion> = Vec::new();
+    for suggestion in suggestions {
+        let overlaps = accepted.iter().any(|previous| {
+            let end = previous.edit.start + previous.edit.remove;
+            suggestion.edit.start < end
+        });
+        if !overlaps {
+            accepted.push(suggestion);
+        }
+    }
+    accepted
+}
+
+/// Render `suggestions` as a unified diff against `content`, labelling both
+/// sides with `path`. Returns an empty string if there is nothing to change.
+pub fn unified_diff(content: &str, suggestions: &[Suggestion], path: &str) -> String {
+    const CONTEXT: usize = 3;
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    let edits: Vec<&Edit> = suggestions
+        .iter()
+        .map(|suggestion| &suggestion.edit)
+        .collect();
+
+    // Group edits whose context would overlap into one hunk
+    let mut hunks: Vec<Vec<&Edit>> = Vec::new();
+    for edit in edits {
+        match hunks.last_mut() {
+            Some(hunk)
+                if hunk
+                    .last()
+                    .is_some_and(|last| last.start + last.remove + 2 * CONTEXT >= edit.start) =>
+            {
+                hunk.push(edit)
+            }
+            _ => hunks.push(vec![edit]),
+        }
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", path, path);
+    let mut offset: isize = 0;
+    for hunk in hunks {
+        let first = hunk[0].start;
+        let last = hunk.last().map_or(first, |edit| edit.start + edit.remove);
+        let from = first.saturating_sub(CONTEXT);
+        let to = (last + CONTEXT).min(lines.len());
+
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0, 0);
+        let mut line = from;
+        for edit in &hunk {
+            for text in &lines[line..edit.start] {
+                body.push_str(&format!(" {}\n", text));
+            }
+            old_count += edit.start - line;
+            new_count += edit.start - line;
+            for text in &lines[edit.start..edit.start + edit.remove] {
+                body.push_str(&format!("-{}\n", text));
+            }
+            for text in &edit.insert {
+                body.push_str(&format!("+{}\n", text));
+            }
+            old_count += edit.remove;
+            new_count += edit.insert.len();
+            line = edit.start + edit.remove;
+        }
+        for text in &lines[line..to] {
+            body.push_str(&format!(" {}\n", text));
+        }
+        old_count += to - line;
+        new_count += to - line;
+
+        let old_start = if old_count == 0 { from } else { from + 1 };
+        let new_start = (from as isize + offset) as usize + usize::from(new_count > 0);
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n{}",
+            old_start, old_count, new_start, new_count, body
+        ));
+        offset += new_count as isize - old_count as isize;
+    }
+    diff
+}
+
+/// `line` with the plain scalar `scalar` after its key or `- ` replaced by `quoted`.
+fn quote_in_line(line: &str, scalar: &str, quoted: &str) -> Option<String> {
+    let code = match line.find(" #") {
+        Some(comment) => &line[..comment],
+        None => line,
+    };
+    let index = code.trim_end().rfind(scalar)?;
+    Some(format!(
+        "{}{}{}",
+        &line[..index],
+        quoted,
+        &line[index + scalar.len()..]
+    ))
+}
+
+/// The value to fill in for the required property `name`, taken from its
+/// schema, with a description of where it came from.
+///
+/// `required_path` is the keyword location of the failing `required`; local
+/// `$ref`s along it are followed to find the schema that declares `name`.
+fn property_value(
+    schema: &Value,
+    required_path: &str,
+    name: &str,
+) -> Option<(Value, &'static str)> {
+    let mut current = schema;
+    let tokens: Vec<&str> = required_path.split('/').skip(1).collect();
+    let (_, parents) = tokens.split_last()?;
+    for token in parents {
+        current = if *token == "$ref" {
+            let target = refs::deref(schema, current);
+            if std::ptr::eq(target, current) {
+                return None;
+            }
+            target
+        } else {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            match current {
+                Value::Object(object) => object.get(&token)?,
+                Value::Array(items) => items.get(token.parse::<usize>().ok()?)?,
+                _ => return None,
+            }
+        };
+    }
+    let property = refs::deref(
+        schema,
+        refs::deref(schema, current).get("properties")?.get(name)?,
+    );
+
+    if let Some(default) = property.get("default") {
+        return Some((default.clone(), "default"));
+    }
+    if let Some(example) = property
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|examples| examples.first())
+    {
+        return Some((example.clone(), "example"));
+    }
+    if let Some(value) = property.get("const") {
+        return Some((value.clone(), "constant value"));
+    }
+    property
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|values| values.first())
+        .map(|value| (value.clone(), "first allowed value"))
+}
+
+/// An edit adding `name: value` at the end of the block mapping at `object`.
+fn add_property(map: &SourceMap, object: &str, name: &str, value: &Value) -> Option<Edit> {
+    let siblings: Vec<_> = map.children(object).filter(|node| !node.item).collect();
+    let first = siblings.first()?;
+    let end = siblings.iter().map(|node| node.end).max()?;
+    let indent = " ".repeat(first.column);
+
+    let key = serde_yaml::to_string(&Value::String(name.to_string())).ok()?;
+    let rendered = serde_yaml::to_string(&yaml::yaml_value(value)).ok()?;
+    let rendered: Vec<&str> = rendered.lines().collect();
+    let insert = if value.is_object() || value.is_array() {
+        if value.as_object().is_some_and(|object| object.is_empty())
+            || value.as_array().is_some_and(|items| items.is_empty())
+        {
+            vec![format!(
+                "{}{}: {}",
+                indent,
+                key.trim_end(),
+                rendered.join("")
+            )]
+        } else {
+            let mut insert = vec![format!("{}{}:", indent, key.trim_end())];
+            insert.extend(rendered.iter().map(|line| format!("{}  {}", indent, line)));
+            insert
+        }
+    } else {
+        vec![format!(
+            "{}{}: {}",
+            indent,
+            key.trim_end(),
+            rendered.join(" ")
+        )]
+    };
+    Some(Edit {
+        start: end,
+        remove: 0,
+        insert,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_suggest_fixes() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "services"],
+            "additionalProperties": false,
+            "properties": {
+                "name": {"type": "string", "default": "app"},
+                "version": {"type": "string"},
+                "services": {"type": "array", "items": {"$ref": "#/definitions/service"}}
+            },
+            "definitions": {
+                "service": {
+                    "type": "object",
+                    "required": ["port"],
+                    "properties": {
+                        "image": {"type": "string"},
+                        "port": {"type": "integer", "examples": [8080]}
+                    }
+                }
+            }
+        });
+        let validator = Validator::from_value(&schema).unwrap();
+        let content = "\
+version: 1.20
+debug: true
+services:
+  - image: web
+    tags:
+      - a
+";
+
+        let suggestions = suggest_fixes(&validator, &schema, content);
+        let descriptions: Vec<String> = suggestions.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "/version: quote 1.20 as \"1.20\"",
+                "/debug: remove property 'debug'",
+                "/services/0/port: add required property 'port' with its example 8080",
+                "/name: add required property 'name' with its default \"app\"",
+            ]
+        );
+
+        assert_eq!(
+            unified_diff(content, &suggestions, "app.yml"),
+            "\
+--- app.yml
++++ app.yml
+@@ -1,6 +1,7 @@
+-version: 1.20
++version: \"1.20\"
+-debug: true
+ services:
+   - image: web
+     tags:
+       - a
++    port: 8080
++name: app
+"
+        );
+    }
+}
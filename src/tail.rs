@@ -0,0 +1,564 @@
+//! Validating a continuous stream of framed JSON messages.
+//!
+//! Message pipelines hand payloads on one after another rather than as
+//! files. [`MessageStream`] reads messages from any reader, validates each as
+//! it arrives and yields a [`MessageEvent`] per message, so a sidecar can
+//! report every payload without buffering the stream. Messages are framed as
+//! newline-delimited JSON or with a 4-byte big-endian length prefix.
+//!
+//! [`FollowReader`] reads a file that is still being written, such as a
+//! structured log, the way `tail -F` does: it starts at the end, waits for
+//! appended records and reopens the file when it is rotated or truncated.
+
+use crate::failure::ValidationFailure;
+use crate::{json5, ValidationError, Validator};
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How often [`FollowReader`] checks a file for new data by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How messages are delimited in a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// One JSON document per line (NDJSON); blank lines are skipped.
+    #[default]
+    Lines,
+    /// Each message is preceded by its length in bytes, as a 4-byte
+    /// big-endian unsigned integer.
+    LengthPrefixed,
+}
+
+impl FromStr for Framing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lines" | "ndjson" => Ok(Self::Lines),
+            "length-prefixed" => Ok(Self::LengthPrefixed),
+            other => Err(format!(
+                "unknown framing '{}': expected lines or length-prefixed",
+                other
+            )),
+        }
+    }
+}
+
+/// The outcome of validating one message.
+#[derive(Debug)]
+pub enum MessageOutcome {
+    /// The message is valid.
+    Valid,
+    /// The message was parsed but fails the schema.
+    Invalid(Vec<ValidationFailure>),
+    /// The message could not be checked: it is not UTF-8 or JSON, or it
+    /// exceeds the validator's limits.
+    Malformed(ValidationError),
+}
+
+/// One message's result in a stream.
+#[derive(Debug)]
+pub struct MessageEvent {
+    /// 1-based position of the message in the stream.
+    pub sequence: u64,
+    /// What happened.
+    pub outcome: MessageOutcome,
+}
+
+impl MessageEvent {
+    /// Whether the message is valid.
+    pub fn is_valid(&self) -> bool {
+        matches!(self.outcome, MessageOutcome::Valid)
+    }
+
+    /// The event as a JSON object, e.g. `{"sequence": 3, "valid": false,
+    /// "errors": [...]}`, for emitting one event per line.
+    pub fn to_json(&self) -> Value {
+        match &self.outcome {
+            MessageOutcome::Valid => json!({"sequence": self.sequence, "valid": true}),
+            MessageOutcome::Invalid(failures) => json!({
+                "sequence": self.sequence,
+                "valid": false,
+                "errors": failures.iter().map(ValidationFailure::to_json).collect::<Vec<_>>(),
+            }),
+            MessageOutcome::Malformed(error) => json!({
+                "sequence": self.sequence,
+                "valid": false,
+                "error": error.to_string(),
+            }),
+        }
+    }
+}
+
+/// Validates framed messages from a reader as they arrive.
+///
+/// Iteration ends at the end of the input; an I/O error, or input that ends
+/// in the middle of a length-prefixed message, is yielded once and ends it
+/// too.
+///
+/// # Examples
+///
+/// ```rust
+/// use validate_json_schema::tail::{Framing, MessageStream};
+/// use validate_json_schema::Validator;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(r#"{"required": ["id"]}"#)?;
+/// let input = "{\"id\": 1}\n{}\nnot json\n";
+///
+/// let valid: Vec<bool> = MessageStream::new(&validator, input.as_bytes(), Framing::Lines)
+///     .map(|event| event.map(|event| event.is_valid()))
+///     .collect::<Result<_, _>>()?;
+/// assert_eq!(valid, [true, false, false]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct MessageStream<'v, R> {
+    validator: &'v Validator,
+    reader: R,
+    framing: Framing,
+    sequence: u64,
+    done: bool,
+}
+
+impl<'v, R: BufRead> MessageStream<'v, R> {
+    /// Read messages framed with `framing` from `reader`.
+    pub fn new(validator: &'v Validator, reader: R, framing: Framing) -> Self {
+        Self {
+            validator,
+            reader,
+            framing,
+            sequence: 0,
+            done: false,
+        }
+    }
+
+    /// Read the next message, or `None` at the end of the input.
+    fn next_message(&mut self) -> io::Result<Option<Result<String, ValidationError>>> {
+        match self.framing {
+            Framing::Lines => loop {
+                let mut line = Vec::new();
+                let max = self.validator.limits.max_document_size;
+                let Some(length) = read_line(&mut self.reader, &mut line, max)? else {
+                    return Ok(None);
+                };
+                // Skip oversized lines instead of buffering them
+                if let Some(max) = max.filter(|max| length > *max) {
+                    return Ok(Some(Err(ValidationError::LimitExceeded(format!(
+                        "document is {} bytes, more than the maximum of {}",
+                        length, max
+                    )))));
+                }
+                if !line.iter().all(u8::is_ascii_whitespace) {
+                    return Ok(Some(decode(line)));
+                }
+            },
+            Framing::LengthPrefixed => {
+                let mut prefix = [0; 4];
+                match self.reader.read(&mut prefix[..1])? {
+                    0 => return Ok(None),
+                    _ => self.reader.read_exact(&mut prefix[1..])?,
+                }
+                let length = u32::from_be_bytes(prefix) as u64;
+                // Skip oversized messages instead of buffering them
+                if let Some(max) = self.validator.limits.max_document_size {
+                    if length > max as u64 {
+                        let skipped =
+                            io::copy(&mut (&mut self.reader).take(length), &mut io::sink())?;
+                        if skipped < length {
+                            return Err(truncated());
+                        }
+                        return Ok(Some(Err(ValidationError::LimitExceeded(format!(
+                            "document is {} bytes, more than the maximum of {}",
+                            length, max
+                        )))));
+                    }
+                }
+                let mut message = Vec::new();
+                (&mut self.reader).take(length).read_to_end(&mut message)?;
+                if (message.len() as u64) < length {
+                    return Err(truncated());
+                }
+                Ok(Some(decode(message)))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for MessageStream<'_, R> {
+    type Item = io::Result<MessageEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let message = match self.next_message() {
+            Ok(Some(message)) => message,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        self.sequence += 1;
+        let outcome = match message {
+            Ok(message) => check_message(self.validator, &message),
+            Err(error) => MessageOutcome::Malformed(error),
+        };
+        Some(Ok(MessageEvent {
+            sequence: self.sequence,
+            outcome,
+        }))
+    }
+}
+
+/// Parse and validate one message, within the validator's limits.
+pub(crate) fn check_message(validator: &Validator, message: &str) -> MessageOutcome {
+    let document = validator
+        .limits
+        .check_size(message)
+        .and_then(|()| json5::parse_json(message))
+        .and_then(|document| {
+            validator.limits.check_depth(&document)?;
+            Ok(document)
+        });
+    match document {
+        Ok(document) => {
+            let failures = validator.validate_detailed(&document);
+            if failures.is_empty() {
+                MessageOutcome::Valid
+            } else {
+                MessageOutcome::Invalid(failures)
+            }
+        }
+        Err(error) => MessageOutcome::Malformed(error),
+    }
+}
+
+pub(crate) fn decode(bytes: Vec<u8>) -> Result<String, ValidationError> {
+    String::from_utf8(bytes)
+        .map_err(|e| ValidationError::FileRead(io::Error::new(io::ErrorKind::InvalidData, e)))
+}
+
+/// Read a line, including its newline, into `line`, keeping at most one
+/// byte more than `max` of it. Returns the length of the whole line without
+/// its newline, or `None` at the end of the input.
+fn read_line(
+    reader: &mut impl BufRead,
+    line: &mut Vec<u8>,
+    max: Option<usize>,
+) -> io::Result<Option<usize>> {
+    let mut length = 0;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok((length > 0).then_some(length));
+        }
+        let (chunk, done) = match available.iter().position(|b| *b == b'\n') {
+            Some(end) => (&available[..=end], true),
+            None => (available, false),
+        };
+        let keep = match max {
+            Some(max) => chunk.len().min((max + 1).saturating_sub(line.len())),
+            None => chunk.len(),
+        };
+        line.extend_from_slice(&chunk[..keep]);
+        let consumed = chunk.len();
+        reader.consume(consumed);
+        length += consumed;
+        if done {
+            return Ok(Some(length - 1));
+        }
+    }
+}
+
+/// Reads a file as it grows, following it across rotation.
+///
+/// Reading starts at the current end of the file, after any partial last
+/// line, and blocks at the end until more is appended. When the path is
+/// replaced by a new file (log rotation by rename) or the file shrinks
+/// (rotation by truncation), reading continues from the start of the
+/// current file. Only the reader's buffer is held in memory, however long
+/// the file grows. Wrap it in a [`std::io::BufReader`] for
+/// [`MessageStream`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io::BufReader;
+/// use std::time::Duration;
+/// use validate_json_schema::tail::{Framing, FollowReader, MessageStream};
+/// use validate_json_schema::Validator;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let validator = Validator::new(r#"{"required": ["level"]}"#)?;
+/// let log = FollowReader::open("/var/log/app.ndjson")?.idle_timeout(Duration::from_secs(60));
+/// for event in MessageStream::new(&validator, BufReader::new(log), Framing::Lines) {
+///     println!("{}", event?.to_json());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FollowReader {
+    path: PathBuf,
+    file: File,
+    position: u64,
+    /// Whether the writer was in the middle of a record when reading
+    /// started, so the rest of it is skipped.
+    partial: bool,
+    poll_interval: Duration,
+    idle_timeout: Option<Duration>,
+    last_data: Instant,
+    rotations: u64,
+}
+
+impl FollowReader {
+    /// Open the file at `path` and position at its end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or read.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        let position = file.seek(SeekFrom::End(0))?;
+        let mut partial = false;
+        if position > 0 {
+            file.seek(SeekFrom::Start(position - 1))?;
+            let mut last = [0];
+            file.read_exact(&mut last)?;
+            partial = last[0] != b'\n';
+        }
+        Ok(Self {
+            path,
+            file,
+            position,
+            partial,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            idle_timeout: None,
+            last_data: Instant::now(),
+            rotations: 0,
+        })
+    }
+
+    /// Check for new data every `interval` at the end of the file, rather
+    /// than every 250ms.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// End the input once nothing has been appended for `timeout`. By
+    /// default the file is followed until the process is stopped.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// How many times the file was rotated or truncated while followed.
+    pub fn rotations(&self) -> u64 {
+        self.rotations
+    }
+
+    /// Reopen or rewind the file if it was rotated or truncated. Returns
+    /// whether it was.
+    fn check_rotation(&mut self) -> io::Result<bool> {
+        // Between a rename and the creation of the new file, keep the old
+        let Ok(current) = fs::metadata(&self.path) else {
+            return Ok(false);
+        };
+        if !same_file(&self.file.metadata()?, &current) {
+            self.file = File::open(&self.path)?;
+        } else if current.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+        } else {
+            return Ok(false);
+        }
+        self.position = 0;
+        self.rotations += 1;
+        Ok(true)
+    }
+}
+
+impl Read for FollowReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buf)?;
+            if read > 0 {
+                self.position += read as u64;
+                self.last_data = Instant::now();
+                if self.partial {
+                    let Some(end) = buf[..read].iter().position(|b| *b == b'\n') else {
+                        continue;
+                    };
+                    self.partial = false;
+                    buf.copy_within(end + 1..read, 0);
+                    match read - end - 1 {
+                        0 => continue,
+                        rest => return Ok(rest),
+                    }
+                }
+                return Ok(read);
+            }
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            if self.check_rotation()? {
+                self.partial = false;
+                continue;
+            }
+            if let Some(timeout) = self.idle_timeout {
+                if self.last_data.elapsed() >= timeout {
+                    return Ok(0);
+                }
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+/// Whether two metadata describe the same file.
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    (a.dev(), a.ino()) == (b.dev(), b.ino())
+}
+
+/// Whether two metadata describe the same file. Without inode numbers,
+/// rotation by rename is only noticed once the new file is shorter.
+#[cfg(not(unix))]
+fn same_file(_: &fs::Metadata, _: &fs::Metadata) -> bool {
+    true
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "input ended in the middle of a message",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes(validator: &Validator, input: &[u8], framing: Framing) -> Vec<Value> {
+        MessageStream::new(validator, input, framing)
+            .map(|event| event.unwrap().to_json())
+            .collect()
+    }
+
+    #[test]
+    fn test_lines() {
+        let validator = Validator::new(r#"{"properties": {"id": {"type": "integer"}}}"#).unwrap();
+        let events = outcomes(
+            &validator,
+            b"{\"id\": 1}\n\n  \n{\"id\": \"x\"}\n{\"id\": 2,}\n{\"id\": 3}",
+            Framing::Lines,
+        );
+        assert_eq!(events[0], json!({"sequence": 1, "valid": true}));
+        assert_eq!(events[1]["sequence"], 2);
+        assert_eq!(events[1]["errors"][0]["instance_path"], "/id");
+        assert_eq!(events[1]["errors"][0]["keyword"], "type");
+        assert!(events[2]["error"]
+            .as_str()
+            .unwrap()
+            .contains("trailing comma"));
+        assert_eq!(events[3], json!({"sequence": 4, "valid": true}));
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let validator = Validator::builder()
+            .max_document_size(8)
+            .build_str(r#"{"type": "array"}"#)
+            .unwrap();
+        let events = outcomes(
+            &validator,
+            b"[1, 2]\n[\"far too long\"]\n[1,2,3]",
+            Framing::Lines,
+        );
+        assert_eq!(events[0]["valid"], true);
+        assert_eq!(
+            events[1]["error"],
+            "Document limit exceeded: document is 16 bytes, more than the maximum of 8"
+        );
+        assert_eq!(events[2], json!({"sequence": 3, "valid": true}));
+    }
+
+    #[test]
+    fn test_follow() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.ndjson");
+        fs::write(&path, "{\"id\": 1}\n{\"id\":").unwrap();
+        let validator = Validator::new(r#"{"required": ["id"]}"#).unwrap();
+        let reader = FollowReader::open(&path)
+            .unwrap()
+            .poll_interval(Duration::from_millis(5))
+            .idle_timeout(Duration::from_millis(500));
+
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let append = |content: &str| {
+                    std::thread::sleep(Duration::from_millis(50));
+                    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+                    io::Write::write_all(&mut file, content.as_bytes()).unwrap();
+                };
+                // The end of the record in progress at the start is skipped
+                append(" 2}\n{\"id\": 3}\n");
+                std::thread::sleep(Duration::from_millis(50));
+                fs::rename(&path, path.with_extension("1")).unwrap();
+                fs::write(&path, "").unwrap();
+                append("{}\n{\"id\": 4}\n");
+                std::thread::sleep(Duration::from_millis(50));
+                fs::write(&path, "").unwrap();
+                append("{\"id\": 5}\n");
+            })
+        };
+        let mut reader = io::BufReader::new(reader);
+        let events: Vec<Value> = MessageStream::new(&validator, &mut reader, Framing::Lines)
+            .map(|event| event.unwrap().to_json())
+            .collect();
+        writer.join().unwrap();
+        let valid: Vec<&Value> = events.iter().map(|event| &event["valid"]).collect();
+        assert_eq!(valid, [true, false, true, true]);
+        assert_eq!(reader.get_ref().rotations(), 2);
+    }
+
+    #[test]
+    fn test_length_prefixed() {
+        let validator = Validator::builder()
+            .max_document_size(16)
+            .build_str(r#"{"type": "array"}"#)
+            .unwrap();
+        let mut input = Vec::new();
+        for message in ["[1, 2]", "{}", "[\"a rather long message\"]", "[]"] {
+            input.extend((message.len() as u32).to_be_bytes());
+            input.extend(message.as_bytes());
+        }
+        let events = outcomes(&validator, &input, Framing::LengthPrefixed);
+        let valid: Vec<&Value> = events.iter().map(|event| &event["valid"]).collect();
+        assert_eq!(valid, [true, false, false, true]);
+        assert!(events[2]["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Document limit exceeded"));
+
+        // A message cut short ends the stream with an error
+        input.extend(10u32.to_be_bytes());
+        input.extend(b"[1");
+        let mut stream = MessageStream::new(&validator, &input[..], Framing::LengthPrefixed);
+        assert_eq!(stream.by_ref().take_while(Result::is_ok).count(), 4);
+        assert!(stream.next().is_none());
+    }
+}
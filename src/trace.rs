@@ -0,0 +1,384 @@
+//! Tracing which schema keywords apply to one instance location.
+//!
+//! [`trace`] walks the schema alongside the instance down to a JSON Pointer,
+//! following `$ref`s and in-place applicators (`allOf`, `anyOf`, `oneOf`,
+//! `if`/`then`/`else`, `not`, `dependencies`), and reports every subschema that
+//! is evaluated against the value at that pointer with the outcome of each of
+//! its keywords.
+
+use crate::{refs, walk, ValidationError, Validator};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Maximum number of nested subschemas followed before giving up on a cycle.
+const MAX_DEPTH: usize = 64;
+
+/// Keywords that never affect validation and are left out of traces.
+const ANNOTATION_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "readOnly",
+    "writeOnly",
+    "definitions",
+    "$defs",
+];
+
+/// The outcome of evaluating one keyword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeywordOutcome {
+    /// The keyword accepted the value.
+    Passed,
+    /// The keyword rejected the value, with the validator's messages.
+    Failed(Vec<String>),
+    /// An `if` keyword whose condition matched, so `then` applies.
+    ConditionMet,
+    /// An `if` keyword whose condition did not match, so `else` applies.
+    ConditionNotMet,
+    /// A `then` or `else` keyword not selected by its `if`.
+    NotApplied,
+}
+
+/// A subschema evaluated against the traced location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// JSON Pointer of the subschema within the schema document.
+    pub schema_pointer: String,
+    /// The chain of keywords that led from the root schema to this subschema.
+    pub via: Vec<String>,
+    /// Each keyword of the subschema and its outcome, sorted by keyword.
+    pub keywords: Vec<(String, KeywordOutcome)>,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.schema_pointer)?;
+        if !self.via.is_empty() {
+            write!(f, " (via {})", self.via.join(" > "))?;
+        }
+        for (keyword, outcome) in &self.keywords {
+            write!(f, "\n  {}: ", keyword)?;
+            match outcome {
+                KeywordOutcome::Passed => write!(f, "pass")?,
+                KeywordOutcome::Failed(messages) => write!(f, "FAIL - {}", messages.join("; "))?,
+                KeywordOutcome::ConditionMet => write!(f, "condition met, `then` applies")?,
+                KeywordOutcome::ConditionNotMet => write!(f, "condition not met, `else` applies")?,
+                KeywordOutcome::NotApplied => write!(f, "not applied")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trace the evaluation of `schema` against the value at `pointer` in `instance`.
+///
+/// # Errors
+///
+/// Returns an error if `pointer` does not exist in the instance or a
+/// subschema cannot be compiled.
+pub fn trace(
+    schema: &Value,
+    instance: &Value,
+    pointer: &str,
+) -> Result<Vec<TraceStep>, ValidationError> {
+    if instance.pointer(pointer).is_none() {
+        return Err(ValidationError::InvalidPointer(format!(
+            "no value at '{}' in the document",
+            pointer
+        )));
+    }
+    let tokens: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect();
+
+    let mut tracer = Tracer {
+        root: schema,
+        steps: Vec::new(),
+    };
+    tracer.visit("", schema, instance, &tokens, &mut Vec::new(), 0)?;
+    Ok(tracer.steps)
+}
+
+struct Tracer<'a> {
+    root: &'a Value,
+    steps: Vec<TraceStep>,
+}
+
+impl<'a> Tracer<'a> {
+    fn visit(
+        &mut self,
+        schema_pointer: &str,
+        schema: &'a Value,
+        instance: &Value,
+        remaining: &[String],
+        via: &mut Vec<String>,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        if depth > MAX_DEPTH {
+            return Ok(());
+        }
+        let object = match schema {
+            Value::Object(object) => object,
+            // Boolean schemas have no keywords to report
+            _ => return Ok(()),
+        };
+
+        // Draft 7 ignores the siblings of `$ref`
+        if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+            let target_pointer = refs::local_pointer(reference).unwrap_or_default();
+            if let Some(target) = refs::resolve_local(self.root, reference) {
+                via.push(format!("#{}/$ref", schema_pointer));
+                let result =
+                    self.visit(&target_pointer, target, instance, remaining, via, depth + 1);
+                via.pop();
+                return result;
+            }
+            return Ok(());
+        }
+
+        if remaining.is_empty() {
+            let keywords = self.evaluate(schema_pointer, object, instance)?;
+            self.steps.push(TraceStep {
+                schema_pointer: schema_pointer.to_string(),
+                via: via.clone(),
+                keywords,
+            });
+        }
+
+        // In-place applicators see the same instance location
+        for keyword in ["allOf", "anyOf", "oneOf"] {
+            if let Some(Value::Array(branches)) = object.get(keyword) {
+                for (index, branch) in branches.iter().enumerate() {
+                    let pointer = format!("{}/{}/{}", schema_pointer, keyword, index);
+                    self.descend(&pointer, branch, instance, remaining, via, depth)?;
+                }
+            }
+        }
+        if let Some(condition) = object.get("if") {
+            let if_pointer = format!("{}/if", schema_pointer);
+            self.descend(&if_pointer, condition, instance, remaining, via, depth)?;
+            let branch = if self.is_valid(&if_pointer, instance)? {
+                "then"
+            } else {
+                "else"
+            };
+            if let Some(subschema) = object.get(branch) {
+                let pointer = format!("{}/{}", schema_pointer, branch);
+                self.descend(&pointer, subschema, instance, remaining, via, depth)?;
+            }
+        }
+        if let Some(negated) = object.get("not") {
+            let pointer = format!("{}/not", schema_pointer);
+            self.descend(&pointer, negated, instance, remaining, via, depth)?;
+        }
+        if let (Some(Value::Object(dependencies)), Value::Object(properties)) =
+            (object.get("dependencies"), instance)
+        {
+            for (name, dependency) in dependencies {
+                if dependency.is_object() && properties.contains_key(name) {
+                    let pointer = format!(
+                        "{}/dependencies/{}",
+                        schema_pointer,
+                        walk::escape_token(name)
+                    );
+                    self.descend(&pointer, dependency, instance, remaining, via, depth)?;
+                }
+            }
+        }
+
+        // Child applicators move one step closer to the traced location
+        let (token, rest) = match remaining.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+        match instance {
+            Value::Object(properties) => {
+                let child = &properties[token.as_str()];
+                let mut matched = false;
+                if let Some(subschema) = object.get("properties").and_then(|p| p.get(token)) {
+                    matched = true;
+                    let pointer = format!(
+                        "{}/properties/{}",
+                        schema_pointer,
+                        walk::escape_token(token)
+                    );
+                    self.descend(&pointer, subschema, child, rest, via, depth)?;
+                }
+                if let Some(Value::Object(patterns)) = object.get("patternProperties") {
+                    for (pattern, subschema) in patterns {
+                        if Regex::new(pattern).is_ok_and(|regex| regex.is_match(token)) {
+                            matched = true;
+                            let pointer = format!(
+                                "{}/patternProperties/{}",
+                                schema_pointer,
+                                walk::escape_token(pattern)
+                            );
+                            self.descend(&pointer, subschema, child, rest, via, depth)?;
+                        }
+                    }
+                }
+                if let (false, Some(subschema)) = (matched, object.get("additionalProperties")) {
+                    let pointer = format!("{}/additionalProperties", schema_pointer);
+                    self.descend(&pointer, subschema, child, rest, via, depth)?;
+                }
+            }
+            Value::Array(items) => {
+                let index: usize = token.parse().unwrap_or(usize::MAX);
+                let child = match items.get(index) {
+                    Some(child) => child,
+                    None => return Ok(()),
+                };
+                match object.get("items") {
+                    Some(Value::Array(tuple)) => match tuple.get(index) {
+                        Some(subschema) => {
+                            let pointer = format!("{}/items/{}", schema_pointer, index);
+                            self.descend(&pointer, subschema, child, rest, via, depth)?;
+                        }
+                        None => {
+                            if let Some(subschema) = object.get("additionalItems") {
+                                let pointer = format!("{}/additionalItems", schema_pointer);
+                                self.descend(&pointer, subschema, child, rest, via, depth)?;
+                            }
+                        }
+                    },
+                    Some(subschema) => {
+                        let pointer = format!("{}/items", schema_pointer);
+                        self.descend(&pointer, subschema, child, rest, via, depth)?;
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn descend(
+        &mut self,
+        schema_pointer: &str,
+        schema: &'a Value,
+        instance: &Value,
+        remaining: &[String],
+        via: &mut Vec<String>,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        self.visit(schema_pointer, schema, instance, remaining, via, depth + 1)
+    }
+
+    fn is_valid(&self, schema_pointer: &str, instance: &Value) -> Result<bool, ValidationError> {
+        let validator = Validator::from_subschema(self.root, schema_pointer)?;
+        Ok(validator.schema.is_valid(instance))
+    }
+
+    /// Evaluate each keyword of the subschema at `schema_pointer` against `instance`.
+    fn evaluate(
+        &self,
+        schema_pointer: &str,
+        object: &serde_json::Map<String, Value>,
+        instance: &Value,
+    ) -> Result<Vec<(String, KeywordOutcome)>, ValidationError> {
+        let validator = Validator::from_subschema(self.root, schema_pointer)?;
+        let mut failures: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for error in validator.schema.iter_errors(instance) {
+            // Skip the leading `/$ref` of the compiled subschema
+            let schema_path = error.schema_path.to_string();
+            let keyword = schema_path
+                .split('/')
+                .nth(2)
+                .unwrap_or_default()
+                .to_string();
+            let location = error.instance_path.to_string();
+            let message = if location.is_empty() {
+                error.to_string()
+            } else {
+                format!("{}: {}", location, error)
+            };
+            failures.entry(keyword).or_default().push(message);
+        }
+
+        let condition = match object.get("if") {
+            Some(_) => Some(self.is_valid(&format!("{}/if", schema_pointer), instance)?),
+            None => None,
+        };
+
+        let mut keywords = Vec::new();
+        for keyword in object.keys() {
+            if ANNOTATION_KEYWORDS.contains(&keyword.as_str()) {
+                continue;
+            }
+            let outcome = match (keyword.as_str(), condition) {
+                ("if", Some(true)) => KeywordOutcome::ConditionMet,
+                ("if", _) => KeywordOutcome::ConditionNotMet,
+                ("then", Some(false)) | ("else", Some(true)) | ("then" | "else", None) => {
+                    KeywordOutcome::NotApplied
+                }
+                _ => match failures.remove(keyword.as_str()) {
+                    Some(messages) => KeywordOutcome::Failed(messages),
+                    None => KeywordOutcome::Passed,
+                },
+            };
+            keywords.push((keyword.clone(), outcome));
+        }
+        Ok(keywords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_trace_through_refs_and_conditionals() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "services": {
+                    "type": "array",
+                    "items": {"$ref": "#/definitions/service"}
+                }
+            },
+            "definitions": {
+                "service": {
+                    "type": "object",
+                    "properties": {"env": {"type": "string"}},
+                    "if": {"properties": {"type": {"const": "web"}}},
+                    "then": {"properties": {"env": {"enum": ["node", "python"]}}},
+                    "else": {"properties": {"env": {"const": "docker"}}}
+                }
+            }
+        });
+        let instance = json!({"services": [{"type": "web", "env": "ruby"}]});
+
+        let steps = trace(&schema, &instance, "/services/0/env").unwrap();
+        let pointers: Vec<&str> = steps.iter().map(|s| s.schema_pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            [
+                "/definitions/service/then/properties/env",
+                "/definitions/service/properties/env"
+            ]
+        );
+        assert_eq!(steps[1].via, ["#/properties/services/items/$ref"]);
+        assert!(matches!(steps[0].keywords[0].1, KeywordOutcome::Failed(_)));
+        assert_eq!(steps[1].keywords[0].1, KeywordOutcome::Passed);
+
+        let steps = trace(&schema, &instance, "/services/0").unwrap();
+        let service = &steps[0].keywords;
+        assert!(service.contains(&("if".to_string(), KeywordOutcome::ConditionMet)));
+        assert!(matches!(
+            service.iter().find(|(k, _)| k == "then"),
+            Some((_, KeywordOutcome::Failed(_)))
+        ));
+
+        assert!(trace(&schema, &instance, "/services/3").is_err());
+    }
+}
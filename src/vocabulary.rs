@@ -0,0 +1,118 @@
+//! Checks that a schema's required vocabularies are implemented.
+//!
+//! Draft 2019-09 and later let a meta-schema declare the vocabularies its
+//! schemas use through `$vocabulary`, marking each as required (`true`) or
+//! optional (`false`). Keywords from vocabularies the validator does not know
+//! are otherwise silently ignored, so [`check_vocabularies`] lets callers fail
+//! instead.
+
+use crate::resolve::Resolver;
+use crate::{Draft, ValidationError};
+use serde_json::Value;
+
+/// Vocabularies implemented by the validator.
+pub const SUPPORTED_VOCABULARIES: &[&str] = &[
+    "https://json-schema.org/draft/2019-09/vocab/core",
+    "https://json-schema.org/draft/2019-09/vocab/applicator",
+    "https://json-schema.org/draft/2019-09/vocab/validation",
+    "https://json-schema.org/draft/2019-09/vocab/meta-data",
+    "https://json-schema.org/draft/2019-09/vocab/format",
+    "https://json-schema.org/draft/2019-09/vocab/content",
+    "https://json-schema.org/draft/2020-12/vocab/core",
+    "https://json-schema.org/draft/2020-12/vocab/applicator",
+    "https://json-schema.org/draft/2020-12/vocab/unevaluated",
+    "https://json-schema.org/draft/2020-12/vocab/validation",
+    "https://json-schema.org/draft/2020-12/vocab/meta-data",
+    "https://json-schema.org/draft/2020-12/vocab/format-annotation",
+    "https://json-schema.org/draft/2020-12/vocab/format-assertion",
+    "https://json-schema.org/draft/2020-12/vocab/content",
+];
+
+/// The vocabularies a meta-schema marks as required but the validator does not implement.
+pub fn unsupported_vocabularies(meta_schema: &Value) -> Vec<String> {
+    meta_schema
+        .get("$vocabulary")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter(|(uri, required)| {
+            required.as_bool() == Some(true)
+                && !SUPPORTED_VOCABULARIES.contains(&uri.trim_end_matches('#'))
+        })
+        .map(|(uri, _)| uri.clone())
+        .collect()
+}
+
+/// Fail if `schema`'s meta-schema requires a vocabulary the validator does not implement.
+///
+/// Schemas declaring one of the standard drafts (or no `$schema` at all) always
+/// pass. A custom meta-schema is fetched through the schema cache.
+///
+/// # Errors
+///
+/// Returns [`ValidationError::SchemaCompilation`] naming the unsupported
+/// vocabularies, or if the meta-schema cannot be loaded.
+pub fn check_vocabularies(schema: &Value) -> Result<(), ValidationError> {
+    check_with(schema, &Resolver::default())
+}
+
+pub(crate) fn check_with(schema: &Value, resolver: &Resolver) -> Result<(), ValidationError> {
+    let meta_uri = match schema.get("$schema").and_then(Value::as_str) {
+        Some(uri) if Draft::from_uri(uri).is_none() => uri,
+        _ => return Ok(()),
+    };
+    let meta_schema = resolver.load_document(meta_uri).map_err(|e| {
+        ValidationError::SchemaCompilation(format!(
+            "cannot load meta-schema '{}' to check its vocabularies: {}",
+            meta_uri, e
+        ))
+    })?;
+
+    let unsupported = unsupported_vocabularies(&meta_schema);
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::SchemaCompilation(format!(
+            "meta-schema '{}' requires unsupported vocabularies: {}",
+            meta_uri,
+            unsupported.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve::CachePolicy;
+    use crate::{CacheStore, MemoryCacheStore};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_check_vocabularies() {
+        let cache = MemoryCacheStore::new();
+        let meta = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$vocabulary": {
+                "https://json-schema.org/draft/2020-12/vocab/core": true,
+                "https://json-schema.org/draft/2020-12/vocab/validation": true,
+                "https://example.com/vocab/units": true,
+                "https://example.com/vocab/docs": false
+            }
+        });
+        cache
+            .put("https://example.com/meta", &meta.to_string())
+            .unwrap();
+        let resolver = Resolver::default().with_cache(CachePolicy::Store(Arc::new(cache)));
+
+        let error = check_with(&json!({"$schema": "https://example.com/meta"}), &resolver)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("https://example.com/vocab/units"));
+        assert!(!error.contains("https://example.com/vocab/docs"));
+
+        let standard = json!({"$schema": "https://json-schema.org/draft/2020-12/schema"});
+        assert!(check_with(&standard, &resolver).is_ok());
+        assert!(check_with(&json!({"type": "string"}), &resolver).is_ok());
+    }
+}
@@ -0,0 +1,142 @@
+//! Traversal of the subschemas contained in a schema document.
+
+use serde_json::{Map, Value};
+
+/// Keywords whose value is a single subschema.
+pub(crate) const SINGLE_SCHEMA_KEYWORDS: &[&str] = &[
+    "additionalItems",
+    "additionalProperties",
+    "contains",
+    "contentSchema",
+    "else",
+    "if",
+    "items",
+    "not",
+    "propertyNames",
+    "then",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords whose value is an array of subschemas.
+pub(crate) const ARRAY_SCHEMA_KEYWORDS: &[&str] =
+    &["allOf", "anyOf", "items", "oneOf", "prefixItems"];
+
+/// Keywords whose value is an object mapping names to subschemas.
+pub(crate) const MAP_SCHEMA_KEYWORDS: &[&str] = &[
+    "$defs",
+    "definitions",
+    "dependencies",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+/// Escape a single JSON Pointer reference token.
+pub(crate) fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// The entries of `object` sorted by key. serde_json keeps them sorted itself
+/// unless its `preserve_order` feature is on, which the `bson` dependency enables.
+pub(crate) fn sorted_entries(object: &Map<String, Value>) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+/// The direct subschemas of `schema`, paired with their pointer relative to it.
+///
+/// `dependencies` values that are property lists rather than schemas are skipped.
+pub(crate) fn subschemas(schema: &Value) -> Vec<(String, &Value)> {
+    let mut children = Vec::new();
+    let object = match schema.as_object() {
+        Some(object) => object,
+        None => return children,
+    };
+
+    for (keyword, value) in sorted_entries(object) {
+        let keyword = keyword.as_str();
+        match value {
+            Value::Object(_) | Value::Bool(_) if SINGLE_SCHEMA_KEYWORDS.contains(&keyword) => {
+                children.push((format!("/{}", escape_token(keyword)), value));
+            }
+            Value::Array(items) if ARRAY_SCHEMA_KEYWORDS.contains(&keyword) => {
+                for (index, item) in items.iter().enumerate() {
+                    children.push((format!("/{}/{}", escape_token(keyword), index), item));
+                }
+            }
+            Value::Object(entries) if MAP_SCHEMA_KEYWORDS.contains(&keyword) => {
+                for (name, entry) in sorted_entries(entries) {
+                    if entry.is_object() || entry.is_boolean() {
+                        children.push((
+                            format!("/{}/{}", escape_token(keyword), escape_token(name)),
+                            entry,
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    children
+}
+
+/// Visit `schema` and every nested subschema depth-first.
+///
+/// The callback receives the JSON Pointer of each subschema relative to `schema`
+/// (the empty string for `schema` itself).
+pub(crate) fn visit<'a, F>(schema: &'a Value, visitor: &mut F)
+where
+    F: FnMut(&str, &'a Value),
+{
+    visit_at(schema, String::new(), visitor);
+}
+
+fn visit_at<'a, F>(schema: &'a Value, pointer: String, visitor: &mut F)
+where
+    F: FnMut(&str, &'a Value),
+{
+    visitor(&pointer, schema);
+    for (relative, child) in subschemas(schema) {
+        visit_at(child, format!("{}{}", pointer, relative), visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_visit_subschemas() {
+        let schema = json!({
+            "properties": {
+                "a/b": {"type": "string"},
+                "list": {"items": [{"type": "integer"}, true]}
+            },
+            "dependencies": {"a/b": ["list"]},
+            "not": {"enum": [1]},
+            "enum": [{"type": "not a schema"}]
+        });
+
+        let mut pointers = Vec::new();
+        visit(&schema, &mut |pointer, _| {
+            pointers.push(pointer.to_string())
+        });
+        pointers.sort();
+
+        assert_eq!(
+            pointers,
+            vec![
+                "",
+                "/not",
+                "/properties/a~1b",
+                "/properties/list",
+                "/properties/list/items/0",
+                "/properties/list/items/1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Validating XML documents against JSON Schemas.
+//!
+//! Legacy configuration often lives in XML. [`parse_xml`] converts a document
+//! into a JSON value with the common element-to-object mapping, so the same
+//! JSON Schemas can describe it:
+//!
+//! - The document becomes an object with the root element's name as its only
+//!   key.
+//! - An element with neither child elements nor attributes becomes its text,
+//!   trimmed.
+//! - Any other element becomes an object of its child elements by name, with
+//!   its text under [`XmlOptions::text_key`]. A name that appears more than
+//!   once becomes an array, as do the names in [`XmlOptions::arrays`].
+//! - Attributes are mapped as [`XmlOptions::attributes`] chooses.
+//!
+//! Namespaces are dropped: elements and attributes are keyed by their local
+//! names. Comments and processing instructions are skipped, and documents
+//! with a DTD are rejected, so entity expansion cannot blow up.
+//!
+//! Available with the `xml` feature.
+
+use crate::config::coerce;
+use crate::{ValidationError, ValidationFailure, Validator};
+use roxmltree::{Document, Node};
+use serde_json::{Map, Value};
+
+/// How [`parse_xml`] maps the attributes of an element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttributeMode {
+    /// Properties named with [`XmlOptions::attribute_prefix`], e.g. `@id`,
+    /// next to the child elements.
+    #[default]
+    Prefixed,
+    /// Properties named like child elements, so `<port number="80"/>` and
+    /// `<port><number>80</number></port>` give the same value.
+    Merged,
+    /// Left out of the value.
+    Ignored,
+}
+
+/// Settings for [`parse_xml`] and [`validate_xml`].
+#[derive(Debug, Clone)]
+pub struct XmlOptions {
+    /// How attributes are mapped.
+    pub attributes: AttributeMode,
+    /// Prefix of attribute properties in [`AttributeMode::Prefixed`], `@` by
+    /// default.
+    pub attribute_prefix: String,
+    /// Property holding the text of elements that become objects, `#text` by
+    /// default.
+    pub text_key: String,
+    /// Element names that always become arrays, even when they appear once.
+    pub arrays: Vec<String>,
+    /// Convert text to the types the schema declares for its location, as
+    /// [`env_config_from`](crate::env_config_from) does.
+    pub infer_types: bool,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self {
+            attributes: AttributeMode::default(),
+            attribute_prefix: "@".to_string(),
+            text_key: "#text".to_string(),
+            arrays: Vec::new(),
+            infer_types: false,
+        }
+    }
+}
+
+/// Parse XML `content` into a JSON value. Every value is a string.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use validate_json_schema::xml::{parse_xml, XmlOptions};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let content = r#"<server name="web"><port>80</port><port>443</port></server>"#;
+///
+/// assert_eq!(
+///     parse_xml(content, &XmlOptions::default())?,
+///     json!({"server": {"@name": "web", "port": ["80", "443"]}})
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the content is not well-formed XML or has a DTD.
+pub fn parse_xml(content: &str, options: &XmlOptions) -> Result<Value, ValidationError> {
+    let document =
+        Document::parse(content).map_err(|e| ValidationError::XmlParse(e.to_string()))?;
+    let root = document.root_element();
+    let mut value = Map::new();
+    value.insert(
+        root.tag_name().name().to_string(),
+        element_value(root, options),
+    );
+    Ok(Value::Object(value))
+}
+
+/// Validate XML `content` against `validator`, built from `schema`, and
+/// return the failures.
+///
+/// # Errors
+///
+/// Returns an error if the content is not well-formed XML, as for
+/// [`parse_xml`].
+pub fn validate_xml(
+    validator: &Validator,
+    schema: &Value,
+    content: &str,
+    options: &XmlOptions,
+) -> Result<Vec<ValidationFailure>, ValidationError> {
+    let mut document = parse_xml(content, options)?;
+    if options.infer_types {
+        coerce(schema, validator.draft(), &mut document, "");
+    }
+    Ok(validator.validate_detailed(&document))
+}
+
+fn element_value(element: Node<'_, '_>, options: &XmlOptions) -> Value {
+    let mut properties: Vec<(String, Value)> = Vec::new();
+    match options.attributes {
+        AttributeMode::Prefixed => properties.extend(element.attributes().map(|attribute| {
+            (
+                format!("{}{}", options.attribute_prefix, attribute.name()),
+                Value::String(attribute.value().to_string()),
+            )
+        })),
+        AttributeMode::Merged => properties.extend(element.attributes().map(|attribute| {
+            (
+                attribute.name().to_string(),
+                Value::String(attribute.value().to_string()),
+            )
+        })),
+        AttributeMode::Ignored => {}
+    }
+    let mut text = String::new();
+    for child in element.children() {
+        if child.is_element() {
+            properties.push((
+                child.tag_name().name().to_string(),
+                element_value(child, options),
+            ));
+        } else if let Some(fragment) = child.text().filter(|_| child.is_text()) {
+            text.push_str(fragment);
+        }
+    }
+    let text = text.trim();
+
+    if properties.is_empty() {
+        return Value::String(text.to_string());
+    }
+    let mut object = Map::new();
+    for (name, value) in properties {
+        match object.get_mut(&name) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None if options.arrays.contains(&name) => {
+                object.insert(name, Value::Array(vec![value]));
+            }
+            None => {
+                object.insert(name, value);
+            }
+        }
+    }
+    if !text.is_empty() {
+        object.insert(options.text_key.clone(), Value::String(text.to_string()));
+    }
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONFIG: &str = r#"<?xml version="1.0"?>
+        <!-- deployed by ops -->
+        <config xmlns:x="urn:example" version="2">
+          <server x:name="web">
+            <port>8080</port>
+            <tls enabled="true"/>
+          </server>
+          <server name="api"><port>9090</port></server>
+          <note lang="en">Restart <![CDATA[<now>]]></note>
+        </config>"#;
+
+    #[test]
+    fn test_parse_xml() {
+        assert_eq!(
+            parse_xml(CONFIG, &XmlOptions::default()).unwrap(),
+            json!({"config": {
+                "@version": "2",
+                "server": [
+                    {"@name": "web", "port": "8080", "tls": {"@enabled": "true"}},
+                    {"@name": "api", "port": "9090"}
+                ],
+                "note": {"@lang": "en", "#text": "Restart <now>"}
+            }})
+        );
+
+        let options = XmlOptions {
+            attributes: AttributeMode::Merged,
+            arrays: vec!["port".to_string()],
+            ..XmlOptions::default()
+        };
+        let merged = parse_xml(CONFIG, &options).unwrap();
+        assert_eq!(
+            merged["config"]["server"][1],
+            json!({"name": "api", "port": ["9090"]})
+        );
+
+        let options = XmlOptions {
+            attributes: AttributeMode::Ignored,
+            ..XmlOptions::default()
+        };
+        let ignored = parse_xml(CONFIG, &options).unwrap();
+        assert_eq!(ignored["config"]["server"][0]["tls"], "");
+        assert_eq!(ignored["config"]["note"], "Restart <now>");
+
+        assert!(parse_xml("<config><server></config>", &XmlOptions::default()).is_err());
+        let dtd = r#"<!DOCTYPE config [<!ENTITY a "aaaa">]><config>&a;</config>"#;
+        assert!(parse_xml(dtd, &XmlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_validate_xml() {
+        let schema = json!({"properties": {"config": {"properties": {
+            "server": {
+                "type": "array",
+                "items": {"properties": {"port": {"type": "integer", "maximum": 9000}}}
+            }
+        }}}});
+        let validator = Validator::from_value(&schema).unwrap();
+
+        let options = XmlOptions::default();
+        let failures = validate_xml(&validator, &schema, CONFIG, &options).unwrap();
+        assert_eq!(failures.len(), 2);
+
+        let options = XmlOptions {
+            infer_types: true,
+            ..XmlOptions::default()
+        };
+        let failures = validate_xml(&validator, &schema, CONFIG, &options).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].instance_path, "/config/server/1/port");
+    }
+}
@@ -0,0 +1,992 @@
+//! Parsing YAML with a bound on the work adversarial input can cause.
+//!
+//! libyaml, which `serde_yaml` builds on, keeps a possible key for every open
+//! flow collection and rechecks all of them for each token, so a document
+//! opening thousands of `[` or `{` takes time quadratic in their number before
+//! `serde_yaml`'s recursion limit rejects it. Such documents are rejected
+//! before they reach the parser instead.
+//!
+//! Aliases are bounded as well. `serde_yaml` expands every alias into a copy
+//! of its anchored node, so a few lines of nested aliases ("billion laughs")
+//! can expand into billions of nodes. Before a document with aliases is
+//! parsed, its nodes are counted, without building anything, against
+//! [`AliasLimits`].
+//!
+//! `serde_yaml` resolves the tags of the core schema (`!!str`, `!!int`, ...)
+//! itself, reads values with other global tags such as `!!binary` as if they
+//! had none, and cannot represent local tags such as `!Ref` in JSON at all.
+//! [`from_str_tagged`] finds both kinds, converts those with a registered
+//! [`TagHandler`] and applies a [`YamlTags`] policy to the rest.
+//!
+//! `serde_yaml` types plain scalars by the YAML 1.2 core schema, where `NO`
+//! and `on` are strings, except that it also keeps integers with a leading
+//! zero such as `0755` as strings, where the core schema reads the number
+//! 755. [`resolve_yaml11`] retypes them as YAML 1.1 parsers, still common in
+//! deployment tools, read them.
+//!
+//! Directives are checked up front too, since libyaml's errors for them
+//! rarely name the problem: a `%YAML` or `%TAG` line must be followed by a
+//! `---` marker, and only YAML 1.x is understood. Reserved directives, which
+//! the YAML specification says to ignore, are ignored instead of rejected.
+
+use crate::source::SourceMap;
+use crate::walk::escape_token;
+use crate::{ValidationError, YamlTags};
+use base64::Engine;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, Visitor};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+
+/// Deepest flow collection nesting accepted. `serde_yaml` refuses to
+/// deserialize anything nested deeper than this anyway.
+const MAX_FLOW_DEPTH: usize = 128;
+
+/// Nodes expanding aliases may add to a document unless
+/// [`ValidatorBuilder::max_alias_expansion`](crate::ValidatorBuilder::max_alias_expansion)
+/// sets another limit. `serde_yaml` gives up after 100 alias expansions per
+/// event of the document, and each expansion adds at least one node, so a
+/// much higher default would never be reached before it.
+pub(crate) const DEFAULT_MAX_ALIAS_EXPANSION: usize = 10_000;
+
+/// Bounds on the aliases of a YAML document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AliasLimits {
+    /// Maximum number of aliases (`*name`) in the document.
+    pub(crate) max_aliases: Option<usize>,
+    /// Maximum number of nodes expanding the aliases may add.
+    pub(crate) max_expansion: usize,
+}
+
+impl Default for AliasLimits {
+    fn default() -> Self {
+        Self {
+            max_aliases: None,
+            max_expansion: DEFAULT_MAX_ALIAS_EXPANSION,
+        }
+    }
+}
+
+/// Parse YAML `content`, rejecting deeply nested flow collections up front.
+pub(crate) fn from_str<T: DeserializeOwned>(content: &str) -> Result<T, ValidationError> {
+    from_str_limited(content, AliasLimits::default())
+}
+
+/// Parse YAML `content` as [`from_str`] does, with `aliases` instead of the
+/// default alias limits.
+pub(crate) fn from_str_limited<T: DeserializeOwned>(
+    content: &str,
+    aliases: AliasLimits,
+) -> Result<T, ValidationError> {
+    check_flow_depth(content)?;
+    let content = check_directives(content)?;
+    check_aliases(&content, aliases)?;
+    Ok(serde_yaml::from_str(&content)?)
+}
+
+/// Tags of the core schema, without their `!!` handle.
+const CORE_TAGS: &[&str] = &["str", "int", "float", "bool", "null", "map", "seq"];
+
+/// Prefix of the tags the `!!` handle abbreviates.
+const YAML_TAG_PREFIX: &str = "tag:yaml.org,2002:";
+
+/// Characters of a value shown in errors about its tag.
+const PREVIEW_LENGTH: usize = 32;
+
+/// Converts the value of a node with a tag, written as in the document (e.g.
+/// `!Ref`), into the value to validate, or explains why it cannot.
+pub(crate) type TagHandler =
+    Arc<dyn Fn(&serde_json::Value) -> Result<serde_json::Value, String> + Send + Sync>;
+
+/// The functions of CloudFormation's short-form tags; `!Ref` and
+/// `!Condition` are not functions and keep their names.
+const CLOUDFORMATION_FUNCTIONS: &[&str] = &[
+    "And",
+    "Base64",
+    "Cidr",
+    "Equals",
+    "FindInMap",
+    "GetAZs",
+    "GetAtt",
+    "If",
+    "ImportValue",
+    "Join",
+    "Length",
+    "Not",
+    "Or",
+    "Select",
+    "Split",
+    "Sub",
+    "ToJsonString",
+    "Transform",
+];
+
+/// Handlers converting CloudFormation's short-form tags into the long form of
+/// JSON templates: `!Ref Bucket` becomes `{"Ref": "Bucket"}`, `!Sub "${A}"`
+/// becomes `{"Fn::Sub": "${A}"}`, and `!GetAtt Bucket.Arn` becomes
+/// `{"Fn::GetAtt": ["Bucket", "Arn"]}`.
+pub(crate) fn cloudformation_tags() -> Vec<(String, TagHandler)> {
+    let keep = |name: &'static str| -> TagHandler {
+        Arc::new(move |value| Ok(serde_json::json!({ (name): value })))
+    };
+    let mut handlers = vec![
+        ("!Ref".to_string(), keep("Ref")),
+        ("!Condition".to_string(), keep("Condition")),
+    ];
+    for function in CLOUDFORMATION_FUNCTIONS {
+        let name = format!("Fn::{}", function);
+        let handler: TagHandler = if *function == "GetAtt" {
+            Arc::new(move |value| match value.as_str() {
+                Some(text) => match text.split_once('.') {
+                    Some((resource, attribute)) => {
+                        Ok(serde_json::json!({ (name.as_str()): [resource, attribute] }))
+                    }
+                    None => Err(format!("expected Resource.Attribute, found {:?}", text)),
+                },
+                None => Ok(serde_json::json!({ (name.as_str()): value })),
+            })
+        } else {
+            Arc::new(move |value| Ok(serde_json::json!({ (name.as_str()): value })))
+        };
+        handlers.push((format!("!{}", function), handler));
+    }
+    handlers
+}
+
+/// Parse YAML `content` as [`from_str_limited`] does, expand `<<` merge keys
+/// into the mappings that hold them if `merge` is set, convert nodes with a
+/// tag in `handlers`, innermost first, and treat other nodes with tags
+/// outside the core schema as `tags` says.
+pub(crate) fn from_str_tagged(
+    content: &str,
+    aliases: AliasLimits,
+    merge: bool,
+    tags: YamlTags,
+    handlers: &[(String, TagHandler)],
+) -> Result<serde_json::Value, ValidationError> {
+    let mut document: serde_yaml::Value = from_str_limited(content, aliases)?;
+    if merge {
+        document.apply_merge()?;
+    }
+    let mut local = Vec::new();
+    untag(&mut document, "", handlers, &mut local)?;
+    let mut document: serde_json::Value = serde_yaml::from_value(document)?;
+    if tags == YamlTags::Strip && handlers.is_empty() {
+        return Ok(document);
+    }
+
+    // `serde_yaml` dropped global tags, so find them in the source. Local
+    // tags written in block style are found too, so errors follow the order
+    // of the source.
+    for node in SourceMap::parse(content).nodes {
+        let Some(tag) = node_tag(&node.inline) else {
+            continue;
+        };
+        if let Some((_, handler)) = handlers.iter().find(|(name, _)| name == tag) {
+            // Local tags were converted by `untag` already
+            if tag.starts_with("!!") || tag.starts_with("!<") {
+                if let Some(value) = document.pointer_mut(&node.pointer) {
+                    *value = handler(value).map_err(|e| handler_error(&node.pointer, tag, &e))?;
+                }
+            }
+            continue;
+        }
+        if tags == YamlTags::Strip {
+            continue;
+        }
+        let name = tag.strip_prefix("!!").or_else(|| {
+            tag.strip_prefix("!<")?
+                .strip_suffix('>')?
+                .strip_prefix(YAML_TAG_PREFIX)
+        });
+        if name.is_some_and(|name| CORE_TAGS.contains(&name)) {
+            continue;
+        }
+        let Some(value) = document.pointer_mut(&node.pointer) else {
+            continue;
+        };
+        match (tags, name, value) {
+            (YamlTags::Base64Decode, Some("binary"), serde_json::Value::String(text)) => {
+                let encoded: String = text.split_whitespace().collect();
+                if base64::engine::general_purpose::STANDARD
+                    .decode(&encoded)
+                    .is_err()
+                {
+                    return Err(tag_error(
+                        &node.pointer,
+                        &format!("{} is not valid base64", tag),
+                        text,
+                    ));
+                }
+                *text = encoded;
+            }
+            (YamlTags::Base64Decode, _, _) => {}
+            (_, _, value) => return Err(tag_error(&node.pointer, tag, value)),
+        }
+    }
+    match local.into_iter().next() {
+        Some((pointer, tag, value)) if tags == YamlTags::Error => {
+            Err(tag_error(&pointer, &tag, value))
+        }
+        _ => Ok(document),
+    }
+}
+
+/// Replace the local tags in `value`, at `pointer`, by their values,
+/// converted by their handler if `handlers` has one and otherwise recording
+/// each tagged node's pointer, tag and value in `found`.
+fn untag(
+    value: &mut serde_yaml::Value,
+    pointer: &str,
+    handlers: &[(String, TagHandler)],
+    found: &mut Vec<(String, String, serde_json::Value)>,
+) -> Result<(), ValidationError> {
+    match value {
+        serde_yaml::Value::Tagged(tagged) => {
+            let tag = tagged.tag.to_string();
+            let handler = handlers.iter().find(|(name, _)| *name == tag);
+            let index = found.len();
+            if handler.is_none() {
+                found.push((pointer.to_string(), tag.clone(), Default::default()));
+            }
+            *value = std::mem::take(&mut tagged.value);
+            untag(value, pointer, handlers, found)?;
+            let untagged = serde_yaml::from_value(value.clone()).unwrap_or_default();
+            match handler {
+                Some((_, handler)) => {
+                    let converted =
+                        handler(&untagged).map_err(|e| handler_error(pointer, &tag, &e))?;
+                    *value = yaml_value(&converted);
+                }
+                None => found[index].2 = untagged,
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                untag(item, &format!("{}/{}", pointer, index), handlers, found)?;
+            }
+        }
+        serde_yaml::Value::Mapping(entries) => {
+            for (key, item) in entries.iter_mut() {
+                let key = match key {
+                    serde_yaml::Value::String(key) => escape_token(key),
+                    key => serde_yaml::to_string(key)
+                        .unwrap_or_default()
+                        .trim_end()
+                        .to_string(),
+                };
+                untag(item, &format!("{}/{}", pointer, key), handlers, found)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The tag of a node given the text after its `key:` or `- `, skipping an
+/// anchor before it.
+fn node_tag(inline: &str) -> Option<&str> {
+    inline
+        .split_whitespace()
+        .find(|token| !token.starts_with('&'))
+        .filter(|token| token.starts_with('!'))
+}
+
+/// The error for the node at `pointer` with `tag`, showing the start of its
+/// `value`.
+fn tag_error(pointer: &str, tag: &str, value: impl serde::Serialize) -> ValidationError {
+    let mut preview = serde_json::to_string(&value).unwrap_or_default();
+    if let Some((cut, _)) = preview.char_indices().nth(PREVIEW_LENGTH) {
+        preview.truncate(cut);
+        preview.push('…');
+    }
+    let pointer = if pointer.is_empty() { "root" } else { pointer };
+    ValidationError::YamlTag(format!("{}: {} {}", pointer, tag, preview))
+}
+
+/// Retype the plain scalars of `document`, parsed from `content`, that YAML
+/// 1.1 reads as booleans or numbers, such as `NO` or `0755`. Only values on
+/// their own `key:` or `- ` line are found; see [`SourceMap`].
+pub(crate) fn resolve_yaml11(content: &str, document: &mut serde_json::Value) {
+    for node in SourceMap::parse(content).nodes {
+        let Some(reading) = crate::ambiguity::yaml11_reading(&node.inline) else {
+            continue;
+        };
+        if let Some(value) = document.pointer_mut(&node.pointer) {
+            // Quoted scalars keep their quotes in `inline`, so only plain
+            // scalars can match the text
+            if value.as_str() == Some(node.inline.as_str()) {
+                *value = reading;
+            }
+        }
+    }
+}
+
+/// `value` as a YAML value. `serde_yaml` cannot serialize numbers kept as
+/// written by the `arbitrary-precision` feature, so numbers are converted
+/// here, those beyond 64-bit integers to the nearest `f64`.
+pub(crate) fn yaml_value(value: &serde_json::Value) -> serde_yaml::Value {
+    use serde_json::Value;
+    match value {
+        Value::Null => serde_yaml::Value::Null,
+        Value::Bool(b) => serde_yaml::Value::Bool(*b),
+        Value::Number(n) => serde_yaml::Value::Number(match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => u.into(),
+            (_, Some(i)) => i.into(),
+            _ => n.as_f64().unwrap_or(f64::NAN).into(),
+        }),
+        Value::String(s) => serde_yaml::Value::String(s.clone()),
+        Value::Array(items) => serde_yaml::Value::Sequence(items.iter().map(yaml_value).collect()),
+        Value::Object(entries) => serde_yaml::Value::Mapping(
+            entries
+                .iter()
+                .map(|(key, value)| (serde_yaml::Value::String(key.clone()), yaml_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// The error for the node at `pointer` whose handler for `tag` failed.
+fn handler_error(pointer: &str, tag: &str, message: &str) -> ValidationError {
+    let pointer = if pointer.is_empty() { "root" } else { pointer };
+    ValidationError::YamlTag(format!("{}: {}: {}", pointer, tag, message))
+}
+
+/// Find the first anchor (`&name`) or alias (`*name`) in `content`,
+/// returning its line and text.
+pub(crate) fn find_anchor(content: &str) -> Option<(usize, String)> {
+    anchors(content).into_iter().next()
+}
+
+/// The anchors and aliases in `content` with their lines, in order.
+///
+/// Like [`check_flow_depth`], the scan skips comments and quoted scalars. An
+/// `&` or `*` only counts where a node can start: at the start of a line,
+/// after an indicator such as `- ` or `: `, or after a tag. The contents of
+/// block scalars are skipped.
+fn anchors(content: &str) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut block: Option<usize> = None;
+    for (index, line) in content.lines().enumerate() {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if let Some(parent) = block {
+            if line.trim().is_empty() || indent > parent {
+                continue;
+            }
+            block = None;
+        }
+        let line = match line.strip_prefix("---") {
+            Some(rest) if rest.is_empty() || rest.starts_with([' ', '\t']) => rest,
+            _ => line,
+        };
+        let mut node_start = true;
+        let mut previous = ' ';
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if let Some(open) = quote {
+                match (open, c) {
+                    ('"', '\\') => {
+                        chars.next();
+                    }
+                    ('\'', '\'') if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                    }
+                    (open, c) if open == c => quote = None,
+                    _ => {}
+                }
+                previous = c;
+                continue;
+            }
+            let token_start = previous.is_whitespace() || matches!(previous, '[' | '{' | ',');
+            match c {
+                ' ' | '\t' => {}
+                '#' if token_start => break,
+                '"' | '\'' if token_start => {
+                    quote = Some(c);
+                    node_start = false;
+                }
+                '&' | '*' if node_start && token_start => {
+                    let name: String = chars
+                        .clone()
+                        .take_while(|c| {
+                            !c.is_whitespace() && !matches!(c, ',' | '[' | ']' | '{' | '}')
+                        })
+                        .collect();
+                    if !name.is_empty() {
+                        found.push((index + 1, format!("{}{}", c, name)));
+                    }
+                    node_start = false;
+                }
+                '!' if node_start && token_start => {
+                    while chars.next_if(|c| !c.is_whitespace()).is_some() {}
+                }
+                '[' | '{' | ',' => node_start = true,
+                '-' | ':' | '?' if chars.peek().is_none_or(|next| next.is_whitespace()) => {
+                    node_start = true;
+                }
+                '|' | '>' if node_start && token_start => {
+                    block = Some(indent);
+                    break;
+                }
+                _ => node_start = false,
+            }
+            previous = c;
+        }
+    }
+    found
+}
+
+/// Fail if `content` has more aliases than `limits` allow, or expanding
+/// them adds more nodes than allowed.
+///
+/// Without aliases a document has at most about one node per byte, so the
+/// nodes beyond its length are counted as added by expanding aliases.
+/// Documents that fail to parse pass, for the parse that follows to report.
+fn check_aliases(content: &str, limits: AliasLimits) -> Result<(), ValidationError> {
+    if !content.contains('*') {
+        return Ok(());
+    }
+    if let Some(max) = limits.max_aliases {
+        let aliases = anchors(content)
+            .iter()
+            .filter(|(_, token)| token.starts_with('*'))
+            .count();
+        if aliases > max {
+            return Err(ValidationError::LimitExceeded(format!(
+                "document has {} aliases, more than the maximum of {}",
+                aliases, max
+            )));
+        }
+    }
+    let budget = content.len().saturating_add(limits.max_expansion);
+    let remaining = Cell::new(budget);
+    match NodeCounter(&remaining).deserialize(serde_yaml::Deserializer::from_str(content)) {
+        Err(_) if remaining.get() == 0 => Err(ValidationError::LimitExceeded(format!(
+            "aliases expand the document to more than {} nodes",
+            budget
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Counts the nodes of a document as it is deserialized, failing once
+/// `remaining` runs out.
+#[derive(Clone, Copy)]
+struct NodeCounter<'a>(&'a Cell<usize>);
+
+impl NodeCounter<'_> {
+    fn count<E: de::Error>(self) -> Result<(), E> {
+        let remaining = self.0.get().checked_sub(1);
+        self.0.set(remaining.unwrap_or(0));
+        remaining
+            .map(drop)
+            .ok_or_else(|| E::custom("alias expansion limit exceeded"))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for NodeCounter<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeCounter<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a YAML node")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_i128<E: de::Error>(self, _: i128) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_u128<E: de::Error>(self, _: u128) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<(), E> {
+        self.count()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        self.count()?;
+        while seq.next_element_seed(self)?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        self.count()?;
+        while map.next_entry_seed(self, self)?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<(), A::Error> {
+        // A tagged node
+        let (IgnoredAny, variant) = data.variant()?;
+        de::VariantAccess::newtype_variant_seed(variant, self)
+    }
+}
+
+/// Whether `content` holds no document: only blank lines, comments,
+/// directives and document markers.
+pub(crate) fn is_blank(content: &str) -> bool {
+    content.lines().all(|line| {
+        let line = line.trim();
+        let marker = line
+            .strip_prefix("---")
+            .or_else(|| line.strip_prefix("..."))
+            .filter(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+        let rest = marker.map_or(line, str::trim_start);
+        rest.is_empty() || rest.starts_with('#') || (marker.is_none() && rest.starts_with('%'))
+    })
+}
+
+/// Split a stream of YAML documents at their `---` markers, which the YAML
+/// specification only allows at the start of a line outside any scalar.
+///
+/// Comments and directives before the first marker stay with the first
+/// document. Each document is preceded by a blank line for every line
+/// before it, so parse errors point at lines of the whole stream.
+pub(crate) fn split_documents(content: &str) -> Vec<Cow<'_, str>> {
+    let mut starts = vec![0];
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let marker = line
+            .strip_prefix("---")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']));
+        if marker && offset > 0 {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    if starts.len() > 1 && is_blank(&content[..starts[1]]) {
+        starts.remove(1);
+    }
+    let ends = starts.iter().skip(1).copied().chain([content.len()]);
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| match start {
+            0 => Cow::Borrowed(&content[..end]),
+            _ => {
+                let preceding = content[..start].matches('\n').count();
+                Cow::Owned("\n".repeat(preceding) + &content[start..end])
+            }
+        })
+        .collect()
+}
+
+/// Check the directives before the first document, returning the content
+/// with reserved directives commented out.
+fn check_directives(content: &str) -> Result<Cow<'_, str>, ValidationError> {
+    let invalid = |message: String| Err(ValidationError::YamlDirective(message));
+    let mut reserved = Vec::new();
+    let mut directives = false;
+    let mut started = false;
+    for (index, line) in content.lines().enumerate() {
+        if line.starts_with("---") {
+            started = true;
+            break;
+        }
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let Some(directive) = line.strip_prefix('%') else {
+            if directives {
+                return invalid(format!(
+                    "line {}: directives must be followed by a '---' line before the document",
+                    index + 1
+                ));
+            }
+            // The document starts without a marker, so there are no directives
+            started = true;
+            break;
+        };
+        directives = true;
+        let mut words = directive.split_whitespace();
+        match words.next() {
+            Some("YAML") => {
+                let version = words.next().unwrap_or_default();
+                if version.split('.').next() != Some("1") {
+                    return invalid(format!(
+                        "line {}: YAML {} is not supported; only YAML 1.x is",
+                        index + 1,
+                        version
+                    ));
+                }
+            }
+            Some("TAG") => {}
+            _ => reserved.push(index),
+        }
+    }
+    if directives && !started {
+        return invalid("directives must be followed by a '---' line".to_string());
+    }
+    if reserved.is_empty() {
+        return Ok(Cow::Borrowed(content));
+    }
+    let lines: Vec<Cow<'_, str>> = content
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| match reserved.contains(&index) {
+            true => Cow::Owned(format!("#{}", line)),
+            false => Cow::Borrowed(line),
+        })
+        .collect();
+    Ok(Cow::Owned(lines.join("\n")))
+}
+
+/// Fail if `content` opens more than [`MAX_FLOW_DEPTH`] nested flow
+/// collections.
+///
+/// The scan skips comments and quoted scalars, treating a quote as opening
+/// one only where a scalar can start, so apostrophes inside plain scalars do
+/// not hide the brackets after them. Brackets inside plain scalars are
+/// counted, which can only make the estimate larger.
+fn check_flow_depth(content: &str) -> Result<(), ValidationError> {
+    let mut depth = 0usize;
+    let mut previous = '\n';
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        let at_token_start = previous.is_whitespace() || matches!(previous, '[' | '{' | ',' | ':');
+        match c {
+            '[' | '{' => {
+                depth += 1;
+                if depth > MAX_FLOW_DEPTH {
+                    return Err(ValidationError::LimitExceeded(format!(
+                        "document is nested more than {} levels deep",
+                        MAX_FLOW_DEPTH
+                    )));
+                }
+            }
+            ']' | '}' => depth = depth.saturating_sub(1),
+            '#' if at_token_start => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '"' if at_token_start => {
+                let mut escaped = false;
+                chars.by_ref().find(|c| {
+                    let closes = *c == '"' && !escaped;
+                    escaped = *c == '\\' && !escaped;
+                    closes
+                });
+            }
+            '\'' if at_token_start => {
+                // A doubled quote inside is an escaped one
+                while chars.by_ref().any(|c| c == '\'') && chars.clone().next() == Some('\'') {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_flow_depth() {
+        let deep = "a: ".to_string() + &"[".repeat(100_000);
+        assert!(matches!(
+            from_str::<Value>(&deep),
+            Err(ValidationError::LimitExceeded(_))
+        ));
+
+        let brackets = "[".repeat(200);
+        for hidden in [
+            format!("a: \"{}\"", brackets),
+            format!("a: '{}'", brackets),
+            format!("a: 'it''s {}'", brackets),
+            format!("a: 1 # {}", brackets),
+            format!("a: \"\\\" {}\"", brackets),
+        ] {
+            assert!(check_flow_depth(&hidden).is_ok(), "{}", hidden);
+        }
+        assert!(check_flow_depth(&format!("a: don't {}", brackets)).is_err());
+        assert!(from_str::<Value>("a: [[1, {b: [2]}], 'x']").is_ok());
+    }
+
+    #[test]
+    fn test_directives() {
+        let parse = |content: &str| from_str::<Value>(content);
+        assert_eq!(
+            parse("%YAML 1.2\n%TAG !e! tag:example.com,2000:\n---\na: 1\n").unwrap()["a"],
+            1
+        );
+        assert_eq!(parse("# config\n%FUTURE x\n---\na: 1").unwrap()["a"], 1);
+        assert!(matches!(
+            parse("%YAML 1.2\na: 1\n"),
+            Err(ValidationError::YamlDirective(message)) if message.starts_with("line 2:")
+        ));
+        assert!(matches!(
+            parse("%YAML 2.0\n---\na: 1\n"),
+            Err(ValidationError::YamlDirective(_))
+        ));
+        assert!(matches!(
+            parse("%YAML 1.2\n"),
+            Err(ValidationError::YamlDirective(_))
+        ));
+        // Lines starting with '%' inside the document are not directives
+        assert!(parse("a: |\n  %YAML\n").is_ok());
+    }
+
+    #[test]
+    fn test_split_documents() {
+        let stream = "# manifests\n---\nkind: A\n---\nkind: B\n--- # last\nkind: C\n";
+        let documents = split_documents(stream);
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[0], "# manifests\n---\nkind: A\n");
+        assert_eq!(documents[1], "\n\n\n---\nkind: B\n");
+        let values: Vec<serde_json::Value> = documents
+            .iter()
+            .map(|document| from_str(document).unwrap())
+            .collect();
+        assert_eq!(values[2], serde_json::json!({"kind": "C"}));
+
+        assert_eq!(split_documents("a: 1\n").len(), 1);
+        assert_eq!(split_documents("a: |\n  ---\n---a: 1\n").len(), 1);
+        assert_eq!(split_documents("%YAML 1.2\n---\na: 1\n---\n").len(), 2);
+
+        let error = from_str::<serde_json::Value>(&split_documents("a: 1\n---\nb: [\n")[1])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("line 4"), "{}", error);
+    }
+
+    #[test]
+    fn test_merge_keys() {
+        let content =
+            "base: &base\n  image: app\n  replicas: 1\nweb:\n  <<: *base\n  replicas: 3\n";
+        let merged =
+            from_str_tagged(content, AliasLimits::default(), true, YamlTags::Error, &[]).unwrap();
+        assert_eq!(
+            merged["web"],
+            serde_json::json!({"image": "app", "replicas": 3})
+        );
+        let literal: Value = from_str(content).unwrap();
+        assert_eq!(literal["web"]["<<"]["image"], "app");
+        assert!(from_str_tagged(
+            "a:\n  <<: 1\n",
+            AliasLimits::default(),
+            true,
+            YamlTags::Error,
+            &[]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_alias_limits() {
+        let mut bomb = "a: &a [lol, lol, lol, lol, lol, lol, lol, lol, lol]\n".to_string();
+        for (name, previous) in ["bcdefghij".chars().zip("abcdefghi".chars())]
+            .into_iter()
+            .flatten()
+        {
+            let aliases = vec![format!("*{}", previous); 9].join(", ");
+            bomb += &format!("{}: &{} [{}]\n", name, name, aliases);
+        }
+        let limits = |max_aliases, max_expansion| AliasLimits {
+            max_aliases,
+            max_expansion,
+        };
+        // The default limit stops a billion laughs before serde_yaml does
+        assert!(matches!(
+            from_str::<Value>(&bomb),
+            Err(ValidationError::LimitExceeded(message)) if message.contains("nodes")
+        ));
+        assert!(matches!(
+            from_str_limited::<Value>(&bomb, limits(None, 1000)),
+            Err(ValidationError::LimitExceeded(message)) if message.contains("nodes")
+        ));
+
+        let content = "base: &base {image: app}\nweb: *base\nworker: *base\n";
+        assert!(from_str::<Value>(content).is_ok());
+        assert!(from_str_limited::<Value>(content, limits(Some(2), 0)).is_ok());
+        assert!(matches!(
+            from_str_limited::<Value>(content, limits(Some(1), 0)),
+            Err(ValidationError::LimitExceeded(message)) if message.contains("2 aliases")
+        ));
+        // 199 nodes from 62 bytes
+        let nested = "a: &a [[[[[[[[1]]]]]]]]\nb: &b [*a, *a, *a, *a]\nc: [*b, *b, *b, *b]\n";
+        assert!(from_str_limited::<Value>(nested, limits(None, 0)).is_err());
+        assert!(from_str_limited::<Value>(nested, limits(None, 1000)).is_ok());
+    }
+
+    #[test]
+    fn test_tags() {
+        let parse = |content: &str, tags| {
+            from_str_tagged(content, AliasLimits::default(), false, tags, &[])
+        };
+        let content =
+            "name: !!str 1\nicon: !!binary |\n  aGVsbG8g\n  d29ybGQ=\nbucket: !Ref Logs\n";
+        assert_eq!(
+            parse(content, YamlTags::Error).unwrap_err().to_string(),
+            "Unsupported YAML tag: /icon: !!binary \"aGVsbG8g\\nd29ybGQ=\\n\""
+        );
+        let stripped = parse(content, YamlTags::Strip).unwrap();
+        assert_eq!(stripped["name"], "1");
+        assert_eq!(stripped["bucket"], "Logs");
+        let decoded = parse(content, YamlTags::Base64Decode).unwrap();
+        assert_eq!(decoded["icon"], "aGVsbG8gd29ybGQ=");
+
+        let local = parse("items:\n  - &a !Sub\n    x: 1\n", YamlTags::Error);
+        assert_eq!(
+            local.unwrap_err().to_string(),
+            "Unsupported YAML tag: /items/0: !Sub {\"x\":1}"
+        );
+        let long = format!("a: !!binary {}\n", "A".repeat(100));
+        let error = parse(&long, YamlTags::Error).unwrap_err().to_string();
+        assert!(
+            error.ends_with(&format!("\"{}…", "A".repeat(31))),
+            "{}",
+            error
+        );
+        assert!(matches!(
+            parse("a: !!binary not*base64\n", YamlTags::Base64Decode),
+            Err(ValidationError::YamlTag(message)) if message.contains("not valid base64")
+        ));
+        assert!(parse("a: !<tag:yaml.org,2002:int> 1\n", YamlTags::Error).is_ok());
+        let error = parse("a: [1, !Outer {b: !Inner 2}]\n", YamlTags::Error).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unsupported YAML tag: /a/1: !Outer {\"b\":2}"
+        );
+    }
+
+    #[test]
+    fn test_tag_handlers() {
+        let handlers = cloudformation_tags();
+        let parse = |content: &str| {
+            from_str_tagged(
+                content,
+                AliasLimits::default(),
+                false,
+                YamlTags::Error,
+                &handlers,
+            )
+        };
+        let template =
+            "Resources:\n  Topic:\n    Properties:\n      Name: !Sub '${Env}-topic'\n      \
+                        Arn: !GetAtt Bucket.Arn\n      Tags: [!Join ['-', [!Ref Env, x]]]\n";
+        assert_eq!(
+            parse(template).unwrap()["Resources"]["Topic"]["Properties"],
+            serde_json::json!({
+                "Name": {"Fn::Sub": "${Env}-topic"},
+                "Arn": {"Fn::GetAtt": ["Bucket", "Arn"]},
+                "Tags": [{"Fn::Join": ["-", [{"Ref": "Env"}, "x"]]}],
+            })
+        );
+        assert_eq!(
+            parse("a: !GetAtt Bucket\n").unwrap_err().to_string(),
+            "Unsupported YAML tag: /a: !GetAtt: expected Resource.Attribute, found \"Bucket\""
+        );
+        assert!(parse("a: !Custom 1\n").is_err());
+
+        let binary: TagHandler = Arc::new(|value| Ok(value.as_str().unwrap_or("").len().into()));
+        let handlers = [("!!binary".to_string(), binary)];
+        let document = from_str_tagged(
+            "icon: !!binary aGk=\n",
+            AliasLimits::default(),
+            false,
+            YamlTags::Error,
+            &handlers,
+        )
+        .unwrap();
+        assert_eq!(document["icon"], 4);
+    }
+
+    #[test]
+    fn test_resolve_yaml11() {
+        let content =
+            "country: NO\nquoted: 'no'\nmode: 0755\nports:\n  - 22:22\n  - on\nname: Norway\n";
+        let mut document: Value = from_str(content).unwrap();
+        resolve_yaml11(content, &mut document);
+        assert_eq!(
+            document,
+            serde_json::json!({
+                "country": false,
+                "quoted": "no",
+                "mode": 493,
+                "ports": [1342, true],
+                "name": "Norway",
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_anchor() {
+        assert_eq!(
+            find_anchor("a: 1\nbase: &base\n  x: 1\n"),
+            Some((2, "&base".to_string()))
+        );
+        assert_eq!(
+            find_anchor("list:\n  - *item\n"),
+            Some((2, "*item".to_string()))
+        );
+        assert_eq!(
+            find_anchor("--- !!map &root\na: 1\n"),
+            Some((1, "&root".to_string()))
+        );
+        assert_eq!(find_anchor("a: [1, *x]\n"), Some((1, "*x".to_string())));
+        for plain in [
+            "a: Tom & Jerry\n",
+            "a: 2 * 3\n",
+            "a: \"&quoted\"\n",
+            "a: 'it''s &not'\n",
+            "a: \"multi\n  &line\"\n",
+            "a: 1 # &comment\n",
+            "a: |\n  - &literal\n  *text\nb: 1\n",
+            "a: b&c\n",
+        ] {
+            assert_eq!(find_anchor(plain), None, "{:?}", plain);
+        }
+    }
+
+    #[test]
+    fn test_is_blank() {
+        for blank in ["", "\n  \n", "# a\n  # b", "%YAML 1.2\n--- # c\n...\n"] {
+            assert!(is_blank(blank), "{:?}", blank);
+        }
+        for document in ["a: 1", "--- null", "---a", "- 1"] {
+            assert!(!is_blank(document), "{:?}", document);
+        }
+    }
+}
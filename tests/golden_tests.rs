@@ -1,4 +1,4 @@
-use validate_json_schema::Validator;
+use validate_json_schema::{Draft, FixtureMode, RecordReplayLoader, Validator};
 
 /// Test case structure for table-driven tests
 #[derive(Debug)]
@@ -129,6 +129,56 @@ test_cases! {
         should_pass: true,
     },
 
+    // Draft 2020-12: unevaluatedProperties sees properties declared through allOf/$ref
+    valid_crontab_2020_12: TestCase {
+        name: "valid_crontab_2020_12",
+        data_file: "tests/data/crontab.yml",
+        schema_file: "tests/schemas/crd-2020-12.json",
+        should_pass: true,
+    },
+    invalid_crontab_2020_12: TestCase {
+        name: "invalid_crontab_2020_12",
+        data_file: "tests/data/invalid-crontab.yml",
+        schema_file: "tests/schemas/crd-2020-12.json",
+        should_pass: false,
+    },
+
+    // Dynamic references: strict-tree extends tree through $dynamicRef/$recursiveRef
+    tree_accepts_unknown_properties: TestCase {
+        name: "tree_accepts_unknown_properties",
+        data_file: "tests/data/invalid-tree.json",
+        schema_file: "tests/schemas/dynamic/tree.json",
+        should_pass: true,
+    },
+
+    valid_strict_tree: TestCase {
+        name: "valid_strict_tree",
+        data_file: "tests/data/tree.json",
+        schema_file: "tests/schemas/dynamic/strict-tree.json",
+        should_pass: true,
+    },
+
+    invalid_strict_tree: TestCase {
+        name: "invalid_strict_tree",
+        data_file: "tests/data/invalid-tree.json",
+        schema_file: "tests/schemas/dynamic/strict-tree.json",
+        should_pass: false,
+    },
+
+    valid_strict_tree_2019_09: TestCase {
+        name: "valid_strict_tree_2019_09",
+        data_file: "tests/data/tree.json",
+        schema_file: "tests/schemas/dynamic/strict-tree-2019-09.json",
+        should_pass: true,
+    },
+
+    invalid_strict_tree_2019_09: TestCase {
+        name: "invalid_strict_tree_2019_09",
+        data_file: "tests/data/invalid-tree.json",
+        schema_file: "tests/schemas/dynamic/strict-tree-2019-09.json",
+        should_pass: false,
+    },
+
     // Cross-validation tests (should fail)
     package_json_against_github_schema: TestCase {
         name: "package_json_against_github_schema",
@@ -164,6 +214,97 @@ fn test_schema_input_detection() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Test remote schema loading from recorded fixtures, without network access
+#[test]
+fn test_remote_schema_replay() -> Result<(), Box<dyn std::error::Error>> {
+    let loader = RecordReplayLoader::new("tests/fixtures/http", FixtureMode::Replay)?;
+    let validator = Validator::from_url_with_loader(
+        "https://schemas.example.com/render-blueprint.json",
+        loader.clone(),
+    )?;
+
+    assert!(validator
+        .validate_file("tests/data/render-blueprint.yml")
+        .is_ok());
+    assert!(validator
+        .validate_file("tests/data/invalid-render-blueprint.yml")
+        .is_err());
+
+    // Unrecorded URLs fail instead of reaching the network
+    assert!(
+        Validator::from_url_with_loader("https://schemas.example.com/other.json", loader).is_err()
+    );
+
+    Ok(())
+}
+
+/// Test dynamic references resolved through remote schemas
+#[test]
+fn test_remote_dynamic_ref_replay() -> Result<(), Box<dyn std::error::Error>> {
+    let loader = RecordReplayLoader::new("tests/fixtures/http", FixtureMode::Replay)?;
+    let validator =
+        Validator::from_url_with_loader("https://schemas.example.com/strict-tree.json", loader)?;
+
+    assert!(validator.validate_file("tests/data/tree.json").is_ok());
+    assert!(validator
+        .validate_file("tests/data/invalid-tree.json")
+        .is_err());
+
+    Ok(())
+}
+
+/// Test that the draft can be chosen explicitly instead of read from `$schema`
+#[test]
+fn test_draft_override() -> Result<(), Box<dyn std::error::Error>> {
+    let schema = "tests/schemas/crd-2020-12.json";
+    let detected = Validator::from_file(schema)?;
+    assert_eq!(detected.draft(), Draft::Draft202012);
+    assert!(detected
+        .validate_file("tests/data/invalid-crontab.yml")
+        .is_err());
+
+    // Draft 7 has no unevaluatedProperties, so the extra property is allowed
+    let draft7 = Validator::builder()
+        .draft(Draft::Draft7)
+        .build_file(schema)?;
+    assert_eq!(draft7.draft(), Draft::Draft7);
+    draft7.validate_file("tests/data/invalid-crontab.yml")?;
+    Ok(())
+}
+
+/// Test dynamic references between resources bundled in one document
+#[test]
+fn test_bundled_dynamic_ref() -> Result<(), Box<dyn std::error::Error>> {
+    let schema = r##"{
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "https://schemas.example.com/bundle/strict-tree.json",
+        "$dynamicAnchor": "node",
+        "$ref": "tree.json",
+        "unevaluatedProperties": false,
+        "$defs": {
+            "tree": {
+                "$id": "tree.json",
+                "$dynamicAnchor": "node",
+                "type": "object",
+                "properties": {
+                    "data": true,
+                    "children": {"type": "array", "items": {"$dynamicRef": "#node"}}
+                }
+            }
+        }
+    }"##;
+    let validator = Validator::new(schema)?;
+
+    assert!(validator
+        .validate_json(r#"{"children": [{"data": 1}]}"#)
+        .is_ok());
+    assert!(validator
+        .validate_json(r#"{"children": [{"daat": 1}]}"#)
+        .is_err());
+
+    Ok(())
+}
+
 /// Test local schema loading
 #[test]
 fn test_local_schema_loading() -> Result<(), Box<dyn std::error::Error>> {
@@ -178,6 +319,29 @@ fn test_local_schema_loading() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Test relative references between schema files, including from a
+/// referenced file to its own sibling
+#[test]
+fn test_split_schema_files() -> Result<(), Box<dyn std::error::Error>> {
+    let validator = Validator::from_file("tests/schemas/split/deployment.json")?;
+
+    assert!(validator
+        .validate_json(r#"{"services": [{"name": "web", "port": 80}]}"#)
+        .is_ok());
+    let error = validator
+        .validate_json(r#"{"services": [{"port": 0}]}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("\"name\" is a required property"),
+        "{}",
+        error
+    );
+    assert!(error.contains("/services/0/port"), "{}", error);
+
+    Ok(())
+}
+
 /// Test malformed YAML handling
 #[test]
 fn test_malformed_yaml() -> Result<(), Box<dyn std::error::Error>> {